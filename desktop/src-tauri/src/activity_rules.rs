@@ -0,0 +1,327 @@
+// activity_rules.rs — User-editable activity classification.
+// Rules match a snapshot's app / browser URL / window title by substring,
+// regex or domain and assign a category plus a productive score (-1..1, where
+// 1 = 0.5 productive min per 30-sec snapshot, -1 = 0.5 distraction min).
+// Highest priority wins; anything unmatched falls back to the built-in
+// keyword lists in `proactive::classify_activity`.
+use crate::types::HanniDb;
+use std::collections::HashMap;
+
+pub const FIELDS: &[&str] = &["app", "url", "title", "any"];
+pub const KINDS: &[&str] = &["substring", "regex", "domain"];
+/// Built-in categories and the score they get when a rule doesn't set one.
+pub const CATEGORIES: &[(&str, f64)] = &[
+    ("coding", 1.0), ("writing", 1.0), ("learning", 1.0),
+    ("communication", 0.5), ("browsing", 0.5), ("other", 0.5),
+    ("social", -1.0), ("media", -1.0),
+];
+/// Minutes represented by one snapshot.
+pub const SNAPSHOT_MIN: f64 = 0.5;
+
+pub fn default_score(category: &str) -> f64 {
+    CATEGORIES.iter().find(|(c, _)| *c == category).map(|(_, s)| *s).unwrap_or(0.5)
+}
+
+/// (productive_min, distraction_min) for one snapshot with this score.
+pub fn snapshot_minutes(score: f64) -> (f64, f64) {
+    if score >= 0.0 { (SNAPSHOT_MIN * score, 0.0) } else { (0.0, SNAPSHOT_MIN * -score) }
+}
+
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub id: i64,
+    pub field: String,
+    pub kind: String,
+    pub pattern: String,
+    pub category: String,
+    pub score: Option<f64>,
+    pub priority: i64,
+}
+
+enum Matcher {
+    Substring(String),
+    Regex(regex::Regex),
+    Domain(String),
+}
+
+impl Matcher {
+    fn new(kind: &str, pattern: &str) -> Result<Self, String> {
+        let p = pattern.trim();
+        if p.is_empty() { return Err("Пустой шаблон правила".into()); }
+        match kind {
+            "substring" => Ok(Matcher::Substring(p.to_lowercase())),
+            "regex" => regex::RegexBuilder::new(p).case_insensitive(true).build()
+                .map(Matcher::Regex)
+                .map_err(|e| format!("Некорректное регулярное выражение: {}", e)),
+            "domain" => Ok(Matcher::Domain(p.trim_start_matches("*.").trim_start_matches('.').to_lowercase())),
+            _ => Err(format!("Неизвестный тип совпадения: {}", kind)),
+        }
+    }
+
+    fn is_match(&self, text: &str) -> bool {
+        match self {
+            Matcher::Substring(s) => text.to_lowercase().contains(s),
+            Matcher::Regex(re) => re.is_match(text),
+            Matcher::Domain(d) => domains_in(text).iter().any(|h| h == d || h.ends_with(&format!(".{}", d))),
+        }
+    }
+}
+
+/// Hostnames mentioned in a snapshot field — "Safari: https://m.youtube.com/watch | title"
+/// yields `m.youtube.com`.
+pub fn domains_in(text: &str) -> Vec<String> {
+    text.split(|c: char| c.is_whitespace() || c == '|')
+        .filter_map(|tok| {
+            let rest = tok.split_once("://").map(|(_, r)| r).unwrap_or(tok);
+            let host = rest.split(['/', '?', '#']).next()?.split(':').next()?.trim_start_matches("www.").to_lowercase();
+            let valid = host.contains('.') && !host.starts_with('.') && !host.ends_with('.')
+                && host.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+                && host.rsplit('.').next().is_some_and(|tld| tld.len() >= 2 && tld.chars().all(|c| c.is_ascii_alphabetic()));
+            valid.then_some(host)
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Classification {
+    pub category: String,
+    pub score: f64,
+    pub rule_id: Option<i64>,
+}
+
+pub struct RuleSet {
+    rules: Vec<(Rule, Matcher)>,
+}
+
+impl RuleSet {
+    /// Compile rules, highest priority first (ties: oldest first). Rules with a
+    /// bad pattern are skipped — they can only get here via a manual DB edit.
+    pub fn new(mut rules: Vec<Rule>) -> Self {
+        rules.sort_by(|a, b| b.priority.cmp(&a.priority).then(a.id.cmp(&b.id)));
+        RuleSet {
+            rules: rules.into_iter().filter_map(|r| Matcher::new(&r.kind, &r.pattern).ok().map(|m| (r, m))).collect(),
+        }
+    }
+
+    pub fn load(conn: &rusqlite::Connection) -> Self {
+        let rules = conn.prepare(
+            "SELECT id, field, kind, pattern, category, score, priority FROM activity_rules WHERE enabled=1"
+        ).and_then(|mut stmt| {
+            stmt.query_map([], |r| Ok(Rule {
+                id: r.get(0)?, field: r.get(1)?, kind: r.get(2)?, pattern: r.get(3)?,
+                category: r.get(4)?, score: r.get(5)?, priority: r.get(6)?,
+            }))?.collect::<Result<Vec<_>, _>>()
+        }).unwrap_or_default();
+        Self::new(rules)
+    }
+
+    pub fn classify(&self, app: &str, url: &str, title: &str) -> Classification {
+        for (rule, m) in &self.rules {
+            let hit = match rule.field.as_str() {
+                "app" => m.is_match(app),
+                "url" => m.is_match(url),
+                "title" => m.is_match(title),
+                _ => m.is_match(app) || m.is_match(url) || m.is_match(title),
+            };
+            if hit {
+                return Classification {
+                    category: rule.category.clone(),
+                    score: rule.score.unwrap_or_else(|| default_score(&rule.category)),
+                    rule_id: Some(rule.id),
+                };
+            }
+        }
+        let category = crate::proactive::classify_activity(app, url, title);
+        Classification { category: category.to_string(), score: default_score(category), rule_id: None }
+    }
+}
+
+fn validate(field: &str, kind: &str, pattern: &str, category: &str, score: Option<f64>) -> Result<(), String> {
+    if !FIELDS.contains(&field) { return Err(format!("Неизвестное поле правила: {}", field)); }
+    Matcher::new(kind, pattern)?;
+    if category.trim().is_empty() || category == "afk" { return Err("Укажите категорию".into()); }
+    if score.is_some_and(|s| !(-1.0..=1.0).contains(&s)) {
+        return Err("Оценка продуктивности должна быть от -1 до 1".into());
+    }
+    Ok(())
+}
+
+/// Where unclassified time goes: browser time grouped by domain, everything
+/// else by app.
+#[derive(Debug, Default)]
+pub struct UnclassifiedEntry {
+    pub minutes: f64,
+    pub samples: Vec<String>,
+}
+
+pub fn unclassified_key(app: &str, url: &str, category: &str) -> (String, String) {
+    if category == "browsing" {
+        if let Some(d) = domains_in(url).into_iter().next() { return ("domain".into(), d); }
+    }
+    ("app".into(), app.to_string())
+}
+
+// ── Commands ──
+
+#[tauri::command]
+pub fn get_activity_rules(db: tauri::State<'_, HanniDb>) -> Result<serde_json::Value, String> {
+    let conn = db.read();
+    let mut stmt = conn.prepare(
+        "SELECT id, field, kind, pattern, category, score, priority, enabled, created_at
+         FROM activity_rules ORDER BY priority DESC, id"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let rules: Vec<serde_json::Value> = stmt.query_map([], |r| {
+        let category: String = r.get(4)?;
+        let score: Option<f64> = r.get(5)?;
+        Ok(serde_json::json!({
+            "id": r.get::<_, i64>(0)?, "field": r.get::<_, String>(1)?, "kind": r.get::<_, String>(2)?,
+            "pattern": r.get::<_, String>(3)?, "score": score, "effective_score": score.unwrap_or_else(|| default_score(&category)),
+            "category": category, "priority": r.get::<_, i64>(6)?, "enabled": r.get::<_, i64>(7)? != 0,
+            "created_at": r.get::<_, String>(8)?,
+        }))
+    }).map_err(|e| format!("Query error: {}", e))?.flatten().collect();
+    let categories: Vec<serde_json::Value> = CATEGORIES.iter()
+        .map(|(c, s)| serde_json::json!({ "id": c, "score": s })).collect();
+    Ok(serde_json::json!({ "rules": rules, "categories": categories, "fields": FIELDS, "kinds": KINDS }))
+}
+
+#[tauri::command]
+pub fn create_activity_rule(
+    field: String, kind: String, pattern: String, category: String,
+    score: Option<f64>, priority: Option<i64>,
+    db: tauri::State<'_, HanniDb>,
+) -> Result<i64, String> {
+    validate(&field, &kind, &pattern, &category, score)?;
+    let conn = db.conn();
+    conn.execute(
+        "INSERT INTO activity_rules (field, kind, pattern, category, score, priority, enabled, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, 1, ?7)",
+        rusqlite::params![field, kind, pattern.trim(), category.trim(), score, priority.unwrap_or(0), chrono::Local::now().to_rfc3339()],
+    ).map_err(|e| format!("DB error: {}", e))?;
+    Ok(conn.last_insert_rowid())
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn update_activity_rule(
+    id: i64, field: String, kind: String, pattern: String, category: String,
+    score: Option<f64>, priority: i64, enabled: bool,
+    db: tauri::State<'_, HanniDb>,
+) -> Result<(), String> {
+    validate(&field, &kind, &pattern, &category, score)?;
+    let n = db.conn().execute(
+        "UPDATE activity_rules SET field=?1, kind=?2, pattern=?3, category=?4, score=?5, priority=?6, enabled=?7 WHERE id=?8",
+        rusqlite::params![field, kind, pattern.trim(), category.trim(), score, priority, enabled as i64, id],
+    ).map_err(|e| format!("DB error: {}", e))?;
+    if n == 0 { return Err("Правило не найдено".into()); }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_activity_rule(id: i64, db: tauri::State<'_, HanniDb>) -> Result<(), String> {
+    db.conn().execute("DELETE FROM activity_rules WHERE id=?1", [id]).map_err(|e| format!("DB error: {}", e))?;
+    Ok(())
+}
+
+/// Re-run classification over stored snapshots (AFK rows untouched) so rule
+/// edits apply to history, not just new snapshots.
+#[tauri::command]
+pub fn reclassify_activity_snapshots(days: Option<i64>, db: tauri::State<'_, HanniDb>) -> Result<serde_json::Value, String> {
+    let since = (chrono::Local::now() - chrono::Duration::days(days.unwrap_or(30).max(1))).to_rfc3339();
+    let mut conn = db.conn();
+    let rules = RuleSet::load(&conn);
+    let rows: Vec<(i64, String, String, String, String)> = {
+        let mut stmt = conn.prepare(
+            "SELECT id, COALESCE(frontmost_app,''), COALESCE(browser_url,''), COALESCE(window_title,''), COALESCE(category,'other')
+             FROM activity_snapshots WHERE captured_at >= ?1 AND COALESCE(category,'') != 'afk'"
+        ).map_err(|e| format!("DB error: {}", e))?;
+        let rows = stmt.query_map([&since], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?)))
+            .map_err(|e| format!("Query error: {}", e))?.flatten().collect();
+        rows
+    };
+    let tx = conn.transaction().map_err(|e| format!("DB error: {}", e))?;
+    let mut changed = 0;
+    {
+        let mut upd = tx.prepare(
+            "UPDATE activity_snapshots SET category=?1, productive_min=?2, distraction_min=?3 WHERE id=?4"
+        ).map_err(|e| format!("DB error: {}", e))?;
+        for (id, app, url, title, old) in &rows {
+            let c = rules.classify(app, url, title);
+            let (prod, dist) = snapshot_minutes(c.score);
+            upd.execute(rusqlite::params![c.category, prod, dist, id]).map_err(|e| format!("DB error: {}", e))?;
+            if c.category != *old { changed += 1; }
+        }
+    }
+    tx.commit().map_err(|e| format!("DB error: {}", e))?;
+    Ok(serde_json::json!({ "scanned": rows.len(), "changed": changed }))
+}
+
+/// Top apps / domains still landing in `other` or generic `browsing` — the
+/// list to write rules from.
+#[tauri::command]
+pub fn get_unclassified_activity(days: Option<i64>, limit: Option<usize>, db: tauri::State<'_, HanniDb>) -> Result<Vec<serde_json::Value>, String> {
+    let since = (chrono::Local::now() - chrono::Duration::days(days.unwrap_or(7).max(1))).to_rfc3339();
+    let conn = db.read();
+    let mut stmt = conn.prepare(
+        "SELECT COALESCE(frontmost_app,''), COALESCE(browser_url,''), COALESCE(window_title,''), category
+         FROM activity_snapshots WHERE captured_at >= ?1 AND category IN ('other','browsing')"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let mut groups: HashMap<(String, String), UnclassifiedEntry> = HashMap::new();
+    let rows = stmt.query_map([&since], |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?, r.get::<_, String>(2)?, r.get::<_, String>(3)?)))
+        .map_err(|e| format!("Query error: {}", e))?;
+    for (app, url, title, category) in rows.flatten() {
+        if app.is_empty() { continue; }
+        let e = groups.entry(unclassified_key(&app, &url, &category)).or_default();
+        e.minutes += SNAPSHOT_MIN;
+        if !title.is_empty() && e.samples.len() < 3 && !e.samples.contains(&title) { e.samples.push(title); }
+    }
+    let mut out: Vec<_> = groups.into_iter().collect();
+    out.sort_by(|a, b| b.1.minutes.total_cmp(&a.1.minutes));
+    Ok(out.into_iter().take(limit.unwrap_or(20)).map(|((kind, value), e)| serde_json::json!({
+        "kind": kind, "value": value, "minutes": e.minutes, "samples": e.samples,
+    })).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: i64, field: &str, kind: &str, pattern: &str, category: &str, score: Option<f64>, priority: i64) -> Rule {
+        Rule { id, field: field.into(), kind: kind.into(), pattern: pattern.into(), category: category.into(), score, priority }
+    }
+
+    #[test]
+    fn extracts_domains() {
+        assert_eq!(domains_in("Safari: https://www.youtube.com/watch?v=1 | Rust tutorial"), vec!["youtube.com"]);
+        assert_eq!(domains_in("Chrome: docs.rs/regex | regex - Rust"), vec!["docs.rs"]);
+        assert!(domains_in("Firefox: Pull requests · GitHub").is_empty());
+        assert!(domains_in("v1.2 release notes").is_empty());
+    }
+
+    #[test]
+    fn priority_decides_and_falls_back() {
+        let set = RuleSet::new(vec![
+            rule(1, "url", "domain", "youtube.com", "media", None, 0),
+            rule(2, "title", "regex", r"tutorial|lecture|курс", "learning", Some(0.8), 10),
+            rule(3, "app", "substring", "hanni", "coding", None, 0),
+            rule(4, "app", "regex", "(", "coding", None, 99),
+        ]);
+        let yt = "Safari: https://m.youtube.com/watch | Rust Tutorial";
+        assert_eq!(set.classify("Safari", yt, "Rust Tutorial"),
+            Classification { category: "learning".into(), score: 0.8, rule_id: Some(2) });
+        assert_eq!(set.classify("Safari", "Safari: https://youtube.com/shorts | cats", "cats").category, "media");
+        assert_eq!(set.classify("Hanni", "", "").rule_id, Some(3));
+        assert_eq!(set.classify("Telegram", "", ""), Classification { category: "communication".into(), score: 0.5, rule_id: None });
+    }
+
+    #[test]
+    fn scores_and_validation() {
+        assert_eq!(snapshot_minutes(default_score("coding")), (0.5, 0.0));
+        assert_eq!(snapshot_minutes(default_score("social")), (0.0, 0.5));
+        assert_eq!(snapshot_minutes(default_score("other")), (0.25, 0.0));
+        assert!(validate("app", "regex", "[", "coding", None).is_err());
+        assert!(validate("app", "substring", "x", "coding", Some(1.5)).is_err());
+        assert!(validate("url", "domain", "*.example.com", "work", Some(-0.3)).is_ok());
+        assert_eq!(unclassified_key("Arc", "Arc: https://news.ycombinator.com/ | HN", "browsing").1, "news.ycombinator.com");
+    }
+}
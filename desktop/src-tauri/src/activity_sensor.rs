@@ -0,0 +1,416 @@
+// activity_sensor.rs — What the user is doing right now: focused app, window
+// title, browser page, now playing, idle time and screen lock. macOS goes
+// through the AppleScript/ioreg helpers in proactive.rs / macos.rs; Linux picks
+// a backend at runtime from the session environment:
+//   windows — Sway (swaymsg), Hyprland (hyprctl), GNOME Wayland (Shell D-Bus),
+//             X11 (xprop, `_NET_ACTIVE_WINDOW`)
+//   media   — MPRIS over the session bus (playerctl, falling back to gdbus)
+//   idle    — GNOME Mutter IdleMonitor, XScreenSaver (xprintidle), logind
+// Every probe shells out with a short timeout and degrades to "" / 0 when a
+// tool is missing, so a partial setup still yields snapshots.
+use serde::Serialize;
+use std::sync::OnceLock;
+
+/// One reading for the snapshot collector and chat context.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ActivityReading {
+    pub app: String,
+    pub window_title: String,
+    /// "Browser: url | title" on macOS; "Browser: title" on Linux, where the
+    /// URL isn't exposed without a browser extension.
+    pub browser: String,
+    pub music: String,
+    pub idle_secs: f64,
+    pub screen_locked: bool,
+}
+
+pub trait ActivitySensor: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn frontmost_app(&self) -> String;
+    fn window_title(&self) -> String;
+    fn browser_url(&self) -> String;
+    fn now_playing(&self) -> String;
+    fn idle_seconds(&self) -> f64;
+    fn screen_locked(&self) -> bool;
+
+    fn read(&self) -> ActivityReading {
+        ActivityReading {
+            app: self.frontmost_app(),
+            window_title: self.window_title(),
+            browser: self.browser_url(),
+            music: self.now_playing(),
+            idle_secs: self.idle_seconds(),
+            screen_locked: self.screen_locked(),
+        }
+    }
+}
+
+/// The sensor for this machine, chosen once.
+pub fn sensor() -> &'static dyn ActivitySensor {
+    static SENSOR: OnceLock<Box<dyn ActivitySensor>> = OnceLock::new();
+    SENSOR.get_or_init(|| {
+        if cfg!(target_os = "linux") {
+            let backend = detect_backend(|k| std::env::var(k).ok());
+            eprintln!("[activity] linux sensor, window backend: {:?}", backend);
+            Box::new(LinuxSensor { backend })
+        } else {
+            Box::new(MacSensor)
+        }
+    }).as_ref()
+}
+
+// ── macOS ──
+
+pub struct MacSensor;
+
+impl ActivitySensor for MacSensor {
+    fn name(&self) -> &'static str { "macos" }
+    fn frontmost_app(&self) -> String { crate::proactive::get_frontmost_app() }
+    fn window_title(&self) -> String { crate::proactive::get_window_title() }
+    fn browser_url(&self) -> String { crate::proactive::get_browser_url() }
+    fn now_playing(&self) -> String { crate::proactive::get_now_playing_sync() }
+    fn idle_seconds(&self) -> f64 { crate::macos::get_macos_idle_seconds() }
+    fn screen_locked(&self) -> bool { crate::macos::is_screen_locked() }
+}
+
+// ── Linux ──
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WindowBackend {
+    Sway,
+    Hyprland,
+    Gnome,
+    X11,
+    None,
+}
+
+pub(crate) fn detect_backend(env: impl Fn(&str) -> Option<String>) -> WindowBackend {
+    let set = |k: &str| env(k).is_some_and(|v| !v.is_empty());
+    let desktop = env("XDG_CURRENT_DESKTOP").unwrap_or_default().to_lowercase();
+    let wayland = env("XDG_SESSION_TYPE").as_deref() == Some("wayland") || set("WAYLAND_DISPLAY");
+    if set("SWAYSOCK") { return WindowBackend::Sway; }
+    if set("HYPRLAND_INSTANCE_SIGNATURE") { return WindowBackend::Hyprland; }
+    if wayland && desktop.contains("gnome") { return WindowBackend::Gnome; }
+    // Other Wayland compositors still run XWayland; X11 apps are better than nothing.
+    if set("DISPLAY") { return WindowBackend::X11; }
+    WindowBackend::None
+}
+
+pub struct LinuxSensor {
+    backend: WindowBackend,
+}
+
+/// (app, title) of the focused window.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct FocusedWindow {
+    pub app: String,
+    pub title: String,
+}
+
+const BROWSERS: &[(&str, &str)] = &[
+    ("firefox", "Firefox"), ("librewolf", "LibreWolf"), ("chromium", "Chromium"), ("google-chrome", "Google Chrome"),
+    ("brave", "Brave"), ("vivaldi", "Vivaldi"), ("microsoft-edge", "Edge"), ("zen", "Zen"), ("epiphany", "Web"),
+];
+
+/// Run a helper with a timeout; stdout on success. Stdout is drained on a
+/// thread while we wait — `swaymsg -t get_tree` / `hyprctl clients -j` print
+/// more than a pipe buffer and would otherwise block until the timeout.
+fn run(cmd: &str, args: &[&str]) -> Option<String> {
+    let mut child = std::process::Command::new(cmd)
+        .args(args)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .spawn().ok()?;
+    let mut stdout = child.stdout.take()?;
+    let reader = std::thread::spawn(move || {
+        use std::io::Read;
+        let mut out = String::new();
+        stdout.read_to_string(&mut out).ok().map(|_| out)
+    });
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(2);
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                let out = reader.join().ok().flatten();
+                return if status.success() { out } else { None };
+            }
+            Ok(None) if std::time::Instant::now() < deadline => std::thread::sleep(std::time::Duration::from_millis(20)),
+            _ => {
+                // The reader ends on its own once the pipe closes; don't wait on it
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+        }
+    }
+}
+
+/// Strip the gdbus tuple wrapper: `('payload',)` → `payload`.
+fn gdbus_string(out: &str) -> Option<String> {
+    let s = out.trim().strip_prefix("('")?.strip_suffix("',)")?;
+    Some(s.replace("\\'", "'").replace("\\\\", "\\"))
+}
+
+/// `_NET_WM_NAME(UTF8_STRING) = "title"` / `WM_CLASS(STRING) = "inst", "Class"`.
+pub(crate) fn parse_xprop_window(out: &str) -> FocusedWindow {
+    let mut w = FocusedWindow::default();
+    let quoted = |line: &str| -> Vec<String> {
+        line.split_once('=').map(|(_, v)| v).unwrap_or("")
+            .split("\", \"").map(|s| s.trim().trim_matches('"').replace("\\\"", "\"")).collect()
+    };
+    for line in out.lines() {
+        if line.starts_with("WM_CLASS") {
+            w.app = quoted(line).pop().unwrap_or_default();
+        } else if line.starts_with("_NET_WM_NAME") || (line.starts_with("WM_NAME") && w.title.is_empty()) {
+            w.title = quoted(line).join("\", \"");
+        }
+    }
+    w
+}
+
+/// Focused leaf of `swaymsg -t get_tree -r`.
+pub(crate) fn parse_sway_tree(json: &str) -> Option<FocusedWindow> {
+    fn walk(node: &serde_json::Value) -> Option<&serde_json::Value> {
+        if node["focused"].as_bool() == Some(true) && node["type"].as_str() != Some("workspace") { return Some(node); }
+        ["nodes", "floating_nodes"].iter()
+            .filter_map(|k| node[*k].as_array())
+            .flatten()
+            .find_map(walk)
+    }
+    let tree: serde_json::Value = serde_json::from_str(json).ok()?;
+    let node = walk(&tree)?;
+    let app = node["app_id"].as_str().filter(|s| !s.is_empty())
+        .or_else(|| node["window_properties"]["class"].as_str())
+        .unwrap_or_default();
+    Some(FocusedWindow { app: app.to_string(), title: node["name"].as_str().unwrap_or_default().to_string() })
+}
+
+/// `hyprctl activewindow -j`.
+pub(crate) fn parse_hyprctl(json: &str) -> Option<FocusedWindow> {
+    let v: serde_json::Value = serde_json::from_str(json).ok()?;
+    Some(FocusedWindow {
+        app: v["class"].as_str().unwrap_or_default().to_string(),
+        title: v["title"].as_str().unwrap_or_default().to_string(),
+    })
+}
+
+/// Window Calls extension (`org.gnome.Shell.Extensions.Windows.List`): JSON
+/// array with `wm_class`, `title`, `focus`.
+pub(crate) fn parse_gnome_windows(json: &str) -> Option<FocusedWindow> {
+    let list: Vec<serde_json::Value> = serde_json::from_str(json).ok()?;
+    let w = list.iter().find(|w| w["focus"].as_bool() == Some(true))?;
+    Some(FocusedWindow {
+        app: w["wm_class"].as_str().unwrap_or_default().to_string(),
+        title: w["title"].as_str().unwrap_or_default().to_string(),
+    })
+}
+
+/// Friendly browser name and page title. Browsers suffix their window title
+/// with their own name ("Page — Mozilla Firefox"); drop that.
+pub(crate) fn browser_from_window(w: &FocusedWindow) -> String {
+    let app = w.app.to_lowercase();
+    let Some((_, name)) = BROWSERS.iter().find(|(k, _)| app.contains(k)) else { return String::new() };
+    let page = [" — ", " - "].iter()
+        .find_map(|sep| w.title.rsplit_once(sep).map(|(page, _)| page))
+        .unwrap_or(&w.title);
+    if page.is_empty() { String::new() } else { format!("{}: {}", name, page) }
+}
+
+/// `playerctl metadata --format '{{status}}\t{{artist}}\t{{title}}'`.
+pub(crate) fn parse_playerctl(out: &str) -> String {
+    let mut parts = out.trim().splitn(3, '\t');
+    let (status, artist, title) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    match (status, title.is_empty()) {
+        ("Playing", false) if !artist.is_empty() => format!("{} — {}", title, artist),
+        ("Playing", false) => title.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// MPRIS Metadata as printed by gdbus: `'xesam:title': <'Song'>`,
+/// `'xesam:artist': <['Artist']>`.
+pub(crate) fn parse_mpris_metadata(out: &str) -> (String, String) {
+    let field = |key: &str| -> String {
+        let Some(pos) = out.find(&format!("'{}': <", key)) else { return String::new() };
+        let rest = &out[pos + key.len() + 5..];
+        let rest = rest.trim_start_matches('[').trim_start_matches("@as ").trim_start_matches('[');
+        rest.strip_prefix('\'').and_then(|r| r.find('\'').map(|end| r[..end].to_string())).unwrap_or_default()
+    };
+    (field("xesam:title"), field("xesam:artist"))
+}
+
+/// `loginctl show-session -p IdleHint -p IdleSinceHint -p LockedHint`.
+/// Returns (idle seconds, locked).
+pub(crate) fn parse_loginctl(out: &str, now_us: u64) -> (f64, bool) {
+    let prop = |k: &str| out.lines().find_map(|l| l.strip_prefix(&format!("{}=", k))).unwrap_or("");
+    let idle = if prop("IdleHint") == "yes" {
+        prop("IdleSinceHint").parse::<u64>().ok().filter(|s| *s > 0 && *s <= now_us)
+            .map(|since| (now_us - since) as f64 / 1e6).unwrap_or(0.0)
+    } else { 0.0 };
+    (idle, prop("LockedHint") == "yes")
+}
+
+/// `(uint64 12345,)` → 12345.
+fn gdbus_number(out: &str) -> Option<u64> {
+    out.trim().trim_start_matches('(').trim_end_matches(",)").trim_start_matches("uint64 ").trim().parse().ok()
+}
+
+impl LinuxSensor {
+    fn focused(&self) -> FocusedWindow {
+        let w = match self.backend {
+            WindowBackend::Sway => run("swaymsg", &["-t", "get_tree", "-r"]).and_then(|o| parse_sway_tree(&o)),
+            WindowBackend::Hyprland => run("hyprctl", &["activewindow", "-j"]).and_then(|o| parse_hyprctl(&o)),
+            WindowBackend::Gnome => run("gdbus", &["call", "--session", "--dest", "org.gnome.Shell",
+                "--object-path", "/org/gnome/Shell/Extensions/Windows", "--method", "org.gnome.Shell.Extensions.Windows.List"])
+                .and_then(|o| gdbus_string(&o)).and_then(|j| parse_gnome_windows(&j)),
+            WindowBackend::X11 => run("xprop", &["-root", "_NET_ACTIVE_WINDOW"])
+                .and_then(|o| o.split_whitespace().last().map(str::to_string))
+                .filter(|id| id.starts_with("0x") && id != "0x0")
+                .and_then(|id| run("xprop", &["-id", &id, "WM_CLASS", "_NET_WM_NAME", "WM_NAME"]))
+                .map(|o| parse_xprop_window(&o)),
+            WindowBackend::None => None,
+        };
+        let mut w = w.unwrap_or_default();
+        // The webview reports its binary name; show the app's own name like macOS does.
+        if w.app.eq_ignore_ascii_case("hanni") { w.app = "Hanni".into(); }
+        w
+    }
+
+    fn session_hints(&self) -> Option<String> {
+        let id = std::env::var("XDG_SESSION_ID").unwrap_or_else(|_| "auto".into());
+        run("loginctl", &["show-session", &id, "-p", "IdleHint", "-p", "IdleSinceHint", "-p", "LockedHint"])
+    }
+}
+
+impl ActivitySensor for LinuxSensor {
+    fn name(&self) -> &'static str {
+        match self.backend {
+            WindowBackend::Sway => "linux-sway",
+            WindowBackend::Hyprland => "linux-hyprland",
+            WindowBackend::Gnome => "linux-gnome",
+            WindowBackend::X11 => "linux-x11",
+            WindowBackend::None => "linux",
+        }
+    }
+    fn frontmost_app(&self) -> String { self.focused().app }
+    fn window_title(&self) -> String { self.focused().title }
+    fn browser_url(&self) -> String { browser_from_window(&self.focused()) }
+
+    fn now_playing(&self) -> String {
+        if let Some(out) = run("playerctl", &["metadata", "--format", "{{status}}\t{{artist}}\t{{title}}"]) {
+            return parse_playerctl(&out);
+        }
+        let names = run("gdbus", &["call", "--session", "--dest", "org.freedesktop.DBus",
+            "--object-path", "/org/freedesktop/DBus", "--method", "org.freedesktop.DBus.ListNames"]).unwrap_or_default();
+        for player in names.split('\'').filter(|n| n.starts_with("org.mpris.MediaPlayer2.")) {
+            let get = |prop: &str| run("gdbus", &["call", "--session", "--dest", player, "--object-path", "/org/mpris/MediaPlayer2",
+                "--method", "org.freedesktop.DBus.Properties.Get", "org.mpris.MediaPlayer2.Player", prop]);
+            if !get("PlaybackStatus").is_some_and(|s| s.contains("Playing")) { continue; }
+            let (title, artist) = parse_mpris_metadata(&get("Metadata").unwrap_or_default());
+            if title.is_empty() { continue; }
+            return if artist.is_empty() { title } else { format!("{} — {}", title, artist) };
+        }
+        String::new()
+    }
+
+    fn idle_seconds(&self) -> f64 {
+        if self.backend == WindowBackend::Gnome {
+            if let Some(ms) = run("gdbus", &["call", "--session", "--dest", "org.gnome.Mutter.IdleMonitor",
+                "--object-path", "/org/gnome/Mutter/IdleMonitor/Core", "--method", "org.gnome.Mutter.IdleMonitor.GetIdletime"])
+                .and_then(|o| gdbus_number(&o)) {
+                return ms as f64 / 1000.0;
+            }
+        }
+        if self.backend == WindowBackend::X11 {
+            if let Some(ms) = run("xprintidle", &[]).and_then(|o| o.trim().parse::<u64>().ok()) {
+                return ms as f64 / 1000.0;
+            }
+        }
+        let now_us = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_micros() as u64).unwrap_or(0);
+        self.session_hints().map(|o| parse_loginctl(&o, now_us).0).unwrap_or(0.0)
+    }
+
+    fn screen_locked(&self) -> bool {
+        if self.backend == WindowBackend::Gnome {
+            if let Some(out) = run("gdbus", &["call", "--session", "--dest", "org.gnome.ScreenSaver",
+                "--object-path", "/org/gnome/ScreenSaver", "--method", "org.gnome.ScreenSaver.GetActive"]) {
+                return out.contains("true");
+            }
+        }
+        self.session_hints().is_some_and(|o| parse_loginctl(&o, 0).1)
+    }
+
+    /// One window probe per reading instead of three.
+    fn read(&self) -> ActivityReading {
+        let w = self.focused();
+        ActivityReading {
+            browser: browser_from_window(&w),
+            app: w.app,
+            window_title: w.title,
+            music: self.now_playing(),
+            idle_secs: self.idle_seconds(),
+            screen_locked: self.screen_locked(),
+        }
+    }
+}
+
+// ── Commands ──
+
+/// Which sensor is active and what it sees right now (Settings diagnostics).
+#[tauri::command]
+pub async fn get_activity_sensor_status() -> Result<serde_json::Value, String> {
+    tokio::task::spawn_blocking(|| {
+        let s = sensor();
+        serde_json::json!({ "sensor": s.name(), "reading": s.read() })
+    }).await.map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_backend_from_env() {
+        let env = |pairs: &'static [(&'static str, &'static str)]| move |k: &str| pairs.iter().find(|(n, _)| *n == k).map(|(_, v)| v.to_string());
+        assert_eq!(detect_backend(env(&[("SWAYSOCK", "/run/sway.sock"), ("DISPLAY", ":0")])), WindowBackend::Sway);
+        assert_eq!(detect_backend(env(&[("XDG_SESSION_TYPE", "wayland"), ("XDG_CURRENT_DESKTOP", "ubuntu:GNOME")])), WindowBackend::Gnome);
+        assert_eq!(detect_backend(env(&[("XDG_SESSION_TYPE", "x11"), ("XDG_CURRENT_DESKTOP", "GNOME"), ("DISPLAY", ":0")])), WindowBackend::X11);
+        assert_eq!(detect_backend(env(&[])), WindowBackend::None);
+    }
+
+    #[test]
+    fn parses_window_sources() {
+        let x = "WM_CLASS(STRING) = \"Navigator\", \"firefox\"\n_NET_WM_NAME(UTF8_STRING) = \"Pull requests · GitHub — Mozilla Firefox\"\n";
+        let w = parse_xprop_window(x);
+        assert_eq!(w.app, "firefox");
+        assert_eq!(browser_from_window(&w), "Firefox: Pull requests · GitHub");
+
+        let sway = r#"{"type":"root","focused":false,"nodes":[{"type":"workspace","focused":false,"nodes":[
+            {"type":"con","focused":false,"app_id":"foot","name":"~"},
+            {"type":"con","focused":true,"app_id":null,"window_properties":{"class":"Code"},"name":"main.rs - Visual Studio Code"}]}]}"#;
+        assert_eq!(parse_sway_tree(sway).unwrap(), FocusedWindow { app: "Code".into(), title: "main.rs - Visual Studio Code".into() });
+        assert_eq!(parse_hyprctl(r#"{"class":"kitty","title":"nvim"}"#).unwrap().app, "kitty");
+
+        let gdbus = "('[{\"wm_class\":\"org.telegram.desktop\",\"title\":\"Telegram\",\"focus\":true}]',)";
+        assert_eq!(parse_gnome_windows(&gdbus_string(gdbus).unwrap()).unwrap().app, "org.telegram.desktop");
+    }
+
+    #[test]
+    fn run_reads_output_larger_than_a_pipe_buffer() {
+        let out = run("sh", &["-c", "head -c 200000 /dev/zero | tr '\\0' a"]).unwrap();
+        assert_eq!(out.len(), 200_000);
+        assert_eq!(run("sh", &["-c", "exit 1"]), None);
+    }
+
+    #[test]
+    fn parses_media_and_idle() {
+        assert_eq!(parse_playerctl("Playing\tRadiohead\tReckoner\n"), "Reckoner — Radiohead");
+        assert_eq!(parse_playerctl("Paused\tRadiohead\tReckoner\n"), "");
+        let meta = "(<{'mpris:trackid': <objectpath '/x'>, 'xesam:title': <'Reckoner'>, 'xesam:artist': <['Radiohead']>}>,)";
+        assert_eq!(parse_mpris_metadata(meta), ("Reckoner".to_string(), "Radiohead".to_string()));
+        let (idle, locked) = parse_loginctl("IdleHint=yes\nIdleSinceHint=1000000000\nLockedHint=yes\n", 1_090_000_000);
+        assert_eq!((idle, locked), (90.0, true));
+        assert_eq!(parse_loginctl("IdleHint=no\nIdleSinceHint=0\nLockedHint=no\n", 5), (0.0, false));
+        assert_eq!(gdbus_number("(uint64 4200,)"), Some(4200));
+    }
+}
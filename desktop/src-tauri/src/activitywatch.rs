@@ -0,0 +1,431 @@
+// activitywatch.rs — ActivityWatch bucket export ⇄ activity_snapshots.
+// Import resamples window / afk / web events onto Hanni's 30-sec snapshot grid
+// (so every report that counts 0.5 min per snapshot keeps working), classifies
+// them with the user's rules, skips minutes Hanni already recorded itself, and
+// rebuilds the auto AFK/Focus timeline blocks of each touched day. Export goes
+// the other way: consecutive snapshots collapse into AW events, one bucket per
+// watcher, in the format `aw-server` accepts on /api/0/import.
+use crate::types::HanniDb;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use tauri::Manager;
+
+/// Snapshot spacing of the live collector.
+const SLOT_SECS: i64 = 30;
+/// Same AFK threshold as the live collector.
+const AFK_IDLE_SECS: f64 = 120.0;
+/// Snapshots further apart than this end an exported event.
+const MERGE_GAP_SECS: i64 = 60;
+/// `activity_snapshots.source` of imported rows.
+pub const SOURCE: &str = "activitywatch";
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct AwEvent {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub timestamp: String,
+    #[serde(default)]
+    pub duration: f64,
+    #[serde(default)]
+    pub data: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct AwBucket {
+    #[serde(default)]
+    pub id: String,
+    #[serde(rename = "type", default)]
+    pub kind: String,
+    #[serde(default)]
+    pub client: String,
+    #[serde(default)]
+    pub hostname: String,
+    #[serde(default)]
+    pub created: String,
+    #[serde(default)]
+    pub events: Vec<AwEvent>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AwExport {
+    pub buckets: HashMap<String, AwBucket>,
+}
+
+/// Full export (`{"buckets": {...}}`) or a single bucket file.
+pub fn parse_export(json: &str) -> Result<Vec<AwBucket>, String> {
+    if let Ok(export) = serde_json::from_str::<AwExport>(json) {
+        return Ok(export.buckets.into_iter().map(|(id, mut b)| { if b.id.is_empty() { b.id = id; } b }).collect());
+    }
+    serde_json::from_str::<AwBucket>(json).map(|b| vec![b])
+        .map_err(|e| format!("Не похоже на экспорт ActivityWatch: {}", e))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BucketKind { Window, Afk, Web, Other }
+
+fn bucket_kind(b: &AwBucket) -> BucketKind {
+    match b.kind.as_str() {
+        "currentwindow" => BucketKind::Window,
+        "afkstatus" => BucketKind::Afk,
+        k if k.starts_with("web.tab") => BucketKind::Web,
+        _ if b.id.starts_with("aw-watcher-window") => BucketKind::Window,
+        _ if b.id.starts_with("aw-watcher-afk") => BucketKind::Afk,
+        _ if b.id.starts_with("aw-watcher-web") => BucketKind::Web,
+        _ => BucketKind::Other,
+    }
+}
+
+/// "aw-watcher-web-google-chrome_host" → "Google Chrome".
+fn browser_name(bucket_id: &str) -> String {
+    let name = bucket_id.trim_start_matches("aw-watcher-web").trim_start_matches('-');
+    let name = name.split('_').next().unwrap_or("");
+    let title: Vec<String> = name.split('-').filter(|w| !w.is_empty()).map(|w| {
+        let mut c = w.chars();
+        c.next().map(|f| f.to_uppercase().chain(c).collect()).unwrap_or_default()
+    }).collect();
+    if title.is_empty() { "Browser".into() } else { title.join(" ") }
+}
+
+/// "Arc: https://x | title" → ("Arc", "https://x", "title").
+fn split_browser(s: &str) -> Option<(&str, &str, &str)> {
+    let (name, rest) = s.split_once(": ")?;
+    let (url, title) = rest.split_once(" | ").unwrap_or((rest, ""));
+    url.contains("://").then_some((name, url, title))
+}
+
+/// A timed value from one watcher; `a`/`b` are app/title, status, or url/title.
+#[derive(Debug, Clone)]
+struct Span {
+    start: f64,
+    end: f64,
+    a: String,
+    b: String,
+}
+
+fn spans(events: &[AwEvent], a_key: &str, b_key: &str) -> Vec<Span> {
+    let text = |e: &AwEvent, k: &str| e.data.get(k).and_then(|v| v.as_str()).unwrap_or("").to_string();
+    let mut out: Vec<Span> = events.iter().filter_map(|e| {
+        let start = chrono::DateTime::parse_from_rfc3339(&e.timestamp).ok()?.timestamp_millis() as f64 / 1000.0;
+        Some(Span { start, end: start + e.duration.max(0.0), a: text(e, a_key), b: text(e, b_key) })
+    }).collect();
+    out.sort_by(|x, y| x.start.total_cmp(&y.start));
+    out
+}
+
+/// Latest span that started at or before `t` and still covers it.
+fn covering(spans: &[Span], t: f64) -> Option<&Span> {
+    let i = spans.partition_point(|s| s.start <= t);
+    spans[..i].iter().rev().take(8).find(|s| s.end > t)
+}
+
+/// One resampled snapshot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Slot {
+    pub ts: i64,
+    pub app: String,
+    pub title: String,
+    pub browser: String,
+    pub idle_secs: f64,
+}
+
+pub fn resample(buckets: &[AwBucket]) -> Vec<Slot> {
+    let (mut window, mut afk, mut web) = (Vec::new(), Vec::new(), Vec::new());
+    for b in buckets {
+        match bucket_kind(b) {
+            BucketKind::Window => window.extend(spans(&b.events, "app", "title")),
+            BucketKind::Afk => afk.extend(spans(&b.events, "status", "")),
+            BucketKind::Web => {
+                let name = browser_name(&b.id);
+                web.extend(spans(&b.events, "url", "title").into_iter().map(|mut s| {
+                    s.a = format!("{}: {}", name, s.a);
+                    s
+                }));
+            }
+            BucketKind::Other => {}
+        }
+    }
+    for v in [&mut window, &mut afk, &mut web] { v.sort_by(|x, y| x.start.total_cmp(&y.start)); }
+    let bounds = window.iter().chain(&afk).fold(None, |acc: Option<(f64, f64)>, s| {
+        Some(acc.map_or((s.start, s.end), |(lo, hi)| (lo.min(s.start), hi.max(s.end))))
+    });
+    let Some((lo, hi)) = bounds else { return Vec::new() };
+
+    let mut out = Vec::new();
+    let mut t = (lo as i64).div_euclid(SLOT_SECS) * SLOT_SECS;
+    if (t as f64) < lo { t += SLOT_SECS; }
+    while (t as f64) < hi {
+        let tf = t as f64;
+        let w = covering(&window, tf);
+        let away = covering(&afk, tf).filter(|s| s.a == "afk");
+        if w.is_some() || away.is_some() {
+            out.push(Slot {
+                ts: t,
+                app: w.map(|s| s.a.clone()).unwrap_or_default(),
+                title: w.map(|s| s.b.clone()).unwrap_or_default(),
+                browser: covering(&web, tf).map(|s| if s.b.is_empty() { s.a.clone() } else { format!("{} | {}", s.a, s.b) }).unwrap_or_default(),
+                // An AFK span is already past the watcher's timeout, so every slot in it reads as away.
+                idle_secs: away.map(|s| (tf - s.start).max(AFK_IDLE_SECS)).unwrap_or(0.0),
+            });
+        }
+        t += SLOT_SECS;
+    }
+    out
+}
+
+/// Snapshot row as read for export.
+#[derive(Debug, Clone)]
+pub struct SnapshotRow {
+    pub ts: i64,
+    pub app: String,
+    pub title: String,
+    pub browser: String,
+    pub afk: bool,
+}
+
+fn to_timestamp(ts: i64) -> String {
+    chrono::DateTime::from_timestamp(ts, 0).unwrap_or_default().to_rfc3339_opts(chrono::SecondsFormat::Secs, false)
+}
+
+/// Collapse consecutive rows with the same key into events.
+fn collapse(rows: &[SnapshotRow], key: impl Fn(&SnapshotRow) -> Option<serde_json::Value>) -> Vec<AwEvent> {
+    let mut events = Vec::new();
+    let mut cur: Option<(i64, i64, serde_json::Value)> = None;
+    let close = |c: (i64, i64, serde_json::Value), events: &mut Vec<AwEvent>| {
+        let serde_json::Value::Object(data) = c.2 else { return };
+        events.push(AwEvent { id: None, timestamp: to_timestamp(c.0), duration: (c.1 - c.0 + SLOT_SECS) as f64, data });
+    };
+    for r in rows {
+        let k = key(r);
+        match (&mut cur, k) {
+            (Some((_, last, v)), Some(k)) if *v == k && r.ts - *last <= MERGE_GAP_SECS => *last = r.ts,
+            (_, k) => {
+                if let Some(c) = cur.take() { close(c, &mut events); }
+                cur = k.map(|k| (r.ts, r.ts, k));
+            }
+        }
+    }
+    if let Some(c) = cur { close(c, &mut events); }
+    events
+}
+
+pub fn build_export(rows: &[SnapshotRow], host: &str) -> AwExport {
+    let created = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false);
+    let bucket = |id: String, kind: &str, events: Vec<AwEvent>| AwBucket {
+        id, kind: kind.into(), client: "hanni".into(), hostname: host.into(), created: created.clone(), events,
+    };
+    let mut buckets = HashMap::new();
+    let window = collapse(rows, |r| (!r.app.is_empty()).then(|| serde_json::json!({ "app": r.app, "title": r.title })));
+    let afk = collapse(rows, |r| Some(serde_json::json!({ "status": if r.afk { "afk" } else { "not-afk" } })));
+    for (id, kind, events) in [
+        (format!("aw-watcher-window_{}", host), "currentwindow", window),
+        (format!("aw-watcher-afk_{}", host), "afkstatus", afk),
+    ] {
+        if !events.is_empty() { buckets.insert(id.clone(), bucket(id, kind, events)); }
+    }
+    let mut browsers: Vec<&str> = rows.iter().filter_map(|r| split_browser(&r.browser).map(|b| b.0)).collect();
+    browsers.sort();
+    browsers.dedup();
+    for name in browsers {
+        let events = collapse(rows, |r| split_browser(&r.browser)
+            .filter(|b| b.0 == name)
+            .map(|(_, url, title)| serde_json::json!({ "url": url, "title": title, "audible": false, "incognito": false })));
+        let id = format!("aw-watcher-web-{}_{}", name.to_lowercase().replace(' ', "-"), host);
+        buckets.insert(id.clone(), bucket(id, "web.tab.current", events));
+    }
+    AwExport { buckets }
+}
+
+fn host_name() -> String {
+    ["HOSTNAME", "COMPUTERNAME"].iter().find_map(|k| std::env::var(k).ok().filter(|h| !h.is_empty()))
+        .unwrap_or_else(|| "hanni".into())
+}
+
+fn expand_path(path: &str) -> std::path::PathBuf {
+    let path = path.trim();
+    match path.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir().unwrap_or_default().join(rest),
+        None => std::path::PathBuf::from(path),
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ImportStats {
+    pub buckets: usize,
+    pub snapshots: usize,
+    pub skipped_existing: usize,
+    pub days: usize,
+    pub timeline_blocks: i64,
+}
+
+pub fn import_buckets(conn: &mut rusqlite::Connection, buckets: &[AwBucket]) -> Result<ImportStats, String> {
+    let slots = resample(buckets);
+    let mut stats = ImportStats {
+        buckets: buckets.iter().filter(|b| bucket_kind(b) != BucketKind::Other).count(),
+        ..Default::default()
+    };
+    let (Some(first), Some(last)) = (slots.first(), slots.last()) else { return Ok(stats) };
+    let local = |ts: i64| chrono::DateTime::from_timestamp(ts, 0).unwrap_or_default().with_timezone(&chrono::Local);
+    let (from, to) = (local(first.ts).to_rfc3339(), local(last.ts + SLOT_SECS).to_rfc3339());
+
+    // Minutes Hanni recorded itself win over imported ones.
+    let own: HashSet<String> = {
+        let mut stmt = conn.prepare(
+            "SELECT substr(captured_at,1,16) FROM activity_snapshots
+             WHERE captured_at >= ?1 AND captured_at < ?2 AND COALESCE(source,'') != ?3"
+        ).map_err(|e| format!("DB error: {}", e))?;
+        let own = stmt.query_map(rusqlite::params![from, to, SOURCE], |r| r.get::<_, String>(0))
+            .map_err(|e| format!("Query error: {}", e))?.flatten().collect();
+        own
+    };
+    let rules = crate::activity_rules::RuleSet::load(conn);
+    let mut days = BTreeSet::new();
+    let tx = conn.transaction().map_err(|e| format!("DB error: {}", e))?;
+    // Re-importing the same range replaces the previous import.
+    tx.execute("DELETE FROM activity_snapshots WHERE source=?1 AND captured_at >= ?2 AND captured_at < ?3",
+        rusqlite::params![SOURCE, from, to]).map_err(|e| format!("DB error: {}", e))?;
+    {
+        let mut ins = tx.prepare(
+            "INSERT INTO activity_snapshots (captured_at, hour, weekday, frontmost_app, browser_url, music_playing, productive_min, distraction_min, idle_secs, window_title, category, screen_locked, source)
+             VALUES (?1, ?2, ?3, ?4, ?5, '', ?6, ?7, ?8, ?9, ?10, 0, ?11)"
+        ).map_err(|e| format!("DB error: {}", e))?;
+        for s in &slots {
+            use chrono::{Datelike, Timelike};
+            let at = local(s.ts);
+            let captured_at = at.to_rfc3339();
+            if own.contains(&captured_at[..16]) { stats.skipped_existing += 1; continue; }
+            let (category, prod, dist) = if s.idle_secs >= AFK_IDLE_SECS {
+                ("afk".to_string(), 0.0, 0.0)
+            } else {
+                let c = rules.classify(&s.app, &s.browser, &s.title);
+                let (prod, dist) = crate::activity_rules::snapshot_minutes(c.score);
+                (c.category, prod, dist)
+            };
+            ins.execute(rusqlite::params![
+                captured_at, at.hour(), at.weekday().num_days_from_monday(), s.app, s.browser,
+                prod, dist, s.idle_secs, s.title, category, SOURCE,
+            ]).map_err(|e| format!("DB error: {}", e))?;
+            days.insert(captured_at[..10].to_string());
+            stats.snapshots += 1;
+        }
+    }
+    tx.commit().map_err(|e| format!("DB error: {}", e))?;
+    for day in &days {
+        stats.timeline_blocks += crate::timeline_reconstruct::rebuild_day(conn, day).map(|(n, _)| n).unwrap_or(0);
+    }
+    stats.days = days.len();
+    Ok(stats)
+}
+
+// ── Commands ──
+
+/// Import an ActivityWatch export (Settings → Export all buckets, or a single
+/// bucket's JSON) from disk.
+#[tauri::command]
+pub async fn import_activitywatch(path: String, app: tauri::AppHandle) -> Result<ImportStats, String> {
+    tokio::task::spawn_blocking(move || {
+        let json = std::fs::read_to_string(expand_path(&path)).map_err(|e| format!("Не удалось прочитать файл: {}", e))?;
+        let buckets = parse_export(&json)?;
+        drop(json);
+        let db = app.state::<HanniDb>();
+        let mut conn = db.conn();
+        import_buckets(&mut conn, &buckets)
+    }).await.map_err(|e| format!("Import task failed: {}", e))?
+}
+
+/// Write snapshots between `from` and `to` (YYYY-MM-DD, inclusive; default:
+/// everything) as an ActivityWatch export.
+#[tauri::command]
+pub fn export_activitywatch(path: String, from: Option<String>, to: Option<String>, db: tauri::State<'_, HanniDb>) -> Result<serde_json::Value, String> {
+    let from = from.unwrap_or_else(|| "0000-00-00".into());
+    let to = to.map(|d| format!("{}~", d)).unwrap_or_else(|| "9999".into());
+    let rows: Vec<SnapshotRow> = {
+        let conn = db.read();
+        let mut stmt = conn.prepare(
+            "SELECT captured_at, COALESCE(frontmost_app,''), COALESCE(window_title,''), COALESCE(browser_url,''), COALESCE(category,'') = 'afk' OR COALESCE(screen_locked,0) = 1
+             FROM activity_snapshots WHERE captured_at >= ?1 AND captured_at < ?2 ORDER BY captured_at"
+        ).map_err(|e| format!("DB error: {}", e))?;
+        let rows = stmt.query_map(rusqlite::params![from, to], |r| Ok((r.get::<_, String>(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?)))
+            .map_err(|e| format!("Query error: {}", e))?
+            .flatten()
+            .filter_map(|(at, app, title, browser, afk)| Some(SnapshotRow {
+                ts: chrono::DateTime::parse_from_rfc3339(&at).ok()?.timestamp(), app, title, browser, afk,
+            }))
+            .collect();
+        rows
+    };
+    if rows.is_empty() { return Err("Нет данных активности за выбранный период".into()); }
+    let export = build_export(&rows, &host_name());
+    let events: usize = export.buckets.values().map(|b| b.events.len()).sum();
+    let path = expand_path(&path);
+    let json = serde_json::to_string(&export).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| format!("Не удалось записать файл: {}", e))?;
+    Ok(serde_json::json!({ "path": path.to_string_lossy(), "buckets": export.buckets.len(), "events": events, "snapshots": rows.len() }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = r#"{"buckets": {
+      "aw-watcher-window_mac": {"id": "aw-watcher-window_mac", "type": "currentwindow", "client": "aw-watcher-window", "hostname": "mac", "events": [
+        {"id": 2, "timestamp": "2024-03-01T10:01:00+00:00", "duration": 60.0, "data": {"app": "Firefox", "title": "Rust docs"}},
+        {"id": 1, "timestamp": "2024-03-01T10:00:00.250+00:00", "duration": 59.5, "data": {"app": "Code", "title": "main.rs"}}
+      ]},
+      "aw-watcher-afk_mac": {"type": "afkstatus", "events": [
+        {"timestamp": "2024-03-01T10:00:00+00:00", "duration": 120.0, "data": {"status": "not-afk"}},
+        {"timestamp": "2024-03-01T10:02:00+00:00", "duration": 300.0, "data": {"status": "afk"}}
+      ]},
+      "aw-watcher-web-firefox_mac": {"type": "web.tab.current", "events": [
+        {"timestamp": "2024-03-01T10:01:00+00:00", "duration": 60.0, "data": {"url": "https://doc.rust-lang.org/std/", "title": "std"}}
+      ]},
+      "aw-stopwatch": {"type": "general.stopwatch", "events": []}
+    }}"#;
+
+    #[test]
+    fn resamples_buckets() {
+        let buckets = parse_export(EXPORT).unwrap();
+        let slots = resample(&buckets);
+        let t0 = chrono::DateTime::parse_from_rfc3339("2024-03-01T10:00:00+00:00").unwrap().timestamp();
+        // 10:00:30 .. 10:06:30 — first slot after the fractional start, last inside the AFK span.
+        assert_eq!(slots.first().unwrap().ts, t0 + 30);
+        assert_eq!(slots.len(), 13);
+        assert_eq!((slots[0].app.as_str(), slots[0].browser.as_str()), ("Code", ""));
+        assert_eq!(slots[1].app, "Firefox");
+        assert_eq!(slots[1].browser, "Firefox: https://doc.rust-lang.org/std/ | std");
+        let afk = slots.iter().find(|s| s.ts == t0 + 300).unwrap();
+        assert_eq!((afk.app.as_str(), afk.idle_secs), ("", 180.0));
+        // The slot right where the AFK span starts is already away.
+        let first_afk = slots.iter().find(|s| s.ts == t0 + 120).unwrap();
+        assert_eq!(first_afk.idle_secs, AFK_IDLE_SECS);
+    }
+
+    #[test]
+    fn exports_collapsed_events() {
+        let row = |ts: i64, app: &str, browser: &str, afk: bool| SnapshotRow { ts, app: app.into(), title: "t".into(), browser: browser.into(), afk };
+        let rows = vec![
+            row(0, "Code", "", false), row(30, "Code", "", false), row(60, "Arc", "Arc: https://x.dev/a | A", false),
+            row(90, "Arc", "Arc: https://x.dev/a | A", false), row(600, "Arc", "", true),
+        ];
+        let export = build_export(&rows, "box");
+        let window = &export.buckets["aw-watcher-window_box"].events;
+        assert_eq!(window.len(), 3);
+        assert_eq!((window[0].duration, window[1].duration, window[2].duration), (60.0, 60.0, 30.0));
+        let afk = &export.buckets["aw-watcher-afk_box"].events;
+        assert_eq!(afk.iter().map(|e| e.data["status"].as_str().unwrap()).collect::<Vec<_>>(), ["not-afk", "afk"]);
+        let web = &export.buckets["aw-watcher-web-arc_box"];
+        assert_eq!((web.events.len(), web.events[0].data["url"].as_str()), (1, Some("https://x.dev/a")));
+        assert_eq!(browser_name(&web.id), "Arc");
+        assert_eq!(browser_name("aw-watcher-web-google-chrome_mac"), "Google Chrome");
+    }
+
+    #[test]
+    fn roundtrip_through_resample() {
+        let rows: Vec<SnapshotRow> = (0..10).map(|i| SnapshotRow {
+            ts: 1_700_000_010 + i * 30, app: if i < 5 { "Code" } else { "Slack" }.into(), title: "w".into(), browser: String::new(), afk: false,
+        }).collect();
+        let json = serde_json::to_string(&build_export(&rows, "h")).unwrap();
+        let slots = resample(&parse_export(&json).unwrap());
+        assert_eq!(slots.iter().map(|s| (s.ts, s.app.as_str())).collect::<Vec<_>>(),
+            rows.iter().map(|r| (r.ts, r.app.as_str())).collect::<Vec<_>>());
+    }
+}
@@ -185,6 +185,21 @@ async fn execute_tool_call(app: &AppHandle, name: &str, arguments_raw: &str, con
                 Err(e) => format!("DB error: {}", e),
             }
         }
+        "import_recipe" => {
+            let url = args.get("url").and_then(|v| v.as_str()).map(String::from);
+            let text = args.get("text").and_then(|v| v.as_str()).map(String::from);
+            let force = args.get("force").and_then(|v| v.as_bool()).unwrap_or(false);
+            // The loop holds the LLM permit here, so no LLM fallback — structured data only.
+            match crate::recipe_import::import_recipe_inner(app, url, text, false, force, false).await {
+                Ok(v) => match v["status"].as_str().unwrap_or("") {
+                    "created" => format!("Recipe imported: {} (id {})", v["recipe"]["name"].as_str().unwrap_or(""), v["recipe_id"]),
+                    "duplicate" => format!("Recipe already imported (id {})", v["recipe_id"]),
+                    "blocked" => format!("Recipe blocked by food blacklist: {}", v["blacklist"]),
+                    s => format!("Recipe import: {}", s),
+                },
+                Err(e) => format!("Recipe import failed: {}", e),
+            }
+        }
         "web_search" | "read_url" => {
             format!("Tool {} not available in agent mode. Use playwright browser tools instead.", name)
         }
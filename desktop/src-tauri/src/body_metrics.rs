@@ -0,0 +1,370 @@
+// body_metrics.rs — Body measurements and the weight trend model.
+// Weight stays in `health_log` (type 'weight', one row per day) so the
+// dashboard, imports and the chat keep working; body fat and circumferences
+// live in `body_measurements`. On top of the raw weigh-ins we keep an
+// exponentially smoothed trend weight (day-to-day water swings of ±1 kg are
+// noise), a weekly rate of change from the trend's slope, a projection to the
+// goal weight, and an energy-balance estimate that compares the trend slope
+// with logged `food_log` calories.
+use crate::types::HanniDb;
+use chrono::NaiveDate;
+use serde::Serialize;
+
+/// Measurement kinds and their units. `weight` is routed to `health_log`.
+pub(crate) const KINDS: &[(&str, &str)] = &[
+    ("weight", "kg"), ("body_fat", "%"), ("waist", "cm"), ("hips", "cm"),
+    ("chest", "cm"), ("neck", "cm"), ("arm", "cm"), ("thigh", "cm"),
+];
+/// Smoothing per day of the trend (Hacker's Diet uses 10%).
+const ALPHA: f64 = 0.1;
+/// Window for the rate of change and the energy balance.
+const RATE_DAYS: i64 = 28;
+/// The slope needs weigh-ins spread over at least this many days.
+const MIN_RATE_SPAN: i64 = 7;
+/// kcal stored in one kg of body mass (mixed fat/lean tissue).
+const KCAL_PER_KG: f64 = 7700.0;
+/// Fewer food-logged days than this → no TDEE estimate, logs too sparse.
+const MIN_LOGGED_DAYS: usize = 10;
+/// Look-back for the "change" shown next to each measurement.
+const CHANGE_DAYS: i64 = 30;
+
+fn round1(v: f64) -> f64 { (v * 10.0).round() / 10.0 }
+fn round2(v: f64) -> f64 { (v * 100.0).round() / 100.0 }
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrendPoint {
+    pub date: String,
+    pub weight: f64,
+    pub trend: f64,
+}
+
+/// Exponentially smoothed trend. A gap of `g` days applies `g` steps of decay
+/// at once, so a week without weigh-ins lets the next one move the trend more.
+/// `weights` must be sorted by date.
+pub(crate) fn trend(weights: &[(NaiveDate, f64)]) -> Vec<TrendPoint> {
+    let mut out: Vec<TrendPoint> = Vec::with_capacity(weights.len());
+    let mut prev: Option<(NaiveDate, f64)> = None;
+    for &(date, w) in weights {
+        let t = match prev {
+            None => w,
+            Some((pd, pt)) => {
+                let gap = (date - pd).num_days().max(1) as i32;
+                let a = 1.0 - (1.0 - ALPHA).powi(gap);
+                pt + a * (w - pt)
+            }
+        };
+        prev = Some((date, t));
+        out.push(TrendPoint { date: date.to_string(), weight: w, trend: round2(t) });
+    }
+    out
+}
+
+/// Least-squares slope of the trend over the last RATE_DAYS, in kg per day.
+fn slope_per_day(points: &[TrendPoint]) -> Option<f64> {
+    let last = NaiveDate::parse_from_str(&points.last()?.date, "%Y-%m-%d").ok()?;
+    let xy: Vec<(f64, f64)> = points.iter().filter_map(|p| {
+        let d = NaiveDate::parse_from_str(&p.date, "%Y-%m-%d").ok()?;
+        let x = (d - last).num_days();
+        (x > -RATE_DAYS).then_some((x as f64, p.trend))
+    }).collect();
+    if xy.len() < 3 || -xy[0].0 < MIN_RATE_SPAN as f64 { return None; }
+    let n = xy.len() as f64;
+    let mx = xy.iter().map(|p| p.0).sum::<f64>() / n;
+    let my = xy.iter().map(|p| p.1).sum::<f64>() / n;
+    let sxx: f64 = xy.iter().map(|p| (p.0 - mx).powi(2)).sum();
+    let sxy: f64 = xy.iter().map(|p| (p.0 - mx) * (p.1 - my)).sum();
+    (sxx > 0.0).then(|| sxy / sxx)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Projection {
+    pub goal_weight: f64,
+    pub goal_date: Option<String>,
+    pub remaining: f64,
+    /// When the current rate reaches the goal; `None` if moving the wrong way
+    /// or flat.
+    pub projected_date: Option<String>,
+    /// kg/week needed to hit the goal by `goal_date`.
+    pub required_weekly_rate: Option<f64>,
+    pub on_track: Option<bool>,
+}
+
+pub(crate) fn project(
+    trend_now: f64, per_day: Option<f64>, goal: f64, goal_date: Option<NaiveDate>, today: NaiveDate,
+) -> Projection {
+    let remaining = goal - trend_now;
+    let projected = per_day
+        .filter(|r| r.abs() > 1e-4 && r.signum() == remaining.signum())
+        .map(|r| remaining / r)
+        .filter(|days| *days < 3650.0)
+        .map(|days| today + chrono::Duration::days(days.ceil() as i64));
+    let projected = if remaining.abs() < 0.1 { Some(today) } else { projected };
+    let required = goal_date
+        .map(|d| (d - today).num_days())
+        .filter(|days| *days > 0)
+        .map(|days| round2(remaining / days as f64 * 7.0));
+    let on_track = match (projected, goal_date) {
+        (Some(p), Some(g)) => Some(p <= g),
+        (None, Some(_)) => Some(false),
+        _ => None,
+    };
+    Projection {
+        goal_weight: goal,
+        goal_date: goal_date.map(|d| d.to_string()),
+        remaining: round1(remaining),
+        projected_date: projected.map(|d| d.to_string()),
+        required_weekly_rate: required,
+        on_track,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EnergyBalance {
+    /// Average daily intake on days with logged food.
+    pub avg_intake: f64,
+    pub logged_days: usize,
+    pub window_days: i64,
+    /// kcal/day surplus (+) or deficit (−) implied by the trend slope.
+    pub balance: f64,
+    /// Intake minus balance; only when enough days are logged.
+    pub estimated_tdee: Option<f64>,
+}
+
+/// `daily_calories` — (date, kcal) for days inside the rate window.
+pub(crate) fn energy_balance(per_day: f64, daily_calories: &[(String, f64)]) -> EnergyBalance {
+    let logged: Vec<f64> = daily_calories.iter().map(|(_, c)| *c).filter(|c| *c > 0.0).collect();
+    let avg_intake = if logged.is_empty() { 0.0 } else { logged.iter().sum::<f64>() / logged.len() as f64 };
+    let balance = per_day * KCAL_PER_KG;
+    EnergyBalance {
+        avg_intake: avg_intake.round(),
+        logged_days: logged.len(),
+        window_days: RATE_DAYS,
+        balance: balance.round(),
+        estimated_tdee: (logged.len() >= MIN_LOGGED_DAYS).then(|| (avg_intake - balance).round()),
+    }
+}
+
+// ── DB side ──
+
+fn setting(conn: &rusqlite::Connection, key: &str) -> Option<String> {
+    conn.query_row("SELECT value FROM app_settings WHERE key=?1", [key], |r| r.get::<_, String>(0)).ok()
+}
+
+/// Daily weigh-ins from `health_log`, oldest first.
+pub(crate) fn load_weights(conn: &rusqlite::Connection, since: &str) -> Vec<(NaiveDate, f64)> {
+    let Ok(mut stmt) = conn.prepare(
+        "SELECT date, AVG(value) FROM health_log WHERE type='weight' AND value > 0 AND date >= ?1
+         GROUP BY date ORDER BY date",
+    ) else { return Vec::new() };
+    stmt.query_map([since], |r| Ok((r.get::<_, String>(0)?, r.get::<_, f64>(1)?)))
+        .map(|rows| rows.flatten()
+            .filter_map(|(d, w)| Some((NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok()?, w)))
+            .collect())
+        .unwrap_or_default()
+}
+
+fn daily_calories(conn: &rusqlite::Connection, from: &str, to: &str) -> Vec<(String, f64)> {
+    let Ok(mut stmt) = conn.prepare(
+        "SELECT date, SUM(calories) FROM food_log WHERE date >= ?1 AND date <= ?2 GROUP BY date ORDER BY date",
+    ) else { return Vec::new() };
+    stmt.query_map([from, to], |r| Ok((r.get::<_, String>(0)?, r.get::<_, f64>(1)?)))
+        .map(|rows| rows.flatten().collect())
+        .unwrap_or_default()
+}
+
+/// Latest value per measurement kind plus the change since CHANGE_DAYS ago.
+fn latest_measurements(conn: &rusqlite::Connection, today: NaiveDate) -> Vec<serde_json::Value> {
+    let before = (today - chrono::Duration::days(CHANGE_DAYS)).to_string();
+    KINDS.iter().filter(|(k, _)| *k != "weight").filter_map(|&(kind, unit)| {
+        let (id, date, value): (i64, String, f64) = conn.query_row(
+            "SELECT id, date, value FROM body_measurements WHERE kind=?1 ORDER BY date DESC LIMIT 1",
+            [kind], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+        ).ok()?;
+        let earlier: Option<f64> = conn.query_row(
+            "SELECT value FROM body_measurements WHERE kind=?1 AND date <= ?2 ORDER BY date DESC LIMIT 1",
+            rusqlite::params![kind, before], |r| r.get(0),
+        ).ok();
+        Some(serde_json::json!({
+            "id": id, "kind": kind, "unit": unit, "date": date, "value": value,
+            "change": earlier.map(|e| round1(value - e)),
+        }))
+    }).collect()
+}
+
+// ── Commands ──
+
+#[tauri::command]
+pub fn get_body_metrics(days: Option<i64>, db: tauri::State<'_, HanniDb>) -> Result<serde_json::Value, String> {
+    let conn = db.read();
+    let today = chrono::Local::now().date_naive();
+    let days = days.unwrap_or(90).clamp(7, 3650);
+    let from = today - chrono::Duration::days(days - 1);
+    // Warm the trend up on earlier history so the first visible point isn't raw.
+    let points = trend(&load_weights(&conn, &(from - chrono::Duration::days(RATE_DAYS)).to_string()));
+    let per_day = slope_per_day(&points);
+    let trend_now = points.last().map(|p| p.trend);
+
+    let goal = setting(&conn, "body_goal_weight").and_then(|v| v.parse::<f64>().ok());
+    let goal_date = setting(&conn, "body_goal_date").and_then(|v| NaiveDate::parse_from_str(&v, "%Y-%m-%d").ok());
+    let projection = match (goal, trend_now) {
+        (Some(g), Some(t)) => Some(project(t, per_day, g, goal_date, today)),
+        _ => None,
+    };
+    let energy = per_day.map(|r| {
+        let window_from = (today - chrono::Duration::days(RATE_DAYS - 1)).to_string();
+        energy_balance(r, &daily_calories(&conn, &window_from, &today.to_string()))
+    });
+
+    let latest = latest_measurements(&conn, today);
+    let body_fat = latest.iter().find(|m| m["kind"] == "body_fat").and_then(|m| m["value"].as_f64());
+    let from_s = from.to_string();
+    let mut stmt = conn.prepare(
+        "SELECT id, date, kind, value, unit, note FROM body_measurements WHERE date >= ?1 ORDER BY date, kind",
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let history: Vec<serde_json::Value> = stmt.query_map([&from_s], |r| Ok(serde_json::json!({
+        "id": r.get::<_, i64>(0)?, "date": r.get::<_, String>(1)?, "kind": r.get::<_, String>(2)?,
+        "value": r.get::<_, f64>(3)?, "unit": r.get::<_, String>(4)?, "note": r.get::<_, String>(5)?,
+    }))).map_err(|e| format!("Query error: {}", e))?.flatten().collect();
+
+    Ok(serde_json::json!({
+        "weights": points.into_iter().filter(|p| p.date >= from_s).collect::<Vec<_>>(),
+        "trend_weight": trend_now,
+        "weekly_rate": per_day.map(|r| round2(r * 7.0)),
+        "lean_mass": trend_now.zip(body_fat).map(|(w, bf)| round1(w * (1.0 - bf / 100.0))),
+        "goal": projection,
+        "energy": energy,
+        "measurements": latest,
+        "history": history,
+    }))
+}
+
+/// Log a measurement for `date` (default today). One value per kind and day —
+/// a second entry replaces the first. Weight goes to `health_log`.
+#[tauri::command]
+pub fn log_body_measurement(
+    kind: String, value: f64, date: Option<String>, note: Option<String>, db: tauri::State<'_, HanniDb>,
+) -> Result<(), String> {
+    let Some(&(kind, unit)) = KINDS.iter().find(|(k, _)| *k == kind) else {
+        return Err(format!("Неизвестный замер: {}", kind));
+    };
+    if value <= 0.0 || (kind == "body_fat" && value >= 75.0) {
+        return Err("Некорректное значение".into());
+    }
+    let date = date.unwrap_or_else(|| chrono::Local::now().format("%Y-%m-%d").to_string());
+    NaiveDate::parse_from_str(&date, "%Y-%m-%d").map_err(|_| "Некорректная дата".to_string())?;
+    let note = note.unwrap_or_default();
+    let now = chrono::Local::now().to_rfc3339();
+    let conn = db.conn();
+    if kind == "weight" {
+        let updated = conn.execute(
+            "UPDATE health_log SET value=?1, notes=?2 WHERE date=?3 AND type='weight'",
+            rusqlite::params![value, note, date],
+        ).map_err(|e| format!("DB error: {}", e))?;
+        if updated == 0 {
+            conn.execute(
+                "INSERT INTO health_log (id, date, type, value, unit, notes, created_at) VALUES (?1,?2,'weight',?3,'kg',?4,?5)",
+                rusqlite::params![crate::types::new_uuid_v7(), date, value, note, now],
+            ).map_err(|e| format!("DB error: {}", e))?;
+        }
+        return Ok(());
+    }
+    conn.execute(
+        "INSERT INTO body_measurements (date, kind, value, unit, note, created_at) VALUES (?1,?2,?3,?4,?5,?6)
+         ON CONFLICT(date, kind) DO UPDATE SET value=excluded.value, note=excluded.note",
+        rusqlite::params![date, kind, value, unit, note, now],
+    ).map_err(|e| format!("DB error: {}", e))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_body_measurement(id: i64, db: tauri::State<'_, HanniDb>) -> Result<(), String> {
+    db.conn().execute("DELETE FROM body_measurements WHERE id=?1", [id]).map_err(|e| format!("DB error: {}", e))?;
+    Ok(())
+}
+
+/// Save the goal weight and optional target date. `None` clears the setting.
+#[tauri::command]
+pub fn set_body_goal(weight: Option<f64>, date: Option<String>, db: tauri::State<'_, HanniDb>) -> Result<(), String> {
+    if weight.is_some_and(|w| !(20.0..=400.0).contains(&w)) {
+        return Err("Целевой вес должен быть от 20 до 400 кг".into());
+    }
+    if let Some(d) = date.as_deref() {
+        NaiveDate::parse_from_str(d, "%Y-%m-%d").map_err(|_| "Некорректная дата".to_string())?;
+    }
+    let conn = db.conn();
+    for (key, value) in [("body_goal_weight", weight.map(|w| w.to_string())), ("body_goal_date", date)] {
+        match value {
+            Some(v) => conn.execute(
+                "INSERT INTO app_settings (key, value) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET value=?2",
+                rusqlite::params![key, v],
+            ),
+            None => conn.execute("DELETE FROM app_settings WHERE key=?1", [key]),
+        }.map_err(|e| format!("DB error: {}", e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::d;
+
+    #[test]
+    fn trend_smooths_and_decays_over_gaps() {
+        let t = trend(&[(d("2024-05-01"), 80.0), (d("2024-05-02"), 82.0), (d("2024-05-12"), 78.0)]);
+        assert_eq!(t[0].trend, 80.0);
+        assert_eq!(t[1].trend, 80.2);
+        // 10-day gap: 1 − 0.9^10 ≈ 0.65 of the way to 78.
+        assert!((t[2].trend - 78.77).abs() < 0.01, "{}", t[2].trend);
+    }
+
+    #[test]
+    fn rate_projection_and_balance() {
+        // Steady loss of 0.1 kg/day for six weeks.
+        let w: Vec<(NaiveDate, f64)> = (0..42).map(|i| (d("2024-05-01") + chrono::Duration::days(i), 90.0 - 0.1 * i as f64)).collect();
+        let points = trend(&w);
+        let r = slope_per_day(&points).unwrap();
+        assert!((r + 0.1).abs() < 0.01, "{}", r);
+        let today = d("2024-06-11");
+        let trend_now = points.last().unwrap().trend;
+        let p = project(trend_now, Some(r), trend_now - 5.0, Some(d("2024-09-01")), today);
+        // ~50 days; the trend's slope still lags the raw rate a little.
+        assert_eq!(p.projected_date.as_deref(), Some("2024-08-04"));
+        assert_eq!(p.on_track, Some(true));
+        assert_eq!(project(trend_now, Some(r), trend_now - 5.0, Some(d("2024-07-01")), today).on_track, Some(false));
+        // Gaining goal while losing → no projection.
+        assert_eq!(project(trend_now, Some(r), trend_now + 2.0, None, today).projected_date, None);
+
+        let cals: Vec<(String, f64)> = (0..12).map(|i| (format!("2024-06-{:02}", i + 1), 2000.0)).collect();
+        let e = energy_balance(r, &cals);
+        assert!((e.balance + 770.0).abs() < 80.0, "{}", e.balance);
+        assert!(e.estimated_tdee.unwrap() > 2700.0);
+        assert_eq!(energy_balance(r, &cals[..3]).estimated_tdee, None);
+    }
+
+    #[test]
+    fn no_rate_from_a_few_days() {
+        let w = [(d("2024-05-01"), 80.0), (d("2024-05-02"), 79.0), (d("2024-05-03"), 78.0)];
+        assert_eq!(slope_per_day(&trend(&w)), None);
+    }
+
+    #[test]
+    fn empty_and_single_point_series() {
+        assert!(trend(&[]).is_empty());
+        assert_eq!(slope_per_day(&[]), None);
+        let one = trend(&[(d("2024-05-01"), 80.4)]);
+        assert_eq!(one[0].trend, 80.4);
+        assert_eq!(slope_per_day(&one), None);
+
+        let today = d("2024-05-01");
+        // No rate yet: no projection, and no verdict without a goal date.
+        let p = project(80.4, None, 75.0, None, today);
+        assert_eq!((p.projected_date, p.on_track, p.remaining), (None, None, -5.4));
+        // Already at the goal; a goal date in the past has no required rate.
+        let p = project(75.05, None, 75.0, Some(d("2024-04-01")), today);
+        assert_eq!((p.projected_date.as_deref(), p.required_weekly_rate), (Some("2024-05-01"), None));
+
+        let e = energy_balance(0.0, &[]);
+        assert_eq!((e.avg_intake, e.logged_days, e.estimated_tdee), (0.0, 0, None));
+    }
+}
@@ -6,27 +6,53 @@ use chrono::Timelike;
 // ── v0.7.0: Events (Calendar) commands ──
 
 #[tauri::command]
-pub fn create_event(title: String, description: String, date: String, time: String, duration_minutes: i64, category: String, color: String, priority: Option<i32>, linked_tab: Option<String>, db: tauri::State<'_, HanniDb>) -> Result<i64, String> {
+#[allow(clippy::too_many_arguments)]
+pub fn create_event(title: String, description: String, date: String, time: String, duration_minutes: i64, category: String, color: String, priority: Option<i32>, linked_tab: Option<String>, tz: Option<String>, db: tauri::State<'_, HanniDb>) -> Result<i64, String> {
     let conn = db.conn();
     let now = chrono::Local::now().to_rfc3339();
     let pr = priority.unwrap_or(0);
     let tab = linked_tab.unwrap_or_default();
+    // Timed events are entered in the zone the user is in now, unless told otherwise
+    let tz = match tz {
+        Some(z) => crate::time_zones::validate_zone(&z)?,
+        None if time.is_empty() => String::new(),
+        None => crate::time_zones::zone_name(crate::time_zones::current_zone(&conn)),
+    };
     conn.execute(
-        "INSERT INTO events (title, description, date, time, duration_minutes, category, color, priority, linked_tab, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
-        rusqlite::params![title, description, date, time, duration_minutes, category, color, pr, tab, now],
+        "INSERT INTO events (title, description, date, time, duration_minutes, category, color, priority, linked_tab, tz, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        rusqlite::params![title, description, date, time, duration_minutes, category, color, pr, tab, tz, now],
     ).map_err(|e| format!("DB error: {}", e))?;
     Ok(conn.last_insert_rowid())
 }
 
+/// Adds the event's date/time as seen in the current zone; the stored ones
+/// go to `orig_date` / `orig_time`, `shifted` tells whether they differ.
+fn localize_event(mut ev: serde_json::Value, current: Option<chrono_tz::Tz>) -> serde_json::Value {
+    let (date, time, tz) = (ev["date"].as_str().unwrap_or("").to_string(), ev["time"].as_str().unwrap_or("").to_string(), ev["tz"].as_str().unwrap_or("").to_string());
+    let (local_date, local_time) = crate::time_zones::localize(&date, &time, &tz, current);
+    ev["shifted"] = serde_json::json!(local_date != date || local_time != time);
+    ev["date"] = serde_json::json!(local_date);
+    ev["time"] = serde_json::json!(local_time);
+    ev["orig_date"] = serde_json::json!(date);
+    ev["orig_time"] = serde_json::json!(time);
+    ev
+}
+
 #[tauri::command]
 pub fn get_events(month: u32, year: i32, db: tauri::State<'_, HanniDb>) -> Result<Vec<serde_json::Value>, String> {
     let conn = db.read();
     let prefix = format!("{}-{:02}", year, month);
+    let first = chrono::NaiveDate::from_ymd_opt(year, month, 1).ok_or("Некорректный месяц")?;
+    let next = if month == 12 { chrono::NaiveDate::from_ymd_opt(year + 1, 1, 1) } else { chrono::NaiveDate::from_ymd_opt(year, month + 1, 1) }
+        .ok_or("Некорректный месяц")?;
+    // A zone shift moves events by at most a day, so look one day past each edge
+    let from = (first - chrono::Duration::days(1)).format("%Y-%m-%d").to_string();
+    let to = next.format("%Y-%m-%d").to_string();
+    let current = crate::time_zones::current_zone(&conn);
     let mut stmt = conn.prepare(
-        "SELECT id, title, description, date, time, duration_minutes, category, color, completed, COALESCE(source,'manual'), COALESCE(priority,0), COALESCE(linked_tab,'') FROM events WHERE date LIKE ?1 ORDER BY date, time"
+        "SELECT id, title, description, date, time, duration_minutes, category, color, completed, COALESCE(source,'manual'), COALESCE(priority,0), COALESCE(linked_tab,''), COALESCE(tz,'') FROM events WHERE date >= ?1 AND date <= ?2 ORDER BY date, time"
     ).map_err(|e| format!("DB error: {}", e))?;
-    let pattern = format!("{}%", prefix);
-    let rows = stmt.query_map(rusqlite::params![pattern], |row| {
+    let mut rows: Vec<serde_json::Value> = stmt.query_map(rusqlite::params![from, to], |row| {
         Ok(serde_json::json!({
             "id": row.get::<_, i64>(0)?,
             "title": row.get::<_, String>(1)?,
@@ -40,8 +66,13 @@ pub fn get_events(month: u32, year: i32, db: tauri::State<'_, HanniDb>) -> Resul
             "source": row.get::<_, String>(9)?,
             "priority": row.get::<_, i32>(10)?,
             "linked_tab": row.get::<_, String>(11)?,
+            "tz": row.get::<_, String>(12)?,
         }))
-    }).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+    }).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok())
+        .map(|ev| localize_event(ev, current))
+        .filter(|ev| ev["date"].as_str().is_some_and(|d| d.starts_with(&prefix)))
+        .collect();
+    rows.sort_by(|a, b| (a["date"].as_str(), a["time"].as_str()).cmp(&(b["date"].as_str(), b["time"].as_str())));
     Ok(rows)
 }
 
@@ -53,8 +84,28 @@ pub fn delete_event(id: i64, db: tauri::State<'_, HanniDb>) -> Result<(), String
 }
 
 #[tauri::command]
-pub fn update_event(id: i64, title: Option<String>, description: Option<String>, date: Option<String>, time: Option<String>, duration_minutes: Option<i64>, category: Option<String>, color: Option<String>, completed: Option<bool>, priority: Option<i32>, linked_tab: Option<String>, db: tauri::State<'_, HanniDb>) -> Result<(), String> {
+#[allow(clippy::too_many_arguments)]
+pub fn update_event(id: i64, title: Option<String>, description: Option<String>, date: Option<String>, time: Option<String>, duration_minutes: Option<i64>, category: Option<String>, color: Option<String>, completed: Option<bool>, priority: Option<i32>, linked_tab: Option<String>, tz: Option<String>, db: tauri::State<'_, HanniDb>) -> Result<(), String> {
     let conn = db.conn();
+    // Date/time come from the UI in the current zone (or in `tz` when given), so
+    // a rescheduled event is re-anchored there. A half-given pair is completed
+    // from the stored event as seen in that zone.
+    let (date, time, tz) = if date.is_some() || time.is_some() {
+        let tz = match tz {
+            Some(z) => crate::time_zones::validate_zone(&z)?,
+            None => crate::time_zones::zone_name(crate::time_zones::current_zone(&conn)),
+        };
+        let (old_date, old_time, old_tz): (String, String, String) = conn.query_row(
+            "SELECT date, time, COALESCE(tz,'') FROM events WHERE id=?1", [id],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+        ).map_err(|e| format!("DB error: {}", e))?;
+        let (seen_date, seen_time) = crate::time_zones::localize(&old_date, &old_time, &old_tz, crate::time_zones::parse_zone(&tz));
+        let (date, time) = (date.unwrap_or(seen_date), time.unwrap_or(seen_time));
+        let tz = if time.is_empty() { String::new() } else { tz };
+        (Some(date), Some(time), Some(tz))
+    } else {
+        (None, None, tz.map(|z| crate::time_zones::validate_zone(&z)).transpose()?)
+    };
     let mut updates = Vec::new();
     let mut params: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
     let mut idx = 1;
@@ -68,6 +119,7 @@ pub fn update_event(id: i64, title: Option<String>, description: Option<String>,
     if let Some(v) = completed { updates.push(format!("completed=?{}", idx)); params.push(Box::new(v as i64)); idx += 1; }
     if let Some(v) = priority { updates.push(format!("priority=?{}", idx)); params.push(Box::new(v)); idx += 1; }
     if let Some(v) = linked_tab { updates.push(format!("linked_tab=?{}", idx)); params.push(Box::new(v)); idx += 1; }
+    if let Some(v) = tz { updates.push(format!("tz=?{}", idx)); params.push(Box::new(v)); idx += 1; }
     if updates.is_empty() { return Ok(()); }
     params.push(Box::new(id));
     let sql = format!("UPDATE events SET {} WHERE id=?{}", updates.join(","), idx);
@@ -80,9 +132,10 @@ pub fn update_event(id: i64, title: Option<String>, description: Option<String>,
 pub fn get_all_events(db: tauri::State<'_, HanniDb>) -> Result<Vec<serde_json::Value>, String> {
     let conn = db.conn();
     let mut stmt = conn.prepare(
-        "SELECT id, title, description, date, time, duration_minutes, category, color, completed, source, COALESCE(priority,0), COALESCE(linked_tab,'')
+        "SELECT id, title, description, date, time, duration_minutes, category, color, completed, source, COALESCE(priority,0), COALESCE(linked_tab,''), COALESCE(tz,'')
          FROM events ORDER BY date DESC, time DESC"
     ).map_err(|e| format!("DB error: {}", e))?;
+    let current = crate::time_zones::current_zone(&conn);
     let rows = stmt.query_map([], |row| {
         Ok(serde_json::json!({
             "id": row.get::<_, i64>(0)?,
@@ -97,8 +150,10 @@ pub fn get_all_events(db: tauri::State<'_, HanniDb>) -> Result<Vec<serde_json::V
             "source": row.get::<_, Option<String>>(9)?,
             "priority": row.get::<_, i32>(10)?,
             "linked_tab": row.get::<_, String>(11)?,
+            "tz": row.get::<_, String>(12)?,
         }))
-    }).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+    }).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok())
+        .map(|ev| localize_event(ev, current)).collect();
     Ok(rows)
 }
 
@@ -190,6 +245,8 @@ pub async fn sync_apple_calendar(month: u32, year: i32, db: tauri::State<'_, Han
 
     let conn = db.conn();
     let now = chrono::Local::now().to_rfc3339();
+    // Calendar.app reports start dates on the system clock
+    let system_tz = crate::time_zones::zone_name(crate::time_zones::system_zone());
 
     // Clear old apple events for this month
     conn.execute(
@@ -210,9 +267,9 @@ pub async fn sync_apple_calendar(month: u32, year: i32, db: tauri::State<'_, Han
         let desc = parts.get(6).unwrap_or(&"").trim();
 
         conn.execute(
-            "INSERT INTO events (title, description, date, time, duration_minutes, category, color, source, external_id, created_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 'apple', ?8, ?9)",
-            rusqlite::params![title, desc, date, time, dur, cal_name, "#a1a1a6", uid, now],
+            "INSERT INTO events (title, description, date, time, duration_minutes, category, color, source, external_id, tz, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 'apple', ?8, ?9, ?10)",
+            rusqlite::params![title, desc, date, time, dur, cal_name, "#a1a1a6", uid, if time.is_empty() { "" } else { system_tz.as_str() }, now],
         ).map_err(|e| format!("Insert error: {}", e))?;
         count += 1;
     }
@@ -220,16 +277,18 @@ pub async fn sync_apple_calendar(month: u32, year: i32, db: tauri::State<'_, Han
     Ok(serde_json::json!({ "synced": count, "source": "apple", "error": null }))
 }
 
-/// Parse ICS datetime line, handling TZID and UTC 'Z' suffix. Returns (NaiveDate, Option<NaiveTime>, is_allday).
-pub fn parse_ics_datetime(line: &str) -> Option<(chrono::NaiveDate, Option<chrono::NaiveTime>, bool)> {
-    use chrono::{NaiveDate, NaiveTime, NaiveDateTime, TimeZone};
+/// Parse ICS datetime line, handling TZID and UTC 'Z' suffix. Returns (NaiveDate, Option<NaiveTime>, is_allday, zone):
+/// the wall clock as written, in its TZID zone (UTC for 'Z'); zone is None for
+/// all-day, floating or unknown-TZID values, which are kept as is.
+pub fn parse_ics_datetime(line: &str) -> Option<(chrono::NaiveDate, Option<chrono::NaiveTime>, bool, Option<chrono_tz::Tz>)> {
+    use chrono::{NaiveDate, NaiveTime, NaiveDateTime};
 
     // All-day: DTSTART;VALUE=DATE:20250215
     if line.contains("VALUE=DATE") {
         let re_d = regex::Regex::new(r"(\d{4})(\d{2})(\d{2})").unwrap();
         let caps = re_d.captures(line)?;
         let d = NaiveDate::from_ymd_opt(caps[1].parse().ok()?, caps[2].parse().ok()?, caps[3].parse().ok()?)?;
-        return Some((d, None, true));
+        return Some((d, None, true, None));
     }
 
     let re_dt = regex::Regex::new(r"(\d{4})(\d{2})(\d{2})T(\d{2})(\d{2})(\d{2})?").unwrap();
@@ -248,28 +307,15 @@ pub fn parse_ics_datetime(line: &str) -> Option<(chrono::NaiveDate, Option<chron
 
     // Extract TZID if present: DTSTART;TZID=America/New_York:20250215T093000
     let re_tzid = regex::Regex::new(r"TZID=([^:;]+)").unwrap();
-    let local_dt = if let Some(tz_caps) = re_tzid.captures(line) {
-        let tz_str = tz_caps[1].trim();
-        if let Ok(tz) = tz_str.parse::<chrono_tz::Tz>() {
-            // Parse in source timezone, convert to local
-            match tz.from_local_datetime(&naive_dt).earliest() {
-                Some(zoned) => zoned.with_timezone(&chrono::Local).naive_local(),
-                None => naive_dt,
-            }
-        } else {
-            naive_dt // Unknown timezone, use as-is
-        }
-    } else if line.contains('Z') {
-        // UTC: convert to local
-        match chrono::Utc.from_utc_datetime(&naive_dt).with_timezone(&chrono::Local).naive_local() {
-            dt => dt,
-        }
+    let zone = if let Some(tz_caps) = re_tzid.captures(line) {
+        crate::time_zones::parse_zone(tz_caps[1].trim().trim_matches('"')) // Unknown timezone → floating
+    } else if line.trim_end().ends_with('Z') {
+        Some(chrono_tz::UTC)
     } else {
-        // No timezone info — treat as local (floating time)
-        naive_dt
+        None // No timezone info — floating time
     };
 
-    Some((local_dt.date(), Some(local_dt.time()), false))
+    Some((naive_dt.date(), Some(naive_dt.time()), false, zone))
 }
 
 /// Parse RRULE line into components
@@ -508,17 +554,25 @@ pub async fn sync_google_ics(url: String, month: u32, year: i32, db: tauri::Stat
         let desc = get_field("DESCRIPTION:").replace("\\n", "\n").replace("\\,", ",");
 
         // Parse start datetime with timezone handling
-        let (start_date, start_time, is_allday) = match parse_ics_datetime(dtstart_line) {
+        let (start_date, start_time, is_allday, start_tz) = match parse_ics_datetime(dtstart_line) {
             Some(v) => v,
             None => continue,
         };
         let time_str = start_time.map(|t| t.format("%H:%M").to_string()).unwrap_or_default();
+        let tz_name = crate::time_zones::zone_name(start_tz);
 
-        // Calculate duration
+        // Calculate duration (by instants when both ends are zoned — DTEND may use another TZID)
         let dur: i64 = if is_allday {
             0
-        } else if let Some((end_date, end_time, _)) = parse_ics_datetime(dtend_line) {
-            if let (Some(st), Some(et)) = (start_time, end_time) {
+        } else if let Some((end_date, end_time, _, end_tz)) = parse_ics_datetime(dtend_line) {
+            let instants = match (start_time, end_time, start_tz, end_tz) {
+                (Some(st), Some(et), Some(sz), Some(ez)) => crate::time_zones::instant(start_date.and_time(st), sz)
+                    .zip(crate::time_zones::instant(end_date.and_time(et), ez)),
+                _ => None,
+            };
+            if let Some((start_at, end_at)) = instants {
+                (end_at - start_at).num_minutes().max(1)
+            } else if let (Some(st), Some(et)) = (start_time, end_time) {
                 let start_mins = st.hour() as i64 * 60 + st.minute() as i64;
                 let end_mins = et.hour() as i64 * 60 + et.minute() as i64;
                 let day_diff = (end_date - start_date).num_days() * 24 * 60;
@@ -558,9 +612,9 @@ pub async fn sync_google_ics(url: String, month: u32, year: i32, db: tauri::Stat
             };
 
             conn.execute(
-                "INSERT INTO events (title, description, date, time, duration_minutes, category, color, source, external_id, created_at)
-                 VALUES (?1, ?2, ?3, ?4, ?5, 'google', ?6, 'google', ?7, ?8)",
-                rusqlite::params![summary, desc, occ_date_str, time_str, dur, "#a1a1a6", ext_id, now],
+                "INSERT INTO events (title, description, date, time, duration_minutes, category, color, source, external_id, tz, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, 'google', ?6, 'google', ?7, ?8, ?9)",
+                rusqlite::params![summary, desc, occ_date_str, time_str, dur, "#a1a1a6", ext_id, tz_name, now],
             ).map_err(|e| format!("Insert error: {}", e))?;
             count += 1;
         }
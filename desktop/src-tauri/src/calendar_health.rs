@@ -66,14 +66,43 @@ fn upsert_event(
         rusqlite::params![desc, dur_min, color, external_id, now, date, title, time],
     ).unwrap_or(0);
     if updated > 0 { return true; }
+    insert_event(conn, "auto_health", external_id, title, desc, date, time, dur_min, color, now)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn insert_event(
+    conn: &rusqlite::Connection, source: &str,
+    external_id: &str, title: &str, desc: &str,
+    date: &str, time: &str, dur_min: i64, color: &str, now: &str,
+) -> bool {
     conn.execute(
         "INSERT INTO events (title, description, date, time, duration_minutes,
             category, color, source, external_id, created_at, updated_at)
-         VALUES (?1,?2,?3,?4,?5,'health',?6,'auto_health',?7,?8,?8)",
-        rusqlite::params![title, desc, date, time, dur_min, color, external_id, now],
+         VALUES (?1,?2,?3,?4,?5,'health',?6,?7,?8,?9,?9)",
+        rusqlite::params![title, desc, date, time, dur_min, color, source, external_id, now],
     ).is_ok()
 }
 
+/// Upsert for generated events whose identity is stable but whose content
+/// moves (a planned workout rescheduled to another date/time): matched by
+/// (source, external_id) instead of content, otherwise same insert as above.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn upsert_event_by_external_id(
+    conn: &rusqlite::Connection, source: &str,
+    external_id: &str, title: &str, desc: &str,
+    date: &str, time: &str, dur_min: i64, color: &str, now: &str,
+) -> bool {
+    let updated = conn.execute(
+        "UPDATE events
+            SET title=?1, description=?2, date=?3, time=?4, duration_minutes=?5,
+                color=?6, updated_at=?7
+          WHERE source=?8 AND external_id=?9",
+        rusqlite::params![title, desc, date, time, dur_min, color, now, source, external_id],
+    ).unwrap_or(0);
+    if updated > 0 { return true; }
+    insert_event(conn, source, external_id, title, desc, date, time, dur_min, color, now)
+}
+
 /// Sync sleep + exercise for a given date into the events table so they
 /// appear in Calendar Day-view. Upserts by content-derived external_id so
 /// re-syncs don't create duplicate rows; auto_health rows for the date
@@ -178,6 +207,7 @@ pub fn sync_health_to_calendar(date: String, db: tauri::State<'_, HanniDb>) -> R
     // Auto-complete matching schedules (e.g. "Прогулка 30 мин") when the
     // total duration of that activity type on the date crosses 15 min.
     let _ = auto_complete_from_health(&conn, &date, &now);
+    crate::habits::auto_check(&conn, &date);
 
     Ok(count)
 }
@@ -4,8 +4,7 @@ use crate::types::*;
 use crate::prompts::*;
 use crate::mcp::McpState;
 use crate::memory::{build_memory_context_from_db, embed_texts, rerank_facts, search_similar_facts, gather_memory_candidates};
-use crate::proactive::{get_frontmost_app, get_browser_url, get_now_playing_sync};
-use crate::macos::get_macos_idle_seconds;
+use crate::activity_sensor::sensor;
 use tauri::{AppHandle, Emitter, Manager};
 use std::collections::HashMap;
 
@@ -133,29 +132,29 @@ pub async fn chat_openclaw(app: &AppHandle, messages: Vec<serde_json::Value>, _c
         return Err("OpenClaw token not set. Add it to app_settings key 'openclaw_token'.".into());
     }
 
-    // Gather lightweight macOS context (fast: ~1-2s via osascript)
+    // Gather lightweight OS context (fast: ~1-2s via osascript / desktop IPC)
     // This gives OpenClaw awareness of what the user is doing
     let context = tokio::task::spawn_blocking(|| {
         let mut parts: Vec<String> = Vec::new();
         let now = chrono::Local::now();
         parts.push(format!("Сейчас: {}", now.format("%H:%M, %A %d.%m.%Y")));
 
-        let idle = get_macos_idle_seconds();
+        let idle = sensor().idle_seconds();
         if idle > 300.0 {
             parts.push(format!("Пользователь неактивен {:.0} мин", idle / 60.0));
         }
 
-        let app = get_frontmost_app();
+        let app = sensor().frontmost_app();
         if !app.is_empty() && app != "Hanni" {
             parts.push(format!("Активное приложение: {}", app));
         }
 
-        let browser = get_browser_url();
+        let browser = sensor().browser_url();
         if !browser.is_empty() {
             parts.push(format!("Браузер: {}", browser));
         }
 
-        let music = get_now_playing_sync();
+        let music = sensor().now_playing();
         if !music.is_empty() {
             parts.push(format!("Музыка: {}", music));
         }
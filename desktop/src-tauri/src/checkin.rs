@@ -0,0 +1,433 @@
+// checkin.rs — Daily check-in journal: mood / energy / stress on a 1–5 scale,
+// symptoms with severity, optional cycle (period flow) tracking and free text.
+// The correlation report lines each day's check-in up against data the app
+// already has — last night's sleep, workouts, food, screen time, steps and
+// time spent per timeline activity type — and ranks what moves mood the most.
+// Mood is mirrored into `health_log` (type 'mood') so older views keep working.
+use crate::types::HanniDb;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+pub(crate) const OUTCOMES: &[(&str, &str)] = &[("mood", "Настроение"), ("energy", "Энергия"), ("stress", "Стресс")];
+/// Pairs needed before a correlation is reported.
+const MIN_DAYS: usize = 10;
+/// |r| below this is noise at our sample sizes.
+const MIN_R: f64 = 0.2;
+/// Cycle lengths outside this range are treated as gaps in tracking.
+const CYCLE_RANGE: std::ops::RangeInclusive<i64> = 18..=45;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Symptom {
+    pub name: String,
+    /// 1 = mild, 2 = moderate, 3 = severe.
+    pub severity: i64,
+}
+
+/// Pearson correlation; `None` when either side is constant.
+pub(crate) fn pearson(pairs: &[(f64, f64)]) -> Option<f64> {
+    let n = pairs.len() as f64;
+    if pairs.len() < 3 { return None; }
+    let mx = pairs.iter().map(|p| p.0).sum::<f64>() / n;
+    let my = pairs.iter().map(|p| p.1).sum::<f64>() / n;
+    let sxy: f64 = pairs.iter().map(|p| (p.0 - mx) * (p.1 - my)).sum();
+    let sxx: f64 = pairs.iter().map(|p| (p.0 - mx).powi(2)).sum();
+    let syy: f64 = pairs.iter().map(|p| (p.1 - my).powi(2)).sum();
+    (sxx > 0.0 && syy > 0.0).then(|| sxy / (sxx * syy).sqrt())
+}
+
+/// One day: check-in values and everything we know about the day.
+#[derive(Debug, Clone, Default)]
+pub struct DayData {
+    pub outcomes: HashMap<&'static str, f64>,
+    pub factors: BTreeMap<String, f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Finding {
+    pub outcome: &'static str,
+    pub factor: String,
+    pub label: String,
+    pub r: f64,
+    pub days: usize,
+    /// Average outcome on days with the factor above / at-or-below its median.
+    pub avg_high: f64,
+    pub avg_low: f64,
+}
+
+/// Correlate every outcome with every factor; strongest first.
+pub(crate) fn correlate(days: &[DayData], labels: &HashMap<String, String>) -> Vec<Finding> {
+    let factor_keys: std::collections::BTreeSet<&String> = days.iter().flat_map(|d| d.factors.keys()).collect();
+    let mut out = Vec::new();
+    for &(outcome, _) in OUTCOMES {
+        for key in &factor_keys {
+            let pairs: Vec<(f64, f64)> = days.iter()
+                .filter_map(|d| Some((*d.factors.get(*key)?, *d.outcomes.get(outcome)?)))
+                .collect();
+            if pairs.len() < MIN_DAYS { continue; }
+            let Some(r) = pearson(&pairs) else { continue };
+            if r.abs() < MIN_R { continue; }
+            let mut xs: Vec<f64> = pairs.iter().map(|p| p.0).collect();
+            xs.sort_by(|a, b| a.total_cmp(b));
+            let median = xs[xs.len() / 2];
+            let avg = |v: Vec<f64>| if v.is_empty() { 0.0 } else { (v.iter().sum::<f64>() / v.len() as f64 * 10.0).round() / 10.0 };
+            // For 0/1 factors a median split can leave one side empty; split at the value itself.
+            let (high, low): (Vec<&(f64, f64)>, Vec<_>) = if median == xs[0] {
+                pairs.iter().partition(|p| p.0 > median)
+            } else {
+                pairs.iter().partition(|p| p.0 >= median)
+            };
+            if high.is_empty() || low.is_empty() { continue; }
+            out.push(Finding {
+                outcome,
+                factor: (*key).clone(),
+                label: labels.get(*key).cloned().unwrap_or_else(|| (*key).clone()),
+                r: (r * 100.0).round() / 100.0,
+                days: pairs.len(),
+                avg_high: avg(high.iter().map(|p| p.1).collect()),
+                avg_low: avg(low.iter().map(|p| p.1).collect()),
+            });
+        }
+    }
+    out.sort_by(|a, b| b.r.abs().total_cmp(&a.r.abs()));
+    out
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CycleSummary {
+    pub last_start: String,
+    pub cycle_day: i64,
+    pub avg_length: Option<f64>,
+    pub predicted_next: Option<String>,
+}
+
+/// Period starts are flow days whose previous day had no flow. `flow_days`
+/// must be sorted.
+pub(crate) fn cycle_summary(flow_days: &[NaiveDate], today: NaiveDate) -> Option<CycleSummary> {
+    let starts: Vec<NaiveDate> = flow_days.iter().enumerate()
+        .filter(|(i, d)| *i == 0 || (**d - flow_days[i - 1]).num_days() > 1)
+        .map(|(_, d)| *d)
+        .collect();
+    let last = *starts.last()?;
+    let lengths: Vec<i64> = starts.windows(2).map(|w| (w[1] - w[0]).num_days()).filter(|l| CYCLE_RANGE.contains(l)).collect();
+    let avg = (!lengths.is_empty()).then(|| lengths.iter().sum::<i64>() as f64 / lengths.len() as f64);
+    Some(CycleSummary {
+        last_start: last.to_string(),
+        cycle_day: (today - last).num_days() + 1,
+        avg_length: avg.map(|a| (a * 10.0).round() / 10.0),
+        predicted_next: avg.map(|a| (last + chrono::Duration::days(a.round() as i64)).to_string()),
+    })
+}
+
+// ── DB side ──
+
+fn load_symptoms(conn: &rusqlite::Connection, since: &str) -> HashMap<String, Vec<Symptom>> {
+    let mut out: HashMap<String, Vec<Symptom>> = HashMap::new();
+    if let Ok(mut stmt) = conn.prepare("SELECT date, name, severity FROM checkin_symptoms WHERE date >= ?1 ORDER BY severity DESC, name") {
+        let rows = stmt.query_map([since], |r| Ok((r.get::<_, String>(0)?, Symptom { name: r.get(1)?, severity: r.get(2)? })));
+        for (date, s) in rows.into_iter().flatten().flatten() {
+            out.entry(date).or_default().push(s);
+        }
+    }
+    out
+}
+
+fn sum_by_date(conn: &rusqlite::Connection, sql: &str, since: &str) -> HashMap<String, f64> {
+    conn.prepare(sql)
+        .and_then(|mut s| s.query_map([since], |r| Ok((r.get::<_, String>(0)?, r.get::<_, f64>(1)?)))?.collect())
+        .unwrap_or_default()
+}
+
+/// (date, mood, energy, stress, cycle_flow)
+type CheckinRow = (String, Option<f64>, Option<f64>, Option<f64>, Option<i64>);
+
+/// Everything known about each checked-in day since `since`, plus labels for
+/// the dynamic factor keys (timeline types, symptoms).
+fn collect_days(conn: &rusqlite::Connection, since: &str) -> (Vec<DayData>, HashMap<String, String>) {
+    let mut labels: HashMap<String, String> = [
+        ("sleep_hours", "Сон прошлой ночью, ч"), ("sleep_score", "Оценка сна"), ("workout_minutes", "Тренировки, мин"),
+        ("calories", "Калории"), ("screen_minutes", "Экранное время, мин"), ("steps", "Шаги"), ("period", "Дни цикла (менструация)"),
+    ].into_iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+
+    // Last night's sleep belongs to the day it ended on (the night before a day
+    // usually starts on the previous date, an after-midnight one on the same).
+    let prev_since = NaiveDate::parse_from_str(since, "%Y-%m-%d")
+        .map(|d| (d - chrono::Duration::days(1)).to_string()).unwrap_or_else(|_| since.to_string());
+    let mut sleep: HashMap<String, (f64, Option<f64>)> = HashMap::new();
+    if let Ok(mut stmt) = conn.prepare(
+        "SELECT date, start_time, duration_minutes, quality_score FROM sleep_sessions WHERE date >= ?1",
+    ) {
+        for (date, start, minutes, score) in stmt.query_map([&prev_since], |r| Ok((
+            r.get::<_, String>(0)?, r.get::<_, String>(1)?, r.get::<_, i64>(2)?, r.get::<_, Option<f64>>(3)?,
+        ))).into_iter().flatten().flatten() {
+            let Some((_, end)) = crate::heart_rate::sleep_window(&date, &start, minutes) else { continue };
+            let hours = minutes as f64 / 60.0;
+            let entry = sleep.entry(end.date().to_string()).or_insert((hours, score));
+            if hours > entry.0 { *entry = (hours, score); }
+        }
+    }
+    let workouts = sum_by_date(conn, "SELECT date, SUM(duration_minutes) FROM workouts WHERE date >= ?1 GROUP BY date", since);
+    let calories = sum_by_date(conn, "SELECT date, SUM(calories) FROM food_log WHERE date >= ?1 GROUP BY date", since);
+    let steps = sum_by_date(conn, "SELECT date, MAX(value) FROM health_log WHERE type='steps' AND date >= ?1 GROUP BY date", since);
+    let screen = sum_by_date(conn,
+        "SELECT substr(captured_at,1,10), SUM(CASE WHEN COALESCE(category,'') != 'afk' AND COALESCE(screen_locked,0) = 0 THEN 0.5 ELSE 0 END)
+         FROM activity_snapshots WHERE captured_at >= ?1 GROUP BY substr(captured_at,1,10)", since);
+    let mut timeline: HashMap<String, Vec<(String, f64)>> = HashMap::new();
+    if let Ok(mut stmt) = conn.prepare(
+        "SELECT b.date, t.id, t.name, SUM(b.duration_minutes) FROM timeline_blocks b
+         JOIN timeline_activity_types t ON t.id = b.type_id WHERE b.date >= ?1 AND b.source != 'plan' GROUP BY b.date, t.id",
+    ) {
+        for (date, id, name, minutes) in stmt.query_map([since], |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?, r.get::<_, String>(2)?, r.get::<_, f64>(3)?)))
+            .into_iter().flatten().flatten() {
+            let key = format!("timeline:{}", id);
+            labels.insert(key.clone(), format!("{}, мин", name));
+            timeline.entry(date).or_default().push((key, minutes));
+        }
+    }
+    let timeline_keys: Vec<String> = labels.keys().filter(|k| k.starts_with("timeline:")).cloned().collect();
+    let symptoms = load_symptoms(conn, since);
+    let symptom_keys: Vec<String> = symptoms.values().flatten().map(|s| format!("symptom:{}", s.name.to_lowercase())).collect();
+    for s in symptoms.values().flatten() {
+        labels.insert(format!("symptom:{}", s.name.to_lowercase()), format!("Симптом: {}", s.name));
+    }
+
+    let mut days = Vec::new();
+    let Ok(mut stmt) = conn.prepare("SELECT date, mood, energy, stress, cycle_flow FROM daily_checkins WHERE date >= ?1 ORDER BY date") else {
+        return (days, labels);
+    };
+    let rows: Vec<CheckinRow> = stmt
+        .query_map([since], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?)))
+        .map(|rows| rows.flatten().collect()).unwrap_or_default();
+    let cycle_tracked = rows.iter().any(|r| r.4.is_some_and(|f| f > 0));
+    for (date, mood, energy, stress, flow) in rows {
+        let mut d = DayData::default();
+        for (k, v) in [("mood", mood), ("energy", energy), ("stress", stress)] {
+            if let Some(v) = v { d.outcomes.insert(k, v); }
+        }
+        if let Some((h, score)) = sleep.get(&date) {
+            d.factors.insert("sleep_hours".into(), (h * 10.0).round() / 10.0);
+            if let Some(s) = score { d.factors.insert("sleep_score".into(), *s); }
+        }
+        d.factors.insert("workout_minutes".into(), workouts.get(&date).copied().unwrap_or(0.0));
+        if let Some(c) = calories.get(&date).filter(|c| **c > 0.0) { d.factors.insert("calories".into(), *c); }
+        if let Some(s) = steps.get(&date) { d.factors.insert("steps".into(), *s); }
+        if let Some(m) = screen.get(&date) { d.factors.insert("screen_minutes".into(), *m); }
+        // Timeline types count as 0 only on days that have any timeline blocks.
+        if let Some(blocks) = timeline.get(&date) {
+            for key in &timeline_keys {
+                d.factors.insert(key.clone(), blocks.iter().find(|(k, _)| k == key).map(|b| b.1).unwrap_or(0.0));
+            }
+        }
+        let today_symptoms = symptoms.get(&date);
+        for key in &symptom_keys {
+            let sev = today_symptoms.and_then(|list| list.iter().find(|s| format!("symptom:{}", s.name.to_lowercase()) == *key)).map(|s| s.severity);
+            d.factors.insert(key.clone(), sev.unwrap_or(0) as f64);
+        }
+        if cycle_tracked { d.factors.insert("period".into(), if flow.unwrap_or(0) > 0 { 1.0 } else { 0.0 }); }
+        days.push(d);
+    }
+    (days, labels)
+}
+
+fn flow_days(conn: &rusqlite::Connection) -> Vec<NaiveDate> {
+    conn.prepare("SELECT date FROM daily_checkins WHERE cycle_flow > 0 ORDER BY date")
+        .and_then(|mut s| s.query_map([], |r| r.get::<_, String>(0))?.collect::<Result<Vec<_>, _>>())
+        .unwrap_or_default()
+        .into_iter().filter_map(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok())
+        .collect()
+}
+
+// ── Commands ──
+
+/// Create or replace the check-in for `date` (default today). Scales are 1–5;
+/// `cycle_flow` is 0 (none) … 3 (heavy), `None` when not tracked.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn save_checkin(
+    date: Option<String>, mood: Option<i64>, energy: Option<i64>, stress: Option<i64>, cycle_flow: Option<i64>,
+    symptoms: Option<Vec<Symptom>>, notes: Option<String>, db: tauri::State<'_, HanniDb>,
+) -> Result<(), String> {
+    for v in [mood, energy, stress].into_iter().flatten() {
+        if !(1..=5).contains(&v) { return Err("Оценка должна быть от 1 до 5".into()); }
+    }
+    if cycle_flow.is_some_and(|f| !(0..=3).contains(&f)) { return Err("Некорректное значение цикла".into()); }
+    let now = chrono::Local::now();
+    let date = date.unwrap_or_else(|| now.format("%Y-%m-%d").to_string());
+    NaiveDate::parse_from_str(&date, "%Y-%m-%d").map_err(|_| "Некорректная дата".to_string())?;
+    let conn = db.conn();
+    conn.execute(
+        "INSERT INTO daily_checkins (date, mood, energy, stress, cycle_flow, notes, created_at, updated_at)
+         VALUES (?1,?2,?3,?4,?5,?6,?7,?7)
+         ON CONFLICT(date) DO UPDATE SET mood=excluded.mood, energy=excluded.energy, stress=excluded.stress,
+            cycle_flow=excluded.cycle_flow, notes=excluded.notes, updated_at=excluded.updated_at",
+        rusqlite::params![date, mood, energy, stress, cycle_flow, notes.unwrap_or_default(), now.to_rfc3339()],
+    ).map_err(|e| format!("DB error: {}", e))?;
+    if let Some(list) = symptoms {
+        conn.execute("DELETE FROM checkin_symptoms WHERE date=?1", [&date]).map_err(|e| format!("DB error: {}", e))?;
+        for s in list.iter().filter(|s| !s.name.trim().is_empty()) {
+            conn.execute(
+                "INSERT OR REPLACE INTO checkin_symptoms (date, name, severity) VALUES (?1,?2,?3)",
+                rusqlite::params![date, s.name.trim(), s.severity.clamp(1, 3)],
+            ).map_err(|e| format!("DB error: {}", e))?;
+        }
+    }
+    if let Some(m) = mood {
+        let updated = conn.execute("UPDATE health_log SET value=?1 WHERE date=?2 AND type='mood'", rusqlite::params![m, date])
+            .map_err(|e| format!("DB error: {}", e))?;
+        if updated == 0 {
+            conn.execute(
+                "INSERT INTO health_log (id, date, type, value, unit, notes, created_at) VALUES (?1,?2,'mood',?3,'1-5','',?4)",
+                rusqlite::params![crate::types::new_uuid_v7(), date, m, now.to_rfc3339()],
+            ).map_err(|e| format!("DB error: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_checkins(days: Option<i64>, db: tauri::State<'_, HanniDb>) -> Result<serde_json::Value, String> {
+    let conn = db.read();
+    let today = chrono::Local::now().date_naive();
+    let since = (today - chrono::Duration::days(days.unwrap_or(30).clamp(1, 3650) - 1)).to_string();
+    let mut symptoms = load_symptoms(&conn, &since);
+    let mut stmt = conn.prepare(
+        "SELECT date, mood, energy, stress, cycle_flow, notes FROM daily_checkins WHERE date >= ?1 ORDER BY date DESC",
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let entries: Vec<serde_json::Value> = stmt.query_map([&since], |r| {
+        let date: String = r.get(0)?;
+        Ok(serde_json::json!({
+            "date": date, "mood": r.get::<_, Option<i64>>(1)?, "energy": r.get::<_, Option<i64>>(2)?,
+            "stress": r.get::<_, Option<i64>>(3)?, "cycle_flow": r.get::<_, Option<i64>>(4)?, "notes": r.get::<_, String>(5)?,
+        }))
+    }).map_err(|e| format!("Query error: {}", e))?.flatten()
+        .map(|mut e| {
+            let list = e["date"].as_str().and_then(|d| symptoms.remove(d)).unwrap_or_default();
+            e["symptoms"] = serde_json::json!(list);
+            e
+        }).collect();
+    let known: Vec<String> = conn.prepare("SELECT name FROM checkin_symptoms GROUP BY lower(name) ORDER BY COUNT(*) DESC LIMIT 20")
+        .and_then(|mut s| s.query_map([], |r| r.get(0))?.collect())
+        .unwrap_or_default();
+    Ok(serde_json::json!({
+        "entries": entries,
+        "known_symptoms": known,
+        "cycle": cycle_summary(&flow_days(&conn), today),
+    }))
+}
+
+#[tauri::command]
+pub fn delete_checkin(date: String, db: tauri::State<'_, HanniDb>) -> Result<(), String> {
+    let conn = db.conn();
+    conn.execute("DELETE FROM checkin_symptoms WHERE date=?1", [&date]).map_err(|e| format!("DB error: {}", e))?;
+    conn.execute("DELETE FROM daily_checkins WHERE date=?1", [&date]).map_err(|e| format!("DB error: {}", e))?;
+    Ok(())
+}
+
+/// What moves mood / energy / stress over the last `days` (default 90).
+#[tauri::command]
+pub fn get_checkin_correlations(days: Option<i64>, db: tauri::State<'_, HanniDb>) -> Result<serde_json::Value, String> {
+    let conn = db.read();
+    let since = (chrono::Local::now().date_naive() - chrono::Duration::days(days.unwrap_or(90).clamp(14, 3650) - 1)).to_string();
+    let (data, labels) = collect_days(&conn, &since);
+    let averages: BTreeMap<&str, Option<f64>> = OUTCOMES.iter().map(|&(k, _)| {
+        let v: Vec<f64> = data.iter().filter_map(|d| d.outcomes.get(k).copied()).collect();
+        (k, (!v.is_empty()).then(|| (v.iter().sum::<f64>() / v.len() as f64 * 10.0).round() / 10.0))
+    }).collect();
+    Ok(serde_json::json!({
+        "days": data.len(),
+        "min_days": MIN_DAYS,
+        "averages": averages,
+        "findings": correlate(&data, &labels),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::d;
+
+    #[test]
+    fn finds_sleep_driving_mood() {
+        let days: Vec<DayData> = (0..14).map(|i| {
+            let mut d = DayData::default();
+            let sleep = 5.0 + (i % 4) as f64;
+            d.factors.insert("sleep_hours".into(), sleep);
+            d.factors.insert("noise".into(), ((i * 7) % 5) as f64);
+            d.factors.insert("workout_minutes".into(), if i % 2 == 0 { 30.0 } else { 0.0 });
+            d.outcomes.insert("mood", (sleep - 3.0).min(5.0));
+            d
+        }).collect();
+        let labels = HashMap::from([("sleep_hours".to_string(), "Сон".to_string())]);
+        let f = correlate(&days, &labels);
+        let top = &f[0];
+        assert_eq!((top.outcome, top.factor.as_str(), top.label.as_str()), ("mood", "sleep_hours", "Сон"));
+        assert!(top.r > 0.95);
+        assert!(top.avg_high > top.avg_low);
+        assert!(f.iter().all(|x| x.factor != "noise" || x.r.abs() >= MIN_R));
+        // Too few days → nothing.
+        assert!(correlate(&days[..5], &labels).is_empty());
+    }
+
+    #[test]
+    fn binary_factor_split() {
+        let days: Vec<DayData> = (0..12).map(|i| {
+            let mut d = DayData::default();
+            let period = if i < 4 { 1.0 } else { 0.0 };
+            d.factors.insert("period".into(), period);
+            d.outcomes.insert("energy", if period > 0.0 { 2.0 } else { 4.0 });
+            d
+        }).collect();
+        let f = correlate(&days, &HashMap::new());
+        assert_eq!(f.len(), 1);
+        assert_eq!((f[0].avg_high, f[0].avg_low), (2.0, 4.0));
+    }
+
+    #[test]
+    fn cycle_prediction() {
+        let mut flow = Vec::new();
+        for start in ["2024-03-01", "2024-03-29", "2024-04-27"] {
+            for i in 0..4 { flow.push(d(start) + chrono::Duration::days(i)); }
+        }
+        let c = cycle_summary(&flow, d("2024-05-06")).unwrap();
+        assert_eq!(c.last_start, "2024-04-27");
+        assert_eq!(c.cycle_day, 10);
+        assert_eq!(c.avg_length, Some(28.5));
+        assert_eq!(c.predicted_next.as_deref(), Some("2024-05-26"));
+        assert_eq!(cycle_summary(&[], d("2024-05-06")), None);
+    }
+
+    #[test]
+    fn sparse_series_edges() {
+        assert_eq!(pearson(&[]), None);
+        assert_eq!(pearson(&[(1.0, 2.0), (2.0, 3.0)]), None);
+        // A factor that never changes says nothing.
+        assert_eq!(pearson(&[(1.0, 2.0), (1.0, 3.0), (1.0, 4.0)]), None);
+        assert!(correlate(&[], &HashMap::new()).is_empty());
+
+        // A single flow day: a start, but no cycle length to predict from.
+        let c = cycle_summary(&[d("2024-05-01")], d("2024-05-01")).unwrap();
+        assert_eq!((c.cycle_day, c.avg_length, c.predicted_next), (1, None, None));
+        // A gap outside the plausible range (missed logging) is not a cycle.
+        let c = cycle_summary(&[d("2024-01-01"), d("2024-05-01")], d("2024-05-02")).unwrap();
+        assert_eq!((c.last_start.as_str(), c.avg_length), ("2024-05-01", None));
+    }
+
+    #[test]
+    fn sleep_counts_for_the_day_it_ended() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE daily_checkins (date TEXT, mood INTEGER, energy INTEGER, stress INTEGER, cycle_flow INTEGER);
+             CREATE TABLE sleep_sessions (date TEXT, start_time TEXT, duration_minutes INTEGER, quality_score INTEGER);
+             INSERT INTO daily_checkins VALUES ('2024-05-02', 3, 3, 3, NULL), ('2024-05-03', 4, 4, 2, NULL), ('2024-05-04', 2, 2, 4, NULL);
+             INSERT INTO sleep_sessions VALUES
+                ('2024-05-01', '23:30', 480, 80),  -- evening start → the next day
+                ('2024-05-03', '00:40', 420, NULL), -- after midnight → the same day
+                ('2024-05-03', '14:00', 30, NULL);  -- a nap doesn't replace the night"
+        ).unwrap();
+        let (days, _) = collect_days(&conn, "2024-05-02");
+        let sleep: Vec<Option<f64>> = days.iter().map(|d| d.factors.get("sleep_hours").copied()).collect();
+        assert_eq!(sleep, vec![Some(8.0), Some(7.0), None]);
+        assert_eq!(days[0].factors.get("sleep_score"), Some(&80.0));
+        assert_eq!(days[1].factors.get("sleep_score"), None);
+    }
+}
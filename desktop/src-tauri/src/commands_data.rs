@@ -811,7 +811,8 @@ pub fn create_workout(workout_type: String, title: String, duration_minutes: i64
 pub fn get_workouts(_date_range: Option<String>, db: tauri::State<'_, HanniDb>) -> Result<Vec<serde_json::Value>, String> {
     let conn = db.conn();
     let mut stmt = conn.prepare(
-        "SELECT id, type, title, date, duration_minutes, calories, notes FROM workouts ORDER BY date DESC, created_at DESC LIMIT 50"
+        "SELECT w.id, w.type, w.title, w.date, w.duration_minutes, w.calories, w.notes, t.distance_m, t.sport
+         FROM workouts w LEFT JOIN workout_tracks t ON t.workout_id = w.id ORDER BY w.date DESC, w.created_at DESC LIMIT 50"
     ).map_err(|e| format!("DB error: {}", e))?;
     let rows = stmt.query_map([], |row| {
         Ok(serde_json::json!({
@@ -822,6 +823,8 @@ pub fn get_workouts(_date_range: Option<String>, db: tauri::State<'_, HanniDb>)
             "duration_minutes": row.get::<_, i64>(4)?,
             "calories": row.get::<_, Option<i64>>(5)?,
             "notes": row.get::<_, String>(6)?,
+            "distance_m": row.get::<_, Option<f64>>(7)?,
+            "sport": row.get::<_, Option<String>>(8)?,
         }))
     }).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
     Ok(rows)
@@ -841,7 +844,11 @@ pub fn get_workout_stats(db: tauri::State<'_, HanniDb>) -> Result<serde_json::Va
 
 #[tauri::command]
 pub fn delete_workout(id: i64, db: tauri::State<'_, HanniDb>) -> Result<(), String> {
-    db.conn().execute("DELETE FROM workouts WHERE id = ?1", rusqlite::params![id])
+    let conn = db.conn();
+    conn.execute("DELETE FROM workout_sets WHERE workout_id = ?1", rusqlite::params![id]).ok();
+    conn.execute("DELETE FROM exercises WHERE workout_id = ?1", rusqlite::params![id]).ok();
+    crate::track_import::delete_track(&conn, id);
+    conn.execute("DELETE FROM workouts WHERE id = ?1", rusqlite::params![id])
         .map_err(|e| format!("DB error: {}", e))?;
     Ok(())
 }
@@ -1158,15 +1165,15 @@ pub(crate) fn make_workout_from_template(conn: &rusqlite::Connection, template_i
     ).map_err(|e| format!("DB error: {}", e))?;
     let workout_id = conn.last_insert_rowid();
     let mut stmt = conn.prepare(
-        "SELECT name, sets, reps, weight_kg, duration_seconds FROM template_exercises WHERE template_id=?1 ORDER BY order_index"
+        "SELECT name, sets, reps, weight_kg, duration_seconds, exercise_catalog_id FROM template_exercises WHERE template_id=?1 ORDER BY order_index"
     ).map_err(|e| format!("DB error: {}", e))?;
-    let exercises: Vec<(String, i64, i64, f64, i64)> = stmt.query_map(rusqlite::params![template_id], |row| {
-        Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?))
+    let exercises: Vec<(String, i64, i64, f64, i64, Option<i64>)> = stmt.query_map(rusqlite::params![template_id], |row| {
+        Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?))
     }).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
-    for (name, sets, reps, weight, dur) in &exercises {
+    for (name, sets, reps, weight, dur, catalog_id) in &exercises {
         conn.execute(
-            "INSERT INTO exercises (workout_id, name, sets, reps, weight_kg, duration_seconds, created_at) VALUES (?1,?2,?3,?4,?5,?6,?7)",
-            rusqlite::params![workout_id, name, sets, reps, weight, dur, now.to_rfc3339()],
+            "INSERT INTO exercises (workout_id, name, sets, reps, weight_kg, duration_seconds, created_at, exercise_catalog_id) VALUES (?1,?2,?3,?4,?5,?6,?7,?8)",
+            rusqlite::params![workout_id, name, sets, reps, weight, dur, now.to_rfc3339(), catalog_id],
         ).map_err(|e| format!("DB error: {}", e))?;
     }
     Ok(workout_id)
@@ -1539,48 +1546,121 @@ pub fn get_health_today(db: tauri::State<'_, HanniDb>) -> Result<serde_json::Val
 }
 
 #[tauri::command]
-pub fn create_habit(name: String, icon: String, frequency: String, db: tauri::State<'_, HanniDb>) -> Result<i64, String> {
+#[allow(clippy::too_many_arguments)]
+pub fn create_habit(
+    name: String, icon: String, frequency: String,
+    schedule_kind: Option<String>, schedule_days: Option<i64>, schedule_count: Option<i64>,
+    target: Option<f64>, unit: Option<String>, auto_source: Option<String>,
+    db: tauri::State<'_, HanniDb>,
+) -> Result<i64, String> {
+    let schedule = match schedule_kind.as_deref() {
+        Some(kind) => crate::habits::parse_schedule(kind, schedule_days.unwrap_or(0), schedule_count.unwrap_or(1))?,
+        None => crate::habits::Schedule::from_frequency(&frequency),
+    };
+    let auto_source = auto_source.unwrap_or_default();
+    if !auto_source.is_empty() && !crate::habits::AUTO_SOURCES.contains(&auto_source.as_str()) {
+        return Err(format!("Неизвестный источник: {}", auto_source));
+    }
+    let (kind, days, count) = schedule.to_parts();
     let conn = db.conn();
     let now = chrono::Local::now().to_rfc3339();
     conn.execute(
-        "INSERT INTO habits (name, icon, frequency, created_at) VALUES (?1, ?2, ?3, ?4)",
-        rusqlite::params![name, icon, frequency, now],
+        "INSERT INTO habits (name, icon, frequency, schedule_kind, schedule_days, schedule_count, target, unit, auto_source, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        rusqlite::params![name, icon, if frequency.is_empty() { kind.to_string() } else { frequency }, kind, days, count,
+            target.filter(|t| *t > 0.0).unwrap_or(1.0), unit.unwrap_or_default(), auto_source, now],
     ).map_err(|e| format!("DB error: {}", e))?;
     Ok(conn.last_insert_rowid())
 }
 
+fn habit_target(conn: &rusqlite::Connection, habit_id: i64) -> Result<f64, String> {
+    conn.query_row("SELECT target FROM habits WHERE id=?1", rusqlite::params![habit_id], |r| r.get::<_, f64>(0))
+        .map_err(|e| format!("DB error: {}", e))
+}
+
+/// Without `value`: toggle done/undone (done = full target). With `value`:
+/// record that amount — below the target it's a partial check, 0 clears it.
 #[tauri::command]
-pub fn check_habit(habit_id: i64, date: Option<String>, db: tauri::State<'_, HanniDb>) -> Result<(), String> {
+pub fn check_habit(habit_id: i64, date: Option<String>, value: Option<f64>, db: tauri::State<'_, HanniDb>) -> Result<(), String> {
     let conn = db.conn();
     let target_date = date.unwrap_or_else(|| chrono::Local::now().format("%Y-%m-%d").to_string());
-    let now = chrono::Local::now().to_rfc3339();
-    // Toggle: if exists, delete; else insert
-    let existing: Option<i64> = conn.query_row(
-        "SELECT id FROM habit_checks WHERE habit_id=?1 AND date=?2",
-        rusqlite::params![habit_id, target_date],
-        |row| row.get(0),
+    let target = habit_target(&conn, habit_id)?;
+    let value = match value {
+        Some(v) => v,
+        None => {
+            let existing: Option<(String, Option<f64>)> = conn.query_row(
+                "SELECT COALESCE(status,'done'), value FROM habit_checks WHERE habit_id=?1 AND date=?2",
+                rusqlite::params![habit_id, target_date], |row| Ok((row.get(0)?, row.get(1)?)),
+            ).ok();
+            match existing {
+                Some((status, v)) if status != "frozen" && v.is_none_or(|v| v >= target) => 0.0,
+                _ => target,
+            }
+        }
+    };
+    crate::habits::set_value(&conn, habit_id, &target_date, value, target, "manual")
+        .map_err(|e| format!("DB error: {}", e))
+}
+
+/// Toggle a freeze day: keeps the streak alive without counting as done.
+/// Limited to `habits::FREEZES_PER_MONTH` per calendar month.
+#[tauri::command]
+pub fn freeze_habit(habit_id: i64, date: Option<String>, db: tauri::State<'_, HanniDb>) -> Result<(), String> {
+    let conn = db.conn();
+    let target_date = date.unwrap_or_else(|| chrono::Local::now().format("%Y-%m-%d").to_string());
+    let status: Option<String> = conn.query_row(
+        "SELECT COALESCE(status,'done') FROM habit_checks WHERE habit_id=?1 AND date=?2",
+        rusqlite::params![habit_id, target_date], |row| row.get(0),
     ).ok();
-    if let Some(id) = existing {
-        conn.execute("DELETE FROM habit_checks WHERE id=?1", rusqlite::params![id])
+    if status.as_deref() == Some("frozen") {
+        conn.execute("DELETE FROM habit_checks WHERE habit_id=?1 AND date=?2", rusqlite::params![habit_id, target_date])
             .map_err(|e| format!("DB error: {}", e))?;
-    } else {
-        conn.execute(
-            "INSERT INTO habit_checks (habit_id, date, completed, created_at) VALUES (?1, ?2, 1, ?3)",
-            rusqlite::params![habit_id, target_date, now],
-        ).map_err(|e| format!("DB error: {}", e))?;
+        return Ok(());
     }
+    if crate::habits::freezes_used(&conn, habit_id, &target_date) >= crate::habits::FREEZES_PER_MONTH {
+        return Err(format!("Лимит заморозок: {} в месяц", crate::habits::FREEZES_PER_MONTH));
+    }
+    conn.execute(
+        "INSERT INTO habit_checks (habit_id, date, completed, value, status, source, created_at) VALUES (?1, ?2, 1, NULL, 'frozen', 'manual', ?3)
+         ON CONFLICT(habit_id, date) DO UPDATE SET completed=1, value=NULL, status='frozen', source='manual'",
+        rusqlite::params![habit_id, target_date, chrono::Local::now().to_rfc3339()],
+    ).map_err(|e| format!("DB error: {}", e))?;
     Ok(())
 }
 
 #[tauri::command]
-pub fn update_habit(id: i64, name: Option<String>, frequency: Option<String>, icon: Option<String>, db: tauri::State<'_, HanniDb>) -> Result<(), String> {
+#[allow(clippy::too_many_arguments)]
+pub fn update_habit(
+    id: i64, name: Option<String>, frequency: Option<String>, icon: Option<String>,
+    schedule_kind: Option<String>, schedule_days: Option<i64>, schedule_count: Option<i64>,
+    target: Option<f64>, unit: Option<String>, auto_source: Option<String>,
+    db: tauri::State<'_, HanniDb>,
+) -> Result<(), String> {
+    // A plain `frequency` edit (table select) also moves the structured schedule.
+    let schedule = match (schedule_kind.as_deref(), frequency.as_deref()) {
+        (Some(kind), _) => Some(crate::habits::parse_schedule(kind, schedule_days.unwrap_or(0), schedule_count.unwrap_or(1))?),
+        (None, Some(f)) => Some(crate::habits::Schedule::from_frequency(f)),
+        _ => None,
+    };
+    if let Some(src) = auto_source.as_deref().filter(|s| !s.is_empty()) {
+        if !crate::habits::AUTO_SOURCES.contains(&src) { return Err(format!("Неизвестный источник: {}", src)); }
+    }
     let conn = db.conn();
     let mut updates = Vec::new();
     let mut params: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
     let mut idx = 1;
     if let Some(v) = name { updates.push(format!("name=?{}", idx)); params.push(Box::new(v)); idx += 1; }
-    if let Some(v) = frequency { updates.push(format!("frequency=?{}", idx)); params.push(Box::new(v)); idx += 1; }
     if let Some(v) = icon { updates.push(format!("icon=?{}", idx)); params.push(Box::new(v)); idx += 1; }
+    if let Some(s) = schedule {
+        let (kind, days, count) = s.to_parts();
+        let freq = frequency.unwrap_or_else(|| kind.to_string());
+        updates.push(format!("frequency=?{}, schedule_kind=?{}, schedule_days=?{}, schedule_count=?{}", idx, idx + 1, idx + 2, idx + 3));
+        params.push(Box::new(freq)); params.push(Box::new(kind)); params.push(Box::new(days)); params.push(Box::new(count));
+        idx += 4;
+    }
+    if let Some(v) = target.filter(|t| *t > 0.0) { updates.push(format!("target=?{}", idx)); params.push(Box::new(v)); idx += 1; }
+    if let Some(v) = unit { updates.push(format!("unit=?{}", idx)); params.push(Box::new(v)); idx += 1; }
+    if let Some(v) = auto_source { updates.push(format!("auto_source=?{}", idx)); params.push(Box::new(v)); idx += 1; }
     if updates.is_empty() { return Ok(()); }
     params.push(Box::new(id));
     let sql = format!("UPDATE habits SET {} WHERE id=?{}", updates.join(","), idx);
@@ -1648,26 +1728,56 @@ pub fn delete_activity(id: i64, db: tauri::State<'_, HanniDb>) -> Result<(), Str
 #[tauri::command]
 pub fn get_habits_today(db: tauri::State<'_, HanniDb>) -> Result<Vec<serde_json::Value>, String> {
     let conn = db.conn();
-    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let now = chrono::Local::now();
+    let today = now.date_naive();
+    let today_s = today.format("%Y-%m-%d").to_string();
+    // Data-linked habits: pick up steps/workouts/cooking logged since last look.
+    crate::habits::auto_check(&conn, &today_s);
+    crate::habits::auto_check(&conn, &(today - chrono::Duration::days(1)).format("%Y-%m-%d").to_string());
+
     let mut stmt = conn.prepare(
-        "SELECT h.id, h.name, h.icon, h.frequency,
-                (SELECT COUNT(*) FROM habit_checks WHERE habit_id=h.id AND date=?1) as checked,
-                (SELECT COUNT(*) FROM habit_checks hc WHERE hc.habit_id=h.id AND hc.date >= date(?1, '-30 days')) as streak_approx
-         FROM habits h ORDER BY h.created_at"
+        "SELECT id, name, icon, frequency, schedule_kind, schedule_days, schedule_count, target, unit, auto_source, created_at
+         FROM habits ORDER BY created_at"
     ).map_err(|e| format!("DB error: {}", e))?;
-    let rows = stmt.query_map(rusqlite::params![today], |row| {
-        // Simple streak calc: count consecutive days backward
-        let checked: i64 = row.get(4)?;
-        let streak_approx: i64 = row.get(5)?;
-        Ok(serde_json::json!({
-            "id": row.get::<_, i64>(0)?,
-            "name": row.get::<_, String>(1)?,
-            "icon": row.get::<_, String>(2)?,
-            "frequency": row.get::<_, String>(3)?,
-            "completed": checked > 0,
-            "streak": streak_approx,
-        }))
+    #[allow(clippy::type_complexity)]
+    let habits: Vec<(i64, String, String, String, Option<String>, i64, i64, f64, String, String, String)> = stmt.query_map([], |row| {
+        Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?, row.get(6)?,
+            row.get(7)?, row.get(8)?, row.get(9)?, row.get(10)?))
     }).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+
+    let since = (today - chrono::Duration::days(730)).format("%Y-%m-%d").to_string();
+    let rows = habits.into_iter().map(|(id, name, icon, frequency, kind, days, count, target, unit, auto_source, created_at)| {
+        let created = chrono::DateTime::parse_from_rfc3339(&created_at).map(|d| d.date_naive()).unwrap_or(today);
+        let schedule = crate::habits::Schedule::from_parts(kind.as_deref(), days, count, &frequency, created);
+        let marks = crate::habits::load_marks(&conn, id, target, &since);
+        let stats = crate::habits::stats(schedule, &marks, created, today);
+        let (status, value): (Option<String>, Option<f64>) = conn.query_row(
+            "SELECT COALESCE(status,'done'), value FROM habit_checks WHERE habit_id=?1 AND date=?2",
+            rusqlite::params![id, today_s], |r| Ok((Some(r.get(0)?), r.get(1)?)),
+        ).unwrap_or((None, None));
+        let status = status.filter(|s| s != "cleared");
+        let value_today = match (status.as_deref(), value) {
+            (Some("frozen") | None, _) => 0.0,
+            (_, Some(v)) => v,
+            (_, None) => target,
+        };
+        let (kind, days, count) = schedule.to_parts();
+        serde_json::json!({
+            "id": id, "name": name, "icon": icon, "frequency": frequency,
+            "schedule_kind": kind, "schedule_days": days, "schedule_count": count,
+            "schedule_label": schedule.label(),
+            "due_today": schedule.is_due(today),
+            "target": target, "unit": unit, "auto_source": auto_source,
+            "value_today": value_today,
+            "progress": (value_today / target).min(1.0),
+            "status": status.unwrap_or_default(),
+            "completed": value_today >= target,
+            "streak": stats.streak, "best_streak": stats.best_streak, "streak_unit": stats.streak_unit,
+            "consistency": stats.consistency,
+            "week_done": stats.week_done, "week_target": stats.week_target,
+            "freezes_left": (crate::habits::FREEZES_PER_MONTH - crate::habits::freezes_used(&conn, id, &today_s)).max(0),
+        })
+    }).collect();
     Ok(rows)
 }
 
@@ -1707,13 +1817,11 @@ pub fn get_dashboard_data(db: tauri::State<'_, HanniDb>) -> Result<serde_json::V
     // Notes count
     let notes_count: i64 = conn.query_row("SELECT COUNT(*) FROM notes WHERE archived=0", [], |row| row.get(0)).unwrap_or(0);
 
-    // Events today
-    let mut events_stmt = conn.prepare(
-        "SELECT title, time FROM events WHERE date=?1 ORDER BY time"
-    ).map_err(|e| format!("DB error: {}", e))?;
-    let events: Vec<serde_json::Value> = events_stmt.query_map(rusqlite::params![today], |row| {
-        Ok(serde_json::json!({ "title": row.get::<_, String>(0)?, "time": row.get::<_, String>(1)? }))
-    }).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+    // Events today (date and time as seen in the current zone)
+    let current = crate::time_zones::current_zone(&conn);
+    let local_today = crate::time_zones::now_in(current).format("%Y-%m-%d").to_string();
+    let events: Vec<serde_json::Value> = crate::time_zones::events_between(&conn, &local_today, &local_today, current)?
+        .into_iter().map(|e| serde_json::json!({ "title": e.title, "time": e.time })).collect();
 
     // Recent notes
     let mut notes_stmt = conn.prepare(
@@ -1742,21 +1850,20 @@ pub fn get_notifications(db: tauri::State<'_, HanniDb>) -> Result<serde_json::Va
     let now = chrono::Local::now();
     let today = now.format("%Y-%m-%d").to_string();
     let current_time = now.format("%H:%M").to_string();
-    let soon_time = (now + chrono::Duration::hours(2)).format("%H:%M").to_string();
 
-    // Upcoming events (next 2 hours)
-    let mut upcoming: Vec<serde_json::Value> = Vec::new();
-    if let Ok(mut stmt) = conn.prepare(
-        "SELECT title, time FROM events WHERE date = ?1 AND time >= ?2 AND time <= ?3 ORDER BY time"
-    ) {
-        if let Ok(rows) = stmt.query_map(rusqlite::params![&today, &current_time, &soon_time], |row| {
-            let title: String = row.get(0)?;
-            let time: String = row.get(1)?;
-            Ok(serde_json::json!({"type": "event", "title": title, "time": time}))
-        }) {
-            upcoming.extend(rows.flatten());
-        }
-    }
+    // Upcoming events (next 2 hours, in the current zone)
+    let zone = crate::time_zones::current_zone(&conn);
+    let local_now = crate::time_zones::now_in(zone);
+    let from = local_now.format("%Y-%m-%d %H:%M").to_string();
+    let to = (local_now + chrono::Duration::hours(2)).format("%Y-%m-%d %H:%M").to_string();
+    let upcoming: Vec<serde_json::Value> = crate::time_zones::events_between(&conn, &from[..10], &to[..10], zone)
+        .unwrap_or_default().into_iter()
+        .filter(|e| {
+            let at = format!("{} {}", e.date, e.time);
+            !e.time.is_empty() && at >= from && at <= to
+        })
+        .map(|e| serde_json::json!({"type": "event", "title": e.title, "time": e.time}))
+        .collect();
 
     // Overdue tasks
     let mut overdue: Vec<serde_json::Value> = Vec::new();
@@ -2202,19 +2309,33 @@ pub fn get_media_stats(media_type: Option<String>, db: tauri::State<'_, HanniDb>
 // ── v0.8.0: Food commands ──
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub fn log_food(
     date: Option<String>, meal_type: String, name: String,
     calories: Option<i64>, protein: Option<f64>, carbs: Option<f64>, fat: Option<f64>,
-    notes: Option<String>, db: tauri::State<'_, HanniDb>,
+    notes: Option<String>, recipe_id: Option<i64>, servings: Option<f64>,
+    db: tauri::State<'_, HanniDb>,
 ) -> Result<i64, String> {
     let conn = db.conn();
     let now = chrono::Local::now();
     let d = date.unwrap_or_else(|| now.format("%Y-%m-%d").to_string());
+    let srv = servings.filter(|s| *s > 0.0).unwrap_or(1.0);
+    // Eating a recipe reuses its per-serving macros for whatever wasn't given explicitly.
+    let from_recipe = recipe_id.and_then(|rid| crate::nutrition::recipe_serving_macros(&conn, rid));
+    let (name, calories, protein, carbs, fat) = match from_recipe {
+        Some((rname, m)) => {
+            let m = m.scaled(srv);
+            (if name.trim().is_empty() { rname } else { name },
+             calories.unwrap_or(m.kcal.round() as i64), protein.unwrap_or(m.protein),
+             carbs.unwrap_or(m.carbs), fat.unwrap_or(m.fat))
+        }
+        None => (name, calories.unwrap_or(0), protein.unwrap_or(0.0), carbs.unwrap_or(0.0), fat.unwrap_or(0.0)),
+    };
     conn.execute(
-        "INSERT INTO food_log (date, meal_type, name, calories, protein, carbs, fat, notes, created_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-        rusqlite::params![d, meal_type, name, calories.unwrap_or(0), protein.unwrap_or(0.0),
-            carbs.unwrap_or(0.0), fat.unwrap_or(0.0), notes.unwrap_or_default(), now.to_rfc3339()],
+        "INSERT INTO food_log (date, meal_type, name, calories, protein, carbs, fat, notes, recipe_id, servings, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        rusqlite::params![d, meal_type, name, calories, protein, carbs, fat,
+            notes.unwrap_or_default(), recipe_id, srv, now.to_rfc3339()],
     ).map_err(|e| format!("DB error: {}", e))?;
     Ok(conn.last_insert_rowid())
 }
@@ -2224,7 +2345,7 @@ pub fn get_food_log(date: Option<String>, db: tauri::State<'_, HanniDb>) -> Resu
     let conn = db.conn();
     let d = date.unwrap_or_else(|| chrono::Local::now().format("%Y-%m-%d").to_string());
     let mut stmt = conn.prepare(
-        "SELECT id, meal_type, name, calories, protein, carbs, fat, notes FROM food_log WHERE date=?1 ORDER BY created_at"
+        "SELECT id, meal_type, name, calories, protein, carbs, fat, notes, recipe_id, servings FROM food_log WHERE date=?1 ORDER BY created_at"
     ).map_err(|e| format!("DB error: {}", e))?;
     let rows = stmt.query_map(rusqlite::params![d], |row| {
         Ok(serde_json::json!({
@@ -2232,6 +2353,8 @@ pub fn get_food_log(date: Option<String>, db: tauri::State<'_, HanniDb>) -> Resu
             "name": row.get::<_, String>(2)?, "calories": row.get::<_, i64>(3)?,
             "protein": row.get::<_, f64>(4)?, "carbs": row.get::<_, f64>(5)?,
             "fat": row.get::<_, f64>(6)?, "notes": row.get::<_, String>(7)?,
+            "recipe_id": row.get::<_, Option<i64>>(8).unwrap_or(None),
+            "servings": row.get::<_, f64>(9).unwrap_or(1.0),
         }))
     }).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
     Ok(rows)
@@ -2318,6 +2441,7 @@ pub fn create_recipe(
         }
         crate::sync_share::mark_dirty(&conn, "recipe_ingredients");
     }
+    crate::nutrition::auto_recalc_after_edit(&conn, recipe_id, calories.is_some());
     crate::sync_share::mark_dirty(&conn, "recipes");
     Ok(recipe_id)
 }
@@ -2367,7 +2491,7 @@ pub fn recipe_from_row(row: &rusqlite::Row) -> Result<serde_json::Value, rusqlit
 pub fn get_recipe(id: i64, db: tauri::State<'_, HanniDb>) -> Result<serde_json::Value, String> {
     let conn = db.conn();
     let mut recipe = conn.query_row(
-        "SELECT id, name, description, ingredients, instructions, prep_time, cook_time, servings, calories, tags, difficulty, cuisine, health_score, price_score, protein, fat, carbs, favorite, last_cooked, image, taste_rating, cook_note FROM recipes WHERE id=?1",
+        "SELECT id, name, description, ingredients, instructions, prep_time, cook_time, servings, calories, tags, difficulty, cuisine, health_score, price_score, protein, fat, carbs, favorite, last_cooked, image, taste_rating, cook_note, fiber, nutrition_auto, nutrition_coverage FROM recipes WHERE id=?1",
         rusqlite::params![id],
         |row| Ok(serde_json::json!({
             "id": row.get::<_, i64>(0)?, "name": row.get::<_, String>(1)?,
@@ -2387,6 +2511,9 @@ pub fn get_recipe(id: i64, db: tauri::State<'_, HanniDb>) -> Result<serde_json::
             "image": row.get::<_, String>(19).unwrap_or_default(),
             "taste_rating": row.get::<_, i64>(20).unwrap_or(0),
             "cook_note": row.get::<_, String>(21).unwrap_or_default(),
+            "fiber": row.get::<_, i64>(22).unwrap_or(0),
+            "nutrition_auto": row.get::<_, i64>(23).unwrap_or(0) == 1,
+            "nutrition_coverage": row.get::<_, f64>(24).unwrap_or(0.0),
         })),
     ).map_err(|e| format!("Recipe not found: {}", e))?;
     // Attach structured ingredients
@@ -2416,6 +2543,8 @@ pub fn update_recipe(
     ingredient_items: Option<Vec<serde_json::Value>>, db: tauri::State<'_, HanniDb>,
 ) -> Result<(), String> {
     let conn = db.conn();
+    let calories_given = calories.is_some();
+    let nutrition_inputs_changed = ingredient_items.is_some() || servings.is_some();
     let mut updates = Vec::new();
     let mut params: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
     let mut idx = 1;
@@ -2451,6 +2580,12 @@ pub fn update_recipe(
         }
         crate::sync_share::mark_dirty(&conn, "recipe_ingredients");
     }
+    if calories_given || nutrition_inputs_changed {
+        crate::nutrition::auto_recalc_after_edit(&conn, id, calories_given);
+    }
+    if nutrition_inputs_changed {
+        crate::commands_shopping::refresh_plan_shopping_for_recipe(&conn, id);
+    }
     crate::sync_share::mark_dirty(&conn, "recipes");
     Ok(())
 }
@@ -2465,6 +2600,8 @@ pub fn toggle_favorite_recipe(id: i64, db: tauri::State<'_, HanniDb>) -> Result<
     Ok(next)
 }
 
+// Only bumps last_cooked; stock is deducted by log_cooking alone, so a cooking
+// recorded both ways isn't taken from the products twice.
 #[tauri::command]
 pub fn mark_recipe_cooked(id: i64, db: tauri::State<'_, HanniDb>) -> Result<String, String> {
     let conn = db.conn();
@@ -2475,18 +2612,25 @@ pub fn mark_recipe_cooked(id: i64, db: tauri::State<'_, HanniDb>) -> Result<Stri
 }
 
 // Log one cooking of a recipe (immutable history entry) + bump recipes.last_cooked.
+// Ingredients are deducted from products (scaled by servings); pass
+// consume_product_ids to limit which rows may be used, or an empty list to skip.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub fn log_cooking(
     recipe_id: i64, date: String, taste_rating: Option<i64>, cook_note: Option<String>,
-    event_id: Option<i64>, db: tauri::State<'_, HanniDb>,
+    event_id: Option<i64>, servings: Option<i64>, consume_product_ids: Option<Vec<i64>>,
+    db: tauri::State<'_, HanniDb>,
 ) -> Result<i64, String> {
     let conn = db.conn();
     let now = chrono::Local::now().to_rfc3339();
     conn.execute(
-        "INSERT INTO cooking_log (recipe_id, date, taste_rating, cook_note, event_id, created_at) VALUES (?1,?2,?3,?4,?5,?6)",
-        rusqlite::params![recipe_id, date, taste_rating.unwrap_or(0), cook_note.unwrap_or_default(), event_id, now],
+        "INSERT INTO cooking_log (recipe_id, date, taste_rating, cook_note, event_id, servings, created_at) VALUES (?1,?2,?3,?4,?5,?6,?7)",
+        rusqlite::params![recipe_id, date, taste_rating.unwrap_or(0), cook_note.unwrap_or_default(), event_id, servings, now],
     ).map_err(|e| format!("DB error: {}", e))?;
     let id = conn.last_insert_rowid();
+    if let Ok(plan) = crate::pantry::plan_consumption(&conn, recipe_id, servings, consume_product_ids.as_deref()) {
+        crate::pantry::apply_consumption(&conn, &plan, "cooked", Some(id), &date);
+    }
     // last_cooked = latest date in the log for this recipe.
     conn.execute(
         "UPDATE recipes SET last_cooked=(SELECT MAX(date) FROM cooking_log WHERE recipe_id=?1) WHERE id=?1",
@@ -2496,6 +2640,7 @@ pub fn log_cooking(
     crate::sync_share::mark_dirty(&conn, "recipes");
     // Auto-complete any schedule linked to cooking (auto_source='cooking').
     crate::calendar_health::auto_complete_from_cooking(&conn, &date, &now);
+    crate::habits::auto_check(&conn, &date);
     Ok(id)
 }
 
@@ -2553,7 +2698,8 @@ pub fn get_ingredient_catalog(db: tauri::State<'_, HanniDb>) -> Result<Vec<serde
     let conn = db.conn();
     let mut stmt = conn.prepare(
         "SELECT c.id, c.name, c.category, c.tags, COALESCE(c.subgroup,''), \
-                c.parent_id, COALESCE(p.name,'') \
+                c.parent_id, COALESCE(p.name,''), c.kcal_100g, c.protein_100g, c.fat_100g, \
+                c.carbs_100g, c.fiber_100g, c.density_g_ml, c.piece_g \
          FROM ingredient_catalog c \
          LEFT JOIN ingredient_catalog p ON p.id = c.parent_id \
          ORDER BY c.name"
@@ -2565,6 +2711,13 @@ pub fn get_ingredient_catalog(db: tauri::State<'_, HanniDb>) -> Result<Vec<serde
             "subgroup": row.get::<_, String>(4)?,
             "parent_id": row.get::<_, Option<i64>>(5)?,
             "parent_name": row.get::<_, String>(6)?,
+            "kcal_100g": row.get::<_, Option<f64>>(7).unwrap_or(None),
+            "protein_100g": row.get::<_, Option<f64>>(8).unwrap_or(None),
+            "fat_100g": row.get::<_, Option<f64>>(9).unwrap_or(None),
+            "carbs_100g": row.get::<_, Option<f64>>(10).unwrap_or(None),
+            "fiber_100g": row.get::<_, Option<f64>>(11).unwrap_or(None),
+            "density_g_ml": row.get::<_, Option<f64>>(12).unwrap_or(None),
+            "piece_g": row.get::<_, Option<f64>>(13).unwrap_or(None),
         }))
     }).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
     Ok(rows)
@@ -2626,6 +2779,7 @@ pub fn update_ingredient_in_catalog(
         crate::db::rename_legacy_by_name(&conn, "products", "name", &old_name, new_name, "");
         crate::db::rename_legacy_by_name(&conn, "recipe_ingredients", "name", &old_name, new_name, "");
         crate::db::rename_legacy_by_name(&conn, "food_blacklist", "value", &old_name, &lower_new, "type='product'");
+        crate::commands_shopping::refresh_plan_shopping_for_ingredient(&conn, id);
     }
     if let Some(ref cat) = category {
         conn.execute("UPDATE ingredient_catalog SET category=?1 WHERE id=?2",
@@ -2862,10 +3016,11 @@ pub fn add_cuisine(code: String, name: String, emoji: Option<String>, db: tauri:
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub fn add_product(
     name: String, category: Option<String>, quantity: Option<f64>, unit: Option<String>,
     expiry_date: Option<String>, location: Option<String>, notes: Option<String>,
-    catalog_id: Option<i64>,
+    catalog_id: Option<i64>, min_quantity: Option<f64>,
     db: tauri::State<'_, HanniDb>,
 ) -> Result<i64, String> {
     let conn = db.conn();
@@ -2884,12 +3039,12 @@ pub fn add_product(
         None => category.unwrap_or_else(|| "other".into()),
     };
     conn.execute(
-        "INSERT INTO products (name, category, quantity, unit, expiry_date, location, notes, purchased_at, created_at, catalog_id)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8, ?9)",
+        "INSERT INTO products (name, category, quantity, unit, expiry_date, location, notes, purchased_at, created_at, catalog_id, min_quantity)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8, ?9, ?10)",
         rusqlite::params![name, final_category, quantity.unwrap_or(1.0),
             unit.unwrap_or_else(|| "шт".into()), expiry_date,
             location.unwrap_or_else(|| "fridge".into()), notes.unwrap_or_default(), now,
-            resolved_cat_id],
+            resolved_cat_id, min_quantity.unwrap_or(0.0).max(0.0)],
     ).map_err(|e| format!("DB error: {}", e))?;
     let new_id = conn.last_insert_rowid();
     crate::sync_share::mark_dirty(&conn, "products");
@@ -2900,7 +3055,7 @@ pub fn add_product(
 pub fn get_products(location: Option<String>, db: tauri::State<'_, HanniDb>) -> Result<Vec<serde_json::Value>, String> {
     let conn = db.conn();
     let select = "SELECT p.id, p.name, p.category, p.quantity, p.unit, p.expiry_date, p.location, p.notes, \
-                         p.catalog_id, COALESCE(c.name,'') \
+                         p.catalog_id, COALESCE(c.name,''), p.min_quantity \
                   FROM products p LEFT JOIN ingredient_catalog c ON c.id = p.catalog_id";
     if let Some(loc) = location {
         let sql = format!("{} WHERE p.location=?1 ORDER BY p.expiry_date NULLS LAST", select);
@@ -2923,14 +3078,16 @@ pub fn product_from_row(row: &rusqlite::Row) -> Result<serde_json::Value, rusqli
         "location": row.get::<_, String>(6)?, "notes": row.get::<_, String>(7)?,
         "catalog_id": row.get::<_, Option<i64>>(8).unwrap_or(None),
         "catalog_name": row.get::<_, String>(9).unwrap_or_default(),
+        "min_quantity": row.get::<_, f64>(10).unwrap_or(0.0),
     }))
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub fn update_product(
     id: i64, name: Option<String>, quantity: Option<f64>, expiry_date: Option<String>,
     location: Option<String>, notes: Option<String>,
-    catalog_id: Option<i64>, clear_catalog: Option<bool>,
+    catalog_id: Option<i64>, clear_catalog: Option<bool>, min_quantity: Option<f64>,
     db: tauri::State<'_, HanniDb>,
 ) -> Result<(), String> {
     let conn = db.conn();
@@ -2943,6 +3100,7 @@ pub fn update_product(
     if let Some(v) = expiry_date { updates.push(format!("expiry_date=?{}", idx)); params.push(Box::new(v)); idx += 1; }
     if let Some(v) = location { updates.push(format!("location=?{}", idx)); params.push(Box::new(v)); idx += 1; }
     if let Some(v) = notes { updates.push(format!("notes=?{}", idx)); params.push(Box::new(v)); idx += 1; }
+    if let Some(v) = min_quantity { updates.push(format!("min_quantity=?{}", idx)); params.push(Box::new(v.max(0.0))); idx += 1; }
     if clear_catalog.unwrap_or(false) {
         updates.push(format!("catalog_id=NULL"));
     } else if let Some(cid) = catalog_id {
@@ -2962,6 +3120,7 @@ pub fn update_product(
     let param_refs: Vec<&dyn rusqlite::types::ToSql> = params.iter().map(|p| p.as_ref()).collect();
     conn.execute(&sql, param_refs.as_slice()).map_err(|e| format!("DB error: {}", e))?;
     crate::sync_share::mark_dirty(&conn, "products");
+    if quantity.is_some() || min_quantity.is_some() { crate::pantry::check_restock(&conn, id); }
     Ok(())
 }
 
@@ -2990,15 +3149,16 @@ pub fn get_expiring_products(days: Option<i64>, db: tauri::State<'_, HanniDb>) -
 // ── Meal Plan commands ──
 
 #[tauri::command]
-pub fn plan_meal(date: String, meal_type: String, recipe_id: i64, notes: Option<String>, db: tauri::State<'_, HanniDb>) -> Result<i64, String> {
+pub fn plan_meal(date: String, meal_type: String, recipe_id: i64, notes: Option<String>, servings: Option<i64>, db: tauri::State<'_, HanniDb>) -> Result<i64, String> {
     let conn = db.conn();
     let now = chrono::Local::now().to_rfc3339();
     conn.execute(
-        "INSERT INTO meal_plan (date, meal_type, recipe_id, notes, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
-        rusqlite::params![date, meal_type, recipe_id, notes.unwrap_or_default(), now],
+        "INSERT INTO meal_plan (date, meal_type, recipe_id, notes, servings, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        rusqlite::params![date, meal_type, recipe_id, notes.unwrap_or_default(), servings.filter(|s| *s > 0), now],
     ).map_err(|e| format!("DB error: {}", e))?;
     let new_id = conn.last_insert_rowid();
     crate::sync_share::mark_dirty(&conn, "meal_plan");
+    crate::commands_shopping::refresh_plan_shopping(&conn, &date);
     Ok(new_id)
 }
 
@@ -3006,7 +3166,7 @@ pub fn plan_meal(date: String, meal_type: String, recipe_id: i64, notes: Option<
 pub fn get_meal_plan(date: String, db: tauri::State<'_, HanniDb>) -> Result<Vec<serde_json::Value>, String> {
     let conn = db.conn();
     let mut stmt = conn.prepare(
-        "SELECT mp.id, mp.date, mp.meal_type, mp.recipe_id, mp.notes, r.name, r.calories
+        "SELECT mp.id, mp.date, mp.meal_type, mp.recipe_id, mp.notes, r.name, r.calories, COALESCE(mp.servings, r.servings, 1)
          FROM meal_plan mp JOIN recipes r ON mp.recipe_id = r.id WHERE mp.date = ?1 ORDER BY CASE mp.meal_type WHEN 'breakfast' THEN 1 WHEN 'lunch' THEN 2 WHEN 'dinner' THEN 3 ELSE 4 END"
     ).map_err(|e| format!("DB error: {}", e))?;
     let rows = stmt.query_map(rusqlite::params![date], |row| {
@@ -3014,7 +3174,7 @@ pub fn get_meal_plan(date: String, db: tauri::State<'_, HanniDb>) -> Result<Vec<
             "id": row.get::<_, i64>(0)?, "date": row.get::<_, String>(1)?,
             "meal_type": row.get::<_, String>(2)?, "recipe_id": row.get::<_, i64>(3)?,
             "notes": row.get::<_, String>(4)?, "recipe_name": row.get::<_, String>(5)?,
-            "calories": row.get::<_, i64>(6)?,
+            "calories": row.get::<_, i64>(6)?, "servings": row.get::<_, i64>(7).unwrap_or(1),
         }))
     }).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
     Ok(rows)
@@ -3023,8 +3183,10 @@ pub fn get_meal_plan(date: String, db: tauri::State<'_, HanniDb>) -> Result<Vec<
 #[tauri::command]
 pub fn delete_meal_plan(id: i64, db: tauri::State<'_, HanniDb>) -> Result<(), String> {
     let conn = db.conn();
+    let date: Option<String> = conn.query_row("SELECT date FROM meal_plan WHERE id=?1", rusqlite::params![id], |r| r.get(0)).ok();
     conn.execute("DELETE FROM meal_plan WHERE id=?1", rusqlite::params![id]).map_err(|e| format!("DB error: {}", e))?;
     crate::sync_share::mark_dirty(&conn, "meal_plan");
+    if let Some(d) = date { crate::commands_shopping::refresh_plan_shopping(&conn, &d); }
     Ok(())
 }
 
@@ -3297,12 +3459,14 @@ pub fn delete_subscription(id: i64, db: tauri::State<'_, HanniDb>) -> Result<(),
 }
 
 #[tauri::command]
-pub fn add_debt(name: String, debt_type: String, amount: f64, interest_rate: Option<f64>, due_date: Option<String>, description: Option<String>, db: tauri::State<'_, HanniDb>) -> Result<i64, String> {
+#[allow(clippy::too_many_arguments)]
+pub fn add_debt(name: String, debt_type: String, amount: f64, interest_rate: Option<f64>, due_date: Option<String>, description: Option<String>, schedule_type: Option<String>, term_months: Option<i64>, start_date: Option<String>, min_payment: Option<f64>, db: tauri::State<'_, HanniDb>) -> Result<i64, String> {
     let conn = db.conn();
     let now = chrono::Local::now().to_rfc3339();
     conn.execute(
-        "INSERT INTO debts (name, type, amount, remaining, interest_rate, due_date, description, created_at) VALUES (?1, ?2, ?3, ?3, ?4, ?5, ?6, ?7)",
-        rusqlite::params![name, debt_type, amount, interest_rate.unwrap_or(0.0), due_date, description.unwrap_or_default(), now],
+        "INSERT INTO debts (name, type, amount, remaining, interest_rate, due_date, description, created_at, schedule_type, term_months, start_date, min_payment) VALUES (?1, ?2, ?3, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        rusqlite::params![name, debt_type, amount, interest_rate.unwrap_or(0.0), due_date, description.unwrap_or_default(), now,
+            schedule_type.unwrap_or_else(|| "none".into()), term_months.unwrap_or(0), start_date, min_payment.unwrap_or(0.0)],
     ).map_err(|e| format!("DB error: {}", e))?;
     Ok(conn.last_insert_rowid())
 }
@@ -3311,9 +3475,9 @@ pub fn add_debt(name: String, debt_type: String, amount: f64, interest_rate: Opt
 pub fn get_debts(db: tauri::State<'_, HanniDb>) -> Result<Vec<serde_json::Value>, String> {
     let conn = db.conn();
     let mut stmt = conn.prepare(
-        "SELECT id, name, type, amount, remaining, interest_rate, due_date, description FROM debts WHERE remaining > 0 ORDER BY due_date NULLS LAST"
+        "SELECT id, name, type, amount, remaining, interest_rate, due_date, description, schedule_type, term_months, start_date, min_payment FROM debts WHERE remaining > 0 ORDER BY due_date NULLS LAST"
     ).map_err(|e| format!("DB error: {}", e))?;
-    let rows = stmt.query_map([], |row| {
+    let mut rows: Vec<serde_json::Value> = stmt.query_map([], |row| {
         let amt: f64 = row.get(3)?;
         let rem: f64 = row.get(4)?;
         let pct = if amt > 0.0 { ((amt - rem) / amt * 100.0).min(100.0) } else { 0.0 };
@@ -3322,8 +3486,14 @@ pub fn get_debts(db: tauri::State<'_, HanniDb>) -> Result<Vec<serde_json::Value>
             "type": row.get::<_, String>(2)?, "amount": amt, "remaining": rem,
             "interest_rate": row.get::<_, f64>(5)?, "due_date": row.get::<_, Option<String>>(6)?,
             "description": row.get::<_, String>(7)?, "paid_percent": format!("{:.0}", pct),
+            "schedule_type": row.get::<_, String>(8)?, "term_months": row.get::<_, i64>(9)?,
+            "start_date": row.get::<_, Option<String>>(10)?, "min_payment": row.get::<_, f64>(11)?,
         }))
     }).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+    for row in rows.iter_mut() {
+        let id = row["id"].as_i64().unwrap_or(0);
+        row["accrued_interest"] = serde_json::json!(crate::commands_debts::accrued_to_date(&conn, id));
+    }
     Ok(rows)
 }
 
@@ -3331,8 +3501,7 @@ pub fn get_debts(db: tauri::State<'_, HanniDb>) -> Result<Vec<serde_json::Value>
 pub fn update_debt(id: i64, pay_amount: Option<f64>, name: Option<String>, remaining: Option<f64>, debt_type: Option<String>, db: tauri::State<'_, HanniDb>) -> Result<(), String> {
     let conn = db.conn();
     if let Some(pay) = pay_amount {
-        conn.execute("UPDATE debts SET remaining = MAX(0, remaining - ?1) WHERE id=?2", rusqlite::params![pay, id])
-            .map_err(|e| format!("DB error: {}", e))?;
+        crate::commands_debts::record_payment(&conn, id, pay, None, "")?;
     }
     let mut updates = Vec::new();
     let mut params: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
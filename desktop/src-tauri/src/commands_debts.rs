@@ -0,0 +1,519 @@
+// commands_debts.rs — Debt amortization schedules, payment history and payoff planning.
+// A debt's `remaining` is outstanding principal; interest accrues daily between
+// payments (ACT/365) and each payment covers accrued interest first.
+// Payoff simulation compares snowball (smallest balance first) and avalanche
+// (highest rate first) with an optional extra monthly amount.
+use chrono::{Datelike, Months, NaiveDate};
+use serde::Serialize;
+
+use crate::types::HanniDb;
+
+/// Cap for month-by-month projections (50 years) — a payment that never
+/// covers the interest would otherwise loop forever.
+const MAX_MONTHS: u32 = 600;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ScheduleRow {
+    pub n: u32,
+    pub date: String,
+    pub payment: f64,
+    pub principal: f64,
+    pub interest: f64,
+    pub remaining: f64,
+}
+
+fn round2(v: f64) -> f64 { (v * 100.0).round() / 100.0 }
+
+fn monthly_rate(annual_pct: f64) -> f64 { annual_pct.max(0.0) / 100.0 / 12.0 }
+
+fn parse_date(s: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(s.get(..10).unwrap_or(s), "%Y-%m-%d").ok()
+}
+
+fn add_months(d: NaiveDate, n: u32) -> NaiveDate {
+    d.checked_add_months(Months::new(n)).unwrap_or(d)
+}
+
+/// Fixed annuity payment for `principal` over `months` at `annual_pct`.
+pub fn annuity_payment(principal: f64, annual_pct: f64, months: u32) -> f64 {
+    if months == 0 { return principal; }
+    let r = monthly_rate(annual_pct);
+    if r == 0.0 { return principal / months as f64; }
+    principal * r / (1.0 - (1.0 + r).powi(-(months as i32)))
+}
+
+/// Month-by-month schedule. `kind` = "annuity" (equal payments) or
+/// "differentiated" (equal principal, falling interest). The last row absorbs
+/// rounding so `remaining` ends at exactly 0.
+pub fn build_schedule(principal: f64, annual_pct: f64, months: u32, kind: &str, first_payment: NaiveDate) -> Vec<ScheduleRow> {
+    let mut rows = Vec::new();
+    if principal <= 0.0 || months == 0 { return rows; }
+    let r = monthly_rate(annual_pct);
+    let annuity = annuity_payment(principal, annual_pct, months);
+    let flat_principal = principal / months as f64;
+    let mut balance = principal;
+    for n in 1..=months {
+        let interest = balance * r;
+        let mut principal_part = if kind == "differentiated" { flat_principal } else { annuity - interest };
+        if n == months || principal_part > balance { principal_part = balance; }
+        balance -= principal_part;
+        rows.push(ScheduleRow {
+            n,
+            date: add_months(first_payment, n - 1).format("%Y-%m-%d").to_string(),
+            payment: round2(principal_part + interest),
+            principal: round2(principal_part),
+            interest: round2(interest),
+            remaining: round2(balance.max(0.0)),
+        });
+    }
+    rows
+}
+
+/// Simple daily interest (ACT/365) on `balance` between two dates.
+pub fn accrued_interest(balance: f64, annual_pct: f64, from: NaiveDate, to: NaiveDate) -> f64 {
+    let days = (to - from).num_days().max(0) as f64;
+    balance.max(0.0) * annual_pct.max(0.0) / 100.0 * days / 365.0
+}
+
+#[derive(Clone, Debug)]
+pub struct SimDebt {
+    pub id: i64,
+    pub name: String,
+    pub balance: f64,
+    pub rate: f64,
+    pub min_payment: f64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DebtPayoff {
+    pub id: i64,
+    pub name: String,
+    pub months: u32,
+    pub payoff_date: Option<String>,
+    pub interest: f64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct PayoffPlan {
+    pub strategy: String,
+    pub months: u32,
+    pub payoff_date: Option<String>,
+    pub total_interest: f64,
+    pub total_paid: f64,
+    pub monthly_budget: f64,
+    /// false when the budget never outpaces interest (capped at MAX_MONTHS).
+    pub feasible: bool,
+    pub debts: Vec<DebtPayoff>,
+}
+
+/// Simulate paying every debt down month by month. The monthly budget is the
+/// sum of all minimums plus `extra`, held constant — once a debt is cleared its
+/// minimum rolls over to the next target, which is what makes snowball and
+/// avalanche accelerate. Targets: "snowball" = smallest balance first,
+/// "avalanche" = highest rate first.
+pub fn simulate_payoff(debts: &[SimDebt], strategy: &str, extra: f64, start: NaiveDate) -> PayoffPlan {
+    let budget: f64 = debts.iter().map(|d| d.min_payment).sum::<f64>() + extra.max(0.0);
+    let mut bal: Vec<f64> = debts.iter().map(|d| d.balance.max(0.0)).collect();
+    let mut interest: Vec<f64> = vec![0.0; debts.len()];
+    let mut done_at: Vec<Option<u32>> = bal.iter().map(|b| if *b <= 0.005 { Some(0) } else { None }).collect();
+    let mut total_paid = 0.0;
+    let mut month = 0u32;
+
+    while done_at.iter().any(|d| d.is_none()) && month < MAX_MONTHS {
+        month += 1;
+        for (i, d) in debts.iter().enumerate() {
+            if done_at[i].is_some() { continue; }
+            let accrued = bal[i] * monthly_rate(d.rate);
+            bal[i] += accrued;
+            interest[i] += accrued;
+        }
+        let mut pool = budget;
+        for (i, d) in debts.iter().enumerate() {
+            if done_at[i].is_some() { continue; }
+            let pay = d.min_payment.min(bal[i]).min(pool);
+            bal[i] -= pay;
+            pool -= pay;
+            total_paid += pay;
+        }
+        while pool > 0.005 {
+            let open = (0..debts.len()).filter(|&i| done_at[i].is_none() && bal[i] > 0.005);
+            let target = if strategy == "avalanche" {
+                open.max_by(|&a, &b| debts[a].rate.partial_cmp(&debts[b].rate).unwrap_or(std::cmp::Ordering::Equal)
+                    .then(bal[b].partial_cmp(&bal[a]).unwrap_or(std::cmp::Ordering::Equal)))
+            } else {
+                open.min_by(|&a, &b| bal[a].partial_cmp(&bal[b]).unwrap_or(std::cmp::Ordering::Equal))
+            };
+            let Some(t) = target else { break };
+            let pay = pool.min(bal[t]);
+            bal[t] -= pay;
+            pool -= pay;
+            total_paid += pay;
+        }
+        for i in 0..debts.len() {
+            if done_at[i].is_none() && bal[i] <= 0.005 { done_at[i] = Some(month); }
+        }
+    }
+
+    let feasible = done_at.iter().all(|d| d.is_some());
+    let date_of = |m: u32| if m == 0 { start } else { add_months(start, m - 1) };
+    PayoffPlan {
+        strategy: strategy.to_string(),
+        months: if feasible { month } else { MAX_MONTHS },
+        payoff_date: if feasible { Some(date_of(month).format("%Y-%m-%d").to_string()) } else { None },
+        total_interest: round2(interest.iter().sum()),
+        total_paid: round2(total_paid),
+        monthly_budget: round2(budget),
+        feasible,
+        debts: debts.iter().enumerate().map(|(i, d)| DebtPayoff {
+            id: d.id, name: d.name.clone(),
+            months: done_at[i].unwrap_or(MAX_MONTHS),
+            payoff_date: done_at[i].map(|m| date_of(m).format("%Y-%m-%d").to_string()),
+            interest: round2(interest[i]),
+        }).collect(),
+    }
+}
+
+struct DebtTerms {
+    amount: f64,
+    remaining: f64,
+    rate: f64,
+    schedule_type: String,
+    term_months: i64,
+    start_date: Option<String>,
+    min_payment: f64,
+    accrual_from: Option<String>,
+}
+
+fn load_terms(conn: &rusqlite::Connection, id: i64) -> Result<DebtTerms, String> {
+    conn.query_row(
+        "SELECT amount, remaining, COALESCE(interest_rate,0), schedule_type, term_months, start_date, min_payment,
+                COALESCE(last_accrual_date, substr(created_at,1,10))
+         FROM debts WHERE id=?1",
+        rusqlite::params![id],
+        |r| Ok(DebtTerms {
+            amount: r.get(0)?, remaining: r.get(1)?, rate: r.get(2)?, schedule_type: r.get(3)?,
+            term_months: r.get(4)?, start_date: r.get(5)?, min_payment: r.get(6)?, accrual_from: r.get(7)?,
+        }),
+    ).map_err(|e| format!("Debt not found: {}", e))
+}
+
+/// Months left on the original term, counted from the first payment date.
+fn months_left(t: &DebtTerms, today: NaiveDate) -> u32 {
+    if t.term_months <= 0 { return 0; }
+    let start = t.start_date.as_deref().and_then(parse_date).unwrap_or(today);
+    let elapsed = if today > start {
+        (today.year() - start.year()) * 12 + today.month() as i32 - start.month() as i32
+    } else { 0 };
+    (t.term_months as i32 - elapsed.max(0)).max(1) as u32
+}
+
+/// Minimum monthly payment used by the simulator: the explicit `min_payment`,
+/// else the annuity payment over the rest of the term, else interest + 3% of
+/// the balance (typical card-style minimum).
+fn effective_min_payment(t: &DebtTerms, today: NaiveDate) -> f64 {
+    if t.min_payment > 0.0 { return t.min_payment; }
+    let left = months_left(t, today);
+    if left > 0 { return annuity_payment(t.remaining, t.rate, left); }
+    t.remaining * monthly_rate(t.rate) + t.remaining * 0.03
+}
+
+/// Record a payment: accrue interest since the last accrual date, cover it
+/// first, put the rest into principal. Shared by `pay_debt` and the legacy
+/// `update_debt(pay_amount)` path so every payment lands in the history.
+pub(crate) fn record_payment(conn: &rusqlite::Connection, debt_id: i64, amount: f64, date: Option<&str>, notes: &str) -> Result<serde_json::Value, String> {
+    if amount <= 0.0 { return Err("Сумма платежа должна быть больше 0".into()); }
+    let t = load_terms(conn, debt_id)?;
+    let today = chrono::Local::now().date_naive();
+    let pay_date = date.and_then(parse_date).unwrap_or(today);
+    let from = t.accrual_from.as_deref().and_then(parse_date).unwrap_or(pay_date);
+    let interest = round2(accrued_interest(t.remaining, t.rate, from, pay_date));
+    // A backdated payment accrues nothing and must not rewind the accrual date,
+    // or the same days would be charged again on the next payment.
+    let accrued_through = pay_date.max(from);
+    let interest_paid = interest.min(amount);
+    let principal = round2((amount - interest_paid).min(t.remaining));
+    // Unpaid interest capitalises into the balance rather than being dropped.
+    let remaining = round2((t.remaining + (interest - interest_paid) - principal).max(0.0));
+    let date_s = pay_date.format("%Y-%m-%d").to_string();
+    let now = chrono::Local::now().to_rfc3339();
+    // Pre-payment state, so the latest payment can be undone exactly
+    let accrual_before: Option<String> = conn.query_row(
+        "SELECT last_accrual_date FROM debts WHERE id=?1", rusqlite::params![debt_id], |r| r.get(0),
+    ).map_err(|e| format!("DB error: {}", e))?;
+    conn.execute(
+        "INSERT INTO debt_payments (debt_id, date, amount, interest, principal, remaining_after, notes, created_at, remaining_before, accrual_before)
+         VALUES (?1,?2,?3,?4,?5,?6,?7,?8,?9,?10)",
+        rusqlite::params![debt_id, date_s, amount, interest_paid, principal, remaining, notes, now, t.remaining, accrual_before],
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let payment_id = conn.last_insert_rowid();
+    conn.execute(
+        "UPDATE debts SET remaining=?1, last_accrual_date=?2 WHERE id=?3",
+        rusqlite::params![remaining, accrued_through.format("%Y-%m-%d").to_string(), debt_id],
+    ).map_err(|e| format!("DB error: {}", e))?;
+    Ok(serde_json::json!({
+        "id": payment_id, "debt_id": debt_id, "date": date_s, "amount": amount,
+        "interest": interest_paid, "principal": principal, "remaining": remaining,
+    }))
+}
+
+/// Interest accrued on the current balance since the last payment (or start).
+pub(crate) fn accrued_to_date(conn: &rusqlite::Connection, debt_id: i64) -> f64 {
+    let Ok(t) = load_terms(conn, debt_id) else { return 0.0 };
+    let today = chrono::Local::now().date_naive();
+    let from = t.accrual_from.as_deref().and_then(parse_date).unwrap_or(today);
+    round2(accrued_interest(t.remaining, t.rate, from, today))
+}
+
+#[tauri::command]
+pub fn set_debt_terms(
+    id: i64, schedule_type: Option<String>, term_months: Option<i64>, start_date: Option<String>,
+    min_payment: Option<f64>, interest_rate: Option<f64>, db: tauri::State<'_, HanniDb>,
+) -> Result<(), String> {
+    if let Some(k) = schedule_type.as_deref() {
+        if !["none", "annuity", "differentiated"].contains(&k) {
+            return Err(format!("Unknown schedule type: {}", k));
+        }
+    }
+    let conn = db.conn();
+    let mut updates = Vec::new();
+    let mut params: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
+    let mut idx = 1;
+    if let Some(v) = schedule_type { updates.push(format!("schedule_type=?{}", idx)); params.push(Box::new(v)); idx += 1; }
+    if let Some(v) = term_months { updates.push(format!("term_months=?{}", idx)); params.push(Box::new(v.max(0))); idx += 1; }
+    if let Some(v) = start_date { updates.push(format!("start_date=?{}", idx)); params.push(Box::new(v)); idx += 1; }
+    if let Some(v) = min_payment { updates.push(format!("min_payment=?{}", idx)); params.push(Box::new(v.max(0.0))); idx += 1; }
+    if let Some(v) = interest_rate { updates.push(format!("interest_rate=?{}", idx)); params.push(Box::new(v.max(0.0))); idx += 1; }
+    if updates.is_empty() { return Ok(()); }
+    params.push(Box::new(id));
+    let sql = format!("UPDATE debts SET {} WHERE id=?{}", updates.join(","), idx);
+    let param_refs: Vec<&dyn rusqlite::types::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+    conn.execute(&sql, param_refs.as_slice()).map_err(|e| format!("DB error: {}", e))?;
+    Ok(())
+}
+
+/// Amortization schedule. Without `from_remaining` it is the original plan
+/// (full amount over the full term from `start_date`); with it, the rest of the
+/// term is re-amortized from today's balance.
+#[tauri::command]
+pub fn get_debt_schedule(id: i64, from_remaining: Option<bool>, db: tauri::State<'_, HanniDb>) -> Result<serde_json::Value, String> {
+    let conn = db.read();
+    let t = load_terms(&conn, id)?;
+    if t.term_months <= 0 || t.schedule_type == "none" {
+        return Err("У долга не задан срок и тип графика".into());
+    }
+    let today = chrono::Local::now().date_naive();
+    let start = t.start_date.as_deref().and_then(parse_date).unwrap_or(today);
+    let rows = if from_remaining.unwrap_or(false) {
+        let left = months_left(&t, today);
+        let next = if start > today { start } else { add_months(start, (t.term_months as u32).saturating_sub(left)) };
+        build_schedule(t.remaining, t.rate, left, &t.schedule_type, next)
+    } else {
+        build_schedule(t.amount, t.rate, t.term_months as u32, &t.schedule_type, start)
+    };
+    let total_interest: f64 = rows.iter().map(|r| r.interest).sum();
+    let total_paid: f64 = rows.iter().map(|r| r.payment).sum();
+    Ok(serde_json::json!({
+        "debt_id": id, "schedule_type": t.schedule_type, "rows": rows,
+        "total_interest": round2(total_interest), "total_paid": round2(total_paid),
+    }))
+}
+
+#[tauri::command]
+pub fn pay_debt(id: i64, amount: f64, date: Option<String>, notes: Option<String>, db: tauri::State<'_, HanniDb>) -> Result<serde_json::Value, String> {
+    let conn = db.conn();
+    record_payment(&conn, id, amount, date.as_deref(), &notes.unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn get_debt_payments(debt_id: i64, db: tauri::State<'_, HanniDb>) -> Result<Vec<serde_json::Value>, String> {
+    let conn = db.read();
+    let mut stmt = conn.prepare(
+        "SELECT id, date, amount, interest, principal, remaining_after, notes FROM debt_payments WHERE debt_id=?1 ORDER BY date DESC, id DESC"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let rows = stmt.query_map(rusqlite::params![debt_id], |r| Ok(serde_json::json!({
+        "id": r.get::<_, i64>(0)?, "date": r.get::<_, String>(1)?, "amount": r.get::<_, f64>(2)?,
+        "interest": r.get::<_, f64>(3)?, "principal": r.get::<_, f64>(4)?,
+        "remaining": r.get::<_, f64>(5)?, "notes": r.get::<_, String>(6)?,
+    }))).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+    Ok(rows)
+}
+
+/// Undo the latest payment of its debt: the balance goes back by what the
+/// payment changed it (interest it capitalised included) and accrual restarts
+/// from where it did before. Earlier payments can't be removed — later ones
+/// were split against the balance they left.
+pub(crate) fn undo_payment(conn: &rusqlite::Connection, id: i64) -> Result<(), String> {
+    let tx = conn.unchecked_transaction().map_err(|e| format!("DB error: {}", e))?;
+    let (debt_id, principal, remaining_after, remaining_before, accrual_before): (i64, f64, f64, Option<f64>, Option<String>) = tx.query_row(
+        "SELECT debt_id, principal, remaining_after, remaining_before, accrual_before FROM debt_payments WHERE id=?1", rusqlite::params![id],
+        |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?)),
+    ).map_err(|e| format!("Payment not found: {}", e))?;
+    let latest: i64 = tx.query_row(
+        "SELECT MAX(id) FROM debt_payments WHERE debt_id=?1", rusqlite::params![debt_id], |r| r.get(0),
+    ).map_err(|e| format!("DB error: {}", e))?;
+    if latest != id { return Err("Удалить можно только последний платёж по долгу".into()); }
+    match remaining_before {
+        // Relative to the current balance, so a manual edit made since survives
+        Some(before) => tx.execute(
+            "UPDATE debts SET remaining = ROUND(remaining - ?1 + ?2, 2), last_accrual_date=?3 WHERE id=?4",
+            rusqlite::params![remaining_after, before, accrual_before, debt_id],
+        ),
+        // Rows recorded before the pre-payment state was kept: principal only,
+        // accrual from the previous payment
+        None => tx.execute(
+            "UPDATE debts SET remaining = ROUND(remaining + ?1, 2),
+                last_accrual_date=(SELECT MAX(date) FROM debt_payments WHERE debt_id=?2 AND id != ?3) WHERE id=?2",
+            rusqlite::params![principal, debt_id, id],
+        ),
+    }.map_err(|e| format!("DB error: {}", e))?;
+    tx.execute("DELETE FROM debt_payments WHERE id=?1", rusqlite::params![id])
+        .map_err(|e| format!("DB error: {}", e))?;
+    tx.commit().map_err(|e| format!("DB error: {}", e))
+}
+
+#[tauri::command]
+pub fn delete_debt_payment(id: i64, db: tauri::State<'_, HanniDb>) -> Result<(), String> {
+    undo_payment(&db.conn(), id)
+}
+
+/// Compare snowball vs avalanche over all open debts the user owes.
+/// `strategy` limits the output to one plan; default returns both plus the
+/// cheaper one as `recommended`.
+#[tauri::command]
+pub fn simulate_debt_payoff(extra_monthly: Option<f64>, strategy: Option<String>, db: tauri::State<'_, HanniDb>) -> Result<serde_json::Value, String> {
+    let conn = db.read();
+    let today = chrono::Local::now().date_naive();
+    let ids: Vec<(i64, String)> = conn.prepare("SELECT id, name FROM debts WHERE remaining > 0 AND type='owe' ORDER BY id")
+        .and_then(|mut s| { let r: Vec<(i64, String)> = s.query_map([], |r| Ok((r.get(0)?, r.get(1)?)))?.flatten().collect(); Ok(r) })
+        .map_err(|e| format!("DB error: {}", e))?;
+    let mut debts = Vec::new();
+    for (id, name) in ids {
+        let t = load_terms(&conn, id)?;
+        // Interest already accrued since the last payment is owed too.
+        let from = t.accrual_from.as_deref().and_then(parse_date).unwrap_or(today);
+        let balance = t.remaining + accrued_interest(t.remaining, t.rate, from, today);
+        debts.push(SimDebt { id, name, balance, rate: t.rate, min_payment: round2(effective_min_payment(&t, today)) });
+    }
+    if debts.is_empty() { return Ok(serde_json::json!({ "debts": 0, "plans": [] })); }
+    let extra = extra_monthly.unwrap_or(0.0);
+    let first = add_months(today.with_day(1).unwrap_or(today), 1);
+    let strategies: Vec<&str> = match strategy.as_deref() {
+        Some("snowball") => vec!["snowball"],
+        Some("avalanche") => vec!["avalanche"],
+        _ => vec!["snowball", "avalanche"],
+    };
+    let plans: Vec<PayoffPlan> = strategies.iter().map(|s| simulate_payoff(&debts, s, extra, first)).collect();
+    let recommended = plans.iter()
+        .filter(|p| p.feasible)
+        .min_by(|a, b| a.total_interest.partial_cmp(&b.total_interest).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|p| p.strategy.clone());
+    Ok(serde_json::json!({
+        "debts": debts.len(), "extra_monthly": extra, "plans": plans, "recommended": recommended,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::d;
+
+    #[test]
+    fn annuity_schedule_pays_off_exactly() {
+        let rows = build_schedule(100_000.0, 12.0, 12, "annuity", d("2026-01-15"));
+        assert_eq!(rows.len(), 12);
+        assert_eq!(rows.last().unwrap().remaining, 0.0);
+        // 100k at 1%/month over 12 months → 8884.88 per month
+        assert!((rows[0].payment - 8884.88).abs() < 0.01);
+        assert_eq!(rows[11].date, "2026-12-15");
+        let principal: f64 = rows.iter().map(|r| r.principal).sum();
+        assert!((principal - 100_000.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn differentiated_payments_decrease() {
+        let rows = build_schedule(1200.0, 12.0, 12, "differentiated", d("2026-01-31"));
+        assert!((rows[0].payment - 112.0).abs() < 0.01); // 100 principal + 12 interest
+        assert!(rows.windows(2).all(|w| w[1].payment < w[0].payment));
+        assert_eq!(rows[1].date, "2026-02-28"); // month-end clamps
+    }
+
+    #[test]
+    fn avalanche_never_costs_more_interest_than_snowball() {
+        let debts = vec![
+            SimDebt { id: 1, name: "card".into(), balance: 5000.0, rate: 30.0, min_payment: 150.0 },
+            SimDebt { id: 2, name: "friend".into(), balance: 800.0, rate: 0.0, min_payment: 50.0 },
+            SimDebt { id: 3, name: "car".into(), balance: 12000.0, rate: 9.0, min_payment: 300.0 },
+        ];
+        let start = d("2026-02-01");
+        let snow = simulate_payoff(&debts, "snowball", 200.0, start);
+        let aval = simulate_payoff(&debts, "avalanche", 200.0, start);
+        assert!(snow.feasible && aval.feasible);
+        assert!(aval.total_interest <= snow.total_interest);
+        // snowball clears the smallest balance first
+        let first_snow = snow.debts.iter().min_by_key(|p| p.months).unwrap();
+        assert_eq!(first_snow.id, 2);
+        let no_extra = simulate_payoff(&debts, "avalanche", 0.0, start);
+        assert!(no_extra.months > aval.months);
+    }
+
+    #[test]
+    fn undoing_the_latest_payment_restores_the_debt() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE debts (id INTEGER PRIMARY KEY, amount REAL, remaining REAL, interest_rate REAL, schedule_type TEXT DEFAULT 'none',
+                term_months INTEGER DEFAULT 0, start_date TEXT, min_payment REAL DEFAULT 0, last_accrual_date TEXT, created_at TEXT);
+             CREATE TABLE debt_payments (id INTEGER PRIMARY KEY AUTOINCREMENT, debt_id INTEGER, date TEXT, amount REAL, interest REAL,
+                principal REAL, remaining_after REAL, notes TEXT, created_at TEXT, remaining_before REAL, accrual_before TEXT);
+             INSERT INTO debts (id, amount, remaining, interest_rate, created_at) VALUES (1, 100000, 100000, 24, '2026-01-01T10:00:00+03:00');"
+        ).unwrap();
+        let state = || conn.query_row("SELECT remaining, last_accrual_date FROM debts WHERE id=1", [], |r| Ok((r.get::<_, f64>(0)?, r.get::<_, Option<String>>(1)?))).unwrap();
+        let strip = |mut v: serde_json::Value| { v["id"] = serde_json::Value::Null; v };
+
+        let first = record_payment(&conn, 1, 5000.0, Some("2026-02-01"), "").unwrap();
+        let after_first = state();
+        // Less than a month's interest: the unpaid part capitalises.
+        let small = record_payment(&conn, 1, 100.0, Some("2026-03-01"), "").unwrap();
+        assert!(small["remaining"].as_f64().unwrap() > after_first.0);
+        assert!(undo_payment(&conn, first["id"].as_i64().unwrap()).is_err(), "only the latest one");
+
+        undo_payment(&conn, small["id"].as_i64().unwrap()).unwrap();
+        assert_eq!(state(), after_first);
+        let again = record_payment(&conn, 1, 100.0, Some("2026-03-01"), "").unwrap();
+        assert_eq!(strip(again.clone()), strip(small));
+
+        undo_payment(&conn, again["id"].as_i64().unwrap()).unwrap();
+        undo_payment(&conn, first["id"].as_i64().unwrap()).unwrap();
+        assert_eq!(state(), (100000.0, None));
+        assert_eq!(strip(record_payment(&conn, 1, 5000.0, Some("2026-02-01"), "").unwrap()), strip(first));
+    }
+
+    #[test]
+    fn backdated_payment_does_not_rewind_accrual() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE debts (id INTEGER PRIMARY KEY, amount REAL, remaining REAL, interest_rate REAL, schedule_type TEXT DEFAULT 'none',
+                term_months INTEGER DEFAULT 0, start_date TEXT, min_payment REAL DEFAULT 0, last_accrual_date TEXT, created_at TEXT);
+             CREATE TABLE debt_payments (id INTEGER PRIMARY KEY AUTOINCREMENT, debt_id INTEGER, date TEXT, amount REAL, interest REAL,
+                principal REAL, remaining_after REAL, notes TEXT, created_at TEXT, remaining_before REAL, accrual_before TEXT);
+             INSERT INTO debts (id, amount, remaining, interest_rate, created_at) VALUES (1, 36500, 36500, 10, '2026-01-01T10:00:00+03:00');"
+        ).unwrap();
+        record_payment(&conn, 1, 1000.0, Some("2026-03-01"), "").unwrap();
+        // Entered late for February: all principal, accrual stays at March 1.
+        let late = record_payment(&conn, 1, 1000.0, Some("2026-02-10"), "").unwrap();
+        assert_eq!((late["interest"].as_f64(), late["principal"].as_f64()), (Some(0.0), Some(1000.0)));
+        let accrual: String = conn.query_row("SELECT last_accrual_date FROM debts WHERE id=1", [], |r| r.get(0)).unwrap();
+        assert_eq!(accrual, "2026-03-01");
+        // The next payment accrues only the ten days since March 1.
+        let next = record_payment(&conn, 1, 1000.0, Some("2026-03-11"), "").unwrap();
+        let balance = late["remaining"].as_f64().unwrap();
+        assert_eq!(next["interest"].as_f64().unwrap(), round2(balance * 0.10 * 10.0 / 365.0));
+    }
+
+    #[test]
+    fn payment_below_interest_is_infeasible() {
+        let debts = vec![SimDebt { id: 1, name: "x".into(), balance: 10000.0, rate: 24.0, min_payment: 100.0 }];
+        let plan = simulate_payoff(&debts, "snowball", 0.0, d("2026-01-01"));
+        assert!(!plan.feasible);
+        assert!(plan.payoff_date.is_none());
+    }
+}
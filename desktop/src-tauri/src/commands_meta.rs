@@ -1,7 +1,6 @@
 // commands_meta.rs — Mindset, blocklist, goals, settings, home, contacts, properties, views, integrations, model info, health check, updater, HTTP API, focus
 use crate::types::*;
 use crate::chat::chat_inner;
-use crate::macos::run_osascript;
 use crate::commands_data::load_tracker_data;
 use serde::Deserialize;
 use tauri::{AppHandle, Emitter, Manager};
@@ -23,19 +22,8 @@ pub fn auto_eval_callback(cb_id: String, result: String, state: tauri::State<'_,
 
 // ── Focus Commands ──
 
-#[tauri::command]
-pub fn start_focus(
-    duration_minutes: u64,
-    apps: Option<Vec<String>>,
-    sites: Option<Vec<String>>,
-    focus: tauri::State<'_, FocusManager>,
-) -> Result<String, String> {
-    let mut state = focus.0.lock().unwrap_or_else(|e| e.into_inner());
-
-    if state.active {
-        return Err("Focus mode is already active".into());
-    }
-
+/// Apps/sites to block: explicit lists win, then blocker_config.json, then built-in defaults.
+pub(crate) fn blocker_targets(apps: Option<Vec<String>>, sites: Option<Vec<String>>) -> (Vec<String>, Vec<String>) {
     // Load default config if not provided
     let blocker_config_path = hanni_data_dir().join("blocker_config.json");
 
@@ -74,49 +62,23 @@ pub fn start_focus(
         }
     });
 
-    // Sanitize site names — only allow valid hostname chars
-    let safe_site = |s: &str| -> String {
-        s.chars().filter(|c| c.is_ascii_alphanumeric() || *c == '.' || *c == '-').collect()
-    };
-    // Build hosts entries
-    let mut hosts_entries = String::new();
-    for site in &block_sites {
-        let s = safe_site(site);
-        if s.is_empty() { continue; }
-        hosts_entries.push_str(&format!("127.0.0.1 {}\n127.0.0.1 www.{}\n", s, s));
-    }
-
-    // Write to /etc/hosts using osascript for sudo
-    let hosts_block = format!(
-        "# === HANNI FOCUS BLOCKER ===\n{}# === END HANNI FOCUS BLOCKER ===",
-        hosts_entries
-    );
-
-    let script = format!(
-        "do shell script \"printf '\\n{}' >> /etc/hosts && dscacheutil -flushcache && killall -HUP mDNSResponder\" with administrator privileges",
-        hosts_block.replace("'", "'\\''").replace("\n", "\\n")
-    );
-    run_osascript(&script).map_err(|e| format!("Failed to set focus mode (admin needed): {}", e))?;
+    (block_apps, block_sites)
+}
 
-    // Quit blocked apps — sanitize names to prevent AppleScript injection
-    let safe_app = |s: &str| -> String {
-        s.chars().filter(|c| c.is_ascii_alphanumeric() || *c == ' ' || *c == '.').collect()
-    };
-    for app_name in &block_apps {
-        let name = safe_app(app_name);
-        if name.is_empty() { continue; }
-        let _ = run_osascript(&format!(
-            "tell application \"System Events\"\nif (name of processes) contains \"{}\" then\ntell application \"{}\" to quit\nend if\nend tell",
-            name, name
-        ));
-    }
+#[tauri::command]
+pub fn start_focus(
+    duration_minutes: u64,
+    apps: Option<Vec<String>>,
+    sites: Option<Vec<String>>,
+    focus: tauri::State<'_, FocusManager>,
+) -> Result<String, String> {
+    let (block_apps, block_sites) = blocker_targets(apps, sites);
 
+    // Block sites + quit apps through the platform enforcer (hosts helper on Linux, osascript on macOS)
     let end_time = chrono::Local::now() + chrono::Duration::minutes(duration_minutes as i64);
-    state.active = true;
-    state.end_time = Some(end_time);
-    state.blocked_apps = block_apps;
-    state.blocked_sites = block_sites;
-    state.monitor_running.store(true, Ordering::Relaxed);
+    if !crate::focus_enforcer::start(&focus, block_apps, block_sites, Some(end_time), false)? {
+        return Err("Focus mode is already active".into());
+    }
 
     Ok(format!("Focus mode started for {} minutes", duration_minutes))
 }
@@ -129,15 +91,7 @@ pub fn stop_focus(focus: tauri::State<'_, FocusManager>) -> Result<String, Strin
         return Ok("Focus mode is not active".into());
     }
 
-    // Remove HANNI FOCUS BLOCKER section from /etc/hosts
-    let script = "do shell script \"sed -i '' '/# === HANNI FOCUS BLOCKER ===/,/# === END HANNI FOCUS BLOCKER ===/d' /etc/hosts && dscacheutil -flushcache && killall -HUP mDNSResponder\" with administrator privileges";
-    let _ = run_osascript(script);
-
-    state.active = false;
-    state.end_time = None;
-    state.blocked_apps.clear();
-    state.blocked_sites.clear();
-    state.monitor_running.store(false, Ordering::Relaxed);
+    crate::focus_enforcer::end(&mut state);
 
     Ok("Focus mode stopped".into())
 }
@@ -194,6 +148,11 @@ pub fn update_blocklist(apps: Option<Vec<String>>, sites: Option<Vec<String>>) -
 
 #[tauri::command]
 pub fn add_to_blocklist(block_type: String, value: String, schedule: Option<String>, db: tauri::State<'_, HanniDb>) -> Result<i64, String> {
+    let schedule = schedule.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    if let Some(s) = &schedule { crate::focus_enforcer::parse_schedule(s)?; }
+    if block_type == "app" && !crate::focus_enforcer::valid_app_name(&value) {
+        return Err("Название приложения слишком короткое — минимум 3 символа".into());
+    }
     let conn = db.conn();
     let now = chrono::Local::now().to_rfc3339();
     conn.execute(
@@ -226,6 +185,17 @@ pub fn get_blocklist(db: tauri::State<'_, HanniDb>) -> Result<Vec<serde_json::Va
     Ok(rows)
 }
 
+/// Schedule like `mon-fri 09:00-12:00; sat 10:00-11:00`; empty clears it
+/// (item is then only blocked during manual focus sessions).
+#[tauri::command]
+pub fn set_blocklist_schedule(id: i64, schedule: Option<String>, db: tauri::State<'_, HanniDb>) -> Result<(), String> {
+    let schedule = schedule.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    if let Some(s) = &schedule { crate::focus_enforcer::parse_schedule(s)?; }
+    db.conn().execute("UPDATE blocklist SET schedule=?1 WHERE id=?2", rusqlite::params![schedule, id])
+        .map_err(|e| format!("DB error: {}", e))?;
+    Ok(())
+}
+
 #[tauri::command]
 pub fn toggle_blocklist_item(id: i64, db: tauri::State<'_, HanniDb>) -> Result<(), String> {
     let conn = db.conn();
@@ -849,9 +819,7 @@ pub async fn get_integrations() -> Result<IntegrationsInfo, String> {
     };
 
     // Check if blocking is active via /etc/hosts
-    let blocker_active = std::fs::read_to_string("/etc/hosts")
-        .map(|c| c.contains("# === HANNI FOCUS BLOCKER ==="))
-        .unwrap_or(false);
+    let blocker_active = crate::focus_enforcer::hosts_has_block();
 
     let blocked_apps = apps.iter().map(|a| IntegrationItem {
         name: a.clone(),
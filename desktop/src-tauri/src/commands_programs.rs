@@ -1,6 +1,7 @@
 // commands_programs.rs — Multi-day workout programs (monthly / split / muscle-focus / warmup).
 // A program references existing workout_templates per cycle day; a run tracks progress.
-// Starting a program-day reuses make_workout_from_template, so it logs like a manual start.
+// Starting a program-day reuses make_workout_from_template, so it logs like a manual start;
+// the run id is stamped on those workouts so completing the day doesn't log them twice.
 use crate::types::HanniDb;
 
 fn insert_days(conn: &rusqlite::Connection, program_id: i64, days: &[serde_json::Value]) {
@@ -28,17 +29,20 @@ fn program_list_row(r: &rusqlite::Row) -> Result<serde_json::Value, rusqlite::Er
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub fn create_workout_program(
     name: String, kind: Option<String>, cycle_length_days: Option<i64>, duration_weeks: Option<i64>,
     target_muscle_groups: Option<String>, notes: Option<String>, days: Option<Vec<serde_json::Value>>,
+    deload_every_weeks: Option<i64>, deload_pct: Option<f64>, week_percents: Option<String>,
     db: tauri::State<'_, HanniDb>,
 ) -> Result<i64, String> {
     let conn = db.conn();
     let now = chrono::Local::now().to_rfc3339();
     conn.execute(
-        "INSERT INTO workout_programs (name, kind, cycle_length_days, duration_weeks, target_muscle_groups, notes, created_at, updated_at) VALUES (?1,?2,?3,?4,?5,?6,?7,?7)",
+        "INSERT INTO workout_programs (name, kind, cycle_length_days, duration_weeks, target_muscle_groups, notes, created_at, updated_at, deload_every_weeks, deload_pct, week_percents) VALUES (?1,?2,?3,?4,?5,?6,?7,?7,?8,?9,?10)",
         rusqlite::params![name, kind.unwrap_or_else(|| "custom".into()), cycle_length_days.unwrap_or(7),
-            duration_weeks.unwrap_or(0), target_muscle_groups.unwrap_or_default(), notes.unwrap_or_default(), now],
+            duration_weeks.unwrap_or(0), target_muscle_groups.unwrap_or_default(), notes.unwrap_or_default(), now,
+            deload_every_weeks.unwrap_or(0).max(0), deload_pct.unwrap_or(0.6).clamp(0.1, 1.0), week_percents.unwrap_or_default()],
     ).map_err(|e| format!("DB error: {}", e))?;
     let pid = conn.last_insert_rowid();
     if let Some(ds) = days { insert_days(&conn, pid, &ds); }
@@ -46,10 +50,12 @@ pub fn create_workout_program(
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub fn update_workout_program(
     id: i64, name: Option<String>, kind: Option<String>, cycle_length_days: Option<i64>,
     duration_weeks: Option<i64>, target_muscle_groups: Option<String>, notes: Option<String>,
-    days: Option<Vec<serde_json::Value>>, db: tauri::State<'_, HanniDb>,
+    days: Option<Vec<serde_json::Value>>, deload_every_weeks: Option<i64>, deload_pct: Option<f64>,
+    week_percents: Option<String>, db: tauri::State<'_, HanniDb>,
 ) -> Result<(), String> {
     let conn = db.conn();
     let mut sets = Vec::new();
@@ -61,6 +67,9 @@ pub fn update_workout_program(
     if let Some(v) = duration_weeks { sets.push(format!("duration_weeks=?{}", idx)); params.push(Box::new(v)); idx += 1; }
     if let Some(v) = target_muscle_groups { sets.push(format!("target_muscle_groups=?{}", idx)); params.push(Box::new(v)); idx += 1; }
     if let Some(v) = notes { sets.push(format!("notes=?{}", idx)); params.push(Box::new(v)); idx += 1; }
+    if let Some(v) = deload_every_weeks { sets.push(format!("deload_every_weeks=?{}", idx)); params.push(Box::new(v.max(0))); idx += 1; }
+    if let Some(v) = deload_pct { sets.push(format!("deload_pct=?{}", idx)); params.push(Box::new(v.clamp(0.1, 1.0))); idx += 1; }
+    if let Some(v) = week_percents { sets.push(format!("week_percents=?{}", idx)); params.push(Box::new(v)); idx += 1; }
     sets.push(format!("updated_at=?{}", idx)); params.push(Box::new(chrono::Local::now().to_rfc3339())); idx += 1;
     params.push(Box::new(id));
     let sql = format!("UPDATE workout_programs SET {} WHERE id=?{}", sets.join(","), idx);
@@ -70,6 +79,12 @@ pub fn update_workout_program(
         conn.execute("DELETE FROM program_days WHERE program_id=?1", rusqlite::params![id]).ok();
         insert_days(&conn, id, &ds);
     }
+    // Days / cycle / load changed → republish the active run's sessions.
+    let run: Option<i64> = conn.query_row(
+        "SELECT id FROM program_runs WHERE program_id=?1 AND status='active' ORDER BY id DESC LIMIT 1",
+        rusqlite::params![id], |r| r.get(0),
+    ).ok();
+    if let Some(rid) = run { crate::program_schedule::refresh_run(&conn, rid).ok(); }
     Ok(())
 }
 
@@ -96,7 +111,7 @@ pub fn get_workout_programs(search: Option<String>, db: tauri::State<'_, HanniDb
 pub fn get_workout_program(id: i64, db: tauri::State<'_, HanniDb>) -> Result<serde_json::Value, String> {
     let conn = db.conn();
     let mut prog = conn.query_row(
-        "SELECT id, name, kind, cycle_length_days, duration_weeks, target_muscle_groups, favorite, active, notes, created_at, updated_at FROM workout_programs WHERE id=?1",
+        "SELECT id, name, kind, cycle_length_days, duration_weeks, target_muscle_groups, favorite, active, notes, created_at, updated_at, deload_every_weeks, deload_pct, week_percents FROM workout_programs WHERE id=?1",
         rusqlite::params![id],
         |r| Ok(serde_json::json!({
             "id": r.get::<_,i64>(0)?, "name": r.get::<_,String>(1)?, "kind": r.get::<_,String>(2)?,
@@ -104,6 +119,7 @@ pub fn get_workout_program(id: i64, db: tauri::State<'_, HanniDb>) -> Result<ser
             "target_muscle_groups": r.get::<_,String>(5)?, "favorite": r.get::<_,i64>(6)?,
             "active": r.get::<_,i64>(7)?, "notes": r.get::<_,String>(8)?,
             "created_at": r.get::<_,String>(9)?, "updated_at": r.get::<_,String>(10)?,
+            "deload_every_weeks": r.get::<_,i64>(11)?, "deload_pct": r.get::<_,f64>(12)?, "week_percents": r.get::<_,String>(13)?,
         })),
     ).map_err(|e| format!("Program not found: {}", e))?;
     let mut dstmt = conn.prepare(
@@ -122,9 +138,10 @@ pub fn get_workout_program(id: i64, db: tauri::State<'_, HanniDb>) -> Result<ser
         .map_err(|e| format!("Query error: {}", e))?;
     for row in vrows.filter_map(|r| r.ok()) { vol.insert(row.0, serde_json::json!(row.1)); }
     let run = conn.query_row(
-        "SELECT id, started_at, current_day, status, completed_days FROM program_runs WHERE program_id=?1 AND status='active' ORDER BY id DESC LIMIT 1",
+        "SELECT id, started_at, current_day, status, completed_days, anchor_date, session_time, session_minutes, missed_policy FROM program_runs WHERE program_id=?1 AND status='active' ORDER BY id DESC LIMIT 1",
         rusqlite::params![id],
-        |r| Ok(serde_json::json!({"id": r.get::<_,i64>(0)?, "started_at": r.get::<_,String>(1)?, "current_day": r.get::<_,i64>(2)?, "status": r.get::<_,String>(3)?, "completed_days": r.get::<_,i64>(4)?})),
+        |r| Ok(serde_json::json!({"id": r.get::<_,i64>(0)?, "started_at": r.get::<_,String>(1)?, "current_day": r.get::<_,i64>(2)?, "status": r.get::<_,String>(3)?, "completed_days": r.get::<_,i64>(4)?,
+            "anchor_date": r.get::<_,Option<String>>(5)?, "session_time": r.get::<_,String>(6)?, "session_minutes": r.get::<_,i64>(7)?, "missed_policy": r.get::<_,String>(8)?})),
     ).ok();
     let o = prog.as_object_mut().unwrap();
     o.insert("days".into(), serde_json::json!(days));
@@ -136,6 +153,13 @@ pub fn get_workout_program(id: i64, db: tauri::State<'_, HanniDb>) -> Result<ser
 #[tauri::command]
 pub fn delete_workout_program(id: i64, db: tauri::State<'_, HanniDb>) -> Result<(), String> {
     let conn = db.conn();
+    let runs: Vec<i64> = conn.prepare("SELECT id FROM program_runs WHERE program_id=?1")
+        .and_then(|mut s| s.query_map(rusqlite::params![id], |r| r.get(0)).map(|m| m.filter_map(|x| x.ok()).collect()))
+        .unwrap_or_default();
+    for rid in runs {
+        crate::program_schedule::clear_run_events(&conn, rid);
+        conn.execute("DELETE FROM program_run_log WHERE run_id=?1", rusqlite::params![rid]).ok();
+    }
     conn.execute("DELETE FROM program_days WHERE program_id=?1", rusqlite::params![id]).ok();
     conn.execute("DELETE FROM program_runs WHERE program_id=?1", rusqlite::params![id]).ok();
     conn.execute("DELETE FROM workout_programs WHERE id=?1", rusqlite::params![id]).map_err(|e| format!("DB error: {}", e))?;
@@ -154,15 +178,21 @@ pub fn toggle_favorite_program(id: i64, db: tauri::State<'_, HanniDb>) -> Result
 #[tauri::command]
 pub fn start_program(program_id: i64, db: tauri::State<'_, HanniDb>) -> Result<i64, String> {
     let conn = db.conn();
+    let active: Vec<i64> = conn.prepare("SELECT id FROM program_runs WHERE status='active'")
+        .and_then(|mut s| s.query_map([], |r| r.get(0)).map(|m| m.filter_map(|x| x.ok()).collect()))
+        .unwrap_or_default();
+    for rid in active { crate::program_schedule::clear_run_events(&conn, rid); }
     conn.execute("UPDATE program_runs SET status='paused' WHERE status='active'", []).ok();
     conn.execute("UPDATE workout_programs SET active=0 WHERE active=1", []).ok();
     conn.execute("UPDATE workout_programs SET active=1 WHERE id=?1", rusqlite::params![program_id]).ok();
     let now = chrono::Local::now().format("%Y-%m-%d").to_string();
     conn.execute(
-        "INSERT INTO program_runs (program_id, started_at, current_day, status, completed_days) VALUES (?1,?2,0,'active',0)",
+        "INSERT INTO program_runs (program_id, started_at, current_day, status, completed_days, anchor_date) VALUES (?1,?2,0,'active',0,?2)",
         rusqlite::params![program_id, now],
     ).map_err(|e| format!("DB error: {}", e))?;
-    Ok(conn.last_insert_rowid())
+    let run_id = conn.last_insert_rowid();
+    crate::program_schedule::refresh_run(&conn, run_id)?;
+    Ok(run_id)
 }
 
 #[tauri::command]
@@ -170,36 +200,113 @@ pub fn stop_program(run_id: i64, db: tauri::State<'_, HanniDb>) -> Result<(), St
     let conn = db.conn();
     conn.execute("UPDATE workout_programs SET active=0 WHERE id=(SELECT program_id FROM program_runs WHERE id=?1)", rusqlite::params![run_id]).ok();
     conn.execute("UPDATE program_runs SET status='paused' WHERE id=?1", rusqlite::params![run_id]).map_err(|e| format!("DB error: {}", e))?;
+    crate::program_schedule::clear_run_events(&conn, run_id);
     Ok(())
 }
 
+fn day_templates(conn: &rusqlite::Connection, program_id: i64, day: i64) -> Result<Vec<i64>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT template_id FROM program_days WHERE program_id=?1 AND day_index=?2 AND is_rest=0 AND template_id IS NOT NULL ORDER BY order_index"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let tids = stmt.query_map(rusqlite::params![program_id, day], |r| r.get::<_,i64>(0))
+        .map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+    Ok(tids)
+}
+
+/// Today's workout already started for this run + template, if any.
+fn started_workout(conn: &rusqlite::Connection, run_id: i64, template_id: i64) -> Option<i64> {
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    conn.query_row(
+        "SELECT id FROM workouts WHERE program_run_id=?1 AND template_id=?2 AND date=?3 ORDER BY id DESC LIMIT 1",
+        rusqlite::params![run_id, template_id, today], |r| r.get(0),
+    ).ok()
+}
+
+/// Start the run's current day: materialize its workouts (once per day) with the
+/// progression suggestion written onto each exercise, and return them for set logging.
+/// The day advances only on complete_program_day.
 #[tauri::command]
-pub fn complete_program_day(run_id: i64, db: tauri::State<'_, HanniDb>) -> Result<i64, String> {
+pub fn start_program_day(run_id: i64, db: tauri::State<'_, HanniDb>) -> Result<serde_json::Value, String> {
     let conn = db.conn();
+    crate::program_schedule::refresh_run(&conn, run_id)?;
+    let (factor, deload, week) = crate::program_schedule::current_load(&conn, run_id);
     let (program_id, current_day): (i64, i64) = conn.query_row(
         "SELECT program_id, current_day FROM program_runs WHERE id=?1",
         rusqlite::params![run_id], |r| Ok((r.get(0)?, r.get(1)?)),
     ).map_err(|e| format!("Run not found: {}", e))?;
+    let mut workouts = Vec::new();
+    for tid in day_templates(&conn, program_id, current_day)? {
+        let mut suggestions = crate::strength::progression_for_template(&conn, tid);
+        crate::strength::scale_suggestions(&mut suggestions, factor, deload);
+        let wid = match started_workout(&conn, run_id, tid) {
+            Some(w) => w,
+            None => {
+                let w = crate::commands_data::make_workout_from_template(&conn, tid)?;
+                conn.execute("UPDATE workouts SET program_run_id=?1, load_factor=?2 WHERE id=?3", rusqlite::params![run_id, factor, w]).ok();
+                crate::strength::apply_suggestions(&conn, w, &suggestions);
+                w
+            }
+        };
+        let title: String = conn.query_row("SELECT title FROM workouts WHERE id=?1", rusqlite::params![wid], |r| r.get(0)).unwrap_or_default();
+        workouts.push(serde_json::json!({ "workout_id": wid, "template_id": tid, "title": title, "suggestions": suggestions }));
+    }
+    Ok(serde_json::json!({
+        "run_id": run_id, "current_day": current_day, "week": week, "load_factor": factor, "deload": deload, "workouts": workouts,
+    }))
+}
+
+/// Scale a materialized workout's exercise loads by the week factor; the
+/// template weights themselves stay the unscaled base.
+fn scale_exercise_loads(conn: &rusqlite::Connection, workout_id: i64, factor: f64) {
+    let rows: Vec<(i64, f64)> = conn.prepare("SELECT id, weight_kg FROM exercises WHERE workout_id=?1 AND weight_kg>0")
+        .and_then(|mut s| s.query_map(rusqlite::params![workout_id], |r| Ok((r.get(0)?, r.get(1)?))).map(|m| m.filter_map(|x| x.ok()).collect()))
+        .unwrap_or_default();
+    for (id, w) in rows {
+        conn.execute("UPDATE exercises SET weight_kg=?1 WHERE id=?2",
+            rusqlite::params![crate::strength::round_load(w * factor), id]).ok();
+    }
+}
+
+#[tauri::command]
+pub fn complete_program_day(run_id: i64, db: tauri::State<'_, HanniDb>) -> Result<i64, String> {
+    let conn = db.conn();
+    crate::program_schedule::refresh_run(&conn, run_id)?;
+    let (factor, _, _) = crate::program_schedule::current_load(&conn, run_id);
+    let (program_id, current_day, position): (i64, i64, i64) = conn.query_row(
+        "SELECT program_id, current_day, completed_days FROM program_runs WHERE id=?1",
+        rusqlite::params![run_id], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+    ).map_err(|e| format!("Run not found: {}", e))?;
     let (cycle, dur_weeks): (i64, i64) = conn.query_row(
         "SELECT cycle_length_days, duration_weeks FROM workout_programs WHERE id=?1",
         rusqlite::params![program_id], |r| Ok((r.get(0)?, r.get(1)?)),
     ).map_err(|e| format!("DB error: {}", e))?;
-    let mut stmt = conn.prepare(
-        "SELECT template_id FROM program_days WHERE program_id=?1 AND day_index=?2 AND is_rest=0 AND template_id IS NOT NULL ORDER BY order_index"
-    ).map_err(|e| format!("DB error: {}", e))?;
-    let tids: Vec<i64> = stmt.query_map(rusqlite::params![program_id, current_day], |r| r.get::<_,i64>(0))
-        .map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+    let tids = day_templates(&conn, program_id, current_day)?;
     let mut logged = 0i64;
     for tid in &tids {
-        if crate::commands_data::make_workout_from_template(&conn, *tid).is_ok() { logged += 1; }
+        // Already materialized by start_program_day → the sets are logged there.
+        if started_workout(&conn, run_id, *tid).is_some() { logged += 1; continue; }
+        if let Ok(wid) = crate::commands_data::make_workout_from_template(&conn, *tid) {
+            conn.execute("UPDATE workouts SET program_run_id=?1, load_factor=?2 WHERE id=?3", rusqlite::params![run_id, factor, wid]).ok();
+            if (factor - 1.0).abs() > 0.001 {
+                scale_exercise_loads(&conn, wid, factor);
+            }
+            logged += 1;
+        }
     }
     let cycle = if cycle > 0 { cycle } else { 1 };
     let next = (current_day + 1) % cycle;
     conn.execute("UPDATE program_runs SET current_day=?1, completed_days=completed_days+1 WHERE id=?2",
         rusqlite::params![next, run_id]).ok();
-    if dur_weeks > 0 {
-        conn.execute("UPDATE program_runs SET status='done', finished_at=?1 WHERE id=?2 AND completed_days>=?3",
-            rusqlite::params![chrono::Local::now().to_rfc3339(), run_id, dur_weeks * cycle]).ok();
+    crate::program_schedule::mark_day_done(&conn, run_id, position, current_day);
+    let finished = dur_weeks > 0 && conn.execute(
+        "UPDATE program_runs SET status='done', finished_at=?1 WHERE id=?2 AND completed_days>=?3",
+        rusqlite::params![chrono::Local::now().to_rfc3339(), run_id, dur_weeks * cycle],
+    ).unwrap_or(0) > 0;
+    if finished {
+        conn.execute("UPDATE workout_programs SET active=0 WHERE id=?1", rusqlite::params![program_id]).ok();
+        crate::program_schedule::clear_run_events(&conn, run_id);
+    } else {
+        crate::program_schedule::refresh_run(&conn, run_id).ok();
     }
     Ok(logged)
 }
@@ -207,6 +314,8 @@ pub fn complete_program_day(run_id: i64, db: tauri::State<'_, HanniDb>) -> Resul
 #[tauri::command]
 pub fn get_today_program_workout(db: tauri::State<'_, HanniDb>) -> Result<serde_json::Value, String> {
     let conn = db.conn();
+    // Catch up rest days / missed sessions before reporting "today".
+    crate::program_schedule::refresh_active_runs(&conn);
     let run = conn.query_row(
         "SELECT r.id, r.program_id, r.current_day, r.completed_days, p.name, p.kind, p.cycle_length_days, p.duration_weeks FROM program_runs r JOIN workout_programs p ON p.id=r.program_id WHERE r.status='active' ORDER BY r.id DESC LIMIT 1",
         [],
@@ -223,8 +332,11 @@ pub fn get_today_program_workout(db: tauri::State<'_, HanniDb>) -> Result<serde_
         "id": r.get::<_,i64>(0)?, "label": r.get::<_,String>(1)?, "template_id": r.get::<_,Option<i64>>(2)?,
         "is_rest": r.get::<_,i64>(3)?, "template_name": r.get::<_,Option<String>>(4)?, "template_type": r.get::<_,Option<String>>(5)?,
     }))).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+    let (factor, deload, week) = crate::program_schedule::current_load(&conn, run_id);
+    let anchor: Option<String> = conn.query_row("SELECT anchor_date FROM program_runs WHERE id=?1", rusqlite::params![run_id], |r| r.get(0)).ok().flatten();
     Ok(serde_json::json!({
         "run_id": run_id, "program_id": program_id, "program_name": pname, "kind": kind,
         "current_day": current_day, "completed_days": completed, "cycle_length_days": cycle, "duration_weeks": dur_weeks, "days": days,
+        "scheduled_date": anchor, "week": week, "load_factor": factor, "deload": deload,
     }))
 }
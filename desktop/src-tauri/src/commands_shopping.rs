@@ -1,6 +1,9 @@
 // commands_shopping.rs — Shopping-list CRUD for the "🛒 Закупка" event template.
 // Backs the multi-select picker that fills the event description with items
-// and marks them bought_at when the event is saved.
+// and marks them bought_at when the event is saved. Also generates the list
+// from a meal_plan date range (recipe ingredients minus what's in products).
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 use serde::{Deserialize, Serialize};
 
@@ -14,6 +17,10 @@ pub struct ShoppingItem {
     pub note: String,
     pub added_at: String,
     pub bought_at: Option<String>,
+    /// Catalog category for grouping ('' for manual items).
+    pub category: String,
+    /// 'manual' or 'meal_plan' (regenerated rows).
+    pub source: String,
 }
 
 #[tauri::command]
@@ -23,14 +30,16 @@ pub fn list_shopping_items(
 ) -> Result<Vec<ShoppingItem>, String> {
     let conn = db.conn();
     let sql = if include_bought.unwrap_or(false) {
-        "SELECT id, name, qty, note, added_at, bought_at FROM shopping_list ORDER BY added_at DESC"
+        "SELECT id, name, qty, note, added_at, bought_at, category, source FROM shopping_list ORDER BY added_at DESC"
     } else {
-        "SELECT id, name, qty, note, added_at, bought_at FROM shopping_list WHERE bought_at IS NULL ORDER BY added_at DESC"
+        "SELECT id, name, qty, note, added_at, bought_at, category, source FROM shopping_list WHERE bought_at IS NULL ORDER BY added_at DESC"
     };
     let mut stmt = conn.prepare(sql).map_err(|e| format!("DB error: {}", e))?;
     let rows = stmt.query_map([], |r| Ok(ShoppingItem {
         id: r.get(0)?, name: r.get(1)?, qty: r.get(2)?, note: r.get(3)?,
         added_at: r.get(4)?, bought_at: r.get(5)?,
+        category: r.get::<_, Option<String>>(6)?.unwrap_or_default(),
+        source: r.get::<_, Option<String>>(7)?.unwrap_or_else(|| "manual".into()),
     })).map_err(|e| format!("Query error: {}", e))?;
     Ok(rows.filter_map(|r| r.ok()).collect())
 }
@@ -88,3 +97,320 @@ pub fn delete_shopping_item(id: i64, db: tauri::State<'_, HanniDb>) -> Result<()
         .map_err(|e| format!("DB error: {}", e))?;
     Ok(())
 }
+
+// ── Meal-plan driven generation ──
+
+/// Aggregated requirement for one ingredient, kept per dimension (g / ml / шт)
+/// so mixed units only fold together when the catalog knows how to convert.
+#[derive(Debug, Default, Clone)]
+struct Need {
+    name: String,
+    catalog_id: Option<i64>,
+    category: String,
+    density_g_ml: Option<f64>,
+    piece_g: Option<f64>,
+    mass_g: f64,
+    volume_ml: f64,
+    pieces: f64,
+    /// Units we can't convert ("банка", "упаковка") — summed per unit label.
+    other: BTreeMap<String, f64>,
+    /// Only "по вкусу" lines: needed iff nothing is in stock.
+    to_taste: bool,
+    recipes: BTreeSet<String>,
+}
+
+impl Need {
+    fn add(&mut self, amount: f64, unit: &str) {
+        use crate::nutrition::UnitKind;
+        match crate::nutrition::unit_kind(unit) {
+            UnitKind::Mass(g) => self.mass_g += amount * g,
+            UnitKind::Volume(ml) => self.volume_ml += amount * ml,
+            UnitKind::Piece => self.pieces += amount,
+            UnitKind::Negligible => self.to_taste = true,
+            UnitKind::Unknown => *self.other.entry(unit.trim().to_lowercase()).or_insert(0.0) += amount,
+        }
+    }
+
+    /// Fold volume/pieces into grams when the same item is also needed by
+    /// weight and the catalog has a density / piece weight.
+    fn normalize(&mut self) {
+        if self.mass_g <= 0.0 { return; }
+        if self.volume_ml > 0.0 {
+            if let Some(d) = self.density_g_ml.filter(|d| *d > 0.0) {
+                self.mass_g += self.volume_ml * d;
+                self.volume_ml = 0.0;
+            }
+        }
+        if self.pieces > 0.0 {
+            if let Some(p) = self.piece_g.filter(|p| *p > 0.0) {
+                self.mass_g += self.pieces * p;
+                self.pieces = 0.0;
+            }
+        }
+    }
+
+    /// Take stock (`amount unit` from products) off the requirement.
+    fn subtract_stock(&mut self, amount: f64, unit: &str) {
+        use crate::nutrition::UnitKind;
+        if amount <= 0.0 { return; }
+        self.to_taste = false;
+        let mut grams = crate::nutrition::to_grams(amount, unit, self.density_g_ml, self.piece_g);
+        match crate::nutrition::unit_kind(unit) {
+            UnitKind::Mass(g) if self.mass_g > 0.0 => { take(&mut self.mass_g, amount * g); grams = None; }
+            UnitKind::Volume(ml) if self.volume_ml > 0.0 => { take(&mut self.volume_ml, amount * ml); grams = None; }
+            UnitKind::Piece if self.pieces > 0.0 => { take(&mut self.pieces, amount); grams = None; }
+            UnitKind::Unknown => {
+                if let Some(v) = self.other.get_mut(&unit.trim().to_lowercase()) { take(v, amount); }
+                grams = None;
+            }
+            _ => {}
+        }
+        // Different dimension than needed: go through grams if we can.
+        if let Some(g) = grams {
+            if self.mass_g > 0.0 {
+                take(&mut self.mass_g, g);
+            } else if self.pieces > 0.0 {
+                if let Some(p) = self.piece_g.filter(|p| *p > 0.0) { take(&mut self.pieces, g / p); }
+            } else if self.volume_ml > 0.0 {
+                if let Some(d) = self.density_g_ml.filter(|d| *d > 0.0) { take(&mut self.volume_ml, g / d); }
+            }
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.mass_g < 0.5 && self.volume_ml < 0.5 && self.pieces < 0.01
+            && self.other.values().all(|v| *v < 0.01) && !self.to_taste
+    }
+
+    /// Human quantity for shopping_list.qty, rounded up to buyable amounts.
+    fn qty_label(&self) -> String {
+        let mut parts = Vec::new();
+        if self.mass_g >= 0.5 {
+            let g = round_up_shop(self.mass_g);
+            parts.push(if g >= 1000.0 { format!("{} кг", trim_num(g / 1000.0)) } else { format!("{} г", g) });
+        }
+        if self.volume_ml >= 0.5 {
+            let ml = round_up_shop(self.volume_ml);
+            parts.push(if ml >= 1000.0 { format!("{} л", trim_num(ml / 1000.0)) } else { format!("{} мл", ml) });
+        }
+        if self.pieces >= 0.01 {
+            parts.push(format!("{} шт", self.pieces.ceil()));
+        }
+        for (unit, v) in &self.other {
+            if *v >= 0.01 { parts.push(format!("{} {}", trim_num(v.ceil()), unit)); }
+        }
+        parts.join(" + ")
+    }
+}
+
+fn take(have: &mut f64, amount: f64) {
+    *have = (*have - amount).max(0.0);
+}
+
+/// Round a gram/ml requirement up: 1 below 100, 10 below 1000, 50 above.
+fn round_up_shop(x: f64) -> f64 {
+    let step = if x < 100.0 { 1.0 } else if x < 1000.0 { 10.0 } else { 50.0 };
+    (x / step).ceil() * step
+}
+
+fn trim_num(x: f64) -> String {
+    let s = format!("{:.2}", x);
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+const PLAN_RANGE_KEY: &str = "shopping_plan_range";
+
+/// Aggregate ingredients for all meal_plan rows in [from, to], scaled by
+/// planned servings, then subtract current products. Keyed by catalog id,
+/// falling back to the normalized name for unlinked lines.
+fn plan_needs(conn: &rusqlite::Connection, from: &str, to: &str) -> Result<Vec<Need>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT r.name, COALESCE(mp.servings, r.servings, 1), MAX(COALESCE(r.servings,1),1), \
+                ri.name, ri.amount, ri.unit, ri.catalog_id \
+         FROM meal_plan mp JOIN recipes r ON r.id=mp.recipe_id \
+         JOIN recipe_ingredients ri ON ri.recipe_id=r.id \
+         WHERE mp.date BETWEEN ?1 AND ?2"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    #[allow(clippy::type_complexity)]
+    let lines: Vec<(String, f64, f64, String, f64, String, Option<i64>)> = stmt.query_map(rusqlite::params![from, to], |r| Ok((
+        r.get(0)?, r.get::<_, f64>(1)?, r.get::<_, f64>(2)?, r.get(3)?,
+        r.get::<_, Option<f64>>(4)?.unwrap_or(0.0), r.get::<_, Option<String>>(5)?.unwrap_or_default(), r.get(6)?,
+    ))).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+
+    let index = crate::nutrition::catalog_name_index(conn);
+    let mut needs: HashMap<String, Need> = HashMap::new();
+    for (recipe, planned, base, name, amount, unit, cat_id) in lines {
+        let cid = cat_id.or_else(|| index.get(&crate::db::normalize_name(&name)).copied());
+        let key = match cid { Some(id) => format!("#{}", id), None => crate::db::normalize_name(&name) };
+        let need = needs.entry(key).or_insert_with(|| catalog_need(conn, &name, cid));
+        need.add(amount * planned / base, &unit);
+        need.recipes.insert(recipe);
+    }
+    for need in needs.values_mut() { need.normalize(); }
+
+    // Fridge stock, matched the same way.
+    let mut stmt = conn.prepare("SELECT name, quantity, unit, catalog_id FROM products")
+        .map_err(|e| format!("DB error: {}", e))?;
+    let stock: Vec<(String, f64, String, Option<i64>)> = stmt.query_map([], |r| Ok((
+        r.get(0)?, r.get::<_, Option<f64>>(1)?.unwrap_or(0.0), r.get::<_, Option<String>>(2)?.unwrap_or_default(), r.get(3)?,
+    ))).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+    for (name, qty, unit, cat_id) in stock {
+        let cid = cat_id.or_else(|| index.get(&crate::db::normalize_name(&name)).copied());
+        let key = match cid { Some(id) => format!("#{}", id), None => crate::db::normalize_name(&name) };
+        if let Some(need) = needs.get_mut(&key) { need.subtract_stock(qty, &unit); }
+    }
+
+    let mut out: Vec<Need> = needs.into_values().filter(|n| !n.is_empty()).collect();
+    out.sort_by(|a, b| a.category.cmp(&b.category).then_with(|| a.name.cmp(&b.name)));
+    Ok(out)
+}
+
+fn catalog_need(conn: &rusqlite::Connection, name: &str, cid: Option<i64>) -> Need {
+    let (cat_name, category, density, piece) = cid.and_then(|id| conn.query_row(
+        "SELECT name, category, density_g_ml, piece_g FROM ingredient_catalog WHERE id=?1",
+        rusqlite::params![id],
+        |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?, r.get::<_, Option<f64>>(2)?, r.get::<_, Option<f64>>(3)?)),
+    ).ok()).unwrap_or_else(|| (name.trim().to_string(), "other".into(), None, None));
+    Need { name: cat_name, catalog_id: cid, category, density_g_ml: density, piece_g: piece, ..Default::default() }
+}
+
+/// Replace the open meal-plan rows of shopping_list with the current needs, in
+/// one transaction so a failure never leaves the list half-regenerated.
+/// Manual items are never touched; an open manual row with the same name
+/// suppresses the generated one.
+pub(crate) fn regenerate_plan_shopping(conn: &rusqlite::Connection, from: &str, to: &str) -> Result<serde_json::Value, String> {
+    let needs = plan_needs(conn, from, to)?;
+    let now = chrono::Local::now().to_rfc3339();
+    let tx = conn.unchecked_transaction().map_err(|e| format!("DB error: {}", e))?;
+    tx.execute("DELETE FROM shopping_list WHERE source='meal_plan' AND bought_at IS NULL", [])
+        .map_err(|e| format!("DB error: {}", e))?;
+    let manual: BTreeSet<String> = tx.prepare("SELECT name FROM shopping_list WHERE bought_at IS NULL")
+        .and_then(|mut s| s.query_map([], |r| r.get::<_, String>(0)).map(|m| m.filter_map(|x| x.ok()).map(|n| crate::db::normalize_name(&n)).collect()))
+        .unwrap_or_default();
+    let mut groups: BTreeMap<String, Vec<serde_json::Value>> = BTreeMap::new();
+    let mut skipped = 0;
+    for n in &needs {
+        if manual.contains(&crate::db::normalize_name(&n.name)) { skipped += 1; continue; }
+        let qty = n.qty_label();
+        let note = format!("Для: {}", n.recipes.iter().cloned().collect::<Vec<_>>().join(", "));
+        tx.execute(
+            "INSERT INTO shopping_list (name, qty, note, added_at, category, catalog_id, source) \
+             VALUES (?1,?2,?3,?4,?5,?6,'meal_plan')",
+            rusqlite::params![n.name, qty, note, now, n.category, n.catalog_id],
+        ).map_err(|e| format!("DB error: {}", e))?;
+        groups.entry(n.category.clone()).or_default().push(serde_json::json!({
+            "id": tx.last_insert_rowid(), "name": n.name, "qty": qty, "note": note,
+        }));
+    }
+    crate::sync_owner::set_setting(&tx, PLAN_RANGE_KEY, &format!("{}|{}", from, to));
+    tx.commit().map_err(|e| format!("DB error: {}", e))?;
+    let added: usize = groups.values().map(|v| v.len()).sum();
+    Ok(serde_json::json!({
+        "from": from, "to": to, "added": added, "skipped_already_listed": skipped,
+        "groups": groups.into_iter().map(|(category, items)| serde_json::json!({ "category": category, "items": items })).collect::<Vec<_>>(),
+    }))
+}
+
+/// Called after a meal_plan change on `date`: regenerate if that date falls in
+/// the range the list was last generated for.
+pub(crate) fn refresh_plan_shopping(conn: &rusqlite::Connection, date: &str) {
+    let Some((from, to)) = plan_range(conn) else { return };
+    if date >= from.as_str() && date <= to.as_str() {
+        let _ = regenerate_plan_shopping(conn, &from, &to);
+    }
+}
+
+/// Called after a recipe's servings or ingredients change: regenerate if the
+/// recipe is planned inside the tracked range.
+pub(crate) fn refresh_plan_shopping_for_recipe(conn: &rusqlite::Connection, recipe_id: i64) {
+    refresh_if_planned(conn, "mp.recipe_id=?1", recipe_id);
+}
+
+/// Called after a catalog ingredient is renamed: regenerate if a recipe
+/// planned inside the tracked range uses it.
+pub(crate) fn refresh_plan_shopping_for_ingredient(conn: &rusqlite::Connection, catalog_id: i64) {
+    refresh_if_planned(conn, "ri.catalog_id=?1", catalog_id);
+}
+
+fn plan_range(conn: &rusqlite::Connection) -> Option<(String, String)> {
+    let range = crate::sync_owner::get_setting(conn, PLAN_RANGE_KEY)?;
+    let (from, to) = range.split_once('|')?;
+    Some((from.to_string(), to.to_string()))
+}
+
+fn refresh_if_planned(conn: &rusqlite::Connection, filter: &str, id: i64) {
+    let Some((from, to)) = plan_range(conn) else { return };
+    // LEFT JOIN so a recipe whose ingredients were all removed still counts.
+    let sql = format!(
+        "SELECT EXISTS(SELECT 1 FROM meal_plan mp \
+         LEFT JOIN recipe_ingredients ri ON ri.recipe_id=mp.recipe_id \
+         WHERE {} AND mp.date BETWEEN ?2 AND ?3)", filter);
+    let planned: bool = conn.query_row(&sql, rusqlite::params![id, from, to], |r| r.get(0))
+        .unwrap_or(false);
+    if planned {
+        let _ = regenerate_plan_shopping(conn, &from, &to);
+    }
+}
+
+#[tauri::command]
+pub fn generate_shopping_from_plan(
+    date_from: String, date_to: String, db: tauri::State<'_, HanniDb>,
+) -> Result<serde_json::Value, String> {
+    if date_from > date_to { return Err("Дата начала позже даты конца".into()); }
+    let conn = db.conn();
+    regenerate_plan_shopping(&conn, &date_from, &date_to)
+}
+
+/// Stop auto-updating: drop the tracked range and the open generated rows.
+#[tauri::command]
+pub fn clear_plan_shopping(db: tauri::State<'_, HanniDb>) -> Result<usize, String> {
+    let conn = db.conn();
+    let _ = conn.execute("DELETE FROM app_settings WHERE key=?1", rusqlite::params![PLAN_RANGE_KEY]);
+    conn.execute("DELETE FROM shopping_list WHERE source='meal_plan' AND bought_at IS NULL", [])
+        .map_err(|e| format!("DB error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aggregates_and_subtracts_stock() {
+        let mut n = Need { density_g_ml: Some(1.03), piece_g: Some(55.0), ..Default::default() };
+        n.add(200.0, "мл");
+        n.add(0.5, "л");
+        n.add(100.0, "г");
+        n.normalize();
+        assert_eq!(n.volume_ml, 0.0);
+        assert!((n.mass_g - 821.0).abs() < 1e-9);
+        n.subtract_stock(0.5, "л"); // 515 g
+        assert!((n.mass_g - 306.0).abs() < 1e-9);
+        assert_eq!(n.qty_label(), "310 г");
+    }
+
+    #[test]
+    fn pieces_and_to_taste() {
+        let mut n = Need { piece_g: Some(55.0), ..Default::default() };
+        n.add(3.0, "шт");
+        n.add(1.0, "по вкусу");
+        n.subtract_stock(110.0, "г"); // two eggs' worth
+        assert!((n.pieces - 1.0).abs() < 1e-9);
+        assert!(!n.to_taste);
+        assert_eq!(n.qty_label(), "1 шт");
+
+        let mut salt = Need::default();
+        salt.add(1.0, "по вкусу");
+        assert!(!salt.is_empty());
+        salt.subtract_stock(1.0, "шт");
+        assert!(salt.is_empty());
+    }
+
+    #[test]
+    fn shop_rounding() {
+        assert_eq!(round_up_shop(42.3), 43.0);
+        assert_eq!(round_up_shop(421.0), 430.0);
+        assert_eq!(round_up_shop(1210.0), 1250.0);
+        let n = Need { volume_ml: 1500.0, other: BTreeMap::from([("банка".to_string(), 1.5)]), ..Default::default() };
+        assert_eq!(n.qty_label(), "1.5 л + 2 банка");
+    }
+}
@@ -52,6 +52,7 @@ pub fn delete_activity_type(id: i64, db: tauri::State<'_, HanniDb>) -> Result<()
     ).unwrap_or(1);
     if is_sys == 1 { return Err("Cannot delete system type".into()); }
     conn.execute("DELETE FROM timeline_blocks WHERE type_id=?1", rusqlite::params![id]).ok();
+    conn.execute("DELETE FROM timeline_goal_results WHERE goal_id IN (SELECT id FROM timeline_goals WHERE type_id=?1)", rusqlite::params![id]).ok();
     conn.execute("DELETE FROM timeline_goals WHERE type_id=?1", rusqlite::params![id]).ok();
     conn.execute("DELETE FROM timeline_activity_types WHERE id=?1", rusqlite::params![id])
         .map_err(|e| format!("DB error: {}", e))?;
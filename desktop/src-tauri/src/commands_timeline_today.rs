@@ -18,25 +18,35 @@ fn shift_date(date: &str, delta: i64) -> Result<String, String> {
 
 #[tauri::command]
 pub fn get_today_planned(date: String, db: tauri::State<'_, HanniDb>) -> Result<Vec<serde_json::Value>, String> {
-    let conn = db.read();
+    today_planned(&db.read(), &date)
+}
+
+/// Planned items of `date` (events, schedules incl. yesterday's overdue carry-over,
+/// note tasks) with their tracked block merged in. Shared with the day planner.
+pub(crate) fn today_planned(conn: &rusqlite::Connection, date: &str) -> Result<Vec<serde_json::Value>, String> {
+    let date = date.to_string();
     let mut items: Vec<serde_json::Value> = Vec::new();
 
-    // ── 1. Calendar events on date
+    // ── 1. Calendar events on date (as seen in the current zone — a zoned
+    // event may come from the neighbouring stored day)
+    let current = crate::time_zones::current_zone(conn);
+    let (prev, next) = (shift_date(&date, -1)?, shift_date(&date, 1)?);
     let mut e_stmt = conn.prepare(
-        "SELECT id, title, time, duration_minutes, category, color, completed, priority
-         FROM events WHERE date=?1 AND (source IS NULL OR source != 'auto_health')"
+        "SELECT id, title, date, time, duration_minutes, category, color, completed, priority, COALESCE(tz,'')
+         FROM events WHERE date >= ?1 AND date <= ?2 AND (source IS NULL OR source != 'auto_health')"
     ).map_err(|e| format!("DB error: {}", e))?;
-    let events_iter = e_stmt.query_map(rusqlite::params![date], |row| {
+    let events_iter = e_stmt.query_map(rusqlite::params![prev, next], |row| {
         let id: i64 = row.get(0)?;
         let title: String = row.get(1)?;
-        let time: String = row.get(2)?;
-        let dur: Option<i64> = row.get(3)?;
-        let category: String = row.get(4)?;
-        let color: String = row.get(5)?;
-        let completed: i64 = row.get(6)?;
-        let priority: i64 = row.get(7)?;
+        let (ev_date, time) = crate::time_zones::localize(&row.get::<_, String>(2)?, &row.get::<_, String>(3)?, &row.get::<_, String>(9)?, current);
+        let dur: Option<i64> = row.get(4)?;
+        let category: String = row.get(5)?;
+        let color: String = row.get(6)?;
+        let completed: i64 = row.get(7)?;
+        let priority: i64 = row.get(8)?;
+        if ev_date != date { return Ok(None); }
         let planned_time = if time.is_empty() { serde_json::Value::Null } else { serde_json::Value::String(time) };
-        Ok(serde_json::json!({
+        Ok(Some(serde_json::json!({
             "source_type": "event",
             "source_id": id,
             "title": title,
@@ -48,9 +58,9 @@ pub fn get_today_planned(date: String, db: tauri::State<'_, HanniDb>) -> Result<
             "status_extra": if completed == 1 { "done" } else { "planned" },
             "tracking_mode": "track",
             "priority": priority,
-        }))
+        })))
     }).map_err(|e| format!("Query error: {}", e))?;
-    for it in events_iter.flatten() { items.push(it); }
+    for it in events_iter.flatten().flatten() { items.push(it); }
 
     // ── 2. Schedules matching today + completion status
     let dow = day_of_week(&date)?;
@@ -220,7 +230,7 @@ pub fn get_today_planned(date: String, db: tauri::State<'_, HanniDb>) -> Result<
         // drops the row); numeric event/note ids come back as their text form.
         "SELECT id, source_type, CAST(source_id AS TEXT), start_time, end_time, duration_minutes, is_active
          FROM timeline_blocks
-         WHERE date = ?1 AND source_type IS NOT NULL AND source_id IS NOT NULL"
+         WHERE date = ?1 AND source_type IS NOT NULL AND source_id IS NOT NULL AND source != 'plan'"
     ).map_err(|e| format!("DB error: {}", e))?;
     let blocks_iter = b_stmt.query_map(rusqlite::params![date], |row| {
         Ok((
@@ -265,6 +275,23 @@ pub fn get_today_planned(date: String, db: tauri::State<'_, HanniDb>) -> Result<
     Ok(items)
 }
 
+/// Close whatever block is running (any date) at `now`; a block left over from
+/// an earlier day ends at 23:59. Shared by ▶ Start and pomodoro intervals.
+pub(crate) fn close_active_block(conn: &rusqlite::Connection, now: chrono::DateTime<chrono::Local>) {
+    let date = now.format("%Y-%m-%d").to_string();
+    if let Ok((bid, bdate, bstart)) = conn.query_row::<(i64, String, String), _, _>(
+        "SELECT id, date, start_time FROM timeline_blocks WHERE is_active = 1 LIMIT 1",
+        [], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?))
+    ) {
+        let close_end = if bdate == date { now.format("%H:%M").to_string() } else { "23:59".to_string() };
+        let dur = crate::commands_timeline::calc_duration(&bstart, &close_end);
+        conn.execute(
+            "UPDATE timeline_blocks SET is_active=0, end_time=?1, duration_minutes=?2 WHERE id=?3",
+            rusqlite::params![close_end, dur, bid],
+        ).ok();
+    }
+}
+
 #[tauri::command]
 pub fn start_task_block(
     // String, not i64 — schedules use UUIDv7 ids; events/notes pass numeric ids
@@ -280,17 +307,7 @@ pub fn start_task_block(
     let now_hm = now.format("%H:%M").to_string();
 
     // Close any currently active block (across all dates)
-    if let Ok((bid, bdate, bstart)) = conn.query_row::<(i64, String, String), _, _>(
-        "SELECT id, date, start_time FROM timeline_blocks WHERE is_active = 1 LIMIT 1",
-        [], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?))
-    ) {
-        let close_end = if bdate == date { now_hm.clone() } else { "23:59".to_string() };
-        let dur = crate::commands_timeline::calc_duration(&bstart, &close_end);
-        conn.execute(
-            "UPDATE timeline_blocks SET is_active=0, end_time=?1, duration_minutes=?2 WHERE id=?3",
-            rusqlite::params![close_end, dur, bid],
-        ).ok();
-    }
+    close_active_block(&conn, now);
 
     // Resolve type_id (default = "Запланировано" system type)
     let resolved_type_id = match type_id {
@@ -462,13 +479,16 @@ pub fn get_active_block(db: tauri::State<'_, HanniDb>) -> Result<Option<serde_js
 /// picker to show an expected time. Only tasks with real history are returned.
 #[tauri::command]
 pub fn get_task_avg_durations(db: tauri::State<'_, HanniDb>) -> Result<Vec<serde_json::Value>, String> {
-    let conn = db.read();
+    task_avg_durations(&db.read())
+}
+
+pub(crate) fn task_avg_durations(conn: &rusqlite::Connection) -> Result<Vec<serde_json::Value>, String> {
     let mut stmt = conn.prepare(
         "SELECT source_type, CAST(source_id AS TEXT),
                 CAST(ROUND(AVG(duration_minutes)) AS INTEGER) AS avg_min, COUNT(*) AS n
          FROM timeline_blocks
          WHERE is_active = 0 AND source_type IS NOT NULL AND source_id IS NOT NULL
-               AND duration_minutes > 0
+               AND duration_minutes > 0 AND source != 'plan'
          GROUP BY source_type, source_id"
     ).map_err(|e| format!("DB error: {}", e))?;
     let rows = stmt.query_map([], |r| {
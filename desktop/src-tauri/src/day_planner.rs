@@ -0,0 +1,368 @@
+// day_planner.rs — Time budget for a day: lays the planned tasks, schedules and
+// untimed events (get_today_planned) into free slots between sleep, timed events
+// and already-logged blocks, using historic durations (get_task_avg_durations).
+// What doesn't fit is proposed for tomorrow. An accepted plan is written as
+// timeline_blocks with source 'plan' — intentions, excluded from stats.
+use crate::timeline_reconstruct::{hm_to_min, min_to_hm};
+use crate::types::HanniDb;
+use std::collections::HashMap;
+
+const DAY_MIN: usize = 1440;
+const DEFAULT_DAY: (usize, usize) = (8 * 60, 23 * 60);
+/// Slots start on a 5-minute grid.
+const GRID: usize = 5;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Task {
+    pub source_type: String,
+    pub source_id: String,
+    pub title: String,
+    pub minutes: usize,
+    /// Where `minutes` came from: history / estimate / event / default.
+    pub estimate: &'static str,
+    pub priority: i64,
+    pub overdue: bool,
+    /// Preferred start (schedule time_of_day).
+    pub anchor: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Placed { pub task: usize, pub start: usize, pub end: usize }
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct PlanSlot {
+    pub source_type: String,
+    pub source_id: String,
+    pub title: String,
+    pub start: String,
+    pub end: String,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct PlanRef {
+    pub source_type: String,
+    pub source_id: String,
+}
+
+fn median(mut v: Vec<usize>) -> Option<usize> {
+    v.sort_unstable();
+    v.get(v.len() / 2).copied()
+}
+
+/// Free intervals of [window.0, window.1) after `from`, minus `busy`.
+pub fn free_slots(window: (usize, usize), busy: &[(usize, usize)], from: usize) -> Vec<(usize, usize)> {
+    let start = window.0.max(from.div_ceil(GRID) * GRID);
+    let mut taken = vec![false; DAY_MIN];
+    for &(s, e) in busy {
+        for t in taken.iter_mut().take(e.min(DAY_MIN)).skip(s) { *t = true; }
+    }
+    let mut out = Vec::new();
+    let mut m = start;
+    while m < window.1.min(DAY_MIN) {
+        if taken[m] { m += 1; continue; }
+        let s = m;
+        while m < window.1.min(DAY_MIN) && !taken[m] { m += 1; }
+        out.push((s, m));
+    }
+    out
+}
+
+/// Greedy placement. Pass 1 pins anchored tasks to their exact time when free;
+/// pass 2 fills the rest by overdue → priority → shortest first, anchored ones
+/// at/after their anchor when possible. `buffer` minutes are kept after each task.
+/// Returns placements (by start) and the indices that didn't fit.
+pub fn place(tasks: &[Task], free: &[(usize, usize)], buffer: usize) -> (Vec<Placed>, Vec<usize>) {
+    let mut free: Vec<(usize, usize)> = free.to_vec();
+    let mut placed: Vec<Placed> = Vec::new();
+    let take = |free: &mut Vec<(usize, usize)>, slot: usize, start: usize, len: usize| {
+        let (s, e) = free[slot];
+        let end = (start + len + buffer).min(e);
+        free.splice(slot..=slot, [(s, start), (end, e)].into_iter().filter(|(a, b)| b > a));
+        start + len
+    };
+    let fits = |free: &[(usize, usize)], start: usize, len: usize| {
+        free.iter().position(|&(s, e)| s <= start && start + len <= e)
+    };
+
+    let mut pending: Vec<usize> = Vec::new();
+    for (i, t) in tasks.iter().enumerate() {
+        match t.anchor.and_then(|a| fits(&free, a, t.minutes).map(|slot| (a, slot))) {
+            Some((a, slot)) => {
+                let end = take(&mut free, slot, a, t.minutes);
+                placed.push(Placed { task: i, start: a, end });
+            }
+            None => pending.push(i),
+        }
+    }
+    pending.sort_by_key(|&i| (!tasks[i].overdue, -tasks[i].priority, tasks[i].minutes, i));
+
+    let mut overflow = Vec::new();
+    for i in pending {
+        let t = &tasks[i];
+        let after = t.anchor.unwrap_or(0);
+        let pick = free.iter().enumerate()
+            .map(|(slot, &(s, e))| (slot, s.max(after).div_ceil(GRID) * GRID, e))
+            .find(|&(_, s, e)| s + t.minutes <= e)
+            .or_else(|| free.iter().enumerate().map(|(slot, &(s, e))| (slot, s, e)).find(|&(_, s, e)| s + t.minutes <= e));
+        match pick {
+            Some((slot, start, _)) => {
+                let end = take(&mut free, slot, start, t.minutes);
+                placed.push(Placed { task: i, start, end });
+            }
+            None => overflow.push(i),
+        }
+    }
+    placed.sort_by_key(|p| p.start);
+    (placed, overflow)
+}
+
+/// Waking window: `planner_day_start` / `planner_day_end` settings, else the
+/// median wake-up and bedtime of the last two weeks of sleep, else 08:00–23:00.
+fn day_window(conn: &rusqlite::Connection) -> (usize, usize) {
+    let setting = |key: &str| conn.query_row("SELECT value FROM app_settings WHERE key=?1", [key], |r| r.get::<_, String>(0))
+        .ok().and_then(|v| hm_to_min(&v));
+    let mut stmt = match conn.prepare("SELECT start_time, end_time FROM sleep_sessions ORDER BY date DESC LIMIT 14") {
+        Ok(s) => s,
+        Err(_) => return DEFAULT_DAY,
+    };
+    let sleeps: Vec<(String, String)> = stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+        .map(|rows| rows.filter_map(|r| r.ok()).collect()).unwrap_or_default();
+    let norm = |t: &str| hm_to_min(&crate::timeline_health::normalize_time(t));
+    let wake = median(sleeps.iter().filter_map(|(_, e)| norm(e)).collect());
+    // A bedtime after midnight still ends the previous day.
+    let bed = median(sleeps.iter().filter_map(|(s, _)| norm(s)).map(|b| if b < 12 * 60 { DAY_MIN } else { b }).collect());
+    (
+        setting("planner_day_start").or(wake).unwrap_or(DEFAULT_DAY.0),
+        setting("planner_day_end").or(bed).unwrap_or(DEFAULT_DAY.1),
+    )
+}
+
+fn id_str(v: &serde_json::Value) -> String {
+    match v {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Number(n) => n.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Build the proposal for `date`. Tasks, busy intervals and the window come from the DB.
+fn build_plan(conn: &rusqlite::Connection, date: &str, buffer: usize) -> Result<serde_json::Value, String> {
+    let day = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|e| format!("Invalid date: {}", e))?;
+    let now = chrono::Local::now();
+    if day < now.date_naive() { return Err("Нельзя планировать прошедший день".into()); }
+    use chrono::Timelike;
+    let from = if day == now.date_naive() { (now.hour() * 60 + now.minute()) as usize } else { 0 };
+    let window = day_window(conn);
+
+    let avg: HashMap<(String, String), i64> = crate::commands_timeline_today::task_avg_durations(conn)?.iter()
+        .map(|v| ((v["source_type"].as_str().unwrap_or("").to_string(), id_str(&v["source_id"])), v["avg_minutes"].as_i64().unwrap_or(0)))
+        .collect();
+    let mut stmt = conn.prepare("SELECT id, estimate_minutes FROM notes WHERE due_date=?1 AND estimate_minutes > 0")
+        .map_err(|e| format!("DB error: {}", e))?;
+    let estimates: HashMap<String, i64> = stmt.query_map([date], |r| Ok((r.get::<_, i64>(0)?.to_string(), r.get(1)?)))
+        .map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+
+    let mut busy: Vec<(usize, usize)> = Vec::new();
+    let mut fixed = Vec::new();
+    let mut tasks: Vec<Task> = Vec::new();
+    for it in crate::commands_timeline_today::today_planned(conn, date)? {
+        let status = it["status_extra"].as_str().unwrap_or("");
+        if it["completed"].as_bool().unwrap_or(false) || status == "done" || status == "skipped" { continue; }
+        if it["is_active"].as_bool().unwrap_or(false) { continue; }
+        let source_type = it["source_type"].as_str().unwrap_or("").to_string();
+        let source_id = id_str(&it["source_id"]);
+        let title = it["title"].as_str().unwrap_or("").to_string();
+        let planned = it["planned_time"].as_str().and_then(hm_to_min);
+        let event_dur = it["duration_minutes"].as_i64().filter(|d| *d > 0);
+        // Timed events are fixed points of the day, not something to place.
+        if source_type == "event" {
+            if let Some(s) = planned {
+                let e = s + event_dur.unwrap_or(60) as usize;
+                busy.push((s, e));
+                fixed.push(serde_json::json!({ "title": title, "start": min_to_hm(s), "end": min_to_hm(e) }));
+                continue;
+            }
+        }
+        let (minutes, estimate) = if let Some(m) = avg.get(&(source_type.clone(), source_id.clone())).filter(|m| **m > 0) {
+            (*m, "history")
+        } else if let Some(m) = (source_type == "note").then(|| estimates.get(&source_id)).flatten() {
+            (*m, "estimate")
+        } else if let Some(m) = event_dur {
+            (m, "event")
+        } else if it["tracking_mode"] == "check" {
+            (10, "default")
+        } else {
+            (30, "default")
+        };
+        tasks.push(Task {
+            source_type, source_id, title,
+            minutes: minutes.clamp(5, 12 * 60) as usize, estimate,
+            priority: it["priority"].as_i64().unwrap_or(0),
+            overdue: status == "overdue" || it.get("overdue_date").is_some(),
+            anchor: if it["source_type"] == "schedule" { planned } else { None },
+        });
+    }
+
+    // Time already logged (or running) on that date is taken too.
+    let mut stmt = conn.prepare(
+        "SELECT start_time, end_time, COALESCE(is_active,0) FROM timeline_blocks WHERE date=?1 AND source NOT IN ('plan', 'auto_event')"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    for (s, e, active) in stmt.query_map([date], |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?, r.get::<_, i64>(2)?)))
+        .map_err(|e| format!("Query error: {}", e))?.flatten() {
+        if let Some(s) = hm_to_min(&s) {
+            let e = if active == 1 { from.max(s) } else { hm_to_min(&e).unwrap_or(s) };
+            busy.push((s, e));
+        }
+    }
+
+    let free = free_slots(window, &busy, from);
+    let free_minutes: usize = free.iter().map(|(s, e)| e - s).sum();
+    let (placed, overflow) = place(&tasks, &free, buffer);
+    let task_json = |t: &Task| serde_json::json!({
+        "source_type": t.source_type, "source_id": t.source_id, "title": t.title,
+        "minutes": t.minutes, "estimate": t.estimate, "priority": t.priority, "overdue": t.overdue,
+    });
+    let needed: usize = tasks.iter().map(|t| t.minutes + buffer).sum();
+    Ok(serde_json::json!({
+        "date": date,
+        "day_start": min_to_hm(window.0), "day_end": min_to_hm(window.1),
+        "free_minutes": free_minutes,
+        "needed_minutes": needed,
+        "overcommitted_minutes": overflow.iter().map(|&i| tasks[i].minutes).sum::<usize>(),
+        "fixed": fixed,
+        "slots": placed.iter().map(|p| {
+            let mut v = task_json(&tasks[p.task]);
+            v["start"] = serde_json::json!(min_to_hm(p.start));
+            v["end"] = serde_json::json!(min_to_hm(p.end));
+            v["anchored"] = serde_json::json!(tasks[p.task].anchor == Some(p.start));
+            v
+        }).collect::<Vec<_>>(),
+        // Schedules repeat — only events and note tasks can be moved to tomorrow.
+        "overflow": overflow.iter().map(|&i| {
+            let mut v = task_json(&tasks[i]);
+            v["movable"] = serde_json::json!(tasks[i].source_type != "schedule");
+            v
+        }).collect::<Vec<_>>(),
+    }))
+}
+
+// ── Commands ──
+
+/// Propose a plan for `date` (today: from now on). Nothing is written.
+#[tauri::command]
+pub fn plan_day(date: String, buffer_minutes: Option<i64>, db: tauri::State<'_, HanniDb>) -> Result<serde_json::Value, String> {
+    build_plan(&db.read(), &date, buffer_minutes.unwrap_or(5).clamp(0, 60) as usize)
+}
+
+/// Save the accepted slots as planned blocks (replacing an earlier plan of the
+/// day) and move the chosen overflow items to the next day.
+#[tauri::command]
+pub fn accept_day_plan(date: String, slots: Vec<PlanSlot>, move_to_tomorrow: Vec<PlanRef>, db: tauri::State<'_, HanniDb>) -> Result<serde_json::Value, String> {
+    let day = chrono::NaiveDate::parse_from_str(&date, "%Y-%m-%d").map_err(|e| format!("Invalid date: {}", e))?;
+    let tomorrow = (day + chrono::Duration::days(1)).format("%Y-%m-%d").to_string();
+    let conn = db.conn();
+    let type_id: i64 = conn.query_row(
+        "SELECT id FROM timeline_activity_types WHERE name='Запланировано' AND is_system=1 LIMIT 1",
+        [], |r| r.get(0),
+    ).map_err(|e| format!("Default type missing: {}", e))?;
+    let tx = conn.unchecked_transaction().map_err(|e| format!("DB error: {}", e))?;
+    tx.execute("DELETE FROM timeline_blocks WHERE date=?1 AND source='plan'", [&date]).map_err(|e| format!("DB error: {}", e))?;
+    let mut saved = 0;
+    for s in &slots {
+        let (Some(a), Some(b)) = (hm_to_min(&s.start), hm_to_min(&s.end)) else { continue };
+        if b <= a { continue; }
+        tx.execute(
+            "INSERT INTO timeline_blocks (type_id, date, start_time, end_time, duration_minutes, source, notes, is_active, source_type, source_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, 'plan', ?6, 0, ?7, ?8)",
+            rusqlite::params![type_id, date, s.start, s.end, (b - a) as i64, s.title, s.source_type, s.source_id],
+        ).map_err(|e| format!("DB error: {}", e))?;
+        saved += 1;
+    }
+    let now = chrono::Local::now().to_rfc3339();
+    let zone = crate::time_zones::current_zone(&tx);
+    let mut moved = 0;
+    for r in &move_to_tomorrow {
+        let n = match r.source_type.as_str() {
+            // Same wall-clock time tomorrow as seen here, written back in the event's own zone
+            "event" => match tx.query_row(
+                "SELECT date, time, COALESCE(tz,'') FROM events WHERE id=?1", [&r.source_id],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)),
+            ) {
+                Ok((date, time, tz)) => {
+                    let (_, local_time) = crate::time_zones::localize(&date, &time, &tz, zone);
+                    let (date, time) = crate::time_zones::unlocalize(&tomorrow, &local_time, zone, &tz);
+                    tx.execute("UPDATE events SET date=?1, time=?2 WHERE id=?3", rusqlite::params![date, time, r.source_id])
+                }
+                Err(_) => Ok(0),
+            },
+            "note" => tx.execute("UPDATE notes SET due_date=?1, updated_at=?2 WHERE id=?3", rusqlite::params![tomorrow, now, r.source_id]),
+            _ => Ok(0),
+        }.map_err(|e| format!("DB error: {}", e))?;
+        moved += n;
+    }
+    tx.commit().map_err(|e| format!("DB error: {}", e))?;
+    Ok(serde_json::json!({ "saved": saved, "moved": moved }))
+}
+
+/// Drop the accepted plan of `date`.
+#[tauri::command]
+pub fn clear_day_plan(date: String, db: tauri::State<'_, HanniDb>) -> Result<(), String> {
+    db.conn().execute("DELETE FROM timeline_blocks WHERE date=?1 AND source='plan'", [&date])
+        .map_err(|e| format!("DB error: {}", e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(id: &str, minutes: usize, priority: i64, anchor: Option<&str>) -> Task {
+        Task {
+            source_type: "note".into(), source_id: id.into(), title: id.into(), minutes,
+            estimate: "default", priority, overdue: false, anchor: anchor.and_then(hm_to_min),
+        }
+    }
+
+    #[test]
+    fn computes_free_slots() {
+        let busy = [(hm_to_min("10:00").unwrap(), hm_to_min("11:00").unwrap()), (hm_to_min("10:30").unwrap(), hm_to_min("12:00").unwrap())];
+        assert_eq!(free_slots((480, 1380), &busy, 0), vec![(480, 600), (720, 1380)]);
+        // From 08:52 → rounded up to 08:55.
+        assert_eq!(free_slots((480, 1380), &busy, 532)[0], (535, 600));
+        assert!(free_slots((480, 1380), &[], 1400).is_empty());
+        // A day that runs to midnight ends at 24:00, not a minute early.
+        let (_, end) = *free_slots((480, hm_to_min("24:00").unwrap()), &busy, 0).last().unwrap();
+        assert_eq!(min_to_hm(end), "24:00");
+    }
+
+    #[test]
+    fn anchors_then_priority() {
+        let tasks = vec![
+            task("low", 60, 0, None),
+            task("gym", 60, 1, Some("09:00")),
+            task("high", 45, 3, None),
+            task("late", 30, 2, Some("08:30")),
+        ];
+        let free = vec![(480, 630)]; // 08:00–10:30
+        let (placed, overflow) = place(&tasks, &free, 0);
+        let got: Vec<(&str, String, String)> = placed.iter()
+            .map(|p| (tasks[p.task].source_id.as_str(), min_to_hm(p.start), min_to_hm(p.end))).collect();
+        assert_eq!(got, vec![
+            ("late", "08:30".into(), "09:00".into()),
+            ("gym", "09:00".into(), "10:00".into()),
+        ]);
+        // 08:00–08:30 and 10:00–10:30 are left: 30 min each, neither 45 nor 60 fits.
+        assert_eq!(overflow, vec![2, 0]);
+    }
+
+    #[test]
+    fn buffer_and_fallback_before_anchor() {
+        let tasks = vec![task("a", 20, 0, None), task("b", 20, 0, Some("09:00")), task("c", 20, 0, None)];
+        // 08:00–09:00 free; b's anchor is outside, so it falls back to the first gap.
+        let (placed, overflow) = place(&tasks, &[(480, 540)], 5);
+        let got: Vec<(usize, usize, usize)> = placed.iter().map(|p| (p.task, p.start, p.end)).collect();
+        assert_eq!(got, vec![(0, 480, 500), (1, 505, 525)]);
+        // 08:50–09:00 is all that's left after the 5-min buffers — c doesn't fit.
+        assert_eq!(overflow, vec![2]);
+    }
+}
@@ -4355,3 +4355,499 @@ pub fn migrate_shopping_list(conn: &rusqlite::Connection) {
         ).ok();
     }
 }
+
+/// Debt amortization: repayment terms on `debts` (schedule kind, term, first
+/// payment date, fixed minimum) plus a `debt_payments` history. Each payment
+/// row records how it split into accrued interest vs principal, and
+/// `last_accrual_date` marks where interest accrual restarts. Idempotent.
+pub fn migrate_debt_payments(conn: &rusqlite::Connection) {
+    conn.execute("ALTER TABLE debts ADD COLUMN schedule_type TEXT NOT NULL DEFAULT 'none'", []).ok();
+    conn.execute("ALTER TABLE debts ADD COLUMN term_months INTEGER NOT NULL DEFAULT 0", []).ok();
+    conn.execute("ALTER TABLE debts ADD COLUMN start_date TEXT", []).ok();
+    conn.execute("ALTER TABLE debts ADD COLUMN min_payment REAL NOT NULL DEFAULT 0", []).ok();
+    conn.execute("ALTER TABLE debts ADD COLUMN last_accrual_date TEXT", []).ok();
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS debt_payments (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            debt_id INTEGER NOT NULL,
+            date TEXT NOT NULL,
+            amount REAL NOT NULL,
+            interest REAL NOT NULL DEFAULT 0,
+            principal REAL NOT NULL DEFAULT 0,
+            remaining_after REAL NOT NULL DEFAULT 0,
+            notes TEXT NOT NULL DEFAULT '',
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (debt_id) REFERENCES debts(id) ON DELETE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_debt_payments_debt ON debt_payments(debt_id, date);"
+    ).ok();
+}
+
+/// Structured nutrition: per-100g macros plus unit-conversion hints on
+/// `ingredient_catalog` (density for volume units, weight of one piece), an
+/// auto-calculation flag/coverage on `recipes`, and a recipe link on
+/// `food_log` so eaten recipes reuse their per-serving macros. Seeds the
+/// bundled dataset once. Idempotent.
+pub fn migrate_catalog_nutrition(conn: &rusqlite::Connection) {
+    for col in ["kcal_100g", "protein_100g", "fat_100g", "carbs_100g", "fiber_100g", "density_g_ml", "piece_g"] {
+        conn.execute(&format!("ALTER TABLE ingredient_catalog ADD COLUMN {} REAL", col), []).ok();
+    }
+    conn.execute("ALTER TABLE ingredient_catalog ADD COLUMN nutrition_source TEXT NOT NULL DEFAULT ''", []).ok();
+    conn.execute("ALTER TABLE recipes ADD COLUMN fiber INTEGER DEFAULT 0", []).ok();
+    conn.execute("ALTER TABLE recipes ADD COLUMN nutrition_auto INTEGER NOT NULL DEFAULT 0", []).ok();
+    conn.execute("ALTER TABLE recipes ADD COLUMN nutrition_coverage REAL NOT NULL DEFAULT 0", []).ok();
+    conn.execute("ALTER TABLE food_log ADD COLUMN recipe_id INTEGER", []).ok();
+    conn.execute("ALTER TABLE food_log ADD COLUMN servings REAL NOT NULL DEFAULT 1", []).ok();
+    crate::nutrition_seed::seed_catalog_nutrition(conn);
+}
+
+/// Meal-plan driven shopping: `shopping_list` rows carry the catalog category
+/// and link plus a `source` so regenerated rows can be replaced without
+/// touching manual ones; `meal_plan.servings` overrides the recipe's yield
+/// (NULL = cook the recipe as written). Idempotent.
+pub fn migrate_shopping_from_plan(conn: &rusqlite::Connection) {
+    conn.execute("ALTER TABLE shopping_list ADD COLUMN category TEXT NOT NULL DEFAULT ''", []).ok();
+    conn.execute("ALTER TABLE shopping_list ADD COLUMN catalog_id INTEGER", []).ok();
+    conn.execute("ALTER TABLE shopping_list ADD COLUMN source TEXT NOT NULL DEFAULT 'manual'", []).ok();
+    conn.execute("ALTER TABLE meal_plan ADD COLUMN servings INTEGER", []).ok();
+}
+
+/// Pantry consumption: per-product restock minimum and a `wasted_at` marker
+/// on `products`, planned servings on `cooking_log`, and a local `pantry_log`
+/// of every decrement (reason 'cooked' / 'expired') for the waste report.
+pub fn migrate_pantry_log(conn: &rusqlite::Connection) {
+    conn.execute("ALTER TABLE products ADD COLUMN min_quantity REAL NOT NULL DEFAULT 0", []).ok();
+    conn.execute("ALTER TABLE products ADD COLUMN wasted_at TEXT", []).ok();
+    conn.execute("ALTER TABLE cooking_log ADD COLUMN servings INTEGER", []).ok();
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS pantry_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            product_id INTEGER,
+            name TEXT NOT NULL,
+            catalog_id INTEGER,
+            change REAL NOT NULL,
+            unit TEXT NOT NULL DEFAULT '',
+            reason TEXT NOT NULL,
+            cooking_log_id INTEGER,
+            date TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_pantry_log_reason_date ON pantry_log(reason, date);"
+    ).ok();
+}
+
+/// Recipe import: remember where an imported recipe came from so the same
+/// URL isn't imported twice.
+pub fn migrate_recipe_source_url(conn: &rusqlite::Connection) {
+    conn.execute("ALTER TABLE recipes ADD COLUMN source_url TEXT NOT NULL DEFAULT ''", []).ok();
+}
+
+/// Strength logging: one `workout_sets` row per set (reps, load, RPE, rest,
+/// warm-up / failure flags, cached e1RM + PR flag), catalog links on logged
+/// exercises, and the program run a workout was started from.
+pub fn migrate_workout_sets(conn: &rusqlite::Connection) {
+    conn.execute("ALTER TABLE exercises ADD COLUMN exercise_catalog_id INTEGER", []).ok();
+    conn.execute("ALTER TABLE workouts ADD COLUMN program_run_id INTEGER", []).ok();
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS workout_sets (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            workout_id INTEGER NOT NULL,
+            exercise_id INTEGER NOT NULL,
+            exercise_catalog_id INTEGER,
+            set_index INTEGER NOT NULL DEFAULT 1,
+            reps INTEGER NOT NULL DEFAULT 0,
+            weight_kg REAL NOT NULL DEFAULT 0,
+            rpe REAL,
+            rest_seconds INTEGER,
+            is_warmup INTEGER NOT NULL DEFAULT 0,
+            failed INTEGER NOT NULL DEFAULT 0,
+            e1rm REAL NOT NULL DEFAULT 0,
+            is_pr INTEGER NOT NULL DEFAULT 0,
+            notes TEXT NOT NULL DEFAULT '',
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (exercise_id) REFERENCES exercises(id) ON DELETE CASCADE,
+            FOREIGN KEY (exercise_catalog_id) REFERENCES exercise_catalog(id)
+        );
+        CREATE INDEX IF NOT EXISTS idx_workout_sets_exercise ON workout_sets(exercise_id, set_index);
+        CREATE INDEX IF NOT EXISTS idx_workout_sets_catalog ON workout_sets(exercise_catalog_id, is_warmup);
+        CREATE INDEX IF NOT EXISTS idx_workout_sets_workout ON workout_sets(workout_id);"
+    ).ok();
+    // Link already-logged exercises to the catalog (template rows carry the id; else by name).
+    conn.execute(
+        "UPDATE exercises SET exercise_catalog_id=(
+            SELECT te.exercise_catalog_id FROM workouts w JOIN template_exercises te ON te.template_id=w.template_id
+            WHERE w.id=exercises.workout_id AND te.name=exercises.name AND te.exercise_catalog_id IS NOT NULL LIMIT 1)
+         WHERE exercise_catalog_id IS NULL",
+        [],
+    ).ok();
+    conn.execute(
+        "UPDATE exercises SET exercise_catalog_id=(SELECT id FROM exercise_catalog ec WHERE ec.name=exercises.name)
+         WHERE exercise_catalog_id IS NULL",
+        [],
+    ).ok();
+}
+
+/// Program scheduling: runs are anchored to a date with a calendar slot and a
+/// missed-session policy, programs get deload / percentage weeks, and
+/// `program_run_log` keeps done + missed sessions per absolute position.
+pub fn migrate_program_schedule(conn: &rusqlite::Connection) {
+    conn.execute("ALTER TABLE program_runs ADD COLUMN anchor_date TEXT", []).ok();
+    conn.execute("ALTER TABLE program_runs ADD COLUMN session_time TEXT NOT NULL DEFAULT '18:00'", []).ok();
+    conn.execute("ALTER TABLE program_runs ADD COLUMN session_minutes INTEGER NOT NULL DEFAULT 60", []).ok();
+    conn.execute("ALTER TABLE program_runs ADD COLUMN missed_policy TEXT NOT NULL DEFAULT 'shift'", []).ok();
+    conn.execute("ALTER TABLE workout_programs ADD COLUMN deload_every_weeks INTEGER NOT NULL DEFAULT 0", []).ok();
+    conn.execute("ALTER TABLE workout_programs ADD COLUMN deload_pct REAL NOT NULL DEFAULT 0.6", []).ok();
+    conn.execute("ALTER TABLE workout_programs ADD COLUMN week_percents TEXT NOT NULL DEFAULT ''", []).ok();
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS program_run_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            run_id INTEGER NOT NULL,
+            position INTEGER NOT NULL,
+            day_index INTEGER NOT NULL,
+            scheduled_date TEXT NOT NULL,
+            status TEXT NOT NULL,
+            workout_id INTEGER,
+            created_at TEXT NOT NULL,
+            UNIQUE(run_id, position, scheduled_date),
+            FOREIGN KEY (run_id) REFERENCES program_runs(id) ON DELETE CASCADE
+        );"
+    ).ok();
+}
+
+/// Imported GPX / TCX / FIT tracks: per-workout summary with splits and a
+/// downsampled polyline, best efforts for personal bests, and heart-rate
+/// samples tagged with the workout they came from.
+pub fn migrate_workout_tracks(conn: &rusqlite::Connection) {
+    conn.execute("ALTER TABLE heart_rate_samples ADD COLUMN workout_id INTEGER", []).ok();
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS workout_tracks (
+            workout_id INTEGER PRIMARY KEY,
+            sport TEXT NOT NULL DEFAULT 'other',
+            external_id TEXT NOT NULL UNIQUE,
+            started_at TEXT NOT NULL,
+            distance_m REAL NOT NULL DEFAULT 0,
+            moving_seconds INTEGER NOT NULL DEFAULT 0,
+            elevation_gain_m REAL NOT NULL DEFAULT 0,
+            avg_hr INTEGER,
+            max_hr INTEGER,
+            splits_json TEXT NOT NULL DEFAULT '[]',
+            points_json TEXT NOT NULL DEFAULT '[]',
+            file_name TEXT NOT NULL DEFAULT '',
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (workout_id) REFERENCES workouts(id) ON DELETE CASCADE
+        );
+        CREATE TABLE IF NOT EXISTS workout_best_efforts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            workout_id INTEGER NOT NULL,
+            sport TEXT NOT NULL,
+            distance_m REAL NOT NULL,
+            seconds REAL NOT NULL,
+            UNIQUE(workout_id, distance_m),
+            FOREIGN KEY (workout_id) REFERENCES workouts(id) ON DELETE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_best_efforts_sport ON workout_best_efforts(sport, distance_m, seconds);
+        CREATE INDEX IF NOT EXISTS idx_hr_samples_workout ON heart_rate_samples(workout_id);"
+    ).ok();
+}
+
+/// Cache of per-day heart-rate metrics derived from `heart_rate_samples`
+/// (resting HR, max/avg, minutes per zone). Recomputed by heart_rate.rs.
+pub fn migrate_heart_rate_daily(conn: &rusqlite::Connection) {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS heart_rate_daily (
+            date TEXT PRIMARY KEY,
+            resting_hr REAL,
+            resting_source TEXT,
+            sleep_hr_sd REAL,
+            avg_hr REAL,
+            max_hr INTEGER,
+            samples INTEGER NOT NULL DEFAULT 0,
+            zone_minutes TEXT NOT NULL DEFAULT '[0,0,0,0,0]',
+            zones_max_hr INTEGER NOT NULL DEFAULT 0,
+            computed_at TEXT NOT NULL
+        );"
+    ).ok();
+}
+
+/// Structured habit schedules, numeric targets and auto-check sources; per-check
+/// value/status ('done' | 'partial' | 'frozen') and origin ('manual' | 'auto').
+/// Existing habits get a schedule derived from their free-form `frequency`.
+pub fn migrate_habit_schedules(conn: &rusqlite::Connection) {
+    conn.execute("ALTER TABLE habits ADD COLUMN schedule_kind TEXT", []).ok();
+    conn.execute("ALTER TABLE habits ADD COLUMN schedule_days INTEGER NOT NULL DEFAULT 0", []).ok();
+    conn.execute("ALTER TABLE habits ADD COLUMN schedule_count INTEGER NOT NULL DEFAULT 1", []).ok();
+    conn.execute("ALTER TABLE habits ADD COLUMN target REAL NOT NULL DEFAULT 1", []).ok();
+    conn.execute("ALTER TABLE habits ADD COLUMN unit TEXT NOT NULL DEFAULT ''", []).ok();
+    conn.execute("ALTER TABLE habits ADD COLUMN auto_source TEXT NOT NULL DEFAULT ''", []).ok();
+    conn.execute("ALTER TABLE habit_checks ADD COLUMN value REAL", []).ok();
+    conn.execute("ALTER TABLE habit_checks ADD COLUMN status TEXT NOT NULL DEFAULT 'done'", []).ok();
+    conn.execute("ALTER TABLE habit_checks ADD COLUMN source TEXT NOT NULL DEFAULT 'manual'", []).ok();
+    conn.execute(
+        "UPDATE habits SET
+            schedule_kind = CASE frequency WHEN 'weekdays' THEN 'days' WHEN 'weekends' THEN 'days'
+                                           WHEN 'weekly' THEN 'per_week' ELSE 'daily' END,
+            schedule_days = CASE frequency WHEN 'weekdays' THEN 31 WHEN 'weekends' THEN 96 ELSE 0 END
+         WHERE schedule_kind IS NULL",
+        [],
+    ).ok();
+}
+
+/// Body fat and circumferences, one value per kind and day. Weight itself stays
+/// in `health_log`; see body_metrics.rs.
+pub fn migrate_body_measurements(conn: &rusqlite::Connection) {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS body_measurements (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            date TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            value REAL NOT NULL,
+            unit TEXT NOT NULL DEFAULT '',
+            note TEXT NOT NULL DEFAULT '',
+            created_at TEXT NOT NULL,
+            UNIQUE(date, kind)
+        );
+        CREATE INDEX IF NOT EXISTS idx_body_measurements_kind ON body_measurements(kind, date);"
+    ).ok();
+}
+
+/// Medications & supplements with a dose schedule and stock, the dose log, and
+/// a `source` tag on reminders so schedule-generated ones are created once.
+pub fn migrate_medications(conn: &rusqlite::Connection) {
+    conn.execute("ALTER TABLE reminders ADD COLUMN source TEXT", []).ok();
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS medications (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            dose REAL NOT NULL DEFAULT 0,
+            unit TEXT NOT NULL DEFAULT '',
+            per_dose REAL NOT NULL DEFAULT 1,
+            times TEXT NOT NULL DEFAULT '',
+            schedule_kind TEXT NOT NULL DEFAULT 'daily',
+            schedule_days INTEGER NOT NULL DEFAULT 0,
+            schedule_count INTEGER NOT NULL DEFAULT 1,
+            as_needed INTEGER NOT NULL DEFAULT 0,
+            stock REAL,
+            refill_threshold REAL,
+            notes TEXT NOT NULL DEFAULT '',
+            active INTEGER NOT NULL DEFAULT 1,
+            start_date TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS medication_doses (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            medication_id INTEGER NOT NULL REFERENCES medications(id) ON DELETE CASCADE,
+            date TEXT NOT NULL,
+            time TEXT,
+            status TEXT NOT NULL,
+            taken_at TEXT,
+            note TEXT NOT NULL DEFAULT '',
+            created_at TEXT NOT NULL,
+            UNIQUE(medication_id, date, time)
+        );
+        CREATE INDEX IF NOT EXISTS idx_medication_doses_date ON medication_doses(medication_id, date);
+        CREATE INDEX IF NOT EXISTS idx_reminders_source ON reminders(source);"
+    ).ok();
+}
+
+/// Daily check-ins (mood / energy / stress 1–5, cycle flow, notes) and their
+/// symptoms with severity; see checkin.rs.
+pub fn migrate_daily_checkins(conn: &rusqlite::Connection) {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS daily_checkins (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            date TEXT NOT NULL UNIQUE,
+            mood INTEGER,
+            energy INTEGER,
+            stress INTEGER,
+            cycle_flow INTEGER,
+            notes TEXT NOT NULL DEFAULT '',
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS checkin_symptoms (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            date TEXT NOT NULL,
+            name TEXT NOT NULL,
+            severity INTEGER NOT NULL DEFAULT 1,
+            UNIQUE(date, name)
+        );
+        CREATE INDEX IF NOT EXISTS idx_checkin_symptoms_date ON checkin_symptoms(date);"
+    ).ok();
+}
+
+/// User-editable activity classification rules (see activity_rules.rs).
+pub fn migrate_activity_rules(conn: &rusqlite::Connection) {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS activity_rules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            field TEXT NOT NULL DEFAULT 'any',
+            kind TEXT NOT NULL DEFAULT 'substring',
+            pattern TEXT NOT NULL,
+            category TEXT NOT NULL,
+            score REAL,
+            priority INTEGER NOT NULL DEFAULT 0,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL
+        );"
+    ).ok();
+}
+
+/// Where a snapshot came from: '' for the live collector, 'activitywatch' for imports.
+pub fn migrate_activity_snapshot_source(conn: &rusqlite::Connection) {
+    conn.execute("ALTER TABLE activity_snapshots ADD COLUMN source TEXT NOT NULL DEFAULT ''", []).ok();
+}
+
+/// Attempts to open blocked apps/sites during a focus session (see focus_enforcer.rs).
+pub fn migrate_focus_tamper_log(conn: &rusqlite::Connection) {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS focus_tamper_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_started_at TEXT NOT NULL DEFAULT '',
+            at TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            target TEXT NOT NULL,
+            action TEXT NOT NULL DEFAULT ''
+        );
+        CREATE INDEX IF NOT EXISTS idx_focus_tamper_at ON focus_tamper_log(at);"
+    ).ok();
+}
+
+/// Pomodoro sessions and their work/break intervals (see pomodoro.rs), plus
+/// the "Перерыв" timeline type the break intervals are logged under.
+pub fn migrate_pomodoro(conn: &rusqlite::Connection) {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS pomodoro_sessions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            source_type TEXT,
+            source_id TEXT,
+            title TEXT NOT NULL DEFAULT '',
+            project TEXT NOT NULL DEFAULT '',
+            config TEXT NOT NULL DEFAULT '{}',
+            status TEXT NOT NULL DEFAULT 'running',
+            phase TEXT NOT NULL DEFAULT 'work',
+            phase_ends_at TEXT,
+            paused_left_secs INTEGER,
+            cycles INTEGER NOT NULL DEFAULT 0,
+            interruptions INTEGER NOT NULL DEFAULT 0,
+            notes TEXT NOT NULL DEFAULT '',
+            focus_owned INTEGER NOT NULL DEFAULT 0,
+            started_at TEXT NOT NULL,
+            ended_at TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_pomodoro_sessions_started ON pomodoro_sessions(started_at);
+        CREATE TABLE IF NOT EXISTS pomodoro_intervals (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id INTEGER NOT NULL REFERENCES pomodoro_sessions(id) ON DELETE CASCADE,
+            phase TEXT NOT NULL,
+            started_at TEXT NOT NULL,
+            ended_at TEXT,
+            planned_secs INTEGER NOT NULL DEFAULT 0,
+            completed INTEGER NOT NULL DEFAULT 0,
+            interruptions INTEGER NOT NULL DEFAULT 0,
+            block_id INTEGER
+        );
+        CREATE INDEX IF NOT EXISTS idx_pomodoro_intervals_session ON pomodoro_intervals(session_id);"
+    ).ok();
+    conn.execute(
+        "INSERT INTO timeline_activity_types (name, color, icon, is_system, sort_order)
+         SELECT ?1, ?2, ?3, 1, ?4 WHERE NOT EXISTS (
+             SELECT 1 FROM timeline_activity_types WHERE name=?1 AND is_system=1
+         )",
+        rusqlite::params!["Перерыв", "#14b8a6", "☕", 7i64],
+    ).ok();
+}
+
+/// Per-period evaluation of timeline goals. Target/operator are copied in so
+/// history stays stable when the goal is later edited.
+pub fn migrate_timeline_goal_results(conn: &rusqlite::Connection) {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS timeline_goal_results (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            goal_id INTEGER NOT NULL,
+            period_start TEXT NOT NULL,
+            period_end TEXT NOT NULL,
+            actual_minutes INTEGER NOT NULL DEFAULT 0,
+            target_minutes INTEGER NOT NULL,
+            operator TEXT NOT NULL,
+            passed INTEGER NOT NULL DEFAULT 0,
+            evaluated_at TEXT NOT NULL,
+            UNIQUE(goal_id, period_start)
+        );"
+    ).ok();
+}
+
+/// "Отвлечения" system timeline type — distraction time from activity snapshots
+/// (timeline reconstruction splits computer time into focus / distraction / AFK).
+pub fn migrate_distraction_timeline_type(conn: &rusqlite::Connection) {
+    conn.execute(
+        "INSERT INTO timeline_activity_types (name, color, icon, is_system, sort_order)
+         SELECT ?1, ?2, ?3, 1, ?4 WHERE NOT EXISTS (
+             SELECT 1 FROM timeline_activity_types WHERE name=?1 AND is_system=1
+         )",
+        rusqlite::params!["Отвлечения", "#eab308", "📱", 8i64],
+    ).ok();
+}
+
+/// Daily screen-time limits per app / site / activity category, and the
+/// escalation log (one row per limit, day and level reached).
+pub fn migrate_screen_limits(conn: &rusqlite::Connection) {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS screen_limits (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind TEXT NOT NULL,
+            target TEXT NOT NULL,
+            weekday_minutes INTEGER NOT NULL,
+            weekend_minutes INTEGER,
+            block INTEGER NOT NULL DEFAULT 0,
+            is_active INTEGER NOT NULL DEFAULT 1,
+            created_at TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS screen_limit_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            limit_id INTEGER NOT NULL,
+            date TEXT NOT NULL,
+            level TEXT NOT NULL,
+            used_minutes REAL NOT NULL DEFAULT 0,
+            limit_minutes INTEGER NOT NULL,
+            at TEXT NOT NULL,
+            UNIQUE(limit_id, date, level)
+        );"
+    ).ok();
+}
+
+/// `events.tz` — IANA zone the event's date/time were entered in ('' = floating,
+/// shown as is wherever you are). Existing timed events were entered on this
+/// machine, so they get the system zone.
+pub fn migrate_event_time_zones(conn: &rusqlite::Connection) {
+    conn.execute("ALTER TABLE events ADD COLUMN tz TEXT NOT NULL DEFAULT ''", []).ok();
+    let system = crate::time_zones::zone_name(crate::time_zones::system_zone());
+    if !system.is_empty() {
+        conn.execute("UPDATE events SET tz=?1 WHERE tz='' AND time != ''", [&system]).ok();
+    }
+}
+
+/// `debt_payments.remaining_before` / `accrual_before` — the debt's state
+/// before each payment, so undoing the latest one restores it exactly.
+pub fn migrate_debt_payment_undo(conn: &rusqlite::Connection) {
+    conn.execute("ALTER TABLE debt_payments ADD COLUMN remaining_before REAL", []).ok();
+    conn.execute("ALTER TABLE debt_payments ADD COLUMN accrual_before TEXT", []).ok();
+}
+
+/// Program week load multiplier a workout was materialized with, so
+/// progression can read its sets back at the unscaled working load.
+pub fn migrate_workout_load_factor(conn: &rusqlite::Connection) {
+    conn.execute("ALTER TABLE workouts ADD COLUMN load_factor REAL", []).ok();
+}
+
+/// Workout heart-rate samples name their track by `workout_tracks.external_id`
+/// (sport + start time, the same on every device) instead of the local
+/// `workouts` rowid, which means nothing once the samples are synced.
+/// `workout_id` stays for rows written by older clients.
+pub fn migrate_hr_sample_tracks(conn: &rusqlite::Connection) {
+    conn.execute("ALTER TABLE heart_rate_samples ADD COLUMN track_id TEXT", []).ok();
+    conn.execute_batch(
+        "UPDATE heart_rate_samples SET track_id=(SELECT t.external_id FROM workout_tracks t WHERE t.workout_id=heart_rate_samples.workout_id)
+         WHERE workout_id IS NOT NULL AND track_id IS NULL;
+        CREATE INDEX IF NOT EXISTS idx_hr_samples_track ON heart_rate_samples(track_id);"
+    ).ok();
+}
@@ -0,0 +1,625 @@
+// focus_enforcer.rs — Focus mode enforcement behind one trait.
+//   macOS: /etc/hosts via `osascript … with administrator privileges`, apps quit
+//          through System Events.
+//   Linux: /etc/hosts via a small root helper run with pkexec. A polkit policy
+//          (installed once, one password prompt) lets the active session run
+//          that one helper without asking again. Apps are stopped with SIGTERM
+//          to the user's own processes.
+// On top of that: blocklist rows with a `schedule` start/stop focus sessions by
+// themselves, and every blocked app relaunch / blocked site visit during a
+// session lands in `focus_tamper_log`.
+use crate::types::{FocusManager, FocusState, HanniDb};
+use chrono::{Datelike, NaiveDateTime, Timelike};
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use tauri::{Emitter, Manager};
+
+pub const HOSTS_START: &str = "# === HANNI FOCUS BLOCKER ===";
+pub const HOSTS_END: &str = "# === END HANNI FOCUS BLOCKER ===";
+/// Same target logged again only after this many seconds.
+const TAMPER_DEDUP_SECS: i64 = 60;
+/// Shorter (normalized) app names would match unrelated session processes.
+const MIN_APP_NAME: usize = 3;
+/// Linux truncates /proc/<pid>/comm to this many bytes.
+const COMM_LEN: usize = 15;
+
+pub trait FocusEnforcer: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn block_sites(&self, sites: &[String]) -> Result<(), String>;
+    fn unblock_sites(&self) -> Result<(), String>;
+    /// Which of `apps` are running right now.
+    fn running(&self, apps: &[String]) -> Vec<String>;
+    fn quit(&self, app: &str);
+    /// One-time setup still pending (Linux helper not installed).
+    fn setup_needed(&self) -> bool { false }
+}
+
+pub fn enforcer() -> &'static dyn FocusEnforcer {
+    static ENFORCER: OnceLock<Box<dyn FocusEnforcer>> = OnceLock::new();
+    ENFORCER.get_or_init(|| {
+        if cfg!(target_os = "linux") { Box::new(LinuxEnforcer) } else { Box::new(MacEnforcer) }
+    }).as_ref()
+}
+
+/// Only valid hostname chars.
+pub fn safe_site(s: &str) -> String {
+    s.chars().filter(|c| c.is_ascii_alphanumeric() || *c == '.' || *c == '-').collect()
+}
+
+/// No quotes or backslashes — app names end up inside AppleScript strings.
+pub fn safe_app(s: &str) -> String {
+    s.chars().filter(|c| c.is_ascii_alphanumeric() || *c == ' ' || *c == '.').collect()
+}
+
+pub fn hosts_has_block() -> bool {
+    std::fs::read_to_string("/etc/hosts").map(|c| c.contains(HOSTS_START)).unwrap_or(false)
+}
+
+/// Hostnames a hosts block for `sites` redirects.
+fn block_hosts(sites: &[String]) -> BTreeSet<String> {
+    sites.iter().map(|s| safe_site(s)).filter(|s| !s.is_empty())
+        .flat_map(|s| [format!("www.{}", s), s]).collect()
+}
+
+/// Hostnames inside our marked block of a hosts file, `None` without a block.
+fn hosts_in_block(hosts: &str) -> Option<BTreeSet<String>> {
+    let start = hosts.find(HOSTS_START)?;
+    let body = &hosts[start + HOSTS_START.len()..];
+    let body = &body[..body.find(HOSTS_END).unwrap_or(body.len())];
+    Some(body.lines().filter_map(|l| l.split_whitespace().nth(1)).map(str::to_string).collect())
+}
+
+/// Make /etc/hosts block exactly `sites`, going through the privileged
+/// helper only when that changes something: no sites and no block, or the
+/// same block already in place, cost no password prompt.
+fn sync_sites(sites: &[String]) -> Result<(), String> {
+    let want = block_hosts(sites);
+    let current = std::fs::read_to_string("/etc/hosts").ok().and_then(|h| hosts_in_block(&h));
+    match current {
+        None if want.is_empty() => Ok(()),
+        Some(_) if want.is_empty() => enforcer().unblock_sites(),
+        Some(have) if have == want => Ok(()),
+        _ => enforcer().block_sites(sites),
+    }
+}
+
+// ── macOS ──
+
+pub struct MacEnforcer;
+
+impl FocusEnforcer for MacEnforcer {
+    fn name(&self) -> &'static str { "macos" }
+
+    fn block_sites(&self, sites: &[String]) -> Result<(), String> {
+        let mut entries = String::new();
+        for site in sites {
+            let s = safe_site(site);
+            if s.is_empty() { continue; }
+            entries.push_str(&format!("127.0.0.1 {}\n127.0.0.1 www.{}\n", s, s));
+        }
+        let block = format!("{}\n{}{}", HOSTS_START, entries, HOSTS_END);
+        // Drop a previous block in the same call, so a re-block is one prompt
+        let script = format!(
+            "do shell script \"sed -i '' '/{}/,/{}/d' /etc/hosts && printf '\\n{}' >> /etc/hosts && dscacheutil -flushcache && killall -HUP mDNSResponder\" with administrator privileges",
+            HOSTS_START, HOSTS_END, block.replace("'", "'\\''").replace("\n", "\\n")
+        );
+        crate::macos::run_osascript(&script).map(|_| ()).map_err(|e| format!("Failed to set focus mode (admin needed): {}", e))
+    }
+
+    fn unblock_sites(&self) -> Result<(), String> {
+        let script = format!(
+            "do shell script \"sed -i '' '/{}/,/{}/d' /etc/hosts && dscacheutil -flushcache && killall -HUP mDNSResponder\" with administrator privileges",
+            HOSTS_START, HOSTS_END
+        );
+        crate::macos::run_osascript(&script).map(|_| ())
+    }
+
+    fn running(&self, apps: &[String]) -> Vec<String> {
+        let Ok(list) = crate::macos::run_osascript("tell application \"System Events\" to get name of every process") else { return Vec::new() };
+        let procs: Vec<String> = list.split(", ").map(|p| p.trim().to_lowercase()).collect();
+        apps.iter().filter(|a| procs.contains(&a.to_lowercase())).cloned().collect()
+    }
+
+    fn quit(&self, app: &str) {
+        let name = safe_app(app);
+        if name.is_empty() { return; }
+        let _ = crate::macos::run_osascript(&format!("tell application \"{}\" to quit", name));
+    }
+}
+
+// ── Linux ──
+
+pub struct LinuxEnforcer;
+
+const HELPER_PATH: &str = "/usr/local/libexec/hanni-focus-hosts";
+const POLICY_PATH: &str = "/usr/share/polkit-1/actions/app.hanni.focus-hosts.policy";
+/// Rewrites only the marked block of /etc/hosts; hostnames are re-validated
+/// here since the polkit policy lets any active-session process call it.
+const HELPER_SCRIPT: &str = r#"#!/bin/sh
+# hanni-focus-hosts — add (apply HOST...) or remove (clear) Hanni's focus block in /etc/hosts
+set -eu
+START='# === HANNI FOCUS BLOCKER ==='
+END='# === END HANNI FOCUS BLOCKER ==='
+tmp=$(mktemp)
+sed "/^$START\$/,/^$END\$/d" /etc/hosts > "$tmp"
+if [ "${1:-}" = apply ]; then
+  shift
+  echo "$START" >> "$tmp"
+  for h in "$@"; do
+    case "$h" in ''|*[!A-Za-z0-9.-]*) continue ;; esac
+    printf '127.0.0.1 %s\n127.0.0.1 www.%s\n::1 %s\n::1 www.%s\n' "$h" "$h" "$h" "$h" >> "$tmp"
+  done
+  echo "$END" >> "$tmp"
+fi
+cat "$tmp" > /etc/hosts
+rm -f "$tmp"
+if command -v resolvectl >/dev/null 2>&1; then resolvectl flush-caches || true; fi
+"#;
+const POLICY: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE policyconfig PUBLIC "-//freedesktop//DTD PolicyKit Policy Configuration 1.0//EN"
+ "http://www.freedesktop.org/standards/PolicyKit/1/policyconfig.dtd">
+<policyconfig>
+  <action id="app.hanni.focus-hosts">
+    <description>Hanni focus mode site blocking</description>
+    <message>Hanni needs to update /etc/hosts to block distracting sites</message>
+    <defaults>
+      <allow_any>no</allow_any>
+      <allow_inactive>no</allow_inactive>
+      <allow_active>yes</allow_active>
+    </defaults>
+    <annotate key="org.freedesktop.policykit.exec.path">/usr/local/libexec/hanni-focus-hosts</annotate>
+  </action>
+</policyconfig>
+"#;
+
+impl LinuxEnforcer {
+    fn ensure_helper(&self) -> Result<(), String> {
+        if !self.setup_needed() { return Ok(()); }
+        let dir = crate::types::hanni_data_dir().join("focus-helper");
+        std::fs::create_dir_all(&dir).map_err(|e| format!("Dir error: {}", e))?;
+        let (script, policy) = (dir.join("hanni-focus-hosts"), dir.join("app.hanni.focus-hosts.policy"));
+        std::fs::write(&script, HELPER_SCRIPT).map_err(|e| format!("Write error: {}", e))?;
+        std::fs::write(&policy, POLICY).map_err(|e| format!("Write error: {}", e))?;
+        let install = format!("install -D -m 755 \"$1\" {} && install -D -m 644 \"$2\" {}", HELPER_PATH, POLICY_PATH);
+        let status = std::process::Command::new("pkexec")
+            .args(["sh", "-c", &install, "sh"])
+            .arg(&script).arg(&policy)
+            .status()
+            .map_err(|e| format!("pkexec недоступен: {}", e))?;
+        if status.success() { Ok(()) } else { Err("Установка помощника блокировки отменена".into()) }
+    }
+
+    fn helper(&self, args: &[&str]) -> Result<(), String> {
+        self.ensure_helper()?;
+        let status = std::process::Command::new("pkexec").arg(HELPER_PATH).args(args).status()
+            .map_err(|e| format!("pkexec недоступен: {}", e))?;
+        if status.success() { Ok(()) } else { Err("Не удалось изменить /etc/hosts".into()) }
+    }
+}
+
+/// Real uid from /proc/<pid>/status.
+fn proc_uid(pid: &str) -> Option<String> {
+    let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    status.lines().find_map(|l| l.strip_prefix("Uid:")).and_then(|v| v.split_whitespace().next()).map(str::to_string)
+}
+
+fn normalize(s: &str) -> String {
+    s.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+/// App names from the blocklist must be long enough to name one program.
+pub(crate) fn valid_app_name(app: &str) -> bool {
+    normalize(app).chars().count() >= MIN_APP_NAME
+}
+
+/// Does a process (comm / exe basename) belong to a blocked app name? Exact
+/// match only — a prefix would let "gnome" take down gnome-shell. The one
+/// exception is comm cut at 15 bytes: "telegram-deskto" is "Telegram Desktop".
+pub(crate) fn process_matches(proc_name: &str, app: &str) -> bool {
+    if !valid_app_name(app) { return false; }
+    let (p, a) = (normalize(proc_name), normalize(app));
+    if p.is_empty() { return false; }
+    p == a || (proc_name.len() == COMM_LEN && a.starts_with(&p))
+}
+
+/// (pid, names) of the current user's processes, excluding ourselves.
+fn own_processes() -> Vec<(String, Vec<String>)> {
+    let me = std::process::id().to_string();
+    let Some(uid) = proc_uid("self") else { return Vec::new() };
+    let Ok(dir) = std::fs::read_dir("/proc") else { return Vec::new() };
+    dir.flatten()
+        .filter_map(|e| e.file_name().into_string().ok())
+        .filter(|pid| pid.chars().all(|c| c.is_ascii_digit()) && *pid != me)
+        .filter(|pid| proc_uid(pid).as_deref() == Some(uid.as_str()))
+        .map(|pid| {
+            let mut names = Vec::new();
+            if let Ok(comm) = std::fs::read_to_string(format!("/proc/{}/comm", pid)) { names.push(comm.trim().to_string()); }
+            if let Some(exe) = std::fs::read_link(format!("/proc/{}/exe", pid)).ok().and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned())) {
+                names.push(exe);
+            }
+            (pid, names)
+        })
+        .collect()
+}
+
+impl FocusEnforcer for LinuxEnforcer {
+    fn name(&self) -> &'static str { "linux" }
+
+    fn block_sites(&self, sites: &[String]) -> Result<(), String> {
+        let sites: Vec<String> = sites.iter().map(|s| safe_site(s)).filter(|s| !s.is_empty()).collect();
+        let mut args = vec!["apply"];
+        args.extend(sites.iter().map(String::as_str));
+        self.helper(&args)
+    }
+
+    fn unblock_sites(&self) -> Result<(), String> { self.helper(&["clear"]) }
+
+    fn running(&self, apps: &[String]) -> Vec<String> {
+        let procs = own_processes();
+        apps.iter().filter(|a| procs.iter().any(|(_, names)| names.iter().any(|n| process_matches(n, a)))).cloned().collect()
+    }
+
+    fn quit(&self, app: &str) {
+        for (pid, names) in own_processes() {
+            if names.iter().any(|n| process_matches(n, app)) {
+                let _ = std::process::Command::new("kill").args(["-TERM", &pid]).status();
+            }
+        }
+    }
+
+    fn setup_needed(&self) -> bool {
+        std::fs::read_to_string(HELPER_PATH).ok().as_deref() != Some(HELPER_SCRIPT)
+            || !std::path::Path::new(POLICY_PATH).exists()
+    }
+}
+
+// ── Schedules ──
+
+/// One `blocklist.schedule` window. Days are a Monday-first bitmask; minutes
+/// since midnight; `start > end` runs past midnight.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Window {
+    pub days: u8,
+    pub start: u32,
+    pub end: u32,
+}
+
+const DAY_NAMES: [[&str; 2]; 7] = [["mon", "пн"], ["tue", "вт"], ["wed", "ср"], ["thu", "чт"], ["fri", "пт"], ["sat", "сб"], ["sun", "вс"]];
+
+fn day_index(s: &str) -> Option<u32> {
+    let s = s.to_lowercase();
+    DAY_NAMES.iter().position(|names| names.iter().any(|n| s.starts_with(n))).map(|i| i as u32)
+}
+
+fn parse_days(spec: &str) -> Result<u8, String> {
+    let bad = || format!("Не понял дни: {}", spec);
+    match spec.to_lowercase().as_str() {
+        "daily" | "ежедневно" => return Ok(0x7f),
+        "weekdays" | "будни" => return Ok(0x1f),
+        "weekends" | "выходные" => return Ok(0x60),
+        _ => {}
+    }
+    let mut mask = 0u8;
+    for part in spec.split(',').filter(|p| !p.is_empty()) {
+        match part.split_once('-') {
+            Some((a, b)) => {
+                let (a, b) = (day_index(a).ok_or_else(bad)?, day_index(b).ok_or_else(bad)?);
+                let mut d = a;
+                loop {
+                    mask |= 1 << d;
+                    if d == b { break; }
+                    d = (d + 1) % 7;
+                }
+            }
+            None => mask |= 1 << day_index(part).ok_or_else(bad)?,
+        }
+    }
+    if mask == 0 { Err(bad()) } else { Ok(mask) }
+}
+
+fn parse_hm(s: &str) -> Option<u32> {
+    let (h, m) = s.trim().split_once(':')?;
+    let (h, m) = (h.parse::<u32>().ok()?, m.parse::<u32>().ok()?);
+    (h <= 24 && m < 60 && h * 60 + m <= 1440).then_some(h * 60 + m)
+}
+
+/// `"mon-fri 09:00-12:00; sat 10:00-11:00"`, `"22:00-07:00"`, `"будни 9:00-18:00"`.
+pub fn parse_schedule(spec: &str) -> Result<Vec<Window>, String> {
+    spec.split(';').map(str::trim).filter(|w| !w.is_empty()).map(|w| {
+        let (days, time) = match w.rsplit_once(char::is_whitespace) {
+            Some((d, t)) => (parse_days(&d.split_whitespace().collect::<String>())?, t),
+            None => (0x7f, w),
+        };
+        let (a, b) = time.split_once('-').ok_or_else(|| format!("Нужно время вида 09:00-12:00: {}", w))?;
+        let (start, end) = (parse_hm(a), parse_hm(b));
+        match (start, end) {
+            (Some(start), Some(end)) if start != end => Ok(Window { days, start, end }),
+            _ => Err(format!("Нужно время вида 09:00-12:00: {}", w)),
+        }
+    }).collect()
+}
+
+pub fn schedule_active(windows: &[Window], now: NaiveDateTime) -> bool {
+    let day = now.weekday().num_days_from_monday();
+    let yesterday = (day + 6) % 7;
+    let t = now.hour() * 60 + now.minute();
+    windows.iter().any(|w| {
+        let on = |d: u32| w.days & (1 << d) != 0;
+        if w.start < w.end { on(day) && t >= w.start && t < w.end }
+        else { (on(day) && t >= w.start) || (on(yesterday) && t < w.end) }
+    })
+}
+
+/// Apps and sites whose blocklist schedule covers `now`.
+pub fn scheduled_targets(conn: &rusqlite::Connection, now: NaiveDateTime) -> (Vec<String>, Vec<String>) {
+    let rows: Vec<(String, String, String)> = conn.prepare(
+        "SELECT type, value, schedule FROM blocklist WHERE active=1 AND COALESCE(schedule,'') != '' ORDER BY id"
+    ).and_then(|mut stmt| stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))?.collect())
+        .unwrap_or_default();
+    let (mut apps, mut sites) = (Vec::new(), Vec::new());
+    for (kind, value, schedule) in rows {
+        if !parse_schedule(&schedule).is_ok_and(|w| schedule_active(&w, now)) { continue; }
+        let list = if kind == "site" { &mut sites } else { &mut apps };
+        if !list.contains(&value) { list.push(value); }
+    }
+    (apps, sites)
+}
+
+// ── Sessions ──
+
+/// A session start is in progress (its hosts write may sit on a password prompt).
+static STARTING: AtomicBool = AtomicBool::new(false);
+/// Targets of a scheduled start that failed (prompt cancelled, no pkexec):
+/// not retried until the schedule's targets change or its window ends.
+static SCHEDULE_FAILED: Mutex<Option<(Vec<String>, Vec<String>)>> = Mutex::new(None);
+
+/// Privileged half of a session start: hosts block and quitting running apps.
+fn enforce(apps: &[String], sites: &[String]) -> Result<(), String> {
+    sync_sites(sites)?;
+    let e = enforcer();
+    for app in e.running(apps) { e.quit(&app); }
+    Ok(())
+}
+
+fn activate(state: &mut FocusState, apps: Vec<String>, sites: Vec<String>, end_time: Option<chrono::DateTime<chrono::Local>>, scheduled: bool) {
+    state.active = true;
+    state.end_time = end_time;
+    state.started_at = Some(chrono::Local::now());
+    state.scheduled = scheduled;
+    state.owner = None;
+    state.blocked_apps = apps;
+    state.blocked_sites = sites;
+    state.monitor_running.store(true, Ordering::Relaxed);
+}
+
+/// Start a session without holding the FocusManager lock over the hosts
+/// write, so the monitor and note_activity keep running behind a prompt.
+/// `Ok(false)` when another session is active or already starting.
+pub(crate) fn start(focus: &FocusManager, apps: Vec<String>, sites: Vec<String>, end_time: Option<chrono::DateTime<chrono::Local>>, scheduled: bool) -> Result<bool, String> {
+    if focus.0.lock().unwrap_or_else(|e| e.into_inner()).active { return Ok(false); }
+    if STARTING.swap(true, Ordering::SeqCst) { return Ok(false); }
+    let started = enforce(&apps, &sites).map(|()| {
+        let mut state = focus.0.lock().unwrap_or_else(|e| e.into_inner());
+        if state.active { return false; }
+        activate(&mut state, apps, sites, end_time, scheduled);
+        true
+    });
+    STARTING.store(false, Ordering::SeqCst);
+    started
+}
+
+/// Point a running session at new targets: one hosts rewrite (none when the
+/// sites are unchanged) instead of end + begin.
+fn retarget(focus: &FocusManager, apps: Vec<String>, sites: Vec<String>) -> Result<(), String> {
+    sync_sites(&sites)?;
+    let mut state = focus.0.lock().unwrap_or_else(|e| e.into_inner());
+    state.blocked_apps = apps;
+    state.blocked_sites = sites;
+    Ok(())
+}
+
+pub(crate) fn end(state: &mut FocusState) {
+    if hosts_has_block() { let _ = enforcer().unblock_sites(); }
+    state.active = false;
+    state.end_time = None;
+    state.started_at = None;
+    state.scheduled = false;
+    state.owner = None;
+    state.blocked_apps.clear();
+    state.blocked_sites.clear();
+    state.monitor_running.store(false, Ordering::Relaxed);
+}
+
+fn log_attempt(conn: &rusqlite::Connection, session: &str, kind: &str, target: &str, action: &str) {
+    let now = chrono::Local::now();
+    let recent: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM focus_tamper_log WHERE kind=?1 AND target=?2 AND at >= ?3",
+        rusqlite::params![kind, target, (now - chrono::Duration::seconds(TAMPER_DEDUP_SECS)).to_rfc3339()],
+        |r| r.get(0),
+    ).unwrap_or(0);
+    if recent > 0 { return; }
+    let _ = conn.execute(
+        "INSERT INTO focus_tamper_log (session_started_at, at, kind, target, action) VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![session, now.to_rfc3339(), kind, target, action],
+    );
+}
+
+/// Blocked site the user is looking at, if any. Matches the browser URL by
+/// domain, and the window title by the site's name (Linux has no URL).
+pub(crate) fn site_attempt(sites: &[String], browser: &str, title: &str) -> Option<String> {
+    let hosts = crate::activity_rules::domains_in(browser);
+    let title = title.to_lowercase();
+    sites.iter().find(|s| {
+        let s = s.to_lowercase();
+        let stem = s.split('.').next().unwrap_or("");
+        hosts.iter().any(|h| *h == s || h.ends_with(&format!(".{}", s)))
+            || (stem.len() >= 4 && title.contains(stem))
+    }).cloned()
+}
+
+/// One monitor step (every 2 s, blocking): schedule on/off when
+/// `check_schedule`, expiry, and quitting relaunched apps.
+pub fn tick(handle: &tauri::AppHandle, check_schedule: bool) {
+    let focus = handle.state::<FocusManager>();
+    let db = handle.state::<HanniDb>();
+    if check_schedule {
+        let (apps, sites) = scheduled_targets(&db.read(), chrono::Local::now().naive_local());
+        let want = !apps.is_empty() || !sites.is_empty();
+        let (active, scheduled, changed) = {
+            let state = focus.0.lock().unwrap_or_else(|e| e.into_inner());
+            (state.active, state.scheduled, state.blocked_apps != apps || state.blocked_sites != sites)
+        };
+        let mut failed = SCHEDULE_FAILED.lock().unwrap_or_else(|e| e.into_inner());
+        if !want || failed.as_ref().is_some_and(|f| *f != (apps.clone(), sites.clone())) { *failed = None; }
+        // Manual sessions are never touched by the scheduler.
+        if active && scheduled && !want {
+            end(&mut focus.0.lock().unwrap_or_else(|e| e.into_inner()));
+            let _ = handle.emit("focus-ended", ());
+        } else if active && scheduled && changed && failed.is_none() {
+            if let Err(e) = retarget(&focus, apps.clone(), sites.clone()) {
+                eprintln!("[focus] scheduled re-block failed, not retrying this window: {}", e);
+                *failed = Some((apps, sites));
+            }
+        } else if want && !active && failed.is_none() {
+            match start(&focus, apps.clone(), sites.clone(), None, true) {
+                Ok(true) => { let _ = handle.emit("focus-started", "schedule"); }
+                Ok(false) => {}
+                Err(e) => {
+                    eprintln!("[focus] scheduled start failed, not retrying this window: {}", e);
+                    *failed = Some((apps, sites));
+                }
+            }
+        }
+    }
+
+    let (active, end_time, apps, started) = {
+        let state = focus.0.lock().unwrap_or_else(|e| e.into_inner());
+        (state.active, state.end_time, state.blocked_apps.clone(), state.started_at)
+    };
+    if !active { return; }
+    if end_time.is_some_and(|end| chrono::Local::now() >= end) {
+        end(&mut focus.0.lock().unwrap_or_else(|e| e.into_inner()));
+        let _ = handle.emit("focus-ended", ());
+        return;
+    }
+    let running = enforcer().running(&apps);
+    if running.is_empty() { return; }
+    let session = started.map(|s| s.to_rfc3339()).unwrap_or_default();
+    for app in running {
+        enforcer().quit(&app);
+        log_attempt(&db.conn(), &session, "app", &app, "quit");
+    }
+}
+
+/// Called by the snapshot collector with the current browser/window.
+pub fn note_activity(handle: &tauri::AppHandle, browser: &str, title: &str) {
+    let (sites, started) = {
+        let state = handle.state::<FocusManager>();
+        let state = state.0.lock().unwrap_or_else(|e| e.into_inner());
+        if !state.active { return; }
+        (state.blocked_sites.clone(), state.started_at)
+    };
+    if let Some(site) = site_attempt(&sites, browser, title) {
+        let session = started.map(|s| s.to_rfc3339()).unwrap_or_default();
+        log_attempt(&handle.state::<HanniDb>().conn(), &session, "site", &site, "blocked");
+    }
+}
+
+// ── Commands ──
+
+#[tauri::command]
+pub fn get_focus_enforcer_status(db: tauri::State<'_, HanniDb>) -> Result<serde_json::Value, String> {
+    let (apps, sites) = scheduled_targets(&db.read(), chrono::Local::now().naive_local());
+    let e = enforcer();
+    Ok(serde_json::json!({
+        "backend": e.name(), "setup_needed": e.setup_needed(),
+        "scheduled_now": { "apps": apps, "sites": sites },
+    }))
+}
+
+/// Attempts to open blocked apps/sites during focus sessions, newest first,
+/// plus per-target totals.
+#[tauri::command]
+pub fn get_focus_tamper_log(days: Option<i64>, db: tauri::State<'_, HanniDb>) -> Result<serde_json::Value, String> {
+    let since = (chrono::Local::now() - chrono::Duration::days(days.unwrap_or(7).max(1))).to_rfc3339();
+    let conn = db.read();
+    let mut stmt = conn.prepare(
+        "SELECT id, session_started_at, at, kind, target, action FROM focus_tamper_log WHERE at >= ?1 ORDER BY at DESC LIMIT 200"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let entries: Vec<serde_json::Value> = stmt.query_map([&since], |r| Ok(serde_json::json!({
+        "id": r.get::<_, i64>(0)?, "session_started_at": r.get::<_, String>(1)?, "at": r.get::<_, String>(2)?,
+        "kind": r.get::<_, String>(3)?, "target": r.get::<_, String>(4)?, "action": r.get::<_, String>(5)?,
+    }))).map_err(|e| format!("Query error: {}", e))?.flatten().collect();
+    let mut stmt = conn.prepare(
+        "SELECT kind, target, COUNT(*), COUNT(DISTINCT session_started_at) FROM focus_tamper_log WHERE at >= ?1
+         GROUP BY kind, target ORDER BY COUNT(*) DESC"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let totals: Vec<serde_json::Value> = stmt.query_map([&since], |r| Ok(serde_json::json!({
+        "kind": r.get::<_, String>(0)?, "target": r.get::<_, String>(1)?,
+        "count": r.get::<_, i64>(2)?, "sessions": r.get::<_, i64>(3)?,
+    }))).map_err(|e| format!("Query error: {}", e))?.flatten().collect();
+    Ok(serde_json::json!({ "entries": entries, "totals": totals }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> NaiveDateTime { NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap() }
+
+    #[test]
+    fn parses_and_evaluates_schedules() {
+        let w = parse_schedule("mon-fri 09:00-12:00; sat 10:00-11:00").unwrap();
+        assert_eq!(w[0], Window { days: 0x1f, start: 540, end: 720 });
+        // 2024-06-03 is a Monday.
+        assert!(schedule_active(&w, at("2024-06-03 09:00")));
+        assert!(!schedule_active(&w, at("2024-06-03 12:00")));
+        assert!(schedule_active(&w, at("2024-06-08 10:30")));
+        assert!(!schedule_active(&w, at("2024-06-09 10:30")));
+
+        let night = parse_schedule("пт-сб 23:00-02:00").unwrap();
+        assert!(schedule_active(&night, at("2024-06-07 23:30")));
+        assert!(schedule_active(&night, at("2024-06-09 01:00"))); // Saturday's window, Sunday morning
+        assert!(!schedule_active(&night, at("2024-06-10 01:00")));
+        assert_eq!(parse_schedule("sun-tue 8:00-9:00").unwrap()[0].days, 0b100_0011);
+        assert_eq!(parse_schedule("будни 9:00-18:00").unwrap()[0].days, 0x1f);
+
+        assert!(parse_schedule("mon 9-12").is_err());
+        assert!(parse_schedule("funday 09:00-10:00").is_err());
+        assert!(parse_schedule("10:00-10:00").is_err());
+    }
+
+    #[test]
+    fn matches_processes() {
+        assert!(!process_matches("telegram-deskto", "Telegram"));
+        assert!(process_matches("telegram-deskto", "Telegram Desktop"));
+        assert!(process_matches("telegram-desktop", "Telegram Desktop"));
+        assert!(!process_matches("gnome-shell", "gnome"));
+        assert!(!process_matches("Xwayland", "x"));
+        assert!(!process_matches("code-insiders", "code"));
+        assert!(!valid_app_name("vs"));
+        assert!(process_matches("Discord", "discord"));
+        assert!(!process_matches("code", "Codeium"));
+        assert!(!process_matches("sh", "Slack"));
+    }
+
+    #[test]
+    fn compares_hosts_blocks() {
+        let sites = vec!["youtube.com".to_string(), "!!!".to_string()];
+        let hosts = format!("127.0.0.1 localhost\n{}\n127.0.0.1 youtube.com\n127.0.0.1 www.youtube.com\n::1 youtube.com\n{}\n", HOSTS_START, HOSTS_END);
+        assert_eq!(hosts_in_block(&hosts), Some(block_hosts(&sites)));
+        assert_eq!(hosts_in_block("127.0.0.1 localhost\n"), None);
+        assert_eq!(hosts_in_block(&format!("{}\n{}", HOSTS_START, HOSTS_END)), Some(BTreeSet::new()));
+        assert!(block_hosts(&[]).is_empty());
+    }
+
+    #[test]
+    fn detects_site_attempts() {
+        let sites = vec!["youtube.com".to_string(), "x.com".to_string()];
+        assert_eq!(site_attempt(&sites, "Arc: https://m.youtube.com/watch | cats", ""), Some("youtube.com".into()));
+        assert_eq!(site_attempt(&sites, "Firefox: Home", "Home / X — Mozilla Firefox"), None);
+        assert_eq!(site_attempt(&sites, "", "Cats - YouTube — Mozilla Firefox"), Some("youtube.com".into()));
+        assert_eq!(site_attempt(&sites, "Safari: https://x.com/home | Home", ""), Some("x.com".into()));
+    }
+}
@@ -0,0 +1,409 @@
+// habits.rs — Habit engine: structured schedules (daily, weekdays, N per week,
+// every N days), numeric targets with partial completion, freeze days, and
+// schedule-aware streak/consistency. Habits linked to a data source
+// (`auto_source`) are checked automatically, like calendar_health does for
+// schedules. Commands stay in commands_data.rs; this is the logic behind them.
+use chrono::{Datelike, NaiveDate};
+use std::collections::HashMap;
+
+/// Freeze days allowed per calendar month, per habit.
+pub const FREEZES_PER_MONTH: i64 = 2;
+/// How far back streaks are walked.
+const STREAK_LOOKBACK_DAYS: i64 = 730;
+const CONSISTENCY_DAYS: i64 = 30;
+const CONSISTENCY_WEEKS: i64 = 4;
+/// Data sources a habit can be auto-checked from.
+pub const AUTO_SOURCES: &[&str] = &["steps", "sleep", "workout", "cooking", "water"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Schedule {
+    Daily,
+    /// Bitmask, Monday = bit 0 … Sunday = bit 6.
+    Days(u8),
+    /// Any N days within a Monday–Sunday week.
+    PerWeek(i64),
+    /// Every N days counting from `anchor` (the habit's creation date).
+    EveryNDays { n: i64, anchor: NaiveDate },
+}
+
+impl Schedule {
+    /// Build from the stored columns. Rows written by older clients only have
+    /// the free-form `frequency` string, so fall back to it.
+    pub fn from_parts(kind: Option<&str>, days: i64, count: i64, frequency: &str, anchor: NaiveDate) -> Self {
+        match kind.unwrap_or("").trim() {
+            "daily" => Schedule::Daily,
+            "days" if days & 0x7f != 0 => Schedule::Days((days & 0x7f) as u8),
+            "per_week" => Schedule::PerWeek(count.clamp(1, 7)),
+            "every_n_days" => Schedule::EveryNDays { n: count.max(1), anchor },
+            "" => Self::from_frequency(frequency),
+            _ => Schedule::Daily,
+        }
+    }
+
+    pub fn from_frequency(frequency: &str) -> Self {
+        match frequency.trim() {
+            "weekdays" => Schedule::Days(0b0011111),
+            "weekends" => Schedule::Days(0b1100000),
+            "weekly" => Schedule::PerWeek(1),
+            _ => Schedule::Daily,
+        }
+    }
+
+    /// (schedule_kind, schedule_days, schedule_count) for storage.
+    pub fn to_parts(self) -> (&'static str, i64, i64) {
+        match self {
+            Schedule::Daily => ("daily", 0, 1),
+            Schedule::Days(mask) => ("days", mask as i64, 1),
+            Schedule::PerWeek(n) => ("per_week", 0, n),
+            Schedule::EveryNDays { n, .. } => ("every_n_days", 0, n),
+        }
+    }
+
+    /// Whether `date` is a day the habit is expected. Per-week habits have no
+    /// fixed days — any day may count.
+    pub fn is_due(&self, date: NaiveDate) -> bool {
+        match *self {
+            Schedule::Daily | Schedule::PerWeek(_) => true,
+            Schedule::Days(mask) => mask & (1 << date.weekday().num_days_from_monday()) != 0,
+            Schedule::EveryNDays { n, anchor } => date >= anchor && (date - anchor).num_days() % n == 0,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        const NAMES: [&str; 7] = ["Пн", "Вт", "Ср", "Чт", "Пт", "Сб", "Вс"];
+        match *self {
+            Schedule::Daily => "Ежедневно".into(),
+            Schedule::Days(0b0011111) => "Будни".into(),
+            Schedule::Days(0b1100000) => "Выходные".into(),
+            Schedule::Days(mask) => (0..7).filter(|i| mask & (1 << i) != 0).map(|i| NAMES[i]).collect::<Vec<_>>().join(", "),
+            Schedule::PerWeek(n) => format!("{} раз(а) в неделю", n),
+            Schedule::EveryNDays { n, .. } => format!("Каждые {} дн.", n),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mark {
+    Done,
+    /// Fraction of the target reached, 0 < f < 1.
+    Partial(f64),
+    Frozen,
+}
+
+impl Mark {
+    pub fn from_value(value: f64, target: f64) -> Option<Mark> {
+        if value <= 0.0 { None } else if value >= target { Some(Mark::Done) } else { Some(Mark::Partial(value / target)) }
+    }
+    fn credit(self) -> f64 {
+        match self { Mark::Done => 1.0, Mark::Partial(f) => f, Mark::Frozen => 0.0 }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HabitStats {
+    pub streak: i64,
+    pub best_streak: i64,
+    /// "day" or "week" (per-week habits count streaks in weeks).
+    pub streak_unit: &'static str,
+    /// Share of due days (or weeks) met recently, 0–100.
+    pub consistency: f64,
+    /// Per-week habits: done this week vs required.
+    pub week_done: i64,
+    pub week_target: i64,
+}
+
+fn monday(d: NaiveDate) -> NaiveDate {
+    d - chrono::Duration::days(d.weekday().num_days_from_monday() as i64)
+}
+
+/// Streaks and consistency for one habit. `today` being unchecked never breaks
+/// the streak (the day isn't over); frozen days keep it without adding to it.
+pub fn stats(schedule: Schedule, marks: &HashMap<NaiveDate, Mark>, created: NaiveDate, today: NaiveDate) -> HabitStats {
+    match schedule {
+        Schedule::PerWeek(n) => week_stats(n, marks, created, today),
+        _ => day_stats(schedule, marks, created, today),
+    }
+}
+
+fn day_stats(schedule: Schedule, marks: &HashMap<NaiveDate, Mark>, created: NaiveDate, today: NaiveDate) -> HabitStats {
+    let start = created.max(today - chrono::Duration::days(STREAK_LOOKBACK_DAYS));
+    let mut s = HabitStats { streak_unit: "day", ..Default::default() };
+
+    let mut run = 0;
+    for d in start.iter_days().take_while(|d| *d <= today) {
+        if !schedule.is_due(d) { continue; }
+        match marks.get(&d) {
+            Some(Mark::Done) => { run += 1; s.best_streak = s.best_streak.max(run); }
+            Some(Mark::Frozen) => {}
+            _ if d == today => {}
+            _ => run = 0,
+        }
+    }
+    s.streak = run;
+
+    let (mut credit, mut due) = (0.0, 0.0);
+    let from = created.max(today - chrono::Duration::days(CONSISTENCY_DAYS - 1));
+    for d in from.iter_days().take_while(|d| *d <= today) {
+        if !schedule.is_due(d) { continue; }
+        match marks.get(&d) {
+            Some(Mark::Frozen) => continue,
+            None if d == today => continue,
+            m => { due += 1.0; credit += m.map(|m| m.credit()).unwrap_or(0.0); }
+        }
+    }
+    s.consistency = if due > 0.0 { (credit / due * 1000.0).round() / 10.0 } else { 0.0 };
+    s
+}
+
+fn week_stats(n: i64, marks: &HashMap<NaiveDate, Mark>, created: NaiveDate, today: NaiveDate) -> HabitStats {
+    let week_of = |start: NaiveDate| {
+        let (mut done, mut frozen) = (0, 0);
+        for d in start.iter_days().take(7) {
+            match marks.get(&d) {
+                Some(Mark::Done) => done += 1,
+                Some(Mark::Frozen) => frozen += 1,
+                _ => {}
+            }
+        }
+        (done, (n - frozen).max(0))
+    };
+    let this_week = monday(today);
+    let first_week = monday(created.max(today - chrono::Duration::days(STREAK_LOOKBACK_DAYS)));
+    let mut s = HabitStats { streak_unit: "week", ..Default::default() };
+    let (done, need) = week_of(this_week);
+    s.week_done = done;
+    s.week_target = n;
+
+    let mut run = 0;
+    let mut w = first_week;
+    while w <= this_week {
+        let (done, need) = week_of(w);
+        if need == 0 {
+            // fully frozen week: neutral
+        } else if done >= need {
+            run += 1;
+            s.best_streak = s.best_streak.max(run);
+        } else if w != this_week {
+            run = 0;
+        }
+        w += chrono::Duration::days(7);
+    }
+    s.streak = run;
+
+    // Consistency over the last full weeks, plus this one if already met.
+    let (mut credit, mut weeks) = (0.0, 0.0);
+    for k in 1..=CONSISTENCY_WEEKS {
+        let w = this_week - chrono::Duration::days(7 * k);
+        if w + chrono::Duration::days(6) < created { break; }
+        let (done, need) = week_of(w);
+        if need == 0 { continue; }
+        weeks += 1.0;
+        credit += (done as f64 / need as f64).min(1.0);
+    }
+    if need > 0 && done >= need { weeks += 1.0; credit += 1.0; }
+    s.consistency = if weeks > 0.0 { (credit / weeks * 1000.0).round() / 10.0 } else { 0.0 };
+    s
+}
+
+/// Validate a schedule coming from the UI.
+pub(crate) fn parse_schedule(kind: &str, days: i64, count: i64) -> Result<Schedule, String> {
+    let today = chrono::Local::now().date_naive();
+    match kind {
+        "days" if days & 0x7f == 0 => Err("Выберите хотя бы один день недели".into()),
+        "per_week" if !(1..=7).contains(&count) => Err("Раз в неделю: от 1 до 7".into()),
+        "every_n_days" if !(1..=365).contains(&count) => Err("Интервал: от 1 до 365 дней".into()),
+        "daily" | "days" | "per_week" | "every_n_days" => Ok(Schedule::from_parts(Some(kind), days, count, "", today)),
+        other => Err(format!("Неизвестное расписание: {}", other)),
+    }
+}
+
+// ── DB side ──
+
+/// Marks for one habit from `habit_checks`. Rows from older clients have no
+/// status and mean "done".
+pub(crate) fn load_marks(conn: &rusqlite::Connection, habit_id: i64, target: f64, since: &str) -> HashMap<NaiveDate, Mark> {
+    let Ok(mut stmt) = conn.prepare(
+        "SELECT date, COALESCE(status,'done'), value FROM habit_checks WHERE habit_id=?1 AND date>=?2 AND completed=1"
+    ) else { return HashMap::new() };
+    stmt.query_map(rusqlite::params![habit_id, since], |r| {
+        Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?, r.get::<_, Option<f64>>(2)?))
+    }).map(|rows| rows.filter_map(|r| r.ok()).filter_map(|(date, status, value)| {
+        let d = NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok()?;
+        let mark = match status.as_str() {
+            "frozen" => Mark::Frozen,
+            _ => match value {
+                Some(v) => Mark::from_value(v, target)?,
+                None => Mark::Done,
+            },
+        };
+        Some((d, mark))
+    }).collect()).unwrap_or_default()
+}
+
+/// Write a check: value ≤ 0 removes it. A manual clear leaves an uncompleted
+/// 'cleared' row behind, so `auto_check` doesn't fill the day in again. Status
+/// follows the target.
+pub(crate) fn set_value(conn: &rusqlite::Connection, habit_id: i64, date: &str, value: f64, target: f64, source: &str) -> rusqlite::Result<()> {
+    if value <= 0.0 && source == "manual" {
+        conn.execute(
+            "INSERT INTO habit_checks (habit_id, date, completed, value, status, source, created_at) VALUES (?1, ?2, 0, 0, 'cleared', 'manual', ?3)
+             ON CONFLICT(habit_id, date) DO UPDATE SET completed=0, value=0, status='cleared', source='manual'",
+            rusqlite::params![habit_id, date, chrono::Local::now().to_rfc3339()],
+        )?;
+        return Ok(());
+    }
+    if value <= 0.0 {
+        conn.execute("DELETE FROM habit_checks WHERE habit_id=?1 AND date=?2", rusqlite::params![habit_id, date])?;
+        return Ok(());
+    }
+    let status = if value >= target { "done" } else { "partial" };
+    conn.execute(
+        "INSERT INTO habit_checks (habit_id, date, completed, value, status, source, created_at) VALUES (?1, ?2, 1, ?3, ?4, ?5, ?6)
+         ON CONFLICT(habit_id, date) DO UPDATE SET completed=1, value=?3, status=?4, source=?5",
+        rusqlite::params![habit_id, date, value, status, source, chrono::Local::now().to_rfc3339()],
+    )?;
+    Ok(())
+}
+
+/// Freezes used in `date`'s month.
+pub(crate) fn freezes_used(conn: &rusqlite::Connection, habit_id: i64, date: &str) -> i64 {
+    conn.query_row(
+        "SELECT COUNT(*) FROM habit_checks WHERE habit_id=?1 AND status='frozen' AND substr(date,1,7)=substr(?2,1,7)",
+        rusqlite::params![habit_id, date], |r| r.get(0),
+    ).unwrap_or(0)
+}
+
+/// Today's value of a data source, in the habit's natural unit: steps, sleep
+/// minutes, workout minutes, dishes cooked, water (as logged).
+fn source_value(conn: &rusqlite::Connection, source: &str, date: &str) -> Option<f64> {
+    let sql = match source {
+        "steps" => "SELECT COALESCE(SUM(value),0) FROM health_log WHERE date=?1 AND type='steps'",
+        "water" => "SELECT COALESCE(SUM(value),0) FROM health_log WHERE date=?1 AND type='water'",
+        "sleep" => "SELECT COALESCE(SUM(duration_minutes),0) FROM sleep_sessions WHERE date=?1",
+        "workout" => "SELECT COALESCE(SUM(duration_minutes),0) FROM workouts WHERE date=?1",
+        "cooking" => "SELECT COUNT(*) FROM cooking_log WHERE date=?1",
+        _ => return None,
+    };
+    conn.query_row(sql, [date], |r| r.get::<_, f64>(0)).ok()
+}
+
+/// Check habits linked to a data source for `date`. Manual checks, clears and
+/// freezes win over auto values; auto rows are refreshed as the data grows.
+pub(crate) fn auto_check(conn: &rusqlite::Connection, date: &str) {
+    let habits: Vec<(i64, String, f64)> = conn.prepare(
+        "SELECT id, auto_source, target FROM habits WHERE COALESCE(auto_source,'') != ''"
+    ).and_then(|mut s| s.query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))?.collect())
+        .unwrap_or_default();
+    for (id, source, target) in habits {
+        let existing: Option<String> = conn.query_row(
+            "SELECT COALESCE(source,'manual') FROM habit_checks WHERE habit_id=?1 AND date=?2",
+            rusqlite::params![id, date], |r| r.get(0),
+        ).ok();
+        if existing.as_deref().is_some_and(|s| s != "auto") { continue; }
+        if let Some(v) = source_value(conn, &source, date) {
+            set_value(conn, id, date, v, target.max(f64::MIN_POSITIVE), "auto").ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::d;
+
+    fn marks(list: &[(&str, Mark)]) -> HashMap<NaiveDate, Mark> { list.iter().map(|(s, m)| (d(s), *m)).collect() }
+
+    #[test]
+    fn weekday_schedule_skips_off_days() {
+        // 2024-05-06 is a Monday. Mon/Wed/Fri habit, done on all three plus next Monday.
+        let s = Schedule::Days(0b0010101);
+        let m = marks(&[("2024-05-06", Mark::Done), ("2024-05-08", Mark::Done), ("2024-05-10", Mark::Done), ("2024-05-13", Mark::Done)]);
+        let st = stats(s, &m, d("2024-05-01"), d("2024-05-14"));
+        assert_eq!(st.streak, 4);
+        // Wednesday unchecked (today) doesn't break it; missing it afterwards does.
+        assert_eq!(stats(s, &m, d("2024-05-01"), d("2024-05-15")).streak, 4);
+        assert_eq!(stats(s, &m, d("2024-05-01"), d("2024-05-16")).streak, 0);
+    }
+
+    #[test]
+    fn freeze_and_partial() {
+        let s = Schedule::Daily;
+        let m = marks(&[
+            ("2024-05-01", Mark::Done), ("2024-05-02", Mark::Frozen), ("2024-05-03", Mark::Done),
+            ("2024-05-04", Mark::Partial(0.5)), ("2024-05-05", Mark::Done),
+        ]);
+        let st = stats(s, &m, d("2024-05-01"), d("2024-05-05"));
+        assert_eq!(st.streak, 1); // partial on the 4th broke it
+        assert_eq!(st.best_streak, 2); // 1st + 3rd, frozen 2nd in between
+        // 4 due days (frozen excluded): 1 + 1 + 0.5 + 1
+        assert_eq!(st.consistency, 87.5);
+        assert_eq!(Mark::from_value(3.0, 8.0), Some(Mark::Partial(0.375)));
+        assert_eq!(Mark::from_value(8.0, 8.0), Some(Mark::Done));
+    }
+
+    #[test]
+    fn three_times_a_week() {
+        let s = Schedule::PerWeek(3);
+        let m = marks(&[
+            // week of Apr 29: 3 done
+            ("2024-04-29", Mark::Done), ("2024-05-01", Mark::Done), ("2024-05-03", Mark::Done),
+            // week of May 6: 2 done + 1 freeze → still met
+            ("2024-05-06", Mark::Done), ("2024-05-07", Mark::Frozen), ("2024-05-09", Mark::Done),
+            // current week of May 13: 1 done so far
+            ("2024-05-13", Mark::Done),
+        ]);
+        let st = stats(s, &m, d("2024-04-29"), d("2024-05-15"));
+        assert_eq!(st.streak_unit, "week");
+        assert_eq!(st.streak, 2);
+        assert_eq!((st.week_done, st.week_target), (1, 3));
+        assert_eq!(st.consistency, 100.0);
+    }
+
+    #[test]
+    fn every_n_days_and_legacy_frequency() {
+        let s = Schedule::EveryNDays { n: 3, anchor: d("2024-05-01") };
+        assert!(s.is_due(d("2024-05-04")) && !s.is_due(d("2024-05-05")));
+        assert_eq!(Schedule::from_parts(None, 0, 1, "weekdays", d("2024-05-01")), Schedule::Days(0b0011111));
+        assert_eq!(Schedule::from_parts(Some("per_week"), 0, 3, "", d("2024-05-01")).label(), "3 раз(а) в неделю");
+    }
+
+    #[test]
+    fn new_habits() {
+        // Created today, nothing checked yet: no streak, no consistency, nothing broken.
+        let st = stats(Schedule::Daily, &HashMap::new(), d("2024-05-15"), d("2024-05-15"));
+        assert_eq!((st.streak, st.best_streak, st.consistency), (0, 0, 0.0));
+        let st = stats(Schedule::PerWeek(3), &HashMap::new(), d("2024-05-15"), d("2024-05-15"));
+        assert_eq!((st.streak, st.consistency, st.week_done), (0, 0.0, 0));
+        // A single check on the first day.
+        let st = stats(Schedule::Daily, &marks(&[("2024-05-15", Mark::Done)]), d("2024-05-15"), d("2024-05-15"));
+        assert_eq!((st.streak, st.consistency), (1, 100.0));
+        // Nothing is due before an every-N-days anchor.
+        let s = Schedule::EveryNDays { n: 2, anchor: d("2024-05-10") };
+        assert!(!s.is_due(d("2024-05-08")) && s.is_due(d("2024-05-10")));
+        assert_eq!(Mark::from_value(0.0, 8.0), None);
+    }
+
+    #[test]
+    fn manual_clear_outlasts_auto_check() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE habits (id INTEGER PRIMARY KEY, auto_source TEXT, target REAL);
+             CREATE TABLE habit_checks (id INTEGER PRIMARY KEY, habit_id INTEGER, date TEXT, completed INTEGER DEFAULT 1,
+                value REAL, status TEXT, source TEXT, created_at TEXT, UNIQUE(habit_id, date));
+             CREATE TABLE health_log (date TEXT, type TEXT, value REAL);
+             INSERT INTO habits VALUES (1, 'steps', 8000);
+             INSERT INTO health_log VALUES ('2024-05-15', 'steps', 9000);"
+        ).unwrap();
+        auto_check(&conn, "2024-05-15");
+        assert_eq!(load_marks(&conn, 1, 8000.0, "2024-05-01"), marks(&[("2024-05-15", Mark::Done)]));
+        // The user unchecks it; more steps arrive and the day stays cleared.
+        set_value(&conn, 1, "2024-05-15", 0.0, 8000.0, "manual").unwrap();
+        conn.execute("INSERT INTO health_log VALUES ('2024-05-15', 'steps', 500)", []).unwrap();
+        auto_check(&conn, "2024-05-15");
+        assert!(load_marks(&conn, 1, 8000.0, "2024-05-01").is_empty());
+        // Another day is still filled in automatically.
+        conn.execute("INSERT INTO health_log VALUES ('2024-05-16', 'steps', 4000)", []).unwrap();
+        auto_check(&conn, "2024-05-16");
+        assert_eq!(load_marks(&conn, 1, 8000.0, "2024-05-16"), marks(&[("2024-05-16", Mark::Partial(0.5))]));
+    }
+}
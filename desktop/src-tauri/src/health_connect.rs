@@ -112,6 +112,7 @@ pub fn add_sleep_session(db: State<'_, HanniDb>, session: SleepSession) -> Resul
             rusqlite::params![stage_id, id, stage.start_time, stage.end_time, stage.stage],
         ).map_err(|e| e.to_string())?;
     }
+    crate::sleep_analysis::score_sessions(&conn, &session.date);
     Ok(id)
 }
 
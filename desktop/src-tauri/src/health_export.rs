@@ -0,0 +1,541 @@
+// health_export.rs — Desktop import of Apple Health `export.xml` and Google Fit
+// Takeout JSON. Both are read as streams (Apple tag by tag, Takeout "Data Points"
+// element by element) so multi-GB exports never sit in memory, and land in the
+// same tables and dedup keys as the Health Connect path in health_import.rs.
+use crate::types::HanniDb;
+use serde::de::{DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::Serialize;
+use std::collections::HashMap;
+use tauri::{AppHandle, Emitter, Manager};
+
+type Ts = chrono::DateTime<chrono::FixedOffset>;
+
+/// Heart-rate rows written per transaction while streaming.
+const HR_BATCH: usize = 5000;
+/// Emit a progress event every this many parsed items.
+const PROGRESS_EVERY: usize = 50_000;
+/// Google Fit activity sessions that aren't workouts.
+const GOOGLE_NON_WORKOUTS: &[&str] = &["still", "unknown", "in_vehicle", "tilting", "on_foot"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HealthItem {
+    /// A sleep segment; `stage` None = in bed / span only.
+    Sleep { start: Ts, end: Ts, stage: Option<&'static str> },
+    Steps { at: Ts, source: String, count: f64 },
+    HeartRate { at: Ts, bpm: i64 },
+    Weight { at: Ts, kg: f64 },
+    Workout { start: Ts, minutes: f64, kind: String, title: String },
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ImportStats {
+    pub items: usize,
+    pub sleep: usize,
+    pub steps_days: usize,
+    pub heart_rate: usize,
+    pub weight_days: usize,
+    pub workouts: usize,
+    pub skipped_files: usize,
+}
+
+// ── Apple Health ──
+
+fn unescape(s: &str) -> String {
+    s.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&apos;", "'").replace("&amp;", "&")
+}
+
+/// `name="value"` pairs of one start tag.
+fn xml_attrs(tag: &str) -> HashMap<&str, String> {
+    let mut out = HashMap::new();
+    let mut rest = tag;
+    while let Some(eq) = rest.find('=') {
+        let name = rest[..eq].trim_end().rsplit(char::is_whitespace).next().unwrap_or("");
+        let after = rest[eq + 1..].trim_start();
+        let Some(q) = after.chars().next().filter(|c| *c == '"' || *c == '\'') else { break };
+        let Some(end) = after[1..].find(q) else { break };
+        out.insert(name, unescape(&after[1..1 + end]));
+        rest = &after[end + 2..];
+    }
+    out
+}
+
+fn apple_time(s: &str) -> Option<Ts> {
+    chrono::DateTime::parse_from_str(s.trim(), "%Y-%m-%d %H:%M:%S %z").ok()
+}
+
+/// "TraditionalStrengthTraining" → "traditional_strength_training".
+fn snake_case(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 4);
+    for (i, c) in s.chars().enumerate() {
+        if c.is_uppercase() && i > 0 { out.push('_'); }
+        out.extend(c.to_lowercase());
+    }
+    out
+}
+
+fn apple_record(a: &HashMap<&str, String>) -> Option<HealthItem> {
+    let start = apple_time(a.get("startDate")?)?;
+    let value = a.get("value").map(String::as_str).unwrap_or("");
+    match a.get("type")?.as_str() {
+        "HKCategoryTypeIdentifierSleepAnalysis" => {
+            let stage = match value.trim_start_matches("HKCategoryValueSleepAnalysis") {
+                "InBed" => None,
+                "AsleepCore" => Some("light"),
+                "AsleepDeep" => Some("deep"),
+                "AsleepREM" => Some("rem"),
+                "Awake" => Some("awake"),
+                _ => Some("sleeping"),
+            };
+            Some(HealthItem::Sleep { start, end: apple_time(a.get("endDate")?)?, stage })
+        }
+        "HKQuantityTypeIdentifierStepCount" => Some(HealthItem::Steps {
+            at: start, source: a.get("sourceName").cloned().unwrap_or_default(), count: value.parse().ok()?,
+        }),
+        "HKQuantityTypeIdentifierHeartRate" => Some(HealthItem::HeartRate { at: start, bpm: value.parse::<f64>().ok()?.round() as i64 }),
+        "HKQuantityTypeIdentifierBodyMass" => {
+            let v: f64 = value.parse().ok()?;
+            let kg = match a.get("unit").map(String::as_str) {
+                Some("lb") => v * 0.453_592_37,
+                Some("g") => v / 1000.0,
+                _ => v,
+            };
+            Some(HealthItem::Weight { at: start, kg })
+        }
+        _ => None,
+    }
+}
+
+fn apple_workout(a: &HashMap<&str, String>) -> Option<HealthItem> {
+    let start = apple_time(a.get("startDate")?)?;
+    let minutes = match (a.get("duration").and_then(|d| d.parse::<f64>().ok()), a.get("durationUnit").map(String::as_str)) {
+        (Some(d), Some("s" | "sec")) => d / 60.0,
+        (Some(d), Some("hr" | "h")) => d * 60.0,
+        (Some(d), _) => d,
+        (None, _) => (apple_time(a.get("endDate")?)? - start).num_seconds() as f64 / 60.0,
+    };
+    let kind = a.get("workoutActivityType").map(|k| snake_case(k.trim_start_matches("HKWorkoutActivityType"))).unwrap_or_else(|| "other".into());
+    Some(HealthItem::Workout { start, minutes, kind, title: a.get("sourceName").cloned().unwrap_or_default() })
+}
+
+/// Walk `export.xml` one tag at a time. Returns the number of records seen.
+pub fn scan_apple_export<R: std::io::BufRead>(mut reader: R, emit: &mut dyn FnMut(HealthItem)) -> Result<usize, String> {
+    let mut buf = Vec::with_capacity(4096);
+    let mut seen = 0;
+    loop {
+        buf.clear();
+        if reader.read_until(b'>', &mut buf).map_err(|e| format!("Ошибка чтения: {}", e))? == 0 { break; }
+        let Some(lt) = buf.iter().rposition(|&b| b == b'<') else { continue };
+        let tag = &buf[lt..];
+        let is_record = tag.starts_with(b"<Record ");
+        if !is_record && !tag.starts_with(b"<Workout ") { continue; }
+        seen += 1;
+        let tag = String::from_utf8_lossy(tag);
+        let attrs = xml_attrs(&tag);
+        let item = if is_record { apple_record(&attrs) } else { apple_workout(&attrs) };
+        if let Some(item) = item { emit(item); }
+    }
+    Ok(seen)
+}
+
+// ── Google Fit (Takeout) ──
+
+/// Top-level keys of a session file that `google_session` reads.
+const SESSION_KEYS: &[&str] = &["fitnessActivity", "startTime", "endTime", "duration", "name", "segment"];
+
+/// Streams the "Data Points" array of a Takeout file into `emit`; the keys a
+/// session file is made of are collected, anything else is skipped unread.
+struct TakeoutFile<'a> {
+    emit: &'a mut dyn FnMut(&serde_json::Value),
+}
+
+struct DataPoints<'a> {
+    emit: &'a mut dyn FnMut(&serde_json::Value),
+}
+
+impl<'de> DeserializeSeed<'de> for TakeoutFile<'_> {
+    type Value = serde_json::Map<String, serde_json::Value>;
+    fn deserialize<D: Deserializer<'de>>(self, d: D) -> Result<Self::Value, D::Error> {
+        d.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for TakeoutFile<'_> {
+    type Value = serde_json::Map<String, serde_json::Value>;
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("a Google Fit Takeout object")
+    }
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut rest = serde_json::Map::new();
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "Data Points" {
+                map.next_value_seed(DataPoints { emit: &mut *self.emit })?;
+            } else if SESSION_KEYS.contains(&key.as_str()) {
+                rest.insert(key, map.next_value()?);
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(rest)
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for DataPoints<'_> {
+    type Value = ();
+    fn deserialize<D: Deserializer<'de>>(self, d: D) -> Result<(), D::Error> {
+        d.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for DataPoints<'_> {
+    type Value = ();
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("an array of data points")
+    }
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(p) = seq.next_element::<serde_json::Value>()? { (self.emit)(&p); }
+        Ok(())
+    }
+}
+
+fn from_nanos(v: &serde_json::Value) -> Option<Ts> {
+    let n = v.as_i64().or_else(|| v.as_str()?.parse().ok())?;
+    chrono::DateTime::from_timestamp(n.div_euclid(1_000_000_000), n.rem_euclid(1_000_000_000) as u32).map(|d| d.fixed_offset())
+}
+
+fn google_sleep_stage(s: &str) -> Option<&'static str> {
+    match s {
+        "sleep.light" => Some("light"),
+        "sleep.deep" => Some("deep"),
+        "sleep.rem" => Some("rem"),
+        "sleep.awake" | "awake" => Some("awake"),
+        "sleep" => Some("sleeping"),
+        _ => None,
+    }
+}
+
+fn google_point(p: &serde_json::Value, source: &str) -> Option<HealthItem> {
+    let at = from_nanos(&p["startTimeNanos"])?;
+    let value = &p["fitValue"][0]["value"];
+    let number = value["fpVal"].as_f64().or_else(|| value["intVal"].as_f64());
+    match p["dataTypeName"].as_str()? {
+        "com.google.step_count.delta" => Some(HealthItem::Steps { at, source: source.to_string(), count: number? }),
+        "com.google.heart_rate.bpm" => Some(HealthItem::HeartRate { at, bpm: number?.round() as i64 }),
+        "com.google.weight" => Some(HealthItem::Weight { at, kg: number? }),
+        "com.google.sleep.segment" => {
+            let stage = match value["intVal"].as_i64()? {
+                1 => "awake",
+                2 => "sleeping",
+                4 => "light",
+                5 => "deep",
+                6 => "rem",
+                _ => return None, // 3 = out of bed
+            };
+            Some(HealthItem::Sleep { start: at, end: from_nanos(&p["endTimeNanos"])?, stage: Some(stage) })
+        }
+        _ => None,
+    }
+}
+
+fn google_session(s: &serde_json::Map<String, serde_json::Value>, emit: &mut dyn FnMut(HealthItem)) {
+    let time = |k: &str| s.get(k).and_then(|v| v.as_str()).and_then(|v| chrono::DateTime::parse_from_rfc3339(v).ok());
+    let (Some(activity), Some(start), Some(end)) = (s.get("fitnessActivity").and_then(|v| v.as_str()), time("startTime"), time("endTime")) else { return };
+    if activity.starts_with("sleep") {
+        emit(HealthItem::Sleep { start, end, stage: None });
+        for seg in s.get("segment").and_then(|v| v.as_array()).into_iter().flatten() {
+            let t = |k: &str| seg[k].as_str().and_then(|v| chrono::DateTime::parse_from_rfc3339(v).ok());
+            if let (Some(stage), Some(a), Some(b)) = (seg["fitnessActivity"].as_str().and_then(google_sleep_stage), t("startTime"), t("endTime")) {
+                emit(HealthItem::Sleep { start: a, end: b, stage: Some(stage) });
+            }
+        }
+    } else if !GOOGLE_NON_WORKOUTS.contains(&activity) {
+        let minutes = s.get("duration").and_then(|v| v.as_str()).and_then(|d| d.trim_end_matches('s').parse::<f64>().ok())
+            .map(|secs| secs / 60.0)
+            .unwrap_or_else(|| (end - start).num_seconds() as f64 / 60.0);
+        let title = s.get("name").and_then(|v| v.as_str()).unwrap_or("Google Fit").to_string();
+        emit(HealthItem::Workout { start, minutes, kind: activity.to_string(), title });
+    }
+}
+
+/// One Takeout JSON file: data points are streamed, a session object is
+/// handled once its keys are read. `source` tells step streams apart.
+pub fn scan_google_json<R: std::io::Read>(reader: R, source: &str, emit: &mut dyn FnMut(HealthItem)) -> Result<(), String> {
+    let mut de = serde_json::Deserializer::from_reader(reader);
+    let rest = TakeoutFile { emit: &mut |p: &serde_json::Value| if let Some(item) = google_point(p, source) { emit(item) } }
+        .deserialize(&mut de)
+        .map_err(|e| format!("JSON: {}", e))?;
+    google_session(&rest, emit);
+    Ok(())
+}
+
+// ── DB side ──
+
+/// Buffers parsed items: heart rate is flushed in batches while streaming,
+/// the small aggregates (sleep, daily steps, weight, workouts) at the end.
+struct Sink<'a> {
+    db: &'a HanniDb,
+    source: &'static str,
+    progress: &'a dyn Fn(usize),
+    heart_rate: Vec<serde_json::Value>,
+    sleep: Vec<serde_json::Value>,
+    /// (date, stream) → steps; a day takes its largest stream so phone + watch don't add up.
+    steps: HashMap<(String, String), f64>,
+    /// date → (unix ts, kg); the day's last weighing wins.
+    weight: HashMap<String, (i64, f64)>,
+    workouts: Vec<serde_json::Value>,
+    stats: ImportStats,
+}
+
+fn local(ts: &Ts) -> chrono::DateTime<chrono::Local> {
+    ts.with_timezone(&chrono::Local)
+}
+
+impl<'a> Sink<'a> {
+    fn new(db: &'a HanniDb, source: &'static str, progress: &'a dyn Fn(usize)) -> Self {
+        Sink {
+            db, source, progress,
+            heart_rate: Vec::new(), sleep: Vec::new(), steps: HashMap::new(), weight: HashMap::new(),
+            workouts: Vec::new(), stats: ImportStats::default(),
+        }
+    }
+
+    fn push(&mut self, item: HealthItem) {
+        self.stats.items += 1;
+        if self.stats.items.is_multiple_of(PROGRESS_EVERY) { (self.progress)(self.stats.items); }
+        match item {
+            HealthItem::HeartRate { at, bpm } => {
+                let l = local(&at);
+                self.heart_rate.push(serde_json::json!({ "date": l.format("%Y-%m-%d").to_string(), "time": l.format("%H:%M").to_string(), "bpm": bpm }));
+                if self.heart_rate.len() >= HR_BATCH { self.flush_heart_rate(); }
+            }
+            HealthItem::Sleep { start, end, stage } => {
+                let (s, e) = (local(&start), local(&end));
+                let hm = |t: &chrono::DateTime<chrono::Local>| t.format("%H:%M").to_string();
+                let stages = match stage {
+                    Some(st) => serde_json::json!([{ "start_time": hm(&s), "end_time": hm(&e), "stage": st }]),
+                    None => serde_json::json!([]),
+                };
+                self.sleep.push(serde_json::json!({
+                    "date": s.format("%Y-%m-%d").to_string(), "start_time": hm(&s), "end_time": hm(&e),
+                    "start_iso": start.to_rfc3339(), "end_iso": end.to_rfc3339(), "stages": stages,
+                }));
+            }
+            HealthItem::Steps { at, source, count } => {
+                *self.steps.entry((local(&at).format("%Y-%m-%d").to_string(), source)).or_default() += count;
+            }
+            HealthItem::Weight { at, kg } => {
+                let day = local(&at).format("%Y-%m-%d").to_string();
+                let e = self.weight.entry(day).or_insert((i64::MIN, kg));
+                if at.timestamp() >= e.0 { *e = (at.timestamp(), kg); }
+            }
+            HealthItem::Workout { start, minutes, kind, title } => {
+                let l = local(&start);
+                self.workouts.push(serde_json::json!({
+                    "date": l.format("%Y-%m-%d").to_string(), "start_time": l.format("%H:%M").to_string(),
+                    "duration_minutes": minutes.round(), "type": kind, "title": title,
+                }));
+            }
+        }
+    }
+
+    fn flush_heart_rate(&mut self) {
+        if self.heart_rate.is_empty() { return; }
+        let conn = self.db.conn();
+        conn.execute_batch("BEGIN").ok();
+        self.stats.heart_rate += crate::health_import::import_heart_rate(&conn, &self.heart_rate, self.source);
+        conn.execute_batch("COMMIT").ok();
+        self.heart_rate.clear();
+    }
+
+    fn finish(mut self) -> ImportStats {
+        self.flush_heart_rate();
+        let mut days: HashMap<String, f64> = HashMap::new();
+        for ((date, _), n) in self.steps.drain() {
+            let d = days.entry(date).or_default();
+            *d = d.max(n);
+        }
+        let days: Vec<serde_json::Value> = days.into_iter().map(|(date, steps)| serde_json::json!({ "date": date, "steps": steps.round() })).collect();
+        let now = chrono::Local::now().to_rfc3339();
+        let conn = self.db.conn();
+        conn.execute_batch("BEGIN").ok();
+        self.stats.sleep = crate::health_import::import_sleep_sessions(&conn, &self.sleep, self.source);
+        self.stats.steps_days = crate::health_import::import_steps(&conn, &days, true);
+        self.stats.workouts = crate::health_import::import_exercise(&conn, &self.workouts);
+        for (date, (_, kg)) in &self.weight {
+            let kg = (kg * 10.0).round() / 10.0;
+            let updated = conn.execute(
+                "UPDATE health_log SET value=?1 WHERE date=?2 AND type='weight'", rusqlite::params![kg, date],
+            ).unwrap_or(0);
+            if updated == 0 {
+                conn.execute(
+                    "INSERT INTO health_log (id, date, type, value, unit, notes, created_at) VALUES (?1,?2,'weight',?3,'kg','',?4)",
+                    rusqlite::params![crate::types::new_uuid_v7(), date, kg, now],
+                ).ok();
+            }
+            self.stats.weight_days += 1;
+        }
+        conn.execute_batch("COMMIT").ok();
+        self.stats
+    }
+}
+
+/// Takeout keeps Fit data under "Fit/" ("Google Fit/" in older exports); the
+/// other products of a full Takeout (Drive, Keep…) have JSON files too.
+fn is_fit_dir(p: &std::path::Path) -> bool {
+    p.file_name().map(|n| n.to_string_lossy().to_lowercase()).is_some_and(|n| n == "fit" || n == "google fit")
+}
+
+/// JSON files under `dir` that belong to Fit (`in_fit` — `dir` already does).
+fn collect_json(dir: &std::path::Path, in_fit: bool, out: &mut Vec<std::path::PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else { return };
+    for e in entries.flatten() {
+        let p = e.path();
+        if p.is_dir() { collect_json(&p, in_fit || is_fit_dir(&p), out); }
+        else if in_fit && p.extension().is_some_and(|x| x.eq_ignore_ascii_case("json")) { out.push(p); }
+    }
+}
+
+/// Import from an Apple `export.xml` (or the unzipped export folder), a Google
+/// Takeout folder, or a single Takeout JSON file.
+pub(crate) fn import_path(db: &HanniDb, path: &std::path::Path, progress: &dyn Fn(usize)) -> Result<ImportStats, String> {
+    if path.extension().is_some_and(|x| x.eq_ignore_ascii_case("zip")) {
+        return Err("Распакуйте архив и укажите папку или export.xml".into());
+    }
+    let apple = if path.is_dir() {
+        [path.join("export.xml"), path.join("apple_health_export").join("export.xml")].into_iter().find(|p| p.is_file())
+    } else if path.extension().is_some_and(|x| x.eq_ignore_ascii_case("xml")) {
+        Some(path.to_path_buf())
+    } else {
+        None
+    };
+    let open = |p: &std::path::Path| std::fs::File::open(p).map(std::io::BufReader::new).map_err(|e| format!("Не удалось открыть {}: {}", p.display(), e));
+
+    if let Some(xml) = apple {
+        let mut sink = Sink::new(db, "apple_health", progress);
+        scan_apple_export(open(&xml)?, &mut |item| sink.push(item))?;
+        return Ok(sink.finish());
+    }
+    let mut files = Vec::new();
+    if path.is_dir() { collect_json(path, path.ancestors().any(is_fit_dir), &mut files); } else if path.is_file() { files.push(path.to_path_buf()); }
+    if files.is_empty() { return Err("Не найден export.xml Apple Health или JSON из Google Takeout".into()); }
+    let mut sink = Sink::new(db, "google_fit", progress);
+    let mut skipped = 0;
+    for f in &files {
+        let source = f.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        if scan_google_json(open(f)?, &source, &mut |item| sink.push(item)).is_err() { skipped += 1; }
+    }
+    let mut stats = sink.finish();
+    stats.skipped_files = skipped;
+    Ok(stats)
+}
+
+// ── Commands ──
+
+/// Import an Apple Health / Google Takeout export from disk. Runs off the main
+/// thread and reports progress via `health-export-progress` (items parsed).
+#[tauri::command]
+pub async fn import_health_export(path: String, app: AppHandle) -> Result<ImportStats, String> {
+    tokio::task::spawn_blocking(move || {
+        let db = app.state::<HanniDb>();
+        let progress = |n: usize| { let _ = app.emit("health-export-progress", n); };
+        let path = path.trim();
+        let path = match path.strip_prefix("~/") {
+            Some(rest) => dirs::home_dir().unwrap_or_default().join(rest),
+            None => std::path::PathBuf::from(path),
+        };
+        import_path(&db, &path, &progress)
+    }).await.map_err(|e| format!("Import task failed: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scans_apple_export() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE HealthData [
+<!ATTLIST Record type CDATA #REQUIRED>
+]>
+<HealthData locale="ru_RU">
+ <Record type="HKQuantityTypeIdentifierStepCount" sourceName="iPhone" unit="count" startDate="2024-05-01 08:00:00 +0300" endDate="2024-05-01 08:10:00 +0300" value="812"/>
+ <Record type="HKQuantityTypeIdentifierHeartRate" sourceName="Watch" unit="count/min" startDate="2024-05-01 08:01:30 +0300" endDate="2024-05-01 08:01:30 +0300" value="71.6">
+  <MetadataEntry key="HKMetadataKeyHeartRateMotionContext" value="1"/>
+ </Record>
+ <Record type="HKQuantityTypeIdentifierBodyMass" sourceName="Scale" unit="lb" startDate="2024-05-01 07:00:00 +0300" endDate="2024-05-01 07:00:00 +0300" value="176.4"/>
+ <Record type="HKCategoryTypeIdentifierSleepAnalysis" sourceName="Watch" startDate="2024-04-30 23:40:00 +0300" endDate="2024-05-01 01:10:00 +0300" value="HKCategoryValueSleepAnalysisAsleepDeep"/>
+ <Workout workoutActivityType="HKWorkoutActivityTypeTraditionalStrengthTraining" duration="45.5" durationUnit="min" sourceName="Watch &amp; Co" startDate="2024-05-01 18:00:00 +0300" endDate="2024-05-01 18:45:30 +0300">
+  <WorkoutStatistics type="HKQuantityTypeIdentifierActiveEnergyBurned" sum="300" unit="kcal"/>
+ </Workout>
+</HealthData>"#;
+        let mut items = Vec::new();
+        let seen = scan_apple_export(xml.as_bytes(), &mut |i| items.push(i)).unwrap();
+        assert_eq!(seen, 5);
+        assert_eq!(items.len(), 5);
+        let t = |s: &str| apple_time(s).unwrap();
+        assert_eq!(items[0], HealthItem::Steps { at: t("2024-05-01 08:00:00 +0300"), source: "iPhone".into(), count: 812.0 });
+        assert_eq!(items[1], HealthItem::HeartRate { at: t("2024-05-01 08:01:30 +0300"), bpm: 72 });
+        match &items[2] { HealthItem::Weight { kg, .. } => assert!((kg - 80.01).abs() < 0.01), other => panic!("{:?}", other) }
+        assert_eq!(items[3], HealthItem::Sleep { start: t("2024-04-30 23:40:00 +0300"), end: t("2024-05-01 01:10:00 +0300"), stage: Some("deep") });
+        assert_eq!(items[4], HealthItem::Workout {
+            start: t("2024-05-01 18:00:00 +0300"), minutes: 45.5, kind: "traditional_strength_training".into(), title: "Watch & Co".into(),
+        });
+    }
+
+    #[test]
+    fn streams_google_data_points() {
+        let json = r#"{"Data Points":[
+            {"fitValue":[{"value":{"intVal":120}}],"dataTypeName":"com.google.step_count.delta","startTimeNanos":1714546800000000000,"endTimeNanos":1714546860000000000},
+            {"fitValue":[{"value":{"fpVal":64.0}}],"dataTypeName":"com.google.heart_rate.bpm","startTimeNanos":"1714546800000000000","endTimeNanos":"1714546800000000000"},
+            {"fitValue":[{"value":{"intVal":5}}],"dataTypeName":"com.google.sleep.segment","startTimeNanos":1714510800000000000,"endTimeNanos":1714514400000000000},
+            {"fitValue":[{"value":{"intVal":3}}],"dataTypeName":"com.google.sleep.segment","startTimeNanos":1714514400000000000,"endTimeNanos":1714514460000000000}
+        ],"Data Source":"derived:com.google.step_count.delta:merge_step_deltas"}"#;
+        let mut items = Vec::new();
+        scan_google_json(json.as_bytes(), "steps", &mut |i| items.push(i)).unwrap();
+        assert_eq!(items.len(), 3);
+        let at = chrono::DateTime::from_timestamp(1_714_546_800, 0).unwrap().fixed_offset();
+        assert_eq!(items[0], HealthItem::Steps { at, source: "steps".into(), count: 120.0 });
+        assert_eq!(items[1], HealthItem::HeartRate { at, bpm: 64 });
+        assert!(matches!(items[2], HealthItem::Sleep { stage: Some("deep"), .. }));
+    }
+
+    #[test]
+    fn reads_google_sessions() {
+        let sleep = r#"{"fitnessActivity":"sleep","startTime":"2024-04-30T20:00:00.000Z","endTime":"2024-05-01T04:00:00.000Z","duration":"28800s",
+            "segment":[{"fitnessActivity":"sleep.rem","startTime":"2024-04-30T22:00:00.000Z","endTime":"2024-04-30T22:30:00.000Z"}]}"#;
+        let run = r#"{"fitnessActivity":"running","startTime":"2024-05-01T05:00:00Z","endTime":"2024-05-01T05:31:00Z","duration":"1800.000s"}"#;
+        let still = r#"{"fitnessActivity":"still","startTime":"2024-05-01T05:00:00Z","endTime":"2024-05-01T05:31:00Z"}"#;
+        let mut items = Vec::new();
+        for s in [sleep, run, still] { scan_google_json(s.as_bytes(), "s", &mut |i| items.push(i)).unwrap(); }
+        assert_eq!(items.len(), 3);
+        assert!(matches!(items[0], HealthItem::Sleep { stage: None, .. }));
+        assert!(matches!(items[1], HealthItem::Sleep { stage: Some("rem"), .. }));
+        match &items[2] {
+            HealthItem::Workout { minutes, kind, .. } => assert_eq!((*minutes, kind.as_str()), (30.0, "running")),
+            other => panic!("{:?}", other),
+        }
+    }
+    #[test]
+    fn skips_what_is_not_fit() {
+        // Keys no session needs are skipped, whatever their shape.
+        let run = r#"{"fitnessActivity":"running","application":{"packageName":"x"},"aggregate":[{"metricName":"m","intValue":"3"}],
+            "startTime":"2024-05-01T05:00:00Z","endTime":"2024-05-01T05:30:00Z"}"#;
+        let mut items = Vec::new();
+        scan_google_json(run.as_bytes(), "s", &mut |i| items.push(i)).unwrap();
+        assert!(matches!(&items[..], [HealthItem::Workout { .. }]));
+
+        let root = std::env::temp_dir().join(format!("hanni-takeout-{}", std::process::id()));
+        for dir in ["Fit/All Sessions", "Drive", "Google Fit/Daily activity metrics"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+            std::fs::write(root.join(dir).join("a.json"), "{}").unwrap();
+        }
+        let mut files = Vec::new();
+        collect_json(&root, false, &mut files);
+        files.sort();
+        assert_eq!(files, vec![root.join("Fit/All Sessions/a.json"), root.join("Google Fit/Daily activity metrics/a.json")]);
+        // Pointed at the Fit folder itself, everything in it counts.
+        let mut files = Vec::new();
+        collect_json(&root.join("Fit"), root.join("Fit").ancestors().any(is_fit_dir), &mut files);
+        assert_eq!(files.len(), 1);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -20,28 +20,28 @@ pub async fn import_health_connect_all<R: Runtime>(
         // Sleep
         if let Ok(resp) = handle.0.run_mobile_plugin::<serde_json::Value>("readSleep", &()) {
             if let Some(sessions) = resp.get("sessions").and_then(|v| v.as_array()) {
-                let count = import_sleep_sessions(&db, sessions);
+                let count = import_sleep_sessions(&db.conn(), sessions, "health_connect");
                 result["sleep"] = serde_json::json!(count);
             }
         }
         // Steps
         if let Ok(resp) = handle.0.run_mobile_plugin::<serde_json::Value>("readSteps", &()) {
             if let Some(days) = resp.get("days").and_then(|v| v.as_array()) {
-                let count = import_steps(&db, days);
+                let count = import_steps(&db.conn(), days, false);
                 result["steps"] = serde_json::json!(count);
             }
         }
         // Heart rate
         if let Ok(resp) = handle.0.run_mobile_plugin::<serde_json::Value>("readHeartRate", &()) {
             if let Some(samples) = resp.get("samples").and_then(|v| v.as_array()) {
-                let count = import_heart_rate(&db, samples);
+                let count = import_heart_rate(&db.conn(), samples, "health_connect");
                 result["heart_rate"] = serde_json::json!(count);
             }
         }
         // Exercise
         if let Ok(resp) = handle.0.run_mobile_plugin::<serde_json::Value>("readExercise", &()) {
             if let Some(sessions) = resp.get("sessions").and_then(|v| v.as_array()) {
-                let count = import_exercise(&db, sessions);
+                let count = import_exercise(&db.conn(), sessions);
                 result["exercise"] = serde_json::json!(count);
             }
         }
@@ -57,11 +57,9 @@ pub async fn import_health_connect_all<R: Runtime>(
 // Samsung Health writes one night of sleep to Health Connect as several
 // separate SleepSessionRecords (split by wake-ups, plus naps). Segments less
 // than this many minutes apart are treated as one sleep.
-#[cfg(target_os = "android")]
 const SLEEP_MERGE_GAP_MINUTES: i64 = 180;
 
 /// One sleep, possibly assembled from several Health Connect segments.
-#[cfg(target_os = "android")]
 struct SleepNight {
     date: String,
     start_time: String,
@@ -73,7 +71,6 @@ struct SleepNight {
 
 /// Sort raw HC sleep segments by start instant and merge adjacent ones whose
 /// gap is below the threshold (or that overlap) into single nights.
-#[cfg(target_os = "android")]
 fn merge_sleep_segments(sessions: &[serde_json::Value]) -> Vec<SleepNight> {
     let mut segs: Vec<SleepNight> = sessions.iter().filter_map(|s| {
         let start = chrono::DateTime::parse_from_rfc3339(s["start_iso"].as_str()?).ok()?;
@@ -106,19 +103,20 @@ fn merge_sleep_segments(sessions: &[serde_json::Value]) -> Vec<SleepNight> {
     nights
 }
 
-#[cfg(target_os = "android")]
-fn import_sleep_sessions(db: &HanniDb, sessions: &[serde_json::Value]) -> usize {
-    let conn = db.conn();
+/// Also used by the desktop export importer (`health_export`) with its own `source`.
+pub(crate) fn import_sleep_sessions(conn: &rusqlite::Connection, sessions: &[serde_json::Value], source: &str) -> usize {
     let mut count = 0;
-    for night in merge_sleep_segments(sessions) {
+    let nights = merge_sleep_segments(sessions);
+    let first_date = nights.iter().map(|n| n.date.clone()).min();
+    for night in nights {
         // Wall-clock span so a fragmented night shows as one continuous block.
         let dur = (night.end - night.start).num_minutes().max(0);
 
         // Idempotency: dedup by (date, start_time, source). Since Phase 1
         // sleep_sessions has UNIQUE(date, start_time, source) — use that.
         let existing: Option<String> = conn.query_row(
-            "SELECT id FROM sleep_sessions WHERE date=?1 AND start_time=?2 AND source='health_connect'",
-            rusqlite::params![night.date, night.start_time], |r| r.get(0),
+            "SELECT id FROM sleep_sessions WHERE date=?1 AND start_time=?2 AND source=?3",
+            rusqlite::params![night.date, night.start_time, source], |r| r.get(0),
         ).ok();
 
         let sid: String = if let Some(id) = existing {
@@ -133,14 +131,17 @@ fn import_sleep_sessions(db: &HanniDb, sessions: &[serde_json::Value]) -> usize
             let new_id = crate::types::new_uuid_v7();
             if conn.execute(
                 "INSERT INTO sleep_sessions (id, date, start_time, end_time, duration_minutes, source)
-                 VALUES (?1,?2,?3,?4,?5,'health_connect')",
-                rusqlite::params![new_id, night.date, night.start_time, night.end_time, dur],
+                 VALUES (?1,?2,?3,?4,?5,?6)",
+                rusqlite::params![new_id, night.date, night.start_time, night.end_time, dur, source],
             ).is_err() { continue; }
             count += 1;
             new_id
         };
 
+        // Overlapping sources (phone + watch) can report the same stage twice.
+        let mut seen = std::collections::HashSet::new();
         for st in &night.stages {
+            if !seen.insert((st["start_time"].to_string(), st["end_time"].to_string(), st["stage"].to_string())) { continue; }
             let stage_id = crate::types::new_uuid_v7();
             let _ = conn.execute(
                 "INSERT INTO sleep_stages (id, session_id, start_time, end_time, stage) VALUES (?1,?2,?3,?4,?5)",
@@ -153,12 +154,16 @@ fn import_sleep_sessions(db: &HanniDb, sessions: &[serde_json::Value]) -> usize
             );
         }
     }
+    if let Some(first) = first_date {
+        crate::sleep_analysis::score_sessions(conn, &first);
+    }
     count
 }
 
-#[cfg(target_os = "android")]
-fn import_steps(db: &HanniDb, days: &[serde_json::Value]) -> usize {
-    let conn = db.conn();
+/// Daily step totals, one `health_log` row per date. `keep_max` keeps the
+/// larger of the stored and incoming value (historical imports must not
+/// lower a day that another source already counted more completely).
+pub(crate) fn import_steps(conn: &rusqlite::Connection, days: &[serde_json::Value], keep_max: bool) -> usize {
     let mut count = 0;
     let now = chrono::Local::now().to_rfc3339();
     for d in days {
@@ -168,7 +173,8 @@ fn import_steps(db: &HanniDb, days: &[serde_json::Value]) -> usize {
             "SELECT id FROM health_log WHERE date=?1 AND type='steps'", [date], |r| r.get(0),
         ).ok();
         if let Some(id) = existing {
-            let _ = conn.execute("UPDATE health_log SET value=?1 WHERE id=?2", rusqlite::params![steps, id]);
+            let sql = if keep_max { "UPDATE health_log SET value=MAX(value, ?1) WHERE id=?2" } else { "UPDATE health_log SET value=?1 WHERE id=?2" };
+            let _ = conn.execute(sql, rusqlite::params![steps, id]);
         } else {
             let new_id = crate::types::new_uuid_v7();
             let _ = conn.execute(
@@ -182,9 +188,7 @@ fn import_steps(db: &HanniDb, days: &[serde_json::Value]) -> usize {
     count
 }
 
-#[cfg(target_os = "android")]
-fn import_heart_rate(db: &HanniDb, samples: &[serde_json::Value]) -> usize {
-    let conn = db.conn();
+pub(crate) fn import_heart_rate(conn: &rusqlite::Connection, samples: &[serde_json::Value], source: &str) -> usize {
     let mut count = 0;
     for s in samples {
         let date = s["date"].as_str().unwrap_or_default();
@@ -192,16 +196,14 @@ fn import_heart_rate(db: &HanniDb, samples: &[serde_json::Value]) -> usize {
         let bpm = s["bpm"].as_i64().unwrap_or(0);
         let new_id = crate::types::new_uuid_v7();
         if conn.execute(
-            "INSERT OR IGNORE INTO heart_rate_samples (id, date, time, bpm) VALUES (?1,?2,?3,?4)",
-            rusqlite::params![new_id, date, time, bpm],
+            "INSERT OR IGNORE INTO heart_rate_samples (id, date, time, bpm, source) VALUES (?1,?2,?3,?4,?5)",
+            rusqlite::params![new_id, date, time, bpm, source],
         ).is_ok() { count += 1; }
     }
     count
 }
 
-#[cfg(target_os = "android")]
-fn import_exercise(db: &HanniDb, sessions: &[serde_json::Value]) -> usize {
-    let conn = db.conn();
+pub(crate) fn import_exercise(conn: &rusqlite::Connection, sessions: &[serde_json::Value]) -> usize {
     let now = chrono::Local::now().to_rfc3339();
     // Idempotency: Health Connect returns every session in the window each
     // poll. Upsert by (date, start_time, title) so re-imports refresh in
@@ -284,5 +286,8 @@ pub fn get_health_summary(db: State<'_, HanniDb>, days: i64) -> HealthSummary {
     let sleep_count: i64 = conn.query_row(
         "SELECT COUNT(*) FROM sleep_sessions WHERE date>=?1", [&since], |r| r.get(0),
     ).unwrap_or(0);
+    // Real resting HR (sleep-window based) when there's enough data; the
+    // plain average is only a fallback.
+    let avg_hr = crate::heart_rate::avg_resting(&conn, days).unwrap_or(avg_hr);
     HealthSummary { avg_sleep_minutes: avg_sleep, avg_steps, avg_resting_hr: avg_hr, sleep_sessions: sleep_count, days_with_steps: days_steps, hr_samples: hr_count }
 }
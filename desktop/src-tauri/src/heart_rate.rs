@@ -0,0 +1,462 @@
+// heart_rate.rs — Derived heart-rate metrics from `heart_rate_samples`:
+// resting HR over sleep windows, per-day max/avg, time in zones, and a rolling
+// baseline that flags unusual elevation (early illness / overtraining hint).
+// Daily results are cached in `heart_rate_daily`; recent days are recomputed on
+// every read because late syncs keep adding samples to them.
+use crate::types::HanniDb;
+use chrono::{Datelike, NaiveDate, NaiveDateTime, TimeZone, Timelike};
+use serde::Serialize;
+
+/// Zone lower bounds as a fraction of max HR (Z1..Z5).
+const ZONE_BOUNDS: [f64; 5] = [0.5, 0.6, 0.7, 0.8, 0.9];
+/// A sample counts for at most this many minutes (HC often logs every 5–10 min).
+const MAX_SAMPLE_MINUTES: i64 = 5;
+/// Fewer sleep-window samples than this → fall back to the whole day.
+const MIN_SLEEP_SAMPLES: usize = 5;
+const MIN_DAY_SAMPLES: usize = 20;
+/// Rolling baseline: previous N calendar days, needs at least MIN values.
+const BASELINE_DAYS: i64 = 28;
+const MIN_BASELINE_VALUES: usize = 7;
+/// Elevated = above baseline by 2σ and by at least this many bpm.
+const ELEVATED_MIN_BPM: f64 = 5.0;
+/// Days that are always recomputed (today and the two before it).
+const FRESH_DAYS: i64 = 2;
+const DEFAULT_MAX_HR: i64 = 190;
+
+/// Max HR used for zones, and where it came from.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct MaxHr {
+    pub bpm: i64,
+    pub source: &'static str, // "manual" | "age" | "default"
+}
+
+/// `hr_max` setting wins; else Tanaka (208 − 0.7·age) from `birth_year`.
+pub(crate) fn max_hr(conn: &rusqlite::Connection) -> MaxHr {
+    let setting = |key: &str| conn.query_row(
+        "SELECT value FROM app_settings WHERE key=?1", [key], |r| r.get::<_, String>(0),
+    ).ok().and_then(|v| v.trim().parse::<i64>().ok());
+    if let Some(bpm) = setting("hr_max").filter(|v| (100..=230).contains(v)) {
+        return MaxHr { bpm, source: "manual" };
+    }
+    let year = chrono::Local::now().year() as i64;
+    if let Some(born) = setting("birth_year").filter(|y| (year - 100..=year - 5).contains(y)) {
+        return MaxHr { bpm: (208.0 - 0.7 * (year - born) as f64).round() as i64, source: "age" };
+    }
+    MaxHr { bpm: DEFAULT_MAX_HR, source: "default" }
+}
+
+/// 0 = below Z1, 1..=5 = zone.
+fn zone_of(bpm: i64, max_hr: i64) -> usize {
+    let pct = bpm as f64 / max_hr as f64;
+    ZONE_BOUNDS.iter().rposition(|b| pct >= *b).map(|i| i + 1).unwrap_or(0)
+}
+
+/// Minutes spent in Z1..Z5. `samples` are (minute of day, bpm) sorted by minute;
+/// each sample covers the gap to the next one, capped.
+fn zone_minutes(samples: &[(i64, i64)], max_hr: i64) -> [i64; 5] {
+    let mut out = [0; 5];
+    for (i, &(minute, bpm)) in samples.iter().enumerate() {
+        let span = samples.get(i + 1).map(|n| (n.0 - minute).clamp(0, MAX_SAMPLE_MINUTES)).unwrap_or(1);
+        let z = zone_of(bpm, max_hr);
+        if z > 0 { out[z - 1] += span; }
+    }
+    out
+}
+
+/// Nearest-rank percentile of an unsorted slice.
+fn percentile(values: &[i64], p: f64) -> Option<f64> {
+    if values.is_empty() { return None; }
+    let mut v = values.to_vec();
+    v.sort_unstable();
+    let rank = ((p * v.len() as f64).ceil() as usize).clamp(1, v.len());
+    Some(v[rank - 1] as f64)
+}
+
+fn std_dev(values: &[f64]) -> f64 {
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64).sqrt()
+}
+
+/// Sleep window in local time. `start_time` is "HH:MM" (Health Connect,
+/// exports) or a full ISO timestamp (manual entries); `date` is the night's
+/// start date.
+pub(crate) fn sleep_window(date: &str, start_time: &str, minutes: i64) -> Option<(NaiveDateTime, NaiveDateTime)> {
+    sleep_window_in(&chrono::Local, date, start_time, minutes)
+}
+
+/// `sleep_window` in `tz`: the duration is real time, so a night across a DST
+/// switch ends an hour later/earlier on the wall clock.
+fn sleep_window_in<Tz: TimeZone>(tz: &Tz, date: &str, start_time: &str, minutes: i64) -> Option<(NaiveDateTime, NaiveDateTime)> {
+    let start = if start_time.contains('T') {
+        chrono::DateTime::parse_from_rfc3339(start_time).ok()?.with_timezone(tz).naive_local()
+    } else {
+        NaiveDateTime::parse_from_str(&format!("{} {}", date, &start_time[..start_time.len().min(5)]), "%Y-%m-%d %H:%M").ok()?
+    };
+    let dur = chrono::Duration::minutes(minutes);
+    let end = tz.from_local_datetime(&start).earliest()
+        .map(|s| (s + dur).naive_local())
+        .unwrap_or(start + dur);
+    Some((start, end))
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DayMetrics {
+    pub date: String,
+    pub resting_hr: Option<f64>,
+    /// "sleep" when taken from the night ending this morning, "day" otherwise.
+    pub resting_source: Option<&'static str>,
+    /// Spread of overnight HR — the closest thing to HRV bpm samples allow.
+    pub sleep_hr_sd: Option<f64>,
+    pub avg_hr: Option<f64>,
+    pub max_hr: Option<i64>,
+    pub samples: i64,
+    pub zone_minutes: [i64; 5],
+}
+
+fn compute_day(conn: &rusqlite::Connection, date: NaiveDate, max: i64) -> DayMetrics {
+    let day = date.format("%Y-%m-%d").to_string();
+    let mut m = DayMetrics { date: day.clone(), ..Default::default() };
+
+    let mut samples: Vec<(i64, i64, bool)> = Vec::new(); // (minute, bpm, in a workout)
+    if let Ok(mut stmt) = conn.prepare("SELECT time, bpm, COALESCE(track_id, workout_id) IS NOT NULL FROM heart_rate_samples WHERE date=?1 AND bpm > 0 ORDER BY time") {
+        samples = stmt.query_map([&day], |r| Ok((r.get::<_, String>(0)?, r.get(1)?, r.get(2)?)))
+            .map(|rows| rows.filter_map(|r| r.ok()).filter_map(|(t, bpm, w)| {
+                let t = chrono::NaiveTime::parse_from_str(t.get(..5)?, "%H:%M").ok()?;
+                Some(((t.num_seconds_from_midnight() / 60) as i64, bpm, w))
+            }).collect())
+            .unwrap_or_default();
+    }
+    m.samples = samples.len() as i64;
+    if !samples.is_empty() {
+        m.avg_hr = Some((samples.iter().map(|s| s.1).sum::<i64>() as f64 / samples.len() as f64 * 10.0).round() / 10.0);
+        m.max_hr = samples.iter().map(|s| s.1).max();
+        let series: Vec<(i64, i64)> = samples.iter().map(|s| (s.0, s.1)).collect();
+        m.zone_minutes = zone_minutes(&series, max);
+    }
+
+    // Resting HR: the night that ended this morning (sessions are dated by
+    // their start, so look at yesterday's and today's).
+    let prev = (date - chrono::Duration::days(1)).format("%Y-%m-%d").to_string();
+    let mut windows: Vec<(NaiveDateTime, NaiveDateTime)> = Vec::new();
+    if let Ok(mut stmt) = conn.prepare("SELECT date, start_time, duration_minutes FROM sleep_sessions WHERE date IN (?1, ?2) AND duration_minutes >= 60") {
+        windows = stmt.query_map([&prev, &day], |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?, r.get::<_, i64>(2)?)))
+            .map(|rows| rows.filter_map(|r| r.ok()).filter_map(|(d, s, mins)| sleep_window(&d, &s, mins)).collect())
+            .unwrap_or_default();
+    }
+    windows.retain(|w| w.1.date() == date);
+    let mut sleep_bpm: Vec<i64> = Vec::new();
+    for (start, end) in &windows {
+        if let Ok(mut stmt) = conn.prepare(
+            "SELECT bpm FROM heart_rate_samples WHERE date BETWEEN ?3 AND ?4 AND bpm > 0 AND COALESCE(track_id, workout_id) IS NULL
+             AND (date || ' ' || substr(time,1,5)) BETWEEN ?1 AND ?2",
+        ) {
+            let fmt = |t: &NaiveDateTime| t.format("%Y-%m-%d %H:%M").to_string();
+            if let Ok(rows) = stmt.query_map([fmt(start), fmt(end), start.date().to_string(), end.date().to_string()], |r| r.get::<_, i64>(0)) {
+                sleep_bpm.extend(rows.filter_map(|r| r.ok()));
+            }
+        }
+    }
+    if sleep_bpm.len() >= MIN_SLEEP_SAMPLES {
+        m.resting_hr = percentile(&sleep_bpm, 0.1);
+        m.resting_source = Some("sleep");
+        let as_f: Vec<f64> = sleep_bpm.iter().map(|b| *b as f64).collect();
+        m.sleep_hr_sd = Some((std_dev(&as_f) * 10.0).round() / 10.0);
+    } else {
+        let idle: Vec<i64> = samples.iter().filter(|s| !s.2).map(|s| s.1).collect();
+        if idle.len() >= MIN_DAY_SAMPLES {
+            m.resting_hr = percentile(&idle, 0.05);
+            m.resting_source = Some("day");
+        }
+    }
+    m
+}
+
+/// Recompute `heart_rate_daily` for [from, to]: fresh days always, older days
+/// only when missing or computed with a different max HR.
+pub(crate) fn refresh(conn: &rusqlite::Connection, from: NaiveDate, to: NaiveDate) {
+    let max = max_hr(conn).bpm;
+    let fresh_from = chrono::Local::now().date_naive() - chrono::Duration::days(FRESH_DAYS);
+    let mut cached: std::collections::HashMap<String, i64> = std::collections::HashMap::new();
+    if let Ok(mut stmt) = conn.prepare("SELECT date, zones_max_hr FROM heart_rate_daily WHERE date BETWEEN ?1 AND ?2") {
+        if let Ok(rows) = stmt.query_map([from.to_string(), to.to_string()], |r| Ok((r.get::<_, String>(0)?, r.get::<_, i64>(1)?))) {
+            cached.extend(rows.filter_map(|r| r.ok()));
+        }
+    }
+    let now = chrono::Local::now().to_rfc3339();
+    for date in from.iter_days().take_while(|d| *d <= to) {
+        if date < fresh_from && cached.get(&date.to_string()) == Some(&max) { continue; }
+        let m = compute_day(conn, date, max);
+        if m.samples == 0 && m.resting_hr.is_none() {
+            conn.execute("DELETE FROM heart_rate_daily WHERE date=?1", [&m.date]).ok();
+            continue;
+        }
+        conn.execute(
+            "INSERT INTO heart_rate_daily (date, resting_hr, resting_source, sleep_hr_sd, avg_hr, max_hr, samples, zone_minutes, zones_max_hr, computed_at)
+             VALUES (?1,?2,?3,?4,?5,?6,?7,?8,?9,?10)
+             ON CONFLICT(date) DO UPDATE SET resting_hr=?2, resting_source=?3, sleep_hr_sd=?4, avg_hr=?5, max_hr=?6,
+                samples=?7, zone_minutes=?8, zones_max_hr=?9, computed_at=?10",
+            rusqlite::params![m.date, m.resting_hr, m.resting_source, m.sleep_hr_sd, m.avg_hr, m.max_hr, m.samples,
+                serde_json::to_string(&m.zone_minutes).unwrap_or_default(), max, now],
+        ).ok();
+    }
+}
+
+fn load_days(conn: &rusqlite::Connection, from: NaiveDate, to: NaiveDate) -> Vec<DayMetrics> {
+    let Ok(mut stmt) = conn.prepare(
+        "SELECT date, resting_hr, resting_source, sleep_hr_sd, avg_hr, max_hr, samples, zone_minutes
+         FROM heart_rate_daily WHERE date BETWEEN ?1 AND ?2 ORDER BY date",
+    ) else { return Vec::new() };
+    stmt.query_map([from.to_string(), to.to_string()], |r| {
+        let source: Option<String> = r.get(2)?;
+        Ok(DayMetrics {
+            date: r.get(0)?,
+            resting_hr: r.get(1)?,
+            resting_source: source.map(|s| if s == "sleep" { "sleep" } else { "day" }),
+            sleep_hr_sd: r.get(3)?,
+            avg_hr: r.get(4)?,
+            max_hr: r.get(5)?,
+            samples: r.get(6)?,
+            zone_minutes: serde_json::from_str(&r.get::<_, String>(7)?).unwrap_or_default(),
+        })
+    }).map(|rows| rows.filter_map(|r| r.ok()).collect()).unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Trend {
+    pub date: String,
+    pub resting_hr: f64,
+    pub baseline: Option<f64>,
+    pub baseline_sd: Option<f64>,
+    pub delta: Option<f64>,
+    pub elevated: bool,
+}
+
+/// Compare each day's resting HR with the previous BASELINE_DAYS days.
+/// `days` must be sorted by date.
+pub(crate) fn trends(days: &[(NaiveDate, f64)]) -> Vec<Trend> {
+    days.iter().map(|&(date, rhr)| {
+        let window: Vec<f64> = days.iter()
+            .filter(|(d, _)| *d < date && (date - *d).num_days() <= BASELINE_DAYS)
+            .map(|(_, v)| *v).collect();
+        let mut t = Trend { date: date.to_string(), resting_hr: rhr, baseline: None, baseline_sd: None, delta: None, elevated: false };
+        if window.len() >= MIN_BASELINE_VALUES {
+            let mean = window.iter().sum::<f64>() / window.len() as f64;
+            let sd = std_dev(&window);
+            let delta = rhr - mean;
+            t.baseline = Some((mean * 10.0).round() / 10.0);
+            t.baseline_sd = Some((sd * 10.0).round() / 10.0);
+            t.delta = Some((delta * 10.0).round() / 10.0);
+            t.elevated = delta >= (2.0 * sd).max(ELEVATED_MIN_BPM);
+        }
+        t
+    }).collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Alert {
+    /// "elevated" — the latest day is above baseline; "elevated_streak" — two
+    /// or more days in a row; "rising" — the 7-day average has crept up.
+    pub kind: &'static str,
+    pub days: usize,
+    pub delta: f64,
+}
+
+/// Alerts for the most recent days. Only looks at trends ending within the
+/// last couple of days so an old spike doesn't keep nagging.
+pub(crate) fn alerts(trends: &[Trend], today: NaiveDate) -> Vec<Alert> {
+    let mut out = Vec::new();
+    let Some(last) = trends.last() else { return out };
+    let last_date = NaiveDate::parse_from_str(&last.date, "%Y-%m-%d").unwrap_or(today);
+    if (today - last_date).num_days() > 1 { return out; }
+    let streak: Vec<&Trend> = trends.iter().rev().take_while(|t| t.elevated).collect();
+    if !streak.is_empty() {
+        let delta = streak.iter().filter_map(|t| t.delta).sum::<f64>() / streak.len() as f64;
+        let kind = if streak.len() >= 2 { "elevated_streak" } else { "elevated" };
+        out.push(Alert { kind, days: streak.len(), delta: (delta * 10.0).round() / 10.0 });
+        return out;
+    }
+    // Slow drift: last week's mean vs the baseline a week ago.
+    let week: Vec<f64> = trends.iter().rev().take(7).map(|t| t.resting_hr).collect();
+    let base = trends.iter().rev().nth(7).and_then(|t| t.baseline);
+    if let (true, Some(base)) = (week.len() >= 5, base) {
+        let delta = week.iter().sum::<f64>() / week.len() as f64 - base;
+        if delta >= 3.0 {
+            out.push(Alert { kind: "rising", days: week.len(), delta: (delta * 10.0).round() / 10.0 });
+        }
+    }
+    out
+}
+
+/// Refresh the last `days` (plus baseline history) and derive trends + alerts.
+fn analyze(conn: &rusqlite::Connection, days: i64) -> (Vec<DayMetrics>, Vec<Trend>, Vec<Alert>) {
+    let today = chrono::Local::now().date_naive();
+    let from = today - chrono::Duration::days(days.max(1) - 1);
+    let history_from = from - chrono::Duration::days(BASELINE_DAYS);
+    refresh(conn, history_from, today);
+    let all = load_days(conn, history_from, today);
+    let resting: Vec<(NaiveDate, f64)> = all.iter()
+        .filter_map(|d| Some((NaiveDate::parse_from_str(&d.date, "%Y-%m-%d").ok()?, d.resting_hr?)))
+        .collect();
+    let trends = trends(&resting);
+    let alerts = alerts(&trends, today);
+    let from_s = from.to_string();
+    (
+        all.into_iter().filter(|d| d.date >= from_s).collect(),
+        trends.into_iter().filter(|t| t.date >= from_s).collect(),
+        alerts,
+    )
+}
+
+/// Average resting HR over the last `days`, for summaries.
+pub(crate) fn avg_resting(conn: &rusqlite::Connection, days: i64) -> Option<f64> {
+    let (metrics, _, _) = analyze(conn, days);
+    let v: Vec<f64> = metrics.iter().filter_map(|d| d.resting_hr).collect();
+    if v.is_empty() { None } else { Some(v.iter().sum::<f64>() / v.len() as f64) }
+}
+
+/// Lines for the morning digest: last night's resting HR vs baseline, and any
+/// elevation warning.
+pub(crate) fn digest_lines(conn: &rusqlite::Connection) -> Vec<String> {
+    let (_, trends, alerts) = analyze(conn, 7);
+    let mut lines = Vec::new();
+    let today = chrono::Local::now().date_naive().to_string();
+    if let Some(t) = trends.last().filter(|t| t.date == today) {
+        match t.baseline {
+            Some(b) => lines.push(format!("Resting HR: {:.0} bpm (baseline {:.0})", t.resting_hr, b)),
+            None => lines.push(format!("Resting HR: {:.0} bpm", t.resting_hr)),
+        }
+    }
+    for a in alerts {
+        lines.push(match a.kind {
+            "elevated_streak" => format!(
+                "Resting HR elevated {} days in a row (+{:.0} bpm over baseline) — possible illness or overtraining, suggest an easy day",
+                a.days, a.delta),
+            "elevated" => format!("Resting HR is +{:.0} bpm over baseline today — watch recovery", a.delta),
+            _ => format!("Resting HR trending up: 7-day average +{:.0} bpm over baseline", a.delta),
+        });
+    }
+    lines
+}
+
+// ── Commands ──
+
+#[tauri::command]
+pub fn get_heart_rate_metrics(days: Option<i64>, db: tauri::State<'_, HanniDb>) -> Result<serde_json::Value, String> {
+    let conn = db.conn();
+    let max = max_hr(&conn);
+    let (metrics, trends, alerts) = analyze(&conn, days.unwrap_or(30).clamp(1, 365));
+    let zones: Vec<serde_json::Value> = ZONE_BOUNDS.iter().enumerate().map(|(i, lo)| {
+        let hi = ZONE_BOUNDS.get(i + 1).copied().unwrap_or(1.0);
+        serde_json::json!({ "zone": i + 1, "from": (lo * max.bpm as f64).round(), "to": (hi * max.bpm as f64).round() })
+    }).collect();
+    Ok(serde_json::json!({
+        "max_hr": max.bpm, "max_hr_source": max.source, "zones": zones,
+        "days": metrics, "trends": trends, "alerts": alerts,
+    }))
+}
+
+/// Save max HR and/or birth year used for zones. `None` clears the setting.
+#[tauri::command]
+pub fn set_heart_rate_profile(max_hr: Option<i64>, birth_year: Option<i64>, db: tauri::State<'_, HanniDb>) -> Result<(), String> {
+    if max_hr.is_some_and(|v| !(100..=230).contains(&v)) {
+        return Err("Максимальный пульс должен быть от 100 до 230".into());
+    }
+    let conn = db.conn();
+    for (key, value) in [("hr_max", max_hr), ("birth_year", birth_year)] {
+        match value {
+            Some(v) => conn.execute(
+                "INSERT INTO app_settings (key, value) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET value=?2",
+                rusqlite::params![key, v.to_string()],
+            ),
+            None => conn.execute("DELETE FROM app_settings WHERE key=?1", [key]),
+        }.map_err(|e| format!("DB error: {}", e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::d;
+
+    #[test]
+    fn zones_and_minutes() {
+        assert_eq!(zone_of(90, 190), 0);
+        assert_eq!(zone_of(95, 190), 1);
+        assert_eq!(zone_of(171, 190), 5);
+        // 10:00 Z3 for 5 min (capped from 30), 10:30 Z4 for 2 min, 10:32 Z4 last sample = 1 min
+        let m = zone_minutes(&[(600, 140), (630, 160), (632, 160)], 190);
+        assert_eq!(m, [0, 0, 5, 3, 0]);
+    }
+
+    #[test]
+    fn sleep_window_crosses_midnight() {
+        let (s, e) = sleep_window("2024-05-01", "23:30", 480).unwrap();
+        assert_eq!(s.to_string(), "2024-05-01 23:30:00");
+        assert_eq!(e.date(), d("2024-05-02"));
+    }
+
+    #[test]
+    fn sleep_window_across_dst() {
+        let tz = chrono_tz::Europe::Berlin;
+        // Clocks go forward at 02:00: 8 h of sleep from 23:00 ends at 08:00.
+        let (_, e) = sleep_window_in(&tz, "2026-03-28", "23:00", 480).unwrap();
+        assert_eq!(e.to_string(), "2026-03-29 08:00:00");
+        // …and back at 03:00 in October: it ends at 06:00.
+        let (_, e) = sleep_window_in(&tz, "2026-10-24", "23:00", 480).unwrap();
+        assert_eq!(e.to_string(), "2026-10-25 06:00:00");
+        assert!(sleep_window_in(&tz, "2026-10-24", "bad", 480).is_none());
+    }
+
+    #[test]
+    fn empty_and_single_point_series() {
+        assert_eq!(zone_minutes(&[], 190), [0; 5]);
+        assert_eq!(zone_minutes(&[(600, 150)], 190), [0, 0, 1, 0, 0]);
+        assert_eq!(percentile(&[], 0.1), None);
+        assert_eq!(percentile(&[50], 0.1), Some(50.0));
+        assert!(trends(&[]).is_empty());
+        assert!(alerts(&[], d("2024-05-01")).is_empty());
+        // One day has no baseline, so it can't be elevated.
+        let t = trends(&[(d("2024-05-01"), 90.0)]);
+        assert_eq!((t[0].baseline, t[0].elevated), (None, false));
+        assert!(alerts(&t, d("2024-05-01")).is_empty());
+    }
+
+    #[test]
+    fn flags_elevated_streak() {
+        let mut days: Vec<(NaiveDate, f64)> = (1..=20).map(|i| (d("2024-05-01") + chrono::Duration::days(i), 52.0 + (i % 3) as f64)).collect();
+        let base = days.len();
+        days.push((d("2024-05-22"), 61.0));
+        days.push((d("2024-05-23"), 63.0));
+        let t = trends(&days);
+        assert!(!t[base - 1].elevated);
+        assert!(t[base].elevated && t[base + 1].elevated);
+        let a = alerts(&t, d("2024-05-23"));
+        assert_eq!(a.len(), 1);
+        assert_eq!(a[0].kind, "elevated_streak");
+        assert_eq!(a[0].days, 2);
+        // Stale data: nothing to say a week later.
+        assert!(alerts(&t, d("2024-05-30")).is_empty());
+    }
+
+    #[test]
+    fn resting_from_sleep_window() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE app_settings (key TEXT PRIMARY KEY, value TEXT);
+             CREATE TABLE sleep_sessions (date TEXT, start_time TEXT, duration_minutes INTEGER);
+             CREATE TABLE heart_rate_samples (date TEXT, time TEXT, bpm INTEGER, workout_id INTEGER, track_id TEXT);
+             INSERT INTO sleep_sessions VALUES ('2024-05-01', '23:00', 480);",
+        ).unwrap();
+        for (i, bpm) in [58, 52, 50, 49, 51, 55].iter().enumerate() {
+            conn.execute("INSERT INTO heart_rate_samples VALUES ('2024-05-02', ?1, ?2, NULL, NULL)",
+                rusqlite::params![format!("0{}:00", i + 1), bpm]).unwrap();
+        }
+        conn.execute("INSERT INTO heart_rate_samples VALUES ('2024-05-02', '18:00', 175, NULL, 'run:1714662000')", []).unwrap();
+        let m = compute_day(&conn, d("2024-05-02"), 190);
+        assert_eq!(m.resting_source, Some("sleep"));
+        assert_eq!(m.resting_hr, Some(49.0));
+        assert_eq!(m.max_hr, Some(175));
+        assert_eq!(m.zone_minutes[4], 1);
+    }
+}
@@ -164,6 +164,8 @@ fn apply_batch(conn: &rusqlite::Connection, batch: &SyncBatch) -> usize {
             .format("%Y-%m-%d").to_string();
         let _ = crate::calendar_health::auto_complete_from_health(conn, &today, &now);
         let _ = crate::calendar_health::auto_complete_from_health(conn, &yesterday, &now);
+        crate::habits::auto_check(conn, &today);
+        crate::habits::auto_check(conn, &yesterday);
     }
     applied
 }
@@ -4,6 +4,7 @@ mod types;
 mod prompts;
 mod db;
 mod sports_seed;
+mod nutrition_seed;
 mod chat;
 mod memory;
 #[cfg(not(target_os = "android"))]
@@ -13,6 +14,12 @@ mod voice;
 mod voice;
 mod proactive;
 mod macos;
+mod activity_sensor;
+mod activity_rules;
+mod activitywatch;
+mod focus_enforcer;
+mod pomodoro;
+mod timeline_review;
 mod android_update;
 mod web_assets;
 mod calendar;
@@ -40,9 +47,19 @@ mod sync_commands;
 mod health_connect;
 mod health_connect_plugin;
 mod health_import;
+mod health_export;
+mod heart_rate;
+mod habits;
+mod body_metrics;
+mod medications;
+mod checkin;
 mod bg_sync;
 mod sleep_analysis;
 mod timeline_health;
+mod timeline_reconstruct;
+mod day_planner;
+mod screen_limits;
+mod time_zones;
 mod calendar_health;
 mod share_server;
 mod share_auth;
@@ -57,6 +74,13 @@ mod share_static;
 mod share_tunnel;
 mod commands_share;
 mod commands_shopping;
+mod commands_debts;
+mod nutrition;
+mod pantry;
+mod recipe_import;
+mod strength;
+mod program_schedule;
+mod track_import;
 mod sync_share;
 mod sync_owner;
 mod sync_owner_auto;
@@ -68,6 +92,8 @@ mod lan_sync;
 mod google_auth;
 #[cfg(not(target_os = "android"))]
 mod window_state;
+#[cfg(test)]
+mod test_util;
 
 // Re-export types used by run() for state setup
 use types::*;
@@ -79,10 +105,7 @@ use memory::{embed_texts, store_fact_embedding};
 #[cfg(not(target_os = "android"))]
 use voice::speak_silero_core;
 #[cfg(not(target_os = "android"))]
-use proactive::{
-    get_frontmost_app, get_browser_url, get_now_playing_sync,
-    get_upcoming_events_soon, proactive_loop,
-};
+use proactive::{get_upcoming_events_soon, proactive_loop};
 #[cfg(not(target_os = "android"))]
 use macos::run_osascript;
 use commands_meta::spawn_api_server;
@@ -198,7 +221,7 @@ fn init_database() -> HanniDb {
     // user_version so an already-migrated DB skips it and starts fast.
     // CONTRACT: bump SCHEMA_VERSION whenever you add a migration to this block
     // (or change SYNC_TABLES — migrate_sync_meta must re-run to bind triggers).
-    const SCHEMA_VERSION: i64 = 10;
+    const SCHEMA_VERSION: i64 = 34;
     let schema_ver: i64 = conn
         .query_row("PRAGMA user_version", [], |r| r.get(0))
         .unwrap_or(0);
@@ -262,6 +285,30 @@ fn init_database() -> HanniDb {
         db::migrate_routine_ids_deterministic(&conn); // deterministic ids so routines sync (v0.95)
         db::migrate_routine_ids_deterministic_v2(&conn); // content-key the WHOLE graph so chains/nodes/edges converge + sync (v1.0.x)
         db::migrate_sync_meta(&conn); // re-run: bind updated_at/tombstone triggers to the rebuilt routine tables
+        db::migrate_debt_payments(&conn); // debt terms + payment history
+        db::migrate_catalog_nutrition(&conn); // per-100g macros + recipe auto-nutrition
+        db::migrate_shopping_from_plan(&conn); // shopping_list category/source + meal_plan servings
+        db::migrate_pantry_log(&conn); // product minimums + consumption/waste log
+        db::migrate_recipe_source_url(&conn); // imported recipes dedupe by URL
+        db::migrate_workout_sets(&conn); // per-set strength log + exercise catalog links
+        db::migrate_program_schedule(&conn); // dated program runs, deload weeks, run log
+        db::migrate_workout_tracks(&conn); // GPX/TCX/FIT tracks, splits, best efforts
+        db::migrate_heart_rate_daily(&conn); // derived resting HR / zones cache
+        db::migrate_habit_schedules(&conn); // habit schedules, targets, freezes, auto-check
+        db::migrate_body_measurements(&conn); // body fat + circumferences
+        db::migrate_medications(&conn); // medications, dose log, reminders.source
+        db::migrate_daily_checkins(&conn); // mood/energy/stress check-ins + symptoms
+        db::migrate_activity_rules(&conn); // user activity classification rules
+        db::migrate_activity_snapshot_source(&conn); // activity_snapshots.source (ActivityWatch import)
+        db::migrate_focus_tamper_log(&conn); // attempts to open blocked apps/sites during focus
+        db::migrate_pomodoro(&conn); // pomodoro sessions + intervals, "Перерыв" timeline type
+        db::migrate_timeline_goal_results(&conn); // pass/fail history for timeline goals
+        db::migrate_distraction_timeline_type(&conn); // "Отвлечения" timeline type (reconstruction)
+        db::migrate_screen_limits(&conn); // per-app/category daily limits + escalation log
+        db::migrate_event_time_zones(&conn); // events.tz (home/travel zone display)
+        db::migrate_debt_payment_undo(&conn); // pre-payment debt state for exact undo
+        db::migrate_workout_load_factor(&conn); // workouts.load_factor (deload / % weeks)
+        db::migrate_hr_sample_tracks(&conn); // heart_rate_samples.track_id (synced workout link)
         let _ = conn.pragma_update(None, "user_version", SCHEMA_VERSION);
     }
 
@@ -270,6 +317,8 @@ fn init_database() -> HanniDb {
     // Health Connect imports and LAN sync keep re-creating, heal routine-node
     // references re-dirtied by sync.
     commands_timeline_today::auto_close_orphan_blocks(&conn);
+    pantry::sweep_expired_products(&conn);
+    program_schedule::refresh_active_runs(&conn);
     db::migrate_dedup_health_exercise(&conn);
     db::migrate_dedup_auto_health_events(&conn);
     db::backfill_routine_nodes_source_id(&conn);
@@ -383,6 +432,9 @@ pub fn run() {
     let focus_manager = FocusManager(std::sync::Mutex::new(FocusState {
         active: false,
         end_time: None,
+        started_at: None,
+        scheduled: false,
+        owner: None,
         blocked_apps: Vec::new(),
         blocked_sites: Vec::new(),
         monitor_running: focus_monitor_flag.clone(),
@@ -652,6 +704,24 @@ pub fn run() {
             commands_programs::stop_program,
             commands_programs::complete_program_day,
             commands_programs::get_today_program_workout,
+            commands_programs::start_program_day,
+            program_schedule::get_program_schedule,
+            program_schedule::update_program_run,
+            // Strength sets
+            strength::add_workout_exercise,
+            strength::log_workout_set,
+            strength::update_workout_set,
+            strength::delete_workout_set,
+            strength::get_workout_sets,
+            strength::get_exercise_progress,
+            strength::get_strength_volume,
+            strength::get_personal_records,
+            strength::get_progression_suggestions,
+            // Cardio tracks (GPX/TCX/FIT)
+            track_import::import_workout_file,
+            track_import::get_workout_track,
+            track_import::get_cardio_summary,
+            track_import::get_cardio_records,
             // Schedules
             commands_data::create_schedule,
             commands_data::get_schedules,
@@ -690,6 +760,7 @@ pub fn run() {
             commands_data::get_habits_today,
             commands_data::update_habit,
             commands_data::delete_habit,
+            commands_data::freeze_habit,
             // Dashboard
             commands_data::get_dashboard_data,
             commands_data::get_notifications,
@@ -736,6 +807,11 @@ pub fn run() {
             commands_data::list_catalog_subgroups,
             commands_data::delete_ingredient_from_catalog,
             commands_data::check_ingredient_usage,
+            // Nutrition
+            nutrition::get_recipe_nutrition,
+            nutrition::recalc_recipe_nutrition,
+            nutrition::set_ingredient_nutrition,
+            nutrition::import_nutrition_dataset,
             commands_data::list_food_blacklist,
             commands_data::add_food_blacklist,
             commands_data::remove_food_blacklist,
@@ -751,6 +827,15 @@ pub fn run() {
             commands_shopping::add_shopping_item,
             commands_shopping::mark_shopping_bought,
             commands_shopping::delete_shopping_item,
+            commands_shopping::generate_shopping_from_plan,
+            commands_shopping::clear_plan_shopping,
+            // Pantry
+            pantry::preview_recipe_consumption,
+            pantry::consume_recipe_ingredients,
+            pantry::get_pantry_log,
+            pantry::get_waste_report,
+            // Recipe import
+            recipe_import::import_recipe,
             commands_data::add_product,
             commands_data::get_products,
             commands_data::update_product,
@@ -782,11 +867,18 @@ pub fn run() {
             commands_data::get_debts,
             commands_data::update_debt,
             commands_data::delete_debt,
+            commands_debts::set_debt_terms,
+            commands_debts::get_debt_schedule,
+            commands_debts::pay_debt,
+            commands_debts::get_debt_payments,
+            commands_debts::delete_debt_payment,
+            commands_debts::simulate_debt_payoff,
             // Blocklist
             commands_meta::add_to_blocklist,
             commands_meta::remove_from_blocklist,
             commands_meta::get_blocklist,
             commands_meta::toggle_blocklist_item,
+            commands_meta::set_blocklist_schedule,
             // Goals & Settings
             commands_meta::create_goal,
             commands_meta::get_goals,
@@ -902,9 +994,16 @@ pub fn run() {
             timeline_stats::delete_timeline_goal,
             timeline_stats::get_timeline_day_stats,
             timeline_stats::get_timeline_range_stats,
+            timeline_stats::get_timeline_goal_progress,
+            timeline_review::generate_weekly_review,
+            timeline_review::get_weekly_reviews,
             timeline_afk::sync_afk_blocks,
             timeline_afk::sync_timeline_auto,
             timeline_health::sync_health_to_timeline,
+            timeline_reconstruct::reconstruct_timeline,
+            day_planner::plan_day,
+            day_planner::accept_day_plan,
+            day_planner::clear_day_plan,
             calendar_health::sync_health_to_calendar,
             calendar_health::dedup_auto_health_events,
             commands_timeline_today::get_today_planned,
@@ -929,6 +1028,51 @@ pub fn run() {
             health_import::import_health_connect_all,
             health_import::get_heart_rate_samples,
             health_import::get_health_summary,
+            health_export::import_health_export,
+            heart_rate::get_heart_rate_metrics,
+            heart_rate::set_heart_rate_profile,
+            body_metrics::get_body_metrics,
+            body_metrics::log_body_measurement,
+            body_metrics::delete_body_measurement,
+            body_metrics::set_body_goal,
+            medications::get_medications,
+            medications::create_medication,
+            medications::update_medication,
+            medications::delete_medication,
+            medications::log_medication_dose,
+            medications::delete_medication_dose,
+            medications::restock_medication,
+            checkin::save_checkin,
+            checkin::get_checkins,
+            checkin::delete_checkin,
+            checkin::get_checkin_correlations,
+            activity_sensor::get_activity_sensor_status,
+            activity_rules::get_activity_rules,
+            activity_rules::create_activity_rule,
+            activity_rules::update_activity_rule,
+            activity_rules::delete_activity_rule,
+            activity_rules::reclassify_activity_snapshots,
+            activity_rules::get_unclassified_activity,
+            activitywatch::import_activitywatch,
+            activitywatch::export_activitywatch,
+            focus_enforcer::get_focus_enforcer_status,
+            focus_enforcer::get_focus_tamper_log,
+            screen_limits::get_screen_limits,
+            screen_limits::create_screen_limit,
+            screen_limits::update_screen_limit,
+            screen_limits::delete_screen_limit,
+            screen_limits::get_screen_limit_history,
+            time_zones::get_time_zone_settings,
+            time_zones::set_time_zone_settings,
+            pomodoro::get_pomodoro_state,
+            pomodoro::start_pomodoro,
+            pomodoro::pause_pomodoro,
+            pomodoro::resume_pomodoro,
+            pomodoro::skip_pomodoro_phase,
+            pomodoro::interrupt_pomodoro,
+            pomodoro::set_pomodoro_notes,
+            pomodoro::stop_pomodoro,
+            pomodoro::get_pomodoro_stats,
             health_connect_plugin::health_has_permissions,
             health_connect_plugin::health_request_permissions,
             health_connect_plugin::health_background_status,
@@ -940,6 +1084,7 @@ pub fn run() {
             web_assets::web_ls_import,
             web_assets::web_origin_ok,
             sleep_analysis::get_sleep_analysis,
+            sleep_analysis::get_sleep_nights,
             // Share links
             commands_share::create_share_link,
             commands_share::list_share_links,
@@ -1219,63 +1364,74 @@ pub fn run() {
             // Startup cleanup: remove stale focus blocker entries from /etc/hosts
             // (in case app crashed or was force-killed during focus mode)
             #[cfg(not(target_os = "android"))]
-            if std::fs::read_to_string("/etc/hosts")
-                .map(|c| c.contains("# === HANNI FOCUS BLOCKER ==="))
-                .unwrap_or(false)
-            {
-                let _ = run_osascript("do shell script \"sed -i '' '/# === HANNI FOCUS BLOCKER ===/,/# === END HANNI FOCUS BLOCKER ===/d' /etc/hosts && dscacheutil -flushcache && killall -HUP mDNSResponder\" with administrator privileges");
+            if focus_enforcer::hosts_has_block() {
+                let _ = focus_enforcer::enforcer().unblock_sites();
             }
 
-            // Focus mode monitor loop (desktop only — uses osascript)
+            // Focus mode monitor loop (desktop only): expiry + relaunched apps every 2 s,
+            // blocklist schedules every 30 s even when no session is running
             #[cfg(not(target_os = "android"))]
             {
             let focus_handle = app.handle().clone();
             let focus_flag = focus_monitor_flag.clone();
             tauri::async_runtime::spawn(async move {
+                let mut tick: u64 = 0;
                 loop {
                     tokio::time::sleep(std::time::Duration::from_secs(2)).await;
-                    if !focus_flag.load(Ordering::Relaxed) {
+                    let check_schedule = tick.is_multiple_of(15);
+                    tick += 1;
+                    if !check_schedule && !focus_flag.load(Ordering::Relaxed) {
                         continue;
                     }
+                    let h = focus_handle.clone();
+                    let _ = tokio::task::spawn_blocking(move || focus_enforcer::tick(&h, check_schedule)).await;
+                }
+            });
 
-                    let focus = focus_handle.state::<FocusManager>();
-                    let (active, end_time, apps) = {
-                        let state = focus.0.lock().unwrap_or_else(|e| e.into_inner());
-                        (state.active, state.end_time, state.blocked_apps.clone())
-                    };
-
-                    if !active {
-                        continue;
-                    }
+            // Pomodoro phase timer — advances work/break intervals when they run out
+            let pomodoro_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                    let h = pomodoro_handle.clone();
+                    let _ = tokio::task::spawn_blocking(move || pomodoro::tick(&h)).await;
+                }
+            });
 
-                    // Check if focus timer expired
-                    if let Some(end) = end_time {
-                        if chrono::Local::now() >= end {
-                            // Auto-stop focus mode
-                            let script = "do shell script \"sed -i '' '/# === HANNI FOCUS BLOCKER ===/,/# === END HANNI FOCUS BLOCKER ===/d' /etc/hosts && dscacheutil -flushcache && killall -HUP mDNSResponder\" with administrator privileges";
-                            let _ = run_osascript(script);
-                            {
-                                let mut state = focus.0.lock().unwrap_or_else(|e| e.into_inner());
-                                state.active = false;
-                                state.end_time = None;
-                                state.blocked_apps.clear();
-                                state.blocked_sites.clear();
-                                state.monitor_running.store(false, Ordering::Relaxed);
+            // Timeline reconstruction — yesterday once after startup, then today every 10 min
+            let reconstruct_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+                let mut first = true;
+                loop {
+                    let h = reconstruct_handle.clone();
+                    let _ = tokio::task::spawn_blocking(move || {
+                        let today = chrono::Local::now().date_naive();
+                        let db = h.state::<HanniDb>();
+                        let conn = db.conn();
+                        let days = if first { vec![today - chrono::Duration::days(1), today] } else { vec![today] };
+                        for day in days {
+                            if let Err(e) = timeline_reconstruct::rebuild_day(&conn, &day.format("%Y-%m-%d").to_string()) {
+                                eprintln!("[timeline] reconstruct {}: {}", day, e);
                             }
-                            let _ = focus_handle.emit("focus-ended", ());
-                            continue;
                         }
-                    }
+                    }).await;
+                    first = false;
+                    tokio::time::sleep(std::time::Duration::from_secs(600)).await;
+                }
+            });
 
-                    // Kill blocked apps if they relaunch
-                    for app_name in &apps {
-                        let safe = app_name.chars().filter(|c| c.is_ascii_alphanumeric() || *c == ' ' || *c == '.').collect::<String>();
-                        if safe.is_empty() { continue; }
-                        let _ = run_osascript(&format!(
-                            "tell application \"System Events\"\nif (name of processes) contains \"{}\" then\ntell application \"{}\" to quit\nend if\nend tell",
-                            safe, safe
-                        ));
-                    }
+            // Weekly review (auto mode) — checked hourly so a new week is picked up without a restart
+            let review_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                tokio::time::sleep(std::time::Duration::from_secs(90)).await;
+                loop {
+                    let h = review_handle.clone();
+                    let _ = tokio::task::spawn_blocking(move || {
+                        let db = h.state::<HanniDb>();
+                        timeline_review::auto_review(&db.conn(), chrono::Local::now().date_naive());
+                    }).await;
+                    tokio::time::sleep(std::time::Duration::from_secs(3600)).await;
                 }
             });
 
@@ -1290,16 +1446,8 @@ pub fn run() {
                     tokio::time::sleep(std::time::Duration::from_secs(30)).await;
 
                     // Collect OS data in blocking thread
-                    let (app_name, browser, music, window_title, idle_secs, screen_locked) = tokio::task::spawn_blocking(|| {
-                        (
-                            get_frontmost_app(),
-                            get_browser_url(),
-                            get_now_playing_sync(),
-                            proactive::get_window_title(),
-                            macos::get_macos_idle_seconds(),
-                            macos::is_screen_locked(),
-                        )
-                    }).await.unwrap_or_default();
+                    let activity_sensor::ActivityReading { app: app_name, browser, music, window_title, idle_secs, screen_locked } =
+                        tokio::task::spawn_blocking(|| activity_sensor::sensor().read()).await.unwrap_or_default();
 
                     // Skip recording if idle > 30 min (long AFK, saves DB space)
                     if idle_secs > 1800.0 {
@@ -1314,27 +1462,26 @@ pub fn run() {
                     // AFK detection: screen locked OR idle >= 2 min
                     let is_afk = screen_locked || idle_secs >= 120.0;
 
-                    // Classify activity category (only meaningful when not AFK)
-                    let category = if is_afk {
-                        "afk"
-                    } else {
-                        proactive::classify_activity(&app_name, &browser, &window_title)
-                    };
+                    let db = snapshot_handle.state::<HanniDb>();
 
-                    // Compute productive vs distraction (0.5 min per 30-sec snapshot)
-                    // AFK snapshots get 0/0 — they don't count as productive or distraction
-                    let (prod_min, dist_min) = if is_afk {
-                        (0.0_f64, 0.0_f64)
+                    // Classify activity category via user rules + built-in fallback
+                    // (only meaningful when not AFK). Productive vs distraction
+                    // minutes come from the category's score (0.5 min per 30-sec
+                    // snapshot); AFK snapshots get 0/0.
+                    let (category, prod_min, dist_min) = if is_afk {
+                        ("afk".to_string(), 0.0_f64, 0.0_f64)
                     } else {
-                        match category {
-                            "coding" | "writing" | "learning" => (0.5_f64, 0.0_f64),
-                            "social" | "media" => (0.0_f64, 0.5_f64),
-                            _ => (0.25_f64, 0.0_f64),
-                        }
+                        let c = activity_rules::RuleSet::load(&db.read()).classify(&app_name, &browser, &window_title);
+                        let (prod, dist) = activity_rules::snapshot_minutes(c.score);
+                        (c.category, prod, dist)
                     };
 
+                    // Blocked site open during a focus session → tamper log
+                    if !is_afk {
+                        focus_enforcer::note_activity(&snapshot_handle, &browser, &window_title);
+                    }
+
                     // Write to DB
-                    let db = snapshot_handle.state::<HanniDb>();
                     {
                         let conn = db.conn();
                         let _ = conn.execute(
@@ -1355,10 +1502,11 @@ pub fn run() {
                             ],
                         );
 
-                        // Auto-cleanup: remove snapshots older than 30 days (run once per hour)
+                        // Auto-cleanup: remove snapshots older than 30 days (run once per hour).
+                        // Imported ActivityWatch history is kept — importing it is the point.
                         if trigger_counter % 120 == 0 {
                             let _ = conn.execute(
-                                "DELETE FROM activity_snapshots WHERE captured_at < datetime('now', '-30 days')",
+                                "DELETE FROM activity_snapshots WHERE captured_at < datetime('now', '-30 days') AND source = ''",
                                 [],
                             );
                         }
@@ -1366,6 +1514,19 @@ pub fn run() {
 
                     trigger_counter += 1;
 
+                    // Screen-time limits — every minute; a reached limit nudges through proactive
+                    if trigger_counter.is_multiple_of(2) {
+                        let h = snapshot_handle.clone();
+                        let limit_triggers = tokio::task::spawn_blocking(move || screen_limits::tick(&h)).await.unwrap_or_default();
+                        if !limit_triggers.is_empty() {
+                            let mut state = snapshot_proactive_ref.lock().await;
+                            let now_inst = std::time::Instant::now();
+                            for t in limit_triggers {
+                                state.pending_triggers.push((t, now_inst));
+                            }
+                        }
+                    }
+
                     // Check triggers every ~10 min (20 iterations × 30 sec)
                     if trigger_counter % 20 == 0 {
                         let mut triggers: Vec<String> = Vec::new();
@@ -1405,7 +1566,7 @@ pub fn run() {
                     let heartbeat_path = dirs::home_dir()
                         .map(|h| h.join("clawd/HEARTBEAT.md"));
                     if let Some(path) = heartbeat_path {
-                        let idle_secs = macos::get_macos_idle_seconds();
+                        let idle_secs = activity_sensor::sensor().idle_seconds();
                         let mut lines = vec![
                             "# Текущий контекст".to_string(),
                             format!("Время: {}", now.format("%H:%M %d.%m.%Y")),
@@ -1560,16 +1721,22 @@ pub fn run() {
             tauri::async_runtime::spawn(async move {
                 loop {
                     tokio::time::sleep(std::time::Duration::from_secs(30)).await;
-                    let now = chrono::Local::now().to_rfc3339();
+                    // Anchored remind_at (with offset) fires at its instant; a naive one
+                    // at that wall-clock time in the current (home or travel) zone
+                    let now = chrono::Utc::now();
                     let due: Vec<(i64, String)> = {
                         let db = reminder_handle.state::<HanniDb>();
                         let conn = db.conn();
+                        let zone = time_zones::current_zone(&conn);
+                        medications::queue_reminders(&conn, time_zones::now_in(zone));
                         let mut stmt = match conn.prepare(
-                            "SELECT id, title FROM reminders WHERE fired=0 AND remind_at <= ?1"
+                            "SELECT id, title, remind_at FROM reminders WHERE fired=0"
                         ) { Ok(s) => s, Err(_) => continue };
-                        let rows: Vec<(i64, String)> = stmt.query_map(rusqlite::params![now], |row| {
-                            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
-                        }).ok().into_iter().flatten().filter_map(|r| r.ok()).collect();
+                        let rows: Vec<(i64, String)> = stmt.query_map([], |row| {
+                            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+                        }).ok().into_iter().flatten().filter_map(|r| r.ok())
+                            .filter(|(_, _, at)| time_zones::reminder_due(at, now, zone))
+                            .map(|(id, title, _)| (id, title)).collect();
                         // Mark as fired
                         for (id, _) in &rows {
                             let _ = conn.execute("UPDATE reminders SET fired=1 WHERE id=?1", rusqlite::params![id]);
@@ -1587,12 +1754,15 @@ pub fn run() {
                     let note_due: Vec<(i64, String)> = {
                         let db = reminder_handle.state::<HanniDb>();
                         let conn = db.conn();
+                        let zone = time_zones::current_zone(&conn);
                         let mut stmt = match conn.prepare(
-                            "SELECT id, title FROM notes WHERE reminder_at IS NOT NULL AND reminder_at <= ?1"
+                            "SELECT id, title, reminder_at FROM notes WHERE reminder_at IS NOT NULL AND reminder_at != ''"
                         ) { Ok(s) => s, Err(_) => continue };
-                        let rows: Vec<(i64, String)> = stmt.query_map(rusqlite::params![now], |row| {
-                            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
-                        }).ok().into_iter().flatten().filter_map(|r| r.ok()).collect();
+                        let rows: Vec<(i64, String)> = stmt.query_map([], |row| {
+                            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+                        }).ok().into_iter().flatten().filter_map(|r| r.ok())
+                            .filter(|(_, _, at)| time_zones::reminder_due(at, now, zone))
+                            .map(|(id, title, _)| (id, title)).collect();
                         for (id, _) in &rows {
                             let _ = conn.execute("UPDATE notes SET reminder_at=NULL WHERE id=?1", rusqlite::params![id]);
                         }
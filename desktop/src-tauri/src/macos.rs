@@ -312,6 +312,8 @@ pub fn set_reminder(
 ) -> Result<String, String> {
     let conn = db.conn();
     let now = chrono::Local::now().to_rfc3339();
+    // A one-off reminder is meant in the zone the user is in now; pin it there
+    let remind_at = crate::time_zones::anchor_reminder(&remind_at, crate::time_zones::current_zone(&conn));
     conn.execute(
         "INSERT INTO reminders (title, remind_at, repeat, created_at) VALUES (?1, ?2, ?3, ?4)",
         rusqlite::params![title, remind_at, repeat, now],
@@ -0,0 +1,532 @@
+// medications.rs — Medications & supplements: a schedule of dose times on the
+// habit-style day schedule, dose log (taken / late / skipped), stock that
+// decrements with each dose and warns below the refill threshold, and adherence
+// stats. Today's scheduled doses are queued into `reminders` (tagged with a
+// `source` key so they're created once) and fired by the regular reminder loop.
+use crate::habits::Schedule;
+use crate::types::HanniDb;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use serde::Serialize;
+
+/// A dose taken more than this after its time counts as late.
+const LATE_MINUTES: i64 = 60;
+/// A dose with no log this long after its time counts as missed.
+const MISSED_GRACE_MINUTES: i64 = 180;
+const ADHERENCE_DAYS: i64 = 30;
+
+/// Dose times "HH:MM", validated, sorted and de-duplicated.
+pub(crate) fn parse_times(times: &[String]) -> Result<Vec<String>, String> {
+    let mut out: Vec<String> = times.iter().map(|t| {
+        NaiveTime::parse_from_str(t.trim(), "%H:%M")
+            .map(|t| t.format("%H:%M").to_string())
+            .map_err(|_| format!("Некорректное время: {}", t))
+    }).collect::<Result<_, _>>()?;
+    out.sort();
+    out.dedup();
+    Ok(out)
+}
+
+/// Scheduled dose datetimes for one day.
+pub(crate) fn doses_on(schedule: &Schedule, times: &[String], date: NaiveDate) -> Vec<NaiveDateTime> {
+    if !schedule.is_due(date) { return Vec::new(); }
+    times.iter()
+        .filter_map(|t| NaiveTime::parse_from_str(t, "%H:%M").ok())
+        .map(|t| date.and_time(t))
+        .collect()
+}
+
+/// Doses to remind about at `now`: the rest of today plus any from yesterday
+/// still inside the grace window — a 23:30 dose is still pending at 00:30.
+pub(crate) fn pending_doses(schedule: &Schedule, times: &[String], now: NaiveDateTime) -> Vec<NaiveDateTime> {
+    let today = now.date();
+    let yesterday = today.pred_opt().unwrap_or(today);
+    doses_on(schedule, times, yesterday).into_iter()
+        .chain(doses_on(schedule, times, today))
+        .filter(|at| (now - *at).num_minutes() <= MISSED_GRACE_MINUTES)
+        .collect()
+}
+
+/// Day a dose slot without an explicit date belongs to: today, unless that
+/// time is still more than half a day ahead and yesterday had the dose — then
+/// it's last night's (at 00:30 the "23:30" slot is the one just missed).
+pub(crate) fn slot_date(schedule: &Schedule, slot: NaiveTime, now: NaiveDateTime) -> NaiveDate {
+    let today = now.date();
+    match today.pred_opt() {
+        Some(y) if (today.and_time(slot) - now).num_hours() >= 12 && schedule.is_due(y) => y,
+        _ => today,
+    }
+}
+
+/// "taken" becomes "late" when logged well after the scheduled time.
+pub(crate) fn classify(status: &str, scheduled: Option<NaiveDateTime>, taken_at: NaiveDateTime) -> &'static str {
+    match status {
+        "skipped" => "skipped",
+        "late" => "late",
+        _ if scheduled.is_some_and(|s| (taken_at - s).num_minutes() > LATE_MINUTES) => "late",
+        _ => "taken",
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Adherence {
+    pub scheduled: i64,
+    pub taken: i64,
+    pub late: i64,
+    pub skipped: i64,
+    pub missed: i64,
+    /// (taken + late) / scheduled, %; `None` before the first scheduled dose.
+    pub rate: Option<f64>,
+    /// taken / scheduled, %.
+    pub on_time_rate: Option<f64>,
+}
+
+/// Compare the doses scheduled between `from` and `now` with the log.
+/// `logs` — (date, scheduled time, status). Doses still inside the grace
+/// window and not yet logged are ignored rather than counted as missed.
+pub(crate) fn adherence(
+    schedule: &Schedule, times: &[String], from: NaiveDate, now: NaiveDateTime, logs: &[(String, String, String)],
+) -> Adherence {
+    let mut a = Adherence::default();
+    let mut date = from;
+    while date <= now.date() {
+        for at in doses_on(schedule, times, date) {
+            if at > now { continue; }
+            let (d, t) = (date.to_string(), at.format("%H:%M").to_string());
+            match logs.iter().find(|(ld, lt, _)| *ld == d && *lt == t).map(|l| l.2.as_str()) {
+                Some("taken") => a.taken += 1,
+                Some("late") => a.late += 1,
+                Some(_) => a.skipped += 1,
+                None if (now - at).num_minutes() >= MISSED_GRACE_MINUTES => a.missed += 1,
+                None => continue,
+            }
+            a.scheduled += 1;
+        }
+        date += chrono::Duration::days(1);
+    }
+    if a.scheduled > 0 {
+        let pct = |n: i64| (n as f64 / a.scheduled as f64 * 1000.0).round() / 10.0;
+        a.rate = Some(pct(a.taken + a.late));
+        a.on_time_rate = Some(pct(a.taken));
+    }
+    a
+}
+
+/// Days of stock left at the scheduled pace; `None` without stock tracking or
+/// for as-needed medications.
+pub(crate) fn days_left(stock: Option<f64>, per_dose: f64, times: usize, schedule: &Schedule, as_needed: bool) -> Option<f64> {
+    let stock = stock?;
+    if as_needed || times == 0 || per_dose <= 0.0 { return None; }
+    let days_per_week = match *schedule {
+        Schedule::Daily => 7.0,
+        Schedule::Days(mask) => mask.count_ones() as f64,
+        Schedule::PerWeek(n) => n as f64,
+        Schedule::EveryNDays { n, .. } => 7.0 / n as f64,
+    };
+    let per_day = per_dose * times as f64 * days_per_week / 7.0;
+    Some((stock / per_day).floor())
+}
+
+// ── DB side ──
+
+struct Med {
+    id: i64,
+    name: String,
+    dose: f64,
+    unit: String,
+    per_dose: f64,
+    times: Vec<String>,
+    schedule: Schedule,
+    as_needed: bool,
+    stock: Option<f64>,
+    refill_threshold: Option<f64>,
+    notes: String,
+    active: bool,
+    start: NaiveDate,
+}
+
+fn load_meds(conn: &rusqlite::Connection, only_active: bool) -> Vec<Med> {
+    let sql = format!(
+        "SELECT id, name, dose, unit, per_dose, times, schedule_kind, schedule_days, schedule_count, as_needed,
+                stock, refill_threshold, notes, start_date, active FROM medications {} ORDER BY name",
+        if only_active { "WHERE active=1" } else { "" },
+    );
+    let Ok(mut stmt) = conn.prepare(&sql) else { return Vec::new() };
+    stmt.query_map([], |r| {
+        let start = NaiveDate::parse_from_str(&r.get::<_, String>(13)?, "%Y-%m-%d").unwrap_or_default();
+        Ok(Med {
+            id: r.get(0)?,
+            name: r.get(1)?,
+            dose: r.get(2)?,
+            unit: r.get(3)?,
+            per_dose: r.get(4)?,
+            times: r.get::<_, String>(5)?.split(',').filter(|t| !t.is_empty()).map(String::from).collect(),
+            schedule: Schedule::from_parts(Some(&r.get::<_, String>(6)?), r.get(7)?, r.get(8)?, "", start),
+            as_needed: r.get::<_, i64>(9)? != 0,
+            stock: r.get(10)?,
+            refill_threshold: r.get(11)?,
+            notes: r.get(12)?,
+            active: r.get::<_, i64>(14)? != 0,
+            start,
+        })
+    }).map(|rows| rows.flatten().collect()).unwrap_or_default()
+}
+
+fn reminder_source(med_id: i64, date: &str, time: &str) -> String {
+    format!("med:{}:{} {}", med_id, date, time)
+}
+
+fn dose_title(m: &Med) -> String {
+    if m.dose > 0.0 { format!("💊 {} — {} {}", m.name, m.dose, m.unit) } else { format!("💊 {}", m.name) }
+}
+
+/// Queue reminders for today's scheduled doses that aren't logged yet.
+/// Called from the reminder loop; the `source` key makes it idempotent.
+pub(crate) fn queue_reminders(conn: &rusqlite::Connection, now: NaiveDateTime) {
+    for m in load_meds(conn, true).iter().filter(|m| !m.as_needed) {
+        // Don't replay a whole day of reminders when the app starts late.
+        for at in pending_doses(&m.schedule, &m.times, now) {
+            let date_s = at.date().to_string();
+            let time = at.format("%H:%M").to_string();
+            let logged: bool = conn.query_row(
+                "SELECT EXISTS(SELECT 1 FROM medication_doses WHERE medication_id=?1 AND date=?2 AND time=?3)",
+                rusqlite::params![m.id, date_s, time], |r| r.get(0),
+            ).unwrap_or(true);
+            if logged { continue; }
+            conn.execute(
+                "INSERT INTO reminders (title, remind_at, repeat, created_at, source)
+                 SELECT ?1, ?2, NULL, ?3, ?4 WHERE NOT EXISTS (SELECT 1 FROM reminders WHERE source=?4)",
+                rusqlite::params![dose_title(m), at.format("%Y-%m-%dT%H:%M:00").to_string(),
+                    chrono::Local::now().to_rfc3339(), reminder_source(m.id, &date_s, &time)],
+            ).ok();
+        }
+    }
+}
+
+/// Stock change when a dose's status moves from `old` to `new`.
+fn stock_delta(old: Option<&str>, new: Option<&str>, per_dose: f64) -> f64 {
+    let consumed = |s: Option<&str>| matches!(s, Some("taken") | Some("late"));
+    match (consumed(old), consumed(new)) {
+        (false, true) => -per_dose,
+        (true, false) => per_dose,
+        _ => 0.0,
+    }
+}
+
+fn apply_stock(conn: &rusqlite::Connection, med_id: i64, delta: f64) -> rusqlite::Result<()> {
+    if delta != 0.0 {
+        conn.execute(
+            "UPDATE medications SET stock = MAX(0, stock + ?1) WHERE id=?2 AND stock IS NOT NULL",
+            rusqlite::params![delta, med_id],
+        )?;
+    }
+    Ok(())
+}
+
+fn refill_needed(m: &Med) -> bool {
+    matches!((m.stock, m.refill_threshold), (Some(s), Some(t)) if s <= t)
+}
+
+/// Lines for the morning digest: doses planned today and refills due.
+pub(crate) fn digest_lines(conn: &rusqlite::Connection) -> Vec<String> {
+    let today = chrono::Local::now().date_naive();
+    let meds = load_meds(conn, true);
+    let mut lines = Vec::new();
+    let planned: Vec<String> = meds.iter().filter(|m| !m.as_needed).filter_map(|m| {
+        let doses = doses_on(&m.schedule, &m.times, today);
+        (!doses.is_empty()).then(|| format!("{} ({})", m.name,
+            doses.iter().map(|d| d.format("%H:%M").to_string()).collect::<Vec<_>>().join(", ")))
+    }).collect();
+    if !planned.is_empty() {
+        lines.push(format!("Medications today: {}", planned.join("; ")));
+    }
+    for m in meds.iter().filter(|m| refill_needed(m)) {
+        lines.push(format!("Refill needed: {} — {} left", m.name, m.stock.unwrap_or(0.0)));
+    }
+    lines
+}
+
+// ── Commands ──
+
+#[tauri::command]
+pub fn get_medications(include_inactive: Option<bool>, db: tauri::State<'_, HanniDb>) -> Result<Vec<serde_json::Value>, String> {
+    let conn = db.read();
+    let now = chrono::Local::now().naive_local();
+    let today = now.date();
+    let today_s = today.to_string();
+    let from = today - chrono::Duration::days(ADHERENCE_DAYS - 1);
+    let mut out = Vec::new();
+    for m in load_meds(&conn, !include_inactive.unwrap_or(false)) {
+        let mut stmt = conn.prepare(
+            "SELECT id, date, COALESCE(time, ''), status, taken_at FROM medication_doses
+             WHERE medication_id=?1 AND date >= ?2 ORDER BY date, time",
+        ).map_err(|e| format!("DB error: {}", e))?;
+        let logs: Vec<(i64, String, String, String, Option<String>)> = stmt.query_map(
+            rusqlite::params![m.id, from.to_string()],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?)),
+        ).map_err(|e| format!("Query error: {}", e))?.flatten().collect();
+        let triples: Vec<(String, String, String)> = logs.iter().map(|l| (l.1.clone(), l.2.clone(), l.3.clone())).collect();
+        let adh = (!m.as_needed).then(|| adherence(&m.schedule, &m.times, from.max(m.start), now, &triples));
+
+        // Each slot is the dose logging it would hit: before noon a late
+        // evening slot is still last night's.
+        let mut today_doses: Vec<serde_json::Value> = doses_on(&m.schedule, &m.times, today).iter().map(|at| {
+            let at = slot_date(&m.schedule, at.time(), now).and_time(at.time());
+            let (date, time) = (at.date().to_string(), at.format("%H:%M").to_string());
+            let log = logs.iter().find(|l| l.1 == date && l.2 == time);
+            serde_json::json!({
+                "time": time, "dose_id": log.map(|l| l.0), "status": log.map(|l| l.3.clone()),
+                "taken_at": log.and_then(|l| l.4.clone()), "overdue": log.is_none() && at < now,
+            })
+        }).collect();
+        // As-needed (and off-schedule) doses logged today.
+        for l in logs.iter().filter(|l| l.1 == today_s && !m.times.contains(&l.2)) {
+            today_doses.push(serde_json::json!({
+                "time": l.4.as_deref().and_then(|t| t.get(11..16)).unwrap_or(""), "dose_id": l.0,
+                "status": l.3, "taken_at": l.4, "overdue": false, "extra": true,
+            }));
+        }
+        let (kind, days, count) = m.schedule.to_parts();
+        out.push(serde_json::json!({
+            "id": m.id, "name": m.name, "dose": m.dose, "unit": m.unit, "per_dose": m.per_dose,
+            "times": m.times, "schedule_kind": kind, "schedule_days": days, "schedule_count": count,
+            "schedule_label": if m.as_needed { "По необходимости".to_string() } else { m.schedule.label() },
+            "as_needed": m.as_needed, "active": m.active, "notes": m.notes,
+            "stock": m.stock, "refill_threshold": m.refill_threshold, "refill_needed": refill_needed(&m),
+            "days_left": days_left(m.stock, m.per_dose, m.times.len(), &m.schedule, m.as_needed),
+            "today": today_doses, "adherence": adh,
+        }));
+    }
+    Ok(out)
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn create_medication(
+    name: String, dose: Option<f64>, unit: Option<String>, per_dose: Option<f64>, times: Vec<String>,
+    schedule_kind: Option<String>, schedule_days: Option<i64>, schedule_count: Option<i64>,
+    as_needed: Option<bool>, stock: Option<f64>, refill_threshold: Option<f64>, notes: Option<String>,
+    db: tauri::State<'_, HanniDb>,
+) -> Result<i64, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() { return Err("Введите название".into()); }
+    let as_needed = as_needed.unwrap_or(false);
+    let times = parse_times(&times)?;
+    if times.is_empty() && !as_needed { return Err("Добавьте хотя бы одно время приёма".into()); }
+    let schedule = crate::habits::parse_schedule(schedule_kind.as_deref().unwrap_or("daily"), schedule_days.unwrap_or(0), schedule_count.unwrap_or(1))?;
+    if matches!(schedule, Schedule::PerWeek(_)) { return Err("Для лекарств выберите дни или интервал".into()); }
+    let (kind, days, count) = schedule.to_parts();
+    let now = chrono::Local::now();
+    let conn = db.conn();
+    conn.execute(
+        "INSERT INTO medications (name, dose, unit, per_dose, times, schedule_kind, schedule_days, schedule_count,
+            as_needed, stock, refill_threshold, notes, start_date, created_at)
+         VALUES (?1,?2,?3,?4,?5,?6,?7,?8,?9,?10,?11,?12,?13,?14)",
+        rusqlite::params![name, dose.unwrap_or(0.0), unit.unwrap_or_default(), per_dose.unwrap_or(1.0), times.join(","),
+            kind, days, count, as_needed as i64, stock, refill_threshold, notes.unwrap_or_default(),
+            now.format("%Y-%m-%d").to_string(), now.to_rfc3339()],
+    ).map_err(|e| format!("DB error: {}", e))?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Partial update: `None` leaves a field as is. `stock`/`refill_threshold`
+/// below zero switch stock tracking off.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn update_medication(
+    id: i64, name: Option<String>, dose: Option<f64>, unit: Option<String>, per_dose: Option<f64>,
+    times: Option<Vec<String>>, schedule_kind: Option<String>, schedule_days: Option<i64>, schedule_count: Option<i64>,
+    as_needed: Option<bool>, stock: Option<f64>, refill_threshold: Option<f64>, notes: Option<String>, active: Option<bool>,
+    db: tauri::State<'_, HanniDb>,
+) -> Result<(), String> {
+    let conn = db.conn();
+    let set = |col: &str, v: &dyn rusqlite::ToSql| conn.execute(&format!("UPDATE medications SET {}=?1 WHERE id=?2", col), rusqlite::params![v, id])
+        .map(|_| ()).map_err(|e| format!("DB error: {}", e));
+    if let Some(n) = name.map(|n| n.trim().to_string()).filter(|n| !n.is_empty()) { set("name", &n)?; }
+    if let Some(v) = dose { set("dose", &v)?; }
+    if let Some(v) = unit { set("unit", &v)?; }
+    if let Some(v) = per_dose.filter(|v| *v > 0.0) { set("per_dose", &v)?; }
+    if let Some(t) = times { set("times", &parse_times(&t)?.join(","))?; }
+    if let Some(kind) = schedule_kind.as_deref() {
+        let schedule = crate::habits::parse_schedule(kind, schedule_days.unwrap_or(0), schedule_count.unwrap_or(1))?;
+        if matches!(schedule, Schedule::PerWeek(_)) { return Err("Для лекарств выберите дни или интервал".into()); }
+        let (kind, days, count) = schedule.to_parts();
+        set("schedule_kind", &kind)?;
+        set("schedule_days", &days)?;
+        set("schedule_count", &count)?;
+    }
+    if let Some(v) = as_needed { set("as_needed", &(v as i64))?; }
+    if let Some(v) = stock { set("stock", &(v >= 0.0).then_some(v))?; }
+    if let Some(v) = refill_threshold { set("refill_threshold", &(v >= 0.0).then_some(v))?; }
+    if let Some(v) = notes { set("notes", &v)?; }
+    if let Some(v) = active { set("active", &(v as i64))?; }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_medication(id: i64, db: tauri::State<'_, HanniDb>) -> Result<(), String> {
+    let conn = db.conn();
+    conn.execute("DELETE FROM medication_doses WHERE medication_id=?1", [id]).map_err(|e| format!("DB error: {}", e))?;
+    conn.execute("DELETE FROM reminders WHERE fired=0 AND source LIKE ?1", [format!("med:{}:%", id)]).ok();
+    conn.execute("DELETE FROM medications WHERE id=?1", [id]).map_err(|e| format!("DB error: {}", e))?;
+    Ok(())
+}
+
+/// Log a dose. `time` is the scheduled slot ("HH:MM"); leave it empty for an
+/// as-needed / extra dose. Re-logging a slot replaces its status. Stock moves
+/// with the change and the slot's pending reminder is dropped.
+#[tauri::command]
+pub fn log_medication_dose(
+    medication_id: i64, status: String, date: Option<String>, time: Option<String>, note: Option<String>,
+    db: tauri::State<'_, HanniDb>,
+) -> Result<serde_json::Value, String> {
+    if !["taken", "late", "skipped"].contains(&status.as_str()) {
+        return Err(format!("Неизвестный статус: {}", status));
+    }
+    let now = chrono::Local::now();
+    let time = time.map(|t| t.trim().to_string()).filter(|t| !t.is_empty());
+    let slot = match time.as_deref() {
+        Some(t) => Some(NaiveTime::parse_from_str(t, "%H:%M").map_err(|_| format!("Некорректное время: {}", t))?),
+        None => None,
+    };
+    let conn = db.conn();
+    let med = load_meds(&conn, false).into_iter().find(|m| m.id == medication_id)
+        .ok_or_else(|| "Лекарство не найдено".to_string())?;
+    let per_dose = med.per_dose;
+    let date = match (date, slot) {
+        (Some(d), _) => d,
+        (None, Some(t)) => slot_date(&med.schedule, t, now.naive_local()).to_string(),
+        (None, None) => now.format("%Y-%m-%d").to_string(),
+    };
+    let day = NaiveDate::parse_from_str(&date, "%Y-%m-%d").map_err(|_| "Некорректная дата".to_string())?;
+    let taken_at = now.naive_local();
+    let status = classify(&status, slot.map(|t| day.and_time(t)), taken_at);
+    let existing: Option<(i64, String)> = match time.as_deref() {
+        Some(t) => conn.query_row(
+            "SELECT id, status FROM medication_doses WHERE medication_id=?1 AND date=?2 AND time=?3",
+            rusqlite::params![medication_id, date, t], |r| Ok((r.get(0)?, r.get(1)?)),
+        ).ok(),
+        None => None,
+    };
+    let taken_at_s = (status != "skipped").then(|| taken_at.format("%Y-%m-%dT%H:%M:%S").to_string());
+    let id = match &existing {
+        Some((id, _)) => {
+            conn.execute(
+                "UPDATE medication_doses SET status=?1, taken_at=?2, note=COALESCE(?3, note) WHERE id=?4",
+                rusqlite::params![status, taken_at_s, note, id],
+            ).map_err(|e| format!("DB error: {}", e))?;
+            *id
+        }
+        None => {
+            conn.execute(
+                "INSERT INTO medication_doses (medication_id, date, time, status, taken_at, note, created_at)
+                 VALUES (?1,?2,?3,?4,?5,?6,?7)",
+                rusqlite::params![medication_id, date, time, status, taken_at_s, note.unwrap_or_default(), now.to_rfc3339()],
+            ).map_err(|e| format!("DB error: {}", e))?;
+            conn.last_insert_rowid()
+        }
+    };
+    apply_stock(&conn, medication_id, stock_delta(existing.as_ref().map(|e| e.1.as_str()), Some(status), per_dose))
+        .map_err(|e| format!("DB error: {}", e))?;
+    if let Some(t) = time.as_deref() {
+        conn.execute("DELETE FROM reminders WHERE fired=0 AND source=?1", [reminder_source(medication_id, &date, t)]).ok();
+    }
+    let (stock, threshold): (Option<f64>, Option<f64>) = conn.query_row(
+        "SELECT stock, refill_threshold FROM medications WHERE id=?1", [medication_id], |r| Ok((r.get(0)?, r.get(1)?)),
+    ).unwrap_or((None, None));
+    Ok(serde_json::json!({
+        "id": id, "status": status, "stock": stock,
+        "refill_needed": matches!((stock, threshold), (Some(s), Some(t)) if s <= t),
+    }))
+}
+
+/// Remove a logged dose, returning its stock.
+#[tauri::command]
+pub fn delete_medication_dose(id: i64, db: tauri::State<'_, HanniDb>) -> Result<(), String> {
+    let conn = db.conn();
+    let (med_id, status, per_dose): (i64, String, f64) = conn.query_row(
+        "SELECT d.medication_id, d.status, m.per_dose FROM medication_doses d JOIN medications m ON m.id = d.medication_id WHERE d.id=?1",
+        [id], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+    ).map_err(|_| "Запись не найдена".to_string())?;
+    conn.execute("DELETE FROM medication_doses WHERE id=?1", [id]).map_err(|e| format!("DB error: {}", e))?;
+    apply_stock(&conn, med_id, stock_delta(Some(&status), None, per_dose)).map_err(|e| format!("DB error: {}", e))?;
+    Ok(())
+}
+
+/// Add a new pack to the stock (turns stock tracking on if it was off).
+#[tauri::command]
+pub fn restock_medication(id: i64, amount: f64, db: tauri::State<'_, HanniDb>) -> Result<f64, String> {
+    if amount <= 0.0 { return Err("Количество должно быть больше нуля".into()); }
+    let conn = db.conn();
+    conn.execute("UPDATE medications SET stock = COALESCE(stock, 0) + ?1 WHERE id=?2", rusqlite::params![amount, id])
+        .map_err(|e| format!("DB error: {}", e))?;
+    conn.query_row("SELECT stock FROM medications WHERE id=?1", [id], |r| r.get(0)).map_err(|e| format!("DB error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::d;
+
+    fn dt(s: &str) -> NaiveDateTime { NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap() }
+
+    #[test]
+    fn times_and_late_classification() {
+        assert_eq!(parse_times(&["20:00".into(), "8:00".into(), "08:00".into()]).unwrap(), vec!["08:00", "20:00"]);
+        assert!(parse_times(&["25:00".into()]).is_err());
+        let at = Some(dt("2024-05-01 08:00"));
+        assert_eq!(classify("taken", at, dt("2024-05-01 08:45")), "taken");
+        assert_eq!(classify("taken", at, dt("2024-05-01 09:30")), "late");
+        assert_eq!(classify("taken", None, dt("2024-05-01 23:00")), "taken");
+        assert_eq!(classify("skipped", at, dt("2024-05-01 12:00")), "skipped");
+    }
+
+    #[test]
+    fn adherence_counts_missed_after_grace() {
+        let times = vec!["08:00".to_string(), "20:00".to_string()];
+        let logs = vec![
+            ("2024-05-01".to_string(), "08:00".to_string(), "taken".to_string()),
+            ("2024-05-01".to_string(), "20:00".to_string(), "late".to_string()),
+            ("2024-05-02".to_string(), "08:00".to_string(), "skipped".to_string()),
+        ];
+        // 05-02 20:00 is unlogged but only 1h ago → not yet missed; 05-03 08:00 is in the future.
+        let a = adherence(&Schedule::Daily, &times, d("2024-05-01"), dt("2024-05-02 21:00"), &logs);
+        assert_eq!((a.scheduled, a.taken, a.late, a.skipped, a.missed), (3, 1, 1, 1, 0));
+        assert_eq!(a.rate, Some(66.7));
+        let a = adherence(&Schedule::Daily, &times, d("2024-05-01"), dt("2024-05-03 07:00"), &logs);
+        assert_eq!(a.missed, 1);
+        assert_eq!(a.on_time_rate, Some(25.0));
+    }
+
+    #[test]
+    fn stock_and_days_left() {
+        assert_eq!(stock_delta(None, Some("taken"), 2.0), -2.0);
+        assert_eq!(stock_delta(Some("late"), Some("skipped"), 1.0), 1.0);
+        assert_eq!(stock_delta(Some("taken"), Some("late"), 1.0), 0.0);
+        // Two pills twice a day on weekdays: 20/week.
+        assert_eq!(days_left(Some(40.0), 2.0, 2, &Schedule::Days(0b0011111), false), Some(14.0));
+        assert_eq!(days_left(Some(40.0), 1.0, 0, &Schedule::Daily, true), None);
+    }
+
+    #[test]
+    fn late_evening_dose_crosses_midnight() {
+        let times = vec!["08:00".to_string(), "23:30".to_string()];
+        // At 00:30 last night's dose is still pending; the 08:00 one from yesterday isn't.
+        let p = pending_doses(&Schedule::Daily, &times, dt("2024-05-02 00:30"));
+        assert_eq!(p, vec![dt("2024-05-01 23:30"), dt("2024-05-02 08:00"), dt("2024-05-02 23:30")]);
+        // Logged at 01:00 without a date: it's yesterday's slot, taken 90 min late.
+        let hm = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        assert_eq!(slot_date(&Schedule::Daily, hm(23, 30), dt("2024-05-02 01:00")), d("2024-05-01"));
+        assert_eq!(slot_date(&Schedule::Daily, hm(8, 0), dt("2024-05-02 01:00")), d("2024-05-02"));
+        assert_eq!(slot_date(&Schedule::Daily, hm(23, 30), dt("2024-05-02 12:00")), d("2024-05-02"));
+        // 2024-05-01 is a Wednesday: a weekend-only dose has no slot last night.
+        assert_eq!(slot_date(&Schedule::Days(0b1100000), hm(23, 30), dt("2024-05-02 01:00")), d("2024-05-02"));
+        assert_eq!(classify("taken", Some(dt("2024-05-01 23:30")), dt("2024-05-02 01:00")), "late");
+        // Unlogged, it only turns missed once the grace window has passed the next morning.
+        let a = adherence(&Schedule::Daily, &times[1..], d("2024-05-01"), dt("2024-05-02 01:00"), &[]);
+        assert_eq!((a.scheduled, a.missed), (0, 0));
+        let a = adherence(&Schedule::Daily, &times[1..], d("2024-05-01"), dt("2024-05-02 02:30"), &[]);
+        assert_eq!((a.scheduled, a.missed), (1, 1));
+        // Nothing scheduled yet → no rates.
+        assert_eq!(adherence(&Schedule::Daily, &[], d("2024-05-01"), dt("2024-05-02 12:00"), &[]).rate, None);
+    }
+}
@@ -0,0 +1,483 @@
+// nutrition.rs — Structured nutrition: unit → grams conversion, per-100g catalog
+// macros, per-serving recipe calculation (reused by log_food) and dataset import.
+
+use std::collections::HashMap;
+
+use crate::types::HanniDb;
+
+/// What a recipe/product unit measures. Volume is converted through the
+/// ingredient's density, pieces through its piece weight.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnitKind {
+    /// Grams per unit.
+    Mass(f64),
+    /// Millilitres per unit.
+    Volume(f64),
+    Piece,
+    /// "по вкусу", "щепотка" — counted as zero, not as missing data.
+    Negligible,
+    Unknown,
+}
+
+pub fn unit_kind(unit: &str) -> UnitKind {
+    let u = unit.trim().to_lowercase();
+    let u = u.trim_end_matches('.').replace(' ', "");
+    match u.as_str() {
+        "" | "г" | "гр" | "грамм" | "грамма" | "граммов" | "g" => UnitKind::Mass(1.0),
+        "кг" | "kg" => UnitKind::Mass(1000.0),
+        "мг" | "mg" => UnitKind::Mass(0.001),
+        "мл" | "ml" => UnitKind::Volume(1.0),
+        "л" | "l" | "литр" => UnitKind::Volume(1000.0),
+        "ст.л" | "стл" | "ст.ложка" | "столоваяложка" | "tbsp" => UnitKind::Volume(15.0),
+        "ч.л" | "чл" | "ч.ложка" | "чайнаяложка" | "tsp" => UnitKind::Volume(5.0),
+        "стакан" | "стак" | "cup" => UnitKind::Volume(250.0),
+        "шт" | "штука" | "штуки" | "штук" | "pcs" | "зубчик" | "зубчика" | "пучок" | "ломтик" | "кусок" => UnitKind::Piece,
+        "повкусу" | "щепотка" | "щеп" => UnitKind::Negligible,
+        _ => UnitKind::Unknown,
+    }
+}
+
+/// Water density — used for volume units when the ingredient has none stored.
+pub const DEFAULT_DENSITY_G_ML: f64 = 1.0;
+
+/// Convert `amount unit` to grams. `None` means "cannot tell" (piece without
+/// a known piece weight, unknown unit) and is reported as missing coverage.
+pub fn to_grams(amount: f64, unit: &str, density_g_ml: Option<f64>, piece_g: Option<f64>) -> Option<f64> {
+    match unit_kind(unit) {
+        UnitKind::Mass(g) => Some(amount * g),
+        UnitKind::Volume(ml) => Some(amount * ml * density_g_ml.filter(|d| *d > 0.0).unwrap_or(DEFAULT_DENSITY_G_ML)),
+        UnitKind::Piece => piece_g.filter(|p| *p > 0.0).map(|p| amount * p),
+        UnitKind::Negligible => Some(0.0),
+        UnitKind::Unknown => None,
+    }
+}
+
+/// Inverse of `to_grams`: how many `unit` make `grams`.
+pub fn from_grams(grams: f64, unit: &str, density_g_ml: Option<f64>, piece_g: Option<f64>) -> Option<f64> {
+    match unit_kind(unit) {
+        UnitKind::Mass(g) => Some(grams / g),
+        UnitKind::Volume(ml) => Some(grams / density_g_ml.filter(|d| *d > 0.0).unwrap_or(DEFAULT_DENSITY_G_ML) / ml),
+        UnitKind::Piece => piece_g.filter(|p| *p > 0.0).map(|p| grams / p),
+        UnitKind::Negligible | UnitKind::Unknown => None,
+    }
+}
+
+/// Convert `amount` between two units of the same ingredient — directly within
+/// a dimension, otherwise through grams (density / piece weight).
+pub fn convert_amount(amount: f64, from: &str, to: &str, density_g_ml: Option<f64>, piece_g: Option<f64>) -> Option<f64> {
+    match (unit_kind(from), unit_kind(to)) {
+        (UnitKind::Mass(a), UnitKind::Mass(b)) | (UnitKind::Volume(a), UnitKind::Volume(b)) => Some(amount * a / b),
+        (UnitKind::Piece, UnitKind::Piece) => Some(amount),
+        (UnitKind::Unknown, UnitKind::Unknown) if from.trim().to_lowercase() == to.trim().to_lowercase() => Some(amount),
+        _ => from_grams(to_grams(amount, from, density_g_ml, piece_g)?, to, density_g_ml, piece_g),
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize)]
+pub struct Macros {
+    pub kcal: f64,
+    pub protein: f64,
+    pub fat: f64,
+    pub carbs: f64,
+    pub fiber: f64,
+}
+
+impl Macros {
+    pub fn scaled(&self, factor: f64) -> Macros {
+        Macros {
+            kcal: self.kcal * factor,
+            protein: self.protein * factor,
+            fat: self.fat * factor,
+            carbs: self.carbs * factor,
+            fiber: self.fiber * factor,
+        }
+    }
+    pub fn add(&mut self, o: &Macros) {
+        self.kcal += o.kcal;
+        self.protein += o.protein;
+        self.fat += o.fat;
+        self.carbs += o.carbs;
+        self.fiber += o.fiber;
+    }
+}
+
+/// Catalog nutrition row used by the calculator.
+#[derive(Debug, Clone, Copy)]
+pub struct CatalogNutrition {
+    pub per_100g: Option<Macros>,
+    pub density_g_ml: Option<f64>,
+    pub piece_g: Option<f64>,
+}
+
+/// Normalized name → catalog id, built in one scan (SQLite NOCASE can't fold Cyrillic).
+pub fn catalog_name_index(conn: &rusqlite::Connection) -> HashMap<String, i64> {
+    let mut out = HashMap::new();
+    if let Ok(mut stmt) = conn.prepare("SELECT id, name FROM ingredient_catalog") {
+        if let Ok(rows) = stmt.query_map([], |r| Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?))) {
+            for (id, name) in rows.flatten() {
+                out.insert(crate::db::normalize_name(&name), id);
+            }
+        }
+    }
+    out
+}
+
+pub fn catalog_nutrition(conn: &rusqlite::Connection, catalog_id: i64) -> Option<CatalogNutrition> {
+    conn.query_row(
+        "SELECT kcal_100g, protein_100g, fat_100g, carbs_100g, fiber_100g, density_g_ml, piece_g \
+         FROM ingredient_catalog WHERE id=?1",
+        rusqlite::params![catalog_id],
+        |r| {
+            let kcal: Option<f64> = r.get(0)?;
+            Ok(CatalogNutrition {
+                per_100g: kcal.map(|k| Macros {
+                    kcal: k,
+                    protein: r.get::<_, Option<f64>>(1).ok().flatten().unwrap_or(0.0),
+                    fat: r.get::<_, Option<f64>>(2).ok().flatten().unwrap_or(0.0),
+                    carbs: r.get::<_, Option<f64>>(3).ok().flatten().unwrap_or(0.0),
+                    fiber: r.get::<_, Option<f64>>(4).ok().flatten().unwrap_or(0.0),
+                }),
+                density_g_ml: r.get(5)?,
+                piece_g: r.get(6)?,
+            })
+        },
+    ).ok()
+}
+
+/// Macros of one ingredient line, or `None` when it can't be resolved.
+pub fn ingredient_macros(amount: f64, unit: &str, nut: &CatalogNutrition) -> Option<Macros> {
+    let per = nut.per_100g?;
+    let grams = to_grams(amount, unit, nut.density_g_ml, nut.piece_g)?;
+    Some(per.scaled(grams / 100.0))
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RecipeNutrition {
+    pub servings: i64,
+    pub total: Macros,
+    pub per_serving: Macros,
+    /// Resolved ingredient lines / all lines (0..1).
+    pub coverage: f64,
+    pub items: Vec<serde_json::Value>,
+    pub missing: Vec<String>,
+}
+
+/// Compute a recipe's nutrition from its structured `recipe_ingredients`.
+/// Lines without a catalog link are matched by name on the fly.
+pub fn compute_recipe_nutrition(conn: &rusqlite::Connection, recipe_id: i64) -> Result<RecipeNutrition, String> {
+    let servings: i64 = conn.query_row(
+        "SELECT servings FROM recipes WHERE id=?1", rusqlite::params![recipe_id], |r| r.get(0),
+    ).map_err(|e| format!("Recipe not found: {}", e))?;
+    let servings = servings.max(1);
+    let mut stmt = conn.prepare(
+        "SELECT name, amount, unit, catalog_id FROM recipe_ingredients WHERE recipe_id=?1 ORDER BY id"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let lines: Vec<(String, f64, String, Option<i64>)> = stmt.query_map(rusqlite::params![recipe_id], |r| {
+        Ok((r.get(0)?, r.get::<_, Option<f64>>(1)?.unwrap_or(0.0), r.get::<_, Option<String>>(2)?.unwrap_or_default(), r.get(3)?))
+    }).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+
+    let index = if lines.iter().any(|l| l.3.is_none()) { catalog_name_index(conn) } else { HashMap::new() };
+    let mut total = Macros::default();
+    let mut items = Vec::new();
+    let mut missing = Vec::new();
+    for (name, amount, unit, cat_id) in &lines {
+        let cid = cat_id.or_else(|| index.get(&crate::db::normalize_name(name)).copied());
+        // "по вкусу" lines don't need catalog data to count as resolved.
+        let m = if unit_kind(unit) == UnitKind::Negligible {
+            Some(Macros::default())
+        } else {
+            cid.and_then(|id| catalog_nutrition(conn, id))
+                .and_then(|n| ingredient_macros(*amount, unit, &n))
+        };
+        match m {
+            Some(m) => {
+                total.add(&m);
+                items.push(serde_json::json!({
+                    "name": name, "amount": amount, "unit": unit, "catalog_id": cid,
+                    "kcal": round1(m.kcal), "protein": round1(m.protein), "fat": round1(m.fat),
+                    "carbs": round1(m.carbs), "fiber": round1(m.fiber),
+                }));
+            }
+            None => {
+                missing.push(name.clone());
+                items.push(serde_json::json!({
+                    "name": name, "amount": amount, "unit": unit, "catalog_id": cid, "missing": true,
+                }));
+            }
+        }
+    }
+    let coverage = if lines.is_empty() { 0.0 } else { (lines.len() - missing.len()) as f64 / lines.len() as f64 };
+    Ok(RecipeNutrition {
+        servings,
+        total,
+        per_serving: total.scaled(1.0 / servings as f64),
+        coverage,
+        items,
+        missing,
+    })
+}
+
+fn round1(x: f64) -> f64 { (x * 10.0).round() / 10.0 }
+
+/// Write per-serving macros back into `recipes` and flag them as computed.
+/// Skipped when nothing resolved, so a hand-typed value is never zeroed out.
+pub(crate) fn recalc_recipe(conn: &rusqlite::Connection, recipe_id: i64) -> Result<RecipeNutrition, String> {
+    let n = compute_recipe_nutrition(conn, recipe_id)?;
+    if n.coverage > 0.0 {
+        let p = &n.per_serving;
+        conn.execute(
+            "UPDATE recipes SET calories=?1, protein=?2, fat=?3, carbs=?4, fiber=?5, \
+             nutrition_auto=1, nutrition_coverage=?6 WHERE id=?7",
+            rusqlite::params![p.kcal.round() as i64, p.protein.round() as i64, p.fat.round() as i64,
+                p.carbs.round() as i64, p.fiber.round() as i64, n.coverage, recipe_id],
+        ).map_err(|e| format!("DB error: {}", e))?;
+        crate::sync_share::mark_dirty(conn, "recipes");
+    }
+    Ok(n)
+}
+
+/// Called after create/update_recipe. Recomputes unless the user typed
+/// calories by hand (explicit value, or a non-zero value not produced by us).
+pub(crate) fn auto_recalc_after_edit(conn: &rusqlite::Connection, recipe_id: i64, calories_given: bool) {
+    if calories_given {
+        let _ = conn.execute("UPDATE recipes SET nutrition_auto=0 WHERE id=?1", rusqlite::params![recipe_id]);
+        return;
+    }
+    let (auto, kcal): (i64, i64) = conn.query_row(
+        "SELECT nutrition_auto, calories FROM recipes WHERE id=?1", rusqlite::params![recipe_id],
+        |r| Ok((r.get(0)?, r.get::<_, Option<i64>>(1)?.unwrap_or(0))),
+    ).unwrap_or((0, 0));
+    if auto == 1 || kcal == 0 {
+        let _ = recalc_recipe(conn, recipe_id);
+    }
+}
+
+/// Per-serving macros stored on a recipe, for `log_food`.
+pub(crate) fn recipe_serving_macros(conn: &rusqlite::Connection, recipe_id: i64) -> Option<(String, Macros)> {
+    conn.query_row(
+        "SELECT name, calories, protein, fat, carbs, fiber FROM recipes WHERE id=?1",
+        rusqlite::params![recipe_id],
+        |r| Ok((r.get::<_, String>(0)?, Macros {
+            kcal: r.get::<_, Option<i64>>(1)?.unwrap_or(0) as f64,
+            protein: r.get::<_, Option<i64>>(2).ok().flatten().unwrap_or(0) as f64,
+            fat: r.get::<_, Option<i64>>(3).ok().flatten().unwrap_or(0) as f64,
+            carbs: r.get::<_, Option<i64>>(4).ok().flatten().unwrap_or(0) as f64,
+            fiber: r.get::<_, Option<i64>>(5).ok().flatten().unwrap_or(0) as f64,
+        })),
+    ).ok()
+}
+
+/// Parse a nutrition dataset: a JSON array of `{name, kcal, protein, fat,
+/// carbs, fiber, density, piece_g}` or a CSV/TSV with those header names
+/// (Russian/English aliases accepted, `,` decimal commas tolerated with `;`).
+pub(crate) fn parse_dataset(text: &str) -> Result<Vec<crate::nutrition_seed::SeedNutrition>, String> {
+    let trimmed = text.trim_start_matches('\u{feff}').trim();
+    if trimmed.starts_with('[') {
+        return serde_json::from_str(trimmed).map_err(|e| format!("JSON error: {}", e));
+    }
+    let mut lines = trimmed.lines().filter(|l| !l.trim().is_empty());
+    let header = lines.next().ok_or("Пустой файл")?;
+    let delim = if header.contains('\t') { '\t' } else if header.contains(';') { ';' } else { ',' };
+    let cols: Vec<String> = header.split(delim).map(|c| c.trim().trim_matches('"').to_lowercase()).collect();
+    let find = |aliases: &[&str]| cols.iter().position(|c| aliases.contains(&c.as_str()));
+    let name_i = find(&["name", "название", "продукт"]).ok_or("Нет колонки name")?;
+    let kcal_i = find(&["kcal", "calories", "energy", "ккал", "калории"]).ok_or("Нет колонки kcal")?;
+    let prot_i = find(&["protein", "белки", "белок"]);
+    let fat_i = find(&["fat", "жиры", "жир"]);
+    let carb_i = find(&["carbs", "carbohydrates", "углеводы"]);
+    let fib_i = find(&["fiber", "fibre", "клетчатка"]);
+    let dens_i = find(&["density", "density_g_ml", "плотность"]);
+    let piece_i = find(&["piece_g", "piece", "вес_шт"]);
+    let mut out = Vec::new();
+    for line in lines {
+        let cells: Vec<&str> = line.split(delim).map(|c| c.trim().trim_matches('"')).collect();
+        let num = |i: Option<usize>| -> Option<f64> {
+            i.and_then(|i| cells.get(i)).and_then(|v| v.replace(',', ".").parse::<f64>().ok())
+        };
+        let name = cells.get(name_i).map(|s| s.to_string()).unwrap_or_default();
+        let Some(kcal) = num(Some(kcal_i)) else { continue };
+        if name.is_empty() { continue; }
+        out.push(crate::nutrition_seed::SeedNutrition {
+            name,
+            kcal,
+            protein: num(prot_i).unwrap_or(0.0),
+            fat: num(fat_i).unwrap_or(0.0),
+            carbs: num(carb_i).unwrap_or(0.0),
+            fiber: num(fib_i).unwrap_or(0.0),
+            density: num(dens_i),
+            piece_g: num(piece_i),
+        });
+    }
+    Ok(out)
+}
+
+// ── Commands ──
+
+#[tauri::command]
+pub fn get_recipe_nutrition(id: i64, db: tauri::State<'_, HanniDb>) -> Result<RecipeNutrition, String> {
+    let conn = db.read();
+    compute_recipe_nutrition(&conn, id)
+}
+
+#[tauri::command]
+pub fn recalc_recipe_nutrition(id: Option<i64>, db: tauri::State<'_, HanniDb>) -> Result<serde_json::Value, String> {
+    let conn = db.conn();
+    if let Some(id) = id {
+        let n = recalc_recipe(&conn, id)?;
+        return Ok(serde_json::json!({ "updated": if n.coverage > 0.0 { 1 } else { 0 }, "coverage": n.coverage, "missing": n.missing }));
+    }
+    // All recipes whose macros are ours or empty — hand-typed values stay.
+    let ids: Vec<i64> = conn.prepare("SELECT id FROM recipes WHERE nutrition_auto=1 OR COALESCE(calories,0)=0")
+        .map_err(|e| format!("DB error: {}", e))?
+        .query_map([], |r| r.get(0)).map_err(|e| format!("Query error: {}", e))?
+        .filter_map(|r| r.ok()).collect();
+    let mut updated = 0;
+    for rid in ids {
+        if recalc_recipe(&conn, rid).map(|n| n.coverage > 0.0).unwrap_or(false) { updated += 1; }
+    }
+    Ok(serde_json::json!({ "updated": updated }))
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn set_ingredient_nutrition(
+    id: i64, kcal: Option<f64>, protein: Option<f64>, fat: Option<f64>, carbs: Option<f64>,
+    fiber: Option<f64>, density_g_ml: Option<f64>, piece_g: Option<f64>,
+    db: tauri::State<'_, HanniDb>,
+) -> Result<(), String> {
+    let conn = db.conn();
+    let mut updates = Vec::new();
+    let mut params: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
+    let mut idx = 1;
+    for (col, v) in [("kcal_100g", kcal), ("protein_100g", protein), ("fat_100g", fat), ("carbs_100g", carbs),
+                     ("fiber_100g", fiber), ("density_g_ml", density_g_ml), ("piece_g", piece_g)] {
+        if let Some(v) = v {
+            if v < 0.0 { return Err("Значение не может быть отрицательным".into()); }
+            updates.push(format!("{}=?{}", col, idx)); params.push(Box::new(v)); idx += 1;
+        }
+    }
+    if updates.is_empty() { return Ok(()); }
+    updates.push("nutrition_source='manual'".into());
+    params.push(Box::new(id));
+    let sql = format!("UPDATE ingredient_catalog SET {} WHERE id=?{}", updates.join(","), idx);
+    let param_refs: Vec<&dyn rusqlite::types::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+    conn.execute(&sql, param_refs.as_slice()).map_err(|e| format!("DB error: {}", e))?;
+    crate::sync_share::mark_dirty(&conn, "ingredient_catalog");
+    Ok(())
+}
+
+/// Catalog id for `name`, inserting it when missing; `true` if it was added.
+/// The name index is normalized differently from the NOCASE column, so the
+/// insert can hit an existing row — then it's a no-op and that row's id is used.
+fn ensure_catalog_item(conn: &rusqlite::Connection, name: &str) -> rusqlite::Result<(i64, bool)> {
+    let n = conn.execute(
+        "INSERT OR IGNORE INTO ingredient_catalog (name, category, tags) VALUES (?1, 'other', '')",
+        rusqlite::params![name],
+    )?;
+    if n > 0 { return Ok((conn.last_insert_rowid(), true)); }
+    conn.query_row("SELECT id FROM ingredient_catalog WHERE name=?1", rusqlite::params![name], |r| r.get(0))
+        .map(|id| (id, false))
+}
+
+/// Import an open nutrition dataset (JSON or CSV, per 100 g) into the catalog.
+/// Matches existing items by name; `add_missing` creates the rest under
+/// category "other"; `overwrite` replaces values that are already set.
+#[tauri::command]
+pub fn import_nutrition_dataset(
+    path: String, overwrite: Option<bool>, add_missing: Option<bool>,
+    db: tauri::State<'_, HanniDb>,
+) -> Result<serde_json::Value, String> {
+    let text = std::fs::read_to_string(&path).map_err(|e| format!("Не удалось прочитать файл: {}", e))?;
+    let items = parse_dataset(&text)?;
+    let overwrite = overwrite.unwrap_or(false);
+    let add_missing = add_missing.unwrap_or(false);
+    let conn = db.conn();
+    let mut index = catalog_name_index(&conn);
+    let (mut updated, mut added, mut skipped) = (0, 0, 0);
+    conn.execute_batch("BEGIN").map_err(|e| format!("DB error: {}", e))?;
+    for it in &items {
+        let key = crate::db::normalize_name(&it.name);
+        let id = match index.get(&key) {
+            Some(&id) => id,
+            None if add_missing => {
+                let Ok((id, inserted)) = ensure_catalog_item(&conn, it.name.trim()) else { skipped += 1; continue };
+                index.insert(key, id);
+                if inserted { added += 1; }
+                id
+            }
+            None => { skipped += 1; continue; }
+        };
+        let guard = if overwrite { "" } else { " AND kcal_100g IS NULL" };
+        let n = conn.execute(
+            &format!("UPDATE ingredient_catalog SET kcal_100g=?1, protein_100g=?2, fat_100g=?3, carbs_100g=?4, \
+                      fiber_100g=?5, density_g_ml=COALESCE(?6, density_g_ml), piece_g=COALESCE(?7, piece_g), \
+                      nutrition_source='import' WHERE id=?8{}", guard),
+            rusqlite::params![it.kcal, it.protein, it.fat, it.carbs, it.fiber, it.density, it.piece_g, id],
+        ).unwrap_or(0);
+        if n > 0 { updated += 1; } else { skipped += 1; }
+    }
+    conn.execute_batch("COMMIT").map_err(|e| format!("DB error: {}", e))?;
+    crate::sync_share::mark_dirty(&conn, "ingredient_catalog");
+    Ok(serde_json::json!({ "total": items.len(), "updated": updated, "added": added, "skipped": skipped }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_units() {
+        assert_eq!(to_grams(2.0, "кг", None, None), Some(2000.0));
+        assert_eq!(to_grams(200.0, "мл", Some(1.03), None), Some(206.0));
+        assert_eq!(to_grams(2.0, "ст.л.", Some(0.92), None), Some(27.6));
+        assert_eq!(to_grams(1.0, "ч. л.", None, None), Some(5.0));
+        assert_eq!(to_grams(3.0, "шт", None, Some(55.0)), Some(165.0));
+        assert_eq!(to_grams(3.0, "шт", None, None), None);
+        assert_eq!(to_grams(1.0, "по вкусу", None, None), Some(0.0));
+        assert_eq!(to_grams(1.0, "банка", None, None), None);
+    }
+
+    #[test]
+    fn converts_between_units() {
+        assert_eq!(convert_amount(500.0, "г", "кг", None, None), Some(0.5));
+        assert_eq!(convert_amount(2.0, "ст.л.", "мл", None, None), Some(30.0));
+        assert_eq!(convert_amount(110.0, "г", "шт", None, Some(55.0)), Some(2.0));
+        assert_eq!(convert_amount(1.0, "банка", "Банка", None, None), Some(1.0));
+        assert_eq!(convert_amount(1.0, "банка", "г", None, None), None);
+        assert_eq!(convert_amount(1.0, "шт", "г", None, None), None);
+    }
+
+    #[test]
+    fn scales_per_100g() {
+        let nut = CatalogNutrition {
+            per_100g: Some(Macros { kcal: 157.0, protein: 12.7, fat: 11.5, carbs: 0.7, fiber: 0.0 }),
+            density_g_ml: None,
+            piece_g: Some(55.0),
+        };
+        let m = ingredient_macros(2.0, "шт", &nut).unwrap();
+        assert!((m.kcal - 172.7).abs() < 1e-9);
+        assert!((m.protein - 13.97).abs() < 1e-9);
+    }
+
+    #[test]
+    fn parses_csv_dataset() {
+        let csv = "name;kcal;protein;fat;carbs;density\nМолоко;52;2,8;2,5;4,7;1,03\n;10;1;1;1;\nbad;x;1;1;1;\n";
+        let items = parse_dataset(csv).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].name, "Молоко");
+        assert_eq!(items[0].protein, 2.8);
+        assert_eq!(items[0].density, Some(1.03));
+        assert_eq!(items[0].fiber, 0.0);
+    }
+
+    #[test]
+    fn reuses_catalog_rows_the_index_missed() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE ingredient_catalog (id INTEGER PRIMARY KEY AUTOINCREMENT, name TEXT NOT NULL UNIQUE COLLATE NOCASE, category TEXT NOT NULL DEFAULT 'other', tags TEXT NOT NULL DEFAULT '');
+            INSERT INTO ingredient_catalog (name) VALUES ('Tomato'), ('Onion');").unwrap();
+        assert_eq!(ensure_catalog_item(&conn, "tomato").unwrap(), (1, false));
+        let (garlic, added) = ensure_catalog_item(&conn, "Garlic").unwrap();
+        assert!(added && garlic > 2);
+        assert_eq!(ensure_catalog_item(&conn, "GARLIC").unwrap(), (garlic, false));
+    }
+}
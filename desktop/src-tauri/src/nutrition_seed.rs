@@ -0,0 +1,63 @@
+//! One-time seed of per-100g nutrition for the built-in ingredient catalog from
+//! a bundled dataset (values compiled from USDA FoodData Central, public
+//! domain; raw/dry weight). Only fills rows that have no nutrition yet, so user
+//! edits and later imports are never overwritten. Gated by a `_migrations` flag.
+
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub(crate) struct SeedNutrition {
+    pub name: String,
+    #[serde(default)]
+    pub kcal: f64,
+    #[serde(default)]
+    pub protein: f64,
+    #[serde(default)]
+    pub fat: f64,
+    #[serde(default)]
+    pub carbs: f64,
+    #[serde(default)]
+    pub fiber: f64,
+    #[serde(default)]
+    pub density: Option<f64>,
+    #[serde(default)]
+    pub piece_g: Option<f64>,
+}
+
+pub fn seed_catalog_nutrition(conn: &rusqlite::Connection) {
+    let done = conn
+        .prepare("SELECT 1 FROM _migrations WHERE name='seed_catalog_nutrition_v1'")
+        .ok()
+        .and_then(|mut s| s.query_row([], |_| Ok(())).ok())
+        .is_some();
+    if done {
+        return;
+    }
+    let _ = conn.execute("CREATE TABLE IF NOT EXISTS _migrations (name TEXT PRIMARY KEY)", []);
+
+    let raw = include_str!("nutrition_seed/nutrition.json");
+    let items: Vec<SeedNutrition> = match serde_json::from_str(raw) {
+        Ok(v) => v,
+        Err(_) => return, // malformed bundle → skip seeding, never block startup
+    };
+
+    // One pass over the catalog instead of a full scan per item (COLLATE NOCASE
+    // can't fold Cyrillic, so names are matched in Rust).
+    let index = crate::nutrition::catalog_name_index(conn);
+    let _ = conn.execute_batch("BEGIN");
+    for it in &items {
+        let Some(&id) = index.get(&crate::db::normalize_name(&it.name)) else { continue };
+        let _ = conn.execute(
+            "UPDATE ingredient_catalog SET kcal_100g=?1, protein_100g=?2, fat_100g=?3, carbs_100g=?4, \
+             fiber_100g=?5, density_g_ml=COALESCE(density_g_ml, ?6), piece_g=COALESCE(piece_g, ?7), \
+             nutrition_source='seed' WHERE id=?8 AND kcal_100g IS NULL",
+            rusqlite::params![it.kcal, it.protein, it.fat, it.carbs, it.fiber, it.density, it.piece_g, id],
+        );
+    }
+    let _ = conn.execute_batch("COMMIT");
+    crate::sync_share::mark_dirty(conn, "ingredient_catalog");
+    let _ = conn.execute(
+        "INSERT OR IGNORE INTO _migrations (name) VALUES ('seed_catalog_nutrition_v1')",
+        [],
+    );
+}
@@ -0,0 +1,271 @@
+// pantry.rs — Products consumption: cooking a recipe decrements matching
+// products (scaled by servings), staples below their minimum go to the
+// shopping list, and expired leftovers are logged for the waste report.
+
+use std::collections::HashMap;
+
+use crate::types::HanniDb;
+
+/// One planned decrement of a product row.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Deduction {
+    pub product_id: i64,
+    pub product_name: String,
+    pub ingredient: String,
+    pub unit: String,
+    pub before: f64,
+    pub take: f64,
+    pub after: f64,
+    /// Units didn't convert — one unit taken as a best guess.
+    pub estimated: bool,
+}
+
+struct Stock {
+    id: i64,
+    name: String,
+    quantity: f64,
+    unit: String,
+}
+
+fn round2(x: f64) -> f64 { (x * 100.0).round() / 100.0 }
+
+fn trim_num(x: f64) -> String {
+    let s = format!("{:.2}", x);
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Match the recipe's ingredient lines (scaled to `servings`) against products,
+/// soonest-expiring first. `only` restricts which product rows may be used.
+/// "по вкусу" lines are never deducted.
+pub(crate) fn plan_consumption(
+    conn: &rusqlite::Connection, recipe_id: i64, servings: Option<i64>, only: Option<&[i64]>,
+) -> Result<Vec<Deduction>, String> {
+    let base: i64 = conn.query_row(
+        "SELECT MAX(COALESCE(servings,1),1) FROM recipes WHERE id=?1", rusqlite::params![recipe_id], |r| r.get(0),
+    ).map_err(|e| format!("Recipe not found: {}", e))?;
+    let factor = servings.filter(|s| *s > 0).unwrap_or(base) as f64 / base as f64;
+
+    let mut stmt = conn.prepare(
+        "SELECT name, amount, unit, catalog_id FROM recipe_ingredients WHERE recipe_id=?1 ORDER BY id"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let lines: Vec<(String, f64, String, Option<i64>)> = stmt.query_map(rusqlite::params![recipe_id], |r| Ok((
+        r.get(0)?, r.get::<_, Option<f64>>(1)?.unwrap_or(0.0), r.get::<_, Option<String>>(2)?.unwrap_or_default(), r.get(3)?,
+    ))).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+
+    let index = crate::nutrition::catalog_name_index(conn);
+    let key_of = |name: &str, cid: Option<i64>| -> String {
+        match cid.or_else(|| index.get(&crate::db::normalize_name(name)).copied()) {
+            Some(id) => format!("#{}", id),
+            None => crate::db::normalize_name(name),
+        }
+    };
+
+    // Products grouped by the same key, FIFO by expiry.
+    let mut stmt = conn.prepare(
+        "SELECT id, name, quantity, unit, catalog_id FROM products WHERE quantity > 0 \
+         ORDER BY expiry_date NULLS LAST, id"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let rows: Vec<(Stock, Option<i64>)> = stmt.query_map([], |r| Ok((Stock {
+        id: r.get(0)?, name: r.get(1)?, quantity: r.get::<_, Option<f64>>(2)?.unwrap_or(0.0),
+        unit: r.get::<_, Option<String>>(3)?.unwrap_or_default(),
+    }, r.get(4)?))).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+    let mut stock: HashMap<String, Vec<Stock>> = HashMap::new();
+    for (s, cid) in rows {
+        if only.is_some_and(|ids| !ids.contains(&s.id)) { continue; }
+        stock.entry(key_of(&s.name, cid)).or_default().push(s);
+    }
+
+    let mut out = Vec::new();
+    for (name, amount, unit, cid) in lines {
+        if crate::nutrition::unit_kind(&unit) == crate::nutrition::UnitKind::Negligible { continue; }
+        let key = key_of(&name, cid);
+        let Some(rows) = stock.get_mut(&key) else { continue };
+        let cat = cid.or_else(|| key.strip_prefix('#').and_then(|k| k.parse().ok()))
+            .and_then(|id| crate::nutrition::catalog_nutrition(conn, id));
+        let (density, piece) = cat.map(|c| (c.density_g_ml, c.piece_g)).unwrap_or((None, None));
+        // Remaining need, in the recipe line's own unit.
+        let mut remaining = amount * factor;
+        for s in rows.iter_mut().filter(|s| s.quantity > 0.0) {
+            let conv = crate::nutrition::convert_amount(remaining, &unit, &s.unit, density, piece);
+            let (want, estimated) = match conv {
+                Some(v) => (v, false),
+                None if crate::nutrition::unit_kind(&s.unit) == crate::nutrition::UnitKind::Piece => (1.0, true),
+                None => continue,
+            };
+            if want <= 0.0 { break; }
+            let take = round2(want.min(s.quantity));
+            let before = s.quantity;
+            s.quantity = round2(s.quantity - take);
+            out.push(Deduction {
+                product_id: s.id, product_name: s.name.clone(), ingredient: name.clone(),
+                unit: s.unit.clone(), before, take, after: s.quantity, estimated,
+            });
+            if estimated || take >= want { break; }
+            // Shortfall carries over to the next (later-expiring) row.
+            remaining = crate::nutrition::convert_amount(want - take, &s.unit, &unit, density, piece).unwrap_or(0.0);
+            if remaining <= 0.0 { break; }
+        }
+    }
+    Ok(out)
+}
+
+/// Apply planned deductions: update/delete product rows, write `pantry_log`,
+/// then flag staples that fell below their minimum.
+pub(crate) fn apply_consumption(
+    conn: &rusqlite::Connection, deductions: &[Deduction], reason: &str,
+    cooking_log_id: Option<i64>, date: &str,
+) -> Vec<serde_json::Value> {
+    let now = chrono::Local::now().to_rfc3339();
+    let mut touched: Vec<i64> = Vec::new();
+    for d in deductions {
+        let min_q: f64 = conn.query_row(
+            "SELECT min_quantity FROM products WHERE id=?1", rusqlite::params![d.product_id], |r| r.get(0),
+        ).unwrap_or(0.0);
+        // Eaten after all — it's no longer waste.
+        let _ = conn.execute(
+            "DELETE FROM pantry_log WHERE product_id=?1 AND reason='expired'", rusqlite::params![d.product_id],
+        );
+        let _ = conn.execute(
+            "INSERT INTO pantry_log (product_id, name, catalog_id, change, unit, reason, cooking_log_id, date, created_at) \
+             VALUES (?1, ?2, (SELECT catalog_id FROM products WHERE id=?1), ?3, ?4, ?5, ?6, ?7, ?8)",
+            rusqlite::params![d.product_id, d.product_name, -d.take, d.unit, reason, cooking_log_id, date, now],
+        );
+        if d.after <= 0.0 && min_q <= 0.0 {
+            let _ = conn.execute("DELETE FROM products WHERE id=?1", rusqlite::params![d.product_id]);
+        } else {
+            let _ = conn.execute(
+                "UPDATE products SET quantity=?1, wasted_at=NULL WHERE id=?2",
+                rusqlite::params![d.after.max(0.0), d.product_id],
+            );
+        }
+        if !touched.contains(&d.product_id) { touched.push(d.product_id); }
+    }
+    if !deductions.is_empty() { crate::sync_share::mark_dirty(conn, "products"); }
+    touched.into_iter().filter_map(|id| check_restock(conn, id)).collect()
+}
+
+/// If a product dropped below its `min_quantity`, put the difference on the
+/// shopping list (once — an open entry with the same name is left as is).
+pub(crate) fn check_restock(conn: &rusqlite::Connection, product_id: i64) -> Option<serde_json::Value> {
+    let (name, qty, unit, min_q, category, cid): (String, f64, String, f64, String, Option<i64>) = conn.query_row(
+        "SELECT name, quantity, unit, min_quantity, category, catalog_id FROM products WHERE id=?1",
+        rusqlite::params![product_id],
+        |r| Ok((r.get(0)?, r.get::<_, Option<f64>>(1)?.unwrap_or(0.0), r.get(2)?, r.get(3)?, r.get(4)?, r.get(5)?)),
+    ).ok()?;
+    if min_q <= 0.0 || qty >= min_q { return None; }
+    let open: Vec<String> = conn.prepare("SELECT name FROM shopping_list WHERE bought_at IS NULL")
+        .and_then(|mut s| s.query_map([], |r| r.get::<_, String>(0)).map(|m| m.filter_map(|x| x.ok()).collect()))
+        .unwrap_or_default();
+    let norm = crate::db::normalize_name(&name);
+    if open.iter().any(|n| crate::db::normalize_name(n) == norm) { return None; }
+    let missing = format!("{} {}", trim_num(min_q - qty), unit);
+    conn.execute(
+        "INSERT INTO shopping_list (name, qty, note, added_at, category, catalog_id, source) \
+         VALUES (?1, ?2, 'Запас ниже минимума', ?3, ?4, ?5, 'restock')",
+        rusqlite::params![name, missing, chrono::Local::now().to_rfc3339(), category, cid],
+    ).ok()?;
+    Some(serde_json::json!({ "product_id": product_id, "name": name, "qty": missing }))
+}
+
+/// Log products that passed `expiry_date` with something left as waste (once
+/// per product; cleared again if the product gets used after all).
+pub(crate) fn sweep_expired_products(conn: &rusqlite::Connection) {
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let now = chrono::Local::now().to_rfc3339();
+    let _ = conn.execute(
+        "INSERT INTO pantry_log (product_id, name, catalog_id, change, unit, reason, date, created_at) \
+         SELECT id, name, catalog_id, -quantity, unit, 'expired', expiry_date, ?2 FROM products \
+         WHERE expiry_date IS NOT NULL AND expiry_date < ?1 AND quantity > 0 AND wasted_at IS NULL",
+        rusqlite::params![today, now],
+    );
+    let _ = conn.execute(
+        "UPDATE products SET wasted_at=?1 \
+         WHERE expiry_date IS NOT NULL AND expiry_date < ?1 AND quantity > 0 AND wasted_at IS NULL",
+        rusqlite::params![today],
+    );
+}
+
+// ── Commands ──
+
+#[tauri::command]
+pub fn preview_recipe_consumption(
+    recipe_id: i64, servings: Option<i64>, db: tauri::State<'_, HanniDb>,
+) -> Result<Vec<Deduction>, String> {
+    let conn = db.read();
+    plan_consumption(&conn, recipe_id, servings, None)
+}
+
+/// Manually deduct a recipe's ingredients (without logging a cook).
+#[tauri::command]
+pub fn consume_recipe_ingredients(
+    recipe_id: i64, servings: Option<i64>, product_ids: Option<Vec<i64>>,
+    db: tauri::State<'_, HanniDb>,
+) -> Result<serde_json::Value, String> {
+    let conn = db.conn();
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let plan = plan_consumption(&conn, recipe_id, servings, product_ids.as_deref())?;
+    let restock = apply_consumption(&conn, &plan, "cooked", None, &today);
+    Ok(serde_json::json!({ "deducted": plan, "restock": restock }))
+}
+
+#[tauri::command]
+pub fn get_pantry_log(limit: Option<i64>, db: tauri::State<'_, HanniDb>) -> Result<Vec<serde_json::Value>, String> {
+    let conn = db.read();
+    let mut stmt = conn.prepare(
+        "SELECT id, product_id, name, change, unit, reason, cooking_log_id, date FROM pantry_log \
+         ORDER BY created_at DESC, id DESC LIMIT ?1"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let rows = stmt.query_map(rusqlite::params![limit.unwrap_or(100)], |row| Ok(serde_json::json!({
+        "id": row.get::<_, i64>(0)?, "product_id": row.get::<_, Option<i64>>(1)?,
+        "name": row.get::<_, String>(2)?, "change": row.get::<_, f64>(3)?,
+        "unit": row.get::<_, String>(4)?, "reason": row.get::<_, String>(5)?,
+        "cooking_log_id": row.get::<_, Option<i64>>(6)?, "date": row.get::<_, String>(7)?,
+    }))).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+    Ok(rows)
+}
+
+/// Products that expired unconsumed in [date_from, date_to] (default: last 30
+/// days), with estimated kcal lost and the share of waste vs. cooked usage.
+#[tauri::command]
+pub fn get_waste_report(
+    date_from: Option<String>, date_to: Option<String>, db: tauri::State<'_, HanniDb>,
+) -> Result<serde_json::Value, String> {
+    let conn = db.conn();
+    sweep_expired_products(&conn);
+    let today = chrono::Local::now().date_naive();
+    let to = date_to.unwrap_or_else(|| today.format("%Y-%m-%d").to_string());
+    let from = date_from.unwrap_or_else(|| (today - chrono::Duration::days(30)).format("%Y-%m-%d").to_string());
+    let mut stmt = conn.prepare(
+        "SELECT l.name, -l.change, l.unit, l.date, l.catalog_id, COALESCE(c.category, 'other') \
+         FROM pantry_log l LEFT JOIN ingredient_catalog c ON c.id = l.catalog_id \
+         WHERE l.reason='expired' AND l.date BETWEEN ?1 AND ?2 ORDER BY l.date"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let rows: Vec<(String, f64, String, String, Option<i64>, String)> = stmt.query_map(rusqlite::params![from, to], |r| Ok((
+        r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?, r.get(5)?,
+    ))).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+
+    let mut items = Vec::new();
+    let mut by_category: HashMap<String, i64> = HashMap::new();
+    let mut kcal_total = 0.0;
+    for (name, qty, unit, date, cid, category) in &rows {
+        let kcal = cid.and_then(|id| crate::nutrition::catalog_nutrition(&conn, id))
+            .and_then(|n| crate::nutrition::ingredient_macros(*qty, unit, &n))
+            .map(|m| m.kcal.round());
+        kcal_total += kcal.unwrap_or(0.0);
+        *by_category.entry(category.clone()).or_insert(0) += 1;
+        items.push(serde_json::json!({
+            "name": name, "quantity": qty, "unit": unit, "expired_on": date, "category": category, "kcal": kcal,
+        }));
+    }
+    let used: i64 = conn.query_row(
+        "SELECT COUNT(DISTINCT product_id) FROM pantry_log WHERE reason='cooked' AND date BETWEEN ?1 AND ?2",
+        rusqlite::params![from, to], |r| r.get(0),
+    ).unwrap_or(0);
+    let wasted = rows.len() as i64;
+    let share = if wasted + used > 0 { wasted as f64 / (wasted + used) as f64 } else { 0.0 };
+    Ok(serde_json::json!({
+        "from": from, "to": to, "items": items, "wasted_count": wasted, "used_count": used,
+        "waste_share": (share * 1000.0).round() / 1000.0, "kcal_wasted": kcal_total,
+        "by_category": by_category,
+    }))
+}
@@ -0,0 +1,621 @@
+// pomodoro.rs — Pomodoro / interval sessions on top of the timeline.
+// A session cycles work → short break → … → long break every N work intervals,
+// optionally until a target number of cycles. Every interval (and every
+// paused/resumed piece of one) becomes a `timeline_blocks` row: work pieces
+// are linked to the task (source_type/source_id) like ▶ Start does, breaks go
+// to the "Перерыв" type. Work intervals can switch focus blocking on by
+// themselves. Phase changes are driven by `tick` from a background loop.
+use crate::types::{FocusManager, HanniDb};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tauri::{Emitter, Manager};
+
+pub const WORK: &str = "work";
+pub const SHORT_BREAK: &str = "short_break";
+pub const LONG_BREAK: &str = "long_break";
+const CONFIG_KEY: &str = "pomodoro_config";
+/// `FocusState::owner` of a focus session the pomodoro started.
+const FOCUS_OWNER: &str = "pomodoro";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct PomodoroConfig {
+    pub work_min: i64,
+    pub short_break_min: i64,
+    pub long_break_min: i64,
+    /// Long break after every N completed work intervals.
+    pub long_break_every: i64,
+    /// Session ends after this many completed work intervals (0 = until stopped).
+    pub target_cycles: i64,
+    /// Turn focus blocking on for work intervals.
+    pub auto_focus: bool,
+}
+
+impl Default for PomodoroConfig {
+    fn default() -> Self {
+        Self { work_min: 25, short_break_min: 5, long_break_min: 15, long_break_every: 4, target_cycles: 4, auto_focus: true }
+    }
+}
+
+impl PomodoroConfig {
+    pub fn normalized(self) -> Self {
+        Self {
+            work_min: self.work_min.clamp(1, 180),
+            short_break_min: self.short_break_min.clamp(1, 60),
+            long_break_min: self.long_break_min.clamp(1, 90),
+            long_break_every: self.long_break_every.clamp(1, 12),
+            target_cycles: self.target_cycles.clamp(0, 24),
+            auto_focus: self.auto_focus,
+        }
+    }
+
+    pub fn phase_secs(&self, phase: &str) -> i64 {
+        60 * match phase {
+            WORK => self.work_min,
+            LONG_BREAK => self.long_break_min,
+            _ => self.short_break_min,
+        }
+    }
+}
+
+/// Phase that follows `phase`, given work intervals completed so far.
+/// `None` means the session is finished.
+pub fn next_phase(phase: &str, cycles: i64, cfg: &PomodoroConfig) -> Option<&'static str> {
+    if phase != WORK { return Some(WORK); }
+    if cfg.target_cycles > 0 && cycles >= cfg.target_cycles { return None; }
+    if cycles > 0 && cycles % cfg.long_break_every == 0 { Some(LONG_BREAK) } else { Some(SHORT_BREAK) }
+}
+
+// ── Sessions ──
+
+#[derive(Debug, Clone)]
+struct Session {
+    id: i64,
+    source_type: Option<String>,
+    source_id: Option<String>,
+    title: String,
+    project: String,
+    config: PomodoroConfig,
+    status: String,
+    phase: String,
+    phase_ends_at: Option<String>,
+    paused_left_secs: Option<i64>,
+    cycles: i64,
+    interruptions: i64,
+    notes: String,
+    started_at: String,
+}
+
+impl Session {
+    fn ends_at(&self) -> Option<DateTime<Local>> {
+        self.phase_ends_at.as_deref()
+            .and_then(|e| DateTime::parse_from_rfc3339(e).ok())
+            .map(|e| e.with_timezone(&Local))
+    }
+
+    fn remaining_secs(&self, now: DateTime<Local>) -> i64 {
+        match (self.status.as_str(), self.ends_at()) {
+            ("paused", _) => self.paused_left_secs.unwrap_or(0),
+            (_, Some(end)) => (end - now).num_seconds().max(0),
+            _ => 0,
+        }
+    }
+
+    fn to_json(&self, now: DateTime<Local>) -> serde_json::Value {
+        serde_json::json!({
+            "id": self.id, "source_type": self.source_type, "source_id": self.source_id,
+            "title": self.title, "project": self.project, "config": self.config,
+            "status": self.status, "phase": self.phase, "phase_ends_at": self.phase_ends_at,
+            "phase_secs": self.config.phase_secs(&self.phase), "remaining_secs": self.remaining_secs(now),
+            "cycles": self.cycles, "interruptions": self.interruptions, "notes": self.notes,
+            "started_at": self.started_at,
+        })
+    }
+}
+
+const SESSION_COLS: &str = "id, source_type, source_id, title, project, config, status, phase, phase_ends_at,
+    paused_left_secs, cycles, interruptions, notes, started_at";
+
+fn session_from_row(r: &rusqlite::Row) -> rusqlite::Result<Session> {
+    Ok(Session {
+        id: r.get(0)?, source_type: r.get(1)?, source_id: r.get(2)?, title: r.get(3)?, project: r.get(4)?,
+        config: serde_json::from_str::<PomodoroConfig>(&r.get::<_, String>(5)?).unwrap_or_default().normalized(),
+        status: r.get(6)?, phase: r.get(7)?, phase_ends_at: r.get(8)?, paused_left_secs: r.get(9)?,
+        cycles: r.get(10)?, interruptions: r.get(11)?, notes: r.get(12)?, started_at: r.get(13)?,
+    })
+}
+
+fn load_active(conn: &rusqlite::Connection) -> Option<Session> {
+    conn.query_row(
+        &format!("SELECT {} FROM pomodoro_sessions WHERE status IN ('running','paused') ORDER BY id DESC LIMIT 1", SESSION_COLS),
+        [], session_from_row,
+    ).ok()
+}
+
+fn saved_config(conn: &rusqlite::Connection) -> PomodoroConfig {
+    conn.query_row("SELECT value FROM app_settings WHERE key=?1", [CONFIG_KEY], |r| r.get::<_, String>(0))
+        .ok()
+        .and_then(|v| serde_json::from_str::<PomodoroConfig>(&v).ok())
+        .unwrap_or_default()
+        .normalized()
+}
+
+/// Title + project for a linked task: note → its tab, event/schedule → category.
+fn resolve_task(conn: &rusqlite::Connection, source_type: &str, source_id: &str) -> Option<(String, String)> {
+    let sql = match source_type {
+        "note" => "SELECT title, COALESCE(tab_name, '') FROM notes WHERE id=?1",
+        "event" => "SELECT title, category FROM events WHERE id=?1",
+        "schedule" => "SELECT title, category FROM schedules WHERE id=?1",
+        _ => return None,
+    };
+    conn.query_row(sql, [source_id], |r| Ok((r.get(0)?, r.get(1)?))).ok()
+}
+
+fn type_id(conn: &rusqlite::Connection, phase: &str) -> Result<i64, String> {
+    let name = if phase == WORK { "Фокус" } else { "Перерыв" };
+    conn.query_row(
+        "SELECT id FROM timeline_activity_types WHERE is_system=1 AND name IN (?1, 'Запланировано')
+         ORDER BY name = ?1 DESC LIMIT 1",
+        [name], |r| r.get(0),
+    ).map_err(|e| format!("Default type missing: {}", e))
+}
+
+/// Start a piece of the current phase: timeline block (active) + interval row.
+fn open_interval(conn: &rusqlite::Connection, s: &Session, secs: i64, now: DateTime<Local>) -> Result<(), String> {
+    crate::commands_timeline_today::close_active_block(conn, now);
+    let work = s.phase == WORK;
+    let label = match s.phase.as_str() {
+        WORK => format!("🍅 {} · {}", s.cycles + 1, s.title),
+        LONG_BREAK => "☕ Длинный перерыв".to_string(),
+        _ => "☕ Перерыв".to_string(),
+    };
+    conn.execute(
+        "INSERT INTO timeline_blocks (type_id, date, start_time, end_time, duration_minutes, source, notes, is_active, source_type, source_id)
+         VALUES (?1, ?2, ?3, '--:--', 0, 'pomodoro', ?4, 1, ?5, ?6)",
+        rusqlite::params![
+            type_id(conn, &s.phase)?, now.format("%Y-%m-%d").to_string(), now.format("%H:%M").to_string(), label,
+            if work { s.source_type.clone() } else { None }, if work { s.source_id.clone() } else { None },
+        ],
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let block_id = conn.last_insert_rowid();
+    conn.execute(
+        "INSERT INTO pomodoro_intervals (session_id, phase, started_at, planned_secs, block_id) VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![s.id, s.phase, now.to_rfc3339(), secs, block_id],
+    ).map_err(|e| format!("DB error: {}", e))?;
+    Ok(())
+}
+
+/// End the open interval of a session. Its block is closed only if it is still
+/// the running one — ▶ Start on another task may have closed it already.
+fn close_interval(conn: &rusqlite::Connection, session_id: i64, now: DateTime<Local>, completed: bool) {
+    let Ok((id, block_id)) = conn.query_row(
+        "SELECT id, block_id FROM pomodoro_intervals WHERE session_id=?1 AND ended_at IS NULL ORDER BY id DESC LIMIT 1",
+        [session_id], |r| Ok((r.get::<_, i64>(0)?, r.get::<_, Option<i64>>(1)?)),
+    ) else { return };
+    conn.execute(
+        "UPDATE pomodoro_intervals SET ended_at=?1, completed=?2 WHERE id=?3",
+        rusqlite::params![now.to_rfc3339(), completed as i32, id],
+    ).ok();
+    let Some(block_id) = block_id else { return };
+    let Ok((start, date)) = conn.query_row(
+        "SELECT start_time, date FROM timeline_blocks WHERE id=?1 AND is_active=1",
+        [block_id], |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?)),
+    ) else { return };
+    let end = if date == now.format("%Y-%m-%d").to_string() { now.format("%H:%M").to_string() } else { "23:59".to_string() };
+    let dur = crate::commands_timeline::calc_duration(&start, &end).max(1);
+    conn.execute(
+        "UPDATE timeline_blocks SET is_active=0, end_time=?1, duration_minutes=?2 WHERE id=?3",
+        rusqlite::params![end, dur, block_id],
+    ).ok();
+}
+
+/// Finish the current phase (`completed` = timer ran out, not skipped) and
+/// move to the next one. Returns the new phase, `None` when the session ended.
+fn advance(conn: &rusqlite::Connection, s: &Session, now: DateTime<Local>, completed: bool) -> Result<Option<&'static str>, String> {
+    close_interval(conn, s.id, now, completed);
+    let cycles = s.cycles + (completed && s.phase == WORK) as i64;
+    let Some(next) = next_phase(&s.phase, cycles, &s.config) else {
+        conn.execute(
+            "UPDATE pomodoro_sessions SET status='done', cycles=?1, phase_ends_at=NULL, ended_at=?2 WHERE id=?3",
+            rusqlite::params![cycles, now.to_rfc3339(), s.id],
+        ).map_err(|e| format!("DB error: {}", e))?;
+        return Ok(None);
+    };
+    let secs = s.config.phase_secs(next);
+    conn.execute(
+        "UPDATE pomodoro_sessions SET status='running', phase=?1, cycles=?2, phase_ends_at=?3, paused_left_secs=NULL WHERE id=?4",
+        rusqlite::params![next, cycles, (now + chrono::Duration::seconds(secs)).to_rfc3339(), s.id],
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let s = Session { phase: next.to_string(), cycles, ..s.clone() };
+    open_interval(conn, &s, secs, now)?;
+    Ok(Some(next))
+}
+
+/// Focus blocking follows the session: with `auto_focus` one focus session
+/// covers the whole pomodoro (a single hosts write), and only app quitting is
+/// switched between phases — on for running work intervals, off for breaks
+/// and pauses. Only a session the pomodoro started itself (tagged with
+/// `FOCUS_OWNER`) is ever changed or stopped; once it was replaced by manual
+/// or scheduled focus, that one is left alone.
+fn sync_focus(db: &HanniDb, focus: &FocusManager) {
+    let ours = {
+        let state = focus.0.lock().unwrap_or_else(|e| e.into_inner());
+        state.active && state.owner == Some(FOCUS_OWNER)
+    };
+    let (want, owned) = {
+        let conn = db.conn();
+        let owned: Option<i64> = conn.query_row("SELECT id FROM pomodoro_sessions WHERE focus_owned=1 LIMIT 1", [], |r| r.get(0)).ok();
+        let want = load_active(&conn)
+            .filter(|s| s.config.auto_focus)
+            .map(|s| (s.id, s.status == "running" && s.phase == WORK));
+        (want, owned)
+    };
+    let owned = if owned.is_some() && !ours {
+        // Ended or replaced by someone else's session — nothing left to own
+        db.conn().execute("UPDATE pomodoro_sessions SET focus_owned=0 WHERE focus_owned=1", []).ok();
+        None
+    } else { owned };
+    let (apps, sites) = crate::commands_meta::blocker_targets(None, None);
+    match want {
+        Some((id, working)) if owned == Some(id) => {
+            let mut state = focus.0.lock().unwrap_or_else(|e| e.into_inner());
+            if state.active && state.owner == Some(FOCUS_OWNER) {
+                state.blocked_apps = if working { apps } else { Vec::new() };
+            }
+        }
+        Some((id, working)) if working => {
+            // Not under the FocusManager lock — the hosts write may wait on a prompt
+            match crate::focus_enforcer::start(focus, apps, sites, None, false) {
+                Ok(true) => {
+                    focus.0.lock().unwrap_or_else(|e| e.into_inner()).owner = Some(FOCUS_OWNER);
+                    db.conn().execute("UPDATE pomodoro_sessions SET focus_owned=1 WHERE id=?1", [id]).ok();
+                }
+                Ok(false) => {}
+                Err(e) => eprintln!("[pomodoro] focus start failed: {}", e),
+            }
+        }
+        None if owned.is_some() => {
+            {
+                let mut state = focus.0.lock().unwrap_or_else(|e| e.into_inner());
+                if state.active && state.owner == Some(FOCUS_OWNER) { crate::focus_enforcer::end(&mut state); }
+            }
+            db.conn().execute("UPDATE pomodoro_sessions SET focus_owned=0 WHERE focus_owned=1", []).ok();
+        }
+        _ => {}
+    }
+}
+
+pub(crate) fn notify(title: &str, body: &str) {
+    if cfg!(target_os = "linux") {
+        let _ = std::process::Command::new("notify-send").args(["-a", "Hanni", title, body]).status();
+    } else {
+        let _ = crate::macos::run_osascript(&format!(
+            "display notification \"{}\" with title \"{}\" sound name \"Ping\"",
+            crate::macos::osa_escape(body), crate::macos::osa_escape(title)
+        ));
+    }
+}
+
+fn phase_message(next: Option<&str>, s: &Session) -> String {
+    match next {
+        Some(WORK) => format!("Перерыв окончен — помидор {} · {}", s.cycles + 1, s.title),
+        Some(LONG_BREAK) => format!("Длинный перерыв {} мин", s.config.long_break_min),
+        Some(_) => format!("Перерыв {} мин", s.config.short_break_min),
+        None => format!("Сессия завершена: {} помидоров", s.cycles + 1),
+    }
+}
+
+/// Background step (every few seconds): move a running session whose phase
+/// timer ran out to the next phase.
+pub fn tick(handle: &tauri::AppHandle) {
+    let db = handle.state::<HanniDb>();
+    let now = Local::now();
+    let (next, s) = {
+        let conn = db.conn();
+        let Some(s) = load_active(&conn) else { return };
+        if s.status != "running" || s.ends_at().is_none_or(|e| e > now) { return; }
+        match advance(&conn, &s, now, true) {
+            Ok(next) => (next, s),
+            Err(e) => { eprintln!("[pomodoro] {}", e); return; }
+        }
+    };
+    sync_focus(&db, &handle.state::<FocusManager>());
+    let msg = phase_message(next, &s);
+    let _ = handle.emit("pomodoro-phase", serde_json::json!({ "session_id": s.id, "phase": next, "message": msg }));
+    notify("Помодоро", &msg);
+}
+
+// ── Stats ──
+
+/// One finished-or-running session, as used by the stats.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionSummary {
+    pub task_key: String,
+    pub title: String,
+    pub project: String,
+    pub cycles: i64,
+    pub work_min: f64,
+    pub interruptions: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct Totals {
+    pub key: String,
+    pub title: String,
+    pub sessions: i64,
+    pub cycles: i64,
+    pub work_min: f64,
+    pub interruptions: i64,
+}
+
+/// Sum sessions by task and by project, most completed cycles first.
+pub fn summarize(rows: &[SessionSummary]) -> (Vec<Totals>, Vec<Totals>) {
+    fn group<'a>(rows: &'a [SessionSummary], key: impl Fn(&'a SessionSummary) -> (&'a str, &'a str)) -> Vec<Totals> {
+        let mut map: BTreeMap<&str, Totals> = BTreeMap::new();
+        for r in rows {
+            let (k, title) = key(r);
+            let t = map.entry(k).or_insert_with(|| Totals { key: k.to_string(), title: title.to_string(), ..Default::default() });
+            t.sessions += 1;
+            t.cycles += r.cycles;
+            t.work_min += r.work_min;
+            t.interruptions += r.interruptions;
+        }
+        let mut out: Vec<Totals> = map.into_values().collect();
+        out.sort_by(|a, b| b.cycles.cmp(&a.cycles).then(b.work_min.total_cmp(&a.work_min)));
+        out
+    }
+    let by_task = group(rows, |r| (r.task_key.as_str(), r.title.as_str()));
+    let by_project = group(rows, |r| {
+        let p = if r.project.is_empty() { "—" } else { r.project.as_str() };
+        (p, p)
+    });
+    (by_task, by_project)
+}
+
+// ── Commands ──
+
+#[tauri::command]
+pub fn get_pomodoro_state(db: tauri::State<'_, HanniDb>) -> Result<serde_json::Value, String> {
+    let conn = db.read();
+    let now = Local::now();
+    Ok(serde_json::json!({
+        "session": load_active(&conn).map(|s| s.to_json(now)),
+        "config": saved_config(&conn),
+    }))
+}
+
+/// Start a session, optionally linked to a task from the Today list. The
+/// config given here becomes the default for the next session.
+#[tauri::command]
+pub fn start_pomodoro(
+    source_type: Option<String>,
+    source_id: Option<String>,
+    title: Option<String>,
+    project: Option<String>,
+    config: Option<PomodoroConfig>,
+    db: tauri::State<'_, HanniDb>,
+    focus: tauri::State<'_, FocusManager>,
+) -> Result<serde_json::Value, String> {
+    let now = Local::now();
+    let session = {
+        let conn = db.conn();
+        if load_active(&conn).is_some() {
+            return Err("Сессия помодоро уже идёт".into());
+        }
+        let config = config.unwrap_or_else(|| saved_config(&conn)).normalized();
+        let linked = source_type.as_deref().zip(source_id.as_deref()).and_then(|(st, sid)| resolve_task(&conn, st, sid));
+        let title = title.filter(|t| !t.trim().is_empty())
+            .or_else(|| linked.as_ref().map(|l| l.0.clone()))
+            .unwrap_or_else(|| "Помодоро".to_string());
+        let project = project.filter(|p| !p.trim().is_empty())
+            .or_else(|| linked.as_ref().map(|l| l.1.clone()))
+            .unwrap_or_default();
+        let config_json = serde_json::to_string(&config).map_err(|e| format!("Serialize error: {}", e))?;
+        conn.execute(
+            "INSERT INTO app_settings (key, value) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET value=?2",
+            rusqlite::params![CONFIG_KEY, config_json],
+        ).map_err(|e| format!("DB error: {}", e))?;
+        let secs = config.phase_secs(WORK);
+        conn.execute(
+            "INSERT INTO pomodoro_sessions (source_type, source_id, title, project, config, status, phase, phase_ends_at, started_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, 'running', 'work', ?6, ?7)",
+            rusqlite::params![source_type, source_id, title, project, config_json,
+                (now + chrono::Duration::seconds(secs)).to_rfc3339(), now.to_rfc3339()],
+        ).map_err(|e| format!("DB error: {}", e))?;
+        let s = load_active(&conn).ok_or("Сессия не создана")?;
+        open_interval(&conn, &s, secs, now)?;
+        s
+    };
+    sync_focus(&db, &focus);
+    Ok(session.to_json(now))
+}
+
+#[tauri::command]
+pub fn pause_pomodoro(db: tauri::State<'_, HanniDb>, focus: tauri::State<'_, FocusManager>) -> Result<(), String> {
+    {
+        let conn = db.conn();
+        let now = Local::now();
+        let s = load_active(&conn).filter(|s| s.status == "running").ok_or("Нет активной сессии")?;
+        close_interval(&conn, s.id, now, false);
+        conn.execute(
+            "UPDATE pomodoro_sessions SET status='paused', paused_left_secs=?1, phase_ends_at=NULL WHERE id=?2",
+            rusqlite::params![s.remaining_secs(now), s.id],
+        ).map_err(|e| format!("DB error: {}", e))?;
+    }
+    sync_focus(&db, &focus);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn resume_pomodoro(db: tauri::State<'_, HanniDb>, focus: tauri::State<'_, FocusManager>) -> Result<(), String> {
+    {
+        let conn = db.conn();
+        let now = Local::now();
+        let s = load_active(&conn).filter(|s| s.status == "paused").ok_or("Сессия не на паузе")?;
+        let left = s.paused_left_secs.unwrap_or_else(|| s.config.phase_secs(&s.phase)).max(1);
+        conn.execute(
+            "UPDATE pomodoro_sessions SET status='running', paused_left_secs=NULL, phase_ends_at=?1 WHERE id=?2",
+            rusqlite::params![(now + chrono::Duration::seconds(left)).to_rfc3339(), s.id],
+        ).map_err(|e| format!("DB error: {}", e))?;
+        open_interval(&conn, &s, left, now)?;
+    }
+    sync_focus(&db, &focus);
+    Ok(())
+}
+
+/// End the current phase early; a skipped work interval does not count as a cycle.
+#[tauri::command]
+pub fn skip_pomodoro_phase(db: tauri::State<'_, HanniDb>, focus: tauri::State<'_, FocusManager>) -> Result<Option<String>, String> {
+    let next = {
+        let conn = db.conn();
+        let s = load_active(&conn).ok_or("Нет активной сессии")?;
+        advance(&conn, &s, Local::now(), false)?
+    };
+    sync_focus(&db, &focus);
+    Ok(next.map(String::from))
+}
+
+/// Count an interruption; a non-empty `note` is appended to the session notes.
+#[tauri::command]
+pub fn interrupt_pomodoro(note: Option<String>, db: tauri::State<'_, HanniDb>) -> Result<i64, String> {
+    let conn = db.conn();
+    let now = Local::now();
+    let s = load_active(&conn).ok_or("Нет активной сессии")?;
+    let notes = match note.as_deref().map(str::trim).filter(|n| !n.is_empty()) {
+        Some(n) => {
+            let line = format!("{} ⚡ {}", now.format("%H:%M"), n);
+            if s.notes.is_empty() { line } else { format!("{}\n{}", s.notes, line) }
+        }
+        None => s.notes.clone(),
+    };
+    conn.execute(
+        "UPDATE pomodoro_sessions SET interruptions=interruptions+1, notes=?1 WHERE id=?2",
+        rusqlite::params![notes, s.id],
+    ).map_err(|e| format!("DB error: {}", e))?;
+    conn.execute(
+        "UPDATE pomodoro_intervals SET interruptions=interruptions+1 WHERE session_id=?1 AND ended_at IS NULL",
+        [s.id],
+    ).ok();
+    Ok(s.interruptions + 1)
+}
+
+#[tauri::command]
+pub fn set_pomodoro_notes(id: i64, notes: String, db: tauri::State<'_, HanniDb>) -> Result<(), String> {
+    db.conn().execute("UPDATE pomodoro_sessions SET notes=?1 WHERE id=?2", rusqlite::params![notes, id])
+        .map_err(|e| format!("DB error: {}", e))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn stop_pomodoro(notes: Option<String>, db: tauri::State<'_, HanniDb>, focus: tauri::State<'_, FocusManager>) -> Result<(), String> {
+    {
+        let conn = db.conn();
+        let now = Local::now();
+        let Some(s) = load_active(&conn) else { return Ok(()) };
+        close_interval(&conn, s.id, now, false);
+        conn.execute(
+            "UPDATE pomodoro_sessions SET status='stopped', phase_ends_at=NULL, ended_at=?1, notes=COALESCE(?2, notes) WHERE id=?3",
+            rusqlite::params![now.to_rfc3339(), notes, s.id],
+        ).map_err(|e| format!("DB error: {}", e))?;
+    }
+    sync_focus(&db, &focus);
+    Ok(())
+}
+
+/// Completed cycles, work minutes and interruptions per task and per project,
+/// daily cycle counts, and the most recent sessions.
+#[tauri::command]
+pub fn get_pomodoro_stats(days: Option<i64>, db: tauri::State<'_, HanniDb>) -> Result<serde_json::Value, String> {
+    let days = days.unwrap_or(30).clamp(1, 365);
+    let since = (Local::now() - chrono::Duration::days(days)).to_rfc3339();
+    let conn = db.read();
+    let mut stmt = conn.prepare(
+        "SELECT s.id, s.source_type, s.source_id, s.title, s.project, s.cycles, s.interruptions, s.status, s.started_at, s.notes,
+                COALESCE((SELECT SUM(julianday(i.ended_at) - julianday(i.started_at)) * 1440 FROM pomodoro_intervals i
+                          WHERE i.session_id = s.id AND i.phase = 'work' AND i.ended_at IS NOT NULL), 0)
+         FROM pomodoro_sessions s WHERE s.started_at >= ?1 ORDER BY s.started_at DESC"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let mut summaries = Vec::new();
+    let mut recent = Vec::new();
+    let mut per_day: BTreeMap<String, i64> = BTreeMap::new();
+    let rows = stmt.query_map([&since], |r| Ok((
+        r.get::<_, i64>(0)?, r.get::<_, Option<String>>(1)?, r.get::<_, Option<String>>(2)?, r.get::<_, String>(3)?,
+        r.get::<_, String>(4)?, r.get::<_, i64>(5)?, r.get::<_, i64>(6)?, r.get::<_, String>(7)?, r.get::<_, String>(8)?,
+        r.get::<_, String>(9)?, r.get::<_, f64>(10)?,
+    ))).map_err(|e| format!("Query error: {}", e))?;
+    for (id, st, sid, title, project, cycles, interruptions, status, started_at, notes, work_min) in rows.flatten() {
+        let task_key = match (&st, &sid) {
+            (Some(st), Some(sid)) => format!("{}:{}", st, sid),
+            _ => format!("title:{}", title),
+        };
+        *per_day.entry(started_at.get(..10).unwrap_or("").to_string()).or_default() += cycles;
+        if recent.len() < 20 {
+            recent.push(serde_json::json!({
+                "id": id, "title": title, "project": project, "cycles": cycles, "interruptions": interruptions,
+                "status": status, "started_at": started_at, "notes": notes, "work_min": work_min.round(),
+            }));
+        }
+        summaries.push(SessionSummary { task_key, title, project, cycles, work_min, interruptions });
+    }
+    let (by_task, by_project) = summarize(&summaries);
+    Ok(serde_json::json!({
+        "days": days,
+        "total_cycles": summaries.iter().map(|s| s.cycles).sum::<i64>(),
+        "total_work_min": summaries.iter().map(|s| s.work_min).sum::<f64>().round(),
+        "by_task": by_task, "by_project": by_project,
+        "per_day": per_day.into_iter().map(|(date, cycles)| serde_json::json!({ "date": date, "cycles": cycles })).collect::<Vec<_>>(),
+        "recent": recent,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cycles_through_phases() {
+        let cfg = PomodoroConfig { long_break_every: 2, target_cycles: 3, ..Default::default() };
+        assert_eq!(next_phase(WORK, 1, &cfg), Some(SHORT_BREAK));
+        assert_eq!(next_phase(SHORT_BREAK, 1, &cfg), Some(WORK));
+        assert_eq!(next_phase(WORK, 2, &cfg), Some(LONG_BREAK));
+        assert_eq!(next_phase(LONG_BREAK, 2, &cfg), Some(WORK));
+        assert_eq!(next_phase(WORK, 3, &cfg), None);
+        // A skipped first work interval is no reason for a long break.
+        assert_eq!(next_phase(WORK, 0, &cfg), Some(SHORT_BREAK));
+        let endless = PomodoroConfig { target_cycles: 0, ..cfg };
+        assert_eq!(next_phase(WORK, 40, &endless), Some(LONG_BREAK));
+    }
+
+    #[test]
+    fn config_defaults_and_clamps() {
+        let cfg: PomodoroConfig = serde_json::from_str(r#"{"work_min": 50, "long_break_every": 0}"#).unwrap();
+        let cfg = cfg.normalized();
+        assert_eq!(cfg.work_min, 50);
+        assert_eq!(cfg.short_break_min, 5);
+        assert_eq!(cfg.long_break_every, 1);
+        assert_eq!(cfg.phase_secs(WORK), 3000);
+        assert_eq!(cfg.phase_secs(LONG_BREAK), 900);
+        assert_eq!(PomodoroConfig { work_min: 0, ..cfg }.normalized().work_min, 1);
+    }
+
+    #[test]
+    fn summarizes_by_task_and_project() {
+        let row = |key: &str, project: &str, cycles, work_min, interruptions| SessionSummary {
+            task_key: key.into(), title: key.into(), project: project.into(), cycles, work_min, interruptions,
+        };
+        let rows = vec![
+            row("note:1", "Hanni", 4, 100.0, 1),
+            row("note:1", "Hanni", 2, 50.0, 3),
+            row("event:7", "Hanni", 1, 25.0, 0),
+            row("title:Чтение", "", 3, 75.0, 0),
+        ];
+        let (by_task, by_project) = summarize(&rows);
+        assert_eq!(by_task[0].key, "note:1");
+        assert_eq!((by_task[0].sessions, by_task[0].cycles, by_task[0].interruptions), (2, 6, 4));
+        assert_eq!(by_task[1].key, "title:Чтение");
+        assert_eq!(by_project.len(), 2);
+        assert_eq!((by_project[0].key.as_str(), by_project[0].cycles, by_project[0].work_min), ("Hanni", 7, 175.0));
+        assert_eq!(by_project[1].key, "—");
+    }
+}
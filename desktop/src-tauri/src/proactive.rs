@@ -1,7 +1,7 @@
 // proactive.rs — Proactive messaging logic
 use crate::types::*;
 use crate::memory::{build_memory_context_from_db, save_proactive_settings};
-use crate::macos::{run_osascript, check_calendar_access, classify_app, get_macos_idle_seconds, is_screen_locked};
+use crate::macos::{run_osascript, check_calendar_access, classify_app};
 use crate::voice::speak_tts;
 use tauri::{AppHandle, Emitter, Manager};
 use std::sync::Arc;
@@ -273,20 +273,21 @@ pub fn gather_context_blocking() -> String {
     }
 
     // Now playing
-    let music = get_now_playing_sync();
+    let sensor = crate::activity_sensor::sensor();
+    let music = sensor.now_playing();
     if !music.is_empty() {
-        ctx.push_str(&format!("\n--- Music ---\nApple Music: {}\n", music));
+        ctx.push_str(&format!("\n--- Music ---\nNow playing: {}\n", music));
     }
 
     // Browser tab
-    let browser = get_browser_url();
+    let browser = sensor.browser_url();
     if !browser.is_empty() {
         ctx.push_str(&format!("\n--- Browser ---\n{}\n", browser));
     }
 
     // Active (frontmost) app — only show for distraction alerts
     // Skip "Hanni" — no point telling the model the user is in our own app
-    let front_app = get_frontmost_app();
+    let front_app = sensor.frontmost_app();
     if !front_app.is_empty() && front_app != "Hanni" {
         let distracting = ["YouTube", "Reddit", "Twitter", "TikTok", "Instagram", "Telegram", "Discord", "VK"];
         let is_distracting = distracting.iter().any(|d| front_app.contains(d));
@@ -379,24 +380,19 @@ pub fn get_upcoming_events_soon() -> Result<String, String> {
     let db_path = hanni_db_path();
     if !db_path.exists() { return Ok(String::new()); }
     let conn = rusqlite::Connection::open(&db_path).map_err(|e| e.to_string())?;
-    let now = chrono::Local::now();
-    let today = now.format("%Y-%m-%d").to_string();
-    let current_time = now.format("%H:%M").to_string();
-    let soon_time = (now + chrono::Duration::minutes(90)).format("%H:%M").to_string();
-    let mut stmt = conn.prepare(
-        "SELECT title, time, duration FROM events WHERE date = ?1 AND time >= ?2 AND time <= ?3 ORDER BY time"
-    ).map_err(|e| e.to_string())?;
-    let events: Vec<String> = stmt.query_map(
-        rusqlite::params![today, current_time, soon_time],
-        |row| {
-            let title: String = row.get(0)?;
-            let time: String = row.get(1)?;
-            let dur: i64 = row.get(2)?;
-            Ok(format!("{} — {} ({}мин)", time, title, dur))
-        },
-    ).map_err(|e| e.to_string())?
-    .filter_map(|r| r.ok())
-    .collect();
+    // Wall clock and event times both in the current (home or travel) zone
+    let current = crate::time_zones::current_zone(&conn);
+    let now = crate::time_zones::now_in(current);
+    let from = now.format("%Y-%m-%d %H:%M").to_string();
+    let to = (now + chrono::Duration::minutes(90)).format("%Y-%m-%d %H:%M").to_string();
+    let events: Vec<String> = crate::time_zones::events_between(&conn, &from[..10], &to[..10], current)?
+        .into_iter()
+        .filter(|e| {
+            let at = format!("{} {}", e.date, e.time);
+            !e.time.is_empty() && at >= from && at <= to
+        })
+        .map(|e| format!("{} — {} ({}мин)", e.time, e.title, e.duration_minutes))
+        .collect();
     Ok(events.join("\n"))
 }
 
@@ -471,13 +467,11 @@ pub fn gather_morning_digest() -> Result<String, String> {
     let conn = rusqlite::Connection::open(&db_path).map_err(|e| e.to_string())?;
     let mut digest = String::new();
 
-    // Today's events count
+    // Today's events count (today in the current zone)
     let today = chrono::Local::now().format("%Y-%m-%d").to_string();
-    let event_count: i64 = conn.query_row(
-        "SELECT COUNT(*) FROM events WHERE date = ?1",
-        rusqlite::params![today],
-        |row| row.get(0),
-    ).unwrap_or(0);
+    let zone = crate::time_zones::current_zone(&conn);
+    let local_today = crate::time_zones::now_in(zone).format("%Y-%m-%d").to_string();
+    let event_count = crate::time_zones::events_between(&conn, &local_today, &local_today, zone).map(|e| e.len()).unwrap_or(0);
     digest.push_str(&format!("Today's events: {}\n", event_count));
 
     let yesterday = (chrono::Local::now() - chrono::Duration::days(1)).format("%Y-%m-%d").to_string();
@@ -493,6 +487,16 @@ pub fn gather_morning_digest() -> Result<String, String> {
         }
     }
 
+    // Resting HR vs baseline, elevation warnings
+    for line in crate::heart_rate::digest_lines(&conn) {
+        digest.push_str(&line);
+        digest.push('\n');
+    }
+    for line in crate::medications::digest_lines(&conn) {
+        digest.push_str(&line);
+        digest.push('\n');
+    }
+
     // Active goals with progress
     if let Ok(mut stmt) = conn.prepare(
         "SELECT title, current_value, target_value, unit, deadline FROM tab_goals WHERE current_value < target_value AND status = 'active' ORDER BY deadline ASC LIMIT 5"
@@ -1125,6 +1129,11 @@ pub async fn proactive_loop(proactive_handle: AppHandle, proactive_state_ref: Ar
                 score += 0.25;
             }
 
+            // screen-time limit reached
+            if triggers.iter().any(|t| t.starts_with("Лимит экранного времени")) {
+                score += 0.3;
+            }
+
             // pending trigger (generic — includes smart triggers from DB)
             if !triggers.is_empty() {
                 score += 0.4_f64.min(score + 0.4) - score; // ensure at least +0.15
@@ -1211,7 +1220,8 @@ pub async fn proactive_loop(proactive_handle: AppHandle, proactive_state_ref: Ar
 
         // Auto-quiet: detect idle/sleep without fixed quiet hours
         let (idle_secs, screen_locked) = tokio::task::spawn_blocking(|| {
-            (get_macos_idle_seconds(), is_screen_locked())
+            let sensor = crate::activity_sensor::sensor();
+            (sensor.idle_seconds(), sensor.screen_locked())
         }).await.unwrap_or((0.0, false));
         let idle_min = idle_secs / 60.0;
         let hour = now_t.hour();
@@ -0,0 +1,404 @@
+// program_schedule.rs — Project workout program runs onto dates. A run's absolute
+// position (`completed_days`) sits on `anchor_date`; every later cycle day lands on
+// the following calendar day. Rest days pass on their own, missed sessions either
+// shift the schedule to today or are skipped (`missed_policy`), deload and
+// percentage weeks scale the load, and upcoming sessions are published as
+// calendar events (source 'auto_program') through calendar_health's upsert.
+use crate::types::HanniDb;
+use chrono::NaiveDate;
+
+const EVENT_SOURCE: &str = "auto_program";
+const EVENT_COLOR: &str = "#f59e0b";
+/// How far ahead sessions are published to the calendar.
+const CALENDAR_HORIZON_DAYS: i64 = 28;
+
+/// Per-program load settings.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LoadPlan {
+    /// Every Nth cycle is a deload (0 = never).
+    pub deload_every: i64,
+    /// Load multiplier on deload cycles (0.6 = 60%).
+    pub deload_pct: f64,
+    /// Percent of template load per cycle, repeating ("70,75,80,60"). Empty = 100%.
+    pub week_percents: Vec<f64>,
+}
+
+impl LoadPlan {
+    /// (multiplier, is_deload) for the cycle ("week") with zero-based index `week`.
+    pub fn factor(&self, week: i64) -> (f64, bool) {
+        if self.deload_every > 0 && (week + 1) % self.deload_every == 0 {
+            return (self.deload_pct.clamp(0.1, 1.0), true);
+        }
+        if self.week_percents.is_empty() { return (1.0, false); }
+        let p = self.week_percents[(week as usize) % self.week_percents.len()];
+        (p / 100.0, false)
+    }
+}
+
+pub fn parse_percents(s: &str) -> Vec<f64> {
+    s.split([',', ';', ' ']).filter_map(|p| p.trim().trim_end_matches('%').parse::<f64>().ok())
+        .filter(|p| *p > 0.0 && *p <= 200.0).collect()
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Session {
+    pub position: i64,
+    pub date: String,
+    pub day_index: i64,
+    pub week: i64,
+    pub factor: f64,
+    pub deload: bool,
+}
+
+/// Upcoming training sessions from `position` on `anchor`, up to `until` (inclusive)
+/// and the program's end (`total` positions, 0 = open-ended).
+pub fn project(
+    position: i64, anchor: NaiveDate, until: NaiveDate, cycle: i64, total: i64,
+    is_training: &dyn Fn(i64) -> bool, plan: &LoadPlan,
+) -> Vec<Session> {
+    let cycle = cycle.max(1);
+    let mut out = Vec::new();
+    let (mut pos, mut date) = (position, anchor);
+    while date <= until && (total == 0 || pos < total) {
+        let day = pos % cycle;
+        if is_training(day) {
+            let week = pos / cycle;
+            let (factor, deload) = plan.factor(week);
+            out.push(Session { position: pos, date: date.format("%Y-%m-%d").to_string(), day_index: day, week, factor, deload });
+        }
+        pos += 1;
+        date = date.succ_opt().unwrap_or(date);
+    }
+    out
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reconciled {
+    pub position: i64,
+    pub anchor: NaiveDate,
+    /// (position, scheduled date) of training days that went by undone.
+    pub missed: Vec<(i64, NaiveDate)>,
+}
+
+/// Catch a run up to `today`: rest days before today advance by themselves; a
+/// past training day is missed — with "shift" it moves to today (and so does
+/// everything after it), with "skip" it's dropped and the run moves on.
+pub fn reconcile(
+    position: i64, anchor: NaiveDate, today: NaiveDate, cycle: i64, total: i64,
+    is_training: &dyn Fn(i64) -> bool, policy: &str,
+) -> Reconciled {
+    let cycle = cycle.max(1);
+    let (mut pos, mut date) = (position, anchor);
+    let mut missed = Vec::new();
+    while date < today && (total == 0 || pos < total) {
+        if !is_training(pos % cycle) {
+            pos += 1;
+            date = date.succ_opt().unwrap_or(today);
+            continue;
+        }
+        missed.push((pos, date));
+        if policy == "skip" {
+            pos += 1;
+            date = date.succ_opt().unwrap_or(today);
+        } else {
+            date = today;
+        }
+    }
+    Reconciled { position: pos, anchor: date, missed }
+}
+
+// ── DB side ──
+
+struct RunInfo {
+    run_id: i64,
+    program_id: i64,
+    program_name: String,
+    position: i64,
+    anchor: NaiveDate,
+    cycle: i64,
+    total: i64,
+    policy: String,
+    time: String,
+    minutes: i64,
+    plan: LoadPlan,
+    training_days: std::collections::HashMap<i64, Vec<String>>,
+}
+
+fn today() -> NaiveDate { chrono::Local::now().date_naive() }
+
+fn parse_date(s: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(s.get(..10).unwrap_or(s), "%Y-%m-%d").ok()
+}
+
+fn load_run(conn: &rusqlite::Connection, run_id: i64) -> Result<RunInfo, String> {
+    let mut run = conn.query_row(
+        "SELECT r.program_id, p.name, r.completed_days, r.anchor_date, r.started_at, p.cycle_length_days, p.duration_weeks,
+                r.missed_policy, r.session_time, r.session_minutes, p.deload_every_weeks, p.deload_pct, p.week_percents
+         FROM program_runs r JOIN workout_programs p ON p.id=r.program_id WHERE r.id=?1",
+        rusqlite::params![run_id],
+        |r| {
+            let cycle = r.get::<_, i64>(5)?.max(1);
+            let anchor: Option<String> = r.get(3)?;
+            let started: String = r.get(4)?;
+            Ok(RunInfo {
+                run_id, program_id: r.get(0)?, program_name: r.get(1)?, position: r.get(2)?,
+                anchor: anchor.as_deref().and_then(parse_date).or_else(|| parse_date(&started)).unwrap_or_else(today),
+                cycle, total: r.get::<_, i64>(6)?.max(0) * cycle,
+                policy: r.get(7)?, time: r.get(8)?, minutes: r.get(9)?,
+                plan: LoadPlan { deload_every: r.get(10)?, deload_pct: r.get(11)?, week_percents: parse_percents(&r.get::<_, String>(12)?) },
+                training_days: std::collections::HashMap::new(),
+            })
+        },
+    ).map_err(|e| format!("Run not found: {}", e))?;
+    let mut stmt = conn.prepare(
+        "SELECT pd.day_index, COALESCE(NULLIF(t.name,''), NULLIF(pd.label,''), 'Тренировка') FROM program_days pd
+         LEFT JOIN workout_templates t ON t.id=pd.template_id
+         WHERE pd.program_id=?1 AND pd.is_rest=0 AND pd.template_id IS NOT NULL ORDER BY pd.order_index"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let rows = stmt.query_map(rusqlite::params![run.program_id], |r| Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?)))
+        .map_err(|e| format!("Query error: {}", e))?;
+    for (day, name) in rows.filter_map(|r| r.ok()) {
+        run.training_days.entry(day).or_default().push(name);
+    }
+    Ok(run)
+}
+
+/// Load multiplier for the run's current position (deload / percentage week).
+pub(crate) fn current_load(conn: &rusqlite::Connection, run_id: i64) -> (f64, bool, i64) {
+    match load_run(conn, run_id) {
+        Ok(r) => {
+            let week = r.position / r.cycle;
+            let (f, d) = r.plan.factor(week);
+            (f, d, week)
+        }
+        Err(_) => (1.0, false, 0),
+    }
+}
+
+/// Bring an active run up to today (auto-advance rest days, log + handle
+/// misses) and republish its upcoming sessions. Returns the number of misses.
+pub(crate) fn refresh_run(conn: &rusqlite::Connection, run_id: i64) -> Result<usize, String> {
+    let run = load_run(conn, run_id)?;
+    let today = today();
+    let training = |d: i64| run.training_days.contains_key(&d);
+    let rec = reconcile(run.position, run.anchor, today, run.cycle, run.total, &training, &run.policy);
+    let now = chrono::Local::now().to_rfc3339();
+    for (pos, date) in &rec.missed {
+        conn.execute(
+            "INSERT OR IGNORE INTO program_run_log (run_id, position, day_index, scheduled_date, status, created_at)
+             VALUES (?1,?2,?3,?4,'missed',?5)",
+            rusqlite::params![run_id, pos, pos % run.cycle, date.format("%Y-%m-%d").to_string(), now],
+        ).ok();
+    }
+    conn.execute(
+        "UPDATE program_runs SET completed_days=?1, current_day=?2, anchor_date=?3 WHERE id=?4",
+        rusqlite::params![rec.position, rec.position % run.cycle, rec.anchor.format("%Y-%m-%d").to_string(), run_id],
+    ).map_err(|e| format!("DB error: {}", e))?;
+    if run.total > 0 && rec.position >= run.total {
+        conn.execute("UPDATE program_runs SET status='done', finished_at=?1 WHERE id=?2", rusqlite::params![now, run_id]).ok();
+        conn.execute("UPDATE workout_programs SET active=0 WHERE id=?1", rusqlite::params![run.program_id]).ok();
+        clear_run_events(conn, run_id);
+        return Ok(rec.missed.len());
+    }
+    publish_calendar(conn, &RunInfo { position: rec.position, anchor: rec.anchor, ..run });
+    Ok(rec.missed.len())
+}
+
+/// Record a finished day and move the anchor to tomorrow.
+pub(crate) fn mark_day_done(conn: &rusqlite::Connection, run_id: i64, position: i64, day_index: i64) {
+    let today = today();
+    let now = chrono::Local::now().to_rfc3339();
+    let workout_id: Option<i64> = conn.query_row(
+        "SELECT id FROM workouts WHERE program_run_id=?1 AND date=?2 ORDER BY id DESC LIMIT 1",
+        rusqlite::params![run_id, today.format("%Y-%m-%d").to_string()], |r| r.get(0),
+    ).ok();
+    conn.execute(
+        "INSERT OR REPLACE INTO program_run_log (run_id, position, day_index, scheduled_date, status, workout_id, created_at)
+         VALUES (?1,?2,?3,?4,'done',?5,?6)",
+        rusqlite::params![run_id, position, day_index, today.format("%Y-%m-%d").to_string(), workout_id, now],
+    ).ok();
+    let next = today.succ_opt().unwrap_or(today).format("%Y-%m-%d").to_string();
+    conn.execute("UPDATE program_runs SET anchor_date=?1 WHERE id=?2", rusqlite::params![next, run_id]).ok();
+}
+
+fn publish_calendar(conn: &rusqlite::Connection, run: &RunInfo) {
+    let today = today();
+    let until = today + chrono::Duration::days(CALENDAR_HORIZON_DAYS);
+    let training = |d: i64| run.training_days.contains_key(&d);
+    let sessions = project(run.position, run.anchor, until, run.cycle, run.total, &training, &run.plan);
+    let now = chrono::Local::now().to_rfc3339();
+    let mut wanted = Vec::new();
+    for s in &sessions {
+        // Identity = run + absolute position, so a rescheduled session moves its event.
+        let ext_id = format!("program:{}:{}", run.run_id, s.position);
+        let names = run.training_days.get(&s.day_index).map(|v| v.join(" + ")).unwrap_or_default();
+        let title = format!("🏋 {}", names);
+        let load = if s.deload { format!(" · разгрузка {}%", (s.factor * 100.0).round()) }
+                   else if (s.factor - 1.0).abs() > 0.001 { format!(" · {}% нагрузки", (s.factor * 100.0).round()) }
+                   else { String::new() };
+        let desc = format!("{} · день {}/{} · неделя {}{}", run.program_name, s.day_index + 1, run.cycle, s.week + 1, load);
+        crate::calendar_health::upsert_event_by_external_id(
+            conn, EVENT_SOURCE, &ext_id, &title, &desc, &s.date, &run.time, run.minutes, EVENT_COLOR, &now,
+        );
+        wanted.push(ext_id);
+    }
+    // Drop this run's future events that are no longer projected (finished
+    // positions, or sessions pushed beyond the horizon). Past ones stay as history.
+    let from = today.format("%Y-%m-%d").to_string();
+    let pattern = format!("program:{}:%", run.run_id);
+    let stale: Vec<(i64, String)> = conn.prepare(
+        "SELECT id, external_id FROM events WHERE source=?1 AND external_id LIKE ?2 AND (date>=?3 OR completed=0)"
+    ).and_then(|mut st| st.query_map(rusqlite::params![EVENT_SOURCE, pattern, from], |r| Ok((r.get(0)?, r.get(1)?)))
+        .map(|m| m.filter_map(|x| x.ok()).collect()))
+        .unwrap_or_default();
+    for (id, ext) in stale {
+        let keep = wanted.contains(&ext)
+            // A done position keeps its event where it happened.
+            || ext.rsplit(':').next().and_then(|p| p.parse::<i64>().ok()).is_some_and(|p| p < run.position && done_at(conn, run.run_id, p));
+        if !keep { conn.execute("DELETE FROM events WHERE id=?1", rusqlite::params![id]).ok(); }
+    }
+}
+
+fn done_at(conn: &rusqlite::Connection, run_id: i64, position: i64) -> bool {
+    conn.query_row(
+        "SELECT 1 FROM program_run_log WHERE run_id=?1 AND position=?2 AND status='done'",
+        rusqlite::params![run_id, position], |_| Ok(()),
+    ).is_ok()
+}
+
+/// Remove a run's not-yet-done calendar events (run stopped / finished / deleted).
+pub(crate) fn clear_run_events(conn: &rusqlite::Connection, run_id: i64) {
+    let from = today().format("%Y-%m-%d").to_string();
+    conn.execute(
+        "DELETE FROM events WHERE source=?1 AND external_id LIKE ?2 AND date>=?3",
+        rusqlite::params![EVENT_SOURCE, format!("program:{}:%", run_id), from],
+    ).ok();
+}
+
+/// Startup / periodic pass over every active run.
+pub fn refresh_active_runs(conn: &rusqlite::Connection) {
+    let ids: Vec<i64> = conn.prepare("SELECT id FROM program_runs WHERE status='active'")
+        .and_then(|mut s| s.query_map([], |r| r.get(0)).map(|m| m.filter_map(|x| x.ok()).collect()))
+        .unwrap_or_default();
+    for id in ids { let _ = refresh_run(conn, id); }
+}
+
+// ── Commands ──
+
+/// Upcoming sessions (dates, load factor) plus done/missed history for a run.
+#[tauri::command]
+pub fn get_program_schedule(run_id: i64, days: Option<i64>, db: tauri::State<'_, HanniDb>) -> Result<serde_json::Value, String> {
+    let conn = db.conn();
+    refresh_run(&conn, run_id)?;
+    let run = load_run(&conn, run_id)?;
+    let until = today() + chrono::Duration::days(days.unwrap_or(CALENDAR_HORIZON_DAYS).clamp(1, 366));
+    let training = |d: i64| run.training_days.contains_key(&d);
+    let sessions: Vec<serde_json::Value> = project(run.position, run.anchor, until, run.cycle, run.total, &training, &run.plan)
+        .into_iter().map(|s| {
+            let names = run.training_days.get(&s.day_index).cloned().unwrap_or_default();
+            let mut v = serde_json::to_value(&s).unwrap_or_default();
+            v["templates"] = serde_json::json!(names);
+            v
+        }).collect();
+    let mut stmt = conn.prepare(
+        "SELECT position, day_index, scheduled_date, status, workout_id FROM program_run_log WHERE run_id=?1 ORDER BY position DESC, id DESC LIMIT 60"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let history: Vec<serde_json::Value> = stmt.query_map(rusqlite::params![run_id], |r| Ok(serde_json::json!({
+        "position": r.get::<_, i64>(0)?, "day_index": r.get::<_, i64>(1)?, "date": r.get::<_, String>(2)?,
+        "status": r.get::<_, String>(3)?, "workout_id": r.get::<_, Option<i64>>(4)?,
+    }))).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+    let missed = history.iter().filter(|h| h["status"] == "missed").count();
+    Ok(serde_json::json!({
+        "run_id": run_id, "position": run.position, "anchor_date": run.anchor.format("%Y-%m-%d").to_string(),
+        "total_days": run.total, "session_time": run.time, "session_minutes": run.minutes,
+        "missed_policy": run.policy, "upcoming": sessions, "history": history, "missed_count": missed,
+    }))
+}
+
+/// Calendar slot and missed-session behaviour of a run ("shift" | "skip").
+#[tauri::command]
+pub fn update_program_run(
+    run_id: i64, session_time: Option<String>, session_minutes: Option<i64>, missed_policy: Option<String>,
+    start_date: Option<String>, db: tauri::State<'_, HanniDb>,
+) -> Result<(), String> {
+    let conn = db.conn();
+    if let Some(t) = session_time {
+        let ok = t.len() == 5 && chrono::NaiveTime::parse_from_str(&t, "%H:%M").is_ok();
+        if !ok { return Err("Время в формате ЧЧ:ММ".into()); }
+        conn.execute("UPDATE program_runs SET session_time=?1 WHERE id=?2", rusqlite::params![t, run_id]).map_err(|e| format!("DB error: {}", e))?;
+    }
+    if let Some(m) = session_minutes {
+        conn.execute("UPDATE program_runs SET session_minutes=?1 WHERE id=?2", rusqlite::params![m.clamp(5, 600), run_id]).map_err(|e| format!("DB error: {}", e))?;
+    }
+    if let Some(p) = missed_policy {
+        if p != "shift" && p != "skip" { return Err("missed_policy: shift | skip".into()); }
+        conn.execute("UPDATE program_runs SET missed_policy=?1 WHERE id=?2", rusqlite::params![p, run_id]).map_err(|e| format!("DB error: {}", e))?;
+    }
+    if let Some(d) = start_date {
+        // Re-anchor the current position (e.g. "start next Monday"); only forward, misses aren't invented.
+        let d = parse_date(&d).ok_or("Дата в формате ГГГГ-ММ-ДД")?;
+        let d = d.max(today());
+        conn.execute("UPDATE program_runs SET anchor_date=?1 WHERE id=?2", rusqlite::params![d.format("%Y-%m-%d").to_string(), run_id]).map_err(|e| format!("DB error: {}", e))?;
+    }
+    refresh_run(&conn, run_id)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::d;
+
+    // 3-day cycle: train, rest, train
+    fn split(day: i64) -> bool { day != 1 }
+
+    #[test]
+    fn projects_sessions_over_rest_days_and_end() {
+        let plan = LoadPlan { deload_every: 2, deload_pct: 0.6, week_percents: vec![] };
+        let s = project(0, d("2026-10-05"), d("2026-10-12"), 3, 6, &split, &plan);
+        let dates: Vec<&str> = s.iter().map(|x| x.date.as_str()).collect();
+        // positions 0,2 | 3,5 — program ends after 6 positions
+        assert_eq!(dates, vec!["2026-10-05", "2026-10-07", "2026-10-08", "2026-10-10"]);
+        assert_eq!(s.iter().map(|x| x.deload).collect::<Vec<_>>(), vec![false, false, true, true]);
+        assert_eq!(s[2].factor, 0.6);
+    }
+
+    #[test]
+    fn percentage_weeks_cycle() {
+        let plan = LoadPlan { deload_every: 0, deload_pct: 0.6, week_percents: parse_percents("70, 80%,90") };
+        assert_eq!(plan.factor(0), (0.7, false));
+        assert_eq!(plan.factor(4), (0.8, false));
+        let both = LoadPlan { deload_every: 4, ..plan };
+        assert_eq!(both.factor(3), (0.6, true));
+    }
+
+    #[test]
+    fn reconciles_missed_sessions() {
+        // anchor on a training day two days ago → shifted to today
+        let r = reconcile(0, d("2026-10-03"), d("2026-10-05"), 3, 0, &split, "shift");
+        assert_eq!((r.position, r.anchor), (0, d("2026-10-05")));
+        assert_eq!(r.missed, vec![(0, d("2026-10-03"))]);
+        // skip: 0 missed, 1 rest passes, 2 missed → position 3 today
+        let r = reconcile(0, d("2026-10-02"), d("2026-10-05"), 3, 0, &split, "skip");
+        assert_eq!((r.position, r.anchor), (3, d("2026-10-05")));
+        assert_eq!(r.missed.len(), 2);
+        // rest day yesterday just advances, nothing missed
+        let r = reconcile(1, d("2026-10-04"), d("2026-10-05"), 3, 0, &split, "shift");
+        assert_eq!((r.position, r.anchor, r.missed.len()), (2, d("2026-10-05"), 0));
+    }
+
+    #[test]
+    fn schedule_edges() {
+        let plan = LoadPlan { deload_every: 0, deload_pct: 0.6, week_percents: vec![] };
+        // Anchor past the horizon, or a finished program → nothing to schedule.
+        assert!(project(0, d("2026-10-10"), d("2026-10-05"), 3, 0, &split, &plan).is_empty());
+        assert!(project(6, d("2026-10-05"), d("2026-10-12"), 3, 6, &split, &plan).is_empty());
+        // Nothing to catch up when the anchor is today or the program is over.
+        let r = reconcile(2, d("2026-10-05"), d("2026-10-05"), 3, 0, &split, "shift");
+        assert_eq!((r.position, r.anchor, r.missed.len()), (2, d("2026-10-05"), 0));
+        let r = reconcile(6, d("2026-10-01"), d("2026-10-05"), 3, 6, &split, "skip");
+        assert_eq!((r.position, r.missed.len()), (6, 0));
+        // A zero-length cycle is treated as one training day.
+        assert_eq!(project(0, d("2026-10-05"), d("2026-10-06"), 0, 0, &|_| true, &plan).len(), 2);
+    }
+}
@@ -379,11 +379,13 @@ pub fn build_tool_definitions() -> Vec<serde_json::Value> {
             "required": ["media_type","title"]
         })),
         // Food
-        tool("log_food", "Log a meal with optional nutrition info", serde_json::json!({
+        tool("log_food", "Log a meal with optional nutrition info. Pass recipe_id (+servings) for a saved recipe to fill macros automatically", serde_json::json!({
             "type": "object",
             "properties": {
                 "meal_type": {"type": "string", "enum": ["breakfast","lunch","dinner","snack"]},
                 "name": {"type": "string"},
+                "recipe_id": {"type": "integer"},
+                "servings": {"type": "number"},
                 "calories": {"type": "number"},
                 "protein": {"type": "number"},
                 "carbs": {"type": "number"},
@@ -393,6 +395,14 @@ pub fn build_tool_definitions() -> Vec<serde_json::Value> {
             },
             "required": ["meal_type","name"]
         })),
+        tool("import_recipe", "Import a recipe from a web page URL or pasted text into the recipe book (ingredients mapped to the catalog, food blacklist respected)", serde_json::json!({
+            "type": "object",
+            "properties": {
+                "url": {"type": "string", "description": "Recipe page URL"},
+                "text": {"type": "string", "description": "Recipe text if there is no URL"},
+                "force": {"type": "boolean", "description": "Import even if it hits the food blacklist"}
+            }
+        })),
         tool("add_product", "Add a product with optional expiry tracking", serde_json::json!({
             "type": "object",
             "properties": {
@@ -533,6 +543,8 @@ pub fn select_relevant_tools(user_msg: &str) -> Vec<serde_json::Value> {
          &["start_focus", "stop_focus"]),
         (&["поел", "ел ", "завтрак", "обед", "ужин", "перекус", "калори", "еда", "еду"],
          &["log_food"]),
+        (&["импортируй рецепт", "сохрани рецепт", "добавь рецепт", "рецепт по ссылке", "рецепт с сайта"],
+         &["import_recipe"]),
         (&["продукт", "срок годн", "холодильник"],
          &["add_product"]),
         (&["спал", "сон", "вод", "вес ", "шаг", "здоровь"],
@@ -0,0 +1,750 @@
+// recipe_import.rs — Import a recipe from a URL or pasted text: schema.org
+// `Recipe` JSON-LD first, then a sectioned plain-text parse, then an LLM
+// fallback for unstructured pages. Ingredients are mapped onto
+// ingredient_catalog, checked against food_blacklist and stored as
+// recipes + recipe_ingredients.
+
+use crate::types::*;
+use tauri::{AppHandle, Manager};
+
+/// Parsed recipe before it touches the DB.
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct ParsedRecipe {
+    pub name: String,
+    pub description: String,
+    /// Raw ingredient lines ("200 мл молока").
+    pub ingredients: Vec<String>,
+    pub instructions: Vec<String>,
+    pub servings: Option<i64>,
+    pub prep_time: Option<i64>,
+    pub cook_time: Option<i64>,
+    /// Per serving, when the source states it.
+    pub calories: Option<i64>,
+    pub image: String,
+    pub tags: Vec<String>,
+    pub cuisine: String,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct IngredientLine {
+    pub raw: String,
+    pub name: String,
+    pub amount: f64,
+    pub unit: String,
+}
+
+// ── JSON-LD ──
+
+/// All schema.org Recipe objects found in `<script type="application/ld+json">`
+/// blocks (top level, arrays and `@graph`).
+pub fn extract_jsonld_recipes(html: &str) -> Vec<ParsedRecipe> {
+    let re = regex::Regex::new(r#"(?is)<script[^>]*type\s*=\s*["']application/ld\+json["'][^>]*>(.*?)</script>"#).unwrap();
+    let mut out = Vec::new();
+    for cap in re.captures_iter(html) {
+        let body = cap[1].trim().trim_start_matches("<!--").trim_end_matches("-->").trim();
+        if let Ok(v) = serde_json::from_str::<serde_json::Value>(body) {
+            collect_recipes(&v, &mut out);
+        }
+    }
+    out
+}
+
+fn collect_recipes(v: &serde_json::Value, out: &mut Vec<ParsedRecipe>) {
+    match v {
+        serde_json::Value::Array(items) => for it in items { collect_recipes(it, out) },
+        serde_json::Value::Object(map) => {
+            let is_recipe = match map.get("@type") {
+                Some(serde_json::Value::String(t)) => t == "Recipe",
+                Some(serde_json::Value::Array(ts)) => ts.iter().any(|t| t.as_str() == Some("Recipe")),
+                _ => false,
+            };
+            if is_recipe {
+                if let Some(r) = recipe_from_jsonld(v) { out.push(r); }
+            } else if let Some(g) = map.get("@graph") {
+                collect_recipes(g, out);
+            } else if let Some(m) = map.get("mainEntity") {
+                collect_recipes(m, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn recipe_from_jsonld(v: &serde_json::Value) -> Option<ParsedRecipe> {
+    let s = |key: &str| v.get(key).and_then(|x| x.as_str()).map(clean_text).unwrap_or_default();
+    let name = s("name");
+    if name.is_empty() { return None; }
+    let ingredients: Vec<String> = v.get("recipeIngredient").or_else(|| v.get("ingredients"))
+        .and_then(|x| x.as_array())
+        .map(|a| a.iter().filter_map(|i| i.as_str()).map(clean_text).filter(|l| !l.is_empty()).collect())
+        .unwrap_or_default();
+    let mut instructions = Vec::new();
+    if let Some(ins) = v.get("recipeInstructions") { collect_steps(ins, &mut instructions); }
+    let calories = v.get("nutrition").and_then(|n| n.get("calories"))
+        .and_then(|c| c.as_str().map(String::from).or_else(|| c.as_f64().map(|f| f.to_string())))
+        .and_then(|c| first_number(&c)).map(|c| c.round() as i64);
+    let mut tags: Vec<String> = Vec::new();
+    for key in ["keywords", "recipeCategory"] {
+        match v.get(key) {
+            Some(serde_json::Value::String(k)) => tags.extend(k.split(',').map(clean_text)),
+            Some(serde_json::Value::Array(a)) => tags.extend(a.iter().filter_map(|x| x.as_str()).map(clean_text)),
+            _ => {}
+        }
+    }
+    tags.retain(|t| !t.is_empty());
+    tags.dedup();
+    let cuisine = match v.get("recipeCuisine") {
+        Some(serde_json::Value::String(c)) => clean_text(c),
+        Some(serde_json::Value::Array(a)) => a.iter().filter_map(|x| x.as_str()).next().map(clean_text).unwrap_or_default(),
+        _ => String::new(),
+    };
+    Some(ParsedRecipe {
+        name,
+        description: s("description"),
+        ingredients,
+        instructions,
+        servings: v.get("recipeYield").and_then(yield_servings),
+        prep_time: v.get("prepTime").and_then(|x| x.as_str()).and_then(iso_minutes),
+        cook_time: v.get("cookTime").and_then(|x| x.as_str()).and_then(iso_minutes)
+            .or_else(|| v.get("totalTime").and_then(|x| x.as_str()).and_then(iso_minutes)),
+        calories,
+        image: image_url(v.get("image")),
+        tags,
+        cuisine,
+    })
+}
+
+/// recipeInstructions: string | [string] | HowToStep{text} | HowToSection{itemListElement}.
+fn collect_steps(v: &serde_json::Value, out: &mut Vec<String>) {
+    match v {
+        serde_json::Value::String(s) => out.extend(
+            clean_text_keep_lines(s).lines().map(|l| l.trim().to_string()).filter(|l| !l.is_empty()),
+        ),
+        serde_json::Value::Array(a) => for it in a { collect_steps(it, out) },
+        serde_json::Value::Object(m) => {
+            if let Some(items) = m.get("itemListElement") {
+                collect_steps(items, out);
+            } else if let Some(t) = m.get("text").or_else(|| m.get("name")).and_then(|t| t.as_str()) {
+                let t = clean_text(t);
+                if !t.is_empty() { out.push(t); }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn image_url(v: Option<&serde_json::Value>) -> String {
+    match v {
+        Some(serde_json::Value::String(s)) => s.clone(),
+        Some(serde_json::Value::Array(a)) => image_url(a.first()),
+        Some(serde_json::Value::Object(m)) => m.get("url").and_then(|u| u.as_str()).unwrap_or_default().to_string(),
+        _ => String::new(),
+    }
+}
+
+fn yield_servings(v: &serde_json::Value) -> Option<i64> {
+    match v {
+        serde_json::Value::Number(n) => n.as_f64().map(|f| f.round() as i64),
+        serde_json::Value::String(s) => first_number(s).map(|f| f.round() as i64),
+        serde_json::Value::Array(a) => a.iter().find_map(yield_servings),
+        _ => None,
+    }.filter(|n| *n > 0)
+}
+
+/// ISO-8601 duration ("PT1H30M", "P0DT0H20M") → minutes.
+pub fn iso_minutes(s: &str) -> Option<i64> {
+    let re = regex::Regex::new(r"(?i)^P(?:(\d+)D)?(?:T(?:(\d+)H)?(?:(\d+)M)?(?:(\d+)S)?)?$").unwrap();
+    let c = re.captures(s.trim())?;
+    let n = |i: usize| c.get(i).and_then(|m| m.as_str().parse::<i64>().ok()).unwrap_or(0);
+    let total = n(1) * 1440 + n(2) * 60 + n(3) + (n(4) + 59) / 60;
+    if total > 0 { Some(total) } else { None }
+}
+
+// ── Text helpers ──
+
+fn decode_entities(s: &str) -> String {
+    let s = s.replace("&nbsp;", " ").replace("&quot;", "\"").replace("&#39;", "'").replace("&#039;", "'")
+        .replace("&apos;", "'").replace("&lt;", "<").replace("&gt;", ">").replace("&frac12;", "½")
+        .replace("&frac14;", "¼").replace("&frac34;", "¾").replace("&mdash;", "—").replace("&ndash;", "–");
+    let re = regex::Regex::new(r"&#(\d+);").unwrap();
+    let s = re.replace_all(&s, |c: &regex::Captures| {
+        c[1].parse::<u32>().ok().and_then(char::from_u32).map(String::from).unwrap_or_default()
+    }).to_string();
+    s.replace("&amp;", "&")
+}
+
+fn clean_text_keep_lines(s: &str) -> String {
+    let re = regex::Regex::new(r"(?i)<br\s*/?>|</p>|</li>").unwrap();
+    let s = re.replace_all(s, "\n");
+    let tags = regex::Regex::new(r"<[^>]+>").unwrap();
+    decode_entities(&tags.replace_all(&s, " "))
+}
+
+fn clean_text(s: &str) -> String {
+    clean_text_keep_lines(s).split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Visible page text for the LLM fallback (scripts/styles dropped, capped).
+pub fn html_to_text(html: &str, max_chars: usize) -> String {
+    let re = regex::Regex::new(r"(?is)<(script|style|noscript|svg|nav|footer|header)[^>]*>.*?</(script|style|noscript|svg|nav|footer|header)>").unwrap();
+    let s = re.replace_all(html, " ");
+    let block = regex::Regex::new(r"(?i)<(br|/p|/li|/h\d|/div|/tr)[^>]*>").unwrap();
+    let s = block.replace_all(&s, "\n");
+    let text = clean_text_keep_lines(&s);
+    let lines: Vec<String> = text.lines()
+        .map(|l| l.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|l| !l.is_empty()).collect();
+    lines.join("\n").chars().take(max_chars).collect()
+}
+
+/// Leading number with fractions: "1/2", "½", "1 1/2", "1,5", "2-3" (→ 2.5).
+fn parse_quantity(s: &str) -> Option<(f64, usize)> {
+    let re = regex::Regex::new(
+        r"^\s*(?:(?:(\d+)\s+)?(\d+)\s*/\s*(\d+)|(\d+(?:[.,]\d+)?)?\s*([½¼¾⅓⅔])?)(?:\s*[-–]\s*(\d+(?:[.,]\d+)?))?",
+    ).unwrap();
+    let c = re.captures(s)?;
+    let num = |i: usize| c.get(i).and_then(|m| m.as_str().replace(',', ".").parse::<f64>().ok());
+    let mut v = if let (Some(n), Some(d)) = (num(2), num(3)) {
+        if d == 0.0 { return None; }
+        num(1).unwrap_or(0.0) + n / d
+    } else {
+        let glyph = c.get(5).map(|m| match m.as_str() { "½" => 0.5, "¼" => 0.25, "¾" => 0.75, "⅓" => 1.0 / 3.0, _ => 2.0 / 3.0 });
+        if num(4).is_none() && glyph.is_none() { return None; }
+        num(4).unwrap_or(0.0) + glyph.unwrap_or(0.0)
+    };
+    if let Some(hi) = num(6) {
+        v = (v + hi) / 2.0;
+    }
+    Some((v, c.get(0).unwrap().end()))
+}
+
+fn first_number(s: &str) -> Option<f64> {
+    let re = regex::Regex::new(r"\d+(?:[.,]\d+)?").unwrap();
+    re.find(s).and_then(|m| m.as_str().replace(',', ".").parse().ok())
+}
+
+/// Unit spellings → the canonical unit stored in recipe_ingredients.
+const UNIT_ALIASES: &[(&str, &str)] = &[
+    ("столовые ложки", "ст.л."), ("столовых ложек", "ст.л."), ("столовая ложка", "ст.л."),
+    ("чайные ложки", "ч.л."), ("чайных ложек", "ч.л."), ("чайная ложка", "ч.л."),
+    ("ст. л.", "ст.л."), ("ст.л.", "ст.л."), ("ст.л", "ст.л."), ("ст л", "ст.л."),
+    ("ч. л.", "ч.л."), ("ч.л.", "ч.л."), ("ч.л", "ч.л."), ("ч л", "ч.л."),
+    ("стаканов", "стакан"), ("стакана", "стакан"), ("стакан", "стакан"),
+    ("граммов", "г"), ("грамма", "г"), ("грамм", "г"), ("гр.", "г"), ("гр", "г"), ("г.", "г"), ("г", "г"),
+    ("кг.", "кг"), ("кг", "кг"), ("мл.", "мл"), ("мл", "мл"), ("литра", "л"), ("литр", "л"), ("л.", "л"), ("л", "л"),
+    ("штуки", "шт"), ("штука", "шт"), ("штук", "шт"), ("шт.", "шт"), ("шт", "шт"),
+    ("зубчиков", "зубчик"), ("зубчика", "зубчик"), ("зубчик", "зубчик"),
+    ("пучка", "пучок"), ("пучок", "пучок"), ("щепотки", "щепотка"), ("щепотка", "щепотка"),
+    ("банки", "банка"), ("банка", "банка"), ("упаковки", "упаковка"), ("упаковка", "упаковка"),
+    ("tbsp", "ст.л."), ("tsp", "ч.л."), ("cups", "стакан"), ("cup", "стакан"),
+    ("kg", "кг"), ("g", "г"), ("ml", "мл"), ("l", "л"), ("pcs", "шт"),
+];
+
+/// `s` without a leading `prefix`, compared case-insensitively char by char,
+/// so the cut always lands on a char boundary of `s` itself.
+fn strip_prefix_ci<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+    let mut chars = s.char_indices();
+    for p in prefix.chars() {
+        let (_, c) = chars.next()?;
+        if !c.to_lowercase().eq(p.to_lowercase()) { return None; }
+    }
+    Some(chars.next().map_or("", |(i, _)| &s[i..]))
+}
+
+/// Byte offset of the first case-insensitive occurrence of `needle` in `s`.
+fn find_ci(s: &str, needle: &str) -> Option<usize> {
+    s.char_indices().map(|(i, _)| i).find(|&i| strip_prefix_ci(&s[i..], needle).is_some())
+}
+
+/// Split a leading unit off `rest` (word boundary required after it).
+fn take_unit(rest: &str) -> (String, &str) {
+    for (alias, canon) in UNIT_ALIASES {
+        if let Some(after) = strip_prefix_ci(rest, alias) {
+            if after.chars().next().is_none_or(|c| !c.is_alphanumeric()) {
+                return (canon.to_string(), after.trim_start_matches(['.', ' ']));
+            }
+        }
+    }
+    (String::new(), rest)
+}
+
+/// "200 мл молока", "Молоко — 200 мл", "Соль по вкусу", "2-3 зубчика чеснока".
+pub fn parse_ingredient_line(line: &str) -> IngredientLine {
+    let raw = line.trim().to_string();
+    let mut text = raw.trim_start_matches(['-', '•', '*', '–', ' ']).to_string();
+    if let Some(pos) = find_ci(&text, "по вкусу") {
+        let name = text[..pos].trim().trim_end_matches([',', '—', '-', ':', ' ']).to_string();
+        let name = if name.is_empty() { strip_prefix_ci(&text[pos..], "по вкусу").unwrap_or("").trim().to_string() } else { name };
+        return IngredientLine { raw, name: tidy_name(&name), amount: 0.0, unit: "по вкусу".into() };
+    }
+    // "Name — qty unit" / "Name: qty unit"
+    for sep in [" — ", " – ", " - ", ":"] {
+        if let Some((left, right)) = text.split_once(sep) {
+            if let Some((amount, end)) = parse_quantity(right) {
+                let (unit, _) = take_unit(right[end..].trim_start());
+                return IngredientLine { raw, name: tidy_name(left), amount, unit: if unit.is_empty() { "шт".into() } else { unit } };
+            }
+        }
+    }
+    // "qty unit name"
+    if let Some((amount, end)) = parse_quantity(&text) {
+        let rest = text[end..].trim_start().to_string();
+        let (unit, name) = take_unit(&rest);
+        let name = name.trim_start_matches("of ").to_string();
+        return IngredientLine { raw, name: tidy_name(&name), amount, unit: if unit.is_empty() { "шт".into() } else { unit } };
+    }
+    // "Name 200 г" (quantity at the end)
+    let re = regex::Regex::new(r"^(.*?)[\s,]+(\d+(?:[.,]\d+)?(?:\s*[-–]\s*\d+)?)\s*([^\d\s]*\.?)$").unwrap();
+    if let Some(c) = re.captures(&text.clone()) {
+        let (unit, tail) = take_unit(&c[3]);
+        if tail.trim().is_empty() {
+            if let Some((amount, _)) = parse_quantity(&c[2]) {
+                text = c[1].to_string();
+                return IngredientLine { raw, name: tidy_name(&text), amount, unit: if unit.is_empty() { "шт".into() } else { unit } };
+            }
+        }
+    }
+    IngredientLine { raw, name: tidy_name(&text), amount: 0.0, unit: "шт".into() }
+}
+
+/// Drop parentheticals and trailing preparation notes ("лук, мелко нарезанный").
+fn tidy_name(s: &str) -> String {
+    let re = regex::Regex::new(r"\([^)]*\)").unwrap();
+    let s = re.replace_all(s, " ");
+    let s = s.split(',').next().unwrap_or("");
+    s.split_whitespace().collect::<Vec<_>>().join(" ").trim_matches(['.', ':', '-', '—', ' ']).to_string()
+}
+
+/// Plain text with "Ингредиенты" / "Приготовление" sections.
+pub fn parse_sectioned_text(text: &str) -> Option<ParsedRecipe> {
+    let ing_hdr = regex::Regex::new(r"(?i)^\s*(ингредиенты|состав|продукты|ingredients)\b").unwrap();
+    let step_hdr = regex::Regex::new(r"(?i)^\s*(приготовление|способ приготовления|инструкция|шаги|как готовить|method|instructions|directions|preparation)\b").unwrap();
+    let lines: Vec<&str> = text.lines().map(|l| l.trim()).filter(|l| !l.is_empty()).collect();
+    let ing_at = lines.iter().position(|l| ing_hdr.is_match(l))?;
+    let step_at = lines.iter().position(|l| step_hdr.is_match(l)).filter(|s| *s > ing_at).unwrap_or(lines.len());
+    let ingredients: Vec<String> = lines[ing_at + 1..step_at].iter().map(|l| l.to_string()).collect();
+    if ingredients.len() < 2 { return None; }
+    let instructions: Vec<String> = lines.iter().skip(step_at + 1)
+        .map(|l| l.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.' || c == ')' || c == ' ').to_string())
+        .filter(|l| !l.is_empty()).collect();
+    let name = if ing_at > 0 { lines[0].to_string() } else { "Импортированный рецепт".into() };
+    let servings = lines[..ing_at].iter()
+        .find(|l| l.to_lowercase().contains("порци"))
+        .and_then(|l| first_number(l)).map(|n| n as i64);
+    Some(ParsedRecipe { name, ingredients, instructions, servings, ..Default::default() })
+}
+
+/// First balanced `{...}` in an LLM reply (tolerates ```json fences / prose).
+fn extract_json_object(text: &str) -> Option<serde_json::Value> {
+    let start = text.find('{')?;
+    let bytes = text.as_bytes();
+    let (mut depth, mut in_str, mut esc) = (0i32, false, false);
+    for (i, b) in bytes.iter().enumerate().skip(start) {
+        if esc { esc = false; continue; }
+        match b {
+            b'\\' if in_str => esc = true,
+            b'"' => in_str = !in_str,
+            b'{' if !in_str => depth += 1,
+            b'}' if !in_str => {
+                depth -= 1;
+                if depth == 0 { return serde_json::from_str(&text[start..=i]).ok(); }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn recipe_from_llm_json(v: &serde_json::Value) -> Option<ParsedRecipe> {
+    let name = v.get("name").and_then(|x| x.as_str()).map(clean_text).unwrap_or_default();
+    let ingredients: Vec<String> = v.get("ingredients").and_then(|x| x.as_array())?.iter().filter_map(|i| {
+        if let Some(s) = i.as_str() { return Some(s.to_string()); }
+        let n = i.get("name")?.as_str()?;
+        let a = i.get("amount").and_then(|a| a.as_f64()).filter(|a| *a > 0.0);
+        let u = i.get("unit").and_then(|u| u.as_str()).unwrap_or("");
+        Some(match a { Some(a) => format!("{} — {} {}", n, a, u), None if !u.is_empty() => format!("{} {}", n, u), None => n.to_string() })
+    }).collect();
+    if name.is_empty() || ingredients.is_empty() { return None; }
+    let mut instructions = Vec::new();
+    if let Some(i) = v.get("instructions") { collect_steps(i, &mut instructions); }
+    let num = |k: &str| v.get(k).and_then(|x| x.as_f64().or_else(|| x.as_str().and_then(first_number))).map(|f| f.round() as i64).filter(|n| *n > 0);
+    Some(ParsedRecipe {
+        name,
+        description: v.get("description").and_then(|x| x.as_str()).map(clean_text).unwrap_or_default(),
+        ingredients,
+        instructions,
+        servings: num("servings"),
+        prep_time: num("prep_time"),
+        cook_time: num("cook_time"),
+        tags: v.get("tags").and_then(|x| x.as_array())
+            .map(|a| a.iter().filter_map(|t| t.as_str()).map(clean_text).collect()).unwrap_or_default(),
+        ..Default::default()
+    })
+}
+
+// ── Catalog mapping ──
+
+/// Crude Russian stem: drop up to two trailing vowels/soft signs so genitive
+/// and plural forms ("молока", "яиц" aside) meet the catalog's nominative.
+fn stem(word: &str) -> String {
+    let mut chars: Vec<char> = word.chars().collect();
+    for _ in 0..2 {
+        if chars.len() > 4 && matches!(chars.last(), Some('а' | 'я' | 'о' | 'е' | 'ы' | 'и' | 'у' | 'ю' | 'й' | 'ь' | 'ё')) {
+            chars.pop();
+        }
+    }
+    chars.into_iter().collect()
+}
+
+fn stems(s: &str) -> Vec<String> {
+    s.to_lowercase().replace('ё', "е")
+        .split(|c: char| !c.is_alphanumeric() && c != '-')
+        .filter(|w| w.chars().count() > 1)
+        .map(stem).collect()
+}
+
+/// Best catalog entry for an ingredient name: exact normalized match, else the
+/// catalog name whose every word-stem appears in the ingredient (most words wins).
+pub fn match_catalog<'a>(name: &str, catalog: &'a [(i64, String, String)]) -> Option<&'a (i64, String, String)> {
+    let norm = crate::db::normalize_name(name).replace('ё', "е");
+    if let Some(hit) = catalog.iter().find(|c| crate::db::normalize_name(&c.1).replace('ё', "е") == norm) {
+        return Some(hit);
+    }
+    let ing = stems(name);
+    if ing.is_empty() { return None; }
+    catalog.iter()
+        .filter_map(|c| {
+            let cs = stems(&c.1);
+            if cs.is_empty() { return None; }
+            let all = cs.iter().all(|s| ing.iter().any(|w| w == s || (s.chars().count() >= 4 && w.starts_with(s.as_str()))));
+            if all { Some((cs.len(), c.1.chars().count(), c)) } else { None }
+        })
+        .max_by_key(|(n, len, _)| (*n, *len))
+        .map(|(_, _, c)| c)
+}
+
+// ── DB side ──
+
+fn load_catalog(conn: &rusqlite::Connection) -> Vec<(i64, String, String)> {
+    conn.prepare("SELECT id, name, category FROM ingredient_catalog")
+        .and_then(|mut s| s.query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?))).map(|m| m.filter_map(|x| x.ok()).collect()))
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct MappedIngredient {
+    pub raw: String,
+    pub name: String,
+    pub amount: f64,
+    pub unit: String,
+    pub catalog_id: Option<i64>,
+    pub category: Option<String>,
+}
+
+fn map_ingredients(conn: &rusqlite::Connection, lines: &[String]) -> Vec<MappedIngredient> {
+    let catalog = load_catalog(conn);
+    lines.iter().map(|l| {
+        let p = parse_ingredient_line(l);
+        let hit = match_catalog(&p.name, &catalog);
+        MappedIngredient {
+            raw: p.raw,
+            // Store the canonical catalog name so renames cascade by catalog_id.
+            name: hit.map(|h| h.1.clone()).unwrap_or(p.name),
+            amount: p.amount,
+            unit: p.unit,
+            catalog_id: hit.map(|h| h.0),
+            category: hit.map(|h| h.2.clone()),
+        }
+    }).filter(|m| !m.name.is_empty()).collect()
+}
+
+/// food_blacklist entries (hard/soft) the recipe would violate.
+fn blacklist_hits(conn: &rusqlite::Connection, recipe: &ParsedRecipe, items: &[MappedIngredient]) -> Vec<serde_json::Value> {
+    let rows: Vec<(String, String, Option<i64>, String)> = conn
+        .prepare("SELECT type, value, catalog_id, level FROM food_blacklist WHERE level IN ('hard','soft')")
+        .and_then(|mut s| s.query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?))).map(|m| m.filter_map(|x| x.ok()).collect()))
+        .unwrap_or_default();
+    let tags: Vec<String> = recipe.tags.iter().map(|t| crate::db::normalize_name(t)).collect();
+    let rname = crate::db::normalize_name(&recipe.name);
+    let mut hits = Vec::new();
+    for (kind, value, cid, level) in rows {
+        let v = crate::db::normalize_name(&value);
+        let matched: Option<String> = match kind.as_str() {
+            "product" => items.iter().find(|i| (cid.is_some() && i.catalog_id == cid) || crate::db::normalize_name(&i.name) == v)
+                .map(|i| i.name.clone()),
+            "category" => items.iter().find(|i| i.category.as_deref() == Some(value.as_str())).map(|i| i.name.clone()),
+            "tag" | "keyword" => tags.iter().find(|t| **t == v).cloned()
+                .or_else(|| if !v.is_empty() && rname.contains(&v) { Some(recipe.name.clone()) } else { None }),
+            "recipe" => if rname == v { Some(recipe.name.clone()) } else { None },
+            _ => None,
+        };
+        if let Some(m) = matched {
+            hits.push(serde_json::json!({ "type": kind, "value": value, "level": level, "matched": m }));
+        }
+    }
+    hits
+}
+
+/// "Italian" / "Итальянская кухня" → custom_cuisines code (default "other").
+fn cuisine_code(conn: &rusqlite::Connection, cuisine: &str) -> String {
+    let c = cuisine.to_lowercase();
+    if c.is_empty() { return "other".into(); }
+    const EN: &[(&str, &str)] = &[("kazakh", "kz"), ("russian", "ru"), ("italian", "it"), ("japanese", "jp"),
+        ("georgian", "ge"), ("turkish", "tr"), ("uzbek", "uz"), ("korean", "kr"), ("american", "us"), ("mexican", "mx")];
+    if let Some((_, code)) = EN.iter().find(|(k, _)| c.contains(k)) { return code.to_string(); }
+    let rows: Vec<(String, String)> = conn.prepare("SELECT code, name FROM custom_cuisines")
+        .and_then(|mut s| s.query_map([], |r| Ok((r.get(0)?, r.get(1)?))).map(|m| m.filter_map(|x| x.ok()).collect()))
+        .unwrap_or_default();
+    rows.into_iter()
+        .find(|(_, name)| { let st: String = name.to_lowercase().chars().take(6).collect(); st.chars().count() >= 4 && c.contains(&st) })
+        .map(|(code, _)| code)
+        .unwrap_or_else(|| "other".into())
+}
+
+fn insert_recipe(
+    conn: &rusqlite::Connection, r: &ParsedRecipe, items: &[MappedIngredient], source_url: &str,
+) -> Result<i64, String> {
+    let now = chrono::Local::now().to_rfc3339();
+    let ingredients_text = items.iter().map(|i| {
+        if i.unit == "по вкусу" { format!("{}: по вкусу", i.name) }
+        else if i.amount > 0.0 { format!("{}: {} {}", i.name, trim_num(i.amount), i.unit) }
+        else { i.name.clone() }
+    }).collect::<Vec<_>>().join("\n");
+    conn.execute(
+        "INSERT INTO recipes (name, description, ingredients, instructions, prep_time, cook_time, servings, calories, tags, difficulty, cuisine, image, source_url, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, 'easy', ?10, ?11, ?12, ?13, ?13)",
+        rusqlite::params![r.name, r.description, ingredients_text, r.instructions.join("\n"),
+            r.prep_time.unwrap_or(0), r.cook_time.unwrap_or(0), r.servings.unwrap_or(1).max(1),
+            r.calories.unwrap_or(0), r.tags.join(", "), cuisine_code(conn, &r.cuisine), r.image,
+            source_url, now],
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let id = conn.last_insert_rowid();
+    for i in items {
+        let _ = conn.execute(
+            "INSERT INTO recipe_ingredients (recipe_id, name, amount, unit, catalog_id, alternatives) VALUES (?1,?2,?3,?4,?5,'')",
+            rusqlite::params![id, i.name, i.amount, i.unit, i.catalog_id],
+        );
+    }
+    crate::nutrition::auto_recalc_after_edit(conn, id, r.calories.is_some());
+    crate::sync_share::mark_dirty(conn, "recipes");
+    crate::sync_share::mark_dirty(conn, "recipe_ingredients");
+    Ok(id)
+}
+
+fn trim_num(x: f64) -> String {
+    let s = format!("{:.2}", x);
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+async fn fetch_html(client: &reqwest::Client, url: &str) -> Result<String, String> {
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return Err("Нужна ссылка http(s)".into());
+    }
+    let resp = client.get(url)
+        .header("User-Agent", "Mozilla/5.0 (compatible; Hanni recipe import)")
+        .header("Accept", "text/html,application/xhtml+xml")
+        .timeout(std::time::Duration::from_secs(20))
+        .send().await
+        .map_err(|e| format!("Не удалось загрузить страницу: {}", e))?;
+    if !resp.status().is_success() {
+        return Err(format!("Страница вернула {}", resp.status()));
+    }
+    let body = resp.text().await.map_err(|e| format!("Не удалось прочитать страницу: {}", e))?;
+    // Recipe pages are small; don't hand megabytes of markup to the regexes.
+    Ok(body.chars().take(3_000_000).collect())
+}
+
+async fn llm_extract(app: &AppHandle, page_text: &str) -> Result<Option<ParsedRecipe>, String> {
+    let llm_state = app.state::<LlmBusy>();
+    let _permit = tokio::time::timeout(std::time::Duration::from_secs(60), llm_state.0.acquire()).await
+        .map_err(|_| "LLM busy — timeout".to_string())?
+        .map_err(|_| "LLM semaphore closed".to_string())?;
+    let client = &app.state::<HttpClient>().0;
+    let prompt = format!(
+        "Извлеки рецепт из текста страницы. Верни ТОЛЬКО JSON:\n\
+        {{\"name\": \"...\", \"description\": \"...\", \"servings\": 4, \"prep_time\": 15, \"cook_time\": 30, \
+        \"ingredients\": [{{\"name\": \"молоко\", \"amount\": 200, \"unit\": \"мл\"}}], \
+        \"instructions\": [\"шаг 1\", \"шаг 2\"], \"tags\": [\"завтрак\"]}}\n\
+        Время — в минутах. Единицы: г, кг, мл, л, шт, ст.л., ч.л., стакан, по вкусу. \
+        Если рецепта нет — верни {{\"name\": \"\"}}.\n\nТекст:\n{}\n/no_think",
+        page_text
+    );
+    let request = ChatRequest {
+        model: llm_model(),
+        messages: vec![
+            ChatMessage::text("system", "Ты извлекаешь структурированные рецепты из веб-страниц. Верни только валидный JSON."),
+            ChatMessage::text("user", &prompt),
+        ],
+        max_tokens: 2000,
+        stream: false,
+        temperature: 0.2,
+        repetition_penalty: None,
+        chat_template_kwargs: ChatTemplateKwargs { enable_thinking: false },
+        tools: None,
+    };
+    tokio::task::spawn_blocking(crate::mlx_manager::ensure_mlx).await.ok();
+    let response = client.post(llm_chat_url()).json(&request)
+        .timeout(std::time::Duration::from_secs(120))
+        .send().await.map_err(|e| format!("LLM error: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("LLM error {}", response.status()));
+    }
+    let parsed: NonStreamResponse = response.json().await.map_err(|e| format!("Parse error: {}", e))?;
+    let raw = parsed.choices.first().map(|c| c.message.content.clone()).unwrap_or_default();
+    let re = regex::Regex::new(r"(?s)<think>.*?</think>").unwrap();
+    let text = re.replace_all(&raw, "");
+    Ok(extract_json_object(&text).and_then(|v| recipe_from_llm_json(&v)))
+}
+
+/// Shared entry point for the command, the chat action and the headless agent.
+/// `allow_llm` is false inside the agent loop, which already holds the LLM permit.
+pub(crate) async fn import_recipe_inner(
+    app: &AppHandle, url: Option<String>, text: Option<String>,
+    dry_run: bool, force: bool, allow_llm: bool,
+) -> Result<serde_json::Value, String> {
+    let url = url.map(|u| u.trim().to_string()).filter(|u| !u.is_empty());
+    let text = text.filter(|t| !t.trim().is_empty());
+    if url.is_none() && text.is_none() { return Err("Нужна ссылка или текст рецепта".into()); }
+
+    if let Some(ref u) = url {
+        let db = app.state::<HanniDb>();
+        let conn = db.read();
+        let existing: Option<i64> = conn.query_row(
+            "SELECT id FROM recipes WHERE source_url=?1 LIMIT 1", rusqlite::params![u], |r| r.get(0),
+        ).ok();
+        if let Some(id) = existing {
+            return Ok(serde_json::json!({ "status": "duplicate", "recipe_id": id }));
+        }
+    }
+
+    let raw = match (&url, &text) {
+        (Some(u), _) => fetch_html(&app.state::<HttpClient>().0, u).await?,
+        (None, Some(t)) => t.clone(),
+        _ => unreachable!(),
+    };
+    let (mut parsed, mut source) = (extract_jsonld_recipes(&raw).into_iter().next(), "jsonld");
+    if parsed.is_none() {
+        // Pasted JSON-LD object without the <script> wrapper.
+        if let Ok(v) = serde_json::from_str::<serde_json::Value>(raw.trim()) {
+            let mut found = Vec::new();
+            collect_recipes(&v, &mut found);
+            parsed = found.into_iter().next();
+        }
+    }
+    let page_text = if url.is_some() { html_to_text(&raw, 12_000) } else { raw.chars().take(12_000).collect() };
+    if parsed.is_none() {
+        parsed = parse_sectioned_text(&page_text);
+        source = "text";
+    }
+    if parsed.is_none() && allow_llm {
+        parsed = llm_extract(app, &page_text).await?;
+        source = "llm";
+    }
+    let recipe = parsed.ok_or("Рецепт не найден на странице")?;
+
+    let db = app.state::<HanniDb>();
+    let conn = db.conn();
+    let items = map_ingredients(&conn, &recipe.ingredients);
+    let hits = blacklist_hits(&conn, &recipe, &items);
+    let hard = hits.iter().any(|h| h["level"] == "hard");
+    let preview = serde_json::json!({ "recipe": recipe, "ingredients": items, "blacklist": hits, "source": source });
+    if dry_run || (hard && !force) {
+        let mut out = preview;
+        out["status"] = serde_json::json!(if dry_run { "preview" } else { "blocked" });
+        return Ok(out);
+    }
+    let id = insert_recipe(&conn, &recipe, &items, url.as_deref().unwrap_or(""))?;
+    let mut out = preview;
+    out["status"] = serde_json::json!("created");
+    out["recipe_id"] = serde_json::json!(id);
+    Ok(out)
+}
+
+/// Import a recipe from `url` or pasted `text`. `dry_run` returns the parsed
+/// preview only; a hard food_blacklist hit blocks creation unless `force`.
+#[tauri::command]
+pub async fn import_recipe(
+    url: Option<String>, text: Option<String>, dry_run: Option<bool>, force: Option<bool>,
+    app: AppHandle,
+) -> Result<serde_json::Value, String> {
+    import_recipe_inner(&app, url, text, dry_run.unwrap_or(false), force.unwrap_or(false), true).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_jsonld_graph() {
+        let html = r#"<html><script type="application/ld+json">{"@context":"https://schema.org","@graph":[
+            {"@type":"WebPage","name":"x"},
+            {"@type":["Recipe"],"name":"Блины &amp; мёд","recipeYield":"4 порции","prepTime":"PT10M","cookTime":"PT1H5M",
+             "recipeIngredient":["500 мл молока","2 яйца","Соль по вкусу"],
+             "recipeInstructions":[{"@type":"HowToSection","itemListElement":[{"@type":"HowToStep","text":"Смешать"}]},"Жарить"],
+             "nutrition":{"calories":"210 kcal"},"image":{"url":"https://e/x.jpg"},"keywords":"завтрак, блины"}]}</script></html>"#;
+        let r = &extract_jsonld_recipes(html)[0];
+        assert_eq!(r.name, "Блины & мёд");
+        assert_eq!(r.servings, Some(4));
+        assert_eq!((r.prep_time, r.cook_time), (Some(10), Some(65)));
+        assert_eq!(r.instructions, vec!["Смешать", "Жарить"]);
+        assert_eq!(r.calories, Some(210));
+        assert_eq!(r.image, "https://e/x.jpg");
+        assert_eq!(r.tags, vec!["завтрак", "блины"]);
+    }
+
+    #[test]
+    fn parses_ingredient_lines() {
+        let p = parse_ingredient_line("500 мл молока");
+        assert_eq!((p.name.as_str(), p.amount, p.unit.as_str()), ("молока", 500.0, "мл"));
+        let p = parse_ingredient_line("Сахар — 2 ст. л.");
+        assert_eq!((p.name.as_str(), p.amount, p.unit.as_str()), ("Сахар", 2.0, "ст.л."));
+        let p = parse_ingredient_line("1/2 стакана муки");
+        assert_eq!((p.name.as_str(), p.amount, p.unit.as_str()), ("муки", 0.5, "стакан"));
+        let p = parse_ingredient_line("2-3 зубчика чеснока");
+        assert_eq!((p.name.as_str(), p.amount, p.unit.as_str()), ("чеснока", 2.5, "зубчик"));
+        let p = parse_ingredient_line("Соль, перец по вкусу");
+        assert_eq!((p.name.as_str(), p.unit.as_str()), ("Соль", "по вкусу"));
+        let p = parse_ingredient_line("Лук репчатый (крупный), нарезанный 1 шт");
+        assert_eq!((p.name.as_str(), p.amount, p.unit.as_str()), ("Лук репчатый", 1.0, "шт"));
+        let p = parse_ingredient_line("2 яйца");
+        assert_eq!((p.name.as_str(), p.amount, p.unit.as_str()), ("яйца", 2.0, "шт"));
+        let p = parse_ingredient_line("Горошек 200 г");
+        assert_eq!((p.name.as_str(), p.amount, p.unit.as_str()), ("Горошек", 200.0, "г"));
+        // Case is matched on the original text, even where lowercasing changes byte lengths.
+        assert_eq!(take_unit("ГР İzmir"), ("г".to_string(), "İzmir"));
+        assert_eq!(take_unit("Ⱥ г"), (String::new(), "Ⱥ г"));
+        let p = parse_ingredient_line("Ⱥpio ПО ВКУСУ");
+        assert_eq!((p.name.as_str(), p.unit.as_str()), ("Ⱥpio", "по вкусу"));
+    }
+
+    #[test]
+    fn maps_onto_catalog() {
+        let cat: Vec<(i64, String, String)> = vec![
+            (1, "молоко".into(), "dairy".into()), (2, "лук".into(), "veg".into()),
+            (3, "лук красный".into(), "veg".into()), (4, "куриное филе".into(), "meat".into()),
+            (5, "яйца куриные".into(), "dairy".into()), (6, "мука пшеничная".into(), "grain".into()),
+        ];
+        assert_eq!(match_catalog("молока", &cat).map(|c| c.0), Some(1));
+        assert_eq!(match_catalog("Лук репчатый", &cat).map(|c| c.0), Some(2));
+        assert_eq!(match_catalog("красный лук", &cat).map(|c| c.0), Some(3));
+        assert_eq!(match_catalog("куриного филе", &cat).map(|c| c.0), Some(4));
+        assert_eq!(match_catalog("шоколад", &cat), None);
+    }
+
+    #[test]
+    fn parses_sectioned_text() {
+        let t = "Омлет\nНа 2 порции\nИнгредиенты:\n3 яйца\n100 мл молока\nПриготовление:\n1. Взбить\n2. Жарить";
+        let r = parse_sectioned_text(t).unwrap();
+        assert_eq!(r.name, "Омлет");
+        assert_eq!(r.servings, Some(2));
+        assert_eq!(r.ingredients.len(), 2);
+        assert_eq!(r.instructions, vec!["Взбить", "Жарить"]);
+    }
+
+    #[test]
+    fn iso_durations() {
+        assert_eq!(iso_minutes("PT1H30M"), Some(90));
+        assert_eq!(iso_minutes("P0DT0H20M"), Some(20));
+        assert_eq!(iso_minutes("PT0S"), None);
+    }
+}
@@ -0,0 +1,418 @@
+// screen_limits.rs — Daily screen-time limits per app, site or activity
+// category, with a weekend variant. Usage is counted live from
+// activity_snapshots (one non-AFK snapshot = 0.5 min). Crossing a threshold
+// escalates once per day: 80% — notification, 100% — proactive message,
+// 120% — focus-mode block until midnight (opt-in per limit). Every step lands
+// in `screen_limit_log`, which also backs the history report.
+use crate::types::{FocusManager, HanniDb};
+use chrono::{Datelike, NaiveDate, TimeZone};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{Emitter, Manager};
+
+pub const KINDS: &[&str] = &["app", "site", "category"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level { Warn, Nudge, Block }
+
+impl Level {
+    pub fn as_str(self) -> &'static str {
+        match self { Level::Warn => "warn", Level::Nudge => "nudge", Level::Block => "block" }
+    }
+    /// Share of the limit at which the level fires.
+    fn threshold(self) -> f64 {
+        match self { Level::Warn => 0.8, Level::Nudge => 1.0, Level::Block => 1.2 }
+    }
+}
+
+const LEVELS: [Level; 3] = [Level::Warn, Level::Nudge, Level::Block];
+/// After a failed block (prompt cancelled) the next try waits this long.
+const BLOCK_RETRY_MIN: i64 = 15;
+/// limit id → earliest next block attempt.
+static BLOCK_RETRY: Mutex<Option<HashMap<i64, chrono::DateTime<chrono::Local>>>> = Mutex::new(None);
+
+#[derive(Debug, Clone)]
+pub struct Limit {
+    pub id: i64,
+    pub kind: String,
+    pub target: String,
+    pub weekday_minutes: i64,
+    pub weekend_minutes: Option<i64>,
+    pub block: bool,
+}
+
+/// One non-AFK snapshot, as much of it as accounting needs.
+#[derive(Debug, Clone)]
+pub struct Snap {
+    pub date: String,
+    pub app: String,
+    pub url: String,
+    pub title: String,
+    pub category: String,
+}
+
+/// The browser a URL was read from is the app in front. macOS reports the
+/// active tab of any running browser, even one in the background.
+fn browser_in_front(s: &Snap) -> bool {
+    !s.app.is_empty() && s.url.to_lowercase().starts_with(&s.app.to_lowercase())
+}
+
+impl Limit {
+    pub fn minutes_on(&self, date: NaiveDate) -> i64 {
+        if date.weekday().number_from_monday() >= 6 {
+            self.weekend_minutes.unwrap_or(self.weekday_minutes)
+        } else {
+            self.weekday_minutes
+        }
+    }
+
+    pub fn matches(&self, s: &Snap) -> bool {
+        match self.kind.as_str() {
+            "app" => crate::focus_enforcer::process_matches(&s.app, &self.target),
+            "site" => browser_in_front(s)
+                && crate::focus_enforcer::site_attempt(std::slice::from_ref(&self.target), &s.url, &s.title).is_some(),
+            _ => s.category == self.target,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self.kind.as_str() {
+            "category" => format!("категория «{}»", self.target),
+            _ => self.target.clone(),
+        }
+    }
+}
+
+pub fn used_minutes(limit: &Limit, snaps: &[Snap]) -> f64 {
+    snaps.iter().filter(|s| limit.matches(s)).count() as f64 * crate::activity_rules::SNAPSHOT_MIN
+}
+
+/// Levels reached at `used` of `limit` minutes. Blocking only when the limit opts in.
+pub fn reached(used: f64, limit: i64, block: bool) -> Vec<Level> {
+    if limit <= 0 { return Vec::new(); }
+    LEVELS.iter().copied()
+        .filter(|l| *l != Level::Block || block)
+        .filter(|l| used >= limit as f64 * l.threshold())
+        .collect()
+}
+
+/// Apps and sites to block for an exceeded limit. A category blocks what was
+/// seen in it: browser time by domain, everything else by app.
+pub fn block_targets(limit: &Limit, snaps: &[Snap]) -> (Vec<String>, Vec<String>) {
+    match limit.kind.as_str() {
+        "app" => (vec![limit.target.clone()], Vec::new()),
+        "site" => (Vec::new(), vec![limit.target.clone()]),
+        _ => {
+            let (mut apps, mut sites): (Vec<String>, Vec<String>) = (Vec::new(), Vec::new());
+            for s in snaps.iter().filter(|s| limit.matches(s)) {
+                if browser_in_front(s) {
+                    if let Some(d) = crate::activity_rules::domains_in(&s.url).into_iter().next() {
+                        if !sites.contains(&d) { sites.push(d); }
+                    }
+                } else if !s.app.is_empty() && !apps.contains(&s.app) {
+                    apps.push(s.app.clone());
+                }
+            }
+            (apps, sites)
+        }
+    }
+}
+
+fn load_limits(conn: &rusqlite::Connection, only_active: bool) -> Result<Vec<(Limit, bool)>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT id, kind, target, weekday_minutes, weekend_minutes, block, is_active FROM screen_limits
+         WHERE is_active=1 OR ?1=0 ORDER BY id"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let rows = stmt.query_map([only_active as i64], |r| Ok((Limit {
+        id: r.get(0)?, kind: r.get(1)?, target: r.get(2)?, weekday_minutes: r.get(3)?,
+        weekend_minutes: r.get(4)?, block: r.get::<_, i64>(5)? != 0,
+    }, r.get::<_, i64>(6)? != 0))).map_err(|e| format!("Query error: {}", e))?.flatten().collect();
+    Ok(rows)
+}
+
+/// Non-AFK snapshots captured on `from..=to` (local dates).
+fn load_snaps(conn: &rusqlite::Connection, from: NaiveDate, to: NaiveDate) -> Result<Vec<Snap>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT substr(captured_at, 1, 10), frontmost_app, browser_url, COALESCE(window_title, ''), COALESCE(category, 'other')
+         FROM activity_snapshots WHERE captured_at >= ?1 AND captured_at < ?2 AND COALESCE(category, 'other') != 'afk'"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let end = (to + chrono::Duration::days(1)).format("%Y-%m-%d").to_string();
+    let rows = stmt.query_map([from.format("%Y-%m-%d").to_string(), end], |r| Ok(Snap {
+        date: r.get(0)?, app: r.get(1)?, url: r.get(2)?, title: r.get(3)?, category: r.get(4)?,
+    })).map_err(|e| format!("Query error: {}", e))?.flatten().collect();
+    Ok(rows)
+}
+
+/// Block the limit's targets until midnight; `true` once they are blocked.
+/// A running manual session gains the apps (its hosts block stays as it is),
+/// but counts as the block only if that covers every target until midnight —
+/// otherwise the block is tried again and starts on its own once the session
+/// is over. A
+/// scheduled session is left alone: the scheduler would restart it on any
+/// change of its targets.
+fn block(handle: &tauri::AppHandle, limit: &Limit, snaps: &[Snap], date: NaiveDate) -> bool {
+    let (apps, sites) = block_targets(limit, snaps);
+    if apps.is_empty() && sites.is_empty() { return false; }
+    let now = chrono::Local::now();
+    let mut guard = BLOCK_RETRY.lock().unwrap_or_else(|e| e.into_inner());
+    let retry = guard.get_or_insert_with(HashMap::new);
+    if retry.get(&limit.id).is_some_and(|at| now < *at) { return false; }
+    let midnight = (date + chrono::Duration::days(1)).and_hms_opt(0, 0, 0)
+        .and_then(|t| chrono::Local.from_local_datetime(&t).earliest());
+    let focus = handle.state::<FocusManager>();
+    {
+        let mut state = focus.0.lock().unwrap_or_else(|e| e.into_inner());
+        if state.active {
+            if state.scheduled { return false; }
+            for app in &apps {
+                if !state.blocked_apps.contains(app) { state.blocked_apps.push(app.clone()); }
+            }
+            return sites.is_empty() && state.end_time.zip(midnight).is_some_and(|(end, m)| end >= m);
+        }
+    }
+    match crate::focus_enforcer::start(&focus, apps, sites, midnight, false) {
+        Ok(started) => {
+            if started { let _ = handle.emit("focus-started", "screen_limit"); }
+            started
+        }
+        Err(e) => {
+            eprintln!("[limits] block failed: {}", e);
+            retry.insert(limit.id, now + chrono::Duration::minutes(BLOCK_RETRY_MIN));
+            false
+        }
+    }
+}
+
+/// Background step (every minute, blocking): count today's usage and act on
+/// levels crossed since the last step — only the highest new one, so a late
+/// first check doesn't fire three nudges at once. Returns proactive triggers.
+pub fn tick(handle: &tauri::AppHandle) -> Vec<String> {
+    let db = handle.state::<HanniDb>();
+    let today = chrono::Local::now().date_naive();
+    let date = today.format("%Y-%m-%d").to_string();
+    let (limits, snaps) = {
+        let conn = db.read();
+        match (load_limits(&conn, true), load_snaps(&conn, today, today)) {
+            (Ok(l), Ok(s)) => (l, s),
+            (Err(e), _) | (_, Err(e)) => { eprintln!("[limits] {}", e); return Vec::new(); }
+        }
+    };
+    let now = chrono::Local::now().to_rfc3339();
+    let mut triggers = Vec::new();
+    for (l, _) in limits {
+        let limit = l.minutes_on(today);
+        let used = used_minutes(&l, &snaps);
+        let logged = |level: Level| db.read().query_row(
+            "SELECT COUNT(*) FROM screen_limit_log WHERE limit_id=?1 AND date=?2 AND level=?3",
+            rusqlite::params![l.id, date, level.as_str()], |r| r.get::<_, i64>(0),
+        ).unwrap_or(0) > 0;
+        let pending: Vec<Level> = reached(used, limit, l.block).into_iter().filter(|level| !logged(*level)).collect();
+        // The block level is logged (and announced) only once the block took
+        let blocked = pending.contains(&Level::Block) && block(handle, &l, &snaps, today);
+        let fresh: Vec<Level> = pending.into_iter().filter(|level| {
+            (*level != Level::Block || blocked) && db.conn().execute(
+                "INSERT OR IGNORE INTO screen_limit_log (limit_id, date, level, used_minutes, limit_minutes, at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                rusqlite::params![l.id, date, level.as_str(), used, limit, now],
+            ).unwrap_or(0) == 1
+        }).collect();
+        let Some(level) = fresh.into_iter().max() else { continue };
+        let label = l.label();
+        match level {
+            Level::Warn => crate::pomodoro::notify("Лимит экранного времени",
+                &format!("{}: {:.0} из {} мин — осталось {:.0} мин", label, used, limit, limit as f64 - used)),
+            Level::Nudge => {
+                crate::pomodoro::notify("Лимит исчерпан", &format!("{}: {:.0} мин при лимите {} мин", label, used, limit));
+                triggers.push(format!("Лимит экранного времени: {} — {:.0} мин при лимите {} мин", label, used, limit));
+            }
+            Level::Block => crate::pomodoro::notify("Лимит: блокировка", &format!("{} заблокировано до конца дня", label)),
+        }
+        let _ = handle.emit("screen-limit", serde_json::json!({ "id": l.id, "level": level.as_str(), "used_minutes": used, "limit_minutes": limit }));
+    }
+    triggers
+}
+
+fn validate(kind: &str, target: &str, weekday_minutes: i64, weekend_minutes: Option<i64>) -> Result<String, String> {
+    if !KINDS.contains(&kind) { return Err(format!("Неизвестный тип лимита: {}", kind)); }
+    let target = match kind {
+        "site" => crate::focus_enforcer::safe_site(&target.trim().to_lowercase()).trim_start_matches("www.").to_string(),
+        _ => target.trim().to_string(),
+    };
+    if target.is_empty() || target == "afk" { return Err("Укажите приложение, сайт или категорию".into()); }
+    if kind == "app" && !crate::focus_enforcer::valid_app_name(&target) {
+        return Err("Название приложения слишком короткое — минимум 3 символа".into());
+    }
+    if !(1..=1440).contains(&weekday_minutes) || weekend_minutes.is_some_and(|m| !(1..=1440).contains(&m)) {
+        return Err("Лимит должен быть от 1 до 1440 минут".into());
+    }
+    Ok(target)
+}
+
+// ── Commands ──
+
+/// Limits with today's live usage and the highest level already reached.
+#[tauri::command]
+pub fn get_screen_limits(db: tauri::State<'_, HanniDb>) -> Result<Vec<serde_json::Value>, String> {
+    let today = chrono::Local::now().date_naive();
+    let conn = db.read();
+    let limits = load_limits(&conn, false)?;
+    let snaps = load_snaps(&conn, today, today)?;
+    let mut stmt = conn.prepare("SELECT limit_id, level FROM screen_limit_log WHERE date=?1")
+        .map_err(|e| format!("DB error: {}", e))?;
+    let mut levels: HashMap<i64, Vec<String>> = HashMap::new();
+    for (id, level) in stmt.query_map([today.format("%Y-%m-%d").to_string()], |r| Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?)))
+        .map_err(|e| format!("Query error: {}", e))?.flatten() {
+        levels.entry(id).or_default().push(level);
+    }
+    Ok(limits.into_iter().map(|(l, active)| {
+        let limit = l.minutes_on(today);
+        let used = used_minutes(&l, &snaps);
+        let done = levels.get(&l.id).cloned().unwrap_or_default();
+        let level = LEVELS.iter().rev().find(|lv| done.iter().any(|d| d == lv.as_str())).map(|lv| lv.as_str());
+        serde_json::json!({
+            "id": l.id, "kind": l.kind, "target": l.target, "label": l.label(),
+            "weekday_minutes": l.weekday_minutes, "weekend_minutes": l.weekend_minutes,
+            "block": l.block, "is_active": active,
+            "today_limit": limit, "used_minutes": used,
+            "percent": if limit > 0 { (used / limit as f64 * 100.0).round() } else { 0.0 },
+            "level": level,
+        })
+    }).collect())
+}
+
+#[tauri::command]
+pub fn create_screen_limit(
+    kind: String, target: String, weekday_minutes: i64, weekend_minutes: Option<i64>, block: bool,
+    db: tauri::State<'_, HanniDb>,
+) -> Result<i64, String> {
+    let target = validate(&kind, &target, weekday_minutes, weekend_minutes)?;
+    let conn = db.conn();
+    conn.execute(
+        "INSERT INTO screen_limits (kind, target, weekday_minutes, weekend_minutes, block, is_active, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, 1, ?6)",
+        rusqlite::params![kind, target, weekday_minutes, weekend_minutes, block as i64, chrono::Local::now().to_rfc3339()],
+    ).map_err(|e| format!("DB error: {}", e))?;
+    Ok(conn.last_insert_rowid())
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn update_screen_limit(
+    id: i64, kind: String, target: String, weekday_minutes: i64, weekend_minutes: Option<i64>,
+    block: bool, is_active: bool,
+    db: tauri::State<'_, HanniDb>,
+) -> Result<(), String> {
+    let target = validate(&kind, &target, weekday_minutes, weekend_minutes)?;
+    let n = db.conn().execute(
+        "UPDATE screen_limits SET kind=?1, target=?2, weekday_minutes=?3, weekend_minutes=?4, block=?5, is_active=?6 WHERE id=?7",
+        rusqlite::params![kind, target, weekday_minutes, weekend_minutes, block as i64, is_active as i64, id],
+    ).map_err(|e| format!("DB error: {}", e))?;
+    if n == 0 { return Err("Лимит не найден".into()); }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn delete_screen_limit(id: i64, db: tauri::State<'_, HanniDb>) -> Result<(), String> {
+    let conn = db.conn();
+    conn.execute("DELETE FROM screen_limit_log WHERE limit_id=?1", [id]).map_err(|e| format!("DB error: {}", e))?;
+    conn.execute("DELETE FROM screen_limits WHERE id=?1", [id]).map_err(|e| format!("DB error: {}", e))?;
+    Ok(())
+}
+
+/// Per limit and day over the last `days` (snapshots are kept 30 days): usage,
+/// that day's limit and the highest level reached, plus exceeded-day totals.
+#[tauri::command]
+pub fn get_screen_limit_history(days: Option<i64>, db: tauri::State<'_, HanniDb>) -> Result<Vec<serde_json::Value>, String> {
+    let days = days.unwrap_or(14).clamp(1, 30);
+    let today = chrono::Local::now().date_naive();
+    let from = today - chrono::Duration::days(days - 1);
+    let conn = db.read();
+    let limits = load_limits(&conn, false)?;
+    let snaps = load_snaps(&conn, from, today)?;
+    let mut by_day: HashMap<&str, Vec<Snap>> = HashMap::new();
+    for s in &snaps { by_day.entry(s.date.as_str()).or_default().push(s.clone()); }
+    let mut stmt = conn.prepare("SELECT limit_id, date, level FROM screen_limit_log WHERE date >= ?1")
+        .map_err(|e| format!("DB error: {}", e))?;
+    let mut logged: HashMap<(i64, String), Vec<String>> = HashMap::new();
+    for (id, date, level) in stmt.query_map([from.format("%Y-%m-%d").to_string()], |r| Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?, r.get::<_, String>(2)?)))
+        .map_err(|e| format!("Query error: {}", e))?.flatten() {
+        logged.entry((id, date)).or_default().push(level);
+    }
+    Ok(limits.into_iter().map(|(l, _)| {
+        let (mut exceeded, mut tracked, mut total) = (0, 0, 0.0);
+        let day_rows: Vec<serde_json::Value> = (0..days).map(|i| {
+            let d = from + chrono::Duration::days(i);
+            let ds = d.format("%Y-%m-%d").to_string();
+            let used = by_day.get(ds.as_str()).map(|s| used_minutes(&l, s)).unwrap_or(0.0);
+            let limit = l.minutes_on(d);
+            if by_day.contains_key(ds.as_str()) { tracked += 1; total += used; }
+            if used > limit as f64 { exceeded += 1; }
+            let done = logged.get(&(l.id, ds.clone())).cloned().unwrap_or_default();
+            let level = LEVELS.iter().rev().find(|lv| done.iter().any(|x| x == lv.as_str())).map(|lv| lv.as_str());
+            serde_json::json!({ "date": ds, "used_minutes": used, "limit_minutes": limit, "level": level })
+        }).collect();
+        serde_json::json!({
+            "id": l.id, "label": l.label(), "kind": l.kind,
+            "days": day_rows, "exceeded_days": exceeded, "tracked_days": tracked,
+            "avg_minutes": if tracked > 0 { (total / tracked as f64).round() } else { 0.0 },
+        })
+    }).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limit(kind: &str, target: &str) -> Limit {
+        Limit { id: 1, kind: kind.into(), target: target.into(), weekday_minutes: 60, weekend_minutes: Some(120), block: false }
+    }
+
+    fn snap(app: &str, url: &str, category: &str) -> Snap {
+        Snap { date: "2026-03-02".into(), app: app.into(), url: url.into(), title: String::new(), category: category.into() }
+    }
+
+    #[test]
+    fn weekend_variant_and_matching() {
+        let l = limit("site", "youtube.com");
+        assert_eq!(l.minutes_on(NaiveDate::from_ymd_opt(2026, 3, 6).unwrap()), 60); // Friday
+        assert_eq!(l.minutes_on(NaiveDate::from_ymd_opt(2026, 3, 7).unwrap()), 120);
+        let snaps = [
+            snap("Google Chrome", "Google Chrome: https://m.youtube.com/watch?v=1 | cats", "media"),
+            // Chrome in the background still reports its tab — not YouTube time.
+            snap("Telegram", "Google Chrome: https://m.youtube.com/watch?v=1 | cats", "social"),
+            snap("Safari", "Safari: https://github.com/ | GitHub", "coding"),
+        ];
+        assert_eq!(used_minutes(&l, &snaps), 0.5);
+        assert_eq!(used_minutes(&limit("app", "telegram"), &snaps), 0.5);
+        assert_eq!(used_minutes(&limit("category", "social"), &snaps), 0.5);
+        // App names match whole, as the enforcer kills them: "Code" isn't Xcode.
+        let ide = [snap("Xcode", "", "coding"), snap("Code", "", "coding")];
+        assert_eq!(used_minutes(&limit("app", "code"), &ide), 0.5);
+        assert!(validate("app", " vi ", 30, None).is_err());
+        assert_eq!(validate("app", " Telegram ", 30, None), Ok("Telegram".to_string()));
+    }
+
+    #[test]
+    fn escalation_levels() {
+        assert!(reached(47.5, 60, true).is_empty());
+        assert_eq!(reached(48.0, 60, true), vec![Level::Warn]);
+        assert_eq!(reached(60.0, 60, true), vec![Level::Warn, Level::Nudge]);
+        assert_eq!(reached(72.0, 60, true), vec![Level::Warn, Level::Nudge, Level::Block]);
+        // Blocking is opt-in.
+        assert_eq!(reached(200.0, 60, false), vec![Level::Warn, Level::Nudge]);
+        assert!(reached(10.0, 0, true).is_empty());
+    }
+
+    #[test]
+    fn category_block_targets() {
+        let l = limit("category", "social");
+        let snaps = [
+            snap("Google Chrome", "Google Chrome: https://www.instagram.com/ | Instagram", "social"),
+            snap("Google Chrome", "Google Chrome: https://instagram.com/p/1 | Instagram", "social"),
+            snap("Telegram", "Google Chrome: https://instagram.com/ | Instagram", "social"),
+            snap("Slack", "", "communication"),
+        ];
+        assert_eq!(block_targets(&l, &snaps), (vec!["Telegram".to_string()], vec!["instagram.com".to_string()]));
+        assert_eq!(block_targets(&limit("app", "Telegram"), &snaps), (vec!["Telegram".to_string()], vec![]));
+    }
+}
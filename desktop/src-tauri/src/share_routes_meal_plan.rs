@@ -159,6 +159,7 @@ pub async fn create_meal_plan(
         }).to_string(), &ip, &ua);
 
     crate::sync_share::mark_dirty(&conn, "meal_plan");
+    crate::commands_shopping::refresh_plan_shopping(&conn, &req.date);
 
     Ok(Json(serde_json::json!({ "status": "ok", "id": meal_id })))
 }
@@ -178,6 +179,7 @@ pub async fn delete_meal_plan(
     require_perm(&ctx, "delete")?;
     check_food_meal_scope(&ctx)?;
 
+    let date: Option<String> = conn.query_row("SELECT date FROM meal_plan WHERE id=?1", rusqlite::params![id], |r| r.get(0)).ok();
     let changed = conn.execute("DELETE FROM meal_plan WHERE id=?1", rusqlite::params![id])
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
     if changed == 0 {
@@ -186,5 +188,6 @@ pub async fn delete_meal_plan(
     log_activity(&conn, ctx.id, "delete_meal",
         &serde_json::json!({ "meal_id": id }).to_string(), &ip, &ua);
     crate::sync_share::mark_dirty(&conn, "meal_plan");
+    if let Some(d) = date { crate::commands_shopping::refresh_plan_shopping(&conn, &d); }
     Ok(Json(serde_json::json!({ "status": "ok" })))
 }
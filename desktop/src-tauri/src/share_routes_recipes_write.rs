@@ -236,6 +236,9 @@ pub async fn update_recipe(
         }
         crate::sync_share::mark_dirty(&conn, "recipe_ingredients");
     }
+    if req.ingredient_items.is_some() || req.servings.is_some() {
+        crate::commands_shopping::refresh_plan_shopping_for_recipe(&conn, id);
+    }
     let author_tag = sanitize_author(req.author.as_deref(), "guest");
     log_activity(&conn, ctx.id, "edit_recipe",
         &serde_json::json!({ "recipe_id": id, "author": author_tag }).to_string(),
@@ -1,5 +1,6 @@
 // sleep_analysis.rs — "Why am I sleepy?" analytics based on sleep data
 use crate::types::HanniDb;
+use chrono::Timelike;
 use serde::Serialize;
 use tauri::State;
 
@@ -12,6 +13,13 @@ pub struct SleepAnalysis {
     pub sleep_quality_trend: String,
     pub sleep_debt_hours: f64,
     pub consistency_minutes: i64,
+    /// Averages over nights that have stage data.
+    pub avg_efficiency: Option<f64>,
+    pub avg_latency_minutes: Option<f64>,
+    pub avg_wakeups: Option<f64>,
+    pub avg_deep_minutes: Option<f64>,
+    pub avg_rem_minutes: Option<f64>,
+    pub correlations: Vec<Correlation>,
     pub recommendations: Vec<String>,
 }
 
@@ -33,7 +41,9 @@ pub fn get_sleep_analysis(db: State<'_, HanniDb>, days: i64) -> SleepAnalysis {
             optimal_bedtime: "—".into(), current_avg_bedtime: "—".into(),
             bedtime_deviation_minutes: 0, avg_sleep_quality: 0.0,
             sleep_quality_trend: "unknown".into(), sleep_debt_hours: 0.0,
-            consistency_minutes: 0, recommendations: vec!["Нет данных о сне. Синхронизируйте Samsung Health.".into()],
+            consistency_minutes: 0, avg_efficiency: None, avg_latency_minutes: None, avg_wakeups: None,
+            avg_deep_minutes: None, avg_rem_minutes: None, correlations: Vec::new(),
+            recommendations: vec!["Нет данных о сне. Синхронизируйте Samsung Health.".into()],
         };
     }
 
@@ -64,15 +74,29 @@ pub fn get_sleep_analysis(db: State<'_, HanniDb>, days: i64) -> SleepAnalysis {
     // Deviation from optimal
     let deviation = avg_bed as i64 - best_bucket;
 
-    // Sleep quality: avg duration score (target 480 min = 8h)
+    // Sleep quality: average sleep score (see `sleep_score`)
     let avg_dur = durations.iter().sum::<i64>() as f64 / durations.len() as f64;
-    let quality = (avg_dur / 480.0 * 100.0).min(100.0);
+    let nights = nights_with_factors(&conn, &since);
+    let scores: Vec<i64> = nights.iter().map(|n| n.metrics.score).collect();
+    let quality = if scores.is_empty() { 0.0 } else { scores.iter().sum::<i64>() as f64 / scores.len() as f64 };
 
-    // Trend: compare first half vs second half
-    let mid = durations.len() / 2;
-    let first_avg = if mid > 0 { durations[..mid].iter().sum::<i64>() as f64 / mid as f64 } else { 0.0 };
-    let second_avg = if mid > 0 { durations[mid..].iter().sum::<i64>() as f64 / (durations.len() - mid) as f64 } else { 0.0 };
-    let trend = if second_avg > first_avg + 10.0 { "improving" } else if second_avg < first_avg - 10.0 { "declining" } else { "stable" };
+    // Trend: compare first half vs second half of the scores
+    let mid = scores.len() / 2;
+    let half_avg = |v: &[i64]| if v.is_empty() { 0.0 } else { v.iter().sum::<i64>() as f64 / v.len() as f64 };
+    let (first_avg, second_avg) = if mid > 0 { (half_avg(&scores[..mid]), half_avg(&scores[mid..])) } else { (0.0, 0.0) };
+    let trend = if second_avg > first_avg + 5.0 { "improving" } else if second_avg < first_avg - 5.0 { "declining" } else { "stable" };
+
+    // Stage-based averages
+    let staged: Vec<&NightMetrics> = nights.iter().map(|n| &n.metrics).filter(|m| m.has_stages).collect();
+    let avg_of = |f: &dyn Fn(&NightMetrics) -> f64| if staged.is_empty() { None } else {
+        Some((staged.iter().map(|m| f(m)).sum::<f64>() / staged.len() as f64 * 10.0).round() / 10.0)
+    };
+    let avg_efficiency = avg_of(&|m| m.efficiency.unwrap_or(0.0));
+    let avg_latency = avg_of(&|m| m.latency_minutes.unwrap_or(0) as f64);
+    let avg_wakeups = avg_of(&|m| m.wakeups as f64);
+    let avg_deep = avg_of(&|m| m.deep_minutes as f64);
+    let avg_rem = avg_of(&|m| m.rem_minutes as f64);
+    let correlations = correlate(&nights.iter().map(|n| (n.metrics.score, n.factors.clone())).collect::<Vec<_>>());
 
     // Sleep debt: cumulative deficit from 8h target
     let total_debt: f64 = durations.iter().map(|d| (480.0 - *d as f64).max(0.0)).sum::<f64>() / 60.0;
@@ -91,6 +115,15 @@ pub fn get_sleep_analysis(db: State<'_, HanniDb>, days: i64) -> SleepAnalysis {
     if total_debt > 5.0 {
         recs.push(format!("Сонный долг {:.0}ч за {} дней. Нужно компенсировать", total_debt, days));
     }
+    if avg_efficiency.is_some_and(|e| e < 85.0) {
+        recs.push(format!("Эффективность сна {:.0}% — много времени в постели без сна", avg_efficiency.unwrap_or(0.0)));
+    }
+    if avg_latency.is_some_and(|l| l > 30.0) {
+        recs.push(format!("Засыпание занимает в среднем {:.0} мин", avg_latency.unwrap_or(0.0)));
+    }
+    for c in correlations.iter().filter(|c| c.diff <= -5.0) {
+        recs.push(format!("{}: оценка сна ниже на {:.0} баллов ({:.0} против {:.0})", c.label, -c.diff, c.avg_with, c.avg_without));
+    }
     if recs.is_empty() {
         recs.push("Ваш сон в норме! Продолжайте в том же режиме.".into());
     }
@@ -98,7 +131,9 @@ pub fn get_sleep_analysis(db: State<'_, HanniDb>, days: i64) -> SleepAnalysis {
     SleepAnalysis {
         optimal_bedtime, current_avg_bedtime, bedtime_deviation_minutes: deviation,
         avg_sleep_quality: quality, sleep_quality_trend: trend.into(),
-        sleep_debt_hours: total_debt, consistency_minutes: consistency, recommendations: recs,
+        sleep_debt_hours: total_debt, consistency_minutes: consistency,
+        avg_efficiency, avg_latency_minutes: avg_latency, avg_wakeups,
+        avg_deep_minutes: avg_deep, avg_rem_minutes: avg_rem, correlations, recommendations: recs,
     }
 }
 
@@ -118,3 +153,336 @@ fn min_to_time(min: i64) -> String {
     let m = if min < 0 { min + 1440 } else if min >= 1440 { min - 1440 } else { min };
     format!("{:02}:{:02}", m / 60, m % 60)
 }
+
+// ── Per-night stage metrics & sleep score ──
+
+const ASLEEP_STAGES: &[&str] = &["light", "deep", "rem", "sleeping"];
+const AWAKE_STAGES: &[&str] = &["awake", "out_of_bed"];
+/// Asleep time that earns the full duration component.
+const TARGET_ASLEEP_MINUTES: f64 = 450.0;
+/// Food names counted as caffeine (lowercase whole words, so "tea" skips "steak").
+const CAFFEINE_WORDS: &[&str] = &[
+    "кофе", "coffee", "эспрессо", "espresso", "латте", "latte", "капучино", "cappuccino",
+    "американо", "americano", "раф", "флэт уайт", "flat white", "мокко", "mocha",
+    "энергетик", "energy drink", "red bull", "кола", "cola", "матча", "matcha", "чай", "tea",
+];
+/// Stems that veto a match (lowercase substrings).
+const NOT_CAFFEINE_WORDS: &[&str] = &["травян", "ромаш", "каркаде", "ройбуш", "herbal", "decaf", "без кофеина"];
+/// Caffeine logged at or after this hour counts as "late".
+const LATE_CAFFEINE_HOUR: u32 = 14;
+/// Screen time this long before bed, and the threshold for "a lot".
+const PRE_BED_WINDOW_MINUTES: i64 = 120;
+const LATE_SCREEN_MINUTES: f64 = 30.0;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct NightMetrics {
+    pub session_id: String,
+    pub date: String,
+    pub in_bed_minutes: i64,
+    pub asleep_minutes: i64,
+    pub deep_minutes: i64,
+    pub rem_minutes: i64,
+    pub light_minutes: i64,
+    pub awake_minutes: i64,
+    /// Asleep / in bed, %. None without stage data.
+    pub efficiency: Option<f64>,
+    /// Minutes from getting into bed to the first asleep stage.
+    pub latency_minutes: Option<i64>,
+    /// Awake spells between the first and the last asleep stage.
+    pub wakeups: i64,
+    pub has_stages: bool,
+    pub score: i64,
+}
+
+/// "HH:MM" or ISO timestamp → minutes from midnight (local wall clock).
+fn clock_min(t: &str) -> Option<i64> {
+    let hm = if t.len() >= 16 && t.as_bytes().get(10) == Some(&b'T') {
+        let local = chrono::DateTime::parse_from_rfc3339(t).ok()
+            .map(|d| d.with_timezone(&chrono::Local).format("%H:%M").to_string());
+        local.unwrap_or_else(|| t[11..16].to_string())
+    } else {
+        t.get(..5)?.to_string()
+    };
+    let (h, m) = hm.split_once(':')?;
+    Some(h.parse::<i64>().ok()? * 60 + m.parse::<i64>().ok()?)
+}
+
+/// Totals, latency and wake-ups from raw stages. Stage times are wall-clock
+/// "HH:MM", so they are placed relative to the session start (mod 24 h).
+pub(crate) fn night_metrics(start_time: &str, in_bed: i64, stages: &[(String, String, String)]) -> NightMetrics {
+    let mut m = NightMetrics { in_bed_minutes: in_bed, asleep_minutes: in_bed, ..Default::default() };
+    let Some(start) = clock_min(start_time) else { return m };
+    let mut spans: Vec<(i64, i64, &str)> = stages.iter().filter_map(|(s, e, stage)| {
+        let from = (clock_min(s)? - start).rem_euclid(1440);
+        let len = (clock_min(e)? - clock_min(s)?).rem_euclid(1440);
+        Some((from, from + len, stage.as_str()))
+    }).collect();
+    spans.sort_by_key(|s| s.0);
+    let asleep: Vec<&(i64, i64, &str)> = spans.iter().filter(|s| ASLEEP_STAGES.contains(&s.2)).collect();
+    let (Some(first), Some(last_end)) = (asleep.first(), asleep.iter().map(|s| s.1).max()) else { return m };
+
+    for &(from, to, stage) in &spans {
+        let len = to - from;
+        match stage {
+            "deep" => m.deep_minutes += len,
+            "rem" => m.rem_minutes += len,
+            "light" | "sleeping" => m.light_minutes += len,
+            s if AWAKE_STAGES.contains(&s) => m.awake_minutes += len,
+            _ => {}
+        }
+    }
+    m.has_stages = true;
+    m.asleep_minutes = (m.deep_minutes + m.rem_minutes + m.light_minutes).min(in_bed.max(1));
+    m.efficiency = Some((m.asleep_minutes as f64 / in_bed.max(1) as f64 * 1000.0).round() / 10.0);
+    m.latency_minutes = Some(first.0);
+    // Count awake spells strictly inside the sleep; touching spans merge.
+    let mut spell_end = i64::MIN;
+    for &(from, to, stage) in &spans {
+        if !AWAKE_STAGES.contains(&stage) || from < first.0 || to > last_end { continue; }
+        if from > spell_end { m.wakeups += 1; }
+        spell_end = spell_end.max(to);
+    }
+    m
+}
+
+/// Sleep score, 0–100:
+/// - duration — 40 pts, linear up to 7.5 h asleep;
+/// - efficiency — 20 pts, linear from 75 % (0) to 95 % (full);
+/// - deep — 15 pts, full at ≥ 15 % of sleep;
+/// - REM — 15 pts, full at ≥ 20 % of sleep;
+/// - continuity — 10 pts, −2 per wake-up after the first, −3 if falling
+///   asleep took over 30 min.
+///
+/// Without stage data only duration is known: it is scaled to 80 so an
+/// unverified night never outranks a good measured one.
+pub(crate) fn sleep_score(m: &NightMetrics) -> i64 {
+    let asleep = m.asleep_minutes as f64;
+    let duration = (asleep / TARGET_ASLEEP_MINUTES).clamp(0.0, 1.0);
+    if !m.has_stages {
+        return (duration * 80.0).round() as i64;
+    }
+    let eff = ((m.efficiency.unwrap_or(0.0) - 75.0) / 20.0).clamp(0.0, 1.0);
+    let share = |v: i64, target: f64| if asleep > 0.0 { (v as f64 / asleep / target).min(1.0) } else { 0.0 };
+    let latency_penalty = if m.latency_minutes.unwrap_or(0) > 30 { 3 } else { 0 };
+    let continuity = (10 - 2 * (m.wakeups - 1).max(0) - latency_penalty).max(0) as f64;
+    (duration * 40.0 + eff * 20.0 + share(m.deep_minutes, 0.15) * 15.0 + share(m.rem_minutes, 0.20) * 15.0 + continuity)
+        .round() as i64
+}
+
+/// Recompute metrics for sessions dated `since` or later and store the score
+/// in `quality_score`. Manual entries keep a score the user typed in.
+pub(crate) fn score_sessions(conn: &rusqlite::Connection, since: &str) -> Vec<NightMetrics> {
+    let Ok(mut stmt) = conn.prepare(
+        "SELECT id, date, start_time, duration_minutes, source, quality_score FROM sleep_sessions WHERE date >= ?1 ORDER BY date"
+    ) else { return Vec::new() };
+    let sessions: Vec<(String, String, String, i64, String, Option<i64>)> = stmt.query_map([since], |r| {
+        Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?, r.get(5)?))
+    }).map(|rows| rows.filter_map(|r| r.ok()).collect()).unwrap_or_default();
+    let Ok(mut stage_stmt) = conn.prepare("SELECT start_time, end_time, stage FROM sleep_stages WHERE session_id=?1") else { return Vec::new() };
+
+    sessions.into_iter().map(|(id, date, start, dur, source, stored)| {
+        let stages: Vec<(String, String, String)> = stage_stmt.query_map([&id], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))
+            .map(|rows| rows.filter_map(|r| r.ok()).collect()).unwrap_or_default();
+        let mut m = night_metrics(&start, dur, &stages);
+        m.score = sleep_score(&m);
+        if source == "manual" && stored.is_some() {
+            m.score = stored.unwrap_or(m.score);
+        } else if stored != Some(m.score) {
+            conn.execute("UPDATE sleep_sessions SET quality_score=?1 WHERE id=?2", rusqlite::params![m.score, id]).ok();
+        }
+        m.session_id = id;
+        m.date = date;
+        m
+    }).collect()
+}
+
+// ── What the day before did to the night ──
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct NightFactors {
+    pub caffeine: i64,
+    pub late_caffeine: bool,
+    pub workout_minutes: i64,
+    /// Active screen minutes in the two hours before bed; None when the
+    /// desktop tracker has nothing for that day.
+    pub pre_bed_screen_minutes: Option<f64>,
+}
+
+fn is_caffeine(name: &str) -> bool {
+    let n = name.to_lowercase();
+    CAFFEINE_WORDS.iter().any(|w| contains_word(&n, w)) && !NOT_CAFFEINE_WORDS.iter().any(|w| n.contains(w))
+}
+
+/// `word` occurs in `hay` with no letter or digit right before or after it.
+fn contains_word(hay: &str, word: &str) -> bool {
+    hay.match_indices(word).any(|(i, _)| {
+        let before = hay[..i].chars().next_back();
+        let after = hay[i + word.len()..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+fn night_factors(conn: &rusqlite::Connection, date: &str, start_time: &str, in_bed: i64) -> NightFactors {
+    let mut f = NightFactors::default();
+    if let Ok(mut stmt) = conn.prepare("SELECT name, created_at FROM food_log WHERE date=?1") {
+        let rows: Vec<(String, String)> = stmt.query_map([date], |r| Ok((r.get(0)?, r.get(1)?)))
+            .map(|rows| rows.filter_map(|r| r.ok()).collect()).unwrap_or_default();
+        for (_, created) in rows.iter().filter(|(n, _)| is_caffeine(n)) {
+            f.caffeine += 1;
+            // created_at is when it was logged; only trust it for same-day entries.
+            let logged = chrono::DateTime::parse_from_rfc3339(created).ok().map(|d| d.with_timezone(&chrono::Local));
+            if logged.is_some_and(|d| d.format("%Y-%m-%d").to_string() == date && d.hour() >= LATE_CAFFEINE_HOUR) {
+                f.late_caffeine = true;
+            }
+        }
+    }
+    f.workout_minutes = conn.query_row(
+        "SELECT COALESCE(SUM(duration_minutes),0) FROM workouts WHERE date=?1", [date], |r| r.get(0),
+    ).unwrap_or(0);
+    let any_snapshots: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM activity_snapshots WHERE substr(captured_at,1,10)=?1", [date], |r| r.get(0),
+    ).unwrap_or(0);
+    if let (true, Some((bed, _))) = (any_snapshots > 0, crate::heart_rate::sleep_window(date, start_time, in_bed)) {
+        let fmt = |t: chrono::NaiveDateTime| t.format("%Y-%m-%dT%H:%M").to_string();
+        let active: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM activity_snapshots WHERE substr(captured_at,1,16) BETWEEN ?1 AND ?2
+             AND COALESCE(category,'') != 'afk' AND COALESCE(screen_locked,0) = 0",
+            [fmt(bed - chrono::Duration::minutes(PRE_BED_WINDOW_MINUTES)), fmt(bed)], |r| r.get(0),
+        ).unwrap_or(0);
+        f.pre_bed_screen_minutes = Some(active as f64 * 0.5); // one snapshot per 30 s
+    }
+    f
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Correlation {
+    pub factor: &'static str,
+    pub label: &'static str,
+    pub nights_with: usize,
+    pub nights_without: usize,
+    pub avg_with: f64,
+    pub avg_without: f64,
+    /// avg_with − avg_without, score points.
+    pub diff: f64,
+}
+
+/// Splits nights into with / without a factor; None = unknown for that night.
+type FactorTest = fn(&NightFactors) -> Option<bool>;
+
+/// Average score with vs without each factor; needs three nights on each side.
+pub(crate) fn correlate(nights: &[(i64, NightFactors)]) -> Vec<Correlation> {
+    let tests: [(&'static str, &'static str, FactorTest); 4] = [
+        ("late_caffeine", "Кофеин после 14:00", |f| Some(f.late_caffeine)),
+        ("caffeine", "Кофеин в течение дня", |f| Some(f.caffeine > 0)),
+        ("workout", "Тренировка в этот день", |f| Some(f.workout_minutes > 0)),
+        ("late_screen", "Экран за 2 часа до сна (30+ мин)", |f| f.pre_bed_screen_minutes.map(|m| m >= LATE_SCREEN_MINUTES)),
+    ];
+    tests.iter().filter_map(|(factor, label, test)| {
+        let (mut with, mut without) = (Vec::new(), Vec::new());
+        for (score, f) in nights {
+            match test(f) {
+                Some(true) => with.push(*score as f64),
+                Some(false) => without.push(*score as f64),
+                None => {}
+            }
+        }
+        if with.len() < 3 || without.len() < 3 { return None; }
+        let avg = |v: &[f64]| (v.iter().sum::<f64>() / v.len() as f64 * 10.0).round() / 10.0;
+        let (a, b) = (avg(&with), avg(&without));
+        Some(Correlation {
+            factor, label, nights_with: with.len(), nights_without: without.len(),
+            avg_with: a, avg_without: b, diff: ((a - b) * 10.0).round() / 10.0,
+        })
+    }).collect()
+}
+
+#[derive(Serialize)]
+pub struct SleepNight {
+    #[serde(flatten)]
+    pub metrics: NightMetrics,
+    pub factors: NightFactors,
+}
+
+fn nights_with_factors(conn: &rusqlite::Connection, since: &str) -> Vec<SleepNight> {
+    let nights = score_sessions(conn, since);
+    let starts: std::collections::HashMap<String, String> = conn.prepare("SELECT id, start_time FROM sleep_sessions WHERE date >= ?1")
+        .and_then(|mut s| s.query_map([since], |r| Ok((r.get(0)?, r.get(1)?)))?.collect())
+        .unwrap_or_default();
+    nights.into_iter().map(|m| {
+        let start = starts.get(&m.session_id).map(String::as_str).unwrap_or("");
+        let factors = night_factors(conn, &m.date, start, m.in_bed_minutes);
+        SleepNight { metrics: m, factors }
+    }).collect()
+}
+
+/// Per-night stage breakdown, score and the day's caffeine/workout/screen factors.
+#[tauri::command]
+pub fn get_sleep_nights(db: State<'_, HanniDb>, days: i64) -> Vec<SleepNight> {
+    let conn = db.conn();
+    let since = (chrono::Local::now() - chrono::Duration::days(days)).format("%Y-%m-%d").to_string();
+    let mut nights = nights_with_factors(&conn, &since);
+    nights.reverse();
+    nights
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn st(s: &str, e: &str, stage: &str) -> (String, String, String) { (s.into(), e.into(), stage.into()) }
+
+    #[test]
+    fn stages_across_midnight() {
+        let stages = vec![
+            st("23:20", "23:50", "awake"),
+            st("23:50", "01:20", "light"),
+            st("01:20", "02:20", "deep"),
+            st("02:20", "02:40", "awake"),
+            st("02:40", "05:00", "light"),
+            st("05:00", "05:05", "awake"),
+            st("05:05", "06:30", "rem"),
+            st("06:30", "06:50", "awake"),
+        ];
+        let m = night_metrics("23:20", 450, &stages);
+        assert!(m.has_stages);
+        assert_eq!(m.latency_minutes, Some(30));
+        assert_eq!(m.deep_minutes, 60);
+        assert_eq!(m.rem_minutes, 85);
+        assert_eq!(m.light_minutes, 90 + 140);
+        assert_eq!(m.asleep_minutes, 375);
+        assert_eq!(m.wakeups, 2); // the final 06:30 wake isn't inside the sleep
+        assert_eq!(m.efficiency, Some(83.3));
+        let score = sleep_score(&m);
+        assert!((60..=85).contains(&score), "{score}");
+    }
+
+    #[test]
+    fn score_without_stages_is_capped() {
+        let m = night_metrics("2024-05-01T23:00:00+03:00", 540, &[]);
+        assert!(!m.has_stages);
+        assert_eq!(sleep_score(&m), 80);
+    }
+
+    #[test]
+    fn caffeine_matches_whole_words() {
+        assert!(is_caffeine("Кофе латте"));
+        assert!(is_caffeine("Кока-кола"));
+        assert!(is_caffeine("Green tea"));
+        assert!(is_caffeine("Flat white"));
+        assert!(!is_caffeine("Beef steak"));
+        assert!(!is_caffeine("Горький шоколад"));
+        assert!(!is_caffeine("Сахар рафинад"));
+        assert!(!is_caffeine("Травяной чай"));
+    }
+
+    #[test]
+    fn correlates_late_caffeine() {
+        let f = |late| NightFactors { late_caffeine: late, caffeine: 1, ..Default::default() };
+        let nights: Vec<(i64, NightFactors)> = [(60, true), (55, true), (58, true), (80, false), (82, false), (78, false)]
+            .into_iter().map(|(s, l)| (s, f(l))).collect();
+        let c = correlate(&nights);
+        let late = c.iter().find(|c| c.factor == "late_caffeine").unwrap();
+        assert_eq!(late.diff, -22.3);
+        assert!(c.iter().all(|c| c.factor != "caffeine" && c.factor != "late_screen"));
+    }
+}
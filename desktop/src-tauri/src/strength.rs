@@ -0,0 +1,546 @@
+// strength.rs — Per-set strength logging on top of `exercises`: each set keeps
+// reps/weight/RPE/rest/warm-up/failure, an estimated 1RM and a PR flag. Also
+// volume per muscle group, per-exercise progress and double-progression
+// suggestions for template exercises (used when a program day is started).
+use crate::types::HanniDb;
+
+/// Sets used for estimates: warm-ups are logged but never count.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetRec {
+    pub reps: i64,
+    pub weight_kg: f64,
+    pub rpe: Option<f64>,
+    pub failed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Suggestion {
+    pub weight_kg: f64,
+    pub reps: i64,
+    pub sets: i64,
+    /// "start" | "increase" | "repeat" | "deload"
+    pub action: &'static str,
+    pub reason: String,
+}
+
+/// Epley estimate; RPE adds the reps left in reserve (RPE 8 ≈ 2 more reps).
+pub fn estimate_1rm(weight_kg: f64, reps: i64, rpe: Option<f64>) -> f64 {
+    if weight_kg <= 0.0 || reps <= 0 { return 0.0; }
+    let rir = rpe.map(|r| (10.0 - r).clamp(0.0, 4.0)).unwrap_or(0.0);
+    let eff = reps as f64 + rir;
+    let e = if eff <= 1.0 { weight_kg } else { weight_kg * (1.0 + eff / 30.0) };
+    (e * 10.0).round() / 10.0
+}
+
+/// Round to something loadable: 2.5 kg plates for bars, 0.5 kg below 20 kg.
+pub(crate) fn round_load(kg: f64) -> f64 {
+    let step = if kg >= 20.0 { 2.5 } else { 0.5 };
+    ((kg / step).round() * step * 100.0).round() / 100.0
+}
+
+/// Working load a set done at `factor` of it stands for.
+pub(crate) fn unscale_load(kg: f64, factor: f64) -> f64 {
+    if factor <= 0.0 || (factor - 1.0).abs() < 0.001 { kg } else { round_load(kg / factor) }
+}
+
+/// ISO-8601 week label ("2025-W01"); the year is the week-numbering year, so
+/// 2024-12-30 already belongs to 2025-W01.
+pub(crate) fn iso_week_label(date: chrono::NaiveDate) -> String {
+    use chrono::Datelike;
+    let w = date.iso_week();
+    format!("{}-W{:02}", w.year(), w.week())
+}
+
+/// Double progression over the last sessions (most recent first, working sets only):
+/// all target sets at the target reps without failure and RPE ≤ 9 → add `increment`;
+/// a miss two sessions running at the same load → deload 10%; otherwise repeat.
+pub fn suggest_next(
+    sessions: &[Vec<SetRec>], target_sets: i64, target_reps: i64, template_weight: f64, increment: f64,
+) -> Suggestion {
+    let target_sets = target_sets.max(1);
+    let target_reps = target_reps.max(1);
+    let Some(last) = sessions.first().filter(|s| !s.is_empty()) else {
+        return Suggestion {
+            weight_kg: template_weight, reps: target_reps, sets: target_sets, action: "start",
+            reason: "Нет истории — начните с веса из шаблона".into(),
+        };
+    };
+    let top = last.iter().map(|s| s.weight_kg).fold(0.0, f64::max);
+    let missed = |sess: &[SetRec], load: f64| {
+        let at: Vec<&SetRec> = sess.iter().filter(|s| s.weight_kg >= load).collect();
+        at.is_empty() || at.iter().any(|s| s.failed || s.reps < target_reps)
+    };
+    let top_sets: Vec<&SetRec> = last.iter().filter(|s| s.weight_kg >= top).collect();
+    if missed(last, top) {
+        let prev_missed = sessions.get(1).is_some_and(|p| {
+            p.iter().map(|s| s.weight_kg).fold(0.0, f64::max) >= top && missed(p, top)
+        });
+        if prev_missed {
+            return Suggestion {
+                weight_kg: round_load(top * 0.9), reps: target_reps, sets: target_sets, action: "deload",
+                reason: format!("Две тренировки подряд не добраны повторы на {} кг — разгрузка −10%", trim_kg(top)),
+            };
+        }
+        return Suggestion {
+            weight_kg: top, reps: target_reps, sets: target_sets, action: "repeat",
+            reason: format!("Добрать {}×{} на {} кг", target_sets, target_reps, trim_kg(top)),
+        };
+    }
+    let hard = top_sets.iter().filter_map(|s| s.rpe).any(|r| r > 9.0);
+    if (top_sets.len() as i64) < target_sets || hard {
+        return Suggestion {
+            weight_kg: top, reps: target_reps, sets: target_sets, action: "repeat",
+            reason: if hard { "Последний раз RPE > 9 — закрепить вес".into() }
+                    else { format!("Сделать все {} подхода на {} кг", target_sets, trim_kg(top)) },
+        };
+    }
+    Suggestion {
+        weight_kg: round_load(top + increment), reps: target_reps, sets: target_sets, action: "increase",
+        reason: format!("{}×{} на {} кг выполнено — +{} кг", target_sets, target_reps, trim_kg(top), trim_kg(increment)),
+    }
+}
+
+fn trim_kg(x: f64) -> String {
+    let s = format!("{:.2}", x);
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Lower body moves more load per step than upper body isolation.
+fn increment_for(muscle_group: &str) -> f64 {
+    if muscle_group == "legs" || muscle_group == "back" { 5.0 } else { 2.5 }
+}
+
+// ── DB helpers ──
+
+/// (exercise_catalog_id, name, workout_id) of an exercises row.
+fn exercise_key(conn: &rusqlite::Connection, exercise_id: i64) -> Result<(Option<i64>, String, i64), String> {
+    conn.query_row(
+        "SELECT exercise_catalog_id, name, workout_id FROM exercises WHERE id=?1",
+        rusqlite::params![exercise_id], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+    ).map_err(|e| format!("Exercise not found: {}", e))
+}
+
+/// Same exercise across workouts: by catalog link, else by exact name.
+const SAME_EXERCISE: &str = "(CASE WHEN ?1 IS NOT NULL THEN s.exercise_catalog_id=?1 ELSE e.name=?2 END)";
+
+/// Last `n` sessions (most recent first) of working sets for one exercise.
+/// Sets from a program's deload / percentage week are read back at the
+/// unscaled working load, so a light week doesn't become the new base.
+fn recent_sessions(conn: &rusqlite::Connection, catalog_id: Option<i64>, name: &str, n: usize) -> Vec<Vec<SetRec>> {
+    let sql = format!(
+        "SELECT s.workout_id, s.reps, s.weight_kg, s.rpe, s.failed, COALESCE(w.load_factor, 1) FROM workout_sets s
+         JOIN exercises e ON e.id=s.exercise_id JOIN workouts w ON w.id=s.workout_id
+         WHERE {} AND s.is_warmup=0
+         ORDER BY w.date DESC, s.workout_id DESC, s.set_index", SAME_EXERCISE);
+    let rows: Vec<(i64, SetRec)> = conn.prepare(&sql)
+        .and_then(|mut st| st.query_map(rusqlite::params![catalog_id, name], |r| Ok((r.get(0)?, SetRec {
+            reps: r.get(1)?, weight_kg: unscale_load(r.get(2)?, r.get(5)?), rpe: r.get(3)?, failed: r.get::<_, i64>(4)? != 0,
+        }))).map(|m| m.filter_map(|x| x.ok()).collect()))
+        .unwrap_or_default();
+    let mut out: Vec<Vec<SetRec>> = Vec::new();
+    let mut cur: Option<i64> = None;
+    for (wid, s) in rows {
+        if cur != Some(wid) {
+            if out.len() == n { break; }
+            out.push(Vec::new());
+            cur = Some(wid);
+        }
+        if let Some(last) = out.last_mut() { last.push(s); }
+    }
+    out
+}
+
+/// Recompute e1RM and the PR flag of a set against earlier sets of the same exercise.
+/// Returns the PR kinds hit ("e1rm", "weight") with the previous bests.
+fn refresh_set_stats(conn: &rusqlite::Connection, set_id: i64) -> Result<serde_json::Value, String> {
+    let (exercise_id, reps, weight, rpe, warm, failed): (i64, i64, f64, Option<f64>, i64, i64) = conn.query_row(
+        "SELECT exercise_id, reps, weight_kg, rpe, is_warmup, failed FROM workout_sets WHERE id=?1",
+        rusqlite::params![set_id], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?, r.get(5)?)),
+    ).map_err(|e| format!("Set not found: {}", e))?;
+    let (catalog_id, name, _) = exercise_key(conn, exercise_id)?;
+    let e1rm = estimate_1rm(weight, reps, rpe);
+    let sql = format!(
+        "SELECT MAX(s.e1rm), MAX(s.weight_kg) FROM workout_sets s JOIN exercises e ON e.id=s.exercise_id
+         WHERE {} AND s.is_warmup=0 AND s.failed=0 AND s.id<?3", SAME_EXERCISE);
+    let (best_e1rm, best_weight): (Option<f64>, Option<f64>) = conn.query_row(
+        &sql, rusqlite::params![catalog_id, name, set_id], |r| Ok((r.get(0)?, r.get(1)?)),
+    ).unwrap_or((None, None));
+    let mut kinds = Vec::new();
+    // The very first working set isn't a record — there's nothing to beat yet.
+    if warm == 0 && failed == 0 && e1rm > 0.0 {
+        if best_e1rm.is_some_and(|b| e1rm > b + 0.05) { kinds.push("e1rm"); }
+        if best_weight.is_some_and(|b| weight > b) { kinds.push("weight"); }
+    }
+    conn.execute(
+        "UPDATE workout_sets SET e1rm=?1, is_pr=?2 WHERE id=?3",
+        rusqlite::params![e1rm, !kinds.is_empty() as i64, set_id],
+    ).map_err(|e| format!("DB error: {}", e))?;
+    Ok(serde_json::json!({
+        "e1rm": e1rm,
+        "pr": if kinds.is_empty() { serde_json::Value::Null } else {
+            serde_json::json!({ "kinds": kinds, "previous_e1rm": best_e1rm, "previous_weight": best_weight })
+        },
+    }))
+}
+
+/// Keep the legacy one-row summary on `exercises` in step with its sets.
+fn sync_exercise_summary(conn: &rusqlite::Connection, exercise_id: i64) {
+    let (n, top): (i64, Option<f64>) = conn.query_row(
+        "SELECT COUNT(*), MAX(weight_kg) FROM workout_sets WHERE exercise_id=?1 AND is_warmup=0",
+        rusqlite::params![exercise_id], |r| Ok((r.get(0)?, r.get(1)?)),
+    ).unwrap_or((0, None));
+    if n == 0 { return; }
+    let reps: i64 = conn.query_row(
+        "SELECT COALESCE(MAX(reps),0) FROM workout_sets WHERE exercise_id=?1 AND is_warmup=0 AND weight_kg=?2",
+        rusqlite::params![exercise_id, top.unwrap_or(0.0)], |r| r.get(0),
+    ).unwrap_or(0);
+    conn.execute(
+        "UPDATE exercises SET sets=?1, reps=?2, weight_kg=?3 WHERE id=?4",
+        rusqlite::params![n, reps, top, exercise_id],
+    ).ok();
+}
+
+/// Suggestion per exercise of a template, based on the logged history.
+pub(crate) fn progression_for_template(conn: &rusqlite::Connection, template_id: i64) -> Vec<serde_json::Value> {
+    let items: Vec<(Option<i64>, String, i64, i64, f64, String)> = conn.prepare(
+        "SELECT te.exercise_catalog_id, te.name, COALESCE(te.sets,3), COALESCE(te.reps,10), COALESCE(te.weight_kg,0), COALESCE(ec.muscle_group,'full_body')
+         FROM template_exercises te LEFT JOIN exercise_catalog ec ON ec.id=te.exercise_catalog_id
+         WHERE te.template_id=?1 ORDER BY te.order_index"
+    ).and_then(|mut st| st.query_map(rusqlite::params![template_id], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?, r.get(5)?)))
+        .map(|m| m.filter_map(|x| x.ok()).collect()))
+        .unwrap_or_default();
+    items.into_iter().map(|(cid, name, sets, reps, weight, mg)| {
+        let sessions = recent_sessions(conn, cid, &name, 2);
+        let s = suggest_next(&sessions, sets, reps, weight, increment_for(&mg));
+        serde_json::json!({ "exercise_catalog_id": cid, "name": name, "muscle_group": mg, "suggestion": s })
+    }).collect()
+}
+
+/// Write the suggested load onto a freshly materialized workout's exercises.
+pub(crate) fn apply_suggestions(conn: &rusqlite::Connection, workout_id: i64, suggestions: &[serde_json::Value]) {
+    for s in suggestions {
+        let name = s["name"].as_str().unwrap_or("");
+        let sg = &s["suggestion"];
+        conn.execute(
+            "UPDATE exercises SET sets=?1, reps=?2, weight_kg=?3 WHERE workout_id=?4 AND name=?5",
+            rusqlite::params![sg["sets"].as_i64(), sg["reps"].as_i64(), sg["weight_kg"].as_f64(), workout_id, name],
+        ).ok();
+    }
+}
+
+/// Apply a program week's load multiplier (deload / percentage week) to suggestions.
+pub(crate) fn scale_suggestions(suggestions: &mut [serde_json::Value], factor: f64, deload: bool) {
+    if (factor - 1.0).abs() < 0.001 { return; }
+    let pct = (factor * 100.0).round();
+    for s in suggestions.iter_mut() {
+        let sg = &mut s["suggestion"];
+        let w = sg["weight_kg"].as_f64().unwrap_or(0.0);
+        sg["weight_kg"] = serde_json::json!(round_load(w * factor));
+        let note = if deload { format!("разгрузочная неделя — {}%", pct) } else { format!("неделя {}%", pct) };
+        let reason = sg["reason"].as_str().unwrap_or("").to_string();
+        sg["reason"] = serde_json::json!(format!("{} · {}", reason, note));
+        if deload { sg["action"] = serde_json::json!("deload"); }
+    }
+}
+
+// ── Commands ──
+
+#[tauri::command]
+pub fn add_workout_exercise(
+    workout_id: i64, name: Option<String>, exercise_catalog_id: Option<i64>, db: tauri::State<'_, HanniDb>,
+) -> Result<i64, String> {
+    let conn = db.conn();
+    let name = match (name.filter(|n| !n.trim().is_empty()), exercise_catalog_id) {
+        (Some(n), _) => n.trim().to_string(),
+        (None, Some(cid)) => conn.query_row("SELECT name FROM exercise_catalog WHERE id=?1", rusqlite::params![cid], |r| r.get(0))
+            .map_err(|e| format!("Exercise not found: {}", e))?,
+        (None, None) => return Err("Укажите упражнение".into()),
+    };
+    conn.execute(
+        "INSERT INTO exercises (workout_id, name, sets, reps, weight_kg, duration_seconds, created_at, exercise_catalog_id) VALUES (?1,?2,0,0,0,0,?3,?4)",
+        rusqlite::params![workout_id, name, chrono::Local::now().to_rfc3339(), exercise_catalog_id],
+    ).map_err(|e| format!("DB error: {}", e))?;
+    Ok(conn.last_insert_rowid())
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn log_workout_set(
+    exercise_id: i64, reps: i64, weight_kg: f64, rpe: Option<f64>, rest_seconds: Option<i64>,
+    is_warmup: Option<bool>, failed: Option<bool>, notes: Option<String>, db: tauri::State<'_, HanniDb>,
+) -> Result<serde_json::Value, String> {
+    let conn = db.conn();
+    let (catalog_id, _, workout_id) = exercise_key(&conn, exercise_id)?;
+    let set_index: i64 = conn.query_row(
+        "SELECT COALESCE(MAX(set_index),0)+1 FROM workout_sets WHERE exercise_id=?1",
+        rusqlite::params![exercise_id], |r| r.get(0),
+    ).unwrap_or(1);
+    conn.execute(
+        "INSERT INTO workout_sets (workout_id, exercise_id, exercise_catalog_id, set_index, reps, weight_kg, rpe, rest_seconds, is_warmup, failed, notes, created_at)
+         VALUES (?1,?2,?3,?4,?5,?6,?7,?8,?9,?10,?11,?12)",
+        rusqlite::params![workout_id, exercise_id, catalog_id, set_index, reps.max(0), weight_kg.max(0.0),
+            rpe.map(|r| r.clamp(1.0, 10.0)), rest_seconds, is_warmup.unwrap_or(false) as i64,
+            failed.unwrap_or(false) as i64, notes.unwrap_or_default(), chrono::Local::now().to_rfc3339()],
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let id = conn.last_insert_rowid();
+    let mut out = refresh_set_stats(&conn, id)?;
+    sync_exercise_summary(&conn, exercise_id);
+    out["id"] = serde_json::json!(id);
+    out["set_index"] = serde_json::json!(set_index);
+    Ok(out)
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn update_workout_set(
+    id: i64, reps: Option<i64>, weight_kg: Option<f64>, rpe: Option<f64>, rest_seconds: Option<i64>,
+    is_warmup: Option<bool>, failed: Option<bool>, notes: Option<String>, db: tauri::State<'_, HanniDb>,
+) -> Result<serde_json::Value, String> {
+    let conn = db.conn();
+    let mut updates = Vec::new();
+    let mut params: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
+    let mut idx = 1;
+    if let Some(v) = reps { updates.push(format!("reps=?{}", idx)); params.push(Box::new(v.max(0))); idx += 1; }
+    if let Some(v) = weight_kg { updates.push(format!("weight_kg=?{}", idx)); params.push(Box::new(v.max(0.0))); idx += 1; }
+    if let Some(v) = rpe { updates.push(format!("rpe=?{}", idx)); params.push(Box::new(v.clamp(1.0, 10.0))); idx += 1; }
+    if let Some(v) = rest_seconds { updates.push(format!("rest_seconds=?{}", idx)); params.push(Box::new(v)); idx += 1; }
+    if let Some(v) = is_warmup { updates.push(format!("is_warmup=?{}", idx)); params.push(Box::new(v as i64)); idx += 1; }
+    if let Some(v) = failed { updates.push(format!("failed=?{}", idx)); params.push(Box::new(v as i64)); idx += 1; }
+    if let Some(v) = notes { updates.push(format!("notes=?{}", idx)); params.push(Box::new(v)); idx += 1; }
+    if !updates.is_empty() {
+        params.push(Box::new(id));
+        let sql = format!("UPDATE workout_sets SET {} WHERE id=?{}", updates.join(","), idx);
+        let param_refs: Vec<&dyn rusqlite::types::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+        conn.execute(&sql, param_refs.as_slice()).map_err(|e| format!("DB error: {}", e))?;
+    }
+    let out = refresh_set_stats(&conn, id)?;
+    if let Ok(eid) = conn.query_row("SELECT exercise_id FROM workout_sets WHERE id=?1", rusqlite::params![id], |r| r.get::<_, i64>(0)) {
+        sync_exercise_summary(&conn, eid);
+    }
+    Ok(out)
+}
+
+#[tauri::command]
+pub fn delete_workout_set(id: i64, db: tauri::State<'_, HanniDb>) -> Result<(), String> {
+    let conn = db.conn();
+    let eid: Option<i64> = conn.query_row("SELECT exercise_id FROM workout_sets WHERE id=?1", rusqlite::params![id], |r| r.get(0)).ok();
+    conn.execute("DELETE FROM workout_sets WHERE id=?1", rusqlite::params![id])
+        .map_err(|e| format!("DB error: {}", e))?;
+    if let Some(eid) = eid {
+        // Close the gap so set numbers stay 1..n.
+        conn.execute(
+            "UPDATE workout_sets SET set_index=(SELECT COUNT(*) FROM workout_sets s2 WHERE s2.exercise_id=workout_sets.exercise_id AND s2.id<=workout_sets.id) WHERE exercise_id=?1",
+            rusqlite::params![eid],
+        ).ok();
+        sync_exercise_summary(&conn, eid);
+    }
+    Ok(())
+}
+
+/// Exercises of a workout with their sets, in logging order.
+#[tauri::command]
+pub fn get_workout_sets(workout_id: i64, db: tauri::State<'_, HanniDb>) -> Result<Vec<serde_json::Value>, String> {
+    let conn = db.read();
+    let mut stmt = conn.prepare(
+        "SELECT e.id, e.name, e.exercise_catalog_id, COALESCE(ec.muscle_group,'full_body'), e.sets, e.reps, e.weight_kg
+         FROM exercises e LEFT JOIN exercise_catalog ec ON ec.id=e.exercise_catalog_id WHERE e.workout_id=?1 ORDER BY e.id"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let mut exercises: Vec<serde_json::Value> = stmt.query_map(rusqlite::params![workout_id], |r| Ok(serde_json::json!({
+        "id": r.get::<_, i64>(0)?, "name": r.get::<_, String>(1)?, "exercise_catalog_id": r.get::<_, Option<i64>>(2)?,
+        "muscle_group": r.get::<_, String>(3)?, "planned_sets": r.get::<_, Option<i64>>(4)?,
+        "planned_reps": r.get::<_, Option<i64>>(5)?, "planned_weight_kg": r.get::<_, Option<f64>>(6)?,
+    }))).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+    let mut sstmt = conn.prepare(
+        "SELECT id, set_index, reps, weight_kg, rpe, rest_seconds, is_warmup, failed, e1rm, is_pr, notes
+         FROM workout_sets WHERE exercise_id=?1 ORDER BY set_index"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    for ex in exercises.iter_mut() {
+        let eid = ex["id"].as_i64().unwrap_or(0);
+        let sets: Vec<serde_json::Value> = sstmt.query_map(rusqlite::params![eid], |r| Ok(serde_json::json!({
+            "id": r.get::<_, i64>(0)?, "set_index": r.get::<_, i64>(1)?, "reps": r.get::<_, i64>(2)?,
+            "weight_kg": r.get::<_, f64>(3)?, "rpe": r.get::<_, Option<f64>>(4)?, "rest_seconds": r.get::<_, Option<i64>>(5)?,
+            "is_warmup": r.get::<_, i64>(6)? != 0, "failed": r.get::<_, i64>(7)? != 0, "e1rm": r.get::<_, f64>(8)?,
+            "is_pr": r.get::<_, i64>(9)? != 0, "notes": r.get::<_, String>(10)?,
+        }))).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+        ex["sets"] = serde_json::json!(sets);
+    }
+    Ok(exercises)
+}
+
+/// Per-session best e1RM, top set and tonnage for one exercise (oldest first, for charts).
+#[tauri::command]
+pub fn get_exercise_progress(
+    exercise_catalog_id: Option<i64>, name: Option<String>, limit: Option<i64>, db: tauri::State<'_, HanniDb>,
+) -> Result<Vec<serde_json::Value>, String> {
+    let conn = db.read();
+    let sql = format!(
+        "SELECT w.date, s.workout_id, MAX(s.e1rm), MAX(s.weight_kg), SUM(s.reps*s.weight_kg), COUNT(*), SUM(s.is_pr)
+         FROM workout_sets s JOIN exercises e ON e.id=s.exercise_id JOIN workouts w ON w.id=s.workout_id
+         WHERE {} AND s.is_warmup=0
+         GROUP BY s.workout_id ORDER BY w.date DESC, s.workout_id DESC LIMIT ?3", SAME_EXERCISE);
+    let mut stmt = conn.prepare(&sql).map_err(|e| format!("DB error: {}", e))?;
+    let mut rows: Vec<serde_json::Value> = stmt.query_map(
+        rusqlite::params![exercise_catalog_id, name.unwrap_or_default(), limit.unwrap_or(30)],
+        |r| Ok(serde_json::json!({
+            "date": r.get::<_, String>(0)?, "workout_id": r.get::<_, i64>(1)?, "best_e1rm": r.get::<_, f64>(2)?,
+            "top_weight_kg": r.get::<_, f64>(3)?, "volume_kg": r.get::<_, f64>(4)?, "sets": r.get::<_, i64>(5)?,
+            "prs": r.get::<_, i64>(6)?,
+        })),
+    ).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+    rows.reverse();
+    Ok(rows)
+}
+
+/// Working sets and tonnage per muscle group per ISO week.
+#[tauri::command]
+pub fn get_strength_volume(date_from: String, date_to: String, db: tauri::State<'_, HanniDb>) -> Result<Vec<serde_json::Value>, String> {
+    let conn = db.read();
+    // SQLite's %W isn't ISO (weeks start at the first Monday of the year), so
+    // group per day here and fold days into ISO weeks below.
+    let mut stmt = conn.prepare(
+        "SELECT w.date, COALESCE(ec.muscle_group,'other') AS mg,
+                COUNT(*), COALESCE(SUM(s.reps*s.weight_kg),0), COALESCE(SUM(s.reps),0)
+         FROM workout_sets s JOIN workouts w ON w.id=s.workout_id
+         LEFT JOIN exercise_catalog ec ON ec.id=s.exercise_catalog_id
+         WHERE s.is_warmup=0 AND w.date BETWEEN ?1 AND ?2
+         GROUP BY w.date, mg ORDER BY w.date"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    type Row = (String, String, i64, f64, i64);
+    let rows: Vec<Row> = stmt.query_map(rusqlite::params![date_from, date_to], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?)))
+        .map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+    // (week, muscle group) → (sets, volume, reps, first logged date); rows arrive date-ordered.
+    let mut weeks: std::collections::BTreeMap<(String, String), (i64, f64, i64, String)> = std::collections::BTreeMap::new();
+    for (date, mg, sets, volume, reps) in rows {
+        let Ok(day) = chrono::NaiveDate::parse_from_str(&date, "%Y-%m-%d") else { continue };
+        let e = weeks.entry((iso_week_label(day), mg)).or_insert_with(|| (0, 0.0, 0, date));
+        e.0 += sets;
+        e.1 += volume;
+        e.2 += reps;
+    }
+    Ok(weeks.into_iter().map(|((week, mg), (sets, volume, reps, start))| serde_json::json!({
+        "week": week, "muscle_group": mg, "sets": sets, "volume_kg": volume, "reps": reps, "week_start": start,
+    })).collect())
+}
+
+/// Best estimated 1RM (with its date) and heaviest working set per exercise.
+#[tauri::command]
+pub fn get_personal_records(db: tauri::State<'_, HanniDb>) -> Result<Vec<serde_json::Value>, String> {
+    let conn = db.read();
+    let mut stmt = conn.prepare(
+        "SELECT COALESCE(ec.name, e.name), s.exercise_catalog_id, COALESCE(ec.muscle_group,'other'), s.e1rm, s.weight_kg, s.reps, w.date
+         FROM workout_sets s JOIN exercises e ON e.id=s.exercise_id JOIN workouts w ON w.id=s.workout_id
+         LEFT JOIN exercise_catalog ec ON ec.id=s.exercise_catalog_id
+         WHERE s.is_warmup=0 AND s.failed=0 AND s.e1rm>0
+         ORDER BY s.e1rm DESC, w.date"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    type Row = (String, Option<i64>, String, f64, f64, i64, String);
+    let rows: Vec<Row> = stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?, r.get(5)?, r.get(6)?)))
+        .map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+    // Rows arrive best-first, so the first row per exercise is its e1RM record.
+    let mut out: Vec<serde_json::Value> = Vec::new();
+    let mut seen: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+    for (name, cid, mg, e1rm, weight, reps, date) in rows {
+        let key = cid.map(|c| c.to_string()).unwrap_or_else(|| name.clone());
+        match seen.get(&key) {
+            Some(&i) => {
+                if weight > out[i]["max_weight_kg"].as_f64().unwrap_or(0.0) {
+                    out[i]["max_weight_kg"] = serde_json::json!(weight);
+                    out[i]["max_weight_date"] = serde_json::json!(date);
+                }
+            }
+            None => {
+                seen.insert(key, out.len());
+                out.push(serde_json::json!({
+                    "exercise": name, "exercise_catalog_id": cid, "muscle_group": mg,
+                    "best_e1rm": e1rm, "best_set": format!("{}×{}", trim_kg(weight), reps), "best_e1rm_date": date,
+                    "max_weight_kg": weight, "max_weight_date": date,
+                }));
+            }
+        }
+    }
+    Ok(out)
+}
+
+#[tauri::command]
+pub fn get_progression_suggestions(template_id: i64, db: tauri::State<'_, HanniDb>) -> Result<Vec<serde_json::Value>, String> {
+    Ok(progression_for_template(&db.read(), template_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn s(reps: i64, w: f64) -> SetRec { SetRec { reps, weight_kg: w, rpe: None, failed: false } }
+
+    #[test]
+    fn estimates_one_rep_max() {
+        assert_eq!(estimate_1rm(100.0, 1, None), 100.0);
+        assert_eq!(estimate_1rm(100.0, 5, None), 116.7);
+        // 5 reps @ RPE 8 ≈ a 7-rep max
+        assert_eq!(estimate_1rm(100.0, 5, Some(8.0)), 123.3);
+        assert_eq!(estimate_1rm(0.0, 5, None), 0.0);
+    }
+
+    #[test]
+    fn double_progression() {
+        let done = vec![vec![s(5, 100.0), s(5, 100.0), s(5, 100.0)]];
+        let g = suggest_next(&done, 3, 5, 60.0, 2.5);
+        assert_eq!((g.action, g.weight_kg), ("increase", 102.5));
+
+        let short = vec![vec![s(5, 100.0), s(5, 100.0), s(3, 100.0)]];
+        let g = suggest_next(&short, 3, 5, 60.0, 2.5);
+        assert_eq!((g.action, g.weight_kg), ("repeat", 100.0));
+
+        let twice = vec![short[0].clone(), vec![s(5, 100.0), SetRec { failed: true, ..s(4, 100.0) }]];
+        let g = suggest_next(&twice, 3, 5, 60.0, 2.5);
+        assert_eq!((g.action, g.weight_kg), ("deload", 90.0));
+
+        let g = suggest_next(&[], 3, 8, 40.0, 2.5);
+        assert_eq!((g.action, g.weight_kg, g.reps), ("start", 40.0, 8));
+
+        let grind = vec![vec![s(5, 100.0), s(5, 100.0), SetRec { rpe: Some(9.5), ..s(5, 100.0) }]];
+        assert_eq!(suggest_next(&grind, 3, 5, 60.0, 2.5).action, "repeat");
+    }
+
+    #[test]
+    fn sparse_history_edges() {
+        // An empty last session counts as no history.
+        let g = suggest_next(&[vec![]], 3, 5, 60.0, 2.5);
+        assert_eq!((g.action, g.weight_kg), ("start", 60.0));
+        // A single failed session can't deload — there's nothing to compare with.
+        let one = vec![vec![SetRec { failed: true, ..s(5, 100.0) }]];
+        assert_eq!(suggest_next(&one, 3, 5, 60.0, 2.5).action, "repeat");
+        // Zero targets are clamped to one set of one rep.
+        let g = suggest_next(&[vec![s(1, 100.0)]], 0, 0, 60.0, 2.5);
+        assert_eq!((g.action, g.weight_kg, g.sets, g.reps), ("increase", 102.5, 1, 1));
+
+        assert_eq!(estimate_1rm(100.0, 0, None), 0.0);
+        assert_eq!(estimate_1rm(100.0, 1, Some(10.0)), 100.0);
+        // RPE below 6 adds at most 4 reps in reserve.
+        assert_eq!(estimate_1rm(100.0, 1, Some(5.0)), 116.7);
+        assert_eq!(round_load(18.3), 18.5);
+        assert_eq!(round_load(101.2), 100.0);
+    }
+
+    #[test]
+    fn weeks_follow_iso_numbering() {
+        use crate::test_util::d;
+        // Monday 2024-12-30 opens 2025-W01; SQLite's %W called it 2024-W53.
+        assert_eq!(iso_week_label(d("2024-12-30")), "2025-W01");
+        assert_eq!(iso_week_label(d("2025-01-05")), "2025-W01");
+        assert_eq!(iso_week_label(d("2025-01-06")), "2025-W02");
+        // Friday 2021-01-01 still belongs to the last week of 2020.
+        assert_eq!(iso_week_label(d("2021-01-01")), "2020-W53");
+    }
+
+    #[test]
+    fn deload_sets_read_back_at_working_load() {
+        // 60% deload of 100 kg logged as 60 → progression sees 100 again.
+        assert_eq!(unscale_load(60.0, 0.6), 100.0);
+        // 85% week of 102.5 was logged as 87.5; reading it back lands on the plate step.
+        assert_eq!(unscale_load(87.5, 0.85), 102.5);
+        assert_eq!(unscale_load(80.0, 1.0), 80.0);
+        // A bogus stored factor leaves the load untouched.
+        assert_eq!(unscale_load(80.0, 0.0), 80.0);
+    }
+}
@@ -0,0 +1,7 @@
+// test_util.rs — Helpers shared by the unit tests of the pure logic modules.
+use chrono::NaiveDate;
+
+/// "YYYY-MM-DD" → date; panics on a malformed literal.
+pub(crate) fn d(s: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+}
@@ -0,0 +1,260 @@
+// time_zones.rs — Home / travel time zones. An event keeps the zone its date
+// and time were entered in (`events.tz`, IANA name; '' = floating wall clock).
+// What the user sees — calendar, today's plan, upcoming events, reminders — is
+// shifted into the current zone: the travel zone while travel mode is on,
+// else home (the system zone unless overridden in settings).
+use crate::types::HanniDb;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+pub fn parse_zone(name: &str) -> Option<Tz> {
+    let name = name.trim();
+    if name.is_empty() { None } else { name.parse().ok() }
+}
+
+/// IANA zone of the machine: $TZ, else the /etc/localtime symlink
+/// (…/zoneinfo/Europe/Moscow), else /etc/timezone.
+pub fn system_zone() -> Option<Tz> {
+    if let Some(z) = std::env::var("TZ").ok().and_then(|v| parse_zone(v.trim_start_matches(':'))) {
+        return Some(z);
+    }
+    if let Ok(target) = std::fs::read_link("/etc/localtime") {
+        if let Some(z) = target.to_string_lossy().split_once("zoneinfo/").and_then(|(_, name)| parse_zone(name)) {
+            return Some(z);
+        }
+    }
+    std::fs::read_to_string("/etc/timezone").ok().and_then(|s| parse_zone(&s))
+}
+
+fn setting(conn: &rusqlite::Connection, key: &str) -> Option<Tz> {
+    conn.query_row("SELECT value FROM app_settings WHERE key=?1", [key], |r| r.get::<_, String>(0))
+        .ok().and_then(|v| parse_zone(&v))
+}
+
+pub fn home_zone(conn: &rusqlite::Connection) -> Option<Tz> {
+    setting(conn, "home_time_zone").or_else(system_zone)
+}
+
+pub fn current_zone(conn: &rusqlite::Connection) -> Option<Tz> {
+    setting(conn, "travel_time_zone").or_else(|| home_zone(conn))
+}
+
+pub fn zone_name(z: Option<Tz>) -> String {
+    z.map(|z| z.name().to_string()).unwrap_or_default()
+}
+
+/// Canonical name of a user-supplied zone ('' stays '' — floating).
+pub fn validate_zone(name: &str) -> Result<String, String> {
+    if name.trim().is_empty() { return Ok(String::new()); }
+    parse_zone(name).map(|z| z.name().to_string()).ok_or_else(|| format!("Неизвестный часовой пояс: {}", name))
+}
+
+/// Wall clock in `zone` → instant. A time inside a DST gap moves forward by the gap.
+pub fn instant(dt: NaiveDateTime, zone: Tz) -> Option<DateTime<Utc>> {
+    zone.from_local_datetime(&dt).earliest()
+        .or_else(|| zone.from_local_datetime(&(dt + chrono::Duration::hours(1))).earliest())
+        .map(|z| z.with_timezone(&Utc))
+}
+
+/// Current wall clock in `zone` (system clock when no zone is known).
+pub fn now_in(zone: Option<Tz>) -> NaiveDateTime {
+    match zone {
+        Some(z) => Utc::now().with_timezone(&z).naive_local(),
+        None => chrono::Local::now().naive_local(),
+    }
+}
+
+/// Event date/time ("YYYY-MM-DD", "HH:MM" or "") stored in `tz`, as seen in
+/// `current`. All-day and floating events and unknown zones stay as they are.
+pub fn localize(date: &str, time: &str, tz: &str, current: Option<Tz>) -> (String, String) {
+    let unchanged = || (date.to_string(), time.to_string());
+    let (Some(from), Some(to)) = (parse_zone(tz), current) else { return unchanged() };
+    if from == to { return unchanged(); }
+    let (Ok(d), Some(Ok(t))) = (
+        NaiveDate::parse_from_str(date, "%Y-%m-%d"),
+        time.get(..5).map(|t| NaiveTime::parse_from_str(t, "%H:%M")),
+    ) else { return unchanged() };
+    match instant(d.and_time(t), from) {
+        Some(at) => {
+            let local = at.with_timezone(&to).naive_local();
+            (local.format("%Y-%m-%d").to_string(), local.format("%H:%M").to_string())
+        }
+        None => unchanged(),
+    }
+}
+
+/// The reverse of `localize`: a date/time entered in `current`, as stored in `tz`.
+pub fn unlocalize(date: &str, time: &str, current: Option<Tz>, tz: &str) -> (String, String) {
+    localize(date, time, &zone_name(current), parse_zone(tz))
+}
+
+/// A calendar event as seen in the current zone.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalEvent {
+    pub id: i64,
+    pub title: String,
+    pub date: String,
+    pub time: String,
+    pub duration_minutes: i64,
+    pub source: String,
+    pub tz: String,
+}
+
+/// Events whose date, seen in `current`, falls on `from..=to` (YYYY-MM-DD),
+/// ordered by that date and time. A zone shift moves an event by at most a
+/// day, so stored dates are searched one day wider on each side.
+pub fn events_between(conn: &rusqlite::Connection, from: &str, to: &str, current: Option<Tz>) -> Result<Vec<LocalEvent>, String> {
+    let widen = |d: &str, days: i64| NaiveDate::parse_from_str(d, "%Y-%m-%d")
+        .map(|d| (d + chrono::Duration::days(days)).format("%Y-%m-%d").to_string())
+        .map_err(|e| format!("Invalid date: {}", e));
+    let mut stmt = conn.prepare(
+        "SELECT id, title, date, time, COALESCE(duration_minutes, 60), COALESCE(source, 'manual'), COALESCE(tz, '')
+         FROM events WHERE date >= ?1 AND date <= ?2"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let mut events: Vec<LocalEvent> = stmt.query_map([widen(from, -1)?, widen(to, 1)?], |r| Ok(LocalEvent {
+        id: r.get(0)?, title: r.get(1)?, date: r.get(2)?, time: r.get(3)?,
+        duration_minutes: r.get(4)?, source: r.get(5)?, tz: r.get(6)?,
+    })).map_err(|e| format!("Query error: {}", e))?.flatten()
+        .map(|e| {
+            let (date, time) = localize(&e.date, &e.time, &e.tz, current);
+            LocalEvent { date, time, ..e }
+        })
+        .filter(|e| e.date.as_str() >= from && e.date.as_str() <= to)
+        .collect();
+    events.sort_by(|a, b| (&a.date, &a.time).cmp(&(&b.date, &b.time)));
+    Ok(events)
+}
+
+/// Is a reminder due? `remind_at` with an offset is an instant; a naive one
+/// ("2026-02-23T15:00:00") is a wall clock in the current zone.
+pub fn reminder_due(remind_at: &str, now: DateTime<Utc>, current: Option<Tz>) -> bool {
+    if let Ok(at) = DateTime::parse_from_rfc3339(remind_at) {
+        return at.with_timezone(&Utc) <= now;
+    }
+    let wall = ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"].iter()
+        .find_map(|f| NaiveDateTime::parse_from_str(remind_at, f).ok());
+    match (wall, current) {
+        (Some(w), Some(z)) => w <= now.with_timezone(&z).naive_local(),
+        (Some(w), None) => w <= now.with_timezone(&chrono::Local).naive_local(),
+        _ => remind_at <= now.with_timezone(&chrono::Local).to_rfc3339().as_str(),
+    }
+}
+
+/// Pin a naive `remind_at` to the current zone, so it keeps firing at the
+/// same instant after a zone change.
+pub fn anchor_reminder(remind_at: &str, current: Option<Tz>) -> String {
+    let Some(zone) = current else { return remind_at.to_string() };
+    if DateTime::parse_from_rfc3339(remind_at).is_ok() { return remind_at.to_string(); }
+    ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M"].iter()
+        .find_map(|f| NaiveDateTime::parse_from_str(remind_at, f).ok())
+        .and_then(|w| instant(w, zone))
+        .map(|at| at.with_timezone(&zone).to_rfc3339())
+        .unwrap_or_else(|| remind_at.to_string())
+}
+
+// ── Commands ──
+
+#[tauri::command]
+pub fn get_time_zone_settings(db: tauri::State<'_, HanniDb>) -> Result<serde_json::Value, String> {
+    let conn = db.read();
+    let (home, current) = (home_zone(&conn), current_zone(&conn));
+    let travel = setting(&conn, "travel_time_zone");
+    let offset_min = |z: Option<Tz>| z.map(|z| {
+        use chrono::Offset;
+        Utc::now().with_timezone(&z).offset().fix().local_minus_utc() / 60
+    });
+    Ok(serde_json::json!({
+        "system": zone_name(system_zone()),
+        "home": zone_name(home),
+        "home_override": setting(&conn, "home_time_zone").is_some(),
+        "travel": zone_name(travel),
+        "current": zone_name(current),
+        "home_now": now_in(home).format("%H:%M").to_string(),
+        "current_now": now_in(current).format("%H:%M").to_string(),
+        "home_offset_minutes": offset_min(home),
+        "current_offset_minutes": offset_min(current),
+        "zones": chrono_tz::TZ_VARIANTS.iter().map(|z| z.name()).collect::<Vec<_>>(),
+    }))
+}
+
+/// `Some("")` clears a zone (home falls back to the system one, travel mode
+/// turns off); `None` leaves it as is.
+#[tauri::command]
+pub fn set_time_zone_settings(home: Option<String>, travel: Option<String>, db: tauri::State<'_, HanniDb>) -> Result<(), String> {
+    let conn = db.conn();
+    for (key, value) in [("home_time_zone", home), ("travel_time_zone", travel)] {
+        let Some(value) = value else { continue };
+        let zone = validate_zone(&value)?;
+        if zone.is_empty() {
+            conn.execute("DELETE FROM app_settings WHERE key=?1", [key]).map_err(|e| format!("DB error: {}", e))?;
+        } else {
+            conn.execute("INSERT OR REPLACE INTO app_settings (key, value) VALUES (?1, ?2)", [key, zone.as_str()])
+                .map_err(|e| format!("DB error: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn localizes_across_zones_and_days() {
+        let tokyo = parse_zone("Asia/Tokyo");
+        // 10:00 in Moscow (UTC+3) is 16:00 in Tokyo (UTC+9).
+        assert_eq!(localize("2026-03-10", "10:00", "Europe/Moscow", tokyo), ("2026-03-10".into(), "16:00".into()));
+        // Late evening crosses midnight.
+        assert_eq!(localize("2026-03-10", "20:30", "Europe/Moscow", tokyo), ("2026-03-11".into(), "02:30".into()));
+        // All-day, floating and same-zone events stay put.
+        assert_eq!(localize("2026-03-10", "", "Europe/Moscow", tokyo), ("2026-03-10".into(), "".into()));
+        assert_eq!(localize("2026-03-10", "10:00", "", tokyo), ("2026-03-10".into(), "10:00".into()));
+        assert_eq!(localize("2026-03-10", "10:00", "Asia/Tokyo", tokyo), ("2026-03-10".into(), "10:00".into()));
+    }
+
+    #[test]
+    fn dst_gap_moves_forward() {
+        // 02:30 doesn't exist in Berlin on 2026-03-29 (clocks jump 02:00 → 03:00).
+        let dt = NaiveDate::from_ymd_opt(2026, 3, 29).unwrap().and_hms_opt(2, 30, 0).unwrap();
+        let at = instant(dt, "Europe/Berlin".parse().unwrap()).unwrap();
+        assert_eq!(at.to_rfc3339(), "2026-03-29T01:30:00+00:00");
+        // Summer time in New York is UTC-4.
+        assert_eq!(localize("2026-07-01", "09:00", "America/New_York", parse_zone("UTC")), ("2026-07-01".into(), "13:00".into()));
+    }
+
+    #[test]
+    fn events_follow_the_current_zone() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE events (id INTEGER PRIMARY KEY, title TEXT, date TEXT, time TEXT, duration_minutes INTEGER, source TEXT, tz TEXT);
+             INSERT INTO events VALUES (1, 'Созвон', '2026-03-10', '20:30', 60, 'google', 'Europe/Moscow');
+             INSERT INTO events VALUES (2, 'Обед', '2026-03-11', '13:00', 60, 'manual', '');
+             INSERT INTO events VALUES (3, 'ДР', '2026-03-11', '', 0, 'manual', '');"
+        ).unwrap();
+        let tokyo = parse_zone("Asia/Tokyo");
+        let day = |d: &str| events_between(&conn, d, d, tokyo).unwrap().into_iter().map(|e| (e.id, e.time)).collect::<Vec<_>>();
+        // 20:30 in Moscow is 02:30 next day in Tokyo; floating and all-day ones stay put.
+        assert_eq!(day("2026-03-10"), vec![]);
+        assert_eq!(day("2026-03-11"), vec![(3, "".into()), (1, "02:30".into()), (2, "13:00".into())]);
+        // Seen in Moscow nothing moves.
+        assert_eq!(events_between(&conn, "2026-03-10", "2026-03-10", parse_zone("Europe/Moscow")).unwrap().len(), 1);
+        // Rescheduling to "tomorrow 02:30" in Tokyo writes Moscow's wall clock back.
+        assert_eq!(unlocalize("2026-03-12", "02:30", tokyo, "Europe/Moscow"), ("2026-03-11".into(), "20:30".into()));
+        assert_eq!(unlocalize("2026-03-12", "", tokyo, "Europe/Moscow"), ("2026-03-12".into(), "".into()));
+    }
+
+    #[test]
+    fn reminders_by_instant_or_wall_clock() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 10, 12, 0, 0).unwrap(); // 15:00 Moscow, 21:00 Tokyo
+        let moscow = parse_zone("Europe/Moscow");
+        let tokyo = parse_zone("Asia/Tokyo");
+        assert!(reminder_due("2026-03-10T15:00:00", now, moscow));
+        assert!(!reminder_due("2026-03-10T15:01:00", now, moscow));
+        // A naive time follows the traveller; an anchored one doesn't.
+        assert!(reminder_due("2026-03-10T21:00", now, tokyo));
+        let anchored = anchor_reminder("2026-03-10T16:00:00", moscow);
+        assert_eq!(anchored, "2026-03-10T16:00:00+03:00");
+        assert!(!reminder_due(&anchored, now, tokyo));
+        assert!(reminder_due("2026-03-10T14:00:00+03:00", now, tokyo));
+    }
+}
@@ -0,0 +1,401 @@
+// timeline_reconstruct.rs — One pass that rebuilds a day's automatic timeline
+// from every source: sleep sessions, workouts + Health Connect exercise, cooking
+// log, activity snapshots (focus / distraction / AFK by category) and calendar
+// events. Sources are painted minute by minute in priority order, so the result
+// never overlaps; manual, task and pomodoro blocks are left alone and everything
+// is painted around them. Re-runs only touch rows whose (type, span, notes) changed.
+use crate::types::HanniDb;
+use std::collections::HashMap;
+
+/// Block sources owned by the reconstruction pass. Anything else is user data.
+pub const AUTO_SOURCES: &[&str] = &[
+    "auto_health", "auto_workout", "auto_cooking", "auto_focus", "auto_distraction", "auto_event", "auto_afk",
+];
+const DAY_MIN: usize = 1440;
+/// Shortest block written; shorter runs are dropped (or absorbed, for snapshots).
+const MIN_BLOCK: usize = 5;
+/// A snapshot carries its state over a gap up to this long…
+const SNAPSHOT_CARRY: usize = 5;
+/// …and a longer gap up to this is AFK (the collector stops writing after 30 min idle).
+const GAP_AFK_MAX: usize = 240;
+
+/// Priority: direct records of what you did beat computer activity, which
+/// beats calendar plans (a plan explains time away from the screen), and AFK
+/// only fills what nothing else claims.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Kind { Afk, Event, Distraction, Focus, Cooking, Workout, Exercise, Sleep }
+
+impl Kind {
+    fn source(self) -> &'static str {
+        match self {
+            Kind::Sleep | Kind::Exercise => "auto_health",
+            Kind::Workout => "auto_workout",
+            Kind::Cooking => "auto_cooking",
+            Kind::Focus => "auto_focus",
+            Kind::Distraction => "auto_distraction",
+            Kind::Event => "auto_event",
+            Kind::Afk => "auto_afk",
+        }
+    }
+    fn type_name(self) -> &'static str {
+        match self {
+            Kind::Sleep => "Сон",
+            Kind::Workout | Kind::Exercise => "Спорт",
+            Kind::Cooking => "Еда",
+            Kind::Focus => "Фокус",
+            Kind::Distraction => "Отвлечения",
+            Kind::Event => "Запланировано",
+            Kind::Afk => "АФК",
+        }
+    }
+}
+
+/// A candidate interval [start, end) in minutes of the day.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub kind: Kind,
+    pub start: usize,
+    pub end: usize,
+    pub notes: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Snap { pub minute: usize, pub afk: bool, pub distraction: bool }
+
+pub fn hm_to_min(t: &str) -> Option<usize> {
+    let (h, m) = t.get(..5)?.split_once(':')?;
+    let v = h.parse::<usize>().ok()? * 60 + m.parse::<usize>().ok()?;
+    (v <= DAY_MIN).then_some(v)
+}
+
+pub fn min_to_hm(m: usize) -> String {
+    let m = m.min(DAY_MIN); // a block running to midnight ends at 24:00
+    format!("{:02}:{:02}", m / 60, m % 60)
+}
+
+/// Snapshots → focus / distraction / AFK spans. Runs shorter than MIN_BLOCK
+/// are absorbed into the preceding run so a quick glance at a chat doesn't
+/// split a focus block.
+pub fn snapshot_spans(snaps: &[Snap], limit: usize) -> Vec<Span> {
+    let mut states: Vec<Option<Kind>> = vec![None; DAY_MIN];
+    for (i, s) in snaps.iter().enumerate() {
+        let kind = if s.afk { Kind::Afk } else if s.distraction { Kind::Distraction } else { Kind::Focus };
+        let next = snaps.get(i + 1).map(|n| n.minute).unwrap_or(s.minute + 1).max(s.minute + 1);
+        let gap = next - s.minute;
+        let carry = if gap <= SNAPSHOT_CARRY { next } else { s.minute + 1 };
+        for st in states.iter_mut().take(carry.min(limit)).skip(s.minute) { *st = Some(kind); }
+        if gap > SNAPSHOT_CARRY && gap <= GAP_AFK_MAX {
+            for st in states.iter_mut().take(next.min(limit)).skip(carry) { *st = Some(Kind::Afk); }
+        }
+    }
+    let mut runs = runs_of(&states);
+    let mut i = 1;
+    while i < runs.len() {
+        let (prev_kind, _, prev_end) = runs[i - 1];
+        let (kind, start, end) = runs[i];
+        if end - start < MIN_BLOCK && prev_end == start && prev_kind != kind {
+            runs[i - 1].2 = end;
+            runs.remove(i);
+            // The absorbed run may have separated two runs of the same kind.
+            if i < runs.len() && runs[i].0 == runs[i - 1].0 && runs[i].1 == runs[i - 1].2 {
+                runs[i - 1].2 = runs[i].2;
+                runs.remove(i);
+            }
+        } else {
+            i += 1;
+        }
+    }
+    runs.into_iter().map(|(kind, start, end)| Span { kind, start, end, notes: String::new() }).collect()
+}
+
+fn runs_of(states: &[Option<Kind>]) -> Vec<(Kind, usize, usize)> {
+    let mut runs: Vec<(Kind, usize, usize)> = Vec::new();
+    for (m, st) in states.iter().enumerate() {
+        let Some(k) = st else { continue };
+        match runs.last_mut() {
+            Some(r) if r.0 == *k && r.2 == m => r.2 = m + 1,
+            _ => runs.push((*k, m, m + 1)),
+        }
+    }
+    runs
+}
+
+/// Paint candidates by priority into the minutes not held by `fixed`, then
+/// cut the result into blocks of at least MIN_BLOCK minutes.
+pub fn merge(mut spans: Vec<Span>, fixed: &[(usize, usize)], limit: usize) -> Vec<Span> {
+    let mut owner: Vec<Option<usize>> = vec![None; DAY_MIN];
+    let mut blocked = vec![false; DAY_MIN];
+    for &(s, e) in fixed {
+        for b in blocked.iter_mut().take(e.min(DAY_MIN)).skip(s) { *b = true; }
+    }
+    // Stable sort: among equal kinds the earlier-listed span wins.
+    spans.sort_by_key(|s| std::cmp::Reverse(s.kind));
+    for (i, sp) in spans.iter().enumerate() {
+        for m in sp.start..sp.end.min(limit).min(DAY_MIN) {
+            if !blocked[m] && owner[m].is_none() { owner[m] = Some(i); }
+        }
+    }
+    let mut out: Vec<Span> = Vec::new();
+    let mut m = 0;
+    while m < DAY_MIN {
+        let Some(i) = owner[m] else { m += 1; continue };
+        let start = m;
+        while m < DAY_MIN && owner[m] == Some(i) { m += 1; }
+        if m - start >= MIN_BLOCK {
+            out.push(Span { kind: spans[i].kind, start, end: m, notes: spans[i].notes.clone() });
+        }
+    }
+    out
+}
+
+/// recipe name, prep+cook minutes, logged at, linked event time / duration
+type CookRow = (String, i64, String, Option<String>, Option<i64>);
+/// type_id, start, end, source, notes — what makes two auto blocks the same
+type BlockKey = (i64, String, String, String, String);
+
+fn collect_spans(conn: &rusqlite::Connection, date: chrono::NaiveDate) -> Result<Vec<Span>, String> {
+    let d = date.format("%Y-%m-%d").to_string();
+    let prev = (date - chrono::Duration::days(1)).format("%Y-%m-%d").to_string();
+    let mut spans = Vec::new();
+
+    // Sleep is dated by its start: a night crossing midnight is split between
+    // the evening of `date` and the morning of the next day.
+    let mut stmt = conn.prepare(
+        "SELECT date, start_time, end_time FROM sleep_sessions WHERE date IN (?1, ?2)"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let rows: Vec<(String, String, String)> = stmt.query_map(rusqlite::params![d, prev], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))
+        .map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+    for (sd, st, en) in rows {
+        let (Some(s), Some(e)) = (hm_to_min(&crate::timeline_health::normalize_time(&st)), hm_to_min(&crate::timeline_health::normalize_time(&en))) else { continue };
+        let (start, end) = match (sd == d, s < e) {
+            (true, true) => (s, e),
+            (true, false) => (s, DAY_MIN),
+            (false, false) => (0, e),
+            (false, true) => continue,
+        };
+        spans.push(Span { kind: Kind::Sleep, start, end, notes: "Сон".into() });
+    }
+
+    // Health Connect exercise: real start when known, otherwise laid out from 12:00.
+    let mut stmt = conn.prepare(
+        "SELECT value, notes, COALESCE(start_time,'') FROM health_log WHERE date=?1 AND type='exercise' ORDER BY start_time, rowid"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let rows: Vec<(f64, String, String)> = stmt.query_map(rusqlite::params![d], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))
+        .map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+    let mut fallback = 12 * 60;
+    for (dur, notes, st) in rows {
+        let dur = dur as usize;
+        let start = hm_to_min(&st).unwrap_or_else(|| { let s = fallback; fallback += dur; s });
+        spans.push(Span { kind: Kind::Exercise, start, end: start + dur, notes });
+    }
+
+    // Logged workouts: timestamp in `date` if present, else logged right after finishing.
+    let mut stmt = conn.prepare(
+        "SELECT date, created_at, COALESCE(duration_minutes,0), CASE WHEN title != '' THEN title ELSE type END
+         FROM workouts WHERE substr(date,1,10)=?1"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let rows: Vec<(String, String, i64, String)> = stmt.query_map(rusqlite::params![d], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)))
+        .map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+    for (wd, created, dur, title) in rows {
+        let dur = dur.max(0) as usize;
+        if dur == 0 { continue; }
+        let start = if wd.len() > 10 {
+            hm_to_min(&crate::timeline_health::normalize_time(&wd))
+        } else {
+            local_minute(&created, &d).map(|end| end.saturating_sub(dur))
+        };
+        if let Some(start) = start { spans.push(Span { kind: Kind::Workout, start, end: start + dur, notes: title }); }
+    }
+
+    // Cooking: the linked event's slot (seen in the current zone), else prep+cook time ending when it was logged.
+    let zone = crate::time_zones::current_zone(conn);
+    let mut stmt = conn.prepare(
+        "SELECT r.name, COALESCE(r.prep_time,0) + COALESCE(r.cook_time,0), c.created_at, e.date, e.time, COALESCE(e.tz,''), e.duration_minutes
+         FROM cooking_log c JOIN recipes r ON r.id = c.recipe_id LEFT JOIN events e ON e.id = c.event_id
+         WHERE c.date=?1"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let rows: Vec<CookRow> = stmt.query_map(rusqlite::params![d], |r| {
+        let ev_time = match (r.get::<_, Option<String>>(3)?, r.get::<_, Option<String>>(4)?) {
+            (Some(date), Some(time)) => Some(crate::time_zones::localize(&date, &time, &r.get::<_, String>(5)?, zone))
+                .filter(|(date, _)| *date == d).map(|(_, time)| time),
+            _ => None,
+        };
+        Ok((r.get(0)?, r.get(1)?, r.get(2)?, ev_time, r.get(6)?))
+    }).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+    for (name, total, created, ev_time, ev_dur) in rows {
+        let span = match ev_time.as_deref().and_then(hm_to_min) {
+            Some(s) => Some((s, s + ev_dur.unwrap_or(60).max(0) as usize)),
+            None => {
+                let dur = if total > 0 { total as usize } else { 30 };
+                local_minute(&created, &d).map(|end| (end.saturating_sub(dur), end))
+            }
+        };
+        if let Some((start, end)) = span { spans.push(Span { kind: Kind::Cooking, start, end, notes: name }); }
+    }
+
+    // Timed calendar events in the current zone (auto_health events mirror data painted above).
+    for e in crate::time_zones::events_between(conn, &d, &d, zone)? {
+        if e.time.is_empty() || e.source == "auto_health" { continue; }
+        if let Some(s) = hm_to_min(&e.time) { spans.push(Span { kind: Kind::Event, start: s, end: s + e.duration_minutes.max(0) as usize, notes: e.title }); }
+    }
+    Ok(spans)
+}
+
+/// Minute of `date` at which an RFC 3339 timestamp falls (local time), if it falls on that date.
+fn local_minute(ts: &str, date: &str) -> Option<usize> {
+    let t = chrono::DateTime::parse_from_rfc3339(ts).ok()?.with_timezone(&chrono::Local);
+    use chrono::Timelike;
+    (t.format("%Y-%m-%d").to_string() == date).then(|| (t.hour() * 60 + t.minute()) as usize)
+}
+
+fn load_snaps(conn: &rusqlite::Connection, date: &str) -> Result<Vec<Snap>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT captured_at, COALESCE(idle_secs,0), COALESCE(screen_locked,0), COALESCE(distraction_min,0), COALESCE(productive_min,0)
+         FROM activity_snapshots WHERE captured_at LIKE ?1 ORDER BY captured_at"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let snaps = stmt.query_map(rusqlite::params![format!("{date}%")], |r| Ok((
+        r.get::<_, String>(0)?, r.get::<_, f64>(1)?, r.get::<_, i64>(2)?, r.get::<_, f64>(3)?, r.get::<_, f64>(4)?,
+    ))).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok())
+        .filter_map(|(at, idle, locked, dist, prod)| Some(Snap {
+            minute: hm_to_min(at.get(11..16)?)?,
+            afk: idle > 300.0 || locked == 1,
+            distraction: dist > prod,
+        })).collect();
+    Ok(snaps)
+}
+
+/// Rebuild the automatic blocks of one date. Returns (inserted, deleted).
+pub fn rebuild_day(conn: &rusqlite::Connection, date: &str) -> Result<(i64, i64), String> {
+    let day = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|e| format!("Invalid date: {}", e))?;
+    let now = chrono::Local::now();
+    let today = now.date_naive();
+    if day > today { return Ok((0, 0)); }
+    use chrono::Timelike;
+    let limit = if day == today { (now.hour() * 60 + now.minute()) as usize } else { DAY_MIN };
+
+    let mut types: HashMap<&str, i64> = HashMap::new();
+    for kind in [Kind::Afk, Kind::Event, Kind::Distraction, Kind::Focus, Kind::Cooking, Kind::Workout, Kind::Sleep] {
+        let id = conn.query_row(
+            "SELECT id FROM timeline_activity_types WHERE name=?1 AND is_system=1",
+            [kind.type_name()], |r| r.get(0),
+        ).map_err(|_| format!("Activity type '{}' not found", kind.type_name()))?;
+        types.insert(kind.type_name(), id);
+    }
+
+    // Manual / task / pomodoro blocks keep their minutes; accepted day plans
+    // (source 'plan') are intentions, not time spent, so they don't.
+    let placeholders = AUTO_SOURCES.iter().map(|s| format!("'{}'", s)).collect::<Vec<_>>().join(",");
+    let mut stmt = conn.prepare(&format!(
+        "SELECT start_time, end_time FROM timeline_blocks WHERE date=?1 AND source NOT IN ({}, 'plan')", placeholders
+    )).map_err(|e| format!("DB error: {}", e))?;
+    let fixed: Vec<(usize, usize)> = stmt.query_map([date], |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?)))
+        .map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok())
+        .filter_map(|(s, e)| {
+            let s = hm_to_min(&s)?;
+            let e = hm_to_min(&e).filter(|e| *e > s).unwrap_or(limit.max(s));
+            Some((s, e))
+        }).collect();
+
+    let mut spans = collect_spans(conn, day)?;
+    spans.extend(snapshot_spans(&load_snaps(conn, date)?, limit));
+    let blocks = merge(spans, &fixed, limit);
+
+    // Diff against what's there so an unchanged day writes nothing (keeps sync quiet).
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, type_id, start_time, end_time, source, notes FROM timeline_blocks WHERE date=?1 AND source IN ({})", placeholders
+    )).map_err(|e| format!("DB error: {}", e))?;
+    let mut existing: Vec<(i64, BlockKey)> = stmt.query_map([date], |r| Ok((
+        r.get(0)?, (r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?, r.get(5)?),
+    ))).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+
+    let tx = conn.unchecked_transaction().map_err(|e| format!("DB error: {}", e))?;
+    let mut inserted = 0i64;
+    for b in &blocks {
+        let key = (types[b.kind.type_name()], min_to_hm(b.start), min_to_hm(b.end), b.kind.source().to_string(), b.notes.clone());
+        if let Some(pos) = existing.iter().position(|(_, k)| *k == key) {
+            existing.swap_remove(pos);
+            continue;
+        }
+        tx.execute(
+            "INSERT INTO timeline_blocks (type_id, date, start_time, end_time, duration_minutes, source, notes) VALUES (?1,?2,?3,?4,?5,?6,?7)",
+            rusqlite::params![key.0, date, key.1, key.2, (b.end - b.start) as i64, key.3, key.4],
+        ).map_err(|e| format!("DB error: {}", e))?;
+        inserted += 1;
+    }
+    for (id, _) in &existing {
+        tx.execute("DELETE FROM timeline_blocks WHERE id=?1", [id]).map_err(|e| format!("DB error: {}", e))?;
+    }
+    tx.commit().map_err(|e| format!("DB error: {}", e))?;
+    Ok((inserted, existing.len() as i64))
+}
+
+// ── Commands ──
+
+/// Rebuild one date's automatic timeline from all sources.
+#[tauri::command]
+pub fn reconstruct_timeline(date: String, db: tauri::State<'_, HanniDb>) -> Result<serde_json::Value, String> {
+    let (inserted, deleted) = rebuild_day(&db.conn(), &date)?;
+    Ok(serde_json::json!({ "date": date, "inserted": inserted, "deleted": deleted }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(kind: Kind, s: &str, e: &str) -> Span {
+        Span { kind, start: hm_to_min(s).unwrap(), end: hm_to_min(e).unwrap(), notes: String::new() }
+    }
+
+    #[test]
+    fn higher_priority_wins_around_fixed_blocks() {
+        let spans = vec![
+            span(Kind::Afk, "08:00", "12:00"),
+            span(Kind::Event, "09:00", "10:00"),
+            span(Kind::Focus, "09:30", "11:00"),
+            span(Kind::Workout, "07:30", "08:30"),
+            span(Kind::Focus, "23:15", "24:00"),
+        ];
+        let fixed = [(hm_to_min("10:30").unwrap(), hm_to_min("10:45").unwrap())];
+        let got: Vec<(Kind, String, String)> = merge(spans, &fixed, DAY_MIN).into_iter()
+            .map(|b| (b.kind, min_to_hm(b.start), min_to_hm(b.end))).collect();
+        assert_eq!(got, vec![
+            (Kind::Workout, "07:30".into(), "08:30".into()),
+            (Kind::Afk, "08:30".into(), "09:00".into()),
+            (Kind::Event, "09:00".into(), "09:30".into()),
+            (Kind::Focus, "09:30".into(), "10:30".into()),
+            (Kind::Focus, "10:45".into(), "11:00".into()),
+            (Kind::Afk, "11:00".into(), "12:00".into()),
+            (Kind::Focus, "23:15".into(), "24:00".into()),
+        ]);
+    }
+
+    #[test]
+    fn snapshots_smooth_short_runs_and_fill_gaps() {
+        let snap = |t: &str, afk, distraction| Snap { minute: hm_to_min(t).unwrap(), afk, distraction };
+        let snaps = vec![
+            snap("10:00", false, false), snap("10:03", false, false),
+            snap("10:05", false, true), snap("10:07", false, false), // 2-min glance → absorbed
+            snap("10:10", false, false),
+            snap("10:40", false, false), snap("10:44", false, true), snap("10:48", false, true),
+            snap("10:52", false, true),
+        ];
+        let got: Vec<(Kind, String, String)> = snapshot_spans(&snaps, DAY_MIN).into_iter()
+            .map(|b| (b.kind, min_to_hm(b.start), min_to_hm(b.end))).collect();
+        assert_eq!(got, vec![
+            (Kind::Focus, "10:00".into(), "10:11".into()),
+            // 4 min back at the desk before the distraction → still counted as away.
+            (Kind::Afk, "10:11".into(), "10:44".into()),
+            (Kind::Distraction, "10:44".into(), "10:53".into()),
+        ]);
+        // Today: nothing past the current minute.
+        assert!(snapshot_spans(&snaps, hm_to_min("10:20").unwrap()).iter().all(|s| s.end <= 620));
+    }
+
+    #[test]
+    fn parses_times() {
+        assert_eq!(hm_to_min("07:45"), Some(465));
+        assert_eq!(hm_to_min("24:00"), Some(1440));
+        assert_eq!(hm_to_min("--:--"), None);
+        assert_eq!(min_to_hm(1440), "24:00");
+    }
+}
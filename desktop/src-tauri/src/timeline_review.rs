@@ -0,0 +1,416 @@
+// timeline_review.rs — Weekly review: time by activity type, timeline goals hit/missed,
+// top distractions (activity snapshots), completed tasks, workouts and spending for
+// one Mon–Sun week, rendered as markdown and saved as a note (tag `weekly-review`).
+// The LLM can add a short summary on top; the review itself never depends on it.
+use crate::types::*;
+use chrono::NaiveDate;
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager};
+
+pub const REVIEW_TAG: &str = "weekly-review";
+const AUTO_KEY: &str = "weekly_review_auto";
+/// Monday of the latest week a review was generated for (auto or manual).
+const DONE_KEY: &str = "weekly_review_done";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TypeTime {
+    pub icon: String,
+    pub name: String,
+    pub minutes: i64,
+    pub prev_minutes: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GoalLine {
+    pub label: String,
+    pub period: String,
+    pub passed: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReviewData {
+    pub start: String,
+    pub end: String,
+    pub by_type: Vec<TypeTime>,
+    pub goals: Vec<GoalLine>,
+    pub distractions: Vec<(String, f64)>,
+    pub tasks: Vec<String>,
+    pub workouts: Vec<(String, i64)>,
+    /// (currency, category, amount)
+    pub spending: Vec<(String, String, f64)>,
+}
+
+fn fmt_minutes(m: i64) -> String {
+    if m >= 60 { format!("{}ч {:02}м", m / 60, m % 60) } else { format!("{}м", m) }
+}
+
+fn goal_label(operator: &str, target: i64, period: &str, icon: &str, name: &str) -> String {
+    let per = match period { "weekly" => "в неделю", "monthly" => "в месяц", _ => "в день" };
+    format!("{} {} {} {} {}", icon, name, operator, fmt_minutes(target), per)
+}
+
+pub fn render_review(d: &ReviewData) -> String {
+    let mut md = format!("# Недельный обзор {} — {}\n", d.start, d.end);
+
+    md.push_str("\n## Время по типам активности\n");
+    let tracked: Vec<&TypeTime> = d.by_type.iter().filter(|t| t.minutes > 0 || t.prev_minutes > 0).collect();
+    if tracked.is_empty() { md.push_str("Нет записей в таймлайне.\n"); }
+    for t in tracked {
+        let diff = t.minutes - t.prev_minutes;
+        let trend = match diff.signum() {
+            1 => format!(" (+{})", fmt_minutes(diff)),
+            -1 => format!(" (−{})", fmt_minutes(-diff)),
+            _ => String::new(),
+        };
+        md.push_str(&format!("- {} {}: {}{}\n", t.icon, t.name, fmt_minutes(t.minutes), trend));
+    }
+
+    if !d.goals.is_empty() {
+        md.push_str("\n## Цели\n");
+        for g in &d.goals {
+            let mark = if g.total > 0 && g.passed == g.total { "✅" } else if g.passed == 0 { "❌" } else { "➖" };
+            let detail = if g.period == "daily" { format!("{} из {} дней", g.passed, g.total) }
+                else if g.passed > 0 { "выполнена".to_string() } else { "не выполнена".to_string() };
+            md.push_str(&format!("- {} {} — {}\n", mark, g.label, detail));
+        }
+    }
+
+    if !d.distractions.is_empty() {
+        md.push_str("\n## Главные отвлечения\n");
+        for (name, min) in &d.distractions {
+            md.push_str(&format!("- {}: {}\n", name, fmt_minutes(min.round() as i64)));
+        }
+    }
+
+    md.push_str(&format!("\n## Сделано ({})\n", d.tasks.len()));
+    if d.tasks.is_empty() { md.push_str("Нет завершённых задач.\n"); }
+    for t in d.tasks.iter().take(30) { md.push_str(&format!("- {}\n", t)); }
+    if d.tasks.len() > 30 { md.push_str(&format!("- …и ещё {}\n", d.tasks.len() - 30)); }
+
+    if !d.workouts.is_empty() {
+        let total: i64 = d.workouts.iter().map(|w| w.1).sum();
+        md.push_str(&format!("\n## Тренировки ({}, {})\n", d.workouts.len(), fmt_minutes(total)));
+        for (title, min) in &d.workouts { md.push_str(&format!("- {} — {}\n", title, fmt_minutes(*min))); }
+    }
+
+    if !d.spending.is_empty() {
+        md.push_str("\n## Расходы\n");
+        let mut totals: Vec<(String, f64)> = Vec::new();
+        for (cur, _, amount) in &d.spending {
+            match totals.iter_mut().find(|t| &t.0 == cur) {
+                Some(t) => t.1 += amount,
+                None => totals.push((cur.clone(), *amount)),
+            }
+        }
+        for (cur, total) in &totals { md.push_str(&format!("Итого: {:.0} {}\n", total, cur)); }
+        for (cur, cat, amount) in d.spending.iter().take(8) {
+            md.push_str(&format!("- {}: {:.0} {}\n", cat, amount, cur));
+        }
+    }
+    md
+}
+
+/// Monday of the last full week before `today`.
+pub fn last_full_week(today: NaiveDate) -> NaiveDate {
+    crate::timeline_stats::period_range("weekly", today).0 - chrono::Duration::days(7)
+}
+
+fn collect(conn: &rusqlite::Connection, start: NaiveDate) -> Result<ReviewData, String> {
+    let end = start + chrono::Duration::days(6);
+    let (s, e) = (start.format("%Y-%m-%d").to_string(), end.format("%Y-%m-%d").to_string());
+    let (ps, pe) = ((start - chrono::Duration::days(7)).format("%Y-%m-%d").to_string(), (start - chrono::Duration::days(1)).format("%Y-%m-%d").to_string());
+    let mut d = ReviewData { start: s.clone(), end: e.clone(), ..Default::default() };
+
+    let mut stmt = conn.prepare(
+        "SELECT t.icon, t.name,
+                COALESCE((SELECT SUM(duration_minutes) FROM timeline_blocks b WHERE b.type_id=t.id AND b.date BETWEEN ?1 AND ?2 AND b.source != 'plan'), 0),
+                COALESCE((SELECT SUM(duration_minutes) FROM timeline_blocks b WHERE b.type_id=t.id AND b.date BETWEEN ?3 AND ?4 AND b.source != 'plan'), 0)
+         FROM timeline_activity_types t ORDER BY 3 DESC, t.sort_order"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    d.by_type = stmt.query_map(rusqlite::params![s, e, ps, pe], |r| Ok(TypeTime {
+        icon: r.get(0)?, name: r.get(1)?, minutes: r.get(2)?, prev_minutes: r.get(3)?,
+    })).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+
+    // Goals: weekly → this week's result; daily → passed days; monthly → the month containing the week's end.
+    let mut stmt = conn.prepare(
+        "SELECT g.id, g.operator, g.target_minutes, g.period, t.icon, t.name FROM timeline_goals g
+         JOIN timeline_activity_types t ON t.id = g.type_id WHERE g.active=1 ORDER BY t.sort_order"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let goals: Vec<(i64, String, i64, String, String, String)> = stmt.query_map([], |r| Ok((
+        r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?, r.get(5)?,
+    ))).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+    for (id, op, target, period, icon, name) in goals {
+        let (from, to) = if period == "monthly" {
+            let (a, b) = crate::timeline_stats::period_range("monthly", end);
+            (a.format("%Y-%m-%d").to_string(), b.format("%Y-%m-%d").to_string())
+        } else { (s.clone(), e.clone()) };
+        let (passed, total): (i64, i64) = conn.query_row(
+            "SELECT COALESCE(SUM(passed),0), COUNT(*) FROM timeline_goal_results WHERE goal_id=?1 AND period_start BETWEEN ?2 AND ?3",
+            rusqlite::params![id, from, to], |r| Ok((r.get(0)?, r.get(1)?)),
+        ).unwrap_or((0, 0));
+        if total == 0 { continue; }
+        d.goals.push(GoalLine { label: goal_label(&op, target, &period, &icon, &name), period, passed: passed as usize, total: total as usize });
+    }
+
+    // Distractions: snapshot minutes by site (browser domain) or app.
+    let mut stmt = conn.prepare(
+        "SELECT frontmost_app, browser_url, distraction_min FROM activity_snapshots
+         WHERE substr(captured_at,1,10) BETWEEN ?1 AND ?2 AND distraction_min > 0"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let mut distractions: HashMap<String, f64> = HashMap::new();
+    for (app, url, min) in stmt.query_map(rusqlite::params![s, e], |r| Ok((
+        r.get::<_, String>(0)?, r.get::<_, Option<String>>(1)?.unwrap_or_default(), r.get::<_, f64>(2)?,
+    ))).map_err(|e| format!("Query error: {}", e))?.flatten() {
+        let key = crate::activity_rules::domains_in(&url).into_iter().next().unwrap_or(app);
+        if key.is_empty() { continue; }
+        *distractions.entry(key).or_default() += min;
+    }
+    let mut distractions: Vec<(String, f64)> = distractions.into_iter().collect();
+    distractions.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    distractions.truncate(5);
+    d.distractions = distractions;
+
+    // Completed tasks across calendar events, schedules and note-tasks.
+    let mut stmt = conn.prepare(
+        "SELECT title FROM events WHERE completed=1 AND date BETWEEN ?1 AND ?2
+         UNION ALL
+         SELECT s.title FROM schedule_completions c JOIN schedules s ON s.id = c.schedule_id
+           WHERE c.status='done' AND c.date BETWEEN ?1 AND ?2
+         UNION ALL
+         SELECT title FROM notes WHERE status='done' AND substr(updated_at,1,10) BETWEEN ?1 AND ?2"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for t in stmt.query_map(rusqlite::params![s, e], |r| r.get::<_, String>(0)).map_err(|e| format!("Query error: {}", e))?.flatten() {
+        match counts.iter_mut().find(|c| c.0 == t) {
+            Some(c) => c.1 += 1,
+            None => counts.push((t, 1)),
+        }
+    }
+    d.tasks = counts.into_iter().map(|(t, n)| if n > 1 { format!("{} ×{}", t, n) } else { t }).collect();
+
+    let mut stmt = conn.prepare(
+        "SELECT CASE WHEN title != '' THEN title ELSE type END, COALESCE(duration_minutes,0) FROM workouts
+         WHERE substr(date,1,10) BETWEEN ?1 AND ?2 ORDER BY date"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    d.workouts = stmt.query_map(rusqlite::params![s, e], |r| Ok((r.get(0)?, r.get(1)?)))
+        .map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+
+    let mut stmt = conn.prepare(
+        "SELECT currency, category, SUM(amount) FROM transactions
+         WHERE type='expense' AND substr(date,1,10) BETWEEN ?1 AND ?2 GROUP BY currency, category ORDER BY 3 DESC"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    d.spending = stmt.query_map(rusqlite::params![s, e], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))
+        .map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+    Ok(d)
+}
+
+/// Insert the review note, or replace the one already saved for that week.
+fn save_note(conn: &rusqlite::Connection, title: &str, content: &str) -> Result<i64, String> {
+    let now = chrono::Local::now().to_rfc3339();
+    let existing: Option<i64> = conn.query_row(
+        "SELECT id FROM notes WHERE title=?1 AND tags LIKE ?2 LIMIT 1",
+        rusqlite::params![title, format!("%{}%", REVIEW_TAG)], |r| r.get(0),
+    ).ok();
+    if let Some(id) = existing {
+        conn.execute("UPDATE notes SET content=?1, updated_at=?2 WHERE id=?3", rusqlite::params![content, now, id])
+            .map_err(|e| format!("DB error: {}", e))?;
+        return Ok(id);
+    }
+    conn.execute(
+        "INSERT INTO notes (title, content, tags, status, created_at, updated_at) VALUES (?1, ?2, ?3, 'note', ?4, ?4)",
+        rusqlite::params![title, content, REVIEW_TAG, now],
+    ).map_err(|e| format!("DB error: {}", e))?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Build the review for the week starting `start` and save it. Goals are
+/// evaluated by the caller, once, before anything is rendered.
+pub fn build_and_save(conn: &rusqlite::Connection, start: NaiveDate, today: NaiveDate, summary: Option<&str>) -> Result<(i64, String, String), String> {
+    let data = collect(conn, start)?;
+    let mut md = render_review(&data);
+    if let Some(summary) = summary.map(str::trim).filter(|s| !s.is_empty()) {
+        let (head, rest) = md.split_once('\n').unwrap_or((&md, ""));
+        md = format!("{}\n\n## Кратко\n{}\n{}", head, summary, rest);
+    }
+    let title = format!("Недельный обзор {} — {}", data.start, data.end);
+    let id = save_note(conn, &title, &md)?;
+    mark_reviewed(conn, start, today);
+    Ok((id, title, md))
+}
+
+fn reviewed_through(conn: &rusqlite::Connection) -> Option<NaiveDate> {
+    conn.query_row("SELECT value FROM app_settings WHERE key=?1", [DONE_KEY], |r| r.get::<_, String>(0)).ok()
+        .and_then(|v| NaiveDate::parse_from_str(&v, "%Y-%m-%d").ok())
+}
+
+/// Remember `start` as reviewed once its week is over; a review of the running
+/// week is only a draft, and regenerating an older week doesn't move it back.
+fn mark_reviewed(conn: &rusqlite::Connection, start: NaiveDate, today: NaiveDate) {
+    if start + chrono::Duration::days(6) >= today { return; }
+    if reviewed_through(conn).is_some_and(|d| d >= start) { return; }
+    let _ = conn.execute(
+        "INSERT INTO app_settings (key, value) VALUES (?1, ?2) ON CONFLICT(key) DO UPDATE SET value=?2",
+        rusqlite::params![DONE_KEY, start.format("%Y-%m-%d").to_string()],
+    );
+}
+
+async fn llm_summary(app: &AppHandle, review: &str) -> Result<String, String> {
+    let llm_state = app.state::<LlmBusy>();
+    let _permit = tokio::time::timeout(std::time::Duration::from_secs(60), llm_state.0.acquire()).await
+        .map_err(|_| "LLM busy — timeout".to_string())?
+        .map_err(|_| "LLM semaphore closed".to_string())?;
+    let client = &app.state::<HttpClient>().0;
+    let request = ChatRequest {
+        model: llm_model(),
+        messages: vec![
+            ChatMessage::text("system", "Ты — Ханни, личный ассистент. Пишешь короткие, честные и тёплые итоги недели."),
+            ChatMessage::text("user", &format!(
+                "Вот данные за неделю. Напиши 3–5 предложений: что получилось, что нет, и один конкретный совет на следующую неделю. Без заголовков и списков.\n\n{}\n/no_think",
+                review
+            )),
+        ],
+        max_tokens: 600,
+        stream: false,
+        temperature: 0.5,
+        repetition_penalty: None,
+        chat_template_kwargs: ChatTemplateKwargs { enable_thinking: false },
+        tools: None,
+    };
+    tokio::task::spawn_blocking(crate::mlx_manager::ensure_mlx).await.ok();
+    let response = client.post(llm_chat_url()).json(&request)
+        .timeout(std::time::Duration::from_secs(120))
+        .send().await.map_err(|e| format!("LLM error: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("LLM error {}", response.status()));
+    }
+    let parsed: NonStreamResponse = response.json().await.map_err(|e| format!("Parse error: {}", e))?;
+    let raw = parsed.choices.first().map(|c| c.message.content.clone()).unwrap_or_default();
+    let re = regex::Regex::new(r"(?s)<think>.*?</think>").unwrap();
+    Ok(re.replace_all(&raw, "").trim().to_string())
+}
+
+/// Auto mode (setting `weekly_review_auto` = "true"): once last week is over,
+/// save its review without the LLM unless that week was already reviewed.
+pub fn auto_review(conn: &rusqlite::Connection, today: NaiveDate) {
+    let on = conn.query_row("SELECT value FROM app_settings WHERE key=?1", [AUTO_KEY], |r| r.get::<_, String>(0))
+        .map(|v| v == "true").unwrap_or(false);
+    if !on { return; }
+    let start = last_full_week(today);
+    if reviewed_through(conn).is_some_and(|d| d >= start) { return; }
+    crate::timeline_stats::evaluate_goals(conn, today);
+    if let Err(e) = build_and_save(conn, start, today, None) { eprintln!("[weekly-review] {}", e); }
+}
+
+// ── Commands ──
+
+/// Build and save the review for the week starting `week_start` (any date in
+/// the week works; default = last full week). `summarize` adds an LLM summary;
+/// if the model is unavailable the review is saved without it.
+#[tauri::command]
+pub async fn generate_weekly_review(week_start: Option<String>, summarize: Option<bool>, app: AppHandle) -> Result<serde_json::Value, String> {
+    let today = chrono::Local::now().date_naive();
+    let start = match week_start.as_deref() {
+        Some(w) => crate::timeline_stats::period_range("weekly", NaiveDate::parse_from_str(w, "%Y-%m-%d").map_err(|e| format!("Invalid date: {}", e))?).0,
+        None => last_full_week(today),
+    };
+    let draft = {
+        let db = app.state::<HanniDb>();
+        let conn = db.conn();
+        crate::timeline_stats::evaluate_goals(&conn, today);
+        render_review(&collect(&conn, start)?)
+    };
+    let (summary, llm_error) = if summarize.unwrap_or(false) {
+        match llm_summary(&app, &draft).await {
+            Ok(s) => (Some(s), None),
+            Err(e) => (None, Some(e)),
+        }
+    } else { (None, None) };
+    let db = app.state::<HanniDb>();
+    let (id, title, content) = build_and_save(&db.conn(), start, today, summary.as_deref())?;
+    Ok(serde_json::json!({ "note_id": id, "title": title, "content": content, "llm_error": llm_error }))
+}
+
+#[tauri::command]
+pub fn get_weekly_reviews(limit: Option<i64>, db: tauri::State<'_, HanniDb>) -> Result<Vec<serde_json::Value>, String> {
+    let conn = db.read();
+    let mut stmt = conn.prepare(
+        "SELECT id, title, content, updated_at FROM notes WHERE tags LIKE ?1 AND archived=0 ORDER BY title DESC LIMIT ?2"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let rows = stmt.query_map(rusqlite::params![format!("%{}%", REVIEW_TAG), limit.unwrap_or(12)], |r| Ok(serde_json::json!({
+        "id": r.get::<_, i64>(0)?, "title": r.get::<_, String>(1)?,
+        "content": r.get::<_, String>(2)?, "updated_at": r.get::<_, String>(3)?,
+    }))).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::d;
+
+    #[test]
+    fn picks_last_full_week() {
+        assert_eq!(last_full_week(d("2024-06-10")), d("2024-06-03"));
+        assert_eq!(last_full_week(d("2024-06-16")), d("2024-06-03"));
+    }
+
+    #[test]
+    fn remembers_the_latest_reviewed_week() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE app_settings (key TEXT PRIMARY KEY, value TEXT)").unwrap();
+        let today = d("2024-06-18");
+        assert_eq!(reviewed_through(&conn), None);
+        mark_reviewed(&conn, d("2024-06-03"), today);
+        assert_eq!(reviewed_through(&conn), Some(d("2024-06-03")));
+        // Regenerating an older week keeps the marker on the newest one.
+        mark_reviewed(&conn, d("2024-05-27"), today);
+        assert_eq!(reviewed_through(&conn), Some(d("2024-06-03")));
+        // The running week (Sunday 23rd is still ahead) is only a draft.
+        mark_reviewed(&conn, d("2024-06-17"), today);
+        assert_eq!(reviewed_through(&conn), Some(d("2024-06-03")));
+        mark_reviewed(&conn, d("2024-06-10"), today);
+        assert_eq!(reviewed_through(&conn), Some(d("2024-06-10")));
+        // A week ending on Sunday is over on Monday, not before.
+        mark_reviewed(&conn, d("2024-06-17"), d("2024-06-23"));
+        assert_eq!(reviewed_through(&conn), Some(d("2024-06-10")));
+        mark_reviewed(&conn, d("2024-06-17"), d("2024-06-24"));
+        assert_eq!(reviewed_through(&conn), Some(d("2024-06-17")));
+    }
+
+    #[test]
+    fn renders_sections() {
+        let data = ReviewData {
+            start: "2024-06-03".into(), end: "2024-06-09".into(),
+            by_type: vec![
+                TypeTime { icon: "💻".into(), name: "Фокус".into(), minutes: 600, prev_minutes: 480 },
+                TypeTime { icon: "🏋️".into(), name: "Спорт".into(), minutes: 0, prev_minutes: 0 },
+            ],
+            goals: vec![
+                GoalLine { label: "💻 Фокус >= 2ч 00м в день".into(), period: "daily".into(), passed: 4, total: 7 },
+                GoalLine { label: "🌙 Сон >= 49ч 00м в неделю".into(), period: "weekly".into(), passed: 1, total: 1 },
+            ],
+            distractions: vec![("youtube.com".into(), 95.4)],
+            tasks: vec!["Отчёт".into()],
+            workouts: vec![("Бег".into(), 40), ("Силовая".into(), 60)],
+            spending: vec![("KZT".into(), "food".into(), 12000.0), ("KZT".into(), "taxi".into(), 3000.0)],
+        };
+        let md = render_review(&data);
+        assert!(md.starts_with("# Недельный обзор 2024-06-03 — 2024-06-09\n"));
+        assert!(md.contains("- 💻 Фокус: 10ч 00м (+2ч 00м)\n"));
+        assert!(!md.contains("Спорт:"));
+        assert!(md.contains("- ➖ 💻 Фокус >= 2ч 00м в день — 4 из 7 дней\n"));
+        assert!(md.contains("- ✅ 🌙 Сон >= 49ч 00м в неделю — выполнена\n"));
+        assert!(md.contains("- youtube.com: 1ч 35м\n"));
+        assert!(md.contains("## Тренировки (2, 1ч 40м)"));
+        assert!(md.contains("Итого: 15000 KZT\n"));
+    }
+
+    #[test]
+    fn empty_week_still_renders() {
+        let md = render_review(&ReviewData { start: "2024-06-03".into(), end: "2024-06-09".into(), ..Default::default() });
+        assert!(md.contains("Нет записей в таймлайне."));
+        assert!(md.contains("## Сделано (0)\nНет завершённых задач."));
+        assert!(!md.contains("## Цели"));
+    }
+}
@@ -1,4 +1,4 @@
-// timeline_stats.rs — Timeline stats, goals CRUD, AFK sync
+// timeline_stats.rs — Timeline stats, goals CRUD + evaluation (pass/fail history, streaks), AFK sync
 use crate::types::HanniDb;
 
 // ── Goals CRUD ──
@@ -48,6 +48,7 @@ pub fn update_timeline_goal(id: i64, operator: Option<String>, target_minutes: O
 #[tauri::command]
 pub fn delete_timeline_goal(id: i64, db: tauri::State<'_, HanniDb>) -> Result<(), String> {
     let conn = db.conn();
+    conn.execute("DELETE FROM timeline_goal_results WHERE goal_id=?1", rusqlite::params![id]).ok();
     conn.execute("DELETE FROM timeline_goals WHERE id=?1", rusqlite::params![id])
         .map_err(|e| format!("DB error: {}", e))?;
     Ok(())
@@ -61,7 +62,7 @@ pub fn get_timeline_day_stats(date: String, db: tauri::State<'_, HanniDb>) -> Re
     let mut stmt = conn.prepare(
         "SELECT t.id, t.name, t.color, t.icon, COALESCE(SUM(b.duration_minutes),0) as total
          FROM timeline_activity_types t
-         LEFT JOIN timeline_blocks b ON b.type_id = t.id AND b.date=?1
+         LEFT JOIN timeline_blocks b ON b.type_id = t.id AND b.date=?1 AND b.source != 'plan'
          GROUP BY t.id ORDER BY t.sort_order"
     ).map_err(|e| format!("DB error: {}", e))?;
     let per_type: Vec<serde_json::Value> = stmt.query_map(rusqlite::params![date], |row| {
@@ -74,7 +75,7 @@ pub fn get_timeline_day_stats(date: String, db: tauri::State<'_, HanniDb>) -> Re
         }))
     }).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
     let total: i64 = conn.query_row(
-        "SELECT COALESCE(SUM(duration_minutes),0) FROM timeline_blocks WHERE date=?1",
+        "SELECT COALESCE(SUM(duration_minutes),0) FROM timeline_blocks WHERE date=?1 AND source != 'plan'",
         rusqlite::params![date], |r| r.get(0)
     ).unwrap_or(0);
     Ok(serde_json::json!({ "date": date, "total_minutes": total, "per_type": per_type }))
@@ -89,7 +90,7 @@ pub fn get_timeline_range_stats(start_date: String, end_date: String, db: tauri:
     let mut stmt = conn.prepare(
         "SELECT t.id, t.name, t.color, t.icon, COALESCE(SUM(b.duration_minutes),0) as total
          FROM timeline_activity_types t
-         LEFT JOIN timeline_blocks b ON b.type_id = t.id AND b.date BETWEEN ?1 AND ?2
+         LEFT JOIN timeline_blocks b ON b.type_id = t.id AND b.date BETWEEN ?1 AND ?2 AND b.source != 'plan'
          GROUP BY t.id ORDER BY t.sort_order"
     ).map_err(|e| format!("DB error: {}", e))?;
     let current: Vec<serde_json::Value> = stmt.query_map(rusqlite::params![start_date, end_date], |row| {
@@ -117,7 +118,7 @@ pub fn get_timeline_range_stats(start_date: String, end_date: String, db: tauri:
     let mut stmt2 = conn.prepare(
         "SELECT t.id, COALESCE(SUM(b.duration_minutes),0)
          FROM timeline_activity_types t
-         LEFT JOIN timeline_blocks b ON b.type_id = t.id AND b.date BETWEEN ?1 AND ?2
+         LEFT JOIN timeline_blocks b ON b.type_id = t.id AND b.date BETWEEN ?1 AND ?2 AND b.source != 'plan'
          GROUP BY t.id ORDER BY t.sort_order"
     ).map_err(|e| format!("DB error: {}", e))?;
     let previous: Vec<(i64, i64)> = stmt2.query_map(rusqlite::params![prev_start, prev_end], |row| {
@@ -134,3 +135,181 @@ pub fn get_timeline_range_stats(start_date: String, end_date: String, db: tauri:
     }))
 }
 
+
+// ── Goal evaluation ──
+
+/// First and last date of the daily / weekly (Mon–Sun) / monthly period that
+/// contains `date`.
+pub fn period_range(period: &str, date: chrono::NaiveDate) -> (chrono::NaiveDate, chrono::NaiveDate) {
+    use chrono::Datelike;
+    match period {
+        "weekly" => {
+            let start = date - chrono::Duration::days(date.weekday().num_days_from_monday() as i64);
+            (start, start + chrono::Duration::days(6))
+        }
+        "monthly" => {
+            let start = date.with_day(1).unwrap_or(date);
+            let next = if start.month() == 12 {
+                chrono::NaiveDate::from_ymd_opt(start.year() + 1, 1, 1)
+            } else {
+                chrono::NaiveDate::from_ymd_opt(start.year(), start.month() + 1, 1)
+            };
+            (start, next.map(|n| n - chrono::Duration::days(1)).unwrap_or(start))
+        }
+        _ => (date, date),
+    }
+}
+
+pub fn goal_met(operator: &str, actual: i64, target: i64) -> bool {
+    match operator {
+        ">=" => actual >= target,
+        ">" => actual > target,
+        "<" => actual < target,
+        "=" | "==" => actual == target,
+        _ => actual <= target,
+    }
+}
+
+/// Status of a period: closed periods are simply pass/fail; the running one
+/// is "pass" once a minimum is reached, "fail" once a maximum is exceeded,
+/// otherwise "pending".
+pub fn period_status(operator: &str, actual: i64, target: i64, closed: bool) -> &'static str {
+    let met = goal_met(operator, actual, target);
+    if closed { return if met { "pass" } else { "fail" }; }
+    match operator {
+        ">=" | ">" if met => "pass",
+        "<=" | "<" if !met => "fail",
+        _ => "pending",
+    }
+}
+
+/// (current, best) runs of passes; `results` is oldest first.
+pub fn streaks(results: &[bool]) -> (usize, usize) {
+    let current = results.iter().rev().take_while(|p| **p).count();
+    let best = results.split(|p| !*p).map(<[bool]>::len).max().unwrap_or(0);
+    (current, best)
+}
+
+/// How far back closed periods are (re)evaluated.
+fn backfill_days(period: &str) -> i64 {
+    match period { "monthly" => 366, "weekly" => 7 * 26, _ => 90 }
+}
+
+fn minutes_between(conn: &rusqlite::Connection, type_id: i64, start: &str, end: &str) -> i64 {
+    conn.query_row(
+        "SELECT COALESCE(SUM(duration_minutes),0) FROM timeline_blocks WHERE type_id=?1 AND date BETWEEN ?2 AND ?3 AND source != 'plan'",
+        rusqlite::params![type_id, start, end], |r| r.get(0),
+    ).unwrap_or(0)
+}
+
+/// Record pass/fail for every closed period of every active goal within the
+/// backfill window. Blocks can be edited after the fact, so actuals are upserted.
+pub fn evaluate_goals(conn: &rusqlite::Connection, today: chrono::NaiveDate) {
+    let goals: Vec<(i64, i64, String, i64, String, String)> = conn.prepare(
+        "SELECT id, type_id, operator, target_minutes, period, substr(created_at,1,10) FROM timeline_goals WHERE active=1"
+    ).and_then(|mut stmt| stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?, r.get(5)?)))?.collect())
+        .unwrap_or_default();
+    let now = chrono::Local::now().to_rfc3339();
+    for (id, type_id, operator, target, period, created) in goals {
+        let created = chrono::NaiveDate::parse_from_str(&created, "%Y-%m-%d").unwrap_or(today);
+        let from = created.max(today - chrono::Duration::days(backfill_days(&period)));
+        let (mut start, mut end) = period_range(&period, from);
+        while end < today {
+            let (s, e) = (start.format("%Y-%m-%d").to_string(), end.format("%Y-%m-%d").to_string());
+            let actual = minutes_between(conn, type_id, &s, &e);
+            // Target/operator are frozen at first evaluation — editing a goal
+            // doesn't rewrite its past; only the actual minutes are refreshed.
+            let (target, operator) = conn.query_row(
+                "SELECT target_minutes, operator FROM timeline_goal_results WHERE goal_id=?1 AND period_start=?2",
+                rusqlite::params![id, s], |r| Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?)),
+            ).unwrap_or((target, operator.clone()));
+            conn.execute(
+                "INSERT INTO timeline_goal_results (goal_id, period_start, period_end, actual_minutes, target_minutes, operator, passed, evaluated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                 ON CONFLICT(goal_id, period_start) DO UPDATE SET period_end=?3, actual_minutes=?4, target_minutes=?5, operator=?6, passed=?7, evaluated_at=?8",
+                rusqlite::params![id, s, e, actual, target, operator, goal_met(&operator, actual, target) as i32, now],
+            ).ok();
+            (start, end) = period_range(&period, end + chrono::Duration::days(1));
+        }
+    }
+}
+
+/// (id, type_id, operator, target_minutes, period, name, color, icon)
+type GoalRow = (i64, i64, String, i64, String, String, String, String);
+
+/// Active goals with the running period's progress, recent pass/fail history
+/// and streaks. Evaluates closed periods first.
+#[tauri::command]
+pub fn get_timeline_goal_progress(history: Option<usize>, db: tauri::State<'_, HanniDb>) -> Result<Vec<serde_json::Value>, String> {
+    let today = chrono::Local::now().date_naive();
+    let conn = db.conn();
+    evaluate_goals(&conn, today);
+    let mut stmt = conn.prepare(
+        "SELECT g.id, g.type_id, g.operator, g.target_minutes, g.period, t.name, t.color, t.icon
+         FROM timeline_goals g JOIN timeline_activity_types t ON t.id = g.type_id WHERE g.active=1 ORDER BY t.sort_order"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let goals: Vec<GoalRow> = stmt.query_map([], |r| Ok((
+        r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?, r.get(5)?, r.get(6)?, r.get(7)?,
+    ))).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+    let mut out = Vec::new();
+    for (id, type_id, operator, target, period, name, color, icon) in goals {
+        let (start, end) = period_range(&period, today);
+        let (s, e) = (start.format("%Y-%m-%d").to_string(), end.format("%Y-%m-%d").to_string());
+        let actual = minutes_between(&conn, type_id, &s, &e);
+        let mut hist = conn.prepare(
+            "SELECT period_start, period_end, actual_minutes, passed FROM timeline_goal_results WHERE goal_id=?1 ORDER BY period_start"
+        ).map_err(|e| format!("DB error: {}", e))?;
+        let results: Vec<(String, String, i64, bool)> = hist.query_map([id], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get::<_, i64>(3)? == 1)))
+            .map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+        let passes: Vec<bool> = results.iter().map(|r| r.3).collect();
+        let (current_streak, best_streak) = streaks(&passes);
+        let passed = passes.iter().filter(|p| **p).count();
+        out.push(serde_json::json!({
+            "id": id, "type_id": type_id, "type_name": name, "type_color": color, "type_icon": icon,
+            "operator": operator, "target_minutes": target, "period": period,
+            "period_start": s, "period_end": e, "actual_minutes": actual,
+            "status": period_status(&operator, actual, target, false),
+            "current_streak": current_streak, "best_streak": best_streak,
+            "pass_rate": if passes.is_empty() { serde_json::Value::Null } else { serde_json::json!(passed as f64 / passes.len() as f64) },
+            "history": results.iter().rev().take(history.unwrap_or(14)).rev().map(|(ps, pe, a, p)| serde_json::json!({
+                "period_start": ps, "period_end": pe, "actual_minutes": a, "passed": p,
+            })).collect::<Vec<_>>(),
+        }));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::d;
+
+    #[test]
+    fn period_ranges() {
+        assert_eq!(period_range("daily", d("2024-06-05")), (d("2024-06-05"), d("2024-06-05")));
+        assert_eq!(period_range("weekly", d("2024-06-05")), (d("2024-06-03"), d("2024-06-09")));
+        assert_eq!(period_range("weekly", d("2024-06-09")), (d("2024-06-03"), d("2024-06-09")));
+        assert_eq!(period_range("monthly", d("2024-02-14")), (d("2024-02-01"), d("2024-02-29")));
+        assert_eq!(period_range("monthly", d("2024-12-31")), (d("2024-12-01"), d("2024-12-31")));
+    }
+
+    #[test]
+    fn statuses() {
+        assert_eq!(period_status(">=", 120, 120, true), "pass");
+        assert_eq!(period_status(">=", 60, 120, true), "fail");
+        assert_eq!(period_status(">=", 60, 120, false), "pending");
+        assert_eq!(period_status(">=", 130, 120, false), "pass");
+        assert_eq!(period_status("<=", 90, 60, false), "fail");
+        assert_eq!(period_status("<=", 30, 60, false), "pending");
+        assert_eq!(period_status("<=", 30, 60, true), "pass");
+        assert!(!goal_met("<", 60, 60));
+    }
+
+    #[test]
+    fn counts_streaks() {
+        assert_eq!(streaks(&[]), (0, 0));
+        assert_eq!(streaks(&[true, true, false, true]), (1, 2));
+        assert_eq!(streaks(&[false, true, true, true]), (3, 3));
+        assert_eq!(streaks(&[true, false, false]), (0, 1));
+    }
+}
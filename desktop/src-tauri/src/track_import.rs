@@ -0,0 +1,838 @@
+// track_import.rs — Import GPX / TCX / FIT activity files as cardio workouts.
+// Parses the track (a light tag scanner for the XML formats, a minimal record
+// decoder for FIT), derives distance, moving time, per-km splits, elevation gain
+// and heart rate, stores the summary in `workout_tracks`, per-minute HR in
+// `heart_rate_samples` (source 'track') and best efforts for personal bests.
+use crate::types::HanniDb;
+use std::collections::HashMap;
+
+/// Seconds between the Unix epoch and the FIT epoch (1989-12-31T00:00:00Z).
+const FIT_EPOCH: i64 = 631_065_600;
+/// Below this speed (m/s) a segment counts as a pause.
+const MOVING_MPS: f64 = 0.5;
+/// Climbs smaller than this (m) are GPS / barometer noise.
+const ELEVATION_THRESHOLD_M: f64 = 3.0;
+/// Points kept in `points_json` for charts.
+const MAX_STORED_POINTS: usize = 600;
+const HR_SOURCE: &str = "track";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrackPoint {
+    /// Unix seconds.
+    pub ts: i64,
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    pub ele: Option<f64>,
+    pub hr: Option<i64>,
+    /// Cumulative distance recorded by the device, metres.
+    pub dist: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Track {
+    /// run | ride | walk | hike | swim | other
+    pub sport: String,
+    pub name: String,
+    pub calories: Option<f64>,
+    pub points: Vec<TrackPoint>,
+}
+
+pub fn normalize_sport(s: &str) -> &'static str {
+    let s = s.to_lowercase();
+    if s.contains("run") { "run" }
+    else if s.contains("bik") || s.contains("cycl") || s.contains("ride") { "ride" }
+    else if s.contains("hik") { "hike" }
+    else if s.contains("walk") { "walk" }
+    else if s.contains("swim") { "swim" }
+    else { "other" }
+}
+
+fn sport_label(sport: &str) -> &'static str {
+    match sport {
+        "run" => "Бег",
+        "ride" => "Велосипед",
+        "walk" => "Ходьба",
+        "hike" => "Поход",
+        "swim" => "Плавание",
+        _ => "Кардио",
+    }
+}
+
+/// Distances (m) tracked as best efforts per sport.
+fn effort_distances(sport: &str) -> &'static [f64] {
+    match sport {
+        "run" => &[1000.0, 5000.0, 10000.0, 21097.5, 42195.0],
+        "ride" => &[10000.0, 20000.0, 40000.0, 100000.0],
+        "swim" => &[400.0, 1000.0],
+        _ => &[],
+    }
+}
+
+fn parse_time(s: &str) -> Option<i64> {
+    let s = s.trim();
+    chrono::DateTime::parse_from_rfc3339(s).map(|d| d.timestamp()).ok().or_else(|| {
+        chrono::NaiveDateTime::parse_from_str(s.trim_end_matches('Z'), "%Y-%m-%dT%H:%M:%S%.f")
+            .ok().map(|d| d.and_utc().timestamp())
+    })
+}
+
+// ── XML scanning (GPX / TCX) ──
+
+/// Next `<tag …>…</tag>` at or after `from`, ignoring namespace prefixes.
+/// Returns (attributes of the opening tag, inner XML, end offset).
+fn next_element<'a>(xml: &'a str, tag: &str, from: usize) -> Option<(&'a str, &'a str, usize)> {
+    let mut at = from;
+    while let Some(i) = xml.get(at..)?.find('<') {
+        let start = at + i + 1;
+        let rest = &xml[start..];
+        let name_end = rest.find(|c: char| c == '>' || c == '/' || c.is_whitespace()).unwrap_or(rest.len());
+        let name = &rest[..name_end];
+        at = start;
+        if name.rsplit(':').next() != Some(tag) { continue; }
+        let gt = rest.find('>')?;
+        let attrs = &rest[name_end..gt];
+        if attrs.ends_with('/') { return Some((attrs, "", start + gt + 1)); }
+        let body_start = start + gt + 1;
+        let close = format!("</{}>", name);
+        let close_at = xml[body_start..].find(&close)? + body_start;
+        return Some((attrs, &xml[body_start..close_at], close_at + close.len()));
+    }
+    None
+}
+
+fn elements<'a>(xml: &'a str, tag: &str) -> Vec<(&'a str, &'a str)> {
+    let mut out = Vec::new();
+    let mut from = 0;
+    while let Some((attrs, inner, end)) = next_element(xml, tag, from) {
+        out.push((attrs, inner));
+        from = end;
+    }
+    out
+}
+
+fn first<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    next_element(xml, tag, 0).map(|(_, inner, _)| inner.trim())
+}
+
+fn attr<'a>(attrs: &'a str, name: &str) -> Option<&'a str> {
+    let pat = format!("{}=", name);
+    let mut from = 0;
+    while let Some(i) = attrs[from..].find(&pat) {
+        let at = from + i;
+        from = at + pat.len();
+        if at > 0 && !attrs[..at].ends_with(char::is_whitespace) { continue; }
+        let rest = &attrs[from..];
+        let quote = rest.chars().next()?;
+        if quote != '"' && quote != '\'' { continue; }
+        let end = rest[1..].find(quote)?;
+        return Some(&rest[1..1 + end]);
+    }
+    None
+}
+
+fn text(s: &str) -> String {
+    let s = s.trim();
+    let s = s.strip_prefix("<![CDATA[").and_then(|x| x.strip_suffix("]]>")).unwrap_or(s);
+    s.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&apos;", "'").replace("&amp;", "&").trim().to_string()
+}
+
+fn num(s: Option<&str>) -> Option<f64> {
+    s.and_then(|v| v.trim().parse::<f64>().ok()).filter(|v| v.is_finite())
+}
+
+pub fn parse_gpx(xml: &str) -> Result<Vec<Track>, String> {
+    let mut out = Vec::new();
+    for (_, trk) in elements(xml, "trk") {
+        let mut points = Vec::new();
+        for (attrs, pt) in elements(trk, "trkpt") {
+            let Some(ts) = first(pt, "time").and_then(parse_time) else { continue };
+            points.push(TrackPoint {
+                ts,
+                lat: num(attr(attrs, "lat")),
+                lon: num(attr(attrs, "lon")),
+                ele: num(first(pt, "ele")),
+                hr: num(first(pt, "hr")).map(|v| v.round() as i64),
+                dist: None,
+            });
+        }
+        if points.is_empty() { continue; }
+        // <name>/<type> sit before the first <trkseg>; don't pick up waypoint tags.
+        let head = &trk[..trk.find("trkseg").unwrap_or(trk.len())];
+        out.push(Track {
+            sport: normalize_sport(first(head, "type").unwrap_or("")).to_string(),
+            name: first(head, "name").map(text).unwrap_or_default(),
+            calories: None,
+            points,
+        });
+    }
+    if out.is_empty() { Err("В GPX нет трека с отметками времени".into()) } else { Ok(out) }
+}
+
+pub fn parse_tcx(xml: &str) -> Result<Vec<Track>, String> {
+    let mut out = Vec::new();
+    for (attrs, act) in elements(xml, "Activity") {
+        let mut points = Vec::new();
+        for (_, tp) in elements(act, "Trackpoint") {
+            let Some(ts) = first(tp, "Time").and_then(parse_time) else { continue };
+            points.push(TrackPoint {
+                ts,
+                lat: num(first(tp, "LatitudeDegrees")),
+                lon: num(first(tp, "LongitudeDegrees")),
+                ele: num(first(tp, "AltitudeMeters")),
+                hr: num(first(tp, "HeartRateBpm").and_then(|h| first(h, "Value"))).map(|v| v.round() as i64),
+                dist: num(first(tp, "DistanceMeters")),
+            });
+        }
+        if points.is_empty() { continue; }
+        let calories: f64 = elements(act, "Lap").iter().filter_map(|(_, lap)| num(first(lap, "Calories"))).sum();
+        out.push(Track {
+            sport: normalize_sport(attr(attrs, "Sport").unwrap_or("")).to_string(),
+            name: first(act, "Notes").map(text).unwrap_or_default(),
+            calories: (calories > 0.0).then_some(calories),
+            points,
+        });
+    }
+    if out.is_empty() { Err("В TCX нет точек трека".into()) } else { Ok(out) }
+}
+
+// ── FIT ──
+
+struct FitDef {
+    global: u16,
+    big_endian: bool,
+    /// (field number, size, base type)
+    fields: Vec<(u8, u8, u8)>,
+    dev_size: usize,
+}
+
+/// Integer field value, or None for the format's "invalid" marker / non-integer sizes.
+fn fit_value(bytes: &[u8], big_endian: bool, base: u8) -> Option<i64> {
+    let raw: u64 = match (bytes.len(), big_endian) {
+        (1, _) => bytes[0] as u64,
+        (2, false) => u16::from_le_bytes([bytes[0], bytes[1]]) as u64,
+        (2, true) => u16::from_be_bytes([bytes[0], bytes[1]]) as u64,
+        (4, false) => u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as u64,
+        (4, true) => u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as u64,
+        _ => return None,
+    };
+    let bits = bytes.len() as u32 * 8;
+    match base & 0x1F {
+        // sint8 / sint16 / sint32
+        1 | 3 | 5 => {
+            let max = (1u64 << (bits - 1)) - 1;
+            if raw == max { return None; }
+            Some(if raw > max { raw as i64 - (1i64 << bits) } else { raw as i64 })
+        }
+        // uint8z / uint16z / uint32z: zero is invalid
+        10..=12 if raw == 0 => None,
+        _ if raw == (1u64 << bits) - 1 => None,
+        _ => Some(raw as i64),
+    }
+}
+
+fn fit_sport(code: i64) -> &'static str {
+    match code {
+        1 => "run",
+        2 => "ride",
+        5 => "swim",
+        11 => "walk",
+        17 => "hike",
+        _ => "other",
+    }
+}
+
+pub fn parse_fit(data: &[u8]) -> Result<Vec<Track>, String> {
+    let bad = || "Файл FIT повреждён".to_string();
+    if data.len() < 12 || data.get(8..12) != Some(b".FIT".as_slice()) {
+        return Err("Это не FIT-файл".into());
+    }
+    let header = data[0] as usize;
+    let size = u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize;
+    let end = (header + size).min(data.len());
+    let mut defs: HashMap<u8, FitDef> = HashMap::new();
+    let mut track = Track { sport: "other".into(), ..Default::default() };
+    let mut last_ts: i64 = 0;
+    let mut pos = header;
+    while pos < end {
+        let rh = data[pos];
+        pos += 1;
+        if rh & 0x40 != 0 && rh & 0x80 == 0 {
+            // Definition message
+            let head = data.get(pos..pos + 5).ok_or_else(bad)?;
+            let big_endian = head[1] == 1;
+            let global = if big_endian { u16::from_be_bytes([head[2], head[3]]) } else { u16::from_le_bytes([head[2], head[3]]) };
+            let n = head[4] as usize;
+            pos += 5;
+            let raw = data.get(pos..pos + 3 * n).ok_or_else(bad)?;
+            let fields = raw.chunks(3).map(|f| (f[0], f[1], f[2])).collect();
+            pos += 3 * n;
+            let mut dev_size = 0;
+            if rh & 0x20 != 0 {
+                let nd = *data.get(pos).ok_or_else(bad)? as usize;
+                let raw = data.get(pos + 1..pos + 1 + 3 * nd).ok_or_else(bad)?;
+                dev_size = raw.chunks(3).map(|f| f[1] as usize).sum();
+                pos += 1 + 3 * nd;
+            }
+            defs.insert(rh & 0x0F, FitDef { global, big_endian, fields, dev_size });
+            continue;
+        }
+        // Data message, either normal or with a compressed timestamp header.
+        let (local, compressed_ts) = if rh & 0x80 != 0 {
+            let offset = (rh & 0x1F) as i64;
+            last_ts += (offset - (last_ts & 0x1F)) & 0x1F;
+            ((rh >> 5) & 0x03, Some(last_ts))
+        } else {
+            (rh & 0x0F, None)
+        };
+        let def = defs.get(&local).ok_or_else(bad)?;
+        let mut values: HashMap<u8, i64> = HashMap::new();
+        for &(num, size, base) in &def.fields {
+            let bytes = data.get(pos..pos + size as usize).ok_or_else(bad)?;
+            if let Some(v) = fit_value(bytes, def.big_endian, base) { values.insert(num, v); }
+            pos += size as usize;
+        }
+        pos += def.dev_size;
+        if let Some(&ts) = values.get(&253) { last_ts = ts; }
+        match def.global {
+            // record
+            20 => {
+                let Some(ts) = values.get(&253).copied().or(compressed_ts) else { continue };
+                let semicircles = 180.0 / 2f64.powi(31);
+                let ele = values.get(&78).or_else(|| values.get(&2)).map(|&v| v as f64 / 5.0 - 500.0);
+                track.points.push(TrackPoint {
+                    ts: FIT_EPOCH + ts,
+                    lat: values.get(&0).map(|&v| v as f64 * semicircles),
+                    lon: values.get(&1).map(|&v| v as f64 * semicircles),
+                    ele,
+                    hr: values.get(&3).copied(),
+                    dist: values.get(&5).map(|&v| v as f64 / 100.0),
+                });
+            }
+            // session
+            18 => {
+                if let Some(&s) = values.get(&5) { track.sport = fit_sport(s).into(); }
+                if let Some(&kcal) = values.get(&11) { track.calories = Some(track.calories.unwrap_or(0.0) + kcal as f64); }
+            }
+            // sport
+            12 => {
+                if let Some(&s) = values.get(&0) { track.sport = fit_sport(s).into(); }
+            }
+            _ => {}
+        }
+    }
+    if track.points.is_empty() { return Err("В FIT-файле нет записей трека".into()); }
+    Ok(vec![track])
+}
+
+/// Pick the parser by extension, falling back to sniffing the content.
+/// Gzipped files (`.gpx.gz`, bulk exports) are unpacked first.
+pub fn parse_file(file_name: &str, data: &[u8]) -> Result<Vec<Track>, String> {
+    if data.starts_with(&[0x1f, 0x8b]) {
+        use std::io::Read;
+        let mut out = Vec::new();
+        flate2::read::GzDecoder::new(data).read_to_end(&mut out).map_err(|e| format!("Не удалось распаковать: {}", e))?;
+        return parse_file(file_name.trim_end_matches(".gz"), &out);
+    }
+    let lower = file_name.to_lowercase();
+    if lower.ends_with(".fit") || data.get(8..12) == Some(b".FIT".as_slice()) {
+        return parse_fit(data);
+    }
+    let xml = String::from_utf8_lossy(data);
+    if lower.ends_with(".tcx") || xml.contains("<TrainingCenterDatabase") {
+        parse_tcx(&xml)
+    } else if lower.ends_with(".gpx") || xml.contains("<gpx") {
+        parse_gpx(&xml)
+    } else {
+        Err("Неизвестный формат: нужен GPX, TCX или FIT".into())
+    }
+}
+
+// ── Analysis ──
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Split {
+    pub index: usize,
+    pub distance_m: f64,
+    pub seconds: f64,
+    pub pace_s_km: f64,
+    pub avg_hr: Option<i64>,
+    pub elevation_gain_m: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct TrackSummary {
+    pub start_ts: i64,
+    pub duration_s: i64,
+    pub moving_s: i64,
+    pub distance_m: f64,
+    pub elevation_gain_m: f64,
+    pub avg_hr: Option<i64>,
+    pub max_hr: Option<i64>,
+    pub splits: Vec<Split>,
+    /// (distance m, seconds) fastest stretch for each tracked distance.
+    pub best_efforts: Vec<(f64, f64)>,
+}
+
+fn haversine_m(a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (la1, la2) = (a.0.to_radians(), b.0.to_radians());
+    let dlat = la2 - la1;
+    let dlon = (b.1 - a.1).to_radians();
+    let h = (dlat / 2.0).sin().powi(2) + la1.cos() * la2.cos() * (dlon / 2.0).sin().powi(2);
+    2.0 * 6_371_000.0 * h.sqrt().asin()
+}
+
+/// Cumulative distance per point: the device's own counter when most points
+/// carry one, otherwise great-circle distance between GPS fixes.
+fn cumulative_distance(points: &[TrackPoint]) -> Vec<f64> {
+    let with_dist = points.iter().filter(|p| p.dist.is_some()).count();
+    let mut out = Vec::with_capacity(points.len());
+    let mut total = 0.0;
+    let mut last_fix: Option<(f64, f64)> = None;
+    for p in points {
+        if with_dist * 2 >= points.len() {
+            total = p.dist.unwrap_or(total).max(total);
+        } else if let (Some(lat), Some(lon)) = (p.lat, p.lon) {
+            if let Some(prev) = last_fix { total += haversine_m(prev, (lat, lon)); }
+            last_fix = Some((lat, lon));
+        }
+        out.push(total);
+    }
+    out
+}
+
+/// Cumulative elevation gain per point with a noise threshold.
+fn cumulative_gain(points: &[TrackPoint]) -> Vec<f64> {
+    let mut out = Vec::with_capacity(points.len());
+    let (mut gain, mut base): (f64, Option<f64>) = (0.0, None);
+    for p in points {
+        if let Some(e) = p.ele {
+            match base {
+                None => base = Some(e),
+                Some(b) if e - b >= ELEVATION_THRESHOLD_M => { gain += e - b; base = Some(e); }
+                Some(b) if e < b => base = Some(e),
+                _ => {}
+            }
+        }
+        out.push(gain);
+    }
+    out
+}
+
+/// Time at which the cumulative distance reaches `goal`, interpolated within segment j-1..j.
+fn time_at(d: &[f64], t: &[f64], j: usize, goal: f64) -> f64 {
+    if j == 0 || d[j] <= d[j - 1] { return t[j]; }
+    t[j - 1] + (goal - d[j - 1]) / (d[j] - d[j - 1]) * (t[j] - t[j - 1])
+}
+
+/// Fastest time (on the moving clock `t`) to cover `target` metres anywhere in the track.
+pub fn best_effort(d: &[f64], t: &[f64], target: f64) -> Option<f64> {
+    if target <= 0.0 || d.last().copied().unwrap_or(0.0) < target { return None; }
+    let mut best: Option<f64> = None;
+    let mut j = 0;
+    for i in 0..d.len() {
+        let goal = d[i] + target;
+        j = j.max(i);
+        while j < d.len() && d[j] < goal { j += 1; }
+        if j == d.len() { break; }
+        let secs = time_at(d, t, j, goal) - t[i];
+        if secs > 0.0 && best.is_none_or(|b| secs < b) { best = Some(secs); }
+    }
+    best
+}
+
+pub fn analyze(track: &mut Track) -> Option<TrackSummary> {
+    track.points.sort_by_key(|p| p.ts);
+    track.points.dedup_by_key(|p| p.ts);
+    let pts = &track.points;
+    if pts.len() < 2 { return None; }
+    let d = cumulative_distance(pts);
+    let gain = cumulative_gain(pts);
+    // Moving clock: pauses (auto-pause gaps, standing at lights) don't count.
+    let mut t = vec![0.0; pts.len()];
+    for i in 1..pts.len() {
+        let dt = (pts[i].ts - pts[i - 1].ts) as f64;
+        let moving = dt > 0.0 && (d[i] - d[i - 1]) / dt >= MOVING_MPS;
+        t[i] = t[i - 1] + if moving { dt } else { 0.0 };
+    }
+    let distance = *d.last().unwrap_or(&0.0);
+    let duration = pts[pts.len() - 1].ts - pts[0].ts;
+    // Without distance (indoor, pool without a counter) the whole session is "moving".
+    if distance < 1.0 {
+        for (ti, p) in t.iter_mut().zip(pts) { *ti = (p.ts - pts[0].ts) as f64; }
+    }
+
+    let hr_avg = |from: usize, to: usize| {
+        let hrs: Vec<i64> = pts[from..=to].iter().filter_map(|p| p.hr).filter(|&h| h > 0).collect();
+        (!hrs.is_empty()).then(|| (hrs.iter().sum::<i64>() as f64 / hrs.len() as f64).round() as i64)
+    };
+
+    let mut splits = Vec::new();
+    let (mut mark, mut last_t, mut last_i, mut last_gain) = (1000.0, 0.0, 0usize, 0.0);
+    for j in 1..pts.len() {
+        while d[j] >= mark {
+            let tm = time_at(&d, &t, j, mark);
+            splits.push(Split {
+                index: splits.len() + 1,
+                distance_m: 1000.0,
+                seconds: tm - last_t,
+                pace_s_km: tm - last_t,
+                avg_hr: hr_avg(last_i, j),
+                elevation_gain_m: gain[j] - last_gain,
+            });
+            (last_t, last_i, last_gain) = (tm, j, gain[j]);
+            mark += 1000.0;
+        }
+    }
+    let rest = distance - (mark - 1000.0);
+    let last = pts.len() - 1;
+    if rest >= 50.0 {
+        let secs = t[last] - last_t;
+        splits.push(Split {
+            index: splits.len() + 1,
+            distance_m: rest,
+            seconds: secs,
+            pace_s_km: secs / rest * 1000.0,
+            avg_hr: hr_avg(last_i, last),
+            elevation_gain_m: gain[last] - last_gain,
+        });
+    }
+
+    let best_efforts = effort_distances(&track.sport).iter()
+        .filter_map(|&target| best_effort(&d, &t, target).map(|s| (target, s)))
+        .collect();
+    Some(TrackSummary {
+        start_ts: pts[0].ts,
+        duration_s: duration,
+        moving_s: t[last].round() as i64,
+        distance_m: distance,
+        elevation_gain_m: gain[last],
+        avg_hr: hr_avg(0, last),
+        max_hr: pts.iter().filter_map(|p| p.hr).max(),
+        splits,
+        best_efforts,
+    })
+}
+
+/// Downsampled [lat, lon, ele, moving seconds, hr, distance] rows for charts.
+fn chart_points(points: &[TrackPoint]) -> Vec<serde_json::Value> {
+    let d = cumulative_distance(points);
+    let step = points.len().div_ceil(MAX_STORED_POINTS).max(1);
+    let start = points.first().map(|p| p.ts).unwrap_or(0);
+    let round = |v: Option<f64>, k: f64| v.map(|x| (x * k).round() / k);
+    points.iter().enumerate()
+        .filter(|(i, _)| i % step == 0 || *i == points.len() - 1)
+        .map(|(i, p)| serde_json::json!([round(p.lat, 1e5), round(p.lon, 1e5), round(p.ele, 10.0), p.ts - start, p.hr, d[i].round()]))
+        .collect()
+}
+
+// ── DB side ──
+
+fn local_dt(ts: i64) -> chrono::DateTime<chrono::Local> {
+    chrono::DateTime::from_timestamp(ts, 0).unwrap_or_default().with_timezone(&chrono::Local)
+}
+
+/// Per-minute average heart rate into `heart_rate_samples`, tagged with the
+/// track's `external_id` — a workout rowid differs between synced devices.
+fn store_heart_rate(conn: &rusqlite::Connection, track_id: &str, points: &[TrackPoint]) -> usize {
+    let mut minutes: Vec<(i64, i64, i64)> = Vec::new(); // (minute, sum, count)
+    for p in points {
+        let Some(hr) = p.hr.filter(|&h| h > 0) else { continue };
+        let m = p.ts.div_euclid(60);
+        match minutes.last_mut() {
+            Some(last) if last.0 == m => { last.1 += hr; last.2 += 1; }
+            _ => minutes.push((m, hr, 1)),
+        }
+    }
+    let mut n = 0;
+    for (m, sum, count) in minutes {
+        let dt = local_dt(m * 60);
+        n += conn.execute(
+            "INSERT OR IGNORE INTO heart_rate_samples (id, date, time, bpm, source, track_id) VALUES (?1,?2,?3,?4,?5,?6)",
+            rusqlite::params![crate::types::new_uuid_v7(), dt.format("%Y-%m-%d").to_string(), dt.format("%H:%M").to_string(),
+                (sum as f64 / count as f64).round() as i64, HR_SOURCE, track_id],
+        ).unwrap_or(0);
+    }
+    n
+}
+
+fn best_so_far(conn: &rusqlite::Connection, sport: &str, distance: f64) -> Option<f64> {
+    conn.query_row(
+        "SELECT MIN(seconds) FROM workout_best_efforts WHERE sport=?1 AND distance_m=?2",
+        rusqlite::params![sport, distance], |r| r.get::<_, Option<f64>>(0),
+    ).ok().flatten()
+}
+
+fn effort_label(distance: f64) -> String {
+    match distance as i64 {
+        21097 => "полумарафон".into(),
+        42195 => "марафон".into(),
+        d if d >= 1000 => format!("{} км", d / 1000),
+        d => format!("{} м", d),
+    }
+}
+
+/// Insert one analysed track as a workout. Returns None when it was already imported.
+fn insert_track(
+    conn: &rusqlite::Connection, track: &Track, s: &TrackSummary, file_name: &str, records: &mut Vec<serde_json::Value>,
+) -> Result<Option<i64>, String> {
+    let external_id = format!("{}:{}", track.sport, s.start_ts);
+    let exists: bool = conn.query_row(
+        "SELECT COUNT(*) FROM workout_tracks WHERE external_id=?1", rusqlite::params![external_id], |r| r.get::<_, i64>(0),
+    ).unwrap_or(0) > 0;
+    if exists { return Ok(None); }
+    let start = local_dt(s.start_ts);
+    let now = chrono::Local::now().to_rfc3339();
+    let workout_type = if track.sport == "swim" { "swimming" } else { "cardio" };
+    let title = if track.name.is_empty() { sport_label(&track.sport).to_string() } else { track.name.clone() };
+    conn.execute(
+        "INSERT INTO workouts (type, title, date, duration_minutes, calories, notes, created_at) VALUES (?1,?2,?3,?4,?5,?6,?7)",
+        rusqlite::params![workout_type, title, start.format("%Y-%m-%d").to_string(), (s.duration_s as f64 / 60.0).round() as i64,
+            track.calories.map(|c| c.round() as i64), format!("Импорт: {}", file_name), now],
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let workout_id = conn.last_insert_rowid();
+    conn.execute(
+        "INSERT INTO workout_tracks (workout_id, sport, external_id, started_at, distance_m, moving_seconds, elevation_gain_m,
+            avg_hr, max_hr, splits_json, points_json, file_name, created_at) VALUES (?1,?2,?3,?4,?5,?6,?7,?8,?9,?10,?11,?12,?13)",
+        rusqlite::params![workout_id, track.sport, external_id, start.to_rfc3339(), s.distance_m.round(), s.moving_s,
+            s.elevation_gain_m.round(), s.avg_hr, s.max_hr, serde_json::to_string(&s.splits).unwrap_or_default(),
+            serde_json::Value::Array(chart_points(&track.points)).to_string(), file_name, now],
+    ).map_err(|e| format!("DB error: {}", e))?;
+    for &(distance, secs) in &s.best_efforts {
+        let previous = best_so_far(conn, &track.sport, distance);
+        if previous.is_none_or(|p| secs < p) {
+            records.push(serde_json::json!({
+                "sport": track.sport, "distance_m": distance, "label": effort_label(distance),
+                "seconds": secs.round(), "previous_seconds": previous.map(f64::round),
+            }));
+        }
+        conn.execute(
+            "INSERT OR IGNORE INTO workout_best_efforts (workout_id, sport, distance_m, seconds) VALUES (?1,?2,?3,?4)",
+            rusqlite::params![workout_id, track.sport, distance, secs],
+        ).ok();
+    }
+    store_heart_rate(conn, &external_id, &track.points);
+    Ok(Some(workout_id))
+}
+
+/// Drop the track side of a workout (summary, best efforts, HR series).
+pub(crate) fn delete_track(conn: &rusqlite::Connection, workout_id: i64) {
+    conn.execute(
+        "DELETE FROM heart_rate_samples WHERE track_id=(SELECT external_id FROM workout_tracks WHERE workout_id=?1)",
+        rusqlite::params![workout_id],
+    ).ok();
+    conn.execute("DELETE FROM workout_best_efforts WHERE workout_id=?1", rusqlite::params![workout_id]).ok();
+    conn.execute("DELETE FROM workout_tracks WHERE workout_id=?1", rusqlite::params![workout_id]).ok();
+}
+
+// ── Commands ──
+
+/// Import a GPX / TCX / FIT file (optionally gzipped) from a path or raw bytes.
+/// Already imported tracks (same sport + start time) are skipped.
+#[tauri::command]
+pub fn import_workout_file(
+    file_name: String, path: Option<String>, bytes: Option<Vec<u8>>,
+    db: tauri::State<'_, HanniDb>,
+) -> Result<serde_json::Value, String> {
+    let data = match (bytes, path.as_deref()) {
+        (Some(b), _) if !b.is_empty() => b,
+        (_, Some(p)) => std::fs::read(p).map_err(|e| format!("Не удалось прочитать файл: {}", e))?,
+        _ => return Err("Нет данных файла".into()),
+    };
+    let tracks = parse_file(&file_name, &data)?;
+    let conn = db.conn();
+    let (mut imported, mut skipped, mut records) = (Vec::new(), 0, Vec::new());
+    conn.execute_batch("BEGIN").map_err(|e| format!("DB error: {}", e))?;
+    for mut track in tracks {
+        let Some(summary) = analyze(&mut track) else { skipped += 1; continue };
+        match insert_track(&conn, &track, &summary, &file_name, &mut records) {
+            Ok(Some(id)) => imported.push(serde_json::json!({
+                "workout_id": id, "sport": track.sport, "distance_m": summary.distance_m.round(),
+                "moving_seconds": summary.moving_s, "elevation_gain_m": summary.elevation_gain_m.round(),
+                "avg_hr": summary.avg_hr,
+            })),
+            Ok(None) => skipped += 1,
+            Err(e) => { conn.execute_batch("ROLLBACK").ok(); return Err(e); }
+        }
+    }
+    conn.execute_batch("COMMIT").map_err(|e| format!("DB error: {}", e))?;
+    Ok(serde_json::json!({ "imported": imported, "skipped": skipped, "records": records }))
+}
+
+#[tauri::command]
+pub fn get_workout_track(workout_id: i64, db: tauri::State<'_, HanniDb>) -> Result<serde_json::Value, String> {
+    let conn = db.read();
+    let mut track = conn.query_row(
+        "SELECT t.sport, t.started_at, t.distance_m, t.moving_seconds, t.elevation_gain_m, t.avg_hr, t.max_hr,
+                t.splits_json, t.points_json, t.file_name, w.title, w.date, w.duration_minutes, w.calories
+         FROM workout_tracks t JOIN workouts w ON w.id=t.workout_id WHERE t.workout_id=?1",
+        rusqlite::params![workout_id],
+        |r| Ok(serde_json::json!({
+            "workout_id": workout_id, "sport": r.get::<_, String>(0)?, "started_at": r.get::<_, String>(1)?,
+            "distance_m": r.get::<_, f64>(2)?, "moving_seconds": r.get::<_, i64>(3)?, "elevation_gain_m": r.get::<_, f64>(4)?,
+            "avg_hr": r.get::<_, Option<i64>>(5)?, "max_hr": r.get::<_, Option<i64>>(6)?,
+            "splits": serde_json::from_str::<serde_json::Value>(&r.get::<_, String>(7)?).unwrap_or_default(),
+            "points": serde_json::from_str::<serde_json::Value>(&r.get::<_, String>(8)?).unwrap_or_default(),
+            "file_name": r.get::<_, String>(9)?, "title": r.get::<_, String>(10)?, "date": r.get::<_, String>(11)?,
+            "duration_minutes": r.get::<_, i64>(12)?, "calories": r.get::<_, Option<i64>>(13)?,
+        })),
+    ).map_err(|_| "Трек не найден".to_string())?;
+    let mut stmt = conn.prepare(
+        "SELECT distance_m, seconds FROM workout_best_efforts WHERE workout_id=?1 ORDER BY distance_m"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let efforts: Vec<serde_json::Value> = stmt.query_map(rusqlite::params![workout_id], |r| {
+        let d: f64 = r.get(0)?;
+        Ok(serde_json::json!({ "distance_m": d, "label": effort_label(d), "seconds": r.get::<_, f64>(1)?.round() }))
+    }).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+    track["best_efforts"] = serde_json::Value::Array(efforts);
+    Ok(track)
+}
+
+/// Per-sport totals over the last `days` days.
+#[tauri::command]
+pub fn get_cardio_summary(days: Option<i64>, db: tauri::State<'_, HanniDb>) -> Result<Vec<serde_json::Value>, String> {
+    let conn = db.read();
+    let since = (chrono::Local::now() - chrono::Duration::days(days.unwrap_or(30))).format("%Y-%m-%d").to_string();
+    let mut stmt = conn.prepare(
+        "SELECT t.sport, COUNT(*), SUM(t.distance_m), SUM(t.moving_seconds), SUM(t.elevation_gain_m), AVG(t.avg_hr)
+         FROM workout_tracks t JOIN workouts w ON w.id=t.workout_id WHERE w.date >= ?1
+         GROUP BY t.sport ORDER BY SUM(t.moving_seconds) DESC"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let rows = stmt.query_map(rusqlite::params![since], |r| {
+        let (distance, moving): (f64, i64) = (r.get(2)?, r.get(3)?);
+        Ok(serde_json::json!({
+            "sport": r.get::<_, String>(0)?, "count": r.get::<_, i64>(1)?, "distance_m": distance, "moving_seconds": moving,
+            "elevation_gain_m": r.get::<_, f64>(4)?, "avg_hr": r.get::<_, Option<f64>>(5)?.map(f64::round),
+            "avg_pace_s_km": (distance > 0.0).then(|| (moving as f64 / distance * 1000.0).round()),
+        }))
+    }).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+    Ok(rows)
+}
+
+/// Personal bests: fastest effort per sport and distance, plus the longest
+/// distance, longest moving time and biggest climb per sport.
+#[tauri::command]
+pub fn get_cardio_records(db: tauri::State<'_, HanniDb>) -> Result<serde_json::Value, String> {
+    let conn = db.read();
+    // SQLite returns the other columns from the row that holds MIN(seconds).
+    let mut stmt = conn.prepare(
+        "SELECT e.sport, e.distance_m, MIN(e.seconds), e.workout_id, w.date
+         FROM workout_best_efforts e JOIN workouts w ON w.id=e.workout_id
+         GROUP BY e.sport, e.distance_m ORDER BY e.sport, e.distance_m"
+    ).map_err(|e| format!("DB error: {}", e))?;
+    let fastest: Vec<serde_json::Value> = stmt.query_map([], |r| {
+        let d: f64 = r.get(1)?;
+        let secs: f64 = r.get(2)?;
+        Ok(serde_json::json!({
+            "sport": r.get::<_, String>(0)?, "distance_m": d, "label": effort_label(d), "seconds": secs.round(),
+            "pace_s_km": (secs / d * 1000.0).round(), "workout_id": r.get::<_, i64>(3)?, "date": r.get::<_, String>(4)?,
+        }))
+    }).map_err(|e| format!("Query error: {}", e))?.filter_map(|r| r.ok()).collect();
+
+    let mut longest = Vec::new();
+    for (kind, column) in [("distance", "t.distance_m"), ("time", "t.moving_seconds"), ("climb", "t.elevation_gain_m")] {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT t.sport, MAX({}), t.workout_id, w.date, w.title FROM workout_tracks t JOIN workouts w ON w.id=t.workout_id
+             GROUP BY t.sport HAVING MAX({}) > 0", column, column
+        )).map_err(|e| format!("DB error: {}", e))?;
+        let rows = stmt.query_map([], |r| Ok(serde_json::json!({
+            "kind": kind, "sport": r.get::<_, String>(0)?, "value": r.get::<_, f64>(1)?,
+            "workout_id": r.get::<_, i64>(2)?, "date": r.get::<_, String>(3)?, "title": r.get::<_, String>(4)?,
+        }))).map_err(|e| format!("Query error: {}", e))?;
+        longest.extend(rows.filter_map(|r| r.ok()));
+    }
+    Ok(serde_json::json!({ "fastest": fastest, "longest": longest }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GPX: &str = r#"<?xml version="1.0"?>
+<gpx version="1.1" xmlns:gpxtpx="http://www.garmin.com/xmlschemas/TrackPointExtension/v1">
+  <metadata><name>Export</name></metadata>
+  <trk><name><![CDATA[Morning Run]]></name><type>running</type><trkseg>
+    <trkpt lat="55.7500" lon="37.6000"><ele>150</ele><time>2024-05-01T06:00:00Z</time>
+      <extensions><gpxtpx:TrackPointExtension><gpxtpx:hr>120</gpxtpx:hr></gpxtpx:TrackPointExtension></extensions></trkpt>
+    <trkpt lon="37.6000" lat="55.7545"><ele>156</ele><time>2024-05-01T06:02:30Z</time>
+      <extensions><gpxtpx:TrackPointExtension><gpxtpx:hr>150</gpxtpx:hr></gpxtpx:TrackPointExtension></extensions></trkpt>
+    <trkpt lat="55.7590" lon="37.6000"><ele>154</ele><time>2024-05-01T06:05:00Z</time></trkpt>
+  </trkseg></trk>
+</gpx>"#;
+
+    #[test]
+    fn parses_gpx_and_summarises() {
+        let mut tracks = parse_gpx(GPX).unwrap();
+        assert_eq!(tracks.len(), 1);
+        let t = &mut tracks[0];
+        assert_eq!((t.sport.as_str(), t.name.as_str()), ("run", "Morning Run"));
+        assert_eq!(t.points[1].hr, Some(150));
+        assert_eq!(t.points[1].lat, Some(55.7545));
+        let s = analyze(t).unwrap();
+        // 0.009° of latitude ≈ 1 km in 5 minutes.
+        assert!((s.distance_m - 1000.8).abs() < 2.0, "{}", s.distance_m);
+        assert_eq!((s.duration_s, s.moving_s), (300, 300));
+        assert_eq!(s.elevation_gain_m, 6.0);
+        assert_eq!((s.avg_hr, s.max_hr), (Some(135), Some(150)));
+        assert_eq!(s.splits.len(), 1);
+        assert!((s.splits[0].seconds - 299.8).abs() < 0.5);
+        assert_eq!(s.best_efforts.len(), 1);
+    }
+
+    #[test]
+    fn parses_tcx_with_device_distance() {
+        let tcx = r#"<TrainingCenterDatabase><Activities><Activity Sport="Biking"><Id>x</Id>
+          <Lap StartTime="2024-05-01T06:00:00Z"><Calories>300</Calories><Track>
+            <Trackpoint><Time>2024-05-01T06:00:00Z</Time><DistanceMeters>0</DistanceMeters><HeartRateBpm><Value>100</Value></HeartRateBpm></Trackpoint>
+            <Trackpoint><Time>2024-05-01T06:10:00Z</Time><DistanceMeters>5000</DistanceMeters><HeartRateBpm><Value>140</Value></HeartRateBpm></Trackpoint>
+            <Trackpoint><Time>2024-05-01T06:15:00Z</Time><DistanceMeters>5000</DistanceMeters></Trackpoint>
+          </Track></Lap></Activity></Activities></TrainingCenterDatabase>"#;
+        let mut tracks = parse_file("ride.tcx", tcx.as_bytes()).unwrap();
+        let t = &mut tracks[0];
+        assert_eq!(t.sport, "ride");
+        assert_eq!(t.calories, Some(300.0));
+        let s = analyze(t).unwrap();
+        assert_eq!(s.distance_m, 5000.0);
+        // The last five minutes are a stop: elapsed 15 min, moving 10.
+        assert_eq!((s.duration_s, s.moving_s), (900, 600));
+        assert_eq!(s.splits.len(), 5);
+        assert_eq!(s.splits[0].seconds, 120.0);
+    }
+
+    #[test]
+    fn best_effort_interpolates_the_fastest_window() {
+        let d = [0.0, 1000.0, 2000.0, 3000.0];
+        let t = [0.0, 300.0, 540.0, 840.0];
+        assert_eq!(best_effort(&d, &t, 1000.0), Some(240.0));
+        assert_eq!(best_effort(&d, &t, 1500.0), Some(390.0));
+        assert_eq!(best_effort(&d, &t, 5000.0), None);
+    }
+
+    #[test]
+    fn decodes_fit_records() {
+        // Header (12 bytes) + definition for local 0 = record(20): timestamp u32, hr u8, distance u32.
+        let mut body = vec![0x40, 0, 0, 20, 0, 3, 253, 4, 0x86, 3, 1, 2, 5, 4, 0x86];
+        let ts0: u32 = 1_000_000_000;
+        for (i, (hr, dist)) in [(110u8, 0u32), (130, 50_000), (0xFF, 100_000)].iter().enumerate() {
+            body.push(0x00);
+            body.extend_from_slice(&(ts0 + 60 * i as u32).to_le_bytes());
+            body.push(*hr);
+            body.extend_from_slice(&dist.to_le_bytes());
+        }
+        // session(18) with sport = running: local 1, field 5 (enum, 1 byte).
+        body.extend_from_slice(&[0x41, 0, 0, 18, 0, 1, 5, 1, 0x00, 0x01, 1]);
+        let mut data = vec![12, 0x10, 0, 0];
+        data.extend_from_slice(&(body.len() as u32).to_le_bytes());
+        data.extend_from_slice(b".FIT");
+        data.extend_from_slice(&body);
+        data.extend_from_slice(&[0, 0]); // CRC, unchecked
+        let mut tracks = parse_file("a.fit", &data).unwrap();
+        let t = &mut tracks[0];
+        assert_eq!(t.sport, "run");
+        assert_eq!(t.points.len(), 3);
+        assert_eq!(t.points[0].ts, FIT_EPOCH + ts0 as i64);
+        assert_eq!(t.points[2].hr, None);
+        assert_eq!(t.points[1].dist, Some(500.0));
+        let s = analyze(t).unwrap();
+        assert_eq!((s.distance_m, s.moving_s, s.avg_hr), (1000.0, 120, Some(120)));
+    }
+}
@@ -441,6 +441,12 @@ pub struct AudioRecording(pub std::sync::Mutex<WhisperState>, pub std::sync::Con
 pub struct FocusState {
     pub active: bool,
     pub end_time: Option<chrono::DateTime<chrono::Local>>,
+    pub started_at: Option<chrono::DateTime<chrono::Local>>,
+    /// Started by a blocklist schedule rather than by the user.
+    pub scheduled: bool,
+    /// Feature that started the session and alone may change or stop it
+    /// (`None` — started by the user or a schedule).
+    pub owner: Option<&'static str>,
     pub blocked_apps: Vec<String>,
     pub blocked_sites: Vec<String>,
     pub monitor_running: Arc<AtomicBool>,
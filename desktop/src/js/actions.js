@@ -249,8 +249,27 @@ export async function executeAction(actionJson) {
           name: action.name || '', calories: action.calories || null,
           protein: action.protein || null, carbs: action.carbs || null,
           fat: action.fat || null, notes: action.notes || null,
+          recipeId: action.recipe_id || null, servings: action.servings || null,
         });
         break;
+      case 'import_recipe': {
+        const r = await invoke('import_recipe', {
+          url: action.url || null, text: action.text || null, force: action.force || false,
+        });
+        if (r.status === 'created') {
+          const unmapped = (r.ingredients || []).filter(i => !i.catalog_id).map(i => i.name);
+          result = `Рецепт «${r.recipe.name}» сохранён (id ${r.recipe_id}, ${r.ingredients.length} ингр.)`
+            + (unmapped.length ? `; не найдены в каталоге: ${unmapped.join(', ')}` : '')
+            + ((r.blacklist || []).length ? `; чёрный список: ${r.blacklist.map(b => b.matched).join(', ')}` : '');
+        } else if (r.status === 'duplicate') {
+          result = `Этот рецепт уже импортирован (id ${r.recipe_id})`;
+        } else if (r.status === 'blocked') {
+          result = `Рецепт «${r.recipe.name}» не сохранён — в чёрном списке: ${r.blacklist.map(b => b.matched).join(', ')}`;
+        } else {
+          result = JSON.stringify(r);
+        }
+        break;
+      }
       case 'add_product':
         result = await invoke('add_product', {
           name: action.name || '', category: action.category || null,
@@ -307,15 +326,13 @@ export async function executeAction(actionJson) {
         break;
       case 'start_pomodoro': {
         const title = action.title || action.name || 'Помодоро';
-        const cat = action.category || 'work';
-        tabLoaders.startPomodoro?.(title, cat, action.focus_mode || false);
-        result = `Помодоро запущен: ${title} (25 мин)`;
+        await tabLoaders.startPomodoro?.(title, action.focus_mode || false);
+        result = `Помодоро запущен: ${title}`;
         setTimeout(() => tabLoaders.updateFocusWidget?.(), 100);
         break;
       }
       case 'stop_pomodoro':
-        S.pomodoroState.active = false;
-        await invoke('stop_activity').catch(() => {});
+        await invoke('stop_pomodoro', { notes: null }).catch(() => {});
         result = 'Помодоро остановлен';
         setTimeout(() => tabLoaders.updateFocusWidget?.(), 100);
         break;
@@ -1,6 +1,7 @@
 // Day-grid overlay: paint completed timeline_blocks as absolutely-positioned
 // rectangles spanning their real start..end time. Also marks free gaps and
-// highlights blocks that conflict with planned events.
+// highlights blocks that conflict with planned events. Accepted day-plan blocks
+// (source 'plan') are drawn dashed — intentions, not facts.
 
 import { invoke } from './state.js';
 import { escapeHtml } from './utils.js';
@@ -59,7 +60,7 @@ function blockHtml(block, label, conflict) {
   const start = block.start_time.slice(0, 5);
   const end = block.end_time.slice(0, 5);
   const tip = `${label || 'Активность'} · ${start}–${end} · ${fmtDur(block.duration_minutes || 0)}${conflict ? ' · ⚠️ конфликт с встречей' : ''}`;
-  const cls = ['day-tl-block', conflict && 'day-tl-conflict'].filter(Boolean).join(' ');
+  const cls = ['day-tl-block', conflict && 'day-tl-conflict', block.source === 'plan' && 'day-tl-planned'].filter(Boolean).join(' ');
   return `<div class="${cls}" style="--tl-color:${colour};--tl-bg:${bg};" title="${escapeHtml(tip)}">
     <div class="day-tl-block-head">
       <span class="day-tl-block-icon">${icon}</span>
@@ -77,6 +78,8 @@ function gapHtml(startMin, endMin, minToPx) {
   </div>`;
 }
 
+const reconstructedDays = new Set();
+
 export async function injectTimelineOverlay(rootEl, date, plannedEvents = [], hourPx = DEFAULT_HOUR_PX) {
   if (!rootEl) return;
   const minToPx = (m) => Math.round(m * (hourPx / 60));
@@ -87,8 +90,15 @@ export async function injectTimelineOverlay(rootEl, date, plannedEvents = [], ho
   const todayStr = `${now.getFullYear()}-${pad2(now.getMonth() + 1)}-${pad2(now.getDate())}`;
   const dayIsPast = date < todayStr;
   const nowMin = (date === todayStr) ? now.getHours() * 60 + now.getMinutes() : null;
+  // Rebuild automatic blocks (sleep, workouts, snapshots…) before drawing.
+  // Past days only change when data is imported late, so once per session is enough.
+  if (date <= todayStr && !reconstructedDays.has(date)) {
+    await invoke('reconstruct_timeline', { date }).catch(() => {});
+    if (dayIsPast) reconstructedDays.add(date);
+  }
   const blocks = await invoke('get_timeline_blocks', { date }).catch(() => []);
-  const completed = (blocks || []).filter(b => !b.is_active && b.start_time && b.end_time && b.duration_minutes > 0);
+  // auto_event blocks mirror the planned events already drawn on the grid.
+  const completed = (blocks || []).filter(b => !b.is_active && b.start_time && b.end_time && b.duration_minutes > 0 && b.source !== 'auto_event');
   const titles = await loadSourceTitles(completed);
 
   // Build event-time intervals for conflict detection
@@ -108,7 +118,7 @@ export async function injectTimelineOverlay(rootEl, date, plannedEvents = [], ho
   // Event pixel boxes (+ the event obj) so a completion that lands on a planned
   // event folds into it as a "+N" badge and a click-through pager, instead of
   // being drawn as its own marker competing for the same row.
-  const EV_SOURCE = { manual: 'Вручную', apple: 'Apple Calendar', auto_health: 'Apple Health', google: 'Google Calendar' };
+  const EV_SOURCE = { manual: 'Вручную', apple: 'Apple Calendar', auto_health: 'Apple Health', auto_program: 'Программа тренировок', google: 'Google Calendar' };
   const toHM = (m) => `${String(Math.floor(m / 60) % 24).padStart(2, '0')}:${String(m % 60).padStart(2, '0')}`;
   const MARKER_PX = 16;
   const evBoxes = (plannedEvents || [])
@@ -135,6 +145,7 @@ export async function injectTimelineOverlay(rootEl, date, plannedEvents = [], ho
     const dur = e.duration_minutes || 0;
     const rows = [];
     if (dur) rows.push({ text: `⏱ ${e.source === 'auto_health' ? 'Факт' : 'Длительность'}: ${fmtDur(dur)}` });
+    if (e.shifted) rows.push({ text: `🌍 ${e.orig_date === e.date ? '' : `${e.orig_date} `}${e.orig_time} · ${e.tz}`, muted: true });
     rows.push({ text: EV_SOURCE[e.source] || 'Вручную', muted: true });
     rows.push(e.completed ? { text: '✓ Выполнено', done: true } : { text: '○ Не отмечено', muted: true });
     return { title: e.title || 'Событие', subtitle: s != null ? `${e.time} – ${toHM(s + dur)}` : (e.time || ''), rows };
@@ -192,7 +203,9 @@ export async function injectTimelineOverlay(rootEl, date, plannedEvents = [], ho
   for (const p of positioned) {
     const top = minToPx(p.startMin);
     const height = Math.max(20, minToPx(p.endMin - p.startMin));
-    const label = titles.get(`${p.block.source_type}:${p.block.source_id}`) || p.block.type_name || '';
+    const isPlan = p.block.source === 'plan';
+    const label = titles.get(`${p.block.source_type}:${p.block.source_id}`)
+      || ((isPlan || p.block.source.startsWith('auto_')) && p.block.notes) || p.block.type_name || '';
     const wrap = document.createElement('div');
     wrap.className = 'day-tl-block-wrap';
     if (p.totalCols > 1) {
@@ -215,9 +228,9 @@ export async function injectTimelineOverlay(rootEl, date, plannedEvents = [], ho
       const end = p.block.end_time.slice(0, 5);
       const rows = [
         { text: `🕐 ${start} – ${end}` },
-        { text: `⏱ Факт: ${fmtDur(p.block.duration_minutes || 0)}` },
+        { text: `⏱ ${isPlan ? 'План' : 'Факт'}: ${fmtDur(p.block.duration_minutes || 0)}` },
         { text: SOURCE_NAME[p.block.source_type] || p.block.type_name || 'Активность', muted: true },
-        { text: '✓ Выполнено', done: true },
+        isPlan ? { text: '📋 Запланировано', muted: true } : { text: '✓ Выполнено', done: true },
       ];
       if (conflict) rows.push({ text: '⚠️ конфликт с встречей', muted: true });
       showTimelinePopover(ev.clientX, ev.clientY, { title: label || p.block.type_name || 'Активность', rows });
@@ -256,7 +269,7 @@ export async function injectTimelineOverlay(rootEl, date, plannedEvents = [], ho
   // already drawn as proper blocks above — skip them here to avoid double.
   const blockedSchedIds = new Set(
     (blocks || [])
-      .filter(b => b.source_type === 'schedule' && b.source_id != null)
+      .filter(b => b.source_type === 'schedule' && b.source_id != null && b.source !== 'plan')
       .map(b => b.source_id)
   );
   // Show what was actually DONE on this calendar day, keyed by completed_at:
@@ -0,0 +1,88 @@
+// Day planner modal (calendar list → "⏱ Спланировать"). Shows the proposal from
+// plan_day: tasks laid into free slots around sleep, timed events and logged
+// blocks, plus what doesn't fit. "Принять" writes planned blocks and moves the
+// ticked overflow items to tomorrow; "Очистить план" drops an accepted plan.
+
+import { invoke } from './state.js';
+import { escapeHtml, toast } from './utils.js';
+
+const ESTIMATE_LABELS = { history: 'по истории', estimate: 'оценка', event: 'длительность', default: 'по умолчанию' };
+const KIND_ICONS = { event: '📅', schedule: '🔁', note: '📝' };
+
+const fmtMin = m => m >= 60 ? `${Math.floor(m / 60)}ч${m % 60 ? ` ${m % 60}м` : ''}` : `${m}м`;
+
+function slotRow(s) {
+  return `<div class="dp-row">
+    <span class="dp-time">${s.start}–${s.end}</span>
+    <span class="dp-title">${KIND_ICONS[s.source_type] || ''} ${escapeHtml(s.title)}</span>
+    <span class="dp-meta">${s.anchored ? '📌 ' : ''}${fmtMin(s.minutes)} · ${ESTIMATE_LABELS[s.estimate] || s.estimate}</span>
+  </div>`;
+}
+
+function render(plan, buffer) {
+  const fixed = plan.fixed.map(f => `<div class="dp-row dp-fixed">
+    <span class="dp-time">${f.start}–${f.end}</span>
+    <span class="dp-title">📅 ${escapeHtml(f.title)}</span>
+  </div>`).join('');
+  const overflow = plan.overflow.map((o, i) => `<label class="dp-row">
+    <input type="checkbox" data-dp-move="${i}" ${o.movable ? 'checked' : 'disabled title="Расписание повторяется — не переносится"'}>
+    <span class="dp-title">${KIND_ICONS[o.source_type] || ''} ${escapeHtml(o.title)}</span>
+    <span class="dp-meta">${fmtMin(o.minutes)}</span>
+  </label>`).join('');
+  return `<div class="modal dp-modal">
+    <div class="modal-title">План дня · ${plan.date}</div>
+    <div class="dp-summary">День ${plan.day_start}–${plan.day_end} · свободно ${fmtMin(plan.free_minutes)} · нужно ${fmtMin(plan.needed_minutes)}
+      · буфер <input class="form-input" id="dp-buffer" type="number" min="0" max="60" value="${buffer}" style="width:56px;display:inline-block;padding:2px 4px;"> мин</div>
+    ${plan.overcommitted_minutes > 0 ? `<div class="dp-warn">⚠️ Перегруз: не помещается ${fmtMin(plan.overcommitted_minutes)}</div>` : ''}
+    ${fixed ? `<div class="dp-section">Фиксированное</div><div class="dp-list">${fixed}</div>` : ''}
+    <div class="dp-section">Раскладка</div>
+    <div class="dp-list">${plan.slots.map(slotRow).join('') || '<div class="body-hint">Нечего раскладывать</div>'}</div>
+    ${overflow ? `<div class="dp-section">Не помещается — перенести на завтра</div><div class="dp-list">${overflow}</div>` : ''}
+    <div class="modal-actions">
+      <button class="btn-secondary" id="dp-clear">Очистить план</button>
+      <button class="btn-secondary" id="dp-cancel">Отмена</button>
+      <button class="btn-primary" id="dp-accept"${plan.slots.length || plan.overflow.length ? '' : ' disabled'}>Принять</button>
+    </div>
+  </div>`;
+}
+
+export async function showDayPlanner(date, onDone) {
+  const overlay = document.createElement('div');
+  overlay.className = 'modal-overlay';
+  document.body.appendChild(overlay);
+  const close = () => overlay.remove();
+  overlay.addEventListener('click', (e) => { if (e.target === overlay) close(); });
+  overlay.addEventListener('keydown', (e) => { if (e.key === 'Escape') close(); });
+
+  const load = async (buffer) => {
+    let plan;
+    try { plan = await invoke('plan_day', { date, bufferMinutes: buffer }); }
+    catch (err) { toast(String(err)); close(); return; }
+    overlay.innerHTML = render(plan, buffer);
+
+    overlay.querySelector('#dp-buffer').addEventListener('change', (e) => {
+      load(Math.max(0, Math.min(60, parseInt(e.target.value, 10) || 0)));
+    });
+    overlay.querySelector('#dp-cancel').addEventListener('click', close);
+    overlay.querySelector('#dp-clear').addEventListener('click', async () => {
+      try { await invoke('clear_day_plan', { date }); toast('План очищен', 'success'); }
+      catch (err) { toast(String(err)); }
+      close();
+      onDone && onDone();
+    });
+    overlay.querySelector('#dp-accept').addEventListener('click', async (e) => {
+      e.currentTarget.disabled = true;
+      const moveToTomorrow = [...overlay.querySelectorAll('[data-dp-move]:checked')]
+        .map(cb => plan.overflow[+cb.dataset.dpMove])
+        .map(o => ({ source_type: o.source_type, source_id: o.source_id }));
+      const slots = plan.slots.map(s => ({ source_type: s.source_type, source_id: s.source_id, title: s.title, start: s.start, end: s.end }));
+      try {
+        const r = await invoke('accept_day_plan', { date, slots, moveToTomorrow });
+        toast(`План сохранён: ${r.saved}${r.moved ? ` · перенесено на завтра: ${r.moved}` : ''}`, 'success');
+      } catch (err) { toast(String(err)); }
+      close();
+      onDone && onDone();
+    });
+  };
+  await load(5);
+}
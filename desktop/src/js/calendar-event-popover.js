@@ -11,6 +11,7 @@ const SOURCE_LABEL = {
   manual: 'Вручную',
   apple: 'Apple Calendar',
   auto_health: 'Apple Health',
+  auto_program: 'Программа тренировок',
   google: 'Google Calendar',
 };
 
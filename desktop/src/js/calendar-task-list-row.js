@@ -49,7 +49,8 @@ export function renderItemRow(item, dateStr) {
   const trackBtns = active
     ? `<button class="ctl-track ctl-pause" data-ctl-pause="${item.block.id}" title="Пауза">⏸</button>
        <button class="ctl-track ctl-finish" data-ctl-finish="${item.block.id}" title="Готово">✓</button>`
-    : (showStart ? `<button class="ctl-track ctl-start" data-ctl-start title="${done && target > 0 ? 'Продолжить' : 'Запустить'}">▶</button>` : '');
+    : (showStart ? `<button class="ctl-track ctl-start" data-ctl-start title="${done && target > 0 ? 'Продолжить' : 'Запустить'}">▶</button>
+       <button class="ctl-track ctl-pomo" data-ctl-pomo title="Помодоро">🍅</button>` : '');
   // "Не выполнено" — red ✗ box paired with the ✓ check (schedule-only). Hidden
   // only while a timer runs. Lets a reflection / overdue task be closed honestly
   // so it leaves the tasker; reads as a clear да/нет choice next to the ✓.
@@ -68,7 +68,8 @@ async function loadDayItems(date) {
   // Group all blocks by source: each source can have multiple blocks per day (target_minutes feature)
   const blocksBySrc = new Map();
   for (const b of (blocks || [])) {
-    if (!b.source_type || b.source_id == null) continue;
+    // Accepted day-plan blocks are intentions, not tracked time.
+    if (!b.source_type || b.source_id == null || b.source === 'plan') continue;
     const key = `${b.source_type}:${b.source_id}`;
     if (!blocksBySrc.has(key)) blocksBySrc.set(key, []);
     blocksBySrc.get(key).push(b);
@@ -126,7 +127,7 @@ async function loadDayItems(date) {
 }
 
 
-function renderToolbar(dayLabel, isToday) {
+function renderToolbar(dayLabel, isToday, date) {
   return `<div class="cal-list-toolbar">
     <div class="cal-list-nav">
       <button class="calendar-nav-btn" id="ctl-prev">&lt;</button>
@@ -134,6 +135,7 @@ function renderToolbar(dayLabel, isToday) {
       <button class="calendar-nav-btn" id="ctl-next">&gt;</button>
       ${!isToday ? `<button class="cal-today-btn" id="ctl-today">Сегодня</button>` : ''}
       <button class="ctl-hide-btn${S.calHideDone ? ' ctl-hide-on' : ''}" id="ctl-hide-done" style="margin-left:auto;" title="Показывать только невыполненные">${S.calHideDone ? '☑ Готовые скрыты' : '☐ Скрыть готовые'}</button>
+      ${date >= todayStr() ? `<button class="ctl-hide-btn" id="ctl-plan-day" title="Разложить задачи по свободному времени">⏱ Спланировать</button>` : ''}
       <div class="ctl-add-wrap">
         <button class="btn-primary" id="ctl-add">+ Добавить</button>
         <div class="ctl-add-menu" id="ctl-add-menu" hidden>
@@ -163,7 +165,7 @@ async function maybeAutoCheckSchedule(scheduleId, date) {
     const sch = scheds.find(s => s.id === scheduleId);
     if (!sch || !sch.target_minutes) return;
     const total = blocks
-      .filter(b => b.source_type === 'schedule' && b.source_id === scheduleId && !b.is_active)
+      .filter(b => b.source_type === 'schedule' && b.source_id === scheduleId && !b.is_active && b.source !== 'plan')
       .reduce((sum, b) => sum + (b.duration_minutes || 0), 0);
     const isDone = completions.some(c => c.schedule_id === scheduleId && c.completed);
     if (total >= sch.target_minutes && !isDone) {
@@ -216,6 +218,13 @@ function wire(el) {
   el.querySelector('#ctl-next')?.addEventListener('click', () => { S.calDayDate = shiftDate(S.calDayDate || todayStr(), 1); renderCalendarTaskList(el); });
   el.querySelector('#ctl-today')?.addEventListener('click', () => { S.calDayDate = todayStr(); renderCalendarTaskList(el); });
   el.querySelector('#ctl-hide-done')?.addEventListener('click', () => { setHideDone(!S.calHideDone); renderCalendarTaskList(el); });
+  el.querySelector('#ctl-plan-day')?.addEventListener('click', async () => {
+    const { showDayPlanner } = await import('./calendar-day-planner.js');
+    showDayPlanner(S.calDayDate || todayStr(), () => {
+      renderCalendarTaskList(el);
+      window.dispatchEvent(new CustomEvent('hanni:calendar-refresh'));
+    });
+  });
 
   const date = () => S.calDayDate || todayStr();
   const openEvent = () => { S.selectedCalendarDate = date(); tabLoaders.openCalendarAddEvent?.(); };
@@ -298,6 +307,12 @@ function wire(el) {
       catch (err) { console.error('ctl start:', err); }
       renderCalendarTaskList(el);
     });
+    row.querySelector('[data-ctl-pomo]')?.addEventListener('click', async (e) => {
+      e.stopPropagation(); e.currentTarget.disabled = true;
+      try { await invoke('start_pomodoro', { sourceType: kind, sourceId: String(id), title: null, project: null, config: null }); }
+      catch (err) { console.error('ctl pomodoro:', err); }
+      renderCalendarTaskList(el);
+    });
     row.querySelector('[data-ctl-pause]')?.addEventListener('click', async (e) => {
       e.stopPropagation();
       const blockId = parseInt(e.currentTarget.dataset.ctlPause);
@@ -383,6 +398,6 @@ export async function renderCalendarTaskList(el, opts) {
     : (S.calHideDone && !remaining
         ? `<div class="ctl-empty"><div class="ctl-empty-title">Всё выполнено 🎉</div></div>`
         : SECTION_DEFS.map(def => renderSection(def, groups[def.key], date)).join(''));
-  el.innerHTML = renderToolbar(dayLabel, isToday) + `<div class="ctl-body">${bodyHtml}</div>`;
+  el.innerHTML = renderToolbar(dayLabel, isToday, date) + `<div class="ctl-body">${bodyHtml}</div>`;
   wire(el);
 }
@@ -78,6 +78,7 @@ export async function renderUnifiedLayout(el, tabId, config) {
   if (config.renderCatalog) panes.splice(panes.findIndex(p => p.id === 'table'), 0, { id: 'catalog', icon: '📚', label: 'Каталог' });
   if (config.renderTemplates) panes.splice(panes.findIndex(p => p.id === 'table'), 0, { id: 'templates', icon: '📋', label: 'Шаблоны' });
   if (config.renderPrograms) panes.splice(panes.findIndex(p => p.id === 'table'), 0, { id: 'programs', icon: '🗓️', label: 'Программы' });
+  if (config.renderCardio) panes.splice(panes.findIndex(p => p.id === 'table'), 0, { id: 'cardio', icon: '🏃', label: 'Кардио' });
 
   // Fall back if the persisted pane was removed (e.g. memory hidden for this tab).
   if (!panes.some(p => p.id === activePane)) { activePane = panes[0].id; S._unifiedPane[tabId] = activePane; }
@@ -197,6 +198,9 @@ export async function renderUnifiedLayout(el, tabId, config) {
     case 'programs':
       if (config.renderPrograms) await config.renderPrograms(paneEl);
       break;
+    case 'cardio':
+      if (config.renderCardio) await config.renderCardio(paneEl);
+      break;
     case 'goals':
       await renderGoalsPane(paneEl, tabId, config);
       break;
@@ -0,0 +1,130 @@
+// focus-limits.js — Daily screen-time limits (app / site / category, weekend variant)
+// with live usage from snapshots and a per-day history of what was exceeded
+import { invoke } from './state.js';
+import { escapeHtml, confirmModal, toast } from './utils.js';
+
+const KIND_LABELS = { app: 'Приложение', site: 'Сайт', category: 'Категория' };
+const KIND_ICONS = { app: '🖥', site: '🌐', category: '🏷' };
+const LEVEL_LABELS = { warn: '⚠️ 80%', nudge: '🔔 исчерпан', block: '⛔ заблокировано' };
+const PLACEHOLDERS = { app: 'Telegram', site: 'youtube.com', category: 'social' };
+
+const fmtMin = m => m >= 60 ? `${Math.floor(m / 60)}ч ${Math.round(m % 60)}м` : `${Math.round(m)}м`;
+
+function openLimitEditor(limit, categories, onSaved) {
+  const l = limit || { kind: 'app', target: '', weekday_minutes: 60, weekend_minutes: null, block: false, is_active: true };
+  const overlay = document.createElement('div');
+  overlay.className = 'modal-overlay';
+  overlay.innerHTML = `<div class="modal" style="max-width:420px">
+    <div class="modal-title">${limit ? 'Лимит' : 'Новый лимит'}</div>
+    <div class="form-label">Что ограничить</div>
+    <div style="display:flex;gap:8px">
+      <select class="form-select" id="limit-kind">${Object.entries(KIND_LABELS).map(([k, v]) => `<option value="${k}">${v}</option>`).join('')}</select>
+      <input class="form-input" id="limit-target" list="limit-categories" value="${escapeHtml(l.target)}">
+    </div>
+    <datalist id="limit-categories">${categories.map(c => `<option value="${escapeHtml(c)}">`).join('')}</datalist>
+    <div class="form-label">Минут в день: будни и выходные (пусто — как в будни)</div>
+    <div style="display:flex;gap:8px">
+      <input class="form-input" id="limit-weekday" type="number" min="1" max="1440" value="${l.weekday_minutes}">
+      <input class="form-input" id="limit-weekend" type="number" min="1" max="1440" value="${l.weekend_minutes ?? ''}">
+    </div>
+    <label class="body-hint" style="display:flex;align-items:center;gap:6px;margin-top:8px">
+      <input type="checkbox" id="limit-block"${l.block ? ' checked' : ''}> При 120% блокировать до конца дня (режим фокуса)
+    </label>
+    <div class="modal-actions">
+      ${limit ? '<button class="btn-secondary" id="limit-delete" style="margin-right:auto">Удалить</button>' : ''}
+      <button class="btn-secondary" id="limit-cancel">Отмена</button>
+      <button class="btn-primary" id="limit-save">Сохранить</button>
+    </div>
+  </div>`;
+  document.body.appendChild(overlay);
+  const $ = sel => overlay.querySelector(sel);
+  const syncPlaceholder = () => { $('#limit-target').placeholder = PLACEHOLDERS[$('#limit-kind').value]; };
+  $('#limit-kind').value = l.kind;
+  $('#limit-kind').onchange = syncPlaceholder;
+  syncPlaceholder();
+  $('#limit-cancel').onclick = () => overlay.remove();
+  overlay.addEventListener('click', (e) => { if (e.target === overlay) overlay.remove(); });
+  const done = () => { overlay.remove(); onSaved(); };
+  if (limit) {
+    $('#limit-delete').onclick = async () => {
+      if (!await confirmModal('Удалить лимит вместе с историей?', 'Удалить')) return;
+      await invoke('delete_screen_limit', { id: limit.id }).catch(e => toast(String(e)));
+      done();
+    };
+  }
+  $('#limit-save').onclick = async () => {
+    const weekend = parseInt($('#limit-weekend').value, 10);
+    const params = {
+      kind: $('#limit-kind').value,
+      target: $('#limit-target').value,
+      weekdayMinutes: parseInt($('#limit-weekday').value, 10) || 0,
+      weekendMinutes: Number.isFinite(weekend) ? weekend : null,
+      block: $('#limit-block').checked,
+    };
+    try {
+      if (limit) await invoke('update_screen_limit', { id: limit.id, ...params, isActive: limit.is_active });
+      else await invoke('create_screen_limit', params);
+      done();
+    } catch (e) { toast(String(e)); }
+  };
+}
+
+function limitRow(l) {
+  const pct = Math.min(100, l.percent);
+  const over = l.used_minutes > l.today_limit;
+  return `<div class="rules-row${l.is_active ? '' : ' disabled'}" data-limit="${l.id}">
+    <input type="checkbox" data-limit-toggle="${l.id}"${l.is_active ? ' checked' : ''} title="Включено">
+    <span class="rules-cond">${KIND_ICONS[l.kind]} ${escapeHtml(l.target)}${l.block ? ' <span class="rules-score" title="Блокировка при 120%">⛔</span>' : ''}</span>
+    <span class="limit-bar${over ? ' over' : ''}" title="${l.percent}%"><span style="width:${pct}%"></span></span>
+    <span class="rules-target">${fmtMin(l.used_minutes)} / ${fmtMin(l.today_limit)}</span>
+    ${l.level ? `<span class="rules-score">${LEVEL_LABELS[l.level]}</span>` : ''}
+  </div>`;
+}
+
+function historyRows(history) {
+  if (!history.length) return '';
+  return `<div class="rules-list">${history.map(h => `
+    <div class="rules-row review-goal-row">
+      <span class="rules-cond">${KIND_ICONS[h.kind]} ${escapeHtml(h.label)}</span>
+      <span class="review-dots">${h.days.map(d => `<span class="review-dot${d.used_minutes <= d.limit_minutes ? ' pass' : ''}${d.used_minutes === 0 ? ' empty' : ''}" title="${d.date}: ${fmtMin(d.used_minutes)} из ${fmtMin(d.limit_minutes)}${d.level ? ` · ${LEVEL_LABELS[d.level]}` : ''}"></span>`).join('')}</span>
+      <span class="rules-score" title="Дней с превышением / дней с данными · в среднем">${h.exceeded_days}/${h.tracked_days} · ⌀ ${fmtMin(h.avg_minutes)}</span>
+    </div>`).join('')}</div>`;
+}
+
+export async function renderScreenLimits(el) {
+  let limits, history, rules;
+  try {
+    [limits, history, rules] = await Promise.all([
+      invoke('get_screen_limits'),
+      invoke('get_screen_limit_history', { days: 14 }).catch(() => []),
+      invoke('get_activity_rules').catch(() => ({ categories: [], rules: [] })),
+    ]);
+  } catch (e) { el.innerHTML = `<div class="body-hint">${escapeHtml(String(e))}</div>`; return; }
+  const reload = () => renderScreenLimits(el);
+  const categories = [...new Set([...rules.categories.map(c => c.id), ...rules.rules.map(r => r.category)])];
+
+  el.innerHTML = `
+    <div style="display:flex;align-items:center;gap:8px;margin:16px 0 8px;">
+      <div style="font-size:13px;font-weight:600;color:var(--text-primary);flex:1">Лимиты на сегодня</div>
+      <button class="btn-smallall" id="limits-add">+ Лимит</button>
+    </div>
+    ${limits.length ? `<div class="rules-list">${limits.map(limitRow).join('')}</div>`
+      : '<div class="body-hint">Лимитов нет. При 80% придёт уведомление, при 100% — сообщение от Ханни, при 120% — блокировка (если включена).</div>'}
+    ${history.length ? `<div style="font-size:13px;font-weight:600;margin:16px 0 8px;color:var(--text-primary);">История за 14 дней</div>${historyRows(history)}` : ''}`;
+
+  el.querySelector('#limits-add').onclick = () => openLimitEditor(null, categories, reload);
+  el.onclick = (e) => {
+    if (e.target.closest('[data-limit-toggle]')) return;
+    const row = e.target.closest('[data-limit]');
+    if (row) openLimitEditor(limits.find(l => l.id === +row.dataset.limit), categories, reload);
+  };
+  el.onchange = async (e) => {
+    const toggle = e.target.closest('[data-limit-toggle]');
+    if (!toggle) return;
+    const l = limits.find(x => x.id === +toggle.dataset.limitToggle);
+    try {
+      await invoke('update_screen_limit', { id: l.id, kind: l.kind, target: l.target, weekdayMinutes: l.weekday_minutes, weekendMinutes: l.weekend_minutes, block: l.block, isActive: toggle.checked });
+      reload();
+    } catch (err) { toast(String(err)); }
+  };
+}
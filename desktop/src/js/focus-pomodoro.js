@@ -0,0 +1,49 @@
+// focus-pomodoro.js — Pomodoro stats: completed cycles per task / project, daily cycles, recent sessions
+import { invoke } from './state.js';
+import { escapeHtml } from './utils.js';
+
+const STATUS_LABELS = { done: 'завершена', stopped: 'остановлена', running: 'идёт', paused: 'пауза' };
+
+const fmtMin = m => m >= 60 ? `${Math.floor(m / 60)}ч ${Math.round(m % 60)}м` : `${Math.round(m)}м`;
+
+function totalsRows(rows) {
+  if (!rows.length) return '<div class="body-hint">Пока нет сессий.</div>';
+  return `<div class="rules-list">${rows.map(t => `
+    <div class="rules-row pomo-row">
+      <span class="rules-cond">${escapeHtml(t.title)}</span>
+      <span class="rules-target">🍅 ${t.cycles}</span>
+      <span class="rules-target">${fmtMin(t.work_min)}</span>
+      <span class="rules-score" title="Сессий / прерываний">${t.sessions} сес. · ⚡ ${t.interruptions}</span>
+    </div>`).join('')}</div>`;
+}
+
+export async function renderPomodoroStats(el, days = 30) {
+  let st;
+  try { st = await invoke('get_pomodoro_stats', { days }); }
+  catch (e) { el.innerHTML = `<div class="body-hint">${escapeHtml(String(e))}</div>`; return; }
+  const maxDay = Math.max(1, ...st.per_day.map(d => d.cycles));
+
+  el.innerHTML = `
+    <div style="display:flex;align-items:center;gap:8px;margin:16px 0 8px;">
+      <div style="font-size:13px;font-weight:600;color:var(--text-primary);flex:1">Помодоро за ${st.days} дн. — 🍅 ${st.total_cycles} · ${fmtMin(st.total_work_min)}</div>
+      ${[7, 30, 90].map(d => `<button class="btn-smallall${d === st.days ? ' active' : ''}" data-pomo-days="${d}">${d} дн.</button>`).join('')}
+    </div>
+    ${st.per_day.length ? `<div class="pomo-days">${st.per_day.map(d => `
+      <div class="pomo-day" title="${d.date}: ${d.cycles}"><div class="pomo-day-bar" style="height:${Math.round(d.cycles / maxDay * 100)}%"></div></div>`).join('')}</div>` : ''}
+    <div style="font-size:13px;font-weight:600;margin:16px 0 8px;color:var(--text-primary);">По задачам</div>
+    ${totalsRows(st.by_task)}
+    <div style="font-size:13px;font-weight:600;margin:16px 0 8px;color:var(--text-primary);">По проектам</div>
+    ${totalsRows(st.by_project)}
+    ${st.recent.length ? `<div style="font-size:13px;font-weight:600;margin:16px 0 8px;color:var(--text-primary);">Последние сессии</div>
+    <div class="rules-list">${st.recent.map(r => `
+      <div class="rules-row pomo-row" title="${escapeHtml(r.notes)}">
+        <span class="rules-score">${new Date(r.started_at).toLocaleString('ru-RU', { day: '2-digit', month: '2-digit', hour: '2-digit', minute: '2-digit' })}</span>
+        <span class="rules-cond">${escapeHtml(r.title)}${r.notes ? ' 📝' : ''}</span>
+        <span class="rules-target">🍅 ${r.cycles} · ⚡ ${r.interruptions}</span>
+        <span class="rules-score">${STATUS_LABELS[r.status] || r.status}</span>
+      </div>`).join('')}</div>` : ''}`;
+
+  el.querySelectorAll('[data-pomo-days]').forEach(btn => {
+    btn.onclick = () => renderPomodoroStats(el, +btn.dataset.pomoDays);
+  });
+}
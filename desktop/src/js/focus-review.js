@@ -0,0 +1,84 @@
+// focus-review.js — Timeline goals (current period, streaks, pass/fail history) + weekly review note
+import { invoke } from './state.js';
+import { escapeHtml, renderMarkdown, confirmModal, toast } from './utils.js';
+
+const PERIOD_LABELS = { daily: 'в день', weekly: 'в неделю', monthly: 'в месяц' };
+const STATUS_ICONS = { pass: '✅', fail: '❌', pending: '⏳' };
+
+const fmtMin = m => m >= 60 ? `${Math.floor(m / 60)}ч ${Math.round(m % 60)}м` : `${Math.round(m)}м`;
+
+function goalRows(goals) {
+  if (!goals.length) return '<div class="body-hint">Целей пока нет — добавьте первую ниже.</div>';
+  return `<div class="rules-list">${goals.map(g => `
+    <div class="rules-row review-goal-row">
+      <span class="rules-cond">${escapeHtml(g.type_icon)} ${escapeHtml(g.type_name)} ${escapeHtml(g.operator)} ${fmtMin(g.target_minutes)} ${PERIOD_LABELS[g.period] || g.period}</span>
+      <span class="rules-target" title="${g.period_start} — ${g.period_end}">${STATUS_ICONS[g.status] || ''} ${fmtMin(g.actual_minutes)}</span>
+      <span class="review-dots">${g.history.map(h => `<span class="review-dot${h.passed ? ' pass' : ''}" title="${h.period_start}: ${fmtMin(h.actual_minutes)}"></span>`).join('')}</span>
+      <span class="rules-score" title="Текущая / лучшая серия">🔥 ${g.current_streak} · ${g.best_streak}${g.pass_rate != null ? ` · ${Math.round(g.pass_rate * 100)}%` : ''}</span>
+      <button class="btn-smallall" data-goal-delete="${g.id}" title="Удалить">✕</button>
+    </div>`).join('')}</div>`;
+}
+
+export async function renderWeeklyReview(el) {
+  let goals, types, reviews;
+  try {
+    [goals, types, reviews] = await Promise.all([
+      invoke('get_timeline_goal_progress', { history: 14 }),
+      invoke('get_activity_types'),
+      invoke('get_weekly_reviews', { limit: 1 }).catch(() => []),
+    ]);
+  } catch (e) { el.innerHTML = `<div class="body-hint">${escapeHtml(String(e))}</div>`; return; }
+
+  el.innerHTML = `
+    <div style="font-size:13px;font-weight:600;margin:16px 0 8px;color:var(--text-primary);">Цели</div>
+    ${goalRows(goals)}
+    <div class="review-goal-add">
+      <select class="form-input" id="review-goal-type">${types.map(t => `<option value="${t.id}">${escapeHtml(t.icon)} ${escapeHtml(t.name)}</option>`).join('')}</select>
+      <select class="form-input" id="review-goal-op"><option value=">=">не меньше</option><option value="<=">не больше</option></select>
+      <input class="form-input" id="review-goal-min" type="number" min="1" placeholder="минут">
+      <select class="form-input" id="review-goal-period">${Object.entries(PERIOD_LABELS).map(([k, v]) => `<option value="${k}">${v}</option>`).join('')}</select>
+      <button class="btn-smallall" id="review-goal-add">+ Цель</button>
+    </div>
+    <div style="display:flex;align-items:center;gap:8px;margin:16px 0 8px;">
+      <div style="font-size:13px;font-weight:600;color:var(--text-primary);flex:1">Недельный обзор</div>
+      <label class="body-hint" style="display:flex;align-items:center;gap:4px;"><input type="checkbox" id="review-llm"> кратко от Ханни</label>
+      <button class="btn-smallall" id="review-generate">Собрать за прошлую неделю</button>
+    </div>
+    <div class="body-hint">Сохраняется заметкой с тегом weekly-review.</div>
+    <div class="review-note" id="review-note">${reviews[0] ? renderMarkdown(reviews[0].content) : ''}</div>`;
+
+  el.querySelectorAll('[data-goal-delete]').forEach(btn => {
+    btn.onclick = async () => {
+      if (!await confirmModal('Удалить цель вместе с историей?', 'Удалить')) return;
+      await invoke('delete_timeline_goal', { id: +btn.dataset.goalDelete }).catch(err => toast(String(err)));
+      renderWeeklyReview(el);
+    };
+  });
+
+  el.querySelector('#review-goal-add').onclick = async () => {
+    const minutes = parseInt(el.querySelector('#review-goal-min').value, 10);
+    if (!minutes || minutes < 1) { toast('Укажите минуты'); return; }
+    try {
+      await invoke('create_timeline_goal', {
+        typeId: +el.querySelector('#review-goal-type').value,
+        operator: el.querySelector('#review-goal-op').value,
+        targetMinutes: minutes,
+        period: el.querySelector('#review-goal-period').value,
+      });
+      renderWeeklyReview(el);
+    } catch (e) { toast(String(e)); }
+  };
+
+  el.querySelector('#review-generate').onclick = async (e) => {
+    const btn = e.currentTarget;
+    btn.disabled = true;
+    btn.textContent = 'Собираю…';
+    try {
+      const r = await invoke('generate_weekly_review', { summarize: el.querySelector('#review-llm').checked });
+      el.querySelector('#review-note').innerHTML = renderMarkdown(r.content);
+      toast(r.llm_error ? `Обзор сохранён без резюме: ${r.llm_error}` : 'Обзор сохранён в заметки', r.llm_error ? undefined : 'success');
+    } catch (err) { toast(String(err)); }
+    btn.disabled = false;
+    btn.textContent = 'Собрать за прошлую неделю';
+  };
+}
@@ -0,0 +1,186 @@
+// focus-rules.js — Activity classification rules, "unclassified top apps" report, ActivityWatch import/export
+import { invoke } from './state.js';
+import { escapeHtml, toast } from './utils.js';
+
+const CATEGORY_LABELS = {
+  coding: 'Код', writing: 'Письмо', learning: 'Обучение', communication: 'Общение',
+  browsing: 'Браузер', other: 'Другое', social: 'Соцсети', media: 'Развлечения',
+};
+const FIELD_LABELS = { app: 'Приложение', url: 'URL', title: 'Заголовок', any: 'Любое поле' };
+const KIND_LABELS = { substring: 'содержит', regex: 'regex', domain: 'домен' };
+
+const catLabel = c => CATEGORY_LABELS[c] || c;
+const scoreLabel = s => s > 0 ? `+${s}` : `${s}`;
+
+function openRuleEditor(rule, meta, onSaved, draft = {}) {
+  const r = rule || { field: 'app', kind: 'substring', pattern: '', category: 'coding', score: null, priority: 0, enabled: true, ...draft };
+  const overlay = document.createElement('div');
+  overlay.className = 'modal-overlay';
+  overlay.innerHTML = `<div class="modal" style="max-width:440px">
+    <div class="modal-title">${rule ? 'Правило' : 'Новое правило'}</div>
+    <div class="form-label">Условие</div>
+    <div style="display:flex;gap:8px">
+      <select class="form-select" id="rule-field">${meta.fields.map(f => `<option value="${f}">${FIELD_LABELS[f]}</option>`).join('')}</select>
+      <select class="form-select" id="rule-kind">${meta.kinds.map(k => `<option value="${k}">${KIND_LABELS[k]}</option>`).join('')}</select>
+    </div>
+    <input class="form-input" id="rule-pattern" value="${escapeHtml(r.pattern)}" placeholder="youtube.com, Jira, tutorial|курс">
+    <div class="form-label">Категория</div>
+    <input class="form-input" id="rule-category" list="rule-categories" value="${escapeHtml(r.category)}">
+    <datalist id="rule-categories">${meta.categories.map(c => `<option value="${c.id}">${catLabel(c.id)}</option>`).join('')}</datalist>
+    <div class="form-label">Продуктивность (−1…1, пусто — по категории) и приоритет</div>
+    <div style="display:flex;gap:8px">
+      <input class="form-input" id="rule-score" type="number" step="0.1" min="-1" max="1" value="${r.score ?? ''}">
+      <input class="form-input" id="rule-priority" type="number" step="1" value="${r.priority}" title="Чем выше, тем раньше проверяется">
+    </div>
+    <div class="modal-actions">
+      ${rule ? '<button class="btn-secondary" id="rule-delete" style="margin-right:auto">Удалить</button>' : ''}
+      <button class="btn-secondary" id="rule-cancel">Отмена</button>
+      <button class="btn-primary" id="rule-save">Сохранить</button>
+    </div>
+  </div>`;
+  document.body.appendChild(overlay);
+  const $ = sel => overlay.querySelector(sel);
+  $('#rule-field').value = r.field;
+  $('#rule-kind').value = r.kind;
+  $('#rule-cancel').onclick = () => overlay.remove();
+  overlay.addEventListener('click', (e) => { if (e.target === overlay) overlay.remove(); });
+  const done = () => { overlay.remove(); onSaved(); };
+  if (rule) {
+    $('#rule-delete').onclick = async () => {
+      await invoke('delete_activity_rule', { id: rule.id }).catch(e => toast(String(e)));
+      done();
+    };
+  }
+  $('#rule-save').onclick = async () => {
+    const score = parseFloat($('#rule-score').value);
+    const params = {
+      field: $('#rule-field').value,
+      kind: $('#rule-kind').value,
+      pattern: $('#rule-pattern').value,
+      category: $('#rule-category').value.trim(),
+      score: Number.isFinite(score) ? score : null,
+      priority: parseInt($('#rule-priority').value, 10) || 0,
+    };
+    try {
+      if (rule) await invoke('update_activity_rule', { id: rule.id, ...params, enabled: rule.enabled });
+      else await invoke('create_activity_rule', params);
+      done();
+    } catch (e) { toast(String(e)); }
+  };
+}
+
+// ActivityWatch: import a bucket export (window / afk / web) or write Hanni's
+// snapshots out in the same format.
+function openActivityWatchModal(onImported) {
+  const overlay = document.createElement('div');
+  overlay.className = 'modal-overlay';
+  overlay.innerHTML = `<div class="modal" style="max-width:520px">
+    <div class="modal-title">ActivityWatch</div>
+    <div class="form-label">Импорт: файл экспорта (все бакеты или один)</div>
+    <input class="form-input" id="aw-import-path" placeholder="~/Downloads/aw-bucket-export.json">
+    <div class="form-label">Экспорт: куда сохранить и за какой период</div>
+    <input class="form-input" id="aw-export-path" value="~/Downloads/hanni-activitywatch.json">
+    <div style="display:flex;gap:8px">
+      <input class="form-input" id="aw-from" type="date" title="С (пусто — с начала)">
+      <input class="form-input" id="aw-to" type="date" title="По (пусто — до сегодня)">
+    </div>
+    <div class="form-hint" id="aw-status" style="margin-top:6px"></div>
+    <div class="modal-actions">
+      <button class="btn-secondary" id="aw-cancel">Закрыть</button>
+      <button class="btn-secondary" id="aw-export">Экспортировать</button>
+      <button class="btn-primary" id="aw-import">Импортировать</button>
+    </div>
+  </div>`;
+  document.body.appendChild(overlay);
+  const $ = sel => overlay.querySelector(sel);
+  $('#aw-cancel').onclick = () => overlay.remove();
+  $('#aw-import').onclick = async () => {
+    const path = $('#aw-import-path').value.trim();
+    if (!path) return;
+    $('#aw-import').disabled = true;
+    $('#aw-status').textContent = 'Импорт…';
+    try {
+      const s = await invoke('import_activitywatch', { path });
+      toast(`ActivityWatch: ${s.snapshots} снимков за ${s.days} дн. (пропущено уже записанных: ${s.skipped_existing})`, 'success');
+      overlay.remove();
+      onImported();
+    } catch (e) {
+      $('#aw-status').textContent = '';
+      toast('Ошибка импорта: ' + e);
+      $('#aw-import').disabled = false;
+    }
+  };
+  $('#aw-export').onclick = async () => {
+    try {
+      const r = await invoke('export_activitywatch', { path: $('#aw-export-path').value.trim(), from: $('#aw-from').value || null, to: $('#aw-to').value || null });
+      $('#aw-status').textContent = `Сохранено: ${r.path} — ${r.events} событий в ${r.buckets} бакетах`;
+    } catch (e) { toast(String(e)); }
+  };
+}
+
+export async function renderActivityRules(el) {
+  let meta, unclassified;
+  try {
+    [meta, unclassified] = await Promise.all([
+      invoke('get_activity_rules'),
+      invoke('get_unclassified_activity', { days: 7, limit: 15 }).catch(() => []),
+    ]);
+  } catch (e) { el.innerHTML = `<div class="body-hint">${escapeHtml(String(e))}</div>`; return; }
+  const reload = () => renderActivityRules(el);
+
+  el.innerHTML = `
+    <div style="display:flex;align-items:center;gap:8px;margin:16px 0 8px;">
+      <div style="font-size:13px;font-weight:600;color:var(--text-primary);flex:1">Правила классификации</div>
+      <button class="btn-smallall" id="rules-reclassify" title="Применить правила к снимкам за 30 дней">↻ Пересчитать историю</button>
+      <button class="btn-smallall" id="rules-aw" title="Импорт и экспорт в формате ActivityWatch">ActivityWatch</button>
+      <button class="btn-smallall" id="rules-add">+ Правило</button>
+    </div>
+    ${meta.rules.length ? `<div class="rules-list">${meta.rules.map(r => `
+      <div class="rules-row${r.enabled ? '' : ' disabled'}" data-rule="${r.id}">
+        <input type="checkbox" data-rule-toggle="${r.id}"${r.enabled ? ' checked' : ''} title="Включено">
+        <span class="rules-cond">${FIELD_LABELS[r.field]} ${KIND_LABELS[r.kind]} <b>${escapeHtml(r.pattern)}</b></span>
+        <span class="rules-target">→ ${escapeHtml(catLabel(r.category))} <span class="rules-score">${scoreLabel(r.effective_score)}</span></span>
+        ${r.priority ? `<span class="rules-priority" title="Приоритет">#${r.priority}</span>` : ''}
+      </div>`).join('')}</div>`
+      : '<div class="body-hint">Правил нет — используется встроенная классификация. Добавьте правило, чтобы переопределить категорию приложения, сайта или заголовка.</div>'}
+    <div style="font-size:13px;font-weight:600;margin:16px 0 8px;color:var(--text-primary);">Без категории за 7 дней</div>
+    ${unclassified.length ? `<div class="rules-list">${unclassified.map((u, i) => `
+      <div class="rules-row" data-unclassified="${i}" title="${escapeHtml(u.samples.join('\n'))}">
+        <span class="rules-cond">${u.kind === 'domain' ? '🌐' : '🖥'} ${escapeHtml(u.value)}</span>
+        <span class="rules-target">${Math.round(u.minutes)} мин</span>
+        <button class="btn-smallall" data-unclassified-add="${i}">+ Правило</button>
+      </div>`).join('')}</div>`
+      : '<div class="body-hint">Всё время распознано.</div>'}`;
+
+  el.querySelector('#rules-add').onclick = () => openRuleEditor(null, meta, reload);
+  el.querySelector('#rules-aw').onclick = () => openActivityWatchModal(reload);
+  el.querySelector('#rules-reclassify').onclick = async () => {
+    try {
+      const r = await invoke('reclassify_activity_snapshots', { days: 30 });
+      toast(`Пересчитано снимков: ${r.scanned}, изменено: ${r.changed}`, 'success');
+      reload();
+    } catch (e) { toast(String(e)); }
+  };
+  el.onclick = (e) => {
+    if (e.target.closest('[data-rule-toggle]')) return;
+    const add = e.target.closest('[data-unclassified-add]');
+    if (add) {
+      const u = unclassified[+add.dataset.unclassifiedAdd];
+      openRuleEditor(null, meta, reload, u.kind === 'domain'
+        ? { field: 'url', kind: 'domain', pattern: u.value }
+        : { field: 'app', kind: 'substring', pattern: u.value });
+      return;
+    }
+    const row = e.target.closest('[data-rule]');
+    if (row) openRuleEditor(meta.rules.find(r => r.id === +row.dataset.rule), meta, reload);
+  };
+  el.onchange = async (e) => {
+    const toggle = e.target.closest('[data-rule-toggle]');
+    if (!toggle) return;
+    const r = meta.rules.find(x => x.id === +toggle.dataset.ruleToggle);
+    try {
+      await invoke('update_activity_rule', { id: r.id, field: r.field, kind: r.kind, pattern: r.pattern, category: r.category, score: r.score, priority: r.priority, enabled: toggle.checked });
+      reload();
+    } catch (err) { toast(String(err)); }
+  };
+}
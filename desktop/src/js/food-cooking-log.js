@@ -5,21 +5,6 @@ import { invoke } from './state.js';
 import { escapeHtml, toast, emptyState } from './utils.js';
 import { EMPTY_ICONS } from './icons.js';
 
-// Convert `amount` between units of the same family (mass: г/кг, volume: мл/л).
-// Returns null when units are incompatible (e.g. г vs шт) so the caller can fall
-// back to deducting a single unit.
-function convertAmount(amount, fromUnit, toUnit) {
-  const u = (x) => String(x || '').trim().toLowerCase().replace(/\.$/, '');
-  const f = u(fromUnit), t = u(toUnit);
-  if (f === t) return amount;
-  const MASS = { 'г': 1, 'гр': 1, 'кг': 1000 };
-  const VOL = { 'мл': 1, 'л': 1000 };
-  for (const fam of [MASS, VOL]) {
-    if (f in fam && t in fam) return amount * fam[f] / fam[t];
-  }
-  return null;
-}
-
 const MEAL_OPTS = [['breakfast', 'Завтрак'], ['lunch', 'Обед'], ['dinner', 'Ужин'], ['snack', 'Перекус']];
 // Default the diary meal-type to the current time of day.
 function mealByHour() {
@@ -32,7 +17,6 @@ export function showCookingLogModal(date, onSaved, preRecipe, preEventId) {
   let selectedId = preRecipe ? preRecipe.id : null;
   let selectedName = preRecipe ? preRecipe.name : '';
   let rating = 0, allRecipes = [], deductMatches = [];
-  let selectedRecipe = null;       // full get_recipe (КБЖУ) — for the diary entry
   let mealType = mealByHour();
 
   const overlay = document.createElement('div');
@@ -102,34 +86,21 @@ export function showCookingLogModal(date, onSaved, preRecipe, preEventId) {
     });
   }
 
-  // Match the selected recipe's ingredients to fridge products so we can offer
-  // to deduct them on save (units rarely line up, so it's an opt-in checklist).
-  const clNorm = (s) => String(s == null ? '' : s).trim().toLowerCase();
+  // Ask the backend which fridge products cooking this recipe would use (unit
+  // conversion via catalog density / piece weight) and offer them as an opt-in
+  // checklist; only the checked rows are deducted on save.
   async function loadDeduct() {
     const box = overlay.querySelector('#cl-deduct');
     if (!selectedId) { box.innerHTML = ''; deductMatches = []; return; }
     box.innerHTML = '<div style="color:var(--text-muted);font-size:12px">Проверяю холодильник…</div>';
-    const [rec, products] = await Promise.all([
-      invoke('get_recipe', { id: selectedId }).catch(() => null),
-      invoke('get_products', {}).catch(() => []),
-    ]);
-    selectedRecipe = rec; // reused on save to fill the diary entry's macros
-    const ings = (rec && rec.ingredient_items) || [];
-    deductMatches = [];
-    for (const ing of ings) {
-      const p = products.find(x =>
-        (ing.catalog_id && x.catalog_id && x.catalog_id === ing.catalog_id) || clNorm(x.name) === clNorm(ing.name));
-      if (p) deductMatches.push({ ing, p });
-    }
+    deductMatches = await invoke('preview_recipe_consumption', { recipeId: selectedId, servings: null }).catch(() => []);
     if (!deductMatches.length) { box.innerHTML = ''; return; }
     box.innerHTML = `<div class="form-group"><label class="form-label">Списать из холодильника</label>
       ${deductMatches.map((m, i) => {
-        const conv = m.ing.amount ? convertAmount(m.ing.amount, m.ing.unit, m.p.unit) : null;
-        const dec = (conv != null) ? Math.round(conv * 100) / 100 : 1;
-        const un = escapeHtml(m.p.unit || 'шт');
+        const un = escapeHtml(m.unit || 'шт');
         return `<label style="display:flex;gap:8px;align-items:center;font-size:13px;padding:2px 0">
         <input type="checkbox" class="cl-deduct-cb" data-i="${i}" checked>
-        ${escapeHtml(m.p.name)} <span style="color:var(--text-muted)">(есть ${m.p.quantity ?? 1} ${un} → спишется ${dec} ${un})</span>
+        ${escapeHtml(m.product_name)} <span style="color:var(--text-muted)">(есть ${m.before} ${un} → спишется ${m.take} ${un}${m.estimated ? ' ≈' : ''})</span>
       </label>`;
       }).join('')}</div>`;
   }
@@ -152,31 +123,16 @@ export function showCookingLogModal(date, onSaved, preRecipe, preEventId) {
           durationMinutes: 30, category: 'Готовка', color, priority: null,
         }).catch(() => null);
       }
-      await invoke('log_cooking', { recipeId: selectedId, date: d, tasteRating: rating, cookNote: note, eventId });
-      // Mirror the cook into the food diary so its macros show up there (v1: recipe totals ×1).
+      const consumeProductIds = [...overlay.querySelectorAll('.cl-deduct-cb:checked')]
+        .map(cb => deductMatches[parseInt(cb.dataset.i)]?.product_id).filter(id => id != null);
+      await invoke('log_cooking', { recipeId: selectedId, date: d, tasteRating: rating, cookNote: note, eventId, servings: null, consumeProductIds });
+      // Mirror the cook into the food diary: one serving, macros filled from the recipe's nutrition.
       if (diaryCb.checked) {
-        const r = selectedRecipe || {};
         await invoke('log_food', {
-          date: d, mealType, name: selectedName,
-          calories: r.calories || null, protein: r.protein || null,
-          carbs: r.carbs || null, fat: r.fat || null, notes: 'из готовки',
+          date: d, mealType, name: selectedName, recipeId: selectedId, servings: 1,
+          notes: 'из готовки',
         }).catch(() => {});
       }
-      // Deduct the checked fridge items: by amount when units match, else one unit.
-      for (const cb of overlay.querySelectorAll('.cl-deduct-cb:checked')) {
-        const m = deductMatches[parseInt(cb.dataset.i)]; if (!m) continue;
-        const conv = m.ing.amount ? convertAmount(m.ing.amount, m.ing.unit, m.p.unit) : null;
-        const dec = (conv != null) ? conv : 1; // convert across compatible units, else one unit
-        const next = Math.round(((parseFloat(m.p.quantity) || 0) - dec) * 100) / 100;
-        try {
-          if (next <= 0) await invoke('delete_product', { id: m.p.id });
-          else {
-            const args = { id: m.p.id, name: m.p.name, quantity: next, expiryDate: m.p.expiry_date, location: m.p.location, notes: m.p.notes };
-            if (m.p.catalog_id != null) args.catalogId = m.p.catalog_id;
-            await invoke('update_product', args);
-          }
-        } catch (_) { /* best-effort deduction */ }
-      }
       close();
       if (onSaved) await onSaved();
     } catch (e) { toast('Ошибка: ' + (e.message || e)); }
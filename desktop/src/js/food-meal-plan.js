@@ -21,13 +21,12 @@ export async function renderMealPlanBlock(date) {
   }).join('');
 
   const totalCal = meals.reduce((s, m) => s + (m.calories || 0), 0);
-  const recipeIds = [...new Set(meals.map(m => m.recipe_id).filter(Boolean))].join(',');
   return `<div class="meal-plan-block">
     <div class="meal-plan-header">
       <span>🍽 План питания</span>
       <span class="meal-plan-header-right">
         <span class="meal-plan-total">${totalCal} kcal</span>
-        <button class="meal-plan-shop" data-mp-shop="${recipeIds}" title="Добавить недостающие ингредиенты в список покупок">🛒 В покупки</button>
+        <button class="meal-plan-shop" data-mp-shop="${date}" title="Добавить недостающие ингредиенты в список покупок">🛒 В покупки</button>
       </span>
     </div>
     ${items}
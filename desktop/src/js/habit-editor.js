@@ -0,0 +1,77 @@
+// ── habit-editor.js — Habit settings modal: schedule, numeric target, auto-check source ──
+import { invoke } from './state.js';
+import { escapeHtml, toast } from './utils.js';
+
+const DAY_NAMES = ['Пн', 'Вт', 'Ср', 'Чт', 'Пт', 'Сб', 'Вс'];
+export const AUTO_SOURCE_LABELS = {
+  '': 'Вручную', steps: 'Шаги', sleep: 'Сон (мин)', workout: 'Тренировки (мин)', cooking: 'Готовка (блюд)', water: 'Вода',
+};
+
+export function openHabitEditor(habit, onSaved) {
+  const h = habit || { name: '', schedule_kind: 'daily', schedule_days: 31, schedule_count: 3, target: 1, unit: '', auto_source: '' };
+  const overlay = document.createElement('div');
+  overlay.className = 'modal-overlay';
+  overlay.innerHTML = `<div class="modal" style="max-width:440px">
+    <div class="modal-title">${habit ? 'Настройки привычки' : 'Новая привычка'}</div>
+    <div class="form-label">Название</div>
+    <input class="form-input" id="he-name" value="${escapeHtml(h.name || '')}">
+    <div class="form-label">Расписание</div>
+    <select class="form-select" id="he-kind">
+      <option value="daily">Каждый день</option>
+      <option value="days">По дням недели</option>
+      <option value="per_week">N раз в неделю</option>
+      <option value="every_n_days">Каждые N дней</option>
+    </select>
+    <div class="habit-days" id="he-days">${DAY_NAMES.map((d, i) =>
+      `<button type="button" class="habit-day${h.schedule_days & (1 << i) ? ' active' : ''}" data-bit="${i}">${d}</button>`).join('')}</div>
+    <input class="form-input" id="he-count" type="number" min="1" max="365" value="${h.schedule_count || 1}">
+    <div class="form-label">Цель за день</div>
+    <div style="display:flex;gap:8px">
+      <input class="form-input" id="he-target" type="number" min="0" step="any" value="${h.target || 1}" style="max-width:120px">
+      <input class="form-input" id="he-unit" placeholder="стаканов, шагов, мин…" value="${escapeHtml(h.unit || '')}">
+    </div>
+    <div class="form-label">Отмечать автоматически</div>
+    <select class="form-select" id="he-auto">${Object.entries(AUTO_SOURCE_LABELS).map(([k, v]) => `<option value="${k}">${v}</option>`).join('')}</select>
+    <div class="modal-actions">
+      <button class="btn-secondary" id="he-cancel">Отмена</button>
+      <button class="btn-primary" id="he-save">Сохранить</button>
+    </div>
+  </div>`;
+  document.body.appendChild(overlay);
+  const $ = sel => overlay.querySelector(sel);
+  $('#he-kind').value = h.schedule_kind || 'daily';
+  $('#he-auto').value = h.auto_source || '';
+
+  const syncKind = () => {
+    const kind = $('#he-kind').value;
+    $('#he-days').style.display = kind === 'days' ? '' : 'none';
+    $('#he-count').style.display = kind === 'per_week' || kind === 'every_n_days' ? '' : 'none';
+    $('#he-count').placeholder = kind === 'per_week' ? 'Раз в неделю' : 'Интервал, дней';
+  };
+  syncKind();
+  $('#he-kind').onchange = syncKind;
+  $('#he-days').onclick = (e) => { e.target.closest('.habit-day')?.classList.toggle('active'); };
+  $('#he-cancel').onclick = () => overlay.remove();
+  overlay.addEventListener('click', (e) => { if (e.target === overlay) overlay.remove(); });
+
+  $('#he-save').onclick = async () => {
+    const name = $('#he-name').value.trim();
+    if (!name) { toast('Введите название'); return; }
+    const days = [...overlay.querySelectorAll('.habit-day.active')].reduce((m, b) => m | (1 << +b.dataset.bit), 0);
+    const params = {
+      name,
+      scheduleKind: $('#he-kind').value,
+      scheduleDays: days,
+      scheduleCount: parseInt($('#he-count').value, 10) || 1,
+      target: parseFloat($('#he-target').value) || 1,
+      unit: $('#he-unit').value.trim(),
+      autoSource: $('#he-auto').value,
+    };
+    try {
+      if (habit) await invoke('update_habit', { id: habit.id, frequency: null, icon: null, ...params });
+      else await invoke('create_habit', { icon: '', frequency: '', ...params });
+      overlay.remove();
+      onSaved?.();
+    } catch (e) { toast(String(e)); }
+  };
+}
@@ -36,8 +36,8 @@ export async function renderSleepAnalysis(el) {
 
     <div class="uni-dash-grid" style="margin-top:var(--space-2);">
       <div class="uni-dash-card ${qualityColor}">
-        <div class="uni-dash-value">${Math.round(analysis.avg_sleep_quality)}%</div>
-        <div class="uni-dash-label">💤 Качество сна</div>
+        <div class="uni-dash-value">${Math.round(analysis.avg_sleep_quality)}</div>
+        <div class="uni-dash-label">💤 Оценка сна</div>
       </div>
       <div class="uni-dash-card gray">
         <div class="uni-dash-value">${trendIcon} ${trendLabel}</div>
@@ -45,6 +45,34 @@ export async function renderSleepAnalysis(el) {
       </div>
     </div>
 
+    ${analysis.avg_efficiency != null ? `
+    <div class="uni-dash-grid" style="margin-top:var(--space-2);">
+      <div class="uni-dash-card ${analysis.avg_efficiency >= 85 ? 'green' : 'yellow'}">
+        <div class="uni-dash-value">${Math.round(analysis.avg_efficiency)}%</div>
+        <div class="uni-dash-label">🛏 Эффективность</div>
+      </div>
+      <div class="uni-dash-card gray">
+        <div class="uni-dash-value">${Math.round(analysis.avg_latency_minutes)} мин</div>
+        <div class="uni-dash-label">⏳ Засыпание</div>
+      </div>
+      <div class="uni-dash-card gray">
+        <div class="uni-dash-value">${analysis.avg_wakeups.toFixed(1)}</div>
+        <div class="uni-dash-label">👁 Пробуждений</div>
+      </div>
+      <div class="uni-dash-card purple">
+        <div class="uni-dash-value">${Math.round(analysis.avg_deep_minutes)} / ${Math.round(analysis.avg_rem_minutes)} мин</div>
+        <div class="uni-dash-label">🌊 Глубокий / REM</div>
+      </div>
+    </div>` : ''}
+
+    ${analysis.correlations.length ? `
+    <div class="health-recs">
+      <div class="health-recs-title">🔍 Что влияет на сон</div>
+      ${analysis.correlations.map(c => `<div class="health-rec-item">${c.label}: ${c.avg_with} против ${c.avg_without}
+        <b style="color:${c.diff < 0 ? 'var(--color-red)' : 'var(--color-green)'}">${c.diff > 0 ? '+' : ''}${c.diff}</b>
+        <span style="color:var(--text-faint)">(${c.nights_with}/${c.nights_without} ночей)</span></div>`).join('')}
+    </div>` : ''}
+
     ${analysis.recommendations.length ? `
     <div class="health-recs">
       <div class="health-recs-title">💡 Рекомендации</div>
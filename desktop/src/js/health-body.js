@@ -0,0 +1,95 @@
+// health-body.js — Body card: trend weight, weekly rate, goal projection, energy balance, measurements
+import { invoke } from './state.js';
+import { escapeHtml, toast } from './utils.js';
+
+const KIND_LABELS = {
+  weight: 'Вес, кг', body_fat: 'Жир, %', waist: 'Талия, см', hips: 'Бёдра, см',
+  chest: 'Грудь, см', neck: 'Шея, см', arm: 'Рука, см', thigh: 'Бедро, см',
+};
+
+const fmtDate = d => d ? new Date(d + 'T00:00:00').toLocaleDateString('ru-RU', { day: 'numeric', month: 'short' }) : '—';
+const signed = (v, digits = 1) => `${v > 0 ? '+' : ''}${v.toFixed(digits)}`;
+
+function chart(points) {
+  if (points.length < 2) return '';
+  const W = 600, H = 120, pad = 6;
+  const vals = points.flatMap(p => [p.weight, p.trend]);
+  const lo = Math.min(...vals), hi = Math.max(...vals);
+  const t0 = new Date(points[0].date).getTime(), t1 = new Date(points[points.length - 1].date).getTime();
+  const x = d => pad + (new Date(d).getTime() - t0) / (t1 - t0 || 1) * (W - 2 * pad);
+  const y = v => H - pad - (v - lo) / (hi - lo || 1) * (H - 2 * pad);
+  const line = points.map(p => `${x(p.date).toFixed(1)},${y(p.trend).toFixed(1)}`).join(' ');
+  const dots = points.map(p => `<circle cx="${x(p.date).toFixed(1)}" cy="${y(p.weight).toFixed(1)}" r="2.5"><title>${p.date}: ${p.weight} (тренд ${p.trend})</title></circle>`).join('');
+  return `<svg class="body-chart" viewBox="0 0 ${W} ${H}" preserveAspectRatio="none">
+    <g class="body-chart-dots">${dots}</g>
+    <polyline class="body-chart-trend" points="${line}" fill="none"/>
+  </svg>`;
+}
+
+function goalText(g) {
+  if (!g) return 'Цель не задана';
+  if (Math.abs(g.remaining) < 0.1) return 'Цель достигнута';
+  const when = g.projected_date ? `при текущем темпе — ${fmtDate(g.projected_date)}` : 'при текущем темпе не достигается';
+  const need = g.required_weekly_rate != null ? ` · нужно ${signed(g.required_weekly_rate, 2)} кг/нед к ${fmtDate(g.goal_date)}` : '';
+  return `${signed(g.remaining)} кг до ${g.goal_weight} кг, ${when}${need}`;
+}
+
+export async function renderBodyMetrics(el) {
+  let m;
+  try { m = await invoke('get_body_metrics', { days: 90 }); } catch { el.innerHTML = ''; return; }
+
+  const e = m.energy;
+  const g = m.goal;
+  el.innerHTML = `
+    <div style="font-size:13px;font-weight:600;margin:16px 0 8px;color:var(--text-primary);">Тело</div>
+    <div class="uni-dash-grid">
+      <div class="uni-dash-card blue">
+        <div class="uni-dash-value">${m.trend_weight != null ? m.trend_weight.toFixed(1) : '—'}</div>
+        <div class="uni-dash-label">⚖️ Тренд веса, кг${m.lean_mass != null ? ` · сухая масса ${m.lean_mass}` : ''}</div>
+      </div>
+      <div class="uni-dash-card ${m.weekly_rate == null ? 'gray' : g && Math.sign(m.weekly_rate) === Math.sign(g.remaining) ? 'green' : 'gray'}">
+        <div class="uni-dash-value">${m.weekly_rate != null ? signed(m.weekly_rate, 2) : '—'}</div>
+        <div class="uni-dash-label">📉 кг в неделю</div>
+      </div>
+      <div class="uni-dash-card gray">
+        <div class="uni-dash-value">${e?.estimated_tdee ?? '—'}</div>
+        <div class="uni-dash-label">🔥 Расход, ккал/день${e ? ` · баланс ${signed(e.balance, 0)}` : ''}</div>
+      </div>
+    </div>
+    ${chart(m.weights)}
+    <div class="body-goal ${g?.on_track === false ? 'off-track' : ''}">🎯 ${goalText(g)}</div>
+    ${e && e.estimated_tdee == null ? `<div class="body-hint">Расход считается по еде из дневника: записано ${e.logged_days} из ${e.window_days} дней, нужно больше.</div>` : ''}
+    ${m.measurements.length ? `<div class="body-measure-list">${m.measurements.map(x => `<span class="body-measure" title="${x.date}">
+      ${KIND_LABELS[x.kind] || escapeHtml(x.kind)}: <b>${x.value}</b>${x.change != null && x.change !== 0 ? ` <i>${signed(x.change)}</i>` : ''}</span>`).join('')}</div>` : ''}
+    <div class="hr-profile">
+      <select class="form-select" id="body-kind">${Object.entries(KIND_LABELS).map(([k, v]) => `<option value="${k}">${v}</option>`).join('')}</select>
+      <input class="form-input" id="body-value" type="number" step="0.1" min="0" placeholder="Значение">
+      <input class="form-input" id="body-date" type="date">
+      <button class="btn-smallall" id="body-log">Записать</button>
+    </div>
+    <div class="hr-profile">
+      <input class="form-input" id="body-goal-weight" type="number" step="0.1" placeholder="Целевой вес" value="${g?.goal_weight ?? ''}">
+      <input class="form-input" id="body-goal-date" type="date" value="${g?.goal_date ?? ''}">
+      <button class="btn-smallall" id="body-goal-save">Цель</button>
+    </div>`;
+
+  el.querySelector('#body-log').onclick = async () => {
+    const value = parseFloat(el.querySelector('#body-value').value);
+    if (!Number.isFinite(value)) { toast('Введите значение'); return; }
+    try {
+      await invoke('log_body_measurement', {
+        kind: el.querySelector('#body-kind').value, value, date: el.querySelector('#body-date').value || null, note: null,
+      });
+      renderBodyMetrics(el);
+    } catch (err) { toast(String(err)); }
+  };
+  el.querySelector('#body-goal-save').onclick = async () => {
+    const weight = parseFloat(el.querySelector('#body-goal-weight').value);
+    try {
+      await invoke('set_body_goal', {
+        weight: Number.isFinite(weight) ? weight : null, date: el.querySelector('#body-goal-date').value || null,
+      });
+      renderBodyMetrics(el);
+    } catch (err) { toast(String(err)); }
+  };
+}
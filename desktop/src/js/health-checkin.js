@@ -0,0 +1,113 @@
+// health-checkin.js — Daily check-in (mood, energy, stress, symptoms, cycle) and "what moves my mood" report
+import { invoke } from './state.js';
+import { escapeHtml, toast } from './utils.js';
+
+const SCALES = [
+  { key: 'mood', label: 'Настроение', icons: ['😞', '🙁', '😐', '🙂', '😄'] },
+  { key: 'energy', label: 'Энергия', icons: ['🪫', '😪', '😐', '⚡', '🚀'] },
+  { key: 'stress', label: 'Стресс', icons: ['😌', '🙂', '😐', '😣', '🤯'] },
+];
+const OUTCOME_LABELS = { mood: 'Настроение', energy: 'Энергия', stress: 'Стресс' };
+const FLOW_LABELS = ['нет', 'слабые', 'средние', 'обильные'];
+const SEVERITY_LABELS = ['', 'слабо', 'средне', 'сильно'];
+const DEFAULT_SYMPTOMS = ['Головная боль', 'Усталость', 'Тревога', 'Спазмы', 'Вздутие', 'Боль в спине'];
+
+function findingText(f) {
+  const up = f.r > 0;
+  return `<b>${OUTCOME_LABELS[f.outcome]}</b> ${up ? 'выше' : 'ниже'}, когда больше «${escapeHtml(f.label)}»
+    <span class="checkin-finding-meta">${f.avg_high} против ${f.avg_low} · r=${f.r.toFixed(2)} · ${f.days} дн.</span>`;
+}
+
+export async function renderCheckin(el) {
+  let data, report;
+  try {
+    [data, report] = await Promise.all([
+      invoke('get_checkins', { days: 30 }),
+      invoke('get_checkin_correlations', { days: 90 }).catch(() => null),
+    ]);
+  } catch { el.innerHTML = ''; return; }
+  const today = new Date().toLocaleDateString('sv-SE');
+  const entry = data.entries.find(e => e.date === today) || { mood: null, energy: null, stress: null, cycle_flow: null, symptoms: [], notes: '' };
+  const state = { ...entry, symptoms: new Map(entry.symptoms.map(s => [s.name, s.severity])) };
+  const symptomNames = [...new Set([...data.known_symptoms, ...DEFAULT_SYMPTOMS, ...state.symptoms.keys()])];
+  const cycle = data.cycle;
+
+  el.innerHTML = `
+    <div style="font-size:13px;font-weight:600;margin:16px 0 8px;color:var(--text-primary);">Самочувствие сегодня</div>
+    <div class="checkin-card">
+      ${SCALES.map(s => `<div class="checkin-scale" data-scale="${s.key}">
+        <span class="checkin-scale-label">${s.label}</span>
+        ${s.icons.map((icon, i) => `<button class="checkin-btn" data-value="${i + 1}" title="${i + 1}">${icon}</button>`).join('')}
+      </div>`).join('')}
+      <div class="checkin-symptoms">${symptomNames.map(n => `<button class="checkin-symptom" data-symptom="${escapeHtml(n)}">${escapeHtml(n)}</button>`).join('')}
+        <button class="checkin-symptom add" id="checkin-symptom-add">+</button></div>
+      <div class="checkin-row">
+        <select class="form-select" id="checkin-flow">
+          <option value="">Цикл: не отслеживаю</option>
+          ${FLOW_LABELS.map((l, i) => `<option value="${i}">Выделения: ${l}</option>`).join('')}
+        </select>
+        ${cycle ? `<span class="checkin-cycle">День цикла ${cycle.cycle_day}${cycle.predicted_next ? ` · следующий ≈ ${cycle.predicted_next.slice(5)}` : ''}</span>` : ''}
+      </div>
+      <textarea class="form-input" id="checkin-notes" rows="2" placeholder="Заметка о дне">${escapeHtml(entry.notes || '')}</textarea>
+      <button class="btn-primary" id="checkin-save">Сохранить</button>
+    </div>
+    ${report ? `<div style="font-size:13px;font-weight:600;margin:16px 0 8px;color:var(--text-primary);">Что влияет на самочувствие</div>
+      ${report.findings.length
+        ? `<div class="health-recs">${report.findings.slice(0, 8).map(f => `<div class="health-rec-item">${findingText(f)}</div>`).join('')}</div>`
+        : `<div class="body-hint">Нужно хотя бы ${report.min_days} дней с отметками и данными (сейчас ${report.days}).</div>`}` : ''}`;
+
+  const paint = () => {
+    for (const s of SCALES) {
+      el.querySelectorAll(`[data-scale="${s.key}"] .checkin-btn`).forEach(b => b.classList.toggle('active', +b.dataset.value === state[s.key]));
+    }
+    el.querySelectorAll('[data-symptom]').forEach(b => {
+      const sev = state.symptoms.get(b.dataset.symptom) || 0;
+      b.className = `checkin-symptom${sev ? ' sev' + sev : ''}`;
+      b.title = sev ? SEVERITY_LABELS[sev] : 'Нажмите, чтобы отметить';
+    });
+  };
+  el.querySelector('#checkin-flow').value = state.cycle_flow ?? '';
+  paint();
+
+  el.onclick = async (e) => {
+    const btn = e.target.closest('.checkin-btn');
+    if (btn) {
+      const key = btn.closest('[data-scale]').dataset.scale;
+      const v = +btn.dataset.value;
+      state[key] = state[key] === v ? null : v;
+      paint();
+      return;
+    }
+    const sym = e.target.closest('[data-symptom]');
+    if (sym) {
+      // Click cycles severity: off → mild → moderate → severe → off.
+      const name = sym.dataset.symptom;
+      const next = ((state.symptoms.get(name) || 0) + 1) % 4;
+      if (next) state.symptoms.set(name, next); else state.symptoms.delete(name);
+      paint();
+      return;
+    }
+    if (e.target.closest('#checkin-symptom-add')) {
+      const name = (prompt('Симптом') || '').trim();
+      if (!name) return;
+      state.symptoms.set(name, 1);
+      await save(false);
+      return;
+    }
+    if (e.target.closest('#checkin-save')) save(true);
+  };
+
+  async function save(notify) {
+    const flow = el.querySelector('#checkin-flow').value;
+    try {
+      await invoke('save_checkin', {
+        date: today, mood: state.mood, energy: state.energy, stress: state.stress,
+        cycleFlow: flow === '' ? null : +flow,
+        symptoms: [...state.symptoms].map(([name, severity]) => ({ name, severity })),
+        notes: el.querySelector('#checkin-notes').value,
+      });
+      if (notify) toast('Сохранено', 'success');
+      renderCheckin(el);
+    } catch (err) { toast(String(err)); }
+  }
+}
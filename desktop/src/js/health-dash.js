@@ -1,6 +1,10 @@
 // health-dash.js — Health dashboard: sleep, steps, heart rate overview + trends
 import { invoke } from './state.js';
 import { renderSleepAnalysis } from './health-analysis.js';
+import { renderHeartRate } from './health-heart.js';
+import { renderBodyMetrics } from './health-body.js';
+import { renderMedications } from './health-meds.js';
+import { renderCheckin } from './health-checkin.js';
 
 export async function renderHealthDash(paneEl) {
   const today = new Date();
@@ -21,9 +25,9 @@ export async function renderHealthDash(paneEl) {
   const avgSteps = Math.round(summary.avg_steps || 0);
   const avgHr = Math.round(summary.avg_resting_hr || 0);
 
-  // Sleep quality: deep+REM ratio from last session
+  // Sleep score is computed on the backend from stages (sleep_analysis.rs)
   const lastSleep = sleepSessions[0];
-  const sleepQuality = lastSleep ? calcSleepScore(lastSleep) : null;
+  const sleepQuality = lastSleep?.quality_score ?? null;
 
   // Sleep debt: diff from 8h target over 7 days
   const totalSleepMin = sleepSessions.reduce((a, s) => a + s.duration_minutes, 0);
@@ -48,7 +52,7 @@ export async function renderHealthDash(paneEl) {
       </div>
       <div class="uni-dash-card ${avgHr ? 'red' : 'gray'}">
         <div class="uni-dash-value">${avgHr || '—'}</div>
-        <div class="uni-dash-label">❤️ Средний пульс</div>
+        <div class="uni-dash-label">❤️ Пульс покоя (7д)</div>
       </div>
       ${sleepQuality !== null ? `<div class="uni-dash-card ${sleepQuality >= 70 ? 'green' : sleepQuality >= 40 ? 'yellow' : 'red'}">
         <div class="uni-dash-value">${sleepQuality}</div>
@@ -70,12 +74,24 @@ export async function renderHealthDash(paneEl) {
 
     ${renderSleepBars(sleepSessions)}
     ${renderStepsBars(steps7)}
+    <div id="health-heart-container"></div>
+    <div id="health-body-container"></div>
+    <div id="health-meds-container"></div>
+    <div id="health-checkin-container"></div>
     <div id="health-analysis-container"></div>
   `;
 
   // Render sleep analysis async (non-blocking)
   const analysisEl = paneEl.querySelector('#health-analysis-container');
   if (analysisEl) renderSleepAnalysis(analysisEl);
+  const heartEl = paneEl.querySelector('#health-heart-container');
+  if (heartEl) renderHeartRate(heartEl);
+  const bodyEl = paneEl.querySelector('#health-body-container');
+  if (bodyEl) renderBodyMetrics(bodyEl);
+  const medsEl = paneEl.querySelector('#health-meds-container');
+  if (medsEl) renderMedications(medsEl);
+  const checkinEl = paneEl.querySelector('#health-checkin-container');
+  if (checkinEl) renderCheckin(checkinEl);
 }
 
 async function getStepsWeek(from, to) {
@@ -86,31 +102,6 @@ async function getStepsWeek(from, to) {
   } catch { return []; }
 }
 
-function calcSleepScore(session) {
-  if (!session.stages?.length) return null;
-  const totalMin = session.duration_minutes || 1;
-  let deep = 0, rem = 0, awake = 0;
-  for (const st of session.stages) {
-    const d = stageDur(st);
-    if (st.stage === 'deep') deep += d;
-    else if (st.stage === 'rem') rem += d;
-    else if (st.stage === 'awake') awake += d;
-  }
-  const deepPct = deep / totalMin;
-  const remPct = rem / totalMin;
-  const durScore = Math.min(1, totalMin / 480) * 40;
-  const deepScore = Math.min(1, deepPct / 0.2) * 30;
-  const remScore = Math.min(1, remPct / 0.25) * 30;
-  return Math.round(durScore + deepScore + remScore);
-}
-
-function stageDur(st) {
-  try {
-    const s = new Date(st.start_time), e = new Date(st.end_time);
-    return Math.max(0, (e - s) / 60000);
-  } catch { return 0; }
-}
-
 function timeToMin(iso) {
   try {
     const d = new Date(iso);
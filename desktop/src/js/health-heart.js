@@ -0,0 +1,74 @@
+// health-heart.js — Heart-rate card: resting HR vs baseline, zones, elevation alerts
+import { invoke } from './state.js';
+import { toast } from './utils.js';
+
+const ZONE_COLORS = ['var(--text-faint)', 'var(--accent-blue)', 'var(--color-green)', 'var(--color-orange, #d9730d)', 'var(--color-red)'];
+const MAX_SOURCE = { manual: 'задан вручную', age: 'по возрасту', default: 'по умолчанию' };
+
+function alertText(a) {
+  if (a.kind === 'elevated_streak') return `Пульс покоя повышен ${a.days} дн. подряд (+${Math.round(a.delta)} уд/мин к норме) — возможна болезнь или перетренированность, стоит снизить нагрузку.`;
+  if (a.kind === 'elevated') return `Пульс покоя сегодня выше нормы на ${Math.round(a.delta)} уд/мин — следите за восстановлением.`;
+  return `Пульс покоя растёт: среднее за неделю +${Math.round(a.delta)} уд/мин к норме.`;
+}
+
+export async function renderHeartRate(el) {
+  let m;
+  try { m = await invoke('get_heart_rate_metrics', { days: 14 }); } catch { el.innerHTML = ''; return; }
+  if (!m.days.length) { el.innerHTML = ''; return; }
+
+  const trends = m.trends || [];
+  const last = trends[trends.length - 1];
+  const lastDay = m.days[m.days.length - 1];
+  const week = m.days.slice(-7);
+  const zoneTotals = [0, 0, 0, 0, 0];
+  for (const d of week) d.zone_minutes.forEach((v, i) => { zoneTotals[i] += v; });
+  const zoneSum = zoneTotals.reduce((a, b) => a + b, 0) || 1;
+
+  const rhrValues = trends.map(t => t.resting_hr);
+  const lo = Math.min(...rhrValues, ...trends.map(t => t.baseline ?? Infinity)) - 3;
+  const hi = Math.max(...rhrValues) + 3;
+  const bars = trends.map(t => {
+    const pct = Math.max(4, (t.resting_hr - lo) / (hi - lo || 1) * 100);
+    const color = t.elevated ? 'var(--color-red)' : 'var(--color-green)';
+    return `<div class="health-bar-col" title="${t.date}: ${Math.round(t.resting_hr)}${t.baseline ? ` (норма ${Math.round(t.baseline)})` : ''}">
+      <div class="health-bar-fill" style="height:${pct}%;background:${color}"></div>
+      <div class="health-bar-label">${t.date.slice(5)}</div>
+    </div>`;
+  }).join('');
+
+  el.innerHTML = `
+    <div style="font-size:13px;font-weight:600;margin:16px 0 8px;color:var(--text-primary);">Пульс</div>
+    ${(m.alerts || []).map(a => `<div class="health-recs hr-alert"><div class="health-rec-item">⚠️ ${alertText(a)}</div></div>`).join('')}
+    <div class="uni-dash-grid">
+      <div class="uni-dash-card ${last?.elevated ? 'red' : last ? 'green' : 'gray'}">
+        <div class="uni-dash-value">${last ? Math.round(last.resting_hr) : '—'}</div>
+        <div class="uni-dash-label">💓 Пульс покоя${last?.baseline ? ` · норма ${Math.round(last.baseline)}` : ''}</div>
+      </div>
+      <div class="uni-dash-card gray">
+        <div class="uni-dash-value">${lastDay.max_hr ?? '—'}</div>
+        <div class="uni-dash-label">📈 Макс. за ${lastDay.date.slice(5)}</div>
+      </div>
+      <div class="uni-dash-card gray">
+        <div class="uni-dash-value">${lastDay.sleep_hr_sd != null ? '±' + lastDay.sleep_hr_sd : '—'}</div>
+        <div class="uni-dash-label">〰️ Разброс пульса во сне</div>
+      </div>
+    </div>
+    ${trends.length > 1 ? `<div class="health-bar-chart">${bars}</div>` : ''}
+    <div class="form-label" style="margin-top:8px">Зоны за 7 дней · макс. ${m.max_hr} (${MAX_SOURCE[m.max_hr_source] || ''})</div>
+    <div class="hr-zone-bar">${zoneTotals.map((v, i) => v ? `<div style="flex:${v};background:${ZONE_COLORS[i]}" title="Z${i + 1}: ${v} мин"></div>` : '').join('')}</div>
+    <div class="hr-zone-legend">${m.zones.map((z, i) => `<span><i style="background:${ZONE_COLORS[i]}"></i>Z${z.zone} ${z.from}–${z.to}: ${zoneTotals[i]} мин (${Math.round(zoneTotals[i] / zoneSum * 100)}%)</span>`).join('')}</div>
+    <div class="hr-profile">
+      <input class="form-input" id="hr-max-input" type="number" min="100" max="230" placeholder="Макс. пульс" value="${m.max_hr_source === 'manual' ? m.max_hr : ''}">
+      <input class="form-input" id="hr-birth-input" type="number" min="1920" max="2025" placeholder="Год рождения">
+      <button class="btn-smallall" id="hr-profile-save">Сохранить</button>
+    </div>`;
+
+  invoke('get_app_setting', { key: 'birth_year' }).then(v => { if (v) el.querySelector('#hr-birth-input').value = v; }).catch(() => {});
+  el.querySelector('#hr-profile-save').onclick = async () => {
+    const num = id => { const v = parseInt(el.querySelector(id).value, 10); return Number.isFinite(v) ? v : null; };
+    try {
+      await invoke('set_heart_rate_profile', { maxHr: num('#hr-max-input'), birthYear: num('#hr-birth-input') });
+      renderHeartRate(el);
+    } catch (e) { toast(String(e)); }
+  };
+}
@@ -0,0 +1,178 @@
+// health-meds.js — Medications card: today's doses, stock / refill warnings, adherence, editor
+import { invoke } from './state.js';
+import { escapeHtml, toast } from './utils.js';
+
+const DAY_NAMES = ['Пн', 'Вт', 'Ср', 'Чт', 'Пт', 'Сб', 'Вс'];
+const STATUS = { taken: '✓', late: '⏱', skipped: '✕' };
+
+function doseChip(m, d) {
+  const cls = d.status ? `med-dose ${d.status}` : `med-dose${d.overdue ? ' overdue' : ''}`;
+  const title = d.status ? `${d.status === 'skipped' ? 'Пропущено' : 'Принято'}${d.taken_at ? ' в ' + d.taken_at.slice(11, 16) : ''}` : 'Отметить приём (ПКМ — пропустить)';
+  return `<button class="${cls}" data-med="${m.id}" data-time="${d.extra ? '' : d.time}" data-dose-id="${d.dose_id ?? ''}" data-status="${d.status || ''}" title="${title}">
+    ${d.status ? STATUS[d.status] + ' ' : ''}${d.time}</button>`;
+}
+
+function medRow(m) {
+  const adh = m.adherence?.rate;
+  const stock = m.stock != null
+    ? `<span class="med-stock${m.refill_needed ? ' low' : ''}" title="${m.days_left != null ? `Хватит на ${m.days_left} дн.` : ''}">📦 ${m.stock}${m.refill_needed ? ' — пора купить' : ''}</span>`
+    : '';
+  return `<div class="med-row${m.active ? '' : ' inactive'}">
+    <div class="med-main">
+      <div class="med-name">${escapeHtml(m.name)}${m.dose ? ` <span class="med-dose-label">${m.dose} ${escapeHtml(m.unit)}</span>` : ''}</div>
+      <div class="med-meta">${escapeHtml(m.schedule_label)}${adh != null ? ` · приём ${adh}%` : ''} ${stock}</div>
+    </div>
+    <div class="med-doses">
+      ${m.today.map(d => doseChip(m, d)).join('')}
+      ${m.as_needed ? `<button class="med-dose" data-med="${m.id}" data-time="" title="Принять сейчас">+ приём</button>` : ''}
+    </div>
+    <div class="med-actions">
+      <button class="btn-icon" data-med-restock="${m.id}" title="Пополнить запас">📦</button>
+      <button class="btn-icon" data-med-edit="${m.id}" title="Настройки">⚙</button>
+    </div>
+  </div>`;
+}
+
+function openMedEditor(med, onSaved) {
+  const m = med || { name: '', dose: '', unit: 'мг', per_dose: 1, times: ['09:00'], schedule_kind: 'daily', schedule_days: 127, schedule_count: 2, as_needed: false, stock: null, refill_threshold: null, notes: '' };
+  const overlay = document.createElement('div');
+  overlay.className = 'modal-overlay';
+  overlay.innerHTML = `<div class="modal" style="max-width:460px">
+    <div class="modal-title">${med ? 'Лекарство' : 'Новое лекарство'}</div>
+    <div class="form-label">Название</div>
+    <input class="form-input" id="med-name" value="${escapeHtml(m.name)}">
+    <div class="form-label">Доза</div>
+    <div style="display:flex;gap:8px">
+      <input class="form-input" id="med-dose" type="number" step="any" min="0" value="${m.dose || ''}" placeholder="500" style="max-width:110px">
+      <input class="form-input" id="med-unit" value="${escapeHtml(m.unit)}" placeholder="мг" style="max-width:90px">
+      <input class="form-input" id="med-per-dose" type="number" step="any" min="0" value="${m.per_dose}" title="Сколько единиц запаса (таблеток) за приём" style="max-width:90px">
+    </div>
+    <label class="med-check"><input type="checkbox" id="med-prn"${m.as_needed ? ' checked' : ''}> По необходимости (без расписания)</label>
+    <div id="med-schedule">
+      <div class="form-label">Время приёма (через запятую)</div>
+      <input class="form-input" id="med-times" value="${m.times.join(', ')}" placeholder="08:00, 20:00">
+      <select class="form-select" id="med-kind">
+        <option value="daily">Каждый день</option>
+        <option value="days">По дням недели</option>
+        <option value="every_n_days">Каждые N дней</option>
+      </select>
+      <div class="habit-days" id="med-days">${DAY_NAMES.map((d, i) =>
+        `<button type="button" class="habit-day${m.schedule_days & (1 << i) ? ' active' : ''}" data-bit="${i}">${d}</button>`).join('')}</div>
+      <input class="form-input" id="med-count" type="number" min="1" max="365" value="${m.schedule_count}" placeholder="Интервал, дней">
+    </div>
+    <div class="form-label">Запас и порог напоминания о покупке</div>
+    <div style="display:flex;gap:8px">
+      <input class="form-input" id="med-stock" type="number" step="any" min="0" value="${m.stock ?? ''}" placeholder="Осталось">
+      <input class="form-input" id="med-threshold" type="number" step="any" min="0" value="${m.refill_threshold ?? ''}" placeholder="Порог">
+    </div>
+    <div class="form-label">Заметки</div>
+    <input class="form-input" id="med-notes" value="${escapeHtml(m.notes)}">
+    <div class="modal-actions">
+      ${med ? `<button class="btn-secondary" id="med-delete" style="margin-right:auto">Удалить</button>
+        <button class="btn-secondary" id="med-toggle">${med.active ? 'Приостановить' : 'Возобновить'}</button>` : ''}
+      <button class="btn-secondary" id="med-cancel">Отмена</button>
+      <button class="btn-primary" id="med-save">Сохранить</button>
+    </div>
+  </div>`;
+  document.body.appendChild(overlay);
+  const $ = sel => overlay.querySelector(sel);
+  $('#med-kind').value = m.schedule_kind === 'per_week' ? 'daily' : m.schedule_kind;
+  const sync = () => {
+    const kind = $('#med-kind').value;
+    $('#med-schedule').style.display = $('#med-prn').checked ? 'none' : '';
+    $('#med-days').style.display = kind === 'days' ? '' : 'none';
+    $('#med-count').style.display = kind === 'every_n_days' ? '' : 'none';
+  };
+  sync();
+  $('#med-kind').onchange = sync;
+  $('#med-prn').onchange = sync;
+  $('#med-days').onclick = (e) => { e.target.closest('.habit-day')?.classList.toggle('active'); };
+  $('#med-cancel').onclick = () => overlay.remove();
+  overlay.addEventListener('click', (e) => { if (e.target === overlay) overlay.remove(); });
+  const done = () => { overlay.remove(); onSaved(); };
+  if (med) {
+    $('#med-delete').onclick = async () => {
+      if (!confirm(`Удалить «${med.name}» и историю приёмов?`)) return;
+      await invoke('delete_medication', { id: med.id }).catch(e => toast(String(e)));
+      done();
+    };
+    $('#med-toggle').onclick = async () => {
+      await invoke('update_medication', { id: med.id, active: !med.active }).catch(e => toast(String(e)));
+      done();
+    };
+  }
+
+  $('#med-save').onclick = async () => {
+    const num = id => { const v = parseFloat($(id).value); return Number.isFinite(v) ? v : null; };
+    const params = {
+      name: $('#med-name').value.trim(),
+      dose: num('#med-dose') ?? 0,
+      unit: $('#med-unit').value.trim(),
+      perDose: num('#med-per-dose') ?? 1,
+      times: $('#med-times').value.split(/[,\s]+/).filter(Boolean),
+      scheduleKind: $('#med-kind').value,
+      scheduleDays: [...overlay.querySelectorAll('.habit-day.active')].reduce((acc, b) => acc | (1 << +b.dataset.bit), 0),
+      scheduleCount: parseInt($('#med-count').value, 10) || 1,
+      asNeeded: $('#med-prn').checked,
+      // Negative clears stock tracking on update.
+      stock: num('#med-stock') ?? (med ? -1 : null),
+      refillThreshold: num('#med-threshold') ?? (med ? -1 : null),
+      notes: $('#med-notes').value,
+    };
+    if (params.asNeeded) params.times = [];
+    try {
+      if (med) await invoke('update_medication', { id: med.id, ...params });
+      else await invoke('create_medication', params);
+      done();
+    } catch (e) { toast(String(e)); }
+  };
+}
+
+export async function renderMedications(el) {
+  let meds;
+  try { meds = await invoke('get_medications', { includeInactive: true }); } catch { el.innerHTML = ''; return; }
+  const reload = () => renderMedications(el);
+  const refill = meds.filter(m => m.active && m.refill_needed);
+
+  el.innerHTML = `
+    <div style="display:flex;align-items:center;margin:16px 0 8px;">
+      <div style="font-size:13px;font-weight:600;color:var(--text-primary);flex:1">Лекарства и добавки</div>
+      <button class="btn-smallall" id="med-add">+ Добавить</button>
+    </div>
+    ${refill.length ? `<div class="health-recs"><div class="health-rec-item">📦 Заканчивается: ${refill.map(m => escapeHtml(m.name)).join(', ')}</div></div>` : ''}
+    ${meds.length ? meds.map(medRow).join('') : '<div class="body-hint">Добавьте лекарство или добавку, чтобы получать напоминания о приёме.</div>'}`;
+
+  el.querySelector('#med-add').onclick = () => openMedEditor(null, reload);
+  const byId = id => meds.find(m => m.id === parseInt(id));
+  const log = async (btn, status) => {
+    const med = byId(btn.dataset.med);
+    try {
+      // Clicking a slot already marked as taken undoes it.
+      if (btn.dataset.doseId && status === 'taken' && btn.dataset.status !== 'skipped') await invoke('delete_medication_dose', { id: parseInt(btn.dataset.doseId) });
+      else {
+        const r = await invoke('log_medication_dose', { medicationId: med.id, status, date: null, time: btn.dataset.time || null, note: null });
+        if (r.refill_needed) toast(`${med.name}: пора пополнить запас (осталось ${r.stock})`, 'info');
+      }
+      reload();
+    } catch (e) { toast(String(e)); }
+  };
+  el.onclick = async (e) => {
+    const dose = e.target.closest('[data-med]');
+    if (dose) { log(dose, 'taken'); return; }
+    const edit = e.target.closest('[data-med-edit]');
+    if (edit) { openMedEditor(byId(edit.dataset.medEdit), reload); return; }
+    const restock = e.target.closest('[data-med-restock]');
+    if (restock) {
+      const amount = parseFloat((prompt('Сколько добавить в запас?', '30') || '').replace(',', '.'));
+      if (!Number.isFinite(amount)) return;
+      await invoke('restock_medication', { id: byId(restock.dataset.medRestock).id, amount }).catch(err => toast(String(err)));
+      reload();
+    }
+  };
+  el.oncontextmenu = (e) => {
+    const dose = e.target.closest('[data-med]');
+    if (!dose || !dose.dataset.time) return;
+    e.preventDefault();
+    log(dose, 'skipped');
+  };
+}
@@ -0,0 +1,123 @@
+// settings-blocklist.js — Settings → Блок-лист.
+// Apps and sites blocked during focus sessions, optional per-item schedules
+// (focus turns on by itself inside the window) and the log of attempts to
+// open something blocked while a session was running.
+
+import { invoke } from './state.js';
+import { escapeHtml, confirmModal, toast } from './utils.js';
+
+const SCHEDULE_HINT = 'Пусто — только в ручном фокусе. Пример: «пн-пт 09:00-12:00; сб 10:00-11:00», «22:00-07:00»';
+
+function formatAt(iso) {
+  const d = new Date(iso);
+  const pad = (n) => String(n).padStart(2, '0');
+  return `${pad(d.getDate())}.${pad(d.getMonth() + 1)} ${pad(d.getHours())}:${pad(d.getMinutes())}`;
+}
+
+function renderItems(items) {
+  if (!items.length) return `<div class="settings-empty-hint">Блок-лист пуст</div>`;
+  return items.map(b => `
+    <div class="settings-row blocklist-row${b.active ? '' : ' disabled'}" data-block-id="${b.id}">
+      <label class="toggle"><input type="checkbox" data-block-toggle="${b.id}"${b.active ? ' checked' : ''}><span class="toggle-track"></span></label>
+      <span class="blocklist-type">${b.type === 'site' ? '🌐' : '🖥'}</span>
+      <span class="settings-label blocklist-value">${escapeHtml(b.value)}</span>
+      <input class="form-input blocklist-schedule" data-block-schedule="${b.id}" value="${escapeHtml(b.schedule || '')}" placeholder="без расписания" title="${SCHEDULE_HINT}">
+      <button class="btn-smallall" data-block-delete="${b.id}" title="Удалить">✕</button>
+    </div>`).join('');
+}
+
+function renderTamper(log) {
+  if (!log.entries.length) {
+    return `<div class="settings-empty-hint">За неделю попыток не было</div>`;
+  }
+  const totals = log.totals.map(t =>
+    `<span class="blocklist-tamper-total">${escapeHtml(t.target)} × ${t.count}</span>`).join('');
+  const rows = log.entries.map(e => `<tr>
+    <td>${formatAt(e.at)}</td>
+    <td>${e.kind === 'site' ? 'сайт' : 'приложение'}</td>
+    <td>${escapeHtml(e.target)}</td>
+    <td>${e.action === 'quit' ? 'закрыто' : 'заблокировано'}</td>
+  </tr>`).join('');
+  return `<div class="blocklist-tamper-totals">${totals}</div>
+    <table class="security-log-table"><thead><tr><th>Время</th><th>Тип</th><th>Цель</th><th>Действие</th></tr></thead><tbody>${rows}</tbody></table>`;
+}
+
+export async function renderBlocklistSection() {
+  const [items, status, log] = await Promise.all([
+    invoke('get_blocklist').catch(() => []),
+    invoke('get_focus_enforcer_status').catch(() => null),
+    invoke('get_focus_tamper_log', { days: 7 }).catch(() => ({ entries: [], totals: [] })),
+  ]);
+  const now = status ? [...status.scheduled_now.apps, ...status.scheduled_now.sites] : [];
+
+  return `
+    <div class="settings-section">
+      <div class="settings-section-title">Блок-лист</div>
+      ${status?.setup_needed ? `<div class="settings-row"><span class="settings-hint">Первый запуск фокуса попросит пароль один раз — чтобы установить помощник, который меняет /etc/hosts без повторных запросов.</span></div>` : ''}
+      ${now.length ? `<div class="settings-row"><span class="settings-hint">Сейчас по расписанию блокируется: ${escapeHtml(now.join(', '))}</span></div>` : ''}
+      <div id="blocklist-items">${renderItems(items)}</div>
+      <div class="settings-row blocklist-add">
+        <select class="form-select" id="blocklist-new-type"><option value="app">Приложение</option><option value="site">Сайт</option></select>
+        <input class="form-input" id="blocklist-new-value" placeholder="Telegram, youtube.com">
+        <input class="form-input blocklist-schedule" id="blocklist-new-schedule" placeholder="расписание (необязательно)" title="${SCHEDULE_HINT}">
+        <button class="btn-smallall" id="blocklist-add-btn">+ Добавить</button>
+      </div>
+      <div class="settings-row"><span class="settings-hint">${SCHEDULE_HINT}</span></div>
+    </div>
+
+    <div class="settings-section">
+      <div class="settings-section-title">Попытки во время фокуса</div>
+      <div id="blocklist-tamper-wrap">${renderTamper(log)}</div>
+    </div>
+  `;
+}
+
+async function refreshItems(el) {
+  const wrap = el.querySelector('#blocklist-items');
+  if (!wrap) return;
+  try { wrap.innerHTML = renderItems(await invoke('get_blocklist')); } catch (_) {}
+}
+
+export function wireBlocklistControls(el) {
+  const list = el.querySelector('#blocklist-items');
+  if (!list) return;
+
+  el.querySelector('#blocklist-add-btn').addEventListener('click', async () => {
+    const value = el.querySelector('#blocklist-new-value').value.trim();
+    if (!value) return;
+    try {
+      await invoke('add_to_blocklist', {
+        blockType: el.querySelector('#blocklist-new-type').value,
+        value,
+        schedule: el.querySelector('#blocklist-new-schedule').value.trim() || null,
+      });
+      el.querySelector('#blocklist-new-value').value = '';
+      el.querySelector('#blocklist-new-schedule').value = '';
+      refreshItems(el);
+    } catch (e) { toast(String(e)); }
+  });
+
+  list.addEventListener('click', async (e) => {
+    const del = e.target.closest('[data-block-delete]');
+    if (!del) return;
+    if (!await confirmModal('Удалить из блок-листа?', 'Удалить')) return;
+    await invoke('remove_from_blocklist', { id: +del.dataset.blockDelete }).catch(err => toast(String(err)));
+    refreshItems(el);
+  });
+
+  list.addEventListener('change', async (e) => {
+    const toggle = e.target.closest('[data-block-toggle]');
+    if (toggle) {
+      await invoke('toggle_blocklist_item', { id: +toggle.dataset.blockToggle }).catch(err => toast(String(err)));
+      refreshItems(el);
+      return;
+    }
+    const sched = e.target.closest('[data-block-schedule]');
+    if (sched) {
+      try {
+        await invoke('set_blocklist_schedule', { id: +sched.dataset.blockSchedule, schedule: sched.value.trim() || null });
+        toast('Расписание сохранено', 'success');
+      } catch (err) { toast(String(err)); }
+    }
+  });
+}
@@ -0,0 +1,73 @@
+// settings-timezone.js — Settings → Часовой пояс.
+// Home zone (defaults to the system one) and travel mode: while it is on,
+// calendar events, today's plan and reminders are shown in the travel zone.
+
+import { invoke } from './state.js';
+import { escapeHtml, toast } from './utils.js';
+
+function fmtOffset(min) {
+  if (min == null) return '';
+  const sign = min < 0 ? '−' : '+';
+  const abs = Math.abs(min);
+  return `UTC${sign}${Math.floor(abs / 60)}${abs % 60 ? `:${String(abs % 60).padStart(2, '0')}` : ''}`;
+}
+
+export async function renderTimeZoneSection() {
+  let s;
+  try { s = await invoke('get_time_zone_settings'); }
+  catch (e) { return `<div class="settings-section"><div class="settings-empty-hint">${escapeHtml(String(e))}</div></div>`; }
+  const travel = !!s.travel;
+
+  return `
+    <div class="settings-section" id="tz-section">
+      <div class="settings-section-title">Часовой пояс</div>
+      <datalist id="tz-zones">${s.zones.map(z => `<option value="${escapeHtml(z)}">`).join('')}</datalist>
+      <div class="settings-row">
+        <span class="settings-label">Домашний пояс</span>
+        <span class="settings-value"><input class="form-input" id="tz-home" list="tz-zones" value="${escapeHtml(s.home_override ? s.home : '')}" placeholder="${escapeHtml(s.system || 'системный')}"></span>
+      </div>
+      <div class="settings-row"><span class="settings-hint">Пусто — системный (${escapeHtml(s.system || 'не определён')}). Сейчас дома ${s.home_now} ${fmtOffset(s.home_offset_minutes)}</span></div>
+      <div class="settings-row">
+        <span class="settings-label">Режим путешествия</span>
+        <span class="settings-value"><label class="toggle"><input type="checkbox" id="tz-travel-toggle"${travel ? ' checked' : ''}><span class="toggle-track"></span></label></span>
+      </div>
+      <div class="settings-row" id="tz-travel-row"${travel ? '' : ' style="display:none"'}>
+        <span class="settings-label">Где вы сейчас</span>
+        <span class="settings-value"><input class="form-input" id="tz-travel" list="tz-zones" value="${escapeHtml(s.travel)}" placeholder="Asia/Tokyo"></span>
+      </div>
+      <div class="settings-row"><span class="settings-hint">События и напоминания показываются в поясе ${escapeHtml(s.current || 'системы')}: ${s.current_now} ${fmtOffset(s.current_offset_minutes)}. Встречи с поясом сдвигаются, целодневные — нет.</span></div>
+    </div>
+  `;
+}
+
+export function wireTimeZoneControls(el) {
+  const section = el.querySelector('#tz-section');
+  if (!section) return;
+  const rerender = async () => {
+    section.outerHTML = await renderTimeZoneSection();
+    wireTimeZoneControls(el);
+  };
+  const save = async (params, msg) => {
+    try {
+      await invoke('set_time_zone_settings', params);
+      toast(msg, 'success');
+      rerender();
+    } catch (e) { toast(String(e)); }
+  };
+
+  section.querySelector('#tz-home').addEventListener('change', (e) => {
+    save({ home: e.target.value.trim(), travel: null }, 'Домашний пояс сохранён');
+  });
+  section.querySelector('#tz-travel-toggle').addEventListener('change', (e) => {
+    if (e.target.checked) {
+      section.querySelector('#tz-travel-row').style.display = '';
+      section.querySelector('#tz-travel').focus();
+    } else {
+      save({ home: null, travel: '' }, 'Режим путешествия выключен');
+    }
+  });
+  section.querySelector('#tz-travel').addEventListener('change', (e) => {
+    const zone = e.target.value.trim();
+    if (zone) save({ home: null, travel: zone }, 'Режим путешествия включён');
+  });
+}
@@ -20,39 +20,13 @@ export async function deleteShoppingItem(id) {
   return await invoke('delete_shopping_item', { id: Number(id) });
 }
 
-// Gather the ingredients of the given recipes that aren't already in the
-// fridge and push them onto the shopping list. Mirrors the per-recipe
-// "+ в покупки" logic in cook-what, but for a whole day's meal plan.
-// Match is by name only (same as cook-what); dedupes across recipes.
-const slNorm = (s) => String(s == null ? '' : s).trim().toLowerCase();
-const slIngredients = (str) => String(str || '').split(/[,;\n]/).map(s => s.trim()).filter(Boolean);
-const slName = (n) => n.split(':')[0].trim(); // "соль: 2ч.л." → "соль"
-
-export async function addMissingForRecipes(recipeIds) {
-  const ids = [...new Set((recipeIds || []).map(Number).filter(Boolean))];
-  if (!ids.length) return 0;
-  const [recipes, products] = await Promise.all([
-    invoke('get_recipes', { search: null }).catch(() => []),
-    invoke('get_products', {}).catch(() => []),
-  ]);
-  const byId = new Map(recipes.map(r => [r.id, r]));
-  const have = new Set(products.map(p => slNorm(p.name)));
-  const seen = new Set();
-  let added = 0;
-  for (const id of ids) {
-    const r = byId.get(id);
-    if (!r) continue;
-    for (const raw of slIngredients(r.ingredients)) {
-      const name = slName(raw);
-      const key = slNorm(name);
-      if (!name || have.has(key) || seen.has(key)) continue;
-      seen.add(key);
-      const c = raw.split(':');
-      await addShoppingItem(name, (c[1] || '').trim(), '').catch(() => {});
-      added++;
-    }
-  }
-  return added;
+// Build the list from a meal-plan date range on the backend: ingredients are
+// scaled by planned servings, unit-normalized and reduced by fridge stock,
+// grouped by catalog category. The range is remembered, so later plan edits
+// inside it regenerate the generated rows. Returns the number of items added.
+export async function generateFromPlan(dateFrom, dateTo = dateFrom) {
+  const res = await invoke('generate_shopping_from_plan', { dateFrom, dateTo });
+  return res?.added || 0;
 }
 
 // Render selected items into the description field of a "Закупка" event —
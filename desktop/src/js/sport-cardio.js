@@ -0,0 +1,121 @@
+// ── sport-cardio.js — Cardio pane: GPX/TCX/FIT import, per-sport totals, personal bests, track detail ──
+import { invoke } from './state.js';
+import { escapeHtml, toast } from './utils.js';
+
+const SPORT_LABELS = { run: 'Бег', ride: 'Велосипед', walk: 'Ходьба', hike: 'Поход', swim: 'Плавание', other: 'Кардио' };
+const SPORT_ICONS = { run: '🏃', ride: '🚴', walk: '🚶', hike: '🥾', swim: '🏊', other: '❤️' };
+
+export function fmtDuration(sec) {
+  sec = Math.round(sec || 0);
+  const h = Math.floor(sec / 3600), m = Math.floor((sec % 3600) / 60), s = sec % 60;
+  return h ? `${h}:${String(m).padStart(2, '0')}:${String(s).padStart(2, '0')}` : `${m}:${String(s).padStart(2, '0')}`;
+}
+export function fmtKm(m) { return `${(Math.round((m || 0) / 10) / 100).toFixed(2)} км`; }
+function fmtPace(sport, secPerKm) {
+  if (!secPerKm) return '—';
+  if (sport === 'ride') return `${(3600 / secPerKm).toFixed(1)} км/ч`;
+  if (sport === 'swim') return `${fmtDuration(secPerKm / 10)} /100м`;
+  return `${fmtDuration(secPerKm)} /км`;
+}
+
+async function importFiles(files) {
+  let imported = 0, skipped = 0;
+  const records = [];
+  for (const file of files) {
+    try {
+      const bytes = Array.from(new Uint8Array(await file.arrayBuffer()));
+      const res = await invoke('import_workout_file', { fileName: file.name, path: null, bytes });
+      imported += res.imported.length; skipped += res.skipped;
+      records.push(...res.records);
+    } catch (err) { toast(`${file.name}: ${err}`); }
+  }
+  if (imported || skipped) toast(`Импортировано: ${imported}${skipped ? `, пропущено: ${skipped}` : ''}`, 'success');
+  for (const r of records) toast(`🏆 Рекорд: ${SPORT_LABELS[r.sport] || r.sport} ${r.label} — ${fmtDuration(r.seconds)}`, 'success');
+}
+
+function sparkline(values, color) {
+  const pts = values.map((v, i) => [i, v]).filter(([, v]) => v != null);
+  if (pts.length < 2) return '';
+  const min = Math.min(...pts.map(p => p[1])), max = Math.max(...pts.map(p => p[1]));
+  const span = max - min || 1, n = values.length - 1 || 1;
+  const path = pts.map(([i, v]) => `${(i / n * 300).toFixed(1)},${(40 - (v - min) / span * 36 - 2).toFixed(1)}`).join(' ');
+  return `<svg class="cardio-spark" viewBox="0 0 300 40" preserveAspectRatio="none"><polyline points="${path}" fill="none" stroke="${color}" stroke-width="1.5"/></svg>`;
+}
+
+export async function showTrackDetail(workoutId) {
+  const t = await invoke('get_workout_track', { workoutId }).catch(err => { toast('Ошибка: ' + err); return null; });
+  if (!t) return;
+  const points = t.points || [];
+  const overlay = document.createElement('div');
+  overlay.className = 'modal-overlay';
+  overlay.innerHTML = `<div class="modal" style="max-width:620px;max-height:85vh;overflow-y:auto">
+    <div class="modal-title">${SPORT_ICONS[t.sport] || ''} ${escapeHtml(t.title)} · ${t.date}</div>
+    <div class="cardio-stats">
+      <div><b>${fmtKm(t.distance_m)}</b><span>дистанция</span></div>
+      <div><b>${fmtDuration(t.moving_seconds)}</b><span>в движении</span></div>
+      <div><b>${fmtPace(t.sport, t.distance_m ? t.moving_seconds / t.distance_m * 1000 : 0)}</b><span>темп</span></div>
+      <div><b>+${Math.round(t.elevation_gain_m)} м</b><span>набор</span></div>
+      <div><b>${t.avg_hr || '—'}${t.max_hr ? ` / ${t.max_hr}` : ''}</b><span>пульс ср/макс</span></div>
+    </div>
+    ${points.some(p => p[4]) ? `<div class="form-label">Пульс</div>${sparkline(points.map(p => p[4]), 'var(--color-red)')}` : ''}
+    ${points.some(p => p[2] != null) ? `<div class="form-label">Высота</div>${sparkline(points.map(p => p[2]), 'var(--color-blue, #3b82f6)')}` : ''}
+    ${(t.splits || []).length ? `<div class="form-label" style="margin-top:8px">Отрезки</div>
+      <div class="cardio-splits">${t.splits.map(s => `<div class="cardio-split-row">
+        <span>${s.index}</span><span>${s.distance_m < 1000 ? Math.round(s.distance_m) + ' м' : 'км'}</span>
+        <span>${fmtPace(t.sport, s.pace_s_km)}</span><span>${s.avg_hr ? '♥ ' + s.avg_hr : ''}</span>
+        <span>${s.elevation_gain_m ? '+' + Math.round(s.elevation_gain_m) + ' м' : ''}</span></div>`).join('')}</div>` : ''}
+    ${(t.best_efforts || []).length ? `<div class="form-label" style="margin-top:8px">Лучшие отрезки</div>
+      <div class="cardio-efforts">${t.best_efforts.map(e => `<span class="badge badge-gray">${e.label}: ${fmtDuration(e.seconds)}</span>`).join(' ')}</div>` : ''}
+    <div class="modal-actions"><button class="btn-primary" id="ct-close">Закрыть</button></div>
+  </div>`;
+  document.body.appendChild(overlay);
+  overlay.querySelector('#ct-close').onclick = () => overlay.remove();
+  overlay.addEventListener('click', (e) => { if (e.target === overlay) overlay.remove(); });
+}
+
+export async function renderCardioPane(el) {
+  el.innerHTML = `<div class="recipe-pane">
+    <div class="recipe-filter-bar">
+      <select class="form-select cardio-period" style="max-width:160px">
+        <option value="7">7 дней</option><option value="30" selected>30 дней</option><option value="365">Год</option>
+      </select>
+      <label class="btn-primary recipe-add-btn">Импорт GPX/TCX/FIT<input type="file" accept=".gpx,.tcx,.fit,.gz" multiple hidden></label>
+    </div>
+    <div class="cardio-summary"></div>
+    <div class="form-label" style="margin-top:12px">Личные рекорды</div>
+    <div class="cardio-records"></div>
+  </div>`;
+  const periodEl = el.querySelector('.cardio-period');
+
+  async function render() {
+    const [summary, records] = await Promise.all([
+      invoke('get_cardio_summary', { days: parseInt(periodEl.value, 10) }).catch(() => []),
+      invoke('get_cardio_records').catch(() => ({ fastest: [], longest: [] })),
+    ]);
+    el.querySelector('.cardio-summary').innerHTML = summary.length ? summary.map(s => `<div class="cardio-sport">
+      <div class="cardio-sport-name">${SPORT_ICONS[s.sport] || ''} ${SPORT_LABELS[s.sport] || s.sport} <span class="uni-tab-count">(${s.count})</span></div>
+      <div class="cardio-sport-meta">${fmtKm(s.distance_m)} · ${fmtDuration(s.moving_seconds)} · ${fmtPace(s.sport, s.avg_pace_s_km)} · +${Math.round(s.elevation_gain_m)} м${s.avg_hr ? ` · ♥ ${s.avg_hr}` : ''}</div>
+    </div>`).join('') : '<div class="uni-empty">Нет кардио-тренировок с треком за период</div>';
+
+    const longLabel = { distance: r => fmtKm(r.value), time: r => fmtDuration(r.value), climb: r => `+${Math.round(r.value)} м` };
+    const longTitle = { distance: 'Самая длинная', time: 'Самая долгая', climb: 'Самый большой набор' };
+    const rows = [
+      ...records.fastest.map(r => ({ id: r.workout_id, sport: r.sport, what: `Быстрейшие ${r.label}`, value: `${fmtDuration(r.seconds)} · ${fmtPace(r.sport, r.pace_s_km)}`, date: r.date })),
+      ...records.longest.map(r => ({ id: r.workout_id, sport: r.sport, what: longTitle[r.kind], value: longLabel[r.kind](r), date: r.date })),
+    ];
+    el.querySelector('.cardio-records').innerHTML = rows.length ? rows.map(r => `<div class="cardio-record" data-id="${r.id}">
+      <span>${SPORT_ICONS[r.sport] || ''} ${escapeHtml(r.what)}</span><b>${r.value}</b><span class="cardio-record-date">${r.date}</span>
+    </div>`).join('') : '<div class="uni-empty">Импортируйте треки, чтобы появились рекорды</div>';
+    el.querySelectorAll('.cardio-record').forEach(row => row.onclick = () => showTrackDetail(Number(row.dataset.id)));
+  }
+
+  periodEl.onchange = render;
+  el.querySelector('input[type=file]').onchange = async (e) => {
+    const files = [...e.target.files];
+    e.target.value = '';
+    if (!files.length) return;
+    await importFiles(files);
+    await render();
+  };
+  await render();
+}
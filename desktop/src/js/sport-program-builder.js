@@ -56,6 +56,11 @@ export async function showProgramBuilder(onSaved, editData) {
       <label class="form-label">Цикл (дней)<input class="form-input" id="pg-cycle" type="number" min="1" value="${editData?.cycle_length_days || initialDays.length || 7}"></label>
       <label class="form-label">Длительность (недель, 0=∞)<input class="form-input" id="pg-weeks" type="number" min="0" value="${editData?.duration_weeks || 0}"></label>
     </div>
+    <div class="program-builder-nums">
+      <label class="form-label">Разгрузка каждые (недель, 0=нет)<input class="form-input" id="pg-deload-every" type="number" min="0" value="${editData?.deload_every_weeks || 0}"></label>
+      <label class="form-label">Нагрузка в разгрузку, %<input class="form-input" id="pg-deload-pct" type="number" min="10" max="100" value="${Math.round((editData?.deload_pct || 0.6) * 100)}"></label>
+    </div>
+    <input class="form-input" id="pg-percents" placeholder="Проценты по неделям: 70,75,80 (пусто = 100%)" value="${escapeHtml(editData?.week_percents || '')}" style="margin-top:6px">
     <div class="form-label" style="margin-top:8px">Целевые мышцы (для баланса)</div>
     <div class="rf-chip-row" id="pg-muscles">${multiChips(MUSCLE_GROUPS, selMuscles, 'muscles')}</div>
     <div class="form-label" style="margin-top:12px">Дни программы</div>
@@ -104,6 +109,9 @@ export async function showProgramBuilder(onSaved, editData) {
       cycleLengthDays: parseInt(overlay.querySelector('#pg-cycle').value, 10) || days.length || 7,
       durationWeeks: parseInt(overlay.querySelector('#pg-weeks').value, 10) || 0,
       targetMuscleGroups: [...selMuscles].join(','),
+      deloadEveryWeeks: parseInt(overlay.querySelector('#pg-deload-every').value, 10) || 0,
+      deloadPct: (parseFloat(overlay.querySelector('#pg-deload-pct').value) || 60) / 100,
+      weekPercents: overlay.querySelector('#pg-percents').value.trim(),
       notes: overlay.querySelector('#pg-notes').value.trim(), days,
     };
     try {
@@ -1,6 +1,6 @@
 // ── sport-programs.js — Workout programs pane (browse + active-program "today" strip) ──
 import { invoke } from './state.js';
-import { chips, escapeHtml } from './utils.js';
+import { chips, escapeHtml, toast } from './utils.js';
 import { renderProgramCard } from './sport-program-card.js';
 import { PROGRAM_KINDS, matchKind, matchSearch } from './sport-program-filters.js';
 
@@ -60,12 +60,27 @@ export async function renderProgramsPane(el) {
     if (!today) { strip.style.display = 'none'; strip.innerHTML = ''; return; }
     strip.style.display = '';
     const labels = (today.days || []).map(d => d.is_rest ? 'Отдых' : (d.template_name || d.label || '—')).join(', ') || 'Отдых';
+    const load = today.deload ? ` · разгрузка ${Math.round(today.load_factor * 100)}%`
+      : (today.load_factor && today.load_factor !== 1 ? ` · ${Math.round(today.load_factor * 100)}%` : '');
+    const when = today.scheduled_date ? ` · ${today.scheduled_date.slice(8, 10)}.${today.scheduled_date.slice(5, 7)}` : '';
     strip.innerHTML = `
       <div class="program-today-info">
         <span class="program-today-name">▶ ${escapeHtml(today.program_name)}</span>
-        <span class="program-today-day">День ${today.current_day + 1}/${today.cycle_length_days} · ${escapeHtml(labels)}</span>
+        <span class="program-today-day">Неделя ${(today.week || 0) + 1} · День ${today.current_day + 1}/${today.cycle_length_days}${when} · ${escapeHtml(labels)}${load}</span>
       </div>
-      <button class="btn-primary program-today-done">Готово</button>`;
+      <div class="program-today-actions">
+        ${(today.days || []).some(d => !d.is_rest && d.template_id) ? '<button class="btn-secondary program-today-start">Начать</button>' : ''}
+        <button class="btn-primary program-today-done">Готово</button>
+      </div>`;
+    strip.querySelector('.program-today-start')?.addEventListener('click', async () => {
+      try {
+        const started = await invoke('start_program_day', { runId: today.run_id });
+        const { showSetLogger } = await import('./sport-set-log.js');
+        for (const w of started.workouts || []) {
+          await new Promise(resolve => showSetLogger(w.workout_id, w.title, w.suggestions, resolve));
+        }
+      } catch (err) { toast('Ошибка: ' + err); }
+    });
     strip.querySelector('.program-today-done').onclick = async () => {
       await invoke('complete_program_day', { runId: today.run_id }).catch(() => {});
       await fullReload();
@@ -0,0 +1,92 @@
+// ── sport-set-log.js — Per-set logger for a started workout (weight × reps, RPE, warm-up/fail, PRs) ──
+import { invoke } from './state.js';
+import { escapeHtml, toast } from './utils.js';
+
+const ACTION_LABELS = { start: 'старт', increase: '↑ вес', repeat: '= вес', deload: '↓ разгрузка' };
+
+function fmtKg(x) { return Number.isFinite(x) ? String(Math.round(x * 100) / 100) : '—'; }
+
+function setRow(s) {
+  const flags = [s.is_warmup ? 'разминка' : '', s.failed ? 'отказ' : ''].filter(Boolean).join(', ');
+  return `<div class="set-row${s.is_warmup ? ' set-warmup' : ''}" data-set="${s.id}">
+    <span class="set-idx">${s.set_index}</span>
+    <span class="set-load">${fmtKg(s.weight_kg)} кг × ${s.reps}</span>
+    <span class="set-rpe">${s.rpe ? 'RPE ' + s.rpe : ''}</span>
+    <span class="set-flags">${flags}</span>
+    <span class="set-e1rm">${s.is_warmup ? '' : '1ПМ≈' + fmtKg(s.e1rm)}</span>
+    <span class="set-pr">${s.is_pr ? '🏆' : ''}</span>
+    <button class="btn-icon set-del" title="Удалить">×</button>
+  </div>`;
+}
+
+// suggestions: [{ name, suggestion: { weight_kg, reps, sets, action, reason } }] from start_program_day.
+export async function showSetLogger(workoutId, title, suggestions = [], onChanged) {
+  const byName = new Map((suggestions || []).map(s => [s.name, s.suggestion]));
+  const overlay = document.createElement('div');
+  overlay.className = 'modal-overlay';
+  overlay.innerHTML = `<div class="modal" style="max-width:620px;max-height:85vh;overflow-y:auto">
+    <div class="modal-title">${escapeHtml(title || 'Тренировка')}</div>
+    <div class="set-log-body"></div>
+    <div class="modal-actions"><button class="btn-primary" id="sl-close">Закрыть</button></div>
+  </div>`;
+  document.body.appendChild(overlay);
+  const close = () => { overlay.remove(); onChanged?.(); };
+  overlay.querySelector('#sl-close').onclick = close;
+  overlay.addEventListener('click', (e) => { if (e.target === overlay) close(); });
+  const body = overlay.querySelector('.set-log-body');
+
+  async function render() {
+    const exercises = await invoke('get_workout_sets', { workoutId }).catch(() => []);
+    if (!exercises.length) { body.innerHTML = '<div class="uni-empty">В тренировке нет упражнений</div>'; return; }
+    body.innerHTML = exercises.map(ex => {
+      const sg = byName.get(ex.name);
+      const working = ex.sets.filter(s => !s.is_warmup);
+      const last = working[working.length - 1];
+      const w = last ? last.weight_kg : (sg ? sg.weight_kg : ex.planned_weight_kg || 0);
+      const r = last ? last.reps : (sg ? sg.reps : ex.planned_reps || 0);
+      return `<div class="set-ex" data-ex="${ex.id}">
+        <div class="set-ex-head">
+          <span class="set-ex-name">${escapeHtml(ex.name)}</span>
+          ${sg ? `<span class="badge badge-gray" title="${escapeHtml(sg.reason)}">${ACTION_LABELS[sg.action] || sg.action} · ${sg.sets}×${sg.reps} @ ${fmtKg(sg.weight_kg)} кг</span>` : ''}
+        </div>
+        ${sg ? `<div class="set-ex-reason">${escapeHtml(sg.reason)}</div>` : ''}
+        <div class="set-list">${ex.sets.map(setRow).join('')}</div>
+        <div class="set-add">
+          <input class="form-input set-w" type="number" step="0.5" min="0" value="${w}" title="Вес, кг" style="max-width:80px">
+          <input class="form-input set-r" type="number" min="0" value="${r}" title="Повторы" style="max-width:64px">
+          <input class="form-input set-rpe-in" type="number" step="0.5" min="1" max="10" placeholder="RPE" style="max-width:64px">
+          <label class="set-chk"><input type="checkbox" class="set-wu"> разм.</label>
+          <label class="set-chk"><input type="checkbox" class="set-fail"> отказ</label>
+          <button class="btn-secondary set-add-btn">+ Подход</button>
+        </div>
+      </div>`;
+    }).join('');
+
+    body.querySelectorAll('.set-ex').forEach(block => {
+      const exerciseId = Number(block.dataset.ex);
+      block.querySelector('.set-add-btn').onclick = async () => {
+        const rpe = parseFloat(block.querySelector('.set-rpe-in').value);
+        try {
+          const res = await invoke('log_workout_set', {
+            exerciseId,
+            reps: parseInt(block.querySelector('.set-r').value) || 0,
+            weightKg: parseFloat(block.querySelector('.set-w').value) || 0,
+            rpe: Number.isFinite(rpe) ? rpe : null,
+            restSeconds: null,
+            isWarmup: block.querySelector('.set-wu').checked,
+            failed: block.querySelector('.set-fail').checked,
+            notes: null,
+          });
+          if (res.pr) toast(`Новый рекорд! 1ПМ≈${fmtKg(res.e1rm)} кг`, 'success');
+          await render();
+        } catch (err) { toast('Ошибка: ' + err); }
+      };
+      block.querySelectorAll('.set-del').forEach(btn => btn.onclick = async () => {
+        const id = Number(btn.closest('.set-row').dataset.set);
+        await invoke('delete_workout_set', { id }).catch(() => {});
+        await render();
+      });
+    });
+  }
+  await render();
+}
@@ -25,6 +25,14 @@ const _SYNC_WRITE_EXACT = new Set([
   // routine_node_status) — push right away so the other device picks the
   // started run / checked step within seconds, not on the next auto-loop.
   'start_routine_run', 'set_routine_node_status', 'delete_routine_run',
+  // Pomodoro intervals open/close timeline_blocks rows.
+  'start_pomodoro', 'pause_pomodoro', 'resume_pomodoro', 'skip_pomodoro_phase', 'stop_pomodoro',
+  // Saves/updates the weekly review note.
+  'generate_weekly_review',
+  // Rewrites the day's automatic timeline_blocks (no-op when nothing changed).
+  'reconstruct_timeline',
+  // Day planner: writes/drops 'plan' timeline_blocks, moves events/notes to tomorrow.
+  'accept_day_plan', 'clear_day_plan',
 ]);
 
 export function invoke(cmd, args) {
@@ -111,7 +119,6 @@ export const S = {
   lastMessageWasVoice: false,
   lastSttTimeMs: 0,
   voiceRecordStartTime: 0,
-  pomodoroState: { active: false, mode: 'work', workMin: 25, breakMin: 5, startedAt: 0, totalSec: 0 },
   customPageAutoSave: null,
   notesViewMode: 'list',
   notePreviewMode: false,
@@ -745,9 +745,9 @@ async function renderDayCalendar(el, events) {
     const pri = Number(e.priority || 0);
     const done = !!e.completed;
     const priBar = pri > 0 ? `<span class="day-event-pri" style="background:${priorityHex(pri)}" title="Важность ${pri}/5"></span>` : '';
-    // auto_health events already carry their own emoji in the title
-    // (🚶/🏃/Сон…), so the generic 📅 badge would just be noise.
-    const srcBadge = (!isManual && e.source !== 'auto_health')
+    // auto_health / auto_program events already carry their own emoji in the
+    // title (🚶/🏃/Сон…/🏋), so the generic 📅 badge would just be noise.
+    const srcBadge = (!isManual && e.source !== 'auto_health' && e.source !== 'auto_program')
       ? `<span class="badge badge-gray" style="margin-left:6px;">${e.source === 'apple' ? '🍎' : '📅'}</span>` : '';
     // data-evt-pop on every block opens the read-only details popover; manual
     // events also keep data-event-id (edit lives behind the popover's button).
@@ -873,11 +873,11 @@ async function renderDayCalendar(el, events) {
   el.querySelector('.meal-plan-shop')?.addEventListener('click', async (e) => {
     e.stopPropagation();
     const btn = e.currentTarget;
-    const ids = (btn.dataset.mpShop || '').split(',').map(Number).filter(Boolean);
+    const date = btn.dataset.mpShop;
     btn.disabled = true;
-    const { addMissingForRecipes } = await import('./shopping-list.js');
+    const { generateFromPlan } = await import('./shopping-list.js');
     const { toast } = await import('./utils.js');
-    const n = await addMissingForRecipes(ids).catch(() => 0);
+    const n = await generateFromPlan(date).catch(() => 0);
     toast(n ? `Добавлено в покупки: ${n}` : 'В холодильнике уже всё есть', n ? 'success' : 'info');
     btn.disabled = false;
   });
@@ -8,6 +8,7 @@ import { DatabaseView } from './db-view/db-view.js';
 import { formatRecurrence } from './db-view/db-recurrence-editor.js';
 import { ICONS } from './icons.js';
 import { scoreTier } from './dev-level.js';
+import { openHabitEditor } from './habit-editor.js';
 
 // Legacy helper: backward-compat wrapper (still used by Recipes, Products)
 function renderDatabaseView(el, tabId, recordTable, records, options) {
@@ -1543,6 +1544,10 @@ async function loadSports(subTab) {
       const { renderProgramsPane } = await import('./sport-programs.js');
       await renderProgramsPane(paneEl);
     },
+    renderCardio: async (paneEl) => {
+      const { renderCardioPane } = await import('./sport-cardio.js');
+      await renderCardioPane(paneEl);
+    },
     renderTable: async (paneEl) => {
       const workouts = await invoke('get_workouts', { dateRange: null }).catch(() => []);
       const stats = await invoke('get_workout_stats').catch(() => ({ count: 0, total_minutes: 0, total_calories: 0 }));
@@ -1570,6 +1575,8 @@ function renderSports(el, workouts, stats) {
       }},
       { key: 'duration_minutes', label: 'Время', editable: true, editType: 'number', render: r => `<span style="font-size:12px;color:var(--text-secondary);">${r.duration_minutes || 0} мин</span>` },
       { key: 'calories', label: 'Калории', editable: true, editType: 'number', render: r => `<span style="font-size:12px;color:var(--text-muted);">${r.calories || '—'}</span>` },
+      { key: 'distance_m', label: 'Дистанция', render: r => r.distance_m
+        ? `<span style="font-size:12px;color:var(--text-secondary);">${(r.distance_m / 1000).toFixed(2)} км</span>` : '' },
     ],
     idField: 'id',
     availableViews: ['table', 'list'],
@@ -1590,6 +1597,12 @@ function renderSports(el, workouts, stats) {
       if (!skipReload) loadSports();
     },
     onDelete: async (id) => { await invoke('delete_workout', { id }); },
+    // Imported GPX/TCX/FIT workouts open their track (splits, HR, best efforts).
+    onRowClick: async (r) => {
+      if (!r.distance_m) return;
+      const { showTrackDetail } = await import('./sport-cardio.js');
+      showTrackDetail(r.id);
+    },
     reloadFn: () => loadSports(),
   });
   dbv.render();
@@ -1697,6 +1710,7 @@ async function loadHealth() {
 function renderHealth(el, today, habits) {
   el.innerHTML = `<div id="habits-dbv"></div>`;
 
+  const fmtNum = v => Number.isInteger(v) ? v : Math.round(v * 10) / 10;
   // Habits as DatabaseView
   const dbvEl = el.querySelector('#habits-dbv');
   const dbv = new DatabaseView(dbvEl, {
@@ -1704,23 +1718,26 @@ function renderHealth(el, today, habits) {
     recordTable: 'habits',
     records: habits,
     fixedColumns: [
-      { key: 'done', label: '', dataType: 'checkbox', render: r => `<div class="habit-check${r.completed ? ' checked' : ''}" style="cursor:pointer;" data-hid="${r.id}">${r.completed ? '&#10003;' : ''}</div>` },
-      { key: 'name', label: 'Привычка', editable: true, editType: 'text', render: r => `<span class="data-table-title">${escapeHtml(r.name)}</span>` },
-      { key: 'frequency', label: 'Частота', editable: true, editType: 'select', editOptions: [
-        { value: 'daily', label: 'Ежедневно', color: 'blue' }, { value: 'weekly', label: 'Еженедельно', color: 'green' },
-        { value: 'weekdays', label: 'Будни', color: 'yellow' }, { value: 'weekends', label: 'Выходные', color: 'purple' },
-      ], render: r => {
-        const freqColors = { daily: 'blue', weekly: 'green', weekdays: 'yellow', weekends: 'purple' };
-        return `<span class="badge badge-${freqColors[r.frequency] || 'gray'}">${r.frequency || 'daily'}</span>`;
+      { key: 'done', label: '', dataType: 'checkbox', render: r => r.target > 1
+        ? `<div class="habit-progress${r.completed ? ' done' : ''}" data-hid="${r.id}" title="${fmtNum(r.value_today)} из ${fmtNum(r.target)} ${escapeHtml(r.unit || '')}">
+            <div class="habit-progress-fill" style="width:${Math.round(r.progress * 100)}%"></div><span>${fmtNum(r.value_today)}/${fmtNum(r.target)}</span></div>`
+        : `<div class="habit-check${r.completed ? ' checked' : ''}${r.status === 'frozen' ? ' frozen' : ''}" style="cursor:pointer;" data-hid="${r.id}">${r.completed ? '&#10003;' : r.status === 'frozen' ? '❄' : ''}</div>` },
+      { key: 'name', label: 'Привычка', editable: true, editType: 'text', render: r => `<span class="data-table-title${r.due_today ? '' : ' habit-off-day'}">${escapeHtml(r.name)}</span>${r.auto_source ? ' <span class="badge badge-gray" title="Отмечается автоматически">авто</span>' : ''}` },
+      { key: 'frequency', label: 'Расписание', render: r => {
+        const week = r.schedule_kind === 'per_week' ? ` · ${r.week_done}/${r.week_target}` : '';
+        return `<span class="badge badge-${r.due_today ? 'blue' : 'gray'}">${escapeHtml(r.schedule_label || r.frequency || '')}${week}</span>`;
       }},
-      { key: 'streak', label: 'Серия', render: r => r.streak > 0 ? `<span class="badge badge-green">${r.streak} дн.</span>` : '<span style="color:var(--text-faint);font-size:12px;">—</span>' },
+      { key: 'streak', label: 'Серия', render: r => r.streak > 0
+        ? `<span class="badge badge-green" title="Рекорд: ${r.best_streak}">${r.streak} ${r.streak_unit === 'week' ? 'нед.' : 'дн.'}</span>`
+        : '<span style="color:var(--text-faint);font-size:12px;">—</span>' },
+      { key: 'consistency', label: 'Регулярность', render: r => `<span style="font-size:12px;color:var(--text-secondary)">${Math.round(r.consistency)}%</span>` },
+      { key: 'actions', label: '', render: r => `<span class="habit-actions">
+        <button class="btn-icon" data-hfreeze="${r.id}" title="Заморозка — серия не прервётся (осталось ${r.freezes_left})"${r.freezes_left || r.status === 'frozen' ? '' : ' disabled'}>❄</button>
+        <button class="btn-icon" data-hedit="${r.id}" title="Настройки">⚙</button></span>` },
     ],
     idField: 'id',
     addButton: '+ Привычка',
-    onAdd: () => {
-      const name = prompt('Название привычки:');
-      if (name) invoke('create_habit', { name, icon: '', frequency: 'daily' }).then(() => loadHealth()).catch(e => alert(e));
-    },
+    onAdd: () => openHabitEditor(null, loadHealth),
     onQuickAdd: async () => {
       await invoke('create_habit', { name: '', icon: '', frequency: '' });
       loadHealth();
@@ -1736,13 +1753,29 @@ function renderHealth(el, today, habits) {
   });
   dbv.render();
 
-  // Delegate habit check clicks
-  el.addEventListener('click', async (e) => {
-    const check = e.target.closest('[data-hid]');
-    if (!check) return;
-    await invoke('check_habit', { habitId: parseInt(check.dataset.hid), date: null }).catch(() => {});
+  // Delegate habit clicks (onclick, not addEventListener: the pane element is
+  // reused across reloads and listeners would stack).
+  el.onclick = async (e) => {
+    const byId = attr => habits.find(h => h.id === parseInt(e.target.closest(`[${attr}]`)?.getAttribute(attr)));
+    const edit = byId('data-hedit');
+    if (edit) { openHabitEditor(edit, loadHealth); return; }
+    const freeze = byId('data-hfreeze');
+    if (freeze) {
+      await invoke('freeze_habit', { habitId: freeze.id, date: null }).catch(err => toast(String(err)));
+      loadHealth();
+      return;
+    }
+    const habit = byId('data-hid');
+    if (!habit) return;
+    let value = null;
+    if (habit.target > 1) {
+      const input = prompt(`${habit.name}: сколько сегодня${habit.unit ? ` (${habit.unit})` : ''}?`, String(habit.value_today || ''));
+      if (input === null) return;
+      value = parseFloat(input.replace(',', '.')) || 0;
+    }
+    await invoke('check_habit', { habitId: habit.id, date: null, value }).catch(() => {});
     loadHealth();
-  });
+  };
 }
 
 // ── Custom Pages ──
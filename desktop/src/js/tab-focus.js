@@ -1,7 +1,7 @@
 // ── js/tab-focus.js — Focus tab (unified layout) + Focus floating widget ──
 
 import { S, invoke, listen, emit, tabLoaders } from './state.js';
-import { escapeHtml, renderPageHeader, setupPageHeaderControls, skeletonPage, loadTabBlockEditor } from './utils.js';
+import { escapeHtml, renderPageHeader, setupPageHeaderControls, skeletonPage, loadTabBlockEditor, toast } from './utils.js';
 
 // ── Focus (unified layout) ──
 
@@ -35,6 +35,10 @@ async function loadFocus(subTab) {
       const views = [
         { id: 'current', label: 'Текущая' },
         { id: 'history', label: 'История' },
+        { id: 'pomodoro', label: 'Помодоро' },
+        { id: 'review', label: 'Обзор' },
+        { id: 'limits', label: 'Лимиты' },
+        { id: 'rules', label: 'Правила' },
       ];
       const activeView = S._focusInner || 'current';
       paneEl.innerHTML = `
@@ -52,6 +56,10 @@ async function loadFocus(subTab) {
 
       const innerEl = paneEl.querySelector('#focus-inner-content');
       if (activeView === 'history') await renderFocusHistory(innerEl);
+      else if (activeView === 'pomodoro') await (await import('./focus-pomodoro.js')).renderPomodoroStats(innerEl);
+      else if (activeView === 'review') await (await import('./focus-review.js')).renderWeeklyReview(innerEl);
+      else if (activeView === 'limits') await (await import('./focus-limits.js')).renderScreenLimits(innerEl);
+      else if (activeView === 'rules') await (await import('./focus-rules.js')).renderActivityRules(innerEl);
       else await renderFocusCurrent(innerEl);
     },
   });
@@ -63,43 +71,56 @@ async function renderFocusCurrent(el) {
     const current = await invoke('get_current_activity').catch(() => null);
     const log = await invoke('get_activity_log', { date: null }).catch(() => []);
     const focusStatus = await invoke('get_focus_status').catch(() => ({ active: false }));
+    const pomoState = await invoke('get_pomodoro_state').catch(() => ({ session: null, config: null }));
+    const pomo = pomoState.session;
+    const pomoCfg = pomoState.config || { work_min: 25, short_break_min: 5, long_break_min: 15, long_break_every: 4, target_cycles: 4 };
 
     let timerHtml = '';
-    if (current) {
-      // Active session — show ring timer
+    if (pomo) {
+      // Pomodoro session (backend-driven work/break cycles)
+      const phaseLabel = { work: 'Работа', short_break: 'Перерыв', long_break: 'Длинный перерыв' }[pomo.phase] || pomo.phase;
+      const paused = pomo.status === 'paused';
+      const target = pomo.config.target_cycles;
       timerHtml = `<div class="focus-current">
         <div class="focus-ring-container">
           <svg class="focus-ring" viewBox="0 0 120 120">
             <circle class="focus-ring-bg" cx="60" cy="60" r="54" />
-            <circle class="focus-ring-progress" id="focus-ring-progress" cx="60" cy="60" r="54" />
+            <circle class="focus-ring-progress ${pomo.phase === 'work' ? '' : 'break'}" id="focus-ring-progress" cx="60" cy="60" r="54" />
           </svg>
           <div class="focus-ring-inner">
             <div class="focus-current-timer" id="focus-timer">00:00</div>
-            <div class="focus-current-activity">${escapeHtml(current.title)}</div>
+            <div class="focus-current-activity">${phaseLabel}${paused ? ' · пауза' : ''}</div>
           </div>
         </div>
+        <div class="focus-pomo-meta">${escapeHtml(pomo.title)} · 🍅 ${pomo.cycles}${target ? ` / ${target}` : ''} · ⚡ ${pomo.interruptions}</div>
         ${focusStatus.active ? '<div class="focus-blocking-badge">🛡 Блокировка активна</div>' : ''}
         <div class="focus-actions">
-          <button class="btn-danger" id="stop-activity-btn">Завершить</button>
+          <button class="btn-secondary" id="pomo-pause-btn">${paused ? '▶ Продолжить' : '⏸ Пауза'}</button>
+          <button class="btn-secondary" id="pomo-skip-btn">Пропустить</button>
+          <button class="btn-danger" id="pomo-stop-btn">Стоп</button>
         </div>
+        <div class="focus-pomo-interrupt">
+          <input id="pomo-interrupt-note" class="form-input" placeholder="Что отвлекло? (необязательно)" autocomplete="off">
+          <button class="btn-secondary" id="pomo-interrupt-btn" title="Отметить прерывание">⚡ Отвлёкся</button>
+        </div>
+        <textarea id="pomo-notes" class="form-input focus-pomo-notes" placeholder="Заметки к сессии">${escapeHtml(pomo.notes)}</textarea>
       </div>`;
-    } else if (S.pomodoroState.active) {
-      // Pomodoro break/work countdown
-      const modeLabel = S.pomodoroState.mode === 'work' ? 'Работа' : 'Перерыв';
+    } else if (current) {
+      // Active session — show ring timer
       timerHtml = `<div class="focus-current">
         <div class="focus-ring-container">
           <svg class="focus-ring" viewBox="0 0 120 120">
             <circle class="focus-ring-bg" cx="60" cy="60" r="54" />
-            <circle class="focus-ring-progress ${S.pomodoroState.mode === 'break' ? 'break' : ''}" id="focus-ring-progress" cx="60" cy="60" r="54" />
+            <circle class="focus-ring-progress" id="focus-ring-progress" cx="60" cy="60" r="54" />
           </svg>
           <div class="focus-ring-inner">
             <div class="focus-current-timer" id="focus-timer">00:00</div>
-            <div class="focus-current-activity">${modeLabel}</div>
+            <div class="focus-current-activity">${escapeHtml(current.title)}</div>
           </div>
         </div>
+        ${focusStatus.active ? '<div class="focus-blocking-badge">🛡 Блокировка активна</div>' : ''}
         <div class="focus-actions">
-          <button class="btn-secondary" id="pomo-skip-btn">Пропустить</button>
-          <button class="btn-danger" id="pomo-stop-btn">Стоп</button>
+          <button class="btn-danger" id="stop-activity-btn">Завершить</button>
         </div>
       </div>`;
     } else {
@@ -124,11 +145,18 @@ async function renderFocusCurrent(el) {
           <button class="focus-preset" data-category="other">Другое</button>
         </div>
         <div class="focus-start-row">
-          <label class="focus-check-label"><input type="checkbox" id="focus-block-check"> Блокировать отвлечения</label>
+          <label class="focus-check-label"><input type="checkbox" id="focus-block-check"${pomoCfg.auto_focus ? ' checked' : ''}> Блокировать отвлечения</label>
+        </div>
+        <div class="focus-pomo-config" id="pomo-config">
+          <label>Работа <input class="form-input" type="number" min="1" max="180" data-pomo-cfg="work_min" value="${pomoCfg.work_min}"></label>
+          <label>Перерыв <input class="form-input" type="number" min="1" max="60" data-pomo-cfg="short_break_min" value="${pomoCfg.short_break_min}"></label>
+          <label>Длинный <input class="form-input" type="number" min="1" max="90" data-pomo-cfg="long_break_min" value="${pomoCfg.long_break_min}"></label>
+          <label>каждые <input class="form-input" type="number" min="1" max="12" data-pomo-cfg="long_break_every" value="${pomoCfg.long_break_every}"></label>
+          <label>Циклов <input class="form-input" type="number" min="0" max="24" data-pomo-cfg="target_cycles" value="${pomoCfg.target_cycles}" title="0 — пока не остановите"></label>
         </div>
         <div class="focus-actions">
           <button class="btn-primary" id="start-activity-btn">Начать</button>
-          <button class="btn-secondary" id="start-pomo-btn" title="Pomodoro 25/5">🍅 Помодоро</button>
+          <button class="btn-secondary" id="start-pomo-btn" title="Pomodoro ${pomoCfg.work_min}/${pomoCfg.short_break_min}">🍅 Помодоро</button>
         </div>
       </div>`;
     }
@@ -194,42 +222,67 @@ async function renderFocusCurrent(el) {
     document.getElementById('start-pomo-btn')?.addEventListener('click', () => {
       const title = document.getElementById('activity-title')?.value?.trim() || 'Помодоро';
       const focusMode = document.getElementById('focus-block-check')?.checked || false;
-      startPomodoro(title, selectedCategory, focusMode);
+      const config = {};
+      el.querySelectorAll('[data-pomo-cfg]').forEach(inp => { config[inp.dataset.pomoCfg] = parseInt(inp.value, 10) || 0; });
+      startPomodoro(title, focusMode, config);
     });
 
-    document.getElementById('pomo-skip-btn')?.addEventListener('click', () => {
-      if (S.pomodoroState.mode === 'work') {
-        invoke('stop_activity').catch(() => {});
-        S.pomodoroState.mode = 'break';
-        S.pomodoroState.startedAt = Date.now();
-        S.pomodoroState.totalSec = S.pomodoroState.breakMin * 60;
-      } else {
-        S.pomodoroState.mode = 'work';
-        S.pomodoroState.startedAt = Date.now();
-        S.pomodoroState.totalSec = S.pomodoroState.workMin * 60;
-        invoke('start_activity', { title: 'Помодоро', category: 'work', focusMode: false, duration: null, apps: null, sites: null }).catch(() => {});
-      }
+    const pomoCall = async (cmd, args) => {
+      try { await invoke(cmd, args); } catch (err) { toast(String(err)); }
       loadFocus('Current');
-    });
-
-    document.getElementById('pomo-stop-btn')?.addEventListener('click', () => {
-      S.pomodoroState.active = false;
-      invoke('stop_activity').catch(() => {});
+    };
+    document.getElementById('pomo-pause-btn')?.addEventListener('click', () =>
+      pomoCall(pomo.status === 'paused' ? 'resume_pomodoro' : 'pause_pomodoro'));
+    document.getElementById('pomo-skip-btn')?.addEventListener('click', () => pomoCall('skip_pomodoro_phase'));
+    document.getElementById('pomo-stop-btn')?.addEventListener('click', () =>
+      pomoCall('stop_pomodoro', { notes: document.getElementById('pomo-notes')?.value ?? null }));
+    document.getElementById('pomo-interrupt-btn')?.addEventListener('click', async () => {
+      const noteEl = document.getElementById('pomo-interrupt-note');
+      try {
+        const n = await invoke('interrupt_pomodoro', { note: noteEl.value.trim() || null });
+        toast(`Прерываний: ${n}`);
+      } catch (err) { toast(String(err)); }
       loadFocus('Current');
     });
+    document.getElementById('pomo-notes')?.addEventListener('change', (e) => {
+      invoke('set_pomodoro_notes', { id: pomo.id, notes: e.target.value }).catch(err => toast(String(err)));
+    });
 
     // Update timer
-    startFocusTimer(current);
+    startFocusTimer(current, pomo);
 
   } catch (e) {
     tabLoaders.showStub?.('focus-content', '🎯', 'Фокус', 'Глубокая работа и трекинг активности');
   }
 }
 
-function startFocusTimer(current) {
+function startFocusTimer(current, pomo) {
   if (S.focusTimerInterval) clearInterval(S.focusTimerInterval);
   const circumference = 2 * Math.PI * 54;
 
+  if (pomo) {
+    // Countdown only — the backend tick switches phases, 'pomodoro-phase' re-renders.
+    const endsAt = Date.now() + pomo.remaining_secs * 1000;
+    const updatePomo = () => {
+      const timerEl = document.getElementById('focus-timer');
+      const ringEl = document.getElementById('focus-ring-progress');
+      if (!timerEl || S.activeTab !== 'focus') { clearInterval(S.focusTimerInterval); return; }
+      const remaining = pomo.status === 'paused' ? pomo.remaining_secs : Math.max(0, Math.round((endsAt - Date.now()) / 1000));
+      const m = Math.floor(remaining / 60);
+      const s = remaining % 60;
+      timerEl.textContent = `${String(m).padStart(2,'0')}:${String(s).padStart(2,'0')}`;
+      if (ringEl) {
+        const progress = 1 - (remaining / pomo.phase_secs);
+        ringEl.style.strokeDasharray = circumference;
+        ringEl.style.strokeDashoffset = circumference * (1 - progress);
+      }
+      if (remaining <= 0 || pomo.status === 'paused') clearInterval(S.focusTimerInterval);
+    };
+    updatePomo();
+    S.focusTimerInterval = setInterval(updatePomo, 1000);
+    return;
+  }
+
   if (current && current.started_at) {
     const startedAt = new Date(current.started_at).getTime();
     const updateTimer = () => {
@@ -250,49 +303,17 @@ function startFocusTimer(current) {
     };
     updateTimer();
     S.focusTimerInterval = setInterval(updateTimer, 1000);
-  } else if (S.pomodoroState.active) {
-    const updatePomo = () => {
-      const timerEl = document.getElementById('focus-timer');
-      const ringEl = document.getElementById('focus-ring-progress');
-      if (!timerEl || S.activeTab !== 'focus') { clearInterval(S.focusTimerInterval); return; }
-      const elapsed = Math.floor((Date.now() - S.pomodoroState.startedAt) / 1000);
-      const remaining = Math.max(0, S.pomodoroState.totalSec - elapsed);
-      const m = Math.floor(remaining / 60);
-      const s = remaining % 60;
-      timerEl.textContent = `${String(m).padStart(2,'0')}:${String(s).padStart(2,'0')}`;
-      if (ringEl) {
-        const progress = 1 - (remaining / S.pomodoroState.totalSec);
-        ringEl.style.strokeDasharray = circumference;
-        ringEl.style.strokeDashoffset = circumference * (1 - progress);
-      }
-      if (remaining <= 0) {
-        clearInterval(S.focusTimerInterval);
-        if (S.pomodoroState.mode === 'work') {
-          invoke('stop_activity').catch(() => {});
-          invoke('send_notification', { title: 'Помодоро', body: 'Время перерыва! 5 минут.' }).catch(() => {});
-          S.pomodoroState.mode = 'break';
-          S.pomodoroState.startedAt = Date.now();
-          S.pomodoroState.totalSec = S.pomodoroState.breakMin * 60;
-        } else {
-          invoke('send_notification', { title: 'Помодоро', body: 'Перерыв окончен! Поехали.' }).catch(() => {});
-          S.pomodoroState.mode = 'work';
-          S.pomodoroState.startedAt = Date.now();
-          S.pomodoroState.totalSec = S.pomodoroState.workMin * 60;
-          invoke('start_activity', { title: 'Помодоро', category: 'work', focusMode: false, duration: null, apps: null, sites: null }).catch(() => {});
-        }
-        loadFocus('Current');
-      }
-    };
-    updatePomo();
-    S.focusTimerInterval = setInterval(updatePomo, 1000);
   }
 }
 
-async function startPomodoro(title, category, focusMode) {
-  S.pomodoroState = { active: true, mode: 'work', workMin: 25, breakMin: 5, startedAt: Date.now(), totalSec: 25 * 60 };
+async function startPomodoro(title, focusMode, config = {}) {
   try {
-    await invoke('start_activity', { title: title || 'Помодоро', category: category || 'work', focusMode, duration: 25, apps: null, sites: null });
-  } catch (_) {}
+    const { config: saved } = await invoke('get_pomodoro_state');
+    await invoke('start_pomodoro', {
+      sourceType: null, sourceId: null, title: title || 'Помодоро', project: null,
+      config: { ...saved, ...config, auto_focus: !!focusMode },
+    });
+  } catch (err) { toast(String(err)); }
   loadFocus('Current');
 }
 
@@ -546,6 +567,12 @@ function updateFocusWidgetVisibility() {
   if (widget) widget.classList.toggle('hidden', S.activeTab === 'focus');
 }
 
+// Pomodoro phase switched by the backend timer
+listen('pomodoro-phase', (ev) => {
+  toast(ev.payload.message);
+  if (S.activeTab === 'focus') loadFocus('Current');
+});
+
 // Listen for focus-state from overlay window (e.g. stop from overlay)
 listen('focus-state', (ev) => {
   if (!ev.payload.active && S.focusWidgetActivity) {
@@ -1,6 +1,6 @@
 // tab-sleep.js — Sleep sessions pane for Health tab
-import { invoke, IS_MOBILE } from './state.js';
-import { escapeHtml } from './utils.js';
+import { invoke, listen, IS_MOBILE } from './state.js';
+import { escapeHtml, toast } from './utils.js';
 import { autoImportHealth } from './health-auto-sync.js';
 
 const STAGE_COLORS = {
@@ -51,12 +51,14 @@ export async function renderSleepPane(el) {
     <div class="sleep-actions">
       <button class="btn-smallall" id="sleep-add-btn">+ Добавить</button>
       ${importBtnHtml}
+      <button class="btn-smallall desktop-only" id="sleep-export-import-btn">📥 Импорт Apple Health / Google Fit</button>
     </div>
     <div class="sleep-list">${renderSessionList(sessions)}</div>`;
 
   el.querySelector('#sleep-add-btn')?.addEventListener('click', () => addManualSleep(el));
   el.querySelector('#sleep-import-btn')?.addEventListener('click', () => importFromHealthConnect(el));
   el.querySelector('#sleep-grant-btn')?.addEventListener('click', () => grantAndImport(el));
+  el.querySelector('#sleep-export-import-btn')?.addEventListener('click', () => importHealthExport(el));
 
   // Lazy auto-import on enter — runs in the background so the pane stays
   // responsive. We force-bypass the 60-second throttle only if last sync
@@ -82,6 +84,7 @@ function renderSessionList(sessions) {
       <div class="sleep-session-time">${shortTime(s.start_time)} — ${shortTime(s.end_time)}</div>
       <div class="sleep-session-dur">${h}ч ${m}м</div>
       <div class="sleep-session-source">${s.source === 'health_connect' ? '📱' : '✏️'}</div>
+      <div class="sleep-session-score" title="Оценка сна">${s.quality_score ?? ''}</div>
       ${stagesHtml}
     </div>`;
   }).join('');
@@ -156,6 +159,49 @@ async function importFromHealthConnect(el) {
   } catch(e) { alert(e); }
 }
 
+// Apple Health export.xml / Google Takeout folder. Both can be gigabytes, so
+// the backend streams them and reports progress instead of returning early.
+function importHealthExport(el) {
+  const overlay = document.createElement('div');
+  overlay.className = 'modal-overlay';
+  overlay.innerHTML = `<div class="modal" style="max-width:520px">
+    <div class="modal-title">Импорт Apple Health / Google Fit</div>
+    <div class="form-label">Путь к распакованному экспорту</div>
+    <input class="form-input" id="he-path" placeholder="~/Downloads/apple_health_export или ~/Downloads/Takeout/Fit">
+    <div class="form-hint" style="margin-top:6px">Apple Health: папка с export.xml. Google Fit: папка Takeout/Fit.
+      ZIP-архив нужно сначала распаковать.</div>
+    <div class="form-hint" id="he-progress" style="margin-top:6px"></div>
+    <div class="modal-actions">
+      <button class="btn-secondary" id="he-cancel">Отмена</button>
+      <button class="btn-primary" id="he-run">Импортировать</button>
+    </div>
+  </div>`;
+  document.body.appendChild(overlay);
+  const close = () => overlay.remove();
+  overlay.querySelector('#he-cancel').onclick = close;
+  overlay.querySelector('#he-run').onclick = async () => {
+    const path = overlay.querySelector('#he-path').value.trim();
+    if (!path) return;
+    const runBtn = overlay.querySelector('#he-run');
+    const progressEl = overlay.querySelector('#he-progress');
+    runBtn.disabled = true;
+    progressEl.textContent = 'Чтение…';
+    const unlisten = await listen('health-export-progress', (e) => {
+      progressEl.textContent = `Обработано записей: ${Number(e.payload || 0).toLocaleString('ru-RU')}`;
+    });
+    try {
+      const s = await invoke('import_health_export', { path });
+      toast(`Импорт: сон ${s.sleep}, шаги ${s.steps_days} дн., пульс ${s.heart_rate}, вес ${s.weight_days} дн., тренировки ${s.workouts}`, 'success');
+      close();
+      renderSleepPane(el);
+    } catch (e) {
+      progressEl.textContent = '';
+      toast('Ошибка импорта: ' + e);
+      runBtn.disabled = false;
+    } finally { unlisten(); }
+  };
+}
+
 async function grantAndImport(el) {
   try {
     const granted = await invoke('health_request_permissions');
@@ -3,6 +3,8 @@
 import { S, invoke, listen, TAB_REGISTRY, TAB_ICONS, TAB_SETTINGS_DEFS, saveTabs, tabLoaders, loadTabSetting, saveTabSetting, IS_MOBILE } from './state.js';
 import { escapeHtml, confirmModal, renderTabSettingsPage, setupPageHeaderControls, renderPageHeader } from './utils.js';
 import { renderSecuritySection, wireSecurityControls } from './settings-security.js';
+import { renderBlocklistSection, wireBlocklistControls } from './settings-blocklist.js';
+import { renderTimeZoneSection, wireTimeZoneControls } from './settings-timezone.js';
 
 // ── Mobile drawer (ChatGPT/Claude-style sidebar) ──
 let drawerBackdrop = null;
@@ -61,6 +63,7 @@ const SETTINGS_SECTIONS = {
     { id: 'skills', label: 'Скиллы' },
     { id: 'integrations', label: 'Интеграции' },
     { id: 'blocklist', label: 'Блок-лист' },
+    { id: 'timezone', label: 'Часовой пояс' },
     { id: 'mcp', label: 'MCP серверы' },
     { id: 'manage', label: 'Управление' },
     // The chat tab (with its own settings incl. «О Hanni») is disabled in
@@ -947,6 +950,8 @@ async function renderSettingsPage(tabId, sectionId) {
   wireSettingsControls(el, tabId);
   wireSyncControls(el);
   wireSecurityControls(el);
+  wireBlocklistControls(el);
+  wireTimeZoneControls(el);
   wireManageControls(el, tabId);
   if (sec.id === 'about') {
     const host = el.querySelector('#settings-about-host');
@@ -992,9 +997,9 @@ async function renderSettingsSectionContent(tabId, sectionId) {
       <div class="settings-empty-hint">Нет подключённых интеграций</div>
       <button class="btn-smallall" style="margin-top:12px;">+ Подключить</button></div>`;
   } else if (sectionId === 'blocklist') {
-    return `<div class="settings-section"><div class="settings-section-title">Блок-лист</div>
-      <div class="settings-empty-hint">Блок-лист пуст</div>
-      <button class="btn-smallall" style="margin-top:12px;">+ Добавить в блок-лист</button></div>`;
+    return await renderBlocklistSection();
+  } else if (sectionId === 'timezone') {
+    return await renderTimeZoneSection();
   } else if (sectionId === 'mcp') {
     return `<div class="settings-section"><div class="settings-section-title">MCP серверы</div>
       <div class="settings-row"><span class="settings-hint">Подключение внешних инструментов через MCP</span></div>